@@ -0,0 +1,312 @@
+// src/audit.rs
+//! Stored role grants with an audit trail (`orm` feature).
+//!
+//! [`RoleGrants`] keeps who holds which role in `__chopin_role_grants` and
+//! writes every grant and revocation to `__chopin_rbac_audit` in the same
+//! statement, so the trail cannot miss a change made through it. Put the
+//! subject's roles into the token at login with [`RoleGrants::roles_of`]:
+//!
+//! ```rust,ignore
+//! use chopin_auth::audit::{AuditQuery, RbacAudit, RoleGrants};
+//!
+//! db::with_db(|db| RoleGrants::ensure_tables(db))?;
+//!
+//! #[post("/admin/users/:id/roles/:role")]
+//! #[role_required("admin")]
+//! fn grant(ctx: Context, id: String, role: String, Auth { claims }: Auth<AdminClaims>) -> Response {
+//!     match db::with_db(|db| RoleGrants::grant(db, &claims.sub, &id, &role, None)) { .. }
+//! }
+//!
+//! #[get("/admin/rbac/audit")]
+//! #[role_required("admin")]
+//! fn audit(ctx: Context) -> Response {
+//!     chopin_auth::audit::audit_log(&ctx)
+//! }
+//! ```
+//!
+//! Applications that keep permissions in their own tables can still log
+//! changes with [`RbacAudit::record`], ideally on the same transaction.
+//! `role` in the trail is whatever was granted: a role or a permission name.
+use crate::jwt::HasJti;
+use chopin_core::extract::Query;
+use chopin_core::http::{Context, Response};
+use chopin_orm::{Executor, OrmError, OrmResult};
+use serde::{Deserialize, Serialize};
+
+/// Whether a role was granted or revoked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    Grant,
+    Revoke,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Grant => "grant",
+            Self::Revoke => "revoke",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "grant" => Some(Self::Grant),
+            "revoke" => Some(Self::Revoke),
+            _ => None,
+        }
+    }
+}
+
+/// One row of the audit trail.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Unix seconds.
+    pub at: i64,
+    /// Who made the change, e.g. the admin's `sub`.
+    pub actor: String,
+    pub action: AuditAction,
+    /// Whose roles changed.
+    pub subject: String,
+    pub role: String,
+    pub reason: Option<String>,
+}
+
+/// Filters for [`RbacAudit::list`]; also the query string of [`audit_log`].
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AuditQuery {
+    pub subject: Option<String>,
+    pub actor: Option<String>,
+    pub role: Option<String>,
+    /// Only entries with a smaller id, for paging backwards.
+    pub before: Option<i64>,
+    /// At most this many entries (default 100, capped at 1000).
+    pub limit: Option<i64>,
+}
+
+const GRANTS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS __chopin_role_grants (
+        subject TEXT NOT NULL,
+        role TEXT NOT NULL,
+        granted_by TEXT NOT NULL,
+        granted_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (subject, role)
+    )
+"#;
+
+const AUDIT_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS __chopin_rbac_audit (
+        id BIGSERIAL PRIMARY KEY,
+        at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        actor TEXT NOT NULL,
+        action TEXT NOT NULL,
+        subject TEXT NOT NULL,
+        role TEXT NOT NULL,
+        reason TEXT
+    )
+"#;
+
+/// Storage operations on the `__chopin_rbac_audit` table.
+pub struct RbacAudit;
+
+impl RbacAudit {
+    /// Creates the `__chopin_rbac_audit` table if it does not exist.
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(AUDIT_TABLE, &[])?;
+        executor.execute(
+            "CREATE INDEX IF NOT EXISTS __chopin_rbac_audit_subject_idx \
+             ON __chopin_rbac_audit (subject, id)",
+            &[],
+        )?;
+        Ok(())
+    }
+
+    /// Log a change made outside [`RoleGrants`]. Returns the entry id.
+    pub fn record(
+        executor: &mut dyn Executor,
+        actor: &str,
+        action: AuditAction,
+        subject: &str,
+        role: &str,
+        reason: Option<&str>,
+    ) -> OrmResult<i64> {
+        let rows = executor.query(
+            "INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING id",
+            &[&actor, &action.as_str(), &subject, &role, &reason],
+        )?;
+        let row = rows.first().ok_or(OrmError::RecordNotFound)?;
+        Ok(row.get_typed_by_name("id")?)
+    }
+
+    /// Entries matching `query`, newest first.
+    pub fn list(executor: &mut dyn Executor, query: &AuditQuery) -> OrmResult<Vec<AuditEntry>> {
+        let limit = query.limit.unwrap_or(100).clamp(1, 1000);
+        let rows = executor.query(
+            "SELECT id, EXTRACT(EPOCH FROM at)::int8 AS at, actor, action, subject, role, reason \
+             FROM __chopin_rbac_audit \
+             WHERE ($1::text IS NULL OR subject = $1) \
+               AND ($2::text IS NULL OR actor = $2) \
+               AND ($3::text IS NULL OR role = $3) \
+               AND ($4::int8 IS NULL OR id < $4) \
+             ORDER BY id DESC LIMIT $5",
+            &[
+                &query.subject,
+                &query.actor,
+                &query.role,
+                &query.before,
+                &limit,
+            ],
+        )?;
+        rows.iter()
+            .map(|row| {
+                let action: String = row.get_typed_by_name("action")?;
+                Ok(AuditEntry {
+                    id: row.get_typed_by_name("id")?,
+                    at: row.get_typed_by_name("at")?,
+                    actor: row.get_typed_by_name("actor")?,
+                    action: AuditAction::parse(&action).ok_or_else(|| {
+                        OrmError::Extraction(format!("unknown audit action `{action}`"))
+                    })?,
+                    subject: row.get_typed_by_name("subject")?,
+                    role: row.get_typed_by_name("role")?,
+                    reason: row.get_typed_by_name("reason")?,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Role assignments in `__chopin_role_grants`, audited in
+/// `__chopin_rbac_audit`.
+pub struct RoleGrants;
+
+impl RoleGrants {
+    /// Creates both tables if they do not exist.
+    pub fn ensure_tables(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(GRANTS_TABLE, &[])?;
+        RbacAudit::ensure_table(executor)
+    }
+
+    /// Give `subject` the `role`, logged as done by `actor`. Returns
+    /// `false`, and logs nothing, if the subject already held it.
+    pub fn grant(
+        executor: &mut dyn Executor,
+        actor: &str,
+        subject: &str,
+        role: &str,
+        reason: Option<&str>,
+    ) -> OrmResult<bool> {
+        let n = executor.execute(
+            "WITH changed AS ( \
+                 INSERT INTO __chopin_role_grants (subject, role, granted_by) \
+                 VALUES ($2, $3, $1) ON CONFLICT DO NOTHING RETURNING subject, role) \
+             INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+             SELECT $1, 'grant', subject, role, $4::text FROM changed",
+            &[&actor, &subject, &role, &reason],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Take `role` away from `subject`, logged as done by `actor`. Returns
+    /// `false`, and logs nothing, if the subject did not hold it.
+    pub fn revoke(
+        executor: &mut dyn Executor,
+        actor: &str,
+        subject: &str,
+        role: &str,
+        reason: Option<&str>,
+    ) -> OrmResult<bool> {
+        let n = executor.execute(
+            "WITH changed AS ( \
+                 DELETE FROM __chopin_role_grants WHERE subject = $2 AND role = $3 \
+                 RETURNING subject, role) \
+             INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+             SELECT $1, 'revoke', subject, role, $4::text FROM changed",
+            &[&actor, &subject, &role, &reason],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Roles currently granted to `subject`, sorted.
+    pub fn roles_of(executor: &mut dyn Executor, subject: &str) -> OrmResult<Vec<String>> {
+        let rows = executor.query(
+            "SELECT role FROM __chopin_role_grants WHERE subject = $1 ORDER BY role",
+            &[&subject],
+        )?;
+        rows.iter()
+            .map(|row| Ok(row.get_typed_by_name("role")?))
+            .collect()
+    }
+}
+
+#[derive(Serialize)]
+struct AuditPage {
+    entries: Vec<AuditEntry>,
+    /// Pass as `before` to get the next (older) page.
+    next_before: Option<i64>,
+}
+
+/// Admin listing of the audit trail as JSON, filtered by the query string
+/// (`?subject=..&actor=..&role=..&before=..&limit=..`). Reads the default
+/// database from [`chopin_core::db`]. It does no authorization of its own;
+/// call it from a handler guarded with e.g. `#[role_required("admin")]`.
+pub fn audit_log(ctx: &Context) -> Response {
+    let query = match ctx.extract::<Query<AuditQuery>>() {
+        Ok(Query(query)) => query,
+        Err(res) => return res,
+    };
+    let entries = match chopin_core::db::with_db(|db| RbacAudit::list(db, &query)) {
+        Ok(entries) => entries,
+        Err(_) => return Response::server_error(),
+    };
+    let limit = query.limit.unwrap_or(100).clamp(1, 1000) as usize;
+    let next_before = (entries.len() == limit)
+        .then(|| entries.last().map(|e| e.id))
+        .flatten();
+    match serde_json::to_vec(&AuditPage {
+        entries,
+        next_before,
+    }) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Audit actors are usually read from the caller's token.
+#[derive(Deserialize)]
+struct ActorClaims {
+    sub: serde_json::Value,
+}
+
+impl HasJti for ActorClaims {}
+
+/// The `sub` claim of the request's bearer token, for use as the `actor`
+/// of a grant or revocation. Fails like [`Auth`](crate::Auth).
+#[allow(clippy::result_large_err)]
+pub fn actor(ctx: &Context) -> Result<String, Response> {
+    let crate::Auth { claims } = ctx.extract::<crate::Auth<ActorClaims>>()?;
+    match claims.sub {
+        serde_json::Value::String(sub) => Ok(sub),
+        serde_json::Value::Number(sub) => Ok(sub.to_string()),
+        _ => Err(Response::new(401)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_round_trip() {
+        for action in [AuditAction::Grant, AuditAction::Revoke] {
+            assert_eq!(AuditAction::parse(action.as_str()), Some(action));
+        }
+        assert_eq!(AuditAction::parse("delete"), None);
+        assert_eq!(
+            serde_json::to_value(AuditAction::Revoke).unwrap(),
+            serde_json::json!("revoke")
+        );
+    }
+}
@@ -0,0 +1,626 @@
+// src/challenge.rs
+//! Brute-force protection for login and other credential endpoints.
+//!
+//! A [`LoginShield`] counts failed attempts per key (a username, an email,
+//! a client address taken from a trusted proxy header, ...). Once a key
+//! reaches `challenge_after` failures within the window, further attempts
+//! must carry a solved challenge from the configured [`ChallengeProvider`];
+//! at `max_attempts` the key is refused outright until the window passes.
+//!
+//! ```rust,ignore
+//! use chopin_auth::{LoginShield, SecurityConfig, init_login_shield, login_shield, verify_login};
+//!
+//! // Chopin.toml:
+//! //   [security]
+//! //   challenge = "turnstile"
+//! //   captcha_secret = "${TURNSTILE_SECRET}"
+//! //   captcha_site_key = "0x4AAAA..."
+//! let cfg = Settings::<SecurityConfig>::load()?;
+//! init_login_shield(LoginShield::from_config(&cfg, Some(Arc::new(http_post)))?);
+//!
+//! #[post("/login")]
+//! fn login(ctx: Context, Json(form): Json<LoginForm>) -> Response {
+//!     let shield = login_shield();
+//!     if let Err(res) = shield.check(&ctx, &form.email) {
+//!         return res;
+//!     }
+//!     let user = find_user(&form.email);
+//!     if !verify_login(form.password.as_bytes(), user.as_ref().map(|u| u.password_hash.as_str())) {
+//!         shield.failed(&form.email);
+//!         return Response::unauthorized();
+//!     }
+//!     shield.succeeded(&form.email);
+//!     ..
+//! }
+//! ```
+//!
+//! | State                                    | Response from [`LoginShield::check`] |
+//! |------------------------------------------|--------------------------------------|
+//! | below `challenge_after`                  | `Ok(())`                             |
+//! | challenge required, none or wrong answer | `428` with an `X-Challenge` header and the same challenge as JSON (`{"type": "pow", ...}`) |
+//! | challenge required and solved           | `Ok(())`                             |
+//! | at `max_attempts`, or no provider        | `429` with `Retry-After`             |
+//!
+//! Failures are counted per submitted key whether or not an account has
+//! it, and [`verify_login`](crate::verify_login) takes as long for unknown
+//! users, so neither the responses nor their timing reveal which accounts
+//! exist. Answer an unknown user and a wrong password with the same `401`.
+//!
+//! Clients send their answer in the `X-Challenge-Response` header: the
+//! CAPTCHA widget's token, or the output of [`ProofOfWork::solve`].
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::oauth::{
+    base64url_encode, constant_time_eq, getrandom, hmac_sha256, percent_encode, sha256,
+};
+use chopin_core::config::SettingsSection;
+use chopin_core::error::{ChopinError, ChopinResult};
+use chopin_core::http::{Context, Response};
+use serde::Deserialize;
+
+/// Request header carrying the client's answer to a challenge.
+pub const CHALLENGE_RESPONSE_HEADER: &str = "X-Challenge-Response";
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// ─── Configuration ───────────────────────────────────────────────────────────
+
+/// Which challenge [`LoginShield::from_config`] installs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChallengeKind {
+    /// No challenge: keys over the threshold are throttled with `429`.
+    None,
+    /// Built-in proof of work, see [`ProofOfWork`].
+    Pow,
+    /// Cloudflare Turnstile.
+    Turnstile,
+    /// hCaptcha.
+    Hcaptcha,
+}
+
+/// The `[security]` section of `Chopin.toml`.
+///
+/// ```toml
+/// [security]
+/// challenge_after = 3
+/// max_attempts = 20
+/// window_secs = 900
+/// challenge = "pow"      # none | pow | turnstile | hcaptcha
+/// pow_difficulty = 18
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SecurityConfig {
+    /// Failed attempts after which a challenge is required.
+    pub challenge_after: u32,
+    /// Failed attempts after which the key is refused until the window ends.
+    pub max_attempts: u32,
+    /// How long failures are remembered, in seconds.
+    pub window_secs: u64,
+    pub challenge: ChallengeKind,
+    /// Leading zero bits a proof-of-work answer needs.
+    pub pow_difficulty: u8,
+    /// Secret used to verify CAPTCHA tokens with the provider.
+    pub captcha_secret: Option<String>,
+    /// Public site key handed to the client's CAPTCHA widget.
+    pub captcha_site_key: Option<String>,
+}
+
+impl Default for SecurityConfig {
+    fn default() -> Self {
+        Self {
+            challenge_after: 3,
+            max_attempts: 20,
+            window_secs: 900,
+            challenge: ChallengeKind::Pow,
+            pow_difficulty: 18,
+            captcha_secret: None,
+            captcha_site_key: None,
+        }
+    }
+}
+
+impl SettingsSection for SecurityConfig {
+    const SECTION: &'static str = "security";
+}
+
+// ─── Providers ───────────────────────────────────────────────────────────────
+
+/// A challenge handed to the client: its kind and the parameters it needs
+/// to produce an answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Challenge {
+    pub kind: &'static str,
+    pub params: Vec<(&'static str, String)>,
+}
+
+impl Challenge {
+    /// `kind k1="v1", k2="v2"`, the `X-Challenge` header value.
+    pub fn header_value(&self) -> String {
+        let mut out = self.kind.to_string();
+        for (i, (k, v)) in self.params.iter().enumerate() {
+            out.push_str(if i == 0 { " " } else { ", " });
+            out.push_str(&format!("{k}=\"{v}\""));
+        }
+        out
+    }
+
+    fn to_json(&self) -> String {
+        let mut obj = serde_json::Map::new();
+        obj.insert("type".into(), self.kind.into());
+        for (k, v) in &self.params {
+            obj.insert((*k).into(), v.clone().into());
+        }
+        serde_json::Value::Object(obj).to_string()
+    }
+}
+
+/// Something that can issue challenges and check the client's answers.
+pub trait ChallengeProvider: Send + Sync {
+    /// A challenge for the client to solve.
+    fn issue(&self) -> Challenge;
+
+    /// Whether `answer` (the `X-Challenge-Response` header) solves a
+    /// challenge issued by this provider.
+    fn verify(&self, answer: &str) -> bool;
+}
+
+fn leading_zero_bits(hash: &[u8]) -> u32 {
+    let mut bits = 0;
+    for b in hash {
+        if *b == 0 {
+            bits += 8;
+        } else {
+            return bits + b.leading_zeros();
+        }
+    }
+    bits
+}
+
+/// Stateless hashcash-style proof of work.
+///
+/// A challenge is `expires.nonce.difficulty.mac`, authenticated with a
+/// server secret so the client cannot pick an easy one. The answer is
+/// `challenge:counter` such that `SHA-256(answer)` starts with `difficulty`
+/// zero bits. Each challenge can be redeemed once.
+pub struct ProofOfWork {
+    secret: Vec<u8>,
+    difficulty: u8,
+    ttl_secs: u64,
+    /// Answers already accepted, with the expiry of their challenge.
+    used: Mutex<HashMap<String, u64>>,
+}
+
+impl ProofOfWork {
+    /// Challenges valid for five minutes, signed with `secret`.
+    pub fn new(secret: &[u8], difficulty: u8) -> Self {
+        Self {
+            secret: secret.to_vec(),
+            difficulty,
+            ttl_secs: 300,
+            used: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Sign challenges with a random per-process secret. Challenges issued
+    /// by one process are not accepted by another.
+    pub fn with_random_secret(difficulty: u8) -> Self {
+        let mut secret = [0u8; 32];
+        getrandom(&mut secret);
+        Self::new(&secret, difficulty)
+    }
+
+    /// How long an issued challenge stays solvable.
+    pub fn with_ttl(mut self, ttl_secs: u64) -> Self {
+        self.ttl_secs = ttl_secs;
+        self
+    }
+
+    fn mac(&self, body: &str) -> String {
+        base64url_encode(&hmac_sha256(&self.secret, body.as_bytes()))
+    }
+
+    fn challenge_at(&self, now: u64) -> String {
+        let mut nonce = [0u8; 16];
+        getrandom(&mut nonce);
+        let body = format!(
+            "{}.{}.{}",
+            now + self.ttl_secs,
+            base64url_encode(&nonce),
+            self.difficulty
+        );
+        let mac = self.mac(&body);
+        format!("{body}.{mac}")
+    }
+
+    /// Find an answer to `challenge`. Meant for clients and tests; cost
+    /// doubles with each bit of difficulty.
+    pub fn solve(challenge: &str) -> Option<String> {
+        let difficulty: u32 = challenge.split('.').nth(2)?.parse().ok()?;
+        (0u64..).find_map(|counter| {
+            let answer = format!("{challenge}:{counter}");
+            (leading_zero_bits(&sha256(answer.as_bytes())) >= difficulty).then_some(answer)
+        })
+    }
+
+    fn verify_at(&self, answer: &str, now: u64) -> bool {
+        let Some((challenge, _counter)) = answer.rsplit_once(':') else {
+            return false;
+        };
+        let Some((body, mac)) = challenge.rsplit_once('.') else {
+            return false;
+        };
+        if !constant_time_eq(self.mac(body).as_bytes(), mac.as_bytes()) {
+            return false;
+        }
+        let mut parts = body.split('.');
+        let expires: u64 = match parts.next().and_then(|p| p.parse().ok()) {
+            Some(e) => e,
+            None => return false,
+        };
+        let difficulty: u32 = match parts.nth(1).and_then(|p| p.parse().ok()) {
+            Some(d) => d,
+            None => return false,
+        };
+        if now > expires || leading_zero_bits(&sha256(answer.as_bytes())) < difficulty {
+            return false;
+        }
+        let Ok(mut used) = self.used.lock() else {
+            return false;
+        };
+        used.retain(|_, exp| *exp >= now);
+        used.insert(challenge.to_string(), expires).is_none()
+    }
+}
+
+impl ChallengeProvider for ProofOfWork {
+    fn issue(&self) -> Challenge {
+        Challenge {
+            kind: "pow",
+            params: vec![
+                ("challenge", self.challenge_at(now_secs())),
+                ("difficulty", self.difficulty.to_string()),
+            ],
+        }
+    }
+
+    fn verify(&self, answer: &str) -> bool {
+        self.verify_at(answer, now_secs())
+    }
+}
+
+/// Sends a form-encoded POST and returns the response body. Chopin has no
+/// HTTP client of its own, so CAPTCHA verification uses whichever one the
+/// application already has.
+pub type HttpPost = Arc<dyn Fn(&str, &str) -> Result<String, String> + Send + Sync>;
+
+/// Verifies CAPTCHA tokens against a `siteverify` endpoint (Turnstile,
+/// hCaptcha, or anything with the same request and `{"success": bool}`
+/// response).
+pub struct CaptchaVerifier {
+    kind: &'static str,
+    verify_url: String,
+    secret: String,
+    site_key: String,
+    post: HttpPost,
+}
+
+impl CaptchaVerifier {
+    /// Cloudflare Turnstile.
+    pub fn turnstile(secret: &str, site_key: &str, post: HttpPost) -> Self {
+        Self::custom(
+            "turnstile",
+            "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+            secret,
+            site_key,
+            post,
+        )
+    }
+
+    /// hCaptcha.
+    pub fn hcaptcha(secret: &str, site_key: &str, post: HttpPost) -> Self {
+        Self::custom(
+            "hcaptcha",
+            "https://api.hcaptcha.com/siteverify",
+            secret,
+            site_key,
+            post,
+        )
+    }
+
+    /// Any provider with a compatible `siteverify` endpoint.
+    pub fn custom(
+        kind: &'static str,
+        verify_url: &str,
+        secret: &str,
+        site_key: &str,
+        post: HttpPost,
+    ) -> Self {
+        Self {
+            kind,
+            verify_url: verify_url.to_string(),
+            secret: secret.to_string(),
+            site_key: site_key.to_string(),
+            post,
+        }
+    }
+}
+
+impl ChallengeProvider for CaptchaVerifier {
+    fn issue(&self) -> Challenge {
+        Challenge {
+            kind: self.kind,
+            params: vec![("sitekey", self.site_key.clone())],
+        }
+    }
+
+    /// Fails closed: transport errors and unparseable replies reject.
+    fn verify(&self, answer: &str) -> bool {
+        if answer.is_empty() {
+            return false;
+        }
+        let form = format!(
+            "secret={}&response={}",
+            percent_encode(&self.secret),
+            percent_encode(answer)
+        );
+        match (self.post)(&self.verify_url, &form) {
+            Ok(body) => serde_json::from_str::<serde_json::Value>(&body)
+                .ok()
+                .and_then(|v| v.get("success")?.as_bool())
+                .unwrap_or(false),
+            Err(_) => false,
+        }
+    }
+}
+
+// ─── Shield ──────────────────────────────────────────────────────────────────
+
+/// Failure counting plus challenge enforcement. Cloning shares the counters.
+#[derive(Clone)]
+pub struct LoginShield {
+    /// Key → (failures, Unix seconds of the first failure in the window).
+    failures: Arc<RwLock<HashMap<String, (u32, u64)>>>,
+    challenge_after: u32,
+    max_attempts: u32,
+    window_secs: u64,
+    provider: Option<Arc<dyn ChallengeProvider>>,
+}
+
+impl LoginShield {
+    /// Challenge after `challenge_after` failures and refuse after
+    /// `max_attempts`, forgetting failures `window_secs` after the first.
+    /// Without a provider, keys that need a challenge are refused instead.
+    pub fn new(challenge_after: u32, max_attempts: u32, window_secs: u64) -> Self {
+        Self {
+            failures: Arc::new(RwLock::new(HashMap::new())),
+            challenge_after,
+            max_attempts,
+            window_secs,
+            provider: None,
+        }
+    }
+
+    /// Require `provider`'s challenges once the threshold is reached.
+    pub fn with_provider(mut self, provider: impl ChallengeProvider + 'static) -> Self {
+        self.provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Build from `[security]`. CAPTCHA kinds need `captcha_secret`,
+    /// `captcha_site_key` and an `HttpPost`.
+    pub fn from_config(cfg: &SecurityConfig, post: Option<HttpPost>) -> ChopinResult<Self> {
+        let shield = Self::new(cfg.challenge_after, cfg.max_attempts, cfg.window_secs);
+        let captcha = |build: fn(&str, &str, HttpPost) -> CaptchaVerifier| {
+            match (&cfg.captcha_secret, &cfg.captcha_site_key, post.clone()) {
+                (Some(secret), Some(site_key), Some(post)) => Ok(build(secret, site_key, post)),
+                _ => Err(ChopinError::Other(
+                    "[security] captcha challenges need captcha_secret, captcha_site_key and an HTTP client"
+                        .to_string(),
+                )),
+            }
+        };
+        Ok(match cfg.challenge {
+            ChallengeKind::None => shield,
+            ChallengeKind::Pow => {
+                shield.with_provider(ProofOfWork::with_random_secret(cfg.pow_difficulty))
+            }
+            ChallengeKind::Turnstile => shield.with_provider(captcha(CaptchaVerifier::turnstile)?),
+            ChallengeKind::Hcaptcha => shield.with_provider(captcha(CaptchaVerifier::hcaptcha)?),
+        })
+    }
+
+    /// Failures recorded for `key` in the current window.
+    pub fn failures(&self, key: &str) -> u32 {
+        self.current(key).map_or(0, |(count, _)| count)
+    }
+
+    fn current(&self, key: &str) -> Option<(u32, u64)> {
+        let (count, since) = *self.failures.read().ok()?.get(key)?;
+        (now_secs().saturating_sub(since) < self.window_secs).then_some((count, since))
+    }
+
+    /// Whether the next attempt for `key` needs a solved challenge.
+    pub fn needs_challenge(&self, key: &str) -> bool {
+        self.failures(key) >= self.challenge_after
+    }
+
+    /// Record a failed attempt for `key`.
+    pub fn failed(&self, key: &str) {
+        let now = now_secs();
+        if let Ok(mut lock) = self.failures.write() {
+            let entry = lock.entry(key.to_string()).or_insert((0, now));
+            if now.saturating_sub(entry.1) >= self.window_secs {
+                *entry = (0, now);
+            }
+            entry.0 = entry.0.saturating_add(1);
+        }
+    }
+
+    /// Forget `key`'s failures after a successful attempt.
+    pub fn succeeded(&self, key: &str) {
+        if let Ok(mut lock) = self.failures.write() {
+            lock.remove(key);
+        }
+    }
+
+    /// Drop counters whose window has passed.
+    pub fn cleanup(&self) {
+        let now = now_secs();
+        if let Ok(mut lock) = self.failures.write() {
+            lock.retain(|_, (_, since)| now.saturating_sub(*since) < self.window_secs);
+        }
+    }
+
+    /// Decide whether an attempt for `key` may proceed; see the
+    /// [module docs](self) for the responses.
+    #[allow(clippy::result_large_err)]
+    pub fn check(&self, ctx: &Context<'_>, key: &str) -> Result<(), Response> {
+        let Some((count, since)) = self.current(key) else {
+            return Ok(());
+        };
+        if count < self.challenge_after {
+            return Ok(());
+        }
+        let provider = match &self.provider {
+            Some(p) if count < self.max_attempts => p,
+            _ => {
+                let retry = (since + self.window_secs).saturating_sub(now_secs()).max(1);
+                return Err(Response::new(429).with_header("Retry-After", retry));
+            }
+        };
+        if let Some(answer) = ctx.header(CHALLENGE_RESPONSE_HEADER)
+            && provider.verify(answer)
+        {
+            return Ok(());
+        }
+        let challenge = provider.issue();
+        let mut res = Response::json_bytes(challenge.to_json());
+        res.status = 428;
+        Err(res.with_header("X-Challenge", challenge.header_value()))
+    }
+}
+
+static GLOBAL_SHIELD: OnceLock<LoginShield> = OnceLock::new();
+
+/// Install the global [`LoginShield`].
+///
+/// Call this **once** before starting the server. Panics if called more than once.
+pub fn init_login_shield(shield: LoginShield) {
+    if GLOBAL_SHIELD.set(shield).is_err() {
+        panic!("LoginShield already initialised — call init_login_shield only once");
+    }
+}
+
+/// The global [`LoginShield`], or one built from the default
+/// [`SecurityConfig`] if [`init_login_shield`] was never called.
+pub fn login_shield() -> &'static LoginShield {
+    GLOBAL_SHIELD.get_or_init(|| {
+        LoginShield::from_config(&SecurityConfig::default(), None)
+            .expect("default SecurityConfig uses proof of work")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pow_solve_and_verify_once() {
+        let pow = ProofOfWork::new(b"secret", 8);
+        let challenge = pow.challenge_at(now_secs());
+        let answer = ProofOfWork::solve(&challenge).unwrap();
+        assert!(leading_zero_bits(&sha256(answer.as_bytes())) >= 8);
+        assert!(pow.verify(&answer));
+        assert!(!pow.verify(&answer), "answers are single-use");
+    }
+
+    #[test]
+    fn test_pow_rejects_forged_expired_and_unsolved() {
+        let pow = ProofOfWork::new(b"secret", 8);
+        let other = ProofOfWork::new(b"other", 8);
+        let answer = ProofOfWork::solve(&other.challenge_at(now_secs())).unwrap();
+        assert!(!pow.verify(&answer));
+
+        // Lowering the difficulty invalidates the mac.
+        let challenge = pow.challenge_at(now_secs());
+        let easy = challenge.replacen(".8.", ".0.", 1);
+        assert!(!pow.verify(&format!("{easy}:0")));
+
+        let answer = ProofOfWork::solve(&pow.challenge_at(now_secs() - 400)).unwrap();
+        assert!(!pow.verify(&answer));
+
+        let hard = ProofOfWork::new(b"secret", 255);
+        assert!(!hard.verify(&format!("{}:0", hard.challenge_at(now_secs()))));
+        assert!(!pow.verify("garbage"));
+    }
+
+    #[test]
+    fn test_captcha_posts_form_and_reads_success() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let post: HttpPost = Arc::new(move |url: &str, form: &str| {
+            log.lock()
+                .unwrap()
+                .push((url.to_string(), form.to_string()));
+            Ok(if form.ends_with("response=good") {
+                r#"{"success":true}"#.to_string()
+            } else {
+                r#"{"success":false,"error-codes":["invalid-input-response"]}"#.to_string()
+            })
+        });
+        let captcha = CaptchaVerifier::turnstile("s3cr&t", "site", post);
+        assert!(captcha.verify("good"));
+        assert!(!captcha.verify("bad"));
+        assert!(!captcha.verify(""));
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert!(seen[0].0.contains("turnstile"));
+        assert_eq!(seen[0].1, "secret=s3cr%26t&response=good");
+        assert_eq!(
+            captcha.issue().header_value(),
+            r#"turnstile sitekey="site""#
+        );
+
+        let down: HttpPost = Arc::new(|_: &str, _: &str| Err("timeout".to_string()));
+        assert!(!CaptchaVerifier::hcaptcha("s", "k", down).verify("good"));
+    }
+
+    #[test]
+    fn test_failures_and_thresholds() {
+        let shield = LoginShield::new(2, 4, 60);
+        assert!(!shield.needs_challenge("a"));
+        shield.failed("a");
+        shield.failed("a");
+        assert_eq!(shield.failures("a"), 2);
+        assert!(shield.needs_challenge("a"));
+        assert!(!shield.needs_challenge("b"));
+        shield.succeeded("a");
+        assert_eq!(shield.failures("a"), 0);
+    }
+
+    #[test]
+    fn test_from_config() {
+        let cfg = SecurityConfig {
+            challenge: ChallengeKind::Turnstile,
+            ..SecurityConfig::default()
+        };
+        assert!(LoginShield::from_config(&cfg, None).is_err());
+        let post: HttpPost = Arc::new(|_: &str, _: &str| Ok(String::new()));
+        let cfg = SecurityConfig {
+            captcha_secret: Some("s".into()),
+            captcha_site_key: Some("k".into()),
+            ..cfg
+        };
+        assert!(LoginShield::from_config(&cfg, Some(post)).is_ok());
+        assert!(LoginShield::from_config(&SecurityConfig::default(), None).is_ok());
+    }
+}
@@ -0,0 +1,204 @@
+// src/claims.rs
+//! Registered JWT claims plus an application-defined custom section.
+//!
+//! Put data that guards need on every request (tenant, plan, feature flags)
+//! into the token when it is issued, and read it back typed with the
+//! [`Claims<T>`] extractor instead of querying the database again:
+//!
+//! ```rust,ignore
+//! use chopin_auth::{Claims, init_jwt_manager, JwtManager};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! struct Tenant { tenant_id: u64, plan: String }
+//!
+//! // At login:
+//! let claims = Claims::new(user.id.to_string(), Duration::from_secs(900), Tenant {
+//!     tenant_id: user.tenant_id,
+//!     plan: user.plan.clone(),
+//! })
+//! .with_session(&session_id);
+//! let token = manager.encode(&claims)?;
+//!
+//! // In handlers and guards; `Claims<T>` derefs to `T`:
+//! #[get("/api/reports")]
+//! #[authorize(Claims<Tenant>, |c| c.plan == "pro")]
+//! fn reports(ctx: Context, claims: Claims<Tenant>) -> Response { .. }
+//! ```
+//!
+//! Custom fields are flattened into the token next to the registered ones,
+//! so other services see a plain `{"sub": .., "tenant_id": .., "plan": ..}`
+//! payload. Avoid custom field names that clash with `sub`, `iat`, `exp`,
+//! `jti` or `sid`.
+use std::ops::{Deref, DerefMut};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::extractor::Auth;
+use crate::jwt::HasJti;
+use crate::oauth::{base64url_encode, getrandom};
+use chopin_core::extract::FromRequest;
+use chopin_core::http::{Context, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Registered claims (`sub`, `iat`, `exp`, `jti`, `sid`) and a custom section `T`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claims<T> {
+    pub sub: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+    /// Session id, used by [`TokenDenylist`](crate::TokenDenylist) and
+    /// [`MfaService`](crate::MfaService).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sid: Option<String>,
+    #[serde(flatten)]
+    pub custom: T,
+}
+
+impl<T> Claims<T> {
+    /// Claims for `sub`, issued now and valid for `ttl`, with a random `jti`.
+    pub fn new(sub: impl Into<String>, ttl: Duration, custom: T) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let mut jti = [0u8; 16];
+        getrandom(&mut jti);
+        Self {
+            sub: sub.into(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+            jti: Some(base64url_encode(&jti)),
+            sid: None,
+            custom,
+        }
+    }
+
+    /// Attach a session id (`sid`).
+    pub fn with_session(mut self, sid: impl Into<String>) -> Self {
+        self.sid = Some(sid.into());
+        self
+    }
+
+    /// Replace the generated `jti`.
+    pub fn with_jti(mut self, jti: impl Into<String>) -> Self {
+        self.jti = Some(jti.into());
+        self
+    }
+
+    /// The custom section.
+    pub fn into_custom(self) -> T {
+        self.custom
+    }
+}
+
+impl<T> Deref for Claims<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.custom
+    }
+}
+
+impl<T> DerefMut for Claims<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.custom
+    }
+}
+
+impl<T> HasJti for Claims<T> {
+    fn jti(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+}
+
+/// Fails like [`Auth`]: `401` for a missing, invalid or revoked token,
+/// including one whose custom section does not deserialize as `T`.
+impl<'a, T> FromRequest<'a> for Claims<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = Response;
+
+    #[allow(clippy::result_large_err)]
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        Auth::<Claims<T>>::from_request(ctx).map(|auth| auth.claims)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::JwtManager;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    struct Tenant {
+        tenant_id: u64,
+        plan: String,
+        #[serde(default)]
+        beta: bool,
+    }
+
+    fn tenant() -> Tenant {
+        Tenant {
+            tenant_id: 7,
+            plan: "pro".into(),
+            beta: false,
+        }
+    }
+
+    #[test]
+    fn test_custom_section_is_flattened() {
+        let claims = Claims::new("u1", Duration::from_secs(60), tenant()).with_session("s1");
+        let json = serde_json::to_value(&claims).unwrap();
+        assert_eq!(json["sub"], "u1");
+        assert_eq!(json["sid"], "s1");
+        assert_eq!(json["tenant_id"], 7);
+        assert_eq!(json["plan"], "pro");
+        assert_eq!(
+            json["exp"].as_u64().unwrap() - json["iat"].as_u64().unwrap(),
+            60
+        );
+        assert!(json.get("custom").is_none());
+    }
+
+    #[test]
+    fn test_round_trip_through_manager() {
+        let mgr = JwtManager::new(b"claims-test");
+        let claims = Claims::new("u1", Duration::from_secs(60), tenant());
+        let token = mgr.encode(&claims).unwrap();
+        let decoded: Claims<Tenant> = mgr.decode(&token).unwrap();
+        assert_eq!(decoded, claims);
+        assert_eq!(decoded.plan, "pro");
+        assert_eq!(decoded.jti(), claims.jti.as_deref());
+    }
+
+    #[test]
+    fn test_tokens_from_other_issuers() {
+        // No jti/sid, extra claims ignored, missing custom default applied.
+        let mgr = JwtManager::new(b"claims-test");
+        let token = mgr
+            .encode(&serde_json::json!({
+                "sub": "u2", "iat": 1, "exp": 253_370_764_800_u64,
+                "tenant_id": 3, "plan": "free", "iss": "idp"
+            }))
+            .unwrap();
+        let decoded: Claims<Tenant> = mgr.decode(&token).unwrap();
+        assert_eq!((decoded.tenant_id, decoded.beta), (3, false));
+        assert_eq!(decoded.jti, None);
+
+        let token = mgr
+            .encode(&serde_json::json!({ "sub": "u3", "iat": 1, "exp": 253_370_764_800_u64 }))
+            .unwrap();
+        assert!(mgr.decode::<Claims<Tenant>>(&token).is_err());
+    }
+
+    #[test]
+    fn test_jti_is_random() {
+        let a = Claims::new("u", Duration::from_secs(1), ());
+        let b = Claims::new("u", Duration::from_secs(1), ());
+        assert_ne!(a.jti, b.jti);
+        assert_eq!(b.with_jti("fixed").jti(), Some("fixed"));
+    }
+}
@@ -3,10 +3,11 @@ use argon2::{
     Argon2, Params,
     password_hash::{
         PasswordHash, PasswordHasher as Argon2PasswordHasher, PasswordVerifier, SaltString,
-        rand_core::OsRng,
+        rand_core::{OsRng, RngCore},
     },
 };
 use chopin_core::error::{ChopinError, ChopinResult};
+use std::sync::{Arc, LazyLock};
 
 // ─── PasswordHasher ──────────────────────────────────────────────────────────
 
@@ -27,18 +28,23 @@ use chopin_core::error::{ChopinError, ChopinResult};
 /// let hash = hasher.hash(b"my-password")?;
 /// assert!(hasher.verify(b"my-password", &hash)?);
 /// ```
+///
+/// Constructing a hasher runs one hash up front, for the dummy that
+/// [`verify_login`](Self::verify_login) checks unknown users against, so
+/// build it once at startup and clone it (clones are cheap) rather than
+/// constructing one per request.
 #[derive(Clone)]
 pub struct PasswordHasher {
     params: Params,
+    /// Hash of a random password with `params`, for [`verify_login`](Self::verify_login).
+    dummy: Arc<str>,
 }
 
 impl PasswordHasher {
     /// Interactive preset (Argon2id defaults: 19 MiB, 2 iterations, 1 thread).
     /// Balanced between speed and security; suitable for most login flows.
     pub fn interactive() -> Self {
-        Self {
-            params: Params::default(),
-        }
+        Self::with_params(Params::default())
     }
 
     /// Sensitive preset (64 MiB, 4 iterations, 2 threads).
@@ -55,7 +61,18 @@ impl PasswordHasher {
     pub fn custom(memory_kib: u32, iterations: u32, parallelism: u32) -> ChopinResult<Self> {
         let params = Params::new(memory_kib, iterations, parallelism, None)
             .map_err(|e| ChopinError::Other(format!("invalid Argon2 params: {e}")))?;
-        Ok(Self { params })
+        Ok(Self::with_params(params))
+    }
+
+    fn with_params(params: Params) -> Self {
+        let mut hasher = Self {
+            params,
+            dummy: Arc::from(""),
+        };
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        hasher.dummy = hasher.hash(&secret).unwrap_or_default().into();
+        hasher
     }
 
     /// Hash a password using Argon2id. Returns a PHC-format string.
@@ -80,6 +97,43 @@ impl PasswordHasher {
             .map_err(|e| ChopinError::Other(format!("invalid hash format: {e}")))?;
         Ok(Argon2::default().verify_password(password, &parsed).is_ok())
     }
+
+    /// Check a login attempt in the same time whether or not the account
+    /// exists.
+    ///
+    /// Pass the stored hash, or `None` when no user matched. Without a hash,
+    /// or with one that does not parse, the password is checked against a
+    /// dummy hash made with this hasher's parameters and `false` is
+    /// returned. A response that comes back faster for unknown users would
+    /// tell an attacker which accounts exist, so answer every `false` the
+    /// same way too:
+    ///
+    /// ```rust,ignore
+    /// let user = User::find().filter(email.eq(&form.email)).first(db)?;
+    /// if !hasher.verify_login(form.password.as_bytes(), user.as_ref().map(|u| u.password_hash.as_str())) {
+    ///     login_shield().failed(&form.email);
+    ///     return Response::unauthorized();
+    /// }
+    /// ```
+    ///
+    /// The dummy costs as much as a real check only if stored hashes were
+    /// made with the same parameters, so verify with the hasher that hashes.
+    /// It is made when the hasher is constructed, so the first unknown user
+    /// is not given away by a slower response either.
+    pub fn verify_login(&self, password: &[u8], hash: Option<&str>) -> bool {
+        if let Some(parsed) = hash.and_then(|h| PasswordHash::new(h).ok()) {
+            return Argon2::default().verify_password(password, &parsed).is_ok();
+        }
+        let dummy = self.dummy_hash();
+        if let Ok(parsed) = PasswordHash::new(dummy) {
+            let _ = Argon2::default().verify_password(password, &parsed);
+        }
+        false
+    }
+
+    fn dummy_hash(&self) -> &str {
+        &self.dummy
+    }
 }
 
 impl Default for PasswordHasher {
@@ -95,14 +149,28 @@ impl Default for PasswordHasher {
 /// This is a convenience wrapper around [`PasswordHasher::interactive`].
 /// For configurable parameters, use [`PasswordHasher`] directly.
 pub fn hash_password(password: &[u8]) -> ChopinResult<String> {
-    PasswordHasher::interactive().hash(password)
+    INTERACTIVE.hash(password)
 }
 
 /// Verify a password against a PHC-format hash string.
 ///
 /// This is a convenience wrapper around [`PasswordHasher::interactive`].
 pub fn verify_password(password: &[u8], hash: &str) -> ChopinResult<bool> {
-    PasswordHasher::interactive().verify(password, hash)
+    INTERACTIVE.verify(password, hash)
+}
+
+/// Shared by the free functions; built, dummy hash included, on first use.
+static INTERACTIVE: LazyLock<PasswordHasher> = LazyLock::new(PasswordHasher::interactive);
+
+/// Check a login attempt against a hash from [`hash_password`], taking as
+/// long when the user does not exist (`None`).
+///
+/// This is a convenience wrapper around [`PasswordHasher::verify_login`].
+/// Its hasher is built by the first call to any of these functions, so
+/// call [`hash_password`] or this at startup, or keep your own
+/// [`PasswordHasher`], to keep that cost off the first login.
+pub fn verify_login(password: &[u8], hash: Option<&str>) -> bool {
+    INTERACTIVE.verify_login(password, hash)
 }
 
 #[cfg(test)]
@@ -175,6 +243,26 @@ mod tests {
         assert!(hasher.verify(b"sensitive", &hash).unwrap());
     }
 
+    #[test]
+    fn test_verify_login() {
+        let hasher = PasswordHasher::custom(8, 1, 1).unwrap();
+        let hash = hasher.hash(b"letmein").unwrap();
+        assert!(hasher.verify_login(b"letmein", Some(&hash)));
+        assert!(!hasher.verify_login(b"wrong", Some(&hash)));
+        assert!(!hasher.verify_login(b"letmein", None));
+        assert!(!hasher.verify_login(b"letmein", Some("not-a-valid-hash")));
+        assert!(!verify_login(b"letmein", None));
+    }
+
+    #[test]
+    fn test_dummy_hash_matches_params_and_is_reused() {
+        let hasher = PasswordHasher::custom(16, 3, 1).unwrap();
+        let dummy = hasher.dummy_hash().to_string();
+        assert!(dummy.contains("m=16,t=3,p=1"), "{dummy}");
+        // Clones share it rather than computing their own.
+        assert_eq!(hasher.clone().dummy_hash(), dummy);
+    }
+
     #[test]
     fn test_custom_params() {
         // Use very low params so the test is fast.
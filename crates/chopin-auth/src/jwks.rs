@@ -298,7 +298,7 @@ fn extract_kid_from_header(token: &str) -> Option<String> {
 }
 
 /// Decode base64url (no padding) to bytes.
-fn base64url_decode(input: &str) -> Option<Vec<u8>> {
+pub(crate) fn base64url_decode(input: &str) -> Option<Vec<u8>> {
     const TABLE: [u8; 128] = {
         let mut t = [255u8; 128];
         let mut i = 0u8;
@@ -4,7 +4,8 @@ use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::sync::Arc;
 
-use crate::revocation::TokenBlacklist;
+use crate::jwks::base64url_decode;
+use crate::revocation::{DenylistClaims, TokenBlacklist, TokenDenylist};
 
 // ─── Error type ──────────────────────────────────────────────────────────────
 
@@ -94,11 +95,13 @@ pub struct JwtConfig {
 /// - [`JwtManager::from_ec_public_pem`] – ES256 (verify only)
 /// - [`JwtManager::with_config`]        – fully custom config
 ///
-/// Add a revocation blacklist with [`JwtManager::with_blacklist`].
+/// Add a revocation blacklist with [`JwtManager::with_blacklist`], or
+/// session- and subject-wide revocation with [`JwtManager::with_denylist`].
 #[derive(Clone)]
 pub struct JwtManager {
     config: Arc<JwtConfig>,
     blacklist: Option<TokenBlacklist>,
+    denylist: Option<TokenDenylist>,
 }
 
 impl JwtManager {
@@ -113,6 +116,7 @@ impl JwtManager {
                 validation,
             }),
             blacklist: None,
+            denylist: None,
         }
     }
 
@@ -127,6 +131,7 @@ impl JwtManager {
                 validation,
             }),
             blacklist: None,
+            denylist: None,
         }
     }
 
@@ -146,6 +151,7 @@ impl JwtManager {
                 validation: Validation::new(Algorithm::RS256),
             }),
             blacklist: None,
+            denylist: None,
         })
     }
 
@@ -164,6 +170,7 @@ impl JwtManager {
                 validation: Validation::new(Algorithm::RS256),
             }),
             blacklist: None,
+            denylist: None,
         })
     }
 
@@ -183,6 +190,7 @@ impl JwtManager {
                 validation: Validation::new(Algorithm::ES256),
             }),
             blacklist: None,
+            denylist: None,
         })
     }
 
@@ -201,6 +209,7 @@ impl JwtManager {
                 validation: Validation::new(Algorithm::ES256),
             }),
             blacklist: None,
+            denylist: None,
         })
     }
 
@@ -209,6 +218,7 @@ impl JwtManager {
         Self {
             config: Arc::new(config),
             blacklist: None,
+            denylist: None,
         }
     }
 
@@ -224,14 +234,27 @@ impl JwtManager {
         self
     }
 
+    /// Attach a [`TokenDenylist`], returning the updated manager.
+    ///
+    /// Unlike the blacklist, the denylist reads `jti`, `sub`, `iat` and the
+    /// session claim from the token itself, so it applies whatever the
+    /// claims type `T` passed to [`decode`](Self::decode) looks like.
+    pub fn with_denylist(mut self, denylist: TokenDenylist) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
     /// Decode and verify a JWT, optionally checking revocation.
     ///
     /// If a [`TokenBlacklist`] is attached and `T::jti()` returns `Some(jti)`,
     /// the JTI is checked for revocation after the signature is verified.
+    /// If a [`TokenDenylist`] is attached, the token's own claims are checked
+    /// against it as well.
     ///
     /// # Errors
     /// - [`AuthError::Expired`]      – the `exp` claim has passed.
-    /// - [`AuthError::Revoked`]      – the JTI is on the blacklist.
+    /// - [`AuthError::Revoked`]      – the JTI is on the blacklist, or the
+    ///   token, its session or its subject is on the denylist.
     /// - [`AuthError::InvalidToken`] – signature or format error.
     pub fn decode<T>(&self, token: &str) -> Result<T, AuthError>
     where
@@ -250,6 +273,19 @@ impl JwtManager {
             return Err(AuthError::Revoked);
         }
 
+        if let Some(dl) = &self.denylist {
+            // The signature has been verified, so the payload can be read as is.
+            let claims = token
+                .split('.')
+                .nth(1)
+                .and_then(base64url_decode)
+                .and_then(|payload| serde_json::from_slice::<DenylistClaims>(&payload).ok())
+                .ok_or_else(|| AuthError::InvalidToken("unreadable claims".to_string()))?;
+            if dl.is_denied(&claims) {
+                return Err(AuthError::Revoked);
+            }
+        }
+
         Ok(token_data.claims)
     }
 
@@ -405,6 +441,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_denylist_ignores_claims_type() {
+        use crate::revocation::TokenDenylist;
+
+        let denylist = TokenDenylist::new();
+        let mgr = JwtManager::new(b"s").with_denylist(denylist.clone());
+        // `TestClaims` has no jti and opts out of the blacklist.
+        let token = mgr
+            .encode(&serde_json::json!({
+                "sub": "u", "sid": "sess-1", "iat": 1_u64, "exp": far_future_exp()
+            }))
+            .unwrap();
+        mgr.decode::<TestClaims>(&token)
+            .expect("valid before logout");
+
+        denylist.revoke_session("sess-1", None);
+        let result = mgr.decode::<TestClaims>(&token);
+        assert!(
+            matches!(result, Err(AuthError::Revoked)),
+            "revoked session should be rejected, got {result:?}"
+        );
+
+        let other = mgr
+            .encode(&serde_json::json!({
+                "sub": "v", "sid": "sess-2", "iat": 1_u64, "exp": far_future_exp()
+            }))
+            .unwrap();
+        mgr.decode::<TestClaims>(&other)
+            .expect("other session unaffected");
+        denylist.revoke_subject("v", None);
+        assert!(matches!(
+            mgr.decode::<TestClaims>(&other),
+            Err(AuthError::Revoked)
+        ));
+    }
+
     #[test]
     fn test_expired_token_returns_expired_error() {
         let mgr = JwtManager::new(b"secret");
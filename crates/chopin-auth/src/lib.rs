@@ -28,24 +28,81 @@
 //! // In a route handler, use `Auth<Claims>` as an extractor:
 //! // async fn my_handler(auth: Auth<Claims>) -> Response { ... }
 //!
-//! // Hash a password:
-//! let hash = PasswordHasher::interactive().hash(b"p4ssw0rd")?;
+//! // Build the hasher once at startup, then hash a password:
+//! let hasher = PasswordHasher::interactive();
+//! let hash = hasher.hash(b"p4ssw0rd")?;
+//!
+//! // Check a login, as slowly for unknown users as for known ones:
+//! let ok = hasher.verify_login(b"p4ssw0rd", Some(&hash));
 //!
 //! // Revoke a token (e.g. on logout):
 //! // blacklist.revoke(claims.jti.clone(), Some(claims.exp));
 //! ```
+//!
+//! To skip writing a claims type, [`Claims<T>`](claims::Claims) carries
+//! `sub`/`iat`/`exp`/`jti`/`sid` plus your own fields `T`; see [`claims`].
+//!
+//! Internal services authenticate as [`ServiceAccount`]s rather than users;
+//! see [`service_account`].
+//!
+//! To let other applications sign users in through yours, mount an
+//! [`OidcModule`]; see [`oidc`].
+//!
+//! Signup, reset and change-password endpoints check new passwords against
+//! one [`PasswordPolicy`]; see [`password`].
+//!
+//! With the `orm` feature, the `audit` module stores role grants and keeps
+//! an audit trail of every change, and [`scim`] lets identity providers
+//! provision users and groups.
+#[cfg(feature = "orm")]
+pub mod audit;
+pub mod challenge;
+pub mod claims;
 pub mod crypto;
 pub mod extractor;
 pub mod jwks;
 pub mod jwt;
+pub mod mfa;
 pub mod middleware;
 pub mod oauth;
+pub mod oidc;
+#[cfg(feature = "orm")]
+pub mod owner;
+pub mod password;
+pub mod rbac;
 pub mod revocation;
+#[cfg(feature = "orm")]
+pub mod scim;
+pub mod service_account;
 
-pub use crypto::{PasswordHasher, hash_password, verify_password};
+pub use challenge::{
+    CaptchaVerifier, Challenge, ChallengeKind, ChallengeProvider, HttpPost, LoginShield,
+    ProofOfWork, SecurityConfig, init_login_shield, login_shield,
+};
+pub use claims::Claims;
+pub use crypto::{PasswordHasher, hash_password, verify_login, verify_password};
 pub use extractor::{Auth, ErrorHandler, init_jwt_manager, set_error_handler};
 pub use jwks::JwksProvider;
 pub use jwt::{AuthError, HasJti, JwtConfig, JwtManager};
+pub use mfa::{MfaService, MfaVerified, init_mfa, mfa};
 pub use middleware::{Role, RoleCheck, ScopeCheck};
 pub use oauth::{AuthorizationUrl, TokenPair, code_challenge_s256, code_verifier, token_pair};
-pub use revocation::TokenBlacklist;
+pub use oidc::{OidcClient, OidcClients, OidcModule, SigningKey};
+pub use password::{
+    CharClass, HttpGet, PasswordConfig, PasswordError, PasswordPolicy, Strength, Violation,
+    init_password_policy, password_policy,
+};
+pub use rbac::{RbacService, Roles, init_rbac, rbac};
+pub use revocation::{DenylistStore, MemoryDenylistStore, TokenBlacklist, TokenDenylist};
+pub use service_account::{
+    MemoryServiceAccountStore, ServiceAccount, ServiceAccountError, ServiceAccountStore,
+    ServiceAccounts, ServiceClaims, ServiceToken, init_service_accounts, service_accounts,
+};
+
+/// `#[mfa_required]` / `#[mfa_required(max_age = 60)]`: see [`mfa`](mod@mfa).
+pub use chopin_core::mfa_required;
+/// `#[owner_required(Post, field = "author_id")]`: see [`owner`].
+#[cfg(feature = "orm")]
+pub use chopin_core::owner_required;
+/// `#[role_required("staff")]`: see [`rbac`](mod@rbac).
+pub use chopin_core::role_required;
@@ -0,0 +1,272 @@
+// src/mfa.rs
+//! Step-up authentication for `#[mfa_required]`.
+//!
+//! Some operations (deleting an account, changing payout details) should
+//! need more than a valid bearer token: the caller must also have passed a
+//! second factor recently. [`MfaService`] remembers when each session last
+//! did so, keyed by a session claim in the token (`sid` by default):
+//!
+//! ```rust,ignore
+//! use chopin_auth::{MfaService, init_mfa, mfa, mfa_required};
+//!
+//! init_mfa(MfaService::new().with_window(Duration::from_secs(300)));
+//!
+//! // After the user's TOTP / WebAuthn check succeeds:
+//! mfa().record(&claims.sid);
+//!
+//! #[delete("/api/account")]
+//! #[mfa_required]
+//! fn delete_account(ctx: Context) -> Response { .. }
+//!
+//! #[put("/api/payouts")]
+//! #[mfa_required(max_age = 60)]
+//! fn update_payouts(ctx: Context, mfa: MfaVerified) -> Response { .. }
+//! ```
+//!
+//! `max_age` (seconds) tightens the service's window for one handler; it
+//! cannot widen it.
+//!
+//! | Outcome                                          | Response |
+//! |--------------------------------------------------|----------|
+//! | missing or invalid token                         | `401`    |
+//! | no session claim, or no fresh verification       | `401` with `WWW-Authenticate: Bearer error="insufficient_user_authentication"` |
+//!
+//! The `WWW-Authenticate` challenge follows RFC 9470 and carries the
+//! `max_age` the client must satisfy, so it can send the user through the
+//! second factor and retry.
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::extractor::Auth;
+use crate::rbac::RawClaims;
+use chopin_core::extract::FromRequest;
+use chopin_core::http::{Context, Response};
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Per-session record of the last successful second-factor check.
+///
+/// Cloning shares the underlying store.
+#[derive(Debug, Clone)]
+pub struct MfaService {
+    /// Session id → Unix seconds of the last verification.
+    verified: Arc<RwLock<HashMap<String, u64>>>,
+    window_secs: u64,
+    claim: String,
+}
+
+impl Default for MfaService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MfaService {
+    /// Five-minute freshness window, sessions read from the `sid` claim.
+    pub fn new() -> Self {
+        Self {
+            verified: Arc::new(RwLock::new(HashMap::new())),
+            window_secs: 300,
+            claim: "sid".to_string(),
+        }
+    }
+
+    /// How long a verification stays fresh.
+    pub fn with_window(mut self, window: Duration) -> Self {
+        self.window_secs = window.as_secs();
+        self
+    }
+
+    /// Read the session id from `claim` instead of `sid`.
+    pub fn with_session_claim(mut self, claim: &str) -> Self {
+        self.claim = claim.to_string();
+        self
+    }
+
+    /// The freshness window in seconds.
+    pub fn window_secs(&self) -> u64 {
+        self.window_secs
+    }
+
+    /// Name of the claim session ids are read from.
+    pub fn session_claim(&self) -> &str {
+        &self.claim
+    }
+
+    /// Record a successful second-factor check for `session` now.
+    pub fn record(&self, session: &str) {
+        self.record_at(session, now_secs());
+    }
+
+    /// Record a second-factor check for `session` at `at` (Unix seconds).
+    pub fn record_at(&self, session: &str, at: u64) {
+        if let Ok(mut lock) = self.verified.write() {
+            lock.insert(session.to_string(), at);
+        }
+    }
+
+    /// When `session` last passed a second factor, if ever.
+    pub fn verified_at(&self, session: &str) -> Option<u64> {
+        self.verified.read().ok()?.get(session).copied()
+    }
+
+    /// Whether `session` passed a second factor within the window, further
+    /// limited to `max_age` seconds when given.
+    ///
+    /// Fails closed: returns `false` if the internal lock is poisoned.
+    pub fn is_fresh(&self, session: &str, max_age: Option<u64>) -> bool {
+        let max_age = max_age.map_or(self.window_secs, |m| m.min(self.window_secs));
+        self.verified_at(session)
+            .is_some_and(|at| now_secs().saturating_sub(at) <= max_age)
+    }
+
+    /// Forget `session`'s verification, e.g. on logout.
+    pub fn clear(&self, session: &str) {
+        if let Ok(mut lock) = self.verified.write() {
+            lock.remove(session);
+        }
+    }
+
+    /// Remove all verifications older than the window.
+    ///
+    /// Call this periodically to bound memory use.
+    pub fn cleanup(&self) {
+        let cutoff = now_secs().saturating_sub(self.window_secs);
+        if let Ok(mut lock) = self.verified.write() {
+            lock.retain(|_, at| *at >= cutoff);
+        }
+    }
+
+    /// Number of sessions currently tracked.
+    pub fn len(&self) -> usize {
+        self.verified.read().map(|l| l.len()).unwrap_or(0)
+    }
+
+    /// `true` when no session is tracked.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+static GLOBAL_MFA: OnceLock<MfaService> = OnceLock::new();
+
+/// Install the global [`MfaService`] used by [`MfaVerified`] and
+/// `#[mfa_required]`.
+///
+/// Call this **once** before starting the server. Panics if called more than once.
+pub fn init_mfa(service: MfaService) {
+    if GLOBAL_MFA.set(service).is_err() {
+        panic!("MfaService already initialised — call init_mfa only once");
+    }
+}
+
+/// The global [`MfaService`], or a default one if [`init_mfa`] was never called.
+pub fn mfa() -> &'static MfaService {
+    GLOBAL_MFA.get_or_init(MfaService::new)
+}
+
+/// Extractor that admits only sessions with a fresh second-factor check
+/// under the global [`MfaService`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MfaVerified {
+    /// The session id from the token.
+    pub session: String,
+    /// Unix seconds of the verification that satisfied the check.
+    pub verified_at: u64,
+}
+
+impl<'a> FromRequest<'a> for MfaVerified {
+    type Error = Response;
+
+    #[allow(clippy::result_large_err)]
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        require_fresh(ctx, None)
+    }
+}
+
+/// The RFC 9470 step-up challenge.
+fn step_up_challenge(max_age: u64) -> Response {
+    Response::new(401).with_header(
+        "WWW-Authenticate",
+        format!(r#"Bearer error="insufficient_user_authentication", max_age={max_age}"#),
+    )
+}
+
+/// Check that the caller's session passed a second factor within the
+/// global window, or within `max_age` seconds if that is shorter.
+/// Expanded from `#[mfa_required]`.
+#[allow(clippy::result_large_err)]
+pub fn require_fresh(ctx: &Context<'_>, max_age: Option<u64>) -> Result<MfaVerified, Response> {
+    let Auth { claims } = Auth::<RawClaims>::from_request(ctx)?;
+    let service = mfa();
+    let limit = max_age.map_or(service.window_secs(), |m| m.min(service.window_secs()));
+    let session = match claims.0.get(service.session_claim()) {
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Number(n)) => n.to_string(),
+        _ => return Err(step_up_challenge(limit)),
+    };
+    match service.verified_at(&session) {
+        Some(at) if now_secs().saturating_sub(at) <= limit => Ok(MfaVerified {
+            session,
+            verified_at: at,
+        }),
+        _ => Err(step_up_challenge(limit)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fresh_within_window() {
+        let mfa = MfaService::new().with_window(Duration::from_secs(60));
+        assert!(!mfa.is_fresh("s1", None));
+        mfa.record("s1");
+        assert!(mfa.is_fresh("s1", None));
+        assert!(mfa.is_fresh("s1", Some(10)));
+        assert!(!mfa.is_fresh("s2", None));
+
+        mfa.record_at("s1", now_secs() - 120);
+        assert!(!mfa.is_fresh("s1", None));
+    }
+
+    #[test]
+    fn test_max_age_only_narrows_window() {
+        let mfa = MfaService::new().with_window(Duration::from_secs(60));
+        mfa.record_at("s1", now_secs() - 30);
+        assert!(mfa.is_fresh("s1", None));
+        assert!(!mfa.is_fresh("s1", Some(10)));
+        assert!(mfa.is_fresh("s1", Some(3_600)));
+        mfa.record_at("s1", now_secs() - 90);
+        assert!(!mfa.is_fresh("s1", Some(3_600)));
+    }
+
+    #[test]
+    fn test_clear_and_cleanup() {
+        let mfa = MfaService::new().with_window(Duration::from_secs(60));
+        mfa.record("fresh");
+        mfa.record_at("stale", now_secs() - 61);
+        mfa.record("gone");
+        mfa.clear("gone");
+        assert_eq!(mfa.len(), 2);
+        mfa.cleanup();
+        assert_eq!(mfa.len(), 1);
+        assert!(mfa.verified_at("fresh").is_some());
+        assert!(mfa.verified_at("stale").is_none());
+    }
+
+    #[test]
+    fn test_clones_share_store() {
+        let a = MfaService::new();
+        let b = a.clone();
+        a.record("s1");
+        assert!(b.is_fresh("s1", None));
+    }
+}
@@ -31,7 +31,7 @@ pub fn code_challenge_s256(verifier: &str) -> String {
 }
 
 /// Minimal SHA-256 (FIPS 180-4) — zero external dependencies.
-fn sha256(data: &[u8]) -> [u8; 32] {
+pub(crate) fn sha256(data: &[u8]) -> [u8; 32] {
     const K: [u32; 64] = [
         0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
         0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
@@ -120,14 +120,42 @@ fn sha256(data: &[u8]) -> [u8; 32] {
 }
 
 /// Fill buffer from OS CSPRNG.
-fn getrandom(buf: &mut [u8]) {
+pub(crate) fn getrandom(buf: &mut [u8]) {
     use std::io::Read;
     let mut f = std::fs::File::open("/dev/urandom").expect("cannot open /dev/urandom");
     f.read_exact(buf).expect("cannot read /dev/urandom");
 }
 
+/// `bytes` random bytes, base64url-encoded.
+pub(crate) fn random_token(bytes: usize) -> String {
+    let mut buf = vec![0u8; bytes];
+    getrandom(&mut buf);
+    base64url_encode(&buf)
+}
+
+/// HMAC-SHA-256 (RFC 2104) over [`sha256`].
+pub(crate) fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut block = [0u8; 64];
+    if key.len() > 64 {
+        block[..32].copy_from_slice(&sha256(key));
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner: Vec<u8> = block.iter().map(|b| b ^ 0x36).collect();
+    inner.extend_from_slice(message);
+    let mut outer: Vec<u8> = block.iter().map(|b| b ^ 0x5c).collect();
+    outer.extend_from_slice(&sha256(&inner));
+    sha256(&outer)
+}
+
+/// Compare secrets without leaking where they differ. Only the length
+/// comparison returns early.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 /// Base64url-encode (no padding) per RFC 4648 §5.
-fn base64url_encode(data: &[u8]) -> String {
+pub(crate) fn base64url_encode(data: &[u8]) -> String {
     const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
     let mut out = String::with_capacity((data.len() * 4).div_ceil(3));
     let mut i = 0;
@@ -227,7 +255,7 @@ impl AuthorizationUrl {
 }
 
 /// Minimal percent-encoding for query parameter values.
-fn percent_encode(s: &str) -> String {
+pub(crate) fn percent_encode(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for b in s.bytes() {
         match b {
@@ -282,6 +310,13 @@ pub fn token_pair<A: Serialize, R: Serialize>(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_hmac_sha256_rfc4231() {
+        // RFC 4231 test case 2.
+        let mac = hmac_sha256(b"Jefe", b"what do ya want for nothing?");
+        assert_eq!(mac[..8], [0x5b, 0xdc, 0xc1, 0x46, 0xbf, 0x60, 0x75, 0x4e]);
+    }
+
     #[test]
     fn test_sha256_empty() {
         let hash = sha256(b"");
@@ -0,0 +1,1289 @@
+// src/oidc.rs
+//! OpenID Connect provider: third-party applications sign their users in
+//! through this app ("Sign in with Acme").
+//!
+//! [`OidcModule`] serves the authorization code flow with mandatory PKCE
+//! (S256), a consent screen, ID and access tokens signed with an RSA key,
+//! and the discovery and JWKS documents clients configure themselves from.
+//! Routes live under the path of the issuer URL:
+//!
+//! | Method     | Path                                 | Description                      |
+//! |------------|--------------------------------------|----------------------------------|
+//! | GET        | `/.well-known/openid-configuration`  | Discovery document               |
+//! | GET        | `/jwks.json`                         | Public signing key               |
+//! | GET / POST | `/authorize`                         | Login check, consent, code       |
+//! | POST       | `/token`                             | Code + verifier → tokens         |
+//! | GET        | `/userinfo`                          | Claims for an access token       |
+//! | POST       | `/register`                          | Dynamic registration, opt-in     |
+//!
+//! ```rust,ignore
+//! use chopin_auth::oidc::{OidcClients, OidcModule, SigningKey};
+//!
+//! let clients = OidcClients::new();
+//! let (wiki, secret) = clients.register("Wiki", &["https://wiki.example.com/callback"], true);
+//!
+//! let key = SigningKey::from_rsa_pem(&std::fs::read("oidc.pem")?)?;
+//! Chopin::new()
+//!     .mount_module(SessionModule::new(PgSessionStore::new()))
+//!     .mount_module(CsrfModule::new())
+//!     .mount_module(
+//!         OidcModule::new("https://id.example.com/oauth", key, clients)
+//!             .current_user(|_ctx| session::get::<String>("user_id"))
+//!             .login_url("/login")
+//!             .claims(profile_claims),
+//!     );
+//! ```
+//!
+//! The application keeps ownership of its users: it tells the module who is
+//! logged in, where the login page is (the original request is passed to it
+//! as `?next=`), and which claims to put into ID tokens and `/userinfo`.
+//! The consent form is a [`Form`](chopin_core::Form), so with
+//! [`CsrfModule`](chopin_core::CsrfModule) mounted it is CSRF-checked.
+//!
+//! Authorization codes and remembered consents are held in the process, so
+//! the whole flow must be served by one instance (or sticky sessions).
+//! Refresh tokens are not issued.
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use chopin_core::Form;
+use chopin_core::extract::Query;
+use chopin_core::form::{csrf_field, escape_html};
+use chopin_core::http::{Context, Response};
+use chopin_core::module::ChopinModule;
+use chopin_core::router::Router;
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::jwks::base64url_decode;
+use crate::jwt::AuthError;
+use crate::oauth::{base64url_encode, code_challenge_s256, constant_time_eq, random_token, sha256};
+use crate::service_account::basic_credentials;
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn json_response<T: Serialize>(status: u16, value: &T) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => {
+            let mut res = Response::json_bytes(body);
+            res.status = status;
+            res
+        }
+        Err(_) => Response::server_error(),
+    }
+}
+
+// ─── Signing key ─────────────────────────────────────────────────────────────
+
+/// RSA key signing ID and access tokens (RS256), published at `/jwks.json`.
+#[derive(Clone)]
+pub struct SigningKey {
+    kid: String,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    jwk: Value,
+}
+
+impl SigningKey {
+    /// Load an RSA private key in PEM form, PKCS#1 (`BEGIN RSA PRIVATE KEY`)
+    /// or PKCS#8 (`BEGIN PRIVATE KEY`). The public half is derived from it,
+    /// and the key id is its RFC 7638 thumbprint.
+    pub fn from_rsa_pem(private_key_pem: &[u8]) -> Result<Self, AuthError> {
+        let encoding = EncodingKey::from_rsa_pem(private_key_pem)
+            .map_err(|e| AuthError::Internal(format!("RSA key: {e}")))?;
+        let (n, e) = rsa_public_components(private_key_pem)
+            .ok_or_else(|| AuthError::Internal("RSA key: cannot read modulus".into()))?;
+        let (n, e) = (base64url_encode(&n), base64url_encode(&e));
+        let decoding = DecodingKey::from_rsa_components(&n, &e)
+            .map_err(|e| AuthError::Internal(format!("RSA key: {e}")))?;
+        // Members in lexicographic order, no whitespace (RFC 7638 §3).
+        let thumbprint = format!(r#"{{"e":"{e}","kty":"RSA","n":"{n}"}}"#);
+        let kid = base64url_encode(&sha256(thumbprint.as_bytes()));
+        let jwk = serde_json::json!({
+            "kty": "RSA",
+            "use": "sig",
+            "alg": "RS256",
+            "kid": kid,
+            "n": n,
+            "e": e,
+        });
+        Ok(Self {
+            kid,
+            encoding,
+            decoding,
+            jwk,
+        })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    /// The public key as a JWK.
+    pub fn jwk(&self) -> &Value {
+        &self.jwk
+    }
+
+    fn sign(&self, claims: &Map<String, Value>) -> Result<String, AuthError> {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some(self.kid.clone());
+        jsonwebtoken::encode(&header, claims, &self.encoding)
+            .map_err(|e| AuthError::Encode(e.to_string()))
+    }
+}
+
+/// Modulus and exponent of a PKCS#1 or PKCS#8 RSA private key.
+fn rsa_public_components(pem: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let pem = std::str::from_utf8(pem).ok()?;
+    let body: String = pem
+        .lines()
+        .filter(|l| !l.starts_with("-----"))
+        .map(str::trim)
+        .collect();
+    let der = base64url_decode(&body)?;
+    let (tag, seq, _) = der_next(&der)?;
+    if tag != 0x30 {
+        return None;
+    }
+    let (_, _version, rest) = der_next(seq)?;
+    let (tag, value, rest) = der_next(rest)?;
+    let rsa_key = match tag {
+        // PKCS#1: version, n, e, ...
+        0x02 => return rsa_n_e(value, rest),
+        // PKCS#8: version, AlgorithmIdentifier, OCTET STRING { PKCS#1 }
+        0x30 => {
+            let (tag, octets, _) = der_next(rest)?;
+            (tag == 0x04).then_some(octets)?
+        }
+        _ => return None,
+    };
+    let (_, seq, _) = der_next(rsa_key)?;
+    let (_, _version, rest) = der_next(seq)?;
+    let (_, n, rest) = der_next(rest)?;
+    rsa_n_e(n, rest)
+}
+
+fn rsa_n_e(n: &[u8], rest: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+    let (_, e, _) = der_next(rest)?;
+    let unsigned = |int: &[u8]| {
+        let skip = int.iter().take_while(|&&b| b == 0).count();
+        int[skip.min(int.len().saturating_sub(1))..].to_vec()
+    };
+    Some((unsigned(n), unsigned(e)))
+}
+
+/// Split one DER TLV off `der`: `(tag, value, rest)`.
+fn der_next(der: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, der) = der.split_first()?;
+    let (&first, mut der) = der.split_first()?;
+    let len = if first < 0x80 {
+        first as usize
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || der.len() < count {
+            return None;
+        }
+        let len = der[..count]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        der = &der[count..];
+        len
+    };
+    (der.len() >= len).then(|| (tag, &der[..len], &der[len..]))
+}
+
+// ─── Clients ─────────────────────────────────────────────────────────────────
+
+/// An application allowed to sign users in through the provider.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OidcClient {
+    pub client_id: String,
+    /// Shown on the consent screen.
+    pub name: String,
+    /// Exact redirect URIs the client may use.
+    pub redirect_uris: Vec<String>,
+    /// SHA-256 of the client secret; `None` for public clients (SPAs, native
+    /// apps), which authenticate with PKCE alone.
+    pub secret_digest: Option<[u8; 32]>,
+    /// First-party clients skip the consent screen.
+    pub trusted: bool,
+}
+
+impl OidcClient {
+    pub fn is_confidential(&self) -> bool {
+        self.secret_digest.is_some()
+    }
+
+    /// Whether `secret` is this client's secret.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        self.secret_digest
+            .is_some_and(|d| constant_time_eq(&sha256(secret.as_bytes()), &d))
+    }
+}
+
+/// Storage behind [`OidcClients`].
+pub trait ClientStore: Send + Sync {
+    fn get(&self, client_id: &str) -> Option<OidcClient>;
+
+    /// Insert or replace the client with the same `client_id`.
+    fn put(&self, client: OidcClient);
+
+    fn remove(&self, client_id: &str) -> bool;
+
+    fn list(&self) -> Vec<OidcClient>;
+}
+
+/// In-process [`ClientStore`]. Cloning shares the clients.
+#[derive(Clone, Default)]
+pub struct MemoryClientStore {
+    clients: Arc<RwLock<HashMap<String, OidcClient>>>,
+}
+
+impl MemoryClientStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ClientStore for MemoryClientStore {
+    fn get(&self, client_id: &str) -> Option<OidcClient> {
+        self.clients.read().ok()?.get(client_id).cloned()
+    }
+
+    fn put(&self, client: OidcClient) {
+        if let Ok(mut lock) = self.clients.write() {
+            lock.insert(client.client_id.clone(), client);
+        }
+    }
+
+    fn remove(&self, client_id: &str) -> bool {
+        self.clients
+            .write()
+            .is_ok_and(|mut lock| lock.remove(client_id).is_some())
+    }
+
+    fn list(&self) -> Vec<OidcClient> {
+        self.clients
+            .read()
+            .map(|l| l.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// Registers and looks up [`OidcClient`]s. Cloning shares the store.
+#[derive(Clone)]
+pub struct OidcClients {
+    store: Arc<dyn ClientStore>,
+}
+
+impl Default for OidcClients {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OidcClients {
+    pub fn new() -> Self {
+        Self::with_store(MemoryClientStore::new())
+    }
+
+    pub fn with_store(store: impl ClientStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+        }
+    }
+
+    /// Register a client. Confidential clients get a secret, returned here
+    /// only; public clients get `None`.
+    pub fn register(
+        &self,
+        name: &str,
+        redirect_uris: &[&str],
+        confidential: bool,
+    ) -> (OidcClient, Option<String>) {
+        let secret = confidential.then(|| random_token(32));
+        let client = OidcClient {
+            client_id: random_token(16),
+            name: name.to_string(),
+            redirect_uris: redirect_uris.iter().map(|u| u.to_string()).collect(),
+            secret_digest: secret.as_deref().map(|s| sha256(s.as_bytes())),
+            trusted: false,
+        };
+        self.store.put(client.clone());
+        (client, secret)
+    }
+
+    /// Let a first-party client skip the consent screen.
+    pub fn set_trusted(&self, client_id: &str, trusted: bool) -> bool {
+        let Some(mut client) = self.store.get(client_id) else {
+            return false;
+        };
+        client.trusted = trusted;
+        self.store.put(client);
+        true
+    }
+
+    pub fn get(&self, client_id: &str) -> Option<OidcClient> {
+        self.store.get(client_id)
+    }
+
+    pub fn remove(&self, client_id: &str) -> bool {
+        self.store.remove(client_id)
+    }
+
+    pub fn list(&self) -> Vec<OidcClient> {
+        self.store.list()
+    }
+}
+
+/// Whether `uri` may be registered as a redirect URI: `https`, or plain
+/// `http` to a loopback host for native apps (RFC 8252 §7.3).
+pub fn is_valid_redirect_uri(uri: &str) -> bool {
+    if uri.contains('#') {
+        return false;
+    }
+    if let Some(rest) = uri.strip_prefix("https://") {
+        return !rest.is_empty();
+    }
+    uri.strip_prefix("http://").is_some_and(|rest| {
+        let host = rest.split(['/', '?']).next().unwrap_or("");
+        let host = host.rsplit_once(':').map_or(host, |(h, _)| h);
+        matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+    })
+}
+
+// ─── Errors ──────────────────────────────────────────────────────────────────
+
+/// An OAuth 2.0 / OpenID Connect error response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OidcError {
+    InvalidRequest(&'static str),
+    /// Unknown client or failed client authentication.
+    InvalidClient,
+    /// Bad, expired or reused code, or a PKCE mismatch.
+    InvalidGrant,
+    UnsupportedGrantType,
+    UnsupportedResponseType,
+    InvalidScope,
+    /// The user refused consent.
+    AccessDenied,
+    /// `prompt=none` but nobody is logged in.
+    LoginRequired,
+    /// `prompt=none` but consent has not been given.
+    ConsentRequired,
+    ServerError,
+}
+
+impl OidcError {
+    /// The `error` code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidGrant => "invalid_grant",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::UnsupportedResponseType => "unsupported_response_type",
+            Self::InvalidScope => "invalid_scope",
+            Self::AccessDenied => "access_denied",
+            Self::LoginRequired => "login_required",
+            Self::ConsentRequired => "consent_required",
+            Self::ServerError => "server_error",
+        }
+    }
+
+    fn description(&self) -> Option<&'static str> {
+        match self {
+            Self::InvalidRequest(why) => Some(why),
+            Self::InvalidScope => Some("the openid scope is required"),
+            _ => None,
+        }
+    }
+
+    /// JSON body for the token and registration endpoints.
+    pub fn to_response(&self) -> Response {
+        let mut body = serde_json::json!({ "error": self.code() });
+        if let Some(description) = self.description() {
+            body["error_description"] = description.into();
+        }
+        let status = match self {
+            Self::InvalidClient => 401,
+            Self::ServerError => 500,
+            _ => 400,
+        };
+        let res = json_response(status, &body);
+        if status == 401 {
+            return res.with_header("WWW-Authenticate", "Basic realm=\"token\"");
+        }
+        res
+    }
+}
+
+impl fmt::Display for OidcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.description() {
+            Some(description) => write!(f, "{}: {description}", self.code()),
+            None => f.write_str(self.code()),
+        }
+    }
+}
+
+impl std::error::Error for OidcError {}
+
+// ─── Authorization requests ──────────────────────────────────────────────────
+
+/// Query parameters of an authorization request, echoed through the
+/// consent form.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuthorizeParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub response_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_uri: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_challenge: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code_challenge_method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ConsentForm {
+    #[serde(flatten)]
+    params: AuthorizeParams,
+    decision: String,
+}
+
+/// What the consent screen asks the user to approve.
+#[derive(Debug, Clone)]
+pub struct ConsentRequest {
+    pub client: OidcClient,
+    /// The logged-in user.
+    pub subject: String,
+    pub scopes: Vec<String>,
+    /// Where the form posts to.
+    pub action: String,
+    pub params: AuthorizeParams,
+}
+
+impl ConsentRequest {
+    /// Hidden inputs carrying the request and the CSRF token. The form adds
+    /// a `decision` field of `allow` or `deny`, usually as two submit
+    /// buttons.
+    pub fn hidden_fields(&self) -> String {
+        let mut html = csrf_field();
+        let pairs = serde_urlencoded::to_string(&self.params)
+            .ok()
+            .and_then(|qs| serde_urlencoded::from_str::<Vec<(String, String)>>(&qs).ok())
+            .unwrap_or_default();
+        for (name, value) in pairs {
+            html.push_str(&format!(
+                r#"<input type="hidden" name="{}" value="{}">"#,
+                escape_html(&name),
+                escape_html(&value)
+            ));
+        }
+        html
+    }
+}
+
+/// The built-in consent screen.
+pub fn default_consent_page(req: &ConsentRequest) -> Response {
+    let scopes: String = req
+        .scopes
+        .iter()
+        .map(|s| format!("<li>{}</li>", escape_html(s)))
+        .collect();
+    let html = format!(
+        r#"<!doctype html><html><head><meta charset="utf-8"><title>Authorize {name}</title></head><body>
+<h1>{name} wants to access your account</h1>
+<ul>{scopes}</ul>
+<form method="post" action="{action}">{fields}
+<button type="submit" name="decision" value="allow">Allow</button>
+<button type="submit" name="decision" value="deny">Deny</button>
+</form></body></html>"#,
+        name = escape_html(&req.client.name),
+        action = escape_html(&req.action),
+        fields = req.hidden_fields(),
+    );
+    let mut res = Response::text(html);
+    res.content_type = "text/html; charset=utf-8";
+    res.with_header("Cache-Control", "no-store")
+        .with_header("X-Frame-Options", "DENY")
+}
+
+/// A validated authorization request.
+struct Authorization {
+    client: OidcClient,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    state: Option<String>,
+    nonce: Option<String>,
+    challenge: String,
+}
+
+struct PendingCode {
+    client_id: String,
+    redirect_uri: String,
+    subject: String,
+    scopes: Vec<String>,
+    nonce: Option<String>,
+    challenge: String,
+    auth_time: u64,
+    expires: Instant,
+}
+
+// ─── Tokens ──────────────────────────────────────────────────────────────────
+
+/// Token endpoint response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OidcTokens {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub id_token: String,
+    pub scope: String,
+}
+
+/// Claims of an access token issued by the provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessClaims {
+    pub iss: String,
+    pub sub: String,
+    pub client_id: String,
+    /// Space-separated granted scopes.
+    pub scope: String,
+    pub iat: u64,
+    pub exp: u64,
+    pub jti: String,
+}
+
+impl AccessClaims {
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scope.split_ascii_whitespace().any(|s| s == scope)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenRequest {
+    grant_type: Option<String>,
+    code: Option<String>,
+    redirect_uri: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    code_verifier: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct RegistrationRequest {
+    client_name: Option<String>,
+    #[serde(default)]
+    redirect_uris: Vec<String>,
+    token_endpoint_auth_method: Option<String>,
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Resolves the logged-in user's subject identifier.
+pub type CurrentUser = fn(&Context) -> Option<String>;
+
+/// Extra claims for a subject, given the granted scopes.
+pub type ClaimsResolver = fn(&str, &[String]) -> Map<String, Value>;
+
+/// Mounts the provider endpoints. See the [module docs](self).
+pub struct OidcModule {
+    issuer: String,
+    key: SigningKey,
+    clients: OidcClients,
+    current_user: CurrentUser,
+    login_url: String,
+    claims: ClaimsResolver,
+    consent_page: fn(&ConsentRequest) -> Response,
+    scopes: Vec<String>,
+    code_ttl: Duration,
+    token_ttl: Duration,
+    registration: Option<fn(&Context) -> bool>,
+}
+
+impl OidcModule {
+    /// `issuer` is the public base URL, e.g. `https://id.example.com/oauth`;
+    /// its path is where the routes are mounted.
+    pub fn new(issuer: &str, key: SigningKey, clients: OidcClients) -> Self {
+        Self {
+            issuer: issuer.trim_end_matches('/').to_string(),
+            key,
+            clients,
+            current_user: |_| None,
+            login_url: "/login".to_string(),
+            claims: |_, _| Map::new(),
+            consent_page: default_consent_page,
+            scopes: vec!["openid".into(), "profile".into(), "email".into()],
+            code_ttl: Duration::from_secs(60),
+            token_ttl: Duration::from_secs(3_600),
+            registration: None,
+        }
+    }
+
+    /// How to find the logged-in user, e.g. from the session.
+    pub fn current_user(mut self, resolve: CurrentUser) -> Self {
+        self.current_user = resolve;
+        self
+    }
+
+    /// Where to send users who are not logged in. The authorization request
+    /// to come back to is appended as `next`.
+    pub fn login_url(mut self, url: &str) -> Self {
+        self.login_url = url.to_string();
+        self
+    }
+
+    /// Claims such as `name` or `email` for ID tokens and `/userinfo`.
+    pub fn claims(mut self, resolve: ClaimsResolver) -> Self {
+        self.claims = resolve;
+        self
+    }
+
+    /// Replace [`default_consent_page`].
+    pub fn consent_page(mut self, render: fn(&ConsentRequest) -> Response) -> Self {
+        self.consent_page = render;
+        self
+    }
+
+    /// Scopes clients may request besides `openid`. Others are ignored.
+    pub fn scopes(mut self, scopes: &[&str]) -> Self {
+        self.scopes = std::iter::once("openid")
+            .chain(scopes.iter().copied().filter(|s| *s != "openid"))
+            .map(str::to_string)
+            .collect();
+        self
+    }
+
+    /// Lifetime of ID and access tokens.
+    pub fn token_ttl(mut self, ttl: Duration) -> Self {
+        self.token_ttl = ttl;
+        self
+    }
+
+    /// Enable `POST /register` (RFC 7591) for requests `allow` accepts,
+    /// e.g. ones carrying an initial access token.
+    pub fn dynamic_registration(mut self, allow: fn(&Context) -> bool) -> Self {
+        self.registration = Some(allow);
+        self
+    }
+
+    fn prefix(&self) -> String {
+        let path = self
+            .issuer
+            .split_once("://")
+            .map_or(self.issuer.as_str(), |(_, rest)| rest);
+        path.find('/')
+            .map_or(String::new(), |i| path[i..].to_string())
+    }
+}
+
+struct Provider {
+    config: OidcModule,
+    prefix: String,
+    codes: Mutex<HashMap<String, PendingCode>>,
+    /// (subject, client_id) → scopes consented to.
+    consents: Mutex<HashMap<(String, String), HashSet<String>>>,
+}
+
+static PROVIDER: RwLock<Option<Arc<Provider>>> = RwLock::new(None);
+
+fn provider() -> Option<Arc<Provider>> {
+    PROVIDER.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+impl ChopinModule for OidcModule {
+    fn name(&self) -> &'static str {
+        "oidc"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        let prefix = self.prefix();
+        let config = OidcModule {
+            issuer: self.issuer.clone(),
+            key: self.key.clone(),
+            clients: self.clients.clone(),
+            login_url: self.login_url.clone(),
+            scopes: self.scopes.clone(),
+            ..*self
+        };
+        router.get(
+            &format!("{prefix}/.well-known/openid-configuration"),
+            discovery_handler,
+        );
+        router.get(&format!("{prefix}/jwks.json"), jwks_handler);
+        router.get(&format!("{prefix}/authorize"), authorize_handler);
+        router.post(&format!("{prefix}/authorize"), consent_handler);
+        router.post(&format!("{prefix}/token"), token_handler);
+        router.get(&format!("{prefix}/userinfo"), userinfo_handler);
+        router.post(&format!("{prefix}/userinfo"), userinfo_handler);
+        if self.registration.is_some() {
+            router.post(&format!("{prefix}/register"), register_handler);
+        }
+        *PROVIDER.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Provider {
+            config,
+            prefix,
+            codes: Mutex::new(HashMap::new()),
+            consents: Mutex::new(HashMap::new()),
+        }));
+    }
+}
+
+impl Provider {
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}{path}", self.config.issuer)
+    }
+
+    fn discovery(&self) -> Value {
+        let mut doc = serde_json::json!({
+            "issuer": self.config.issuer,
+            "authorization_endpoint": self.endpoint("/authorize"),
+            "token_endpoint": self.endpoint("/token"),
+            "userinfo_endpoint": self.endpoint("/userinfo"),
+            "jwks_uri": self.endpoint("/jwks.json"),
+            "response_types_supported": ["code"],
+            "grant_types_supported": ["authorization_code"],
+            "subject_types_supported": ["public"],
+            "id_token_signing_alg_values_supported": ["RS256"],
+            "scopes_supported": self.config.scopes,
+            "token_endpoint_auth_methods_supported":
+                ["client_secret_basic", "client_secret_post", "none"],
+            "code_challenge_methods_supported": ["S256"],
+            "authorization_response_iss_parameter_supported": true,
+        });
+        if self.config.registration.is_some() {
+            doc["registration_endpoint"] = self.endpoint("/register").into();
+        }
+        doc
+    }
+
+    /// Check an authorization request. Problems with the client or redirect
+    /// URI get an error page, since redirecting would hand the error to an
+    /// unverified URI; the rest go back to the client.
+    #[allow(clippy::result_large_err)]
+    fn validate(&self, params: &AuthorizeParams) -> Result<Authorization, Response> {
+        let bad = |why: &str| {
+            let mut res = Response::text(format!("Invalid authorization request: {why}"));
+            res.status = 400;
+            res
+        };
+        let client = params
+            .client_id
+            .as_deref()
+            .and_then(|id| self.config.clients.get(id))
+            .ok_or_else(|| bad("unknown client_id"))?;
+        let redirect_uri = match params.redirect_uri.as_deref() {
+            Some(uri) if client.redirect_uris.iter().any(|r| r == uri) => uri.to_string(),
+            None if client.redirect_uris.len() == 1 => client.redirect_uris[0].clone(),
+            _ => return Err(bad("redirect_uri is not registered for this client")),
+        };
+        let fail =
+            |err: OidcError| self.error_redirect(&redirect_uri, params.state.as_deref(), err);
+
+        if params.response_type.as_deref() != Some("code") {
+            return Err(fail(OidcError::UnsupportedResponseType));
+        }
+        let requested: Vec<&str> = params
+            .scope
+            .as_deref()
+            .unwrap_or("")
+            .split_ascii_whitespace()
+            .collect();
+        if !requested.contains(&"openid") {
+            return Err(fail(OidcError::InvalidScope));
+        }
+        let challenge = match (
+            params.code_challenge.as_deref(),
+            params.code_challenge_method.as_deref(),
+        ) {
+            (Some(c), Some("S256")) if !c.is_empty() => c.to_string(),
+            (Some(_), Some(_)) => {
+                return Err(fail(OidcError::InvalidRequest(
+                    "code_challenge_method must be S256",
+                )));
+            }
+            _ => return Err(fail(OidcError::InvalidRequest("PKCE is required"))),
+        };
+        let mut scopes: Vec<String> = Vec::new();
+        for scope in requested {
+            if self.config.scopes.iter().any(|s| s == scope) && !scopes.iter().any(|s| s == scope) {
+                scopes.push(scope.to_string());
+            }
+        }
+        Ok(Authorization {
+            client,
+            redirect_uri,
+            scopes,
+            state: params.state.clone(),
+            nonce: params.nonce.clone(),
+            challenge,
+        })
+    }
+
+    fn error_redirect(&self, redirect_uri: &str, state: Option<&str>, err: OidcError) -> Response {
+        let mut pairs = vec![("error", err.code())];
+        if let Some(description) = err.description() {
+            pairs.push(("error_description", description));
+        }
+        if let Some(state) = state {
+            pairs.push(("state", state));
+        }
+        pairs.push(("iss", &self.config.issuer));
+        self.redirect(redirect_uri, &pairs)
+    }
+
+    fn redirect(&self, uri: &str, pairs: &[(&str, &str)]) -> Response {
+        let sep = if uri.contains('?') { '&' } else { '?' };
+        let query = serde_urlencoded::to_string(pairs).unwrap_or_default();
+        Response::new(303)
+            .with_header("Location", format!("{uri}{sep}{query}"))
+            .with_header("Cache-Control", "no-store")
+    }
+
+    fn has_consent(&self, subject: &str, auth: &Authorization) -> bool {
+        if auth.client.trusted {
+            return true;
+        }
+        let consents = self.consents.lock().unwrap_or_else(|e| e.into_inner());
+        consents
+            .get(&(subject.to_string(), auth.client.client_id.clone()))
+            .is_some_and(|granted| auth.scopes.iter().all(|s| granted.contains(s)))
+    }
+
+    fn remember_consent(&self, subject: &str, auth: &Authorization) {
+        let mut consents = self.consents.lock().unwrap_or_else(|e| e.into_inner());
+        consents
+            .entry((subject.to_string(), auth.client.client_id.clone()))
+            .or_default()
+            .extend(auth.scopes.iter().cloned());
+    }
+
+    /// Issue a code for `auth` and send the user back to the client.
+    fn grant(&self, subject: String, auth: Authorization) -> Response {
+        let code = random_token(32);
+        let now = Instant::now();
+        {
+            let mut codes = self.codes.lock().unwrap_or_else(|e| e.into_inner());
+            codes.retain(|_, c| c.expires > now);
+            codes.insert(
+                code.clone(),
+                PendingCode {
+                    client_id: auth.client.client_id,
+                    redirect_uri: auth.redirect_uri.clone(),
+                    subject,
+                    scopes: auth.scopes,
+                    nonce: auth.nonce,
+                    challenge: auth.challenge,
+                    auth_time: now_secs(),
+                    expires: now + self.config.code_ttl,
+                },
+            );
+        }
+        let mut pairs = vec![("code", code.as_str())];
+        if let Some(state) = auth.state.as_deref() {
+            pairs.push(("state", state));
+        }
+        pairs.push(("iss", &self.config.issuer));
+        self.redirect(&auth.redirect_uri, &pairs)
+    }
+
+    fn login_redirect(&self, ctx: &Context) -> Response {
+        let mut next = format!("{}/authorize", self.prefix);
+        if let Some(query) = ctx.req.query {
+            next.push('?');
+            next.push_str(query);
+        }
+        self.redirect(&self.config.login_url, &[("next", &next)])
+    }
+
+    fn consent_request(
+        &self,
+        subject: String,
+        auth: &Authorization,
+        params: AuthorizeParams,
+    ) -> ConsentRequest {
+        ConsentRequest {
+            client: auth.client.clone(),
+            subject,
+            scopes: auth.scopes.clone(),
+            action: format!("{}/authorize", self.prefix),
+            params,
+        }
+    }
+
+    fn exchange(&self, ctx: &Context) -> Result<OidcTokens, OidcError> {
+        let form: TokenRequest = serde_urlencoded::from_bytes(ctx.req.body)
+            .map_err(|_| OidcError::InvalidRequest("malformed body"))?;
+        if form.grant_type.as_deref() != Some("authorization_code") {
+            return Err(OidcError::UnsupportedGrantType);
+        }
+        let (client_id, secret) = match basic_credentials(ctx) {
+            Some((id, secret)) => (id, Some(secret)),
+            None => (
+                form.client_id.ok_or(OidcError::InvalidClient)?,
+                form.client_secret,
+            ),
+        };
+        let client = self
+            .config
+            .clients
+            .get(&client_id)
+            .ok_or(OidcError::InvalidClient)?;
+        if client.is_confidential() && !secret.is_some_and(|s| client.verify_secret(&s)) {
+            return Err(OidcError::InvalidClient);
+        }
+
+        let code = form
+            .code
+            .ok_or(OidcError::InvalidRequest("code is required"))?;
+        // Taken out before it is checked, so a wrong verifier or redirect
+        // URI spends the code.
+        let pending = self
+            .codes
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&code)
+            .ok_or(OidcError::InvalidGrant)?;
+        let verifier = form.code_verifier.unwrap_or_default();
+        let valid = pending.expires > Instant::now()
+            && pending.client_id == client.client_id
+            && form.redirect_uri.as_deref() == Some(pending.redirect_uri.as_str())
+            && constant_time_eq(
+                code_challenge_s256(&verifier).as_bytes(),
+                pending.challenge.as_bytes(),
+            );
+        if !valid {
+            return Err(OidcError::InvalidGrant);
+        }
+        self.issue(&client, pending)
+            .map_err(|_| OidcError::ServerError)
+    }
+
+    fn issue(&self, client: &OidcClient, code: PendingCode) -> Result<OidcTokens, AuthError> {
+        let now = now_secs();
+        let exp = now + self.config.token_ttl.as_secs();
+        let scope = code.scopes.join(" ");
+        let access = AccessClaims {
+            iss: self.config.issuer.clone(),
+            sub: code.subject.clone(),
+            client_id: client.client_id.clone(),
+            scope: scope.clone(),
+            iat: now,
+            exp,
+            jti: random_token(16),
+        };
+        let access = match serde_json::to_value(&access) {
+            Ok(Value::Object(map)) => map,
+            _ => return Err(AuthError::Internal("access token claims".into())),
+        };
+
+        let mut id = (self.config.claims)(&code.subject, &code.scopes);
+        id.insert("iss".into(), self.config.issuer.clone().into());
+        id.insert("sub".into(), code.subject.into());
+        id.insert("aud".into(), client.client_id.clone().into());
+        id.insert("azp".into(), client.client_id.clone().into());
+        id.insert("iat".into(), now.into());
+        id.insert("exp".into(), exp.into());
+        id.insert("auth_time".into(), code.auth_time.into());
+        if let Some(nonce) = code.nonce {
+            id.insert("nonce".into(), nonce.into());
+        }
+
+        Ok(OidcTokens {
+            access_token: self.config.key.sign(&access)?,
+            token_type: "Bearer",
+            expires_in: self.config.token_ttl.as_secs(),
+            id_token: self.config.key.sign(&id)?,
+            scope,
+        })
+    }
+
+    /// Verify an access token issued by this provider.
+    fn verify_access_token(&self, token: &str) -> Result<AccessClaims, AuthError> {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.set_issuer(&[&self.config.issuer]);
+        validation.validate_aud = false;
+        validation.leeway = 60;
+        jsonwebtoken::decode::<AccessClaims>(token, &self.config.key.decoding, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| match e.kind() {
+                jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::Expired,
+                _ => AuthError::InvalidToken(e.to_string()),
+            })
+    }
+
+    fn register(&self, ctx: &Context) -> Result<Value, OidcError> {
+        let req: RegistrationRequest = serde_json::from_slice(ctx.req.body)
+            .map_err(|_| OidcError::InvalidRequest("malformed registration"))?;
+        if req.redirect_uris.is_empty()
+            || !req.redirect_uris.iter().all(|u| is_valid_redirect_uri(u))
+        {
+            return Err(OidcError::InvalidRequest(
+                "redirect_uris must be https or loopback http",
+            ));
+        }
+        let method = req
+            .token_endpoint_auth_method
+            .unwrap_or_else(|| "client_secret_basic".into());
+        let confidential = match method.as_str() {
+            "none" => false,
+            "client_secret_basic" | "client_secret_post" => true,
+            _ => {
+                return Err(OidcError::InvalidRequest(
+                    "unsupported token_endpoint_auth_method",
+                ));
+            }
+        };
+        let name = req.client_name.unwrap_or_else(|| "Unnamed client".into());
+        let uris: Vec<&str> = req.redirect_uris.iter().map(String::as_str).collect();
+        let (client, secret) = self.config.clients.register(&name, &uris, confidential);
+        let mut body = serde_json::json!({
+            "client_id": client.client_id,
+            "client_id_issued_at": now_secs(),
+            "client_name": client.name,
+            "redirect_uris": client.redirect_uris,
+            "token_endpoint_auth_method": method,
+            "grant_types": ["authorization_code"],
+            "response_types": ["code"],
+        });
+        if let Some(secret) = secret {
+            body["client_secret"] = secret.into();
+            body["client_secret_expires_at"] = 0.into();
+        }
+        Ok(body)
+    }
+}
+
+// ─── Handlers ────────────────────────────────────────────────────────────────
+
+/// `GET /.well-known/openid-configuration`
+pub fn discovery_handler(_ctx: Context) -> Response {
+    match provider() {
+        Some(p) => json_response(200, &p.discovery()),
+        None => Response::not_found(),
+    }
+}
+
+/// `GET /jwks.json`
+pub fn jwks_handler(_ctx: Context) -> Response {
+    match provider() {
+        Some(p) => json_response(200, &serde_json::json!({ "keys": [p.config.key.jwk] })),
+        None => Response::not_found(),
+    }
+}
+
+/// `GET /authorize`
+pub fn authorize_handler(ctx: Context) -> Response {
+    let Some(p) = provider() else {
+        return Response::not_found();
+    };
+    let params = match ctx.extract::<Query<AuthorizeParams>>() {
+        Ok(Query(params)) => params,
+        Err(res) => return res,
+    };
+    let auth = match p.validate(&params) {
+        Ok(auth) => auth,
+        Err(res) => return res,
+    };
+    let prompt_none = params.prompt.as_deref() == Some("none");
+    let Some(subject) = (p.config.current_user)(&ctx) else {
+        if prompt_none {
+            return p.error_redirect(
+                &auth.redirect_uri,
+                auth.state.as_deref(),
+                OidcError::LoginRequired,
+            );
+        }
+        return p.login_redirect(&ctx);
+    };
+    let forced = params.prompt.as_deref() == Some("consent");
+    if !forced && p.has_consent(&subject, &auth) {
+        return p.grant(subject, auth);
+    }
+    if prompt_none {
+        return p.error_redirect(
+            &auth.redirect_uri,
+            auth.state.as_deref(),
+            OidcError::ConsentRequired,
+        );
+    }
+    let request = p.consent_request(subject, &auth, params);
+    (p.config.consent_page)(&request)
+}
+
+/// `POST /authorize`: the consent form.
+pub fn consent_handler(ctx: Context) -> Response {
+    let Some(p) = provider() else {
+        return Response::not_found();
+    };
+    let form = match ctx.extract::<Form<ConsentForm>>() {
+        Ok(Form(form)) => form,
+        Err(res) => return res,
+    };
+    let auth = match p.validate(&form.params) {
+        Ok(auth) => auth,
+        Err(res) => return res,
+    };
+    let Some(subject) = (p.config.current_user)(&ctx) else {
+        return Response::forbidden();
+    };
+    if form.decision != "allow" {
+        return p.error_redirect(
+            &auth.redirect_uri,
+            auth.state.as_deref(),
+            OidcError::AccessDenied,
+        );
+    }
+    p.remember_consent(&subject, &auth);
+    p.grant(subject, auth)
+}
+
+/// `POST /token`
+pub fn token_handler(ctx: Context) -> Response {
+    let Some(p) = provider() else {
+        return Response::not_found();
+    };
+    match p.exchange(&ctx) {
+        Ok(tokens) => json_response(200, &tokens)
+            .with_header("Cache-Control", "no-store")
+            .with_header("Pragma", "no-cache"),
+        Err(err) => err.to_response(),
+    }
+}
+
+/// `GET|POST /userinfo`
+pub fn userinfo_handler(ctx: Context) -> Response {
+    let Some(p) = provider() else {
+        return Response::not_found();
+    };
+    let token = ctx
+        .header("authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .map(str::trim);
+    let claims = token.map(|t| p.verify_access_token(t));
+    match claims {
+        Some(Ok(claims)) if claims.has_scope("openid") => {
+            let scopes: Vec<String> = claims
+                .scope
+                .split_ascii_whitespace()
+                .map(str::to_string)
+                .collect();
+            let mut info = (p.config.claims)(&claims.sub, &scopes);
+            info.insert("sub".into(), claims.sub.into());
+            json_response(200, &info).with_header("Cache-Control", "no-store")
+        }
+        _ => Response::unauthorized()
+            .with_header("WWW-Authenticate", "Bearer error=\"invalid_token\""),
+    }
+}
+
+/// `POST /register`
+pub fn register_handler(ctx: Context) -> Response {
+    let Some(p) = provider() else {
+        return Response::not_found();
+    };
+    if !p.config.registration.is_some_and(|allow| allow(&ctx)) {
+        return Response::forbidden();
+    }
+    match p.register(&ctx) {
+        Ok(body) => json_response(201, &body).with_header("Cache-Control", "no-store"),
+        Err(err) => err.to_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PKCS1: &[u8] = include_bytes!("../tests/fixtures/oidc_rs256.pem");
+    const PKCS8: &[u8] = include_bytes!("../tests/fixtures/oidc_rs256_pkcs8.pem");
+
+    #[test]
+    fn test_signing_key_reads_pkcs1_and_pkcs8() {
+        let a = SigningKey::from_rsa_pem(PKCS1).unwrap();
+        let b = SigningKey::from_rsa_pem(PKCS8).unwrap();
+        assert_eq!(a.kid(), b.kid());
+        assert_eq!(a.jwk(), b.jwk());
+        assert_eq!(a.jwk()["e"], "AQAB");
+        // 2048-bit modulus, no sign byte.
+        let n = base64url_decode(a.jwk()["n"].as_str().unwrap()).unwrap();
+        assert_eq!(n.len(), 256);
+        assert!(SigningKey::from_rsa_pem(b"not a key").is_err());
+    }
+
+    #[test]
+    fn test_signed_tokens_verify_with_published_key() {
+        let key = SigningKey::from_rsa_pem(PKCS1).unwrap();
+        let mut claims = Map::new();
+        claims.insert("sub".into(), "u1".into());
+        claims.insert("exp".into(), (now_secs() + 60).into());
+        let token = key.sign(&claims).unwrap();
+        assert_eq!(
+            jsonwebtoken::decode_header(&token).unwrap().kid.as_deref(),
+            Some(key.kid())
+        );
+        let decoded = jsonwebtoken::decode::<Value>(
+            &token,
+            &key.decoding,
+            &Validation::new(Algorithm::RS256),
+        )
+        .unwrap();
+        assert_eq!(decoded.claims["sub"], "u1");
+    }
+
+    #[test]
+    fn test_redirect_uri_rules() {
+        assert!(is_valid_redirect_uri("https://app.example.com/cb"));
+        assert!(is_valid_redirect_uri("http://localhost:8080/cb"));
+        assert!(is_valid_redirect_uri("http://127.0.0.1/cb"));
+        assert!(!is_valid_redirect_uri("http://example.com/cb"));
+        assert!(!is_valid_redirect_uri("http://localhost.evil.com/cb"));
+        assert!(!is_valid_redirect_uri("https://app.example.com/cb#frag"));
+        assert!(!is_valid_redirect_uri("javascript:alert(1)"));
+    }
+
+    #[test]
+    fn test_client_secrets_and_trust() {
+        let clients = OidcClients::new();
+        let (client, secret) = clients.register("Wiki", &["https://wiki/cb"], true);
+        let secret = secret.unwrap();
+        assert!(client.verify_secret(&secret));
+        assert!(!client.verify_secret("wrong"));
+        assert!(!client.trusted);
+        assert!(clients.set_trusted(&client.client_id, true));
+        assert!(clients.get(&client.client_id).unwrap().trusted);
+
+        let (public, secret) = clients.register("CLI", &["http://localhost/cb"], false);
+        assert!(secret.is_none());
+        assert!(!public.is_confidential());
+        assert!(!public.verify_secret(""));
+        assert_eq!(clients.list().len(), 2);
+    }
+
+    #[test]
+    fn test_issuer_path_becomes_route_prefix() {
+        let key = SigningKey::from_rsa_pem(PKCS1).unwrap();
+        let module = |issuer| OidcModule::new(issuer, key.clone(), OidcClients::new());
+        assert_eq!(module("https://id.example.com/oauth/").prefix(), "/oauth");
+        assert_eq!(module("https://id.example.com").prefix(), "");
+    }
+}
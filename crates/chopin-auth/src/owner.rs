@@ -0,0 +1,88 @@
+// src/owner.rs
+//! Object-level ownership checks for `#[owner_required]` (`orm` feature).
+//!
+//! ```rust,ignore
+//! use chopin_auth::owner_required;
+//!
+//! #[put("/api/posts/:id")]
+//! #[owner_required(Post, param = "id", field = "author_id", roles("admin"))]
+//! fn update_post(ctx: Context, post: Post) -> Response { .. }
+//! ```
+//!
+//! The guard loads the `Post` whose primary key is the `:id` path parameter
+//! and admits the request when the token's `sub` claim equals the post's
+//! `author_id` (compared through `Display`), or when the caller holds one of
+//! the listed roles under the global [`RbacService`](crate::RbacService).
+//! A handler argument of the model's type receives the loaded row.
+//!
+//! | Outcome                                   | Response |
+//! |-------------------------------------------|----------|
+//! | missing or invalid token                  | `401`    |
+//! | no row with that primary key              | `404`    |
+//! | neither owner nor a bypass role           | `403`    |
+//! | missing path parameter or database error  | `500`    |
+use crate::extractor::Auth;
+use crate::rbac::{RawClaims, Roles, rbac};
+use chopin_core::extract::FromRequest;
+use chopin_core::http::{Context, Response};
+use chopin_orm::Model;
+use chopin_pg::PgValue;
+use chopin_pg::types::ToSql;
+
+/// A path parameter bound with an unspecified type, so the server reads it
+/// as whatever the primary key column is (`int8`, `uuid`, `text`, ...).
+struct PathParam<'a>(&'a str);
+
+impl ToSql for PathParam<'_> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Text(self.0.to_string())
+    }
+}
+
+/// Load the `M` named by path parameter `param` and check that the caller
+/// owns it or holds one of `bypass_roles`. Expanded from `#[owner_required]`.
+#[allow(clippy::result_large_err)]
+pub fn load_owned<M: Model>(
+    ctx: &Context<'_>,
+    param: &str,
+    owner_of: impl FnOnce(&M) -> String,
+    bypass_roles: &[&str],
+) -> Result<M, Response> {
+    let Auth { claims } = Auth::<RawClaims>::from_request(ctx)?;
+    let id = ctx.param(param).ok_or_else(Response::server_error)?;
+    let [pk] = M::primary_key_columns() else {
+        return Err(Response::server_error());
+    };
+
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {} = $1 LIMIT 1",
+        M::select_clause(),
+        M::table_name(),
+        pk
+    );
+    let row = chopin_core::db::with_model_db::<M, _>(|db| {
+        db.query(&sql, &[&PathParam(id)])
+            .map(|rows| rows.into_iter().next())
+    })
+    .map_err(|_| Response::server_error())?;
+    let model = match row {
+        Some(row) => M::from_row(&row).map_err(|_| Response::server_error())?,
+        None => return Err(Response::not_found()),
+    };
+
+    let roles = Roles::from_claims(&claims);
+    if bypass_roles.iter().any(|r| rbac().grants(&roles.0, r)) {
+        return Ok(model);
+    }
+    let owner = owner_of(&model);
+    let is_owner = match claims.0.get("sub") {
+        Some(serde_json::Value::String(sub)) => *sub == owner,
+        Some(serde_json::Value::Number(sub)) => sub.to_string() == owner,
+        _ => false,
+    };
+    if is_owner {
+        Ok(model)
+    } else {
+        Err(Response::forbidden())
+    }
+}
@@ -0,0 +1,1463 @@
+// src/password.rs
+//! Password requirements for every endpoint that sets a password.
+//!
+//! A [`PasswordPolicy`] checks length, character classes, an estimated
+//! strength score and, optionally, whether the password appears in a known
+//! breach. Install one globally and call it from signup, reset and
+//! change-password alike, so the rules cannot drift between them:
+//!
+//! ```rust,ignore
+//! use chopin_auth::{PasswordConfig, PasswordPolicy, init_password_policy, password_policy};
+//!
+//! // Chopin.toml:
+//! //   [password]
+//! //   min_length = 10
+//! //   min_score = 3
+//! //   check_breached = true
+//! let cfg = Settings::<PasswordConfig>::load()?;
+//! init_password_policy(PasswordPolicy::from_config(&cfg, Some(Arc::new(http_get)))?);
+//!
+//! #[post("/signup")]
+//! fn signup(ctx: Context, Json(form): Json<SignupForm>) -> Response {
+//!     if let Err(e) = password_policy().check(&form.password, &[&form.email, &form.name]) {
+//!         return e.response();
+//!     }
+//!     ..
+//! }
+//!
+//! #[post("/account/password")]
+//! fn change_password(ctx: Context, Json(form): Json<ChangeForm>) -> Response {
+//!     let user = ..;
+//!     if let Err(e) = password_policy().check_change(&form.current, &form.new, &[&user.email]) {
+//!         return e.response();
+//!     }
+//!     ..
+//! }
+//! ```
+//!
+//! A rejected password answers `422` with every rule it broke, so a form can
+//! show them all at once:
+//!
+//! ```json
+//! {
+//!   "error": "weak_password",
+//!   "violations": [
+//!     { "code": "too_short", "min": 10, "message": "Use at least 10 characters." },
+//!     { "code": "too_weak", "score": 0, "min_score": 3, "message": "..." }
+//!   ],
+//!   "strength": { "score": 0, "guesses_log10": 2.1, "warning": "This is a top-10 common password.", "suggestions": [..] }
+//! }
+//! ```
+//!
+//! ## Strength
+//!
+//! [`estimate`] follows zxcvbn: it finds the cheapest way to build the
+//! password out of common passwords and words, the user's own details,
+//! keyboard rows, sequences, repeats and years, and scores the number of
+//! guesses that takes from 0 (under 10³) to 4 (over 10¹⁰). Its dictionary
+//! is a few hundred entries rather than zxcvbn's 30 000, so it is kinder to
+//! uncommon words; the breach check catches what it misses.
+//!
+//! ## Breached passwords
+//!
+//! The check uses the Pwned Passwords range API with k-anonymity: only the
+//! first five hex digits of the password's SHA-1 leave the server, and the
+//! match is made locally against the returned suffixes. Chopin has no HTTP
+//! client, so the application supplies an [`HttpGet`]. If the lookup fails
+//! the password is accepted, unless `breach_check_fail_closed` is set.
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chopin_core::config::SettingsSection;
+use chopin_core::error::{ChopinError, ChopinResult};
+use chopin_core::http::Response;
+use chopin_core::websocket::sha1;
+use serde::{Deserialize, Serialize};
+
+// ─── Configuration ───────────────────────────────────────────────────────────
+
+/// The `[password]` section of `Chopin.toml`. Defaults follow NIST
+/// SP 800-63B: a minimum length and a strength floor, no composition rules.
+///
+/// ```toml
+/// [password]
+/// min_length = 8
+/// max_length = 128
+/// require_lowercase = false
+/// require_uppercase = false
+/// require_digit = false
+/// require_symbol = false
+/// min_classes = 0            # of lowercase, uppercase, digit, symbol
+/// min_score = 2              # 0 to 4; 0 turns the strength check off
+/// check_breached = false
+/// breached_min_count = 1     # reject passwords seen at least this many times
+/// breach_check_fail_closed = false
+/// breach_range_url = "https://api.pwnedpasswords.com/range/"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PasswordConfig {
+    /// Minimum length in characters (Unicode scalar values, not bytes).
+    pub min_length: usize,
+    /// Maximum length in characters. Bounds the work spent hashing.
+    pub max_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// How many different character classes the password must mix.
+    pub min_classes: u8,
+    /// Lowest acceptable [`Strength::score`].
+    pub min_score: u8,
+    /// Look the password up in the Pwned Passwords corpus.
+    pub check_breached: bool,
+    /// Reject passwords seen in at least this many breaches.
+    pub breached_min_count: u64,
+    /// Reject the password when the breach lookup itself fails.
+    pub breach_check_fail_closed: bool,
+    /// Range endpoint; the five-digit hash prefix is appended.
+    pub breach_range_url: String,
+}
+
+impl Default for PasswordConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            max_length: 128,
+            require_lowercase: false,
+            require_uppercase: false,
+            require_digit: false,
+            require_symbol: false,
+            min_classes: 0,
+            min_score: 2,
+            check_breached: false,
+            breached_min_count: 1,
+            breach_check_fail_closed: false,
+            breach_range_url: "https://api.pwnedpasswords.com/range/".to_string(),
+        }
+    }
+}
+
+impl SettingsSection for PasswordConfig {
+    const SECTION: &'static str = "password";
+}
+
+/// Sends a GET and returns the response body, for the breach lookup.
+pub type HttpGet = Arc<dyn Fn(&str) -> Result<String, String> + Send + Sync>;
+
+/// A kind of character a policy can require.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CharClass {
+    Lowercase,
+    Uppercase,
+    Digit,
+    /// Anything that is not a letter or a digit, including spaces.
+    Symbol,
+}
+
+impl CharClass {
+    fn of(c: char) -> Self {
+        if c.is_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_alphabetic() {
+            // Letters without case (CJK, Arabic, ...) count as lowercase.
+            CharClass::Lowercase
+        } else {
+            CharClass::Symbol
+        }
+    }
+}
+
+// ─── Violations ──────────────────────────────────────────────────────────────
+
+/// One rule a password broke. Serialized with a `code` tag and the rule's
+/// parameters, for clients to localize.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Violation {
+    TooShort { min: usize },
+    TooLong { max: usize },
+    MissingLowercase,
+    MissingUppercase,
+    MissingDigit,
+    MissingSymbol,
+    TooFewClasses { min: u8, found: u8 },
+    TooWeak { score: u8, min_score: u8 },
+    Breached { count: u64 },
+    BreachCheckUnavailable,
+    SameAsCurrent,
+}
+
+impl Violation {
+    /// English text for the violation.
+    pub fn message(&self) -> String {
+        match self {
+            Violation::TooShort { min } => format!("Use at least {min} characters."),
+            Violation::TooLong { max } => format!("Use at most {max} characters."),
+            Violation::MissingLowercase => "Add a lowercase letter.".to_string(),
+            Violation::MissingUppercase => "Add an uppercase letter.".to_string(),
+            Violation::MissingDigit => "Add a digit.".to_string(),
+            Violation::MissingSymbol => "Add a symbol.".to_string(),
+            Violation::TooFewClasses { min, .. } => format!(
+                "Mix at least {min} of lowercase letters, uppercase letters, digits and symbols."
+            ),
+            Violation::TooWeak { .. } => "This password is too easy to guess.".to_string(),
+            Violation::Breached { .. } => {
+                "This password has appeared in a data breach. Choose another one.".to_string()
+            }
+            Violation::BreachCheckUnavailable => {
+                "The password could not be checked right now. Try again later.".to_string()
+            }
+            Violation::SameAsCurrent => {
+                "The new password must differ from the current one.".to_string()
+            }
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("message".into(), self.message().into());
+        }
+        value
+    }
+}
+
+/// Why a password was refused: every broken rule, and the strength estimate
+/// when one was made.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PasswordError {
+    pub violations: Vec<Violation>,
+    pub strength: Option<Strength>,
+}
+
+impl PasswordError {
+    pub fn has(&self, code: impl Fn(&Violation) -> bool) -> bool {
+        self.violations.iter().any(code)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "error": "weak_password",
+            "violations": self.violations.iter().map(Violation::to_json).collect::<Vec<_>>(),
+        });
+        if let Some(strength) = &self.strength {
+            body["strength"] = serde_json::to_value(strength).unwrap_or_default();
+        }
+        body
+    }
+
+    /// `422` with [`to_json`](Self::to_json) as the body.
+    pub fn response(&self) -> Response {
+        let mut res = Response::json_bytes(self.to_json().to_string());
+        res.status = 422;
+        res
+    }
+}
+
+impl std::fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<String> = self.violations.iter().map(Violation::message).collect();
+        write!(f, "password rejected: {}", messages.join(" "))
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+// ─── Policy ──────────────────────────────────────────────────────────────────
+
+/// Password requirements. Cloning shares the HTTP client.
+#[derive(Clone)]
+pub struct PasswordPolicy {
+    min_length: usize,
+    max_length: usize,
+    required: Vec<CharClass>,
+    min_classes: u8,
+    min_score: u8,
+    breach: Option<BreachCheck>,
+}
+
+#[derive(Clone)]
+struct BreachCheck {
+    get: HttpGet,
+    range_url: String,
+    min_count: u64,
+    fail_closed: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self::from_config(&PasswordConfig::default(), None)
+            .expect("default PasswordConfig does not check breaches")
+    }
+}
+
+impl PasswordPolicy {
+    /// Build from `[password]`. `check_breached` needs an [`HttpGet`].
+    pub fn from_config(cfg: &PasswordConfig, get: Option<HttpGet>) -> ChopinResult<Self> {
+        let required = [
+            (cfg.require_lowercase, CharClass::Lowercase),
+            (cfg.require_uppercase, CharClass::Uppercase),
+            (cfg.require_digit, CharClass::Digit),
+            (cfg.require_symbol, CharClass::Symbol),
+        ]
+        .into_iter()
+        .filter_map(|(on, class)| on.then_some(class))
+        .collect();
+        let breach = match (cfg.check_breached, get) {
+            (false, _) => None,
+            (true, Some(get)) => Some(BreachCheck {
+                get,
+                range_url: cfg.breach_range_url.clone(),
+                min_count: cfg.breached_min_count.max(1),
+                fail_closed: cfg.breach_check_fail_closed,
+            }),
+            (true, None) => {
+                return Err(ChopinError::Other(
+                    "[password] check_breached needs an HTTP client".to_string(),
+                ));
+            }
+        };
+        Ok(Self {
+            min_length: cfg.min_length,
+            max_length: cfg.max_length.max(cfg.min_length),
+            required,
+            min_classes: cfg.min_classes.min(4),
+            min_score: cfg.min_score.min(4),
+            breach,
+        })
+    }
+
+    pub fn min_length(mut self, chars: usize) -> Self {
+        self.min_length = chars;
+        self.max_length = self.max_length.max(chars);
+        self
+    }
+
+    pub fn max_length(mut self, chars: usize) -> Self {
+        self.max_length = chars.max(self.min_length);
+        self
+    }
+
+    /// Require at least one character of `class`.
+    pub fn require(mut self, class: CharClass) -> Self {
+        if !self.required.contains(&class) {
+            self.required.push(class);
+        }
+        self
+    }
+
+    pub fn min_classes(mut self, classes: u8) -> Self {
+        self.min_classes = classes.min(4);
+        self
+    }
+
+    /// Lowest acceptable [`Strength::score`]; 0 turns the check off.
+    pub fn min_score(mut self, score: u8) -> Self {
+        self.min_score = score.min(4);
+        self
+    }
+
+    /// Reject passwords found in the Pwned Passwords corpus, looked up
+    /// through `get`. Fails open.
+    pub fn check_breached(mut self, get: HttpGet) -> Self {
+        let cfg = PasswordConfig::default();
+        self.breach = Some(BreachCheck {
+            get,
+            range_url: cfg.breach_range_url,
+            min_count: cfg.breached_min_count,
+            fail_closed: cfg.breach_check_fail_closed,
+        });
+        self
+    }
+
+    /// Check a new password. `user_inputs` are the account's own details
+    /// (email, name, username), which make a password easier to guess.
+    ///
+    /// The breach lookup is only made for passwords that pass every other
+    /// rule, so a form being filled in does not hit the network each time.
+    pub fn check(&self, password: &str, user_inputs: &[&str]) -> Result<Strength, PasswordError> {
+        let length = password.chars().count();
+        let mut violations = Vec::new();
+        if length < self.min_length {
+            violations.push(Violation::TooShort {
+                min: self.min_length,
+            });
+        }
+        if length > self.max_length {
+            // Nothing else is worth computing for an oversized input.
+            return Err(PasswordError {
+                violations: vec![Violation::TooLong {
+                    max: self.max_length,
+                }],
+                strength: None,
+            });
+        }
+
+        let mut present = [false; 4];
+        for c in password.chars() {
+            present[CharClass::of(c) as usize] = true;
+        }
+        for class in &self.required {
+            if !present[*class as usize] {
+                violations.push(match class {
+                    CharClass::Lowercase => Violation::MissingLowercase,
+                    CharClass::Uppercase => Violation::MissingUppercase,
+                    CharClass::Digit => Violation::MissingDigit,
+                    CharClass::Symbol => Violation::MissingSymbol,
+                });
+            }
+        }
+        let found = present.iter().filter(|p| **p).count() as u8;
+        if found < self.min_classes {
+            violations.push(Violation::TooFewClasses {
+                min: self.min_classes,
+                found,
+            });
+        }
+
+        let strength = estimate(password, user_inputs);
+        if strength.score < self.min_score {
+            violations.push(Violation::TooWeak {
+                score: strength.score,
+                min_score: self.min_score,
+            });
+        }
+
+        if violations.is_empty()
+            && let Some(breach) = &self.breach
+        {
+            match breach.count(password) {
+                Ok(count) if count >= breach.min_count => {
+                    violations.push(Violation::Breached { count });
+                }
+                Ok(_) => {}
+                Err(_) if breach.fail_closed => violations.push(Violation::BreachCheckUnavailable),
+                Err(_) => {}
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(strength)
+        } else {
+            Err(PasswordError {
+                violations,
+                strength: Some(strength),
+            })
+        }
+    }
+
+    /// [`check`](Self::check) for a change of password, which must also
+    /// differ from the current one. The current password counts as a user
+    /// input, so small variations of it score low.
+    pub fn check_change(
+        &self,
+        current: &str,
+        new: &str,
+        user_inputs: &[&str],
+    ) -> Result<Strength, PasswordError> {
+        let mut inputs = user_inputs.to_vec();
+        inputs.push(current);
+        if current == new {
+            let strength = estimate(new, &inputs);
+            return Err(PasswordError {
+                violations: vec![Violation::SameAsCurrent],
+                strength: Some(strength),
+            });
+        }
+        self.check(new, &inputs)
+    }
+}
+
+impl BreachCheck {
+    /// How many breaches the password appears in, per the range API.
+    fn count(&self, password: &str) -> Result<u64, String> {
+        let hash = hex_upper(&sha1(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(5);
+        let body = (self.get)(&format!("{}{prefix}", self.range_url))?;
+        Ok(body
+            .lines()
+            .filter_map(|line| line.trim().split_once(':'))
+            .find(|(s, _)| s.eq_ignore_ascii_case(suffix))
+            .and_then(|(_, count)| count.trim().parse().ok())
+            .unwrap_or(0))
+    }
+}
+
+static GLOBAL_POLICY: OnceLock<PasswordPolicy> = OnceLock::new();
+
+/// Install the global [`PasswordPolicy`].
+///
+/// Call this **once** before starting the server. Panics if called more than once.
+pub fn init_password_policy(policy: PasswordPolicy) {
+    if GLOBAL_POLICY.set(policy).is_err() {
+        panic!("PasswordPolicy already initialised — call init_password_policy only once");
+    }
+}
+
+/// The global [`PasswordPolicy`], or the default one if
+/// [`init_password_policy`] was never called.
+pub fn password_policy() -> &'static PasswordPolicy {
+    GLOBAL_POLICY.get_or_init(PasswordPolicy::default)
+}
+
+fn hex_upper(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+// ─── Strength estimation ─────────────────────────────────────────────────────
+
+/// How hard a password is to guess.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Strength {
+    /// 0 (too guessable) to 4 (very unguessable), on zxcvbn's scale.
+    pub score: u8,
+    /// Estimated guesses needed, as a base-10 logarithm.
+    pub guesses_log10: f64,
+    /// What makes the password weak, if one thing stands out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+}
+
+/// Common passwords and words, most common first. A match's rank is its
+/// position in this list.
+const COMMON: &[&str] = &[
+    "123456",
+    "password",
+    "12345678",
+    "qwerty",
+    "123456789",
+    "12345",
+    "1234",
+    "111111",
+    "1234567",
+    "dragon",
+    "123123",
+    "baseball",
+    "abc123",
+    "football",
+    "monkey",
+    "letmein",
+    "shadow",
+    "master",
+    "696969",
+    "mustang",
+    "666666",
+    "qwertyuiop",
+    "123321",
+    "1234567890",
+    "michael",
+    "654321",
+    "superman",
+    "1qaz2wsx",
+    "7777777",
+    "121212",
+    "000000",
+    "qazwsx",
+    "123qwe",
+    "killer",
+    "trustno1",
+    "jordan",
+    "jennifer",
+    "zxcvbnm",
+    "asdfgh",
+    "hunter",
+    "buster",
+    "soccer",
+    "harley",
+    "batman",
+    "andrew",
+    "tigger",
+    "sunshine",
+    "iloveyou",
+    "2000",
+    "charlie",
+    "robert",
+    "thomas",
+    "hockey",
+    "ranger",
+    "daniel",
+    "starwars",
+    "klaster",
+    "112233",
+    "george",
+    "computer",
+    "michelle",
+    "jessica",
+    "pepper",
+    "1111",
+    "zxcvbn",
+    "555555",
+    "11111111",
+    "131313",
+    "freedom",
+    "777777",
+    "pass",
+    "maggie",
+    "159753",
+    "aaaaaa",
+    "ginger",
+    "princess",
+    "joshua",
+    "cheese",
+    "amanda",
+    "summer",
+    "love",
+    "ashley",
+    "nicole",
+    "chelsea",
+    "biteme",
+    "matthew",
+    "access",
+    "yankees",
+    "987654321",
+    "dallas",
+    "austin",
+    "thunder",
+    "taylor",
+    "matrix",
+    "admin",
+    "welcome",
+    "login",
+    "passw0rd",
+    "hello",
+    "secret",
+    "whatever",
+    "qwerty123",
+    "password1",
+    "changeme",
+    "default",
+    "root",
+    "guest",
+    "test",
+    "letmein1",
+    "qwe123",
+    "1q2w3e4r",
+    "1q2w3e",
+    "q1w2e3r4",
+    "asdf",
+    "asdfasdf",
+    "zaq12wsx",
+    "abcd1234",
+    "flower",
+    "hottie",
+    "loveme",
+    "angel",
+    "baby",
+    "lovely",
+    "purple",
+    "orange",
+    "silver",
+    "golden",
+    "diamond",
+    "winter",
+    "spring",
+    "autumn",
+    "monday",
+    "friday",
+    "sunday",
+    "family",
+    "friend",
+    "friends",
+    "forever",
+    "happy",
+    "smile",
+    "money",
+    "music",
+    "dance",
+    "heart",
+    "chocolate",
+    "cookie",
+    "banana",
+    "apple",
+    "pokemon",
+    "naruto",
+    "minecraft",
+    "google",
+    "facebook",
+    "twitter",
+    "samsung",
+    "iphone",
+    "internet",
+    "server",
+    "system",
+    "database",
+    "oracle",
+    "linux",
+    "windows",
+    "office",
+    "company",
+    "business",
+    "manager",
+    "support",
+    "service",
+    "student",
+    "school",
+    "college",
+    "teacher",
+    "doctor",
+    "office",
+    "london",
+    "paris",
+    "berlin",
+    "america",
+    "canada",
+    "mexico",
+    "brazil",
+    "china",
+    "india",
+    "japan",
+    "dog",
+    "cat",
+    "fish",
+    "bird",
+    "horse",
+    "tiger",
+    "lion",
+    "bear",
+    "wolf",
+    "eagle",
+    "dragon",
+    "king",
+    "queen",
+    "prince",
+    "god",
+    "jesus",
+    "angel",
+    "devil",
+    "hell",
+    "heaven",
+    "star",
+    "sun",
+    "moon",
+    "sky",
+    "blue",
+    "red",
+    "green",
+    "black",
+    "white",
+    "yellow",
+    "pink",
+    "house",
+    "home",
+    "car",
+    "boat",
+    "train",
+    "coffee",
+    "water",
+    "fire",
+    "earth",
+    "wind",
+    "time",
+    "life",
+    "world",
+    "people",
+    "power",
+    "magic",
+    "game",
+    "player",
+    "gamer",
+    "ninja",
+    "pirate",
+    "zombie",
+    "robot",
+    "alien",
+    "hacker",
+    "secure",
+    "private",
+    "account",
+    "user",
+    "username",
+    "letme",
+    "open",
+    "sesame",
+    "opensesame",
+    "trouble",
+    "summer2024",
+    "winter2024",
+    "spring2024",
+    "autumn2024",
+];
+
+/// Keyboard rows for spatial matches.
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Common substitutions, undone before dictionary lookups.
+fn unleet(c: char) -> Option<char> {
+    Some(match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '(' | '{' | '[' | '<' => 'c',
+        '3' => 'e',
+        '6' | '9' => 'g',
+        '1' | '!' | '|' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' | '+' => 't',
+        '%' => 'x',
+        '2' => 'z',
+        _ => return None,
+    })
+}
+
+const MIN_YEAR_SPACE: f64 = 20.0;
+const MIN_GUESSES_SINGLE: f64 = 10.0;
+const MIN_GUESSES_MULTI: f64 = 50.0;
+/// Guesses per character of text no pattern explains.
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pattern {
+    Dictionary {
+        rank: usize,
+        user_input: bool,
+        reversed: bool,
+        l33t: bool,
+    },
+    Spatial,
+    Sequence,
+    Repeat,
+    Year,
+    Bruteforce,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Match {
+    start: usize,
+    /// Exclusive.
+    end: usize,
+    guesses: f64,
+    pattern: Pattern,
+}
+
+fn dictionary() -> &'static HashMap<&'static str, usize> {
+    static DICT: OnceLock<HashMap<&'static str, usize>> = OnceLock::new();
+    DICT.get_or_init(|| {
+        let mut dict = HashMap::new();
+        for (i, word) in COMMON.iter().enumerate() {
+            dict.entry(*word).or_insert(i + 1);
+        }
+        dict
+    })
+}
+
+/// Each user input whole, lowercased, and split into its alphanumeric runs.
+fn user_dictionary(inputs: &[&str]) -> HashMap<String, usize> {
+    let mut dict = HashMap::new();
+    let mut rank = 1;
+    for input in inputs {
+        let lower = input.to_lowercase();
+        let parts =
+            std::iter::once(lower.as_str()).chain(lower.split(|c: char| !c.is_alphanumeric()));
+        for part in parts {
+            if part.chars().count() >= 3 && !dict.contains_key(part) {
+                dict.insert(part.to_string(), rank);
+                rank += 1;
+            }
+        }
+    }
+    dict
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    (1..=k.min(n)).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+/// zxcvbn's capitalization factor: all lowercase adds nothing, a leading
+/// or trailing capital or all caps doubles, anything else counts the ways
+/// to choose the capitals.
+fn uppercase_variations(word: &[char]) -> f64 {
+    let upper = word.iter().filter(|c| c.is_uppercase()).count();
+    let lower = word.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_only = upper == 1 && word.first().is_some_and(|c| c.is_uppercase());
+    let last_only = upper == 1 && word.last().is_some_and(|c| c.is_uppercase());
+    if first_only || last_only || lower == 0 {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|i| binomial(upper + lower, i))
+        .sum::<f64>()
+        .max(2.0)
+}
+
+fn dictionary_matches(chars: &[char], user: &HashMap<String, usize>, out: &mut Vec<Match>) {
+    let lower: Vec<char> = chars.iter().flat_map(|c| c.to_lowercase()).collect();
+    if lower.len() != chars.len() {
+        // A character lowercased into several; positions would not line up.
+        return;
+    }
+    let unleeted: Vec<char> = lower.iter().map(|&c| unleet(c).unwrap_or(c)).collect();
+    let dict = dictionary();
+    let n = chars.len();
+    for i in 0..n {
+        for j in i + 1..=n.min(i + 32) {
+            let whole = i == 0 && j == n;
+            // Short fragments only count as the whole password.
+            if j - i < 3 && !whole {
+                continue;
+            }
+            let mut found: Option<(usize, bool, bool, bool)> = None;
+            let candidates = [
+                (lower[i..j].iter().collect::<String>(), false, false),
+                (lower[i..j].iter().rev().collect::<String>(), true, false),
+                (unleeted[i..j].iter().collect::<String>(), false, true),
+            ];
+            for (word, reversed, l33t) in candidates {
+                if l33t && unleeted[i..j] == lower[i..j] {
+                    continue;
+                }
+                let hit = match user.get(&word) {
+                    Some(&rank) => Some((rank, true)),
+                    None => dict.get(word.as_str()).map(|&rank| (rank, false)),
+                };
+                if let Some((rank, user_input)) = hit
+                    && found.is_none_or(|(r, ..)| rank < r)
+                {
+                    found = Some((rank, user_input, reversed, l33t));
+                }
+            }
+            let Some((rank, user_input, reversed, l33t)) = found else {
+                continue;
+            };
+            let mut guesses = rank as f64 * uppercase_variations(&chars[i..j]);
+            if reversed {
+                guesses *= 2.0;
+            }
+            if l33t {
+                let subs = (i..j).filter(|&k| unleeted[k] != lower[k]).count();
+                guesses *= 2f64.powi(subs as i32);
+            }
+            out.push(Match {
+                start: i,
+                end: j,
+                guesses,
+                pattern: Pattern::Dictionary {
+                    rank,
+                    user_input,
+                    reversed,
+                    l33t,
+                },
+            });
+        }
+    }
+}
+
+fn spatial_matches(chars: &[char], out: &mut Vec<Match>) {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_ascii_lowercase()).collect();
+    let position = |c: char| {
+        KEYBOARD_ROWS
+            .iter()
+            .enumerate()
+            .find_map(|(row, keys)| keys.find(c).map(|col| (row, col as i64)))
+    };
+    let mut i = 0;
+    while i + 2 < lower.len() {
+        let mut j = i + 1;
+        let mut dir = 0i64;
+        while j < lower.len() {
+            match (position(lower[j - 1]), position(lower[j])) {
+                (Some((r1, c1)), Some((r2, c2))) if r1 == r2 && (c2 - c1).abs() == 1 => {
+                    if dir != 0 && c2 - c1 != dir {
+                        break;
+                    }
+                    dir = c2 - c1;
+                    j += 1;
+                }
+                _ => break,
+            }
+        }
+        if j - i >= 3 {
+            let shifted = chars[i..j].iter().filter(|c| c.is_uppercase()).count();
+            out.push(Match {
+                start: i,
+                end: j,
+                guesses: 40.0 * (j - i) as f64 * if shifted > 0 { 2.0 } else { 1.0 },
+                pattern: Pattern::Spatial,
+            });
+            i = j - 1;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn sequence_matches(chars: &[char], out: &mut Vec<Match>) {
+    let same_kind = |a: char, b: char| {
+        (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+            || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+            || (a.is_ascii_digit() && b.is_ascii_digit())
+    };
+    let mut i = 0;
+    while i + 2 < chars.len() {
+        let delta = chars[i + 1] as i64 - chars[i] as i64;
+        let mut j = i + 1;
+        if delta.abs() == 1 {
+            while j < chars.len()
+                && same_kind(chars[j - 1], chars[j])
+                && chars[j] as i64 - chars[j - 1] as i64 == delta
+            {
+                j += 1;
+            }
+        }
+        if j - i >= 3 {
+            let first = chars[i];
+            let base = if matches!(first, 'a' | 'A' | 'z' | 'Z' | '0' | '1' | '9') {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            out.push(Match {
+                start: i,
+                end: j,
+                guesses: base * (j - i) as f64 * if delta < 0 { 2.0 } else { 1.0 },
+                pattern: Pattern::Sequence,
+            });
+            i = j - 1;
+        } else {
+            i += 1;
+        }
+    }
+}
+
+fn repeat_matches(chars: &[char], user_inputs: &[&str], out: &mut Vec<Match>) {
+    let n = chars.len();
+    let mut i = 0;
+    while i < n {
+        // The longest run starting here made of one unit repeated.
+        let mut best: Option<(usize, usize)> = None;
+        for unit in 1..=(n - i) / 2 {
+            let mut count = 1;
+            while i + (count + 1) * unit <= n
+                && chars[i..i + unit] == chars[i + count * unit..i + (count + 1) * unit]
+            {
+                count += 1;
+            }
+            if count >= 2 && best.is_none_or(|(u, c)| unit * count > u * c) {
+                best = Some((unit, count));
+            }
+        }
+        match best {
+            Some((unit, count)) if unit * count >= 3 => {
+                let base: String = chars[i..i + unit].iter().collect();
+                let base_guesses = 10f64.powf(guesses_log10(&base, user_inputs));
+                out.push(Match {
+                    start: i,
+                    end: i + unit * count,
+                    guesses: base_guesses * count as f64,
+                    pattern: Pattern::Repeat,
+                });
+                i += unit * count;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// The current UTC year, which years in a password are scored against.
+fn current_year() -> i64 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    year_of(secs)
+}
+
+/// The UTC year of a Unix timestamp, via civil-from-days (Howard Hinnant).
+fn year_of(unix_secs: i64) -> i64 {
+    let z = unix_secs.div_euclid(86_400) + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    yoe + era * 400 + if mp >= 10 { 1 } else { 0 }
+}
+
+fn year_matches(chars: &[char], reference_year: i64, out: &mut Vec<Match>) {
+    for i in 0..chars.len().saturating_sub(3) {
+        let digits: String = chars[i..i + 4].iter().collect();
+        let Ok(year) = digits.parse::<i64>() else {
+            continue;
+        };
+        if (1900..=2049).contains(&year) && chars[i..i + 4].iter().all(char::is_ascii_digit) {
+            out.push(Match {
+                start: i,
+                end: i + 4,
+                guesses: ((year - reference_year).abs() as f64).max(MIN_YEAR_SPACE),
+                pattern: Pattern::Year,
+            });
+        }
+    }
+}
+
+fn omnimatch(chars: &[char], user_inputs: &[&str]) -> Vec<Match> {
+    let user = user_dictionary(user_inputs);
+    let mut matches = Vec::new();
+    dictionary_matches(chars, &user, &mut matches);
+    spatial_matches(chars, &mut matches);
+    sequence_matches(chars, &mut matches);
+    repeat_matches(chars, user_inputs, &mut matches);
+    year_matches(chars, current_year(), &mut matches);
+    for m in &mut matches {
+        let floor = if m.end - m.start == 1 {
+            MIN_GUESSES_SINGLE
+        } else {
+            MIN_GUESSES_MULTI
+        };
+        m.guesses = m.guesses.max(floor);
+    }
+    matches
+}
+
+/// The cheapest sequence of matches covering the password, with
+/// bruteforce filling the gaps, and its guesses as log10.
+fn cheapest(chars: &[char], matches: &[Match]) -> (f64, Vec<Match>) {
+    let n = chars.len();
+    // best[j]: (log10 guesses, number of pieces, pieces) for chars[..j].
+    let mut best: Vec<Option<(f64, usize, Vec<Match>)>> = vec![None; n + 1];
+    best[0] = Some((0.0, 0, Vec::new()));
+    // zxcvbn charges for the number of pieces, since an attacker must also
+    // guess how the password is split up.
+    let log_factorial = |k: usize| (1..=k).map(|i| (i as f64).log10()).sum::<f64>();
+    for j in 1..=n {
+        let mut candidates: Vec<Match> = matches.iter().filter(|m| m.end == j).copied().collect();
+        for start in 0..j {
+            candidates.push(Match {
+                start,
+                end: j,
+                guesses: BRUTEFORCE_CARDINALITY
+                    .powi((j - start) as i32)
+                    .max(MIN_GUESSES_SINGLE),
+                pattern: Pattern::Bruteforce,
+            });
+        }
+        for m in candidates {
+            let Some((log, count, pieces)) = &best[m.start] else {
+                continue;
+            };
+            let log = log + m.guesses.log10();
+            let cost = log + log_factorial(count + 1);
+            let better = match &best[j] {
+                None => true,
+                Some((l, c, _)) => cost < l + log_factorial(*c),
+            };
+            if better {
+                let mut pieces = pieces.clone();
+                pieces.push(m);
+                best[j] = Some((log, count + 1, pieces));
+            }
+        }
+    }
+    match best.pop().flatten() {
+        Some((log, count, pieces)) => (log + log_factorial(count), pieces),
+        None => (0.0, Vec::new()),
+    }
+}
+
+fn guesses_log10(password: &str, user_inputs: &[&str]) -> f64 {
+    let chars: Vec<char> = password.chars().collect();
+    let matches = omnimatch(&chars, user_inputs);
+    cheapest(&chars, &matches).0
+}
+
+fn score(guesses_log10: f64) -> u8 {
+    // zxcvbn's thresholds, 10³ to 10¹⁰ guesses.
+    match guesses_log10 {
+        g if g < 3.0 => 0,
+        g if g < 6.0 => 1,
+        g if g < 8.0 => 2,
+        g if g < 10.0 => 3,
+        _ => 4,
+    }
+}
+
+fn feedback(score: u8, chars: &[char], pieces: &[Match]) -> (Option<String>, Vec<String>) {
+    if pieces.is_empty() {
+        return (
+            None,
+            vec!["Use a few words, avoid common phrases.".to_string()],
+        );
+    }
+    if score > 2 {
+        return (None, Vec::new());
+    }
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better.".to_string()];
+    let longest = pieces
+        .iter()
+        .filter(|m| m.pattern != Pattern::Bruteforce)
+        .max_by_key(|m| m.end - m.start);
+    let warning = longest.map(|m| match m.pattern {
+        Pattern::Dictionary {
+            rank,
+            user_input,
+            reversed,
+            l33t,
+        } => {
+            let word = &chars[m.start..m.end];
+            if word.first().is_some_and(|c| c.is_uppercase()) {
+                suggestions.push("Capitalization doesn't help very much.".to_string());
+            }
+            if reversed {
+                suggestions.push("Reversed words aren't much harder to guess.".to_string());
+            }
+            if l33t {
+                suggestions.push(
+                    "Predictable substitutions like '@' instead of 'a' don't help very much."
+                        .to_string(),
+                );
+            }
+            if user_input {
+                "This contains your personal details.".to_string()
+            } else if m.start == 0 && m.end == chars.len() && !l33t && !reversed {
+                if rank <= 10 {
+                    "This is a top-10 common password.".to_string()
+                } else if rank <= 100 {
+                    "This is a top-100 common password.".to_string()
+                } else {
+                    "This is a very common password.".to_string()
+                }
+            } else {
+                "This is similar to a commonly used password.".to_string()
+            }
+        }
+        Pattern::Spatial => {
+            suggestions.push("Use a longer keyboard pattern with more turns.".to_string());
+            "Straight rows of keys are easy to guess.".to_string()
+        }
+        Pattern::Sequence => {
+            suggestions.push("Avoid sequences.".to_string());
+            "Sequences like abc or 6543 are easy to guess.".to_string()
+        }
+        Pattern::Repeat => {
+            suggestions.push("Avoid repeated words and characters.".to_string());
+            "Repeats like \"abcabcabc\" are only slightly harder to guess than \"abc\".".to_string()
+        }
+        Pattern::Year => {
+            suggestions.push("Avoid recent years and years associated with you.".to_string());
+            "Recent years are easy to guess.".to_string()
+        }
+        Pattern::Bruteforce => unreachable!("filtered out above"),
+    });
+    (warning, suggestions)
+}
+
+/// Estimate how guessable `password` is. `user_inputs` (email, name, ...)
+/// are treated as words an attacker would try first.
+pub fn estimate(password: &str, user_inputs: &[&str]) -> Strength {
+    let chars: Vec<char> = password.chars().collect();
+    let matches = omnimatch(&chars, user_inputs);
+    let (guesses_log10, pieces) = cheapest(&chars, &matches);
+    let score = score(guesses_log10);
+    let (warning, suggestions) = feedback(score, &chars, &pieces);
+    Strength {
+        score,
+        guesses_log10: (guesses_log10 * 100.0).round() / 100.0,
+        warning,
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_sha1_vectors() {
+        assert_eq!(
+            hex_upper(&sha1(b"")),
+            "DA39A3EE5E6B4B0D3255BFEF95601890AFD80709"
+        );
+        assert_eq!(
+            hex_upper(&sha1(b"abc")),
+            "A9993E364706816ABA3E25717850C26C9CD0D89D"
+        );
+        assert_eq!(
+            hex_upper(&sha1(
+                b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+            )),
+            "84983E441C3BD26EBAAE4AA1F95129E5E54670F1"
+        );
+    }
+
+    #[test]
+    fn test_estimate_scores() {
+        for weak in [
+            "password",
+            "123456",
+            "qwerty",
+            "P@ssw0rd",
+            "abcdefgh",
+            "aaaaaaaaaaaa",
+        ] {
+            let s = estimate(weak, &[]);
+            assert!(s.score <= 1, "{weak}: {s:?}");
+            assert!(s.warning.is_some(), "{weak}");
+        }
+        assert_eq!(
+            estimate("password", &[]).warning.as_deref(),
+            Some("This is a top-10 common password.")
+        );
+        for strong in [
+            "correct horse battery staple",
+            "t7#Kp2vQ!xW9zL",
+            "glacier-moth-velvet-92",
+        ] {
+            assert!(estimate(strong, &[]).score >= 3, "{strong}");
+        }
+        // Longer is better, all else equal.
+        assert!(estimate("kq8vz3", &[]).guesses_log10 < estimate("kq8vz3ma1x", &[]).guesses_log10);
+    }
+
+    #[test]
+    fn test_years_scored_against_reference_year() {
+        assert_eq!(year_of(0), 1970);
+        assert_eq!(year_of(951_782_399), 2000); // 2000-02-28T23:59:59Z
+        assert_eq!(year_of(978_307_199), 2000); // 2000-12-31T23:59:59Z
+        assert_eq!(year_of(978_307_200), 2001);
+        assert_eq!(year_of(4_102_444_800), 2100);
+
+        let guesses = |password: &str, reference_year| {
+            let chars: Vec<char> = password.chars().collect();
+            let mut out = Vec::new();
+            year_matches(&chars, reference_year, &mut out);
+            out.iter().map(|m| m.guesses).collect::<Vec<_>>()
+        };
+        assert_eq!(guesses("x1950", 2000), [50.0]);
+        assert_eq!(guesses("x1950", 2040), [90.0]);
+        // Years near the reference fall back to the minimum year space.
+        assert_eq!(guesses("1999", 2000), [MIN_YEAR_SPACE]);
+        assert!(guesses("1899", 2000).is_empty());
+    }
+
+    #[test]
+    fn test_user_inputs_weaken() {
+        let alone = estimate("wojciechowski1987", &[]);
+        let known = estimate("wojciechowski1987", &["Anna.Wojciechowski@example.com"]);
+        assert!(known.score < alone.score, "{alone:?} {known:?}");
+        assert_eq!(
+            known.warning.as_deref(),
+            Some("This contains your personal details.")
+        );
+    }
+
+    #[test]
+    fn test_policy_collects_every_violation() {
+        let policy = PasswordPolicy::default()
+            .min_length(10)
+            .require(CharClass::Digit)
+            .require(CharClass::Symbol)
+            .min_classes(3)
+            .min_score(3);
+        let err = policy.check("password", &[]).unwrap_err();
+        assert_eq!(
+            err.violations,
+            vec![
+                Violation::TooShort { min: 10 },
+                Violation::MissingDigit,
+                Violation::MissingSymbol,
+                Violation::TooFewClasses { min: 3, found: 1 },
+                Violation::TooWeak {
+                    score: 0,
+                    min_score: 3
+                },
+            ]
+        );
+        let json = err.to_json();
+        assert_eq!(json["violations"][0]["code"], "too_short");
+        assert_eq!(json["violations"][0]["min"], 10);
+        assert_eq!(
+            json["violations"][0]["message"],
+            "Use at least 10 characters."
+        );
+        assert_eq!(json["strength"]["score"], 0);
+
+        assert!(policy.check("t7#Kp2vQ!xW9zL", &[]).is_ok());
+        let long = "a".repeat(200);
+        assert_eq!(
+            policy.check(&long, &[]).unwrap_err().violations,
+            vec![Violation::TooLong { max: 128 }]
+        );
+    }
+
+    #[test]
+    fn test_breach_check_uses_range_prefix() {
+        // SHA-1("glacier-moth-velvet-92") is looked up by its first five digits.
+        let hash = hex_upper(&sha1(b"glacier-moth-velvet-92"));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let log = seen.clone();
+        let suffix = hash[5..].to_string();
+        let get: HttpGet = Arc::new(move |url: &str| {
+            log.lock().unwrap().push(url.to_string());
+            Ok(format!(
+                "0018A45C4D1DEF81644B54AB7F969B88D65:1\r\n{suffix}:42\r\n"
+            ))
+        });
+        let policy = PasswordPolicy::default().check_breached(get);
+        let err = policy.check("glacier-moth-velvet-92", &[]).unwrap_err();
+        assert_eq!(err.violations, vec![Violation::Breached { count: 42 }]);
+        assert_eq!(
+            seen.lock().unwrap().as_slice(),
+            [format!(
+                "https://api.pwnedpasswords.com/range/{}",
+                &hash[..5]
+            )]
+        );
+
+        // Weak passwords are refused before any lookup.
+        seen.lock().unwrap().clear();
+        assert!(policy.check("password", &[]).is_err());
+        assert!(seen.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_breach_check_failure_modes() {
+        let down: HttpGet = Arc::new(|_: &str| Err("timeout".to_string()));
+        let open = PasswordPolicy::default().check_breached(down.clone());
+        assert!(open.check("glacier-moth-velvet-92", &[]).is_ok());
+
+        let cfg = PasswordConfig {
+            check_breached: true,
+            breach_check_fail_closed: true,
+            ..PasswordConfig::default()
+        };
+        let closed = PasswordPolicy::from_config(&cfg, Some(down)).unwrap();
+        assert_eq!(
+            closed
+                .check("glacier-moth-velvet-92", &[])
+                .unwrap_err()
+                .violations,
+            vec![Violation::BreachCheckUnavailable]
+        );
+        assert!(PasswordPolicy::from_config(&cfg, None).is_err());
+    }
+
+    #[test]
+    fn test_check_change() {
+        let policy = PasswordPolicy::default().min_score(3);
+        let err = policy
+            .check_change("glacier-moth-velvet-92", "glacier-moth-velvet-92", &[])
+            .unwrap_err();
+        assert_eq!(err.violations, vec![Violation::SameAsCurrent]);
+        // The current password with a character added is weak.
+        let err = policy
+            .check_change("glacier-moth-velvet-92", "Glacier-moth-velvet-92!", &[])
+            .unwrap_err();
+        assert!(err.has(|v| matches!(v, Violation::TooWeak { .. })));
+        assert!(
+            policy
+                .check_change("glacier-moth-velvet-92", "t7#Kp2vQ!xW9zL", &[])
+                .is_ok()
+        );
+    }
+}
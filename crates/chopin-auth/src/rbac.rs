@@ -0,0 +1,210 @@
+// src/rbac.rs
+//! Role hierarchy for `#[role_required]`.
+//!
+//! Roles come from a JWT claim (`roles` by default, either a string or an
+//! array of strings). A [`RbacService`] says which roles include which, so a
+//! handler that requires `"user"` also admits `"staff"` and `"admin"`:
+//!
+//! ```rust,ignore
+//! use chopin_auth::{RbacService, init_rbac, role_required};
+//!
+//! init_rbac(RbacService::hierarchy(&["admin", "staff", "user"]));
+//!
+//! #[get("/api/reports")]
+//! #[role_required("staff")]
+//! fn reports(ctx: Context) -> Response { .. }
+//! ```
+//!
+//! Without [`init_rbac`], roles are flat: a request must hold the required
+//! role itself.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use crate::extractor::Auth;
+use crate::jwt::HasJti;
+use chopin_core::extract::FromRequest;
+use chopin_core::http::{Context, Response};
+use serde::Deserialize;
+
+/// Which roles include which other roles, and where roles live in the token.
+#[derive(Debug, Clone)]
+pub struct RbacService {
+    /// Role → roles it directly includes.
+    includes: HashMap<String, Vec<String>>,
+    claim: String,
+}
+
+impl Default for RbacService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RbacService {
+    /// Flat roles read from the `roles` claim.
+    pub fn new() -> Self {
+        Self {
+            includes: HashMap::new(),
+            claim: "roles".to_string(),
+        }
+    }
+
+    /// A linear hierarchy, strongest first: `["admin", "staff", "user"]`
+    /// means admin ⊃ staff ⊃ user.
+    pub fn hierarchy(levels: &[&str]) -> Self {
+        levels
+            .windows(2)
+            .fold(Self::new(), |rbac, pair| rbac.inherit(pair[0], &[pair[1]]))
+    }
+
+    /// Declare that `role` includes every role in `includes` (and,
+    /// transitively, whatever those include).
+    pub fn inherit(mut self, role: &str, includes: &[&str]) -> Self {
+        self.includes
+            .entry(role.to_string())
+            .or_default()
+            .extend(includes.iter().map(|r| r.to_string()));
+        self
+    }
+
+    /// Read roles from `claim` instead of `roles`.
+    pub fn with_claim(mut self, claim: &str) -> Self {
+        self.claim = claim.to_string();
+        self
+    }
+
+    /// Name of the claim roles are read from.
+    pub fn claim(&self) -> &str {
+        &self.claim
+    }
+
+    /// Whether holding `held` satisfies a requirement for `required`.
+    pub fn implies(&self, held: &str, required: &str) -> bool {
+        let mut stack = vec![held];
+        let mut seen = Vec::new();
+        while let Some(role) = stack.pop() {
+            if role == required {
+                return true;
+            }
+            if seen.contains(&role) {
+                continue;
+            }
+            seen.push(role);
+            if let Some(included) = self.includes.get(role) {
+                stack.extend(included.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    /// Whether any of the `held` roles satisfies `required`.
+    pub fn grants<S: AsRef<str>>(&self, held: &[S], required: &str) -> bool {
+        held.iter().any(|r| self.implies(r.as_ref(), required))
+    }
+}
+
+static GLOBAL_RBAC: OnceLock<RbacService> = OnceLock::new();
+
+/// Install the global [`RbacService`] used by [`Roles`] and
+/// `#[role_required]`.
+///
+/// Call this **once** before starting the server. Panics if called more than once.
+pub fn init_rbac(service: RbacService) {
+    if GLOBAL_RBAC.set(service).is_err() {
+        panic!("RbacService already initialised — call init_rbac only once");
+    }
+}
+
+/// The global [`RbacService`], or flat roles if [`init_rbac`] was never called.
+pub fn rbac() -> &'static RbacService {
+    GLOBAL_RBAC.get_or_init(RbacService::new)
+}
+
+/// Any claims object; only `sub`, `jti` and the role claim are read.
+#[derive(Deserialize)]
+#[serde(transparent)]
+pub(crate) struct RawClaims(pub(crate) serde_json::Map<String, serde_json::Value>);
+
+impl HasJti for RawClaims {
+    fn jti(&self) -> Option<&str> {
+        self.0.get("jti").and_then(|v| v.as_str())
+    }
+}
+
+/// Extractor for the roles carried by the bearer token, checked against the
+/// global [`RbacService`].
+///
+/// Fails like [`Auth`]: `401` for a missing or invalid token.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Roles(pub Vec<String>);
+
+impl Roles {
+    /// Whether these roles satisfy `required`, following the hierarchy.
+    pub fn has(&self, required: &str) -> bool {
+        rbac().grants(&self.0, required)
+    }
+
+    /// Whether these roles satisfy at least one of `required`.
+    pub fn has_any(&self, required: &[&str]) -> bool {
+        required.iter().any(|r| self.has(r))
+    }
+}
+
+impl<'a> FromRequest<'a> for Roles {
+    type Error = Response;
+
+    #[allow(clippy::result_large_err)]
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        let Auth { claims } = Auth::<RawClaims>::from_request(ctx)?;
+        Ok(Roles::from_claims(&claims))
+    }
+}
+
+impl Roles {
+    pub(crate) fn from_claims(claims: &RawClaims) -> Self {
+        let roles = match claims.0.get(rbac().claim()) {
+            Some(serde_json::Value::String(role)) => vec![role.clone()],
+            Some(serde_json::Value::Array(roles)) => roles
+                .iter()
+                .filter_map(|r| r.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        };
+        Roles(roles)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchy_is_transitive() {
+        let rbac = RbacService::hierarchy(&["admin", "staff", "user"]);
+        assert!(rbac.implies("admin", "user"));
+        assert!(rbac.implies("staff", "user"));
+        assert!(rbac.implies("user", "user"));
+        assert!(!rbac.implies("user", "staff"));
+        assert!(!rbac.implies("staff", "admin"));
+    }
+
+    #[test]
+    fn test_inherit_handles_branches_and_cycles() {
+        let rbac = RbacService::new()
+            .inherit("owner", &["billing", "editor"])
+            .inherit("editor", &["viewer"])
+            .inherit("viewer", &["editor"]);
+        assert!(rbac.implies("owner", "viewer"));
+        assert!(rbac.implies("owner", "billing"));
+        assert!(!rbac.implies("editor", "billing"));
+        assert!(!rbac.implies("viewer", "owner"));
+    }
+
+    #[test]
+    fn test_grants_any_held_role() {
+        let rbac = RbacService::hierarchy(&["admin", "staff"]);
+        assert!(rbac.grants(&["guest", "admin"], "staff"));
+        assert!(!rbac.grants(&["guest"], "staff"));
+        assert!(!rbac.grants::<&str>(&[], "staff"));
+    }
+}
@@ -3,10 +3,18 @@
 //! [`TokenBlacklist`] stores revoked JTIs with optional expiry timestamps.
 //! Entries are automatically treated as un-revoked after their expiry, and
 //! [`TokenBlacklist::cleanup`] removes them from memory to prevent unbounded growth.
+//!
+//! [`TokenDenylist`] goes further: it revokes single tokens, whole sessions,
+//! or every token a subject holds ("log out everywhere"), reads the claims
+//! it needs straight from the token so it works with any claims type, and
+//! keeps its entries in a pluggable [`DenylistStore`] so several instances
+//! can share one (Redis, a cache table, ...).
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use serde::Deserialize;
+
 fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -121,6 +129,195 @@ impl TokenBlacklist {
     }
 }
 
+// ─── Denylist ────────────────────────────────────────────────────────────────
+
+/// Key-value storage behind a [`TokenDenylist`].
+///
+/// Keys look like `jti:<id>`, `sid:<id>` and `sub:<id>`; values are Unix
+/// seconds. A Redis implementation maps [`set`](Self::set) to
+/// `SET key value EXAT expires_at` and [`get`](Self::get) to `GET key`.
+pub trait DenylistStore: Send + Sync {
+    /// Store `value` under `key`, to be forgotten after `expires_at` (Unix
+    /// seconds; `None` keeps it indefinitely).
+    fn set(&self, key: &str, value: u64, expires_at: Option<u64>);
+
+    /// The live value under `key`. Stores should fail closed, e.g. report
+    /// `Some(u64::MAX)` when the backend is unreachable.
+    fn get(&self, key: &str) -> Option<u64>;
+
+    /// Drop expired entries, for stores that do not expire them on their own.
+    fn cleanup(&self) {}
+}
+
+/// A stored value and its optional expiry (Unix seconds).
+type DenylistEntry = (u64, Option<u64>);
+
+/// In-process [`DenylistStore`]. Cloning shares the entries.
+#[derive(Clone, Default)]
+pub struct MemoryDenylistStore {
+    entries: Arc<RwLock<HashMap<String, DenylistEntry>>>,
+}
+
+impl MemoryDenylistStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of stored entries, including expired ones not yet cleaned up.
+    pub fn len(&self) -> usize {
+        self.entries.read().map(|l| l.len()).unwrap_or(0)
+    }
+
+    /// Returns `true` if the store holds no entries.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl DenylistStore for MemoryDenylistStore {
+    fn set(&self, key: &str, value: u64, expires_at: Option<u64>) {
+        if let Ok(mut lock) = self.entries.write() {
+            lock.insert(key.to_string(), (value, expires_at));
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<u64> {
+        let Ok(lock) = self.entries.read() else {
+            return Some(u64::MAX); // fail closed
+        };
+        match lock.get(key) {
+            Some((value, None)) => Some(*value),
+            Some((value, Some(exp))) if now_secs() <= *exp => Some(*value),
+            _ => None,
+        }
+    }
+
+    fn cleanup(&self) {
+        let now = now_secs();
+        if let Ok(mut lock) = self.entries.write() {
+            lock.retain(|_, (_, exp)| exp.is_none_or(|exp| now <= exp));
+        }
+    }
+}
+
+/// The registered claims a [`TokenDenylist`] looks at.
+#[derive(Debug, Default, Deserialize)]
+pub struct DenylistClaims {
+    #[serde(default)]
+    pub jti: Option<String>,
+    #[serde(default)]
+    pub sub: Option<serde_json::Value>,
+    #[serde(default)]
+    pub iat: Option<u64>,
+    /// Every other claim, for the configurable session claim.
+    #[serde(flatten)]
+    pub rest: serde_json::Map<String, serde_json::Value>,
+}
+
+fn claim_str(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Revocation of tokens, sessions and subjects, checked by
+/// [`JwtManager::decode`](crate::JwtManager::decode) (and so by the `Auth`
+/// extractor) once attached with
+/// [`JwtManager::with_denylist`](crate::JwtManager::with_denylist).
+///
+/// ```rust,ignore
+/// let denylist = TokenDenylist::new();
+/// init_jwt_manager(JwtManager::new(secret).with_denylist(denylist.clone()));
+///
+/// // Logout: this session's tokens stop working immediately.
+/// denylist.revoke_session(&claims.sid, Some(claims.exp));
+///
+/// // Password reset: every token issued to the user so far is rejected.
+/// denylist.revoke_subject(&user.id.to_string(), Some(now + ACCESS_TOKEN_TTL));
+/// ```
+///
+/// Subject revocation compares against the `iat` claim; tokens without
+/// `iat` are rejected once their subject has been revoked.
+#[derive(Clone)]
+pub struct TokenDenylist {
+    store: Arc<dyn DenylistStore>,
+    session_claim: String,
+}
+
+impl Default for TokenDenylist {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TokenDenylist {
+    /// An in-memory denylist reading sessions from the `sid` claim.
+    pub fn new() -> Self {
+        Self::with_store(MemoryDenylistStore::new())
+    }
+
+    /// A denylist backed by `store`.
+    pub fn with_store(store: impl DenylistStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            session_claim: "sid".to_string(),
+        }
+    }
+
+    /// Read session ids from `claim` instead of `sid`.
+    pub fn with_session_claim(mut self, claim: &str) -> Self {
+        self.session_claim = claim.to_string();
+        self
+    }
+
+    /// Reject the token with this `jti`. Pass the token's `exp` as
+    /// `expires_at` so the entry can be dropped when the token would have
+    /// expired anyway.
+    pub fn revoke_token(&self, jti: &str, expires_at: Option<u64>) {
+        self.store.set(&format!("jti:{jti}"), 0, expires_at);
+    }
+
+    /// Reject every token carrying this session id.
+    pub fn revoke_session(&self, session: &str, expires_at: Option<u64>) {
+        self.store.set(&format!("sid:{session}"), 0, expires_at);
+    }
+
+    /// Reject every token issued to `subject` before now. Tokens issued
+    /// afterwards (a fresh login) are accepted. `expires_at` should be at
+    /// least now plus the longest token lifetime.
+    pub fn revoke_subject(&self, subject: &str, expires_at: Option<u64>) {
+        self.store
+            .set(&format!("sub:{subject}"), now_secs(), expires_at);
+    }
+
+    /// Whether a token with these claims has been revoked.
+    pub fn is_denied(&self, claims: &DenylistClaims) -> bool {
+        if let Some(jti) = &claims.jti
+            && self.store.get(&format!("jti:{jti}")).is_some()
+        {
+            return true;
+        }
+        if let Some(session) = claims.rest.get(&self.session_claim).and_then(claim_str)
+            && self.store.get(&format!("sid:{session}")).is_some()
+        {
+            return true;
+        }
+        if let Some(subject) = claims.sub.as_ref().and_then(claim_str)
+            && let Some(cutoff) = self.store.get(&format!("sub:{subject}"))
+        {
+            return claims.iat.is_none_or(|iat| iat < cutoff);
+        }
+        false
+    }
+
+    /// Drop expired entries from the store.
+    pub fn cleanup(&self) {
+        self.store.cleanup();
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,4 +430,55 @@ mod tests {
         assert_eq!(bl.len(), 1);
         assert!(!bl.is_empty());
     }
+
+    fn claims(value: serde_json::Value) -> DenylistClaims {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_denylist_token_and_session() {
+        let dl = TokenDenylist::new();
+        let token = claims(serde_json::json!({ "jti": "j1", "sid": "s1", "sub": "u1" }));
+        assert!(!dl.is_denied(&token));
+        dl.revoke_token("j1", Some(FAR_FUTURE));
+        assert!(dl.is_denied(&token));
+
+        let other = claims(serde_json::json!({ "jti": "j2", "sid": "s1" }));
+        assert!(!dl.is_denied(&other));
+        dl.revoke_session("s1", None);
+        assert!(dl.is_denied(&other));
+        assert!(!dl.is_denied(&claims(serde_json::json!({ "sid": "s2" }))));
+    }
+
+    #[test]
+    fn test_denylist_custom_session_claim() {
+        let dl = TokenDenylist::new().with_session_claim("session_id");
+        dl.revoke_session("42", None);
+        assert!(dl.is_denied(&claims(serde_json::json!({ "session_id": 42 }))));
+        assert!(!dl.is_denied(&claims(serde_json::json!({ "sid": "42" }))));
+    }
+
+    #[test]
+    fn test_denylist_subject_cutoff() {
+        let dl = TokenDenylist::new();
+        let now = now_secs();
+        dl.revoke_subject("7", Some(FAR_FUTURE));
+        assert!(dl.is_denied(&claims(serde_json::json!({ "sub": 7, "iat": now - 10 }))));
+        assert!(dl.is_denied(&claims(serde_json::json!({ "sub": "7" }))));
+        assert!(!dl.is_denied(&claims(serde_json::json!({ "sub": "7", "iat": now + 1 }))));
+        assert!(!dl.is_denied(&claims(serde_json::json!({ "sub": "8", "iat": now - 10 }))));
+    }
+
+    #[test]
+    fn test_memory_store_expiry_and_cleanup() {
+        let store = MemoryDenylistStore::new();
+        store.set("a", 5, Some(1));
+        store.set("b", 6, Some(FAR_FUTURE));
+        store.set("c", 7, None);
+        assert_eq!(store.get("a"), None);
+        assert_eq!(store.get("b"), Some(6));
+        assert_eq!(store.get("c"), Some(7));
+        store.cleanup();
+        assert_eq!(store.len(), 2);
+    }
 }
@@ -0,0 +1,2227 @@
+// src/scim.rs
+//! SCIM 2.0 provisioning (`orm` feature): identity providers such as Okta
+//! or Azure AD create, update and deactivate this app's accounts.
+//!
+//! [`ScimModule`] serves the `Users` and `Groups` endpoints of RFC 7644
+//! under `/scim/v2`, authenticated with a static bearer token that is
+//! configured in the identity provider:
+//!
+//! | Method                     | Path                     | Description                   |
+//! |----------------------------|--------------------------|-------------------------------|
+//! | GET                        | `/ServiceProviderConfig` | Supported features            |
+//! | GET                        | `/ResourceTypes`         | `User` and `Group`            |
+//! | GET / POST                 | `/Users`                 | Filtered list, create         |
+//! | GET / PUT / PATCH / DELETE | `/Users/:id`             | Read, replace, modify, delete |
+//! | GET / POST                 | `/Groups`                | Filtered list, create         |
+//! | GET / PUT / PATCH / DELETE | `/Groups/:id`            | Read, replace, modify, delete |
+//!
+//! ```rust,ignore
+//! use chopin_auth::scim::{ScimModule, ScimUsers};
+//!
+//! Chopin::new().mount_module(
+//!     ScimModule::new(&std::env::var("SCIM_TOKEN")?).base_url("https://app.example.com"),
+//! );
+//!
+//! // At login, after the SSO callback:
+//! let user = db::with_db(|db| ScimUsers::by_user_name(db, &email))?;
+//! match user {
+//!     Some(user) if user.active => {
+//!         let roles = db::with_db(|db| RoleGrants::roles_of(db, &user.id))?;
+//!         ..
+//!     }
+//!     _ => return Response::forbidden(),
+//! }
+//! ```
+//!
+//! Users are kept in `__chopin_scim_users`: `userName`, `externalId` and
+//! `active` have columns of their own and every other attribute is stored
+//! as sent. Passwords are never stored; provisioned users sign in through
+//! the identity provider. A group is a role: its members are the subjects
+//! holding the role named by its `displayName` in
+//! [`RoleGrants`], and membership changes are written to the RBAC audit
+//! trail with the actor `"scim"`.
+//!
+//! Filters support every operator of RFC 7644 §3.4.2.2, including value
+//! paths like `emails[type eq "work"]`. `userName`, `externalId`, `id` and
+//! `displayName` equality is answered from an index; other filters scan
+//! the table. Sorting, bulk operations and ETags are not supported.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use chopin_core::extract::Query;
+use chopin_core::http::{Context, Response};
+use chopin_core::module::ChopinModule;
+use chopin_core::router::Router;
+use chopin_orm::{Executor, Migration, OrmError, OrmResult};
+use chopin_pg::Row;
+use serde::Deserialize;
+use serde_json::{Map, Value, json};
+
+use crate::audit::RoleGrants;
+use crate::oauth::{constant_time_eq, getrandom, sha256};
+
+pub const USER_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+pub const GROUP_SCHEMA: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const LIST_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const ERROR_SCHEMA: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+const CONTENT_TYPE: &str = "application/scim+json";
+/// Upper bound on `count` in list requests.
+const MAX_RESULTS: usize = 1000;
+
+/// A random (version 4) UUID.
+fn new_id() -> String {
+    let mut b = [0u8; 16];
+    getrandom(&mut b);
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let hex: String = b.iter().map(|x| format!("{x:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn scim_response(status: u16, value: &Value) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => {
+            let mut res = Response::json_bytes(body);
+            res.status = status;
+            res.content_type = CONTENT_TYPE;
+            res
+        }
+        Err(_) => Response::server_error(),
+    }
+}
+
+// ─── Errors ──────────────────────────────────────────────────────────────────
+
+/// A SCIM error response (RFC 7644 §3.12).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScimError {
+    pub status: u16,
+    /// `scimType`, e.g. `uniqueness` or `invalidFilter`.
+    pub scim_type: Option<&'static str>,
+    pub detail: String,
+}
+
+impl ScimError {
+    fn new(status: u16, scim_type: Option<&'static str>, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            scim_type,
+            detail: detail.into(),
+        }
+    }
+
+    fn not_found(what: &str) -> Self {
+        Self::new(404, None, format!("{what} not found"))
+    }
+
+    fn invalid_filter(detail: impl Into<String>) -> Self {
+        Self::new(400, Some("invalidFilter"), detail)
+    }
+
+    fn invalid_path(detail: impl Into<String>) -> Self {
+        Self::new(400, Some("invalidPath"), detail)
+    }
+
+    fn invalid_value(detail: impl Into<String>) -> Self {
+        Self::new(400, Some("invalidValue"), detail)
+    }
+
+    fn invalid_syntax(detail: impl Into<String>) -> Self {
+        Self::new(400, Some("invalidSyntax"), detail)
+    }
+
+    fn no_target(detail: impl Into<String>) -> Self {
+        Self::new(400, Some("noTarget"), detail)
+    }
+
+    pub fn to_response(&self) -> Response {
+        let mut body = json!({
+            "schemas": [ERROR_SCHEMA],
+            "status": self.status.to_string(),
+            "detail": self.detail,
+        });
+        if let Some(scim_type) = self.scim_type {
+            body["scimType"] = json!(scim_type);
+        }
+        scim_response(self.status, &body)
+    }
+}
+
+impl From<OrmError> for ScimError {
+    fn from(err: OrmError) -> Self {
+        if err.is_unique_violation() {
+            Self::new(409, Some("uniqueness"), "the name is already taken")
+        } else {
+            Self::new(500, None, "database error")
+        }
+    }
+}
+
+fn with_store<R>(
+    f: impl FnOnce(&mut dyn Executor) -> Result<R, ScimError>,
+) -> Result<R, ScimError> {
+    match chopin_core::db::with_db(|db| Ok(f(db))) {
+        Ok(result) => result,
+        Err(_) => Err(ScimError::new(500, None, "database unavailable")),
+    }
+}
+
+// ─── Attribute paths and filters ─────────────────────────────────────────────
+
+fn get_ci<'a>(map: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    map.iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(key))
+        .map(|(_, v)| v)
+}
+
+/// The key of `map` matching `key` case-insensitively, or `key` itself.
+fn key_ci(map: &Map<String, Value>, key: &str) -> String {
+    map.keys()
+        .find(|k| k.eq_ignore_ascii_case(key))
+        .cloned()
+        .unwrap_or_else(|| key.to_string())
+}
+
+fn remove_ci(map: &mut Map<String, Value>, key: &str) -> Option<Value> {
+    let key = key_ci(map, key);
+    map.remove(&key)
+}
+
+/// `name.givenName` → `["name", "givenName"]`. Attributes of the core
+/// schemas may carry their URN; extension attributes are nested under
+/// theirs, as they are in resources.
+#[derive(Debug, Clone, PartialEq)]
+struct AttrPath(Vec<String>);
+
+impl AttrPath {
+    fn parse(s: &str) -> Result<Self, ScimError> {
+        let mut segments = Vec::new();
+        let mut rest = s;
+        if s.len() > 4 && s[..4].eq_ignore_ascii_case("urn:") {
+            let (urn, attr) = s.rsplit_once(':').unwrap_or((s, ""));
+            // Schema URNs end in a resource name (`User`), attributes do not.
+            let (urn, attr) = if attr.starts_with(|c: char| c.is_ascii_uppercase()) {
+                (s, "")
+            } else {
+                (urn, attr)
+            };
+            if !urn.eq_ignore_ascii_case(USER_SCHEMA) && !urn.eq_ignore_ascii_case(GROUP_SCHEMA) {
+                segments.push(urn.to_string());
+            }
+            rest = attr;
+        }
+        if !rest.is_empty() {
+            for part in rest.split('.') {
+                let valid = part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '$'));
+                if part.is_empty() || !valid {
+                    return Err(ScimError::invalid_path(format!("invalid attribute `{s}`")));
+                }
+                segments.push(part.to_string());
+            }
+        }
+        if segments.is_empty() {
+            return Err(ScimError::invalid_path(format!("invalid attribute `{s}`")));
+        }
+        Ok(Self(segments))
+    }
+
+    /// `id` and `externalId` compare case-sensitively, other strings do not.
+    fn case_exact(&self) -> bool {
+        self.0
+            .last()
+            .is_some_and(|a| a.eq_ignore_ascii_case("id") || a.eq_ignore_ascii_case("externalId"))
+    }
+}
+
+/// Values at `path`, with multi-valued attributes flattened.
+fn resolve<'a>(value: &'a Value, path: &[String]) -> Vec<&'a Value> {
+    let mut current = vec![value];
+    for segment in path {
+        let mut next = Vec::new();
+        for value in current {
+            match value {
+                Value::Object(map) => next.extend(get_ci(map, segment)),
+                Value::Array(items) => {
+                    for item in items {
+                        if let Value::Object(map) = item {
+                            next.extend(get_ci(map, segment));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        current = next;
+    }
+    current
+        .into_iter()
+        .flat_map(|value| match value {
+            Value::Array(items) => items.iter().collect(),
+            other => vec![other],
+        })
+        .filter(|value| !value.is_null())
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CompareOp {
+    fn parse(s: &str) -> Option<Self> {
+        Some(match s.to_ascii_lowercase().as_str() {
+            "eq" => Self::Eq,
+            "ne" => Self::Ne,
+            "co" => Self::Co,
+            "sw" => Self::Sw,
+            "ew" => Self::Ew,
+            "gt" => Self::Gt,
+            "ge" => Self::Ge,
+            "lt" => Self::Lt,
+            "le" => Self::Le,
+            _ => return None,
+        })
+    }
+}
+
+/// A parsed `filter` expression (RFC 7644 §3.4.2.2).
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Present(AttrPath),
+    Compare(AttrPath, CompareOp, Value),
+    /// `emails[type eq "work"]`: some element of `emails` matches.
+    ValuePath(AttrPath, Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    OpenBracket,
+    CloseBracket,
+    Word(String),
+    Str(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, ScimError> {
+    let mut tokens = Vec::new();
+    let mut chars = s.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' | '[' | ']' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    '[' => Token::OpenBracket,
+                    _ => Token::CloseBracket,
+                });
+            }
+            '"' => {
+                chars.next();
+                let mut out = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, 'n')) => out.push('\n'),
+                            Some((_, 't')) => out.push('\t'),
+                            Some((_, c)) => out.push(c),
+                            None => return Err(ScimError::invalid_filter("unterminated string")),
+                        },
+                        Some((_, c)) => out.push(c),
+                        None => return Err(ScimError::invalid_filter("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(out));
+            }
+            _ => {
+                while let Some(&(_, c)) = chars.peek() {
+                    if c.is_whitespace() || "()[]\"".contains(c) {
+                        break;
+                    }
+                    chars.next();
+                }
+                let end = chars.peek().map_or(s.len(), |&(i, _)| i);
+                tokens.push(Token::Word(s[start..end].to_string()));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct FilterParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl FilterParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword));
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<(), ScimError> {
+        if self.next() == Some(token) {
+            Ok(())
+        } else {
+            Err(ScimError::invalid_filter(format!("expected {what}")))
+        }
+    }
+
+    fn or(&mut self) -> Result<Filter, ScimError> {
+        let mut left = self.and()?;
+        while self.keyword("or") {
+            left = Filter::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<Filter, ScimError> {
+        let mut left = self.unary()?;
+        while self.keyword("and") {
+            left = Filter::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Filter, ScimError> {
+        if self.keyword("not") {
+            self.expect(Token::Open, "`(` after `not`")?;
+            let inner = self.or()?;
+            self.expect(Token::Close, "`)`")?;
+            return Ok(Filter::Not(Box::new(inner)));
+        }
+        if self.peek() == Some(&Token::Open) {
+            self.pos += 1;
+            let inner = self.or()?;
+            self.expect(Token::Close, "`)`")?;
+            return Ok(inner);
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Filter, ScimError> {
+        let Some(Token::Word(attr)) = self.next() else {
+            return Err(ScimError::invalid_filter("expected an attribute"));
+        };
+        let path = AttrPath::parse(&attr).map_err(|e| ScimError::invalid_filter(e.detail))?;
+        if self.peek() == Some(&Token::OpenBracket) {
+            self.pos += 1;
+            let inner = self.or()?;
+            self.expect(Token::CloseBracket, "`]`")?;
+            return Ok(Filter::ValuePath(path, Box::new(inner)));
+        }
+        let Some(Token::Word(op)) = self.next() else {
+            return Err(ScimError::invalid_filter("expected an operator"));
+        };
+        if op.eq_ignore_ascii_case("pr") {
+            return Ok(Filter::Present(path));
+        }
+        let op = CompareOp::parse(&op)
+            .ok_or_else(|| ScimError::invalid_filter(format!("unknown operator `{op}`")))?;
+        let value = match self.next() {
+            Some(Token::Str(s)) => Value::String(s),
+            Some(Token::Word(w)) => match w.as_str() {
+                "true" => Value::Bool(true),
+                "false" => Value::Bool(false),
+                "null" => Value::Null,
+                _ => w
+                    .parse::<serde_json::Number>()
+                    .map(Value::Number)
+                    .map_err(|_| ScimError::invalid_filter(format!("invalid value `{w}`")))?,
+            },
+            _ => return Err(ScimError::invalid_filter("expected a value")),
+        };
+        Ok(Filter::Compare(path, op, value))
+    }
+}
+
+fn compare(actual: &Value, op: CompareOp, expected: &Value, case_exact: bool) -> bool {
+    use std::cmp::Ordering;
+    // `emails eq "a@example.com"` compares the elements' `value`.
+    let actual = match actual {
+        Value::Object(map) => match get_ci(map, "value") {
+            Some(value) => value,
+            None => return false,
+        },
+        other => other,
+    };
+    let ordering = match (actual, expected) {
+        (Value::String(a), Value::String(b)) => {
+            let (a, b) = if case_exact {
+                (a.clone(), b.clone())
+            } else {
+                (a.to_lowercase(), b.to_lowercase())
+            };
+            match op {
+                CompareOp::Co => return a.contains(&b),
+                CompareOp::Sw => return a.starts_with(&b),
+                CompareOp::Ew => return a.ends_with(&b),
+                _ => a.cmp(&b),
+            }
+        }
+        (Value::Number(a), Value::Number(b)) => {
+            match a
+                .as_f64()
+                .zip(b.as_f64())
+                .and_then(|(a, b)| a.partial_cmp(&b))
+            {
+                Some(ordering) => ordering,
+                None => return false,
+            }
+        }
+        (Value::Bool(a), Value::Bool(b)) => {
+            return match op {
+                CompareOp::Eq => a == b,
+                CompareOp::Ne => a != b,
+                _ => false,
+            };
+        }
+        _ => return false,
+    };
+    match op {
+        CompareOp::Eq => ordering == Ordering::Equal,
+        CompareOp::Ne => ordering != Ordering::Equal,
+        CompareOp::Gt => ordering == Ordering::Greater,
+        CompareOp::Ge => ordering != Ordering::Less,
+        CompareOp::Lt => ordering == Ordering::Less,
+        CompareOp::Le => ordering != Ordering::Greater,
+        CompareOp::Co | CompareOp::Sw | CompareOp::Ew => false,
+    }
+}
+
+impl Filter {
+    fn parse(s: &str) -> Result<Self, ScimError> {
+        let mut parser = FilterParser {
+            tokens: tokenize(s)?,
+            pos: 0,
+        };
+        let filter = parser.or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(ScimError::invalid_filter("unexpected trailing input"));
+        }
+        Ok(filter)
+    }
+
+    fn matches(&self, resource: &Value) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(resource) && b.matches(resource),
+            Self::Or(a, b) => a.matches(resource) || b.matches(resource),
+            Self::Not(inner) => !inner.matches(resource),
+            Self::Present(path) => resolve(resource, &path.0).iter().any(|v| match v {
+                Value::String(s) => !s.is_empty(),
+                Value::Object(m) => !m.is_empty(),
+                _ => true,
+            }),
+            Self::Compare(path, op, expected) => {
+                let values = resolve(resource, &path.0);
+                let exact = path.case_exact();
+                match (op, expected) {
+                    (CompareOp::Eq, Value::Null) => values.is_empty(),
+                    (CompareOp::Ne, Value::Null) => !values.is_empty(),
+                    (CompareOp::Ne, _) => !values
+                        .iter()
+                        .any(|v| compare(v, CompareOp::Eq, expected, exact)),
+                    _ => values.iter().any(|v| compare(v, *op, expected, exact)),
+                }
+            }
+            Self::ValuePath(path, inner) => {
+                resolve(resource, &path.0).iter().any(|v| inner.matches(v))
+            }
+        }
+    }
+
+    /// For `eq` on a top-level attribute listed in `columns`, the SQL
+    /// condition answering it and the value to bind as `$1`.
+    fn pushdown<'a>(&'a self, columns: &[(&str, &'static str)]) -> Option<(&'static str, &'a str)> {
+        match self {
+            Self::Compare(path, CompareOp::Eq, Value::String(value)) if path.0.len() == 1 => {
+                columns
+                    .iter()
+                    .find(|(attr, _)| attr.eq_ignore_ascii_case(&path.0[0]))
+                    .map(|(_, sql)| (*sql, value.as_str()))
+            }
+            Self::And(a, b) => a.pushdown(columns).or_else(|| b.pushdown(columns)),
+            _ => None,
+        }
+    }
+
+    /// The element a value filter made of `eq`s describes, so that
+    /// `replace emails[type eq "work"].value` can add a missing work email.
+    fn seed(&self) -> Option<Map<String, Value>> {
+        match self {
+            Self::Compare(path, CompareOp::Eq, value) if path.0.len() == 1 => {
+                let mut map = Map::new();
+                map.insert(path.0[0].clone(), value.clone());
+                Some(map)
+            }
+            Self::And(a, b) => {
+                let mut map = a.seed()?;
+                map.extend(b.seed()?);
+                Some(map)
+            }
+            _ => None,
+        }
+    }
+}
+
+// ─── Attribute selection ─────────────────────────────────────────────────────
+
+fn copy_path(src: &Map<String, Value>, dst: &mut Map<String, Value>, path: &[String]) {
+    let Some((key, value)) = src.iter().find(|(k, _)| k.eq_ignore_ascii_case(&path[0])) else {
+        return;
+    };
+    if path.len() == 1 {
+        dst.insert(key.clone(), value.clone());
+        return;
+    }
+    match value {
+        Value::Object(inner) => {
+            let entry = dst
+                .entry(key.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(out) = entry {
+                copy_path(inner, out, &path[1..]);
+            }
+        }
+        Value::Array(items) => {
+            let entry = dst
+                .entry(key.clone())
+                .or_insert_with(|| Value::Array(vec![Value::Object(Map::new()); items.len()]));
+            if let Value::Array(out) = entry {
+                for (item, out) in items.iter().zip(out) {
+                    if let (Value::Object(item), Value::Object(out)) = (item, out) {
+                        copy_path(item, out, &path[1..]);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn remove_path(map: &mut Map<String, Value>, path: &[String]) {
+    if path.len() == 1 {
+        remove_ci(map, &path[0]);
+        return;
+    }
+    let key = key_ci(map, &path[0]);
+    match map.get_mut(&key) {
+        Some(Value::Object(inner)) => remove_path(inner, &path[1..]),
+        Some(Value::Array(items)) => {
+            for item in items {
+                if let Value::Object(inner) = item {
+                    remove_path(inner, &path[1..]);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Applies `attributes` / `excludedAttributes`; `schemas` and `id` are
+/// always returned.
+fn project(
+    resource: Value,
+    attributes: Option<&str>,
+    excluded: Option<&str>,
+) -> Result<Value, ScimError> {
+    let Value::Object(mut map) = resource else {
+        return Ok(resource);
+    };
+    let paths = |list: &str| -> Result<Vec<AttrPath>, ScimError> {
+        list.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(AttrPath::parse)
+            .collect()
+    };
+    if let Some(list) = attributes.filter(|s| !s.trim().is_empty()) {
+        let mut out = Map::new();
+        for key in ["schemas", "id"] {
+            if let Some(value) = map.get(key) {
+                out.insert(key.to_string(), value.clone());
+            }
+        }
+        for path in paths(list)? {
+            copy_path(&map, &mut out, &path.0);
+        }
+        map = out;
+    } else if let Some(list) = excluded {
+        for path in paths(list)? {
+            if !matches!(path.0[0].as_str(), "schemas" | "id") {
+                remove_path(&mut map, &path.0);
+            }
+        }
+    }
+    Ok(Value::Object(map))
+}
+
+// ─── PATCH ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+struct PatchRequest {
+    #[serde(rename = "Operations", alias = "operations")]
+    operations: Vec<PatchOp>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PatchOp {
+    op: String,
+    #[serde(default)]
+    path: Option<String>,
+    #[serde(default)]
+    value: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PatchKind {
+    Add,
+    Replace,
+    Remove,
+}
+
+/// `emails[type eq "work"].value` → attribute, value filter, sub-attribute.
+#[derive(Debug)]
+struct PatchPath {
+    attr: AttrPath,
+    filter: Option<Filter>,
+    sub: Option<String>,
+}
+
+impl PatchPath {
+    fn parse(s: &str) -> Result<Self, ScimError> {
+        let Some(open) = s.find('[') else {
+            return Ok(Self {
+                attr: AttrPath::parse(s)?,
+                filter: None,
+                sub: None,
+            });
+        };
+        let close = s
+            .rfind(']')
+            .filter(|&close| close > open)
+            .ok_or_else(|| ScimError::invalid_path(format!("unbalanced `[` in `{s}`")))?;
+        let filter =
+            Filter::parse(&s[open + 1..close]).map_err(|e| ScimError::invalid_path(e.detail))?;
+        let sub = match &s[close + 1..] {
+            "" => None,
+            rest => match rest.strip_prefix('.') {
+                Some(sub) if !sub.is_empty() && sub.chars().all(|c| c.is_ascii_alphanumeric()) => {
+                    Some(sub.to_string())
+                }
+                _ => return Err(ScimError::invalid_path(format!("invalid path `{s}`"))),
+            },
+        };
+        Ok(Self {
+            attr: AttrPath::parse(&s[..open])?,
+            filter: Some(filter),
+            sub,
+        })
+    }
+}
+
+/// The object holding the last segment of `path`, created on the way if
+/// `create` is set.
+fn parent_mut<'a>(
+    doc: &'a mut Map<String, Value>,
+    path: &[String],
+    create: bool,
+) -> Option<&'a mut Map<String, Value>> {
+    let mut current = doc;
+    for segment in &path[..path.len() - 1] {
+        let key = key_ci(current, segment);
+        if !current.contains_key(&key) {
+            if !create {
+                return None;
+            }
+            current.insert(key.clone(), Value::Object(Map::new()));
+        }
+        current = match current.get_mut(&key) {
+            Some(Value::Object(map)) => map,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn merge(target: &mut Map<String, Value>, value: Map<String, Value>) {
+    for (key, value) in value {
+        let key = key_ci(target, &key);
+        target.insert(key, value);
+    }
+}
+
+fn apply_patch(doc: &mut Map<String, Value>, ops: Vec<PatchOp>) -> Result<(), ScimError> {
+    for op in ops {
+        let kind = match op.op.to_ascii_lowercase().as_str() {
+            "add" => PatchKind::Add,
+            "replace" => PatchKind::Replace,
+            "remove" => PatchKind::Remove,
+            _ => return Err(ScimError::invalid_syntax(format!("unknown op `{}`", op.op))),
+        };
+        match op.path.as_deref().map(str::trim) {
+            Some(path) if !path.is_empty() => {
+                apply_path(doc, kind, &PatchPath::parse(path)?, op.value)?
+            }
+            _ => apply_without_path(doc, kind, op.value)?,
+        }
+    }
+    Ok(())
+}
+
+/// `add` / `replace` with an object of attributes and no `path`.
+fn apply_without_path(
+    doc: &mut Map<String, Value>,
+    kind: PatchKind,
+    value: Option<Value>,
+) -> Result<(), ScimError> {
+    if kind == PatchKind::Remove {
+        return Err(ScimError::no_target("remove requires a path"));
+    }
+    let Some(Value::Object(attrs)) = value else {
+        return Err(ScimError::invalid_value("expected an object of attributes"));
+    };
+    for (key, value) in attrs {
+        if ["id", "schemas", "meta"]
+            .iter()
+            .any(|k| k.eq_ignore_ascii_case(&key))
+        {
+            continue;
+        }
+        if key.eq_ignore_ascii_case(USER_SCHEMA) || key.eq_ignore_ascii_case(GROUP_SCHEMA) {
+            apply_without_path(doc, kind, Some(value))?;
+            continue;
+        }
+        let path = PatchPath::parse(&key)?;
+        apply_path(doc, kind, &path, Some(value))?;
+    }
+    Ok(())
+}
+
+fn apply_path(
+    doc: &mut Map<String, Value>,
+    kind: PatchKind,
+    path: &PatchPath,
+    value: Option<Value>,
+) -> Result<(), ScimError> {
+    let remove = kind == PatchKind::Remove;
+    let Some(parent) = parent_mut(doc, &path.attr.0, !remove) else {
+        return if remove {
+            Ok(())
+        } else {
+            Err(ScimError::invalid_path(
+                "the path does not lead to an attribute",
+            ))
+        };
+    };
+    let key = key_ci(parent, &path.attr.0[path.attr.0.len() - 1]);
+    let value = match (kind, value) {
+        (PatchKind::Remove, value) => value,
+        (_, Some(value)) => Some(value),
+        (_, None) => return Err(ScimError::invalid_value("the operation needs a value")),
+    };
+
+    let Some(filter) = &path.filter else {
+        match (kind, value) {
+            // Azure AD removes members by listing them as the value.
+            (PatchKind::Remove, Some(Value::Array(listed))) => {
+                if let Some(Value::Array(items)) = parent.get_mut(&key) {
+                    let ids: Vec<&Value> = listed
+                        .iter()
+                        .map(|v| match v {
+                            Value::Object(m) => get_ci(m, "value").unwrap_or(v),
+                            _ => v,
+                        })
+                        .collect();
+                    items.retain(|item| {
+                        let id = match item {
+                            Value::Object(m) => get_ci(m, "value").unwrap_or(item),
+                            _ => item,
+                        };
+                        !ids.contains(&id)
+                    });
+                } else {
+                    parent.remove(&key);
+                }
+            }
+            (PatchKind::Remove, _) => {
+                parent.remove(&key);
+            }
+            (PatchKind::Add, Some(value)) => match (parent.get_mut(&key), value) {
+                (Some(Value::Array(items)), Value::Array(new)) => {
+                    for item in new {
+                        if !items.contains(&item) {
+                            items.push(item);
+                        }
+                    }
+                }
+                (Some(Value::Array(items)), item) => {
+                    if !items.contains(&item) {
+                        items.push(item);
+                    }
+                }
+                (Some(Value::Object(target)), Value::Object(new)) => merge(target, new),
+                (_, value) => {
+                    parent.insert(key, value);
+                }
+            },
+            (_, Some(value)) => match (parent.get_mut(&key), value) {
+                (Some(Value::Object(target)), Value::Object(new)) => merge(target, new),
+                (_, value) => {
+                    parent.insert(key, value);
+                }
+            },
+            (_, None) => unreachable!(),
+        }
+        return Ok(());
+    };
+
+    if !matches!(parent.get(&key), Some(Value::Array(_))) {
+        if remove {
+            return Ok(());
+        }
+        parent.insert(key.clone(), Value::Array(Vec::new()));
+    }
+    let Some(Value::Array(items)) = parent.get_mut(&key) else {
+        unreachable!()
+    };
+    match (kind, &path.sub) {
+        (PatchKind::Remove, None) => items.retain(|item| !filter.matches(item)),
+        (PatchKind::Remove, Some(sub)) => {
+            for item in items.iter_mut().filter(|item| filter.matches(item)) {
+                if let Value::Object(map) = item {
+                    remove_ci(map, sub);
+                }
+            }
+        }
+        (_, sub) => {
+            let value = value.unwrap_or(Value::Null);
+            let mut matched = false;
+            for item in items.iter_mut().filter(|item| filter.matches(item)) {
+                matched = true;
+                match (sub, item) {
+                    (Some(sub), Value::Object(map)) => {
+                        let sub = key_ci(map, sub);
+                        map.insert(sub, value.clone());
+                    }
+                    (None, Value::Object(map)) if kind == PatchKind::Add => {
+                        if let Value::Object(new) = value.clone() {
+                            merge(map, new);
+                        }
+                    }
+                    (None, item) => *item = value.clone(),
+                    _ => {}
+                }
+            }
+            if !matched {
+                let mut element = filter
+                    .seed()
+                    .ok_or_else(|| ScimError::no_target("no element matches the filter"))?;
+                match (sub, value) {
+                    (Some(sub), value) => {
+                        element.insert(sub.clone(), value);
+                    }
+                    (None, Value::Object(new)) => merge(&mut element, new),
+                    (None, _) => {
+                        return Err(ScimError::invalid_value("expected an object"));
+                    }
+                }
+                items.push(Value::Object(element));
+            }
+        }
+    }
+    Ok(())
+}
+
+// ─── Storage ─────────────────────────────────────────────────────────────────
+
+const USERS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS __chopin_scim_users (
+        id TEXT PRIMARY KEY,
+        user_name TEXT NOT NULL,
+        external_id TEXT,
+        active BOOLEAN NOT NULL DEFAULT TRUE,
+        resource TEXT NOT NULL,
+        version INT8 NOT NULL DEFAULT 1,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )
+"#;
+
+const GROUPS_TABLE: &str = r#"
+    CREATE TABLE IF NOT EXISTS __chopin_scim_groups (
+        id TEXT PRIMARY KEY,
+        display_name TEXT NOT NULL UNIQUE,
+        external_id TEXT,
+        version INT8 NOT NULL DEFAULT 1,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )
+"#;
+
+const META_COLUMNS: &str = "version, \
+    to_char(created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS created, \
+    to_char(updated_at AT TIME ZONE 'UTC', 'YYYY-MM-DD\"T\"HH24:MI:SS\"Z\"') AS updated";
+
+/// Timestamps and version of a stored resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Meta {
+    version: i64,
+    created: String,
+    updated: String,
+}
+
+impl Meta {
+    fn from_row(row: &Row) -> OrmResult<Self> {
+        Ok(Self {
+            version: row.get_typed_by_name("version")?,
+            created: row.get_typed_by_name("created")?,
+            updated: row.get_typed_by_name("updated")?,
+        })
+    }
+
+    fn to_json(&self, resource_type: &str, location: String) -> Value {
+        json!({
+            "resourceType": resource_type,
+            "created": self.created,
+            "lastModified": self.updated,
+            "version": format!("W/\"{}\"", self.version),
+            "location": location,
+        })
+    }
+}
+
+/// A provisioned user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScimUser {
+    pub id: String,
+    pub user_name: String,
+    pub external_id: Option<String>,
+    /// `false` once the identity provider deactivated the user; refuse
+    /// their logins.
+    pub active: bool,
+    /// Every attribute as last sent, e.g. `name` and `emails`.
+    pub attributes: Map<String, Value>,
+    meta: Meta,
+}
+
+impl ScimUser {
+    fn from_row(row: &Row) -> OrmResult<Self> {
+        let resource: String = row.get_typed_by_name("resource")?;
+        Ok(Self {
+            id: row.get_typed_by_name("id")?,
+            user_name: row.get_typed_by_name("user_name")?,
+            external_id: row.get_typed_by_name("external_id")?,
+            active: row.get_typed_by_name("active")?,
+            attributes: serde_json::from_str(&resource)
+                .map_err(|e| OrmError::Extraction(e.to_string()))?,
+            meta: Meta::from_row(row)?,
+        })
+    }
+
+    /// The primary email, or else the first one.
+    pub fn email(&self) -> Option<&str> {
+        let Some(Value::Array(emails)) = get_ci(&self.attributes, "emails") else {
+            return None;
+        };
+        fn value(e: &Value) -> Option<&str> {
+            e.get("value").and_then(Value::as_str)
+        }
+        emails
+            .iter()
+            .find(|e| e.get("primary") == Some(&Value::Bool(true)))
+            .and_then(value)
+            .or_else(|| emails.iter().find_map(value))
+    }
+}
+
+/// Lookups of provisioned users, e.g. to sign them in.
+pub struct ScimUsers;
+
+const USER_SELECT: &str = "SELECT id, user_name, external_id, active, resource, ";
+
+impl ScimUsers {
+    /// Creates the SCIM tables, and the role grant tables groups are
+    /// stored in, if they do not exist.
+    pub fn ensure_tables(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(USERS_TABLE, &[])?;
+        executor.execute(
+            "CREATE UNIQUE INDEX IF NOT EXISTS __chopin_scim_users_user_name_idx \
+             ON __chopin_scim_users (lower(user_name))",
+            &[],
+        )?;
+        executor.execute(
+            "CREATE INDEX IF NOT EXISTS __chopin_scim_users_external_id_idx \
+             ON __chopin_scim_users (external_id)",
+            &[],
+        )?;
+        executor.execute(GROUPS_TABLE, &[])?;
+        RoleGrants::ensure_tables(executor)
+    }
+
+    pub fn find(executor: &mut dyn Executor, id: &str) -> OrmResult<Option<ScimUser>> {
+        Ok(Self::select(executor, Some(("id = $1", id)))?.pop())
+    }
+
+    /// The user with this `userName`, compared case-insensitively.
+    pub fn by_user_name(
+        executor: &mut dyn Executor,
+        user_name: &str,
+    ) -> OrmResult<Option<ScimUser>> {
+        Ok(Self::select(executor, Some(("lower(user_name) = lower($1)", user_name)))?.pop())
+    }
+
+    fn select(
+        executor: &mut dyn Executor,
+        condition: Option<(&str, &str)>,
+    ) -> OrmResult<Vec<ScimUser>> {
+        let rows = match condition {
+            Some((condition, value)) => executor.query(
+                &format!(
+                    "{USER_SELECT}{META_COLUMNS} FROM __chopin_scim_users \
+                     WHERE {condition} ORDER BY created_at, id"
+                ),
+                &[&value],
+            )?,
+            None => executor.query(
+                &format!(
+                    "{USER_SELECT}{META_COLUMNS} FROM __chopin_scim_users ORDER BY created_at, id"
+                ),
+                &[],
+            )?,
+        };
+        rows.iter().map(ScimUser::from_row).collect()
+    }
+
+    /// (group id, display name) of the SCIM groups each user is in.
+    fn groups(
+        executor: &mut dyn Executor,
+        user: Option<&str>,
+    ) -> OrmResult<HashMap<String, Vec<(String, String)>>> {
+        let rows = executor.query(
+            "SELECT r.subject, g.id, g.display_name FROM __chopin_role_grants r \
+             JOIN __chopin_scim_groups g ON g.display_name = r.role \
+             WHERE $1::text IS NULL OR r.subject = $1 ORDER BY g.display_name",
+            &[&user],
+        )?;
+        let mut groups: HashMap<String, Vec<(String, String)>> = HashMap::new();
+        for row in &rows {
+            groups
+                .entry(row.get_typed_by_name("subject")?)
+                .or_default()
+                .push((
+                    row.get_typed_by_name("id")?,
+                    row.get_typed_by_name("display_name")?,
+                ));
+        }
+        Ok(groups)
+    }
+
+    fn insert(executor: &mut dyn Executor, fields: &UserFields) -> OrmResult<String> {
+        let id = new_id();
+        executor.execute(
+            "INSERT INTO __chopin_scim_users (id, user_name, external_id, active, resource) \
+             VALUES ($1, $2, $3, $4, $5)",
+            &[
+                &id,
+                &fields.user_name,
+                &fields.external_id,
+                &fields.active,
+                &fields.resource(),
+            ],
+        )?;
+        Ok(id)
+    }
+
+    fn update(executor: &mut dyn Executor, id: &str, fields: &UserFields) -> OrmResult<bool> {
+        let n = executor.execute(
+            "UPDATE __chopin_scim_users SET user_name = $2, external_id = $3, active = $4, \
+             resource = $5, version = version + 1, updated_at = NOW() WHERE id = $1",
+            &[
+                &id,
+                &fields.user_name,
+                &fields.external_id,
+                &fields.active,
+                &fields.resource(),
+            ],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Deletes the user and revokes all their roles, audited.
+    fn delete(executor: &mut dyn Executor, actor: &str, id: &str) -> OrmResult<bool> {
+        let rows = executor.query(
+            "WITH deleted AS (DELETE FROM __chopin_scim_users WHERE id = $1 RETURNING id), \
+             removed AS ( \
+                 DELETE FROM __chopin_role_grants r USING deleted \
+                 WHERE r.subject = deleted.id RETURNING r.subject, r.role), \
+             logged AS ( \
+                 INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+                 SELECT $2, 'revoke', subject, role, NULL FROM removed) \
+             SELECT count(*)::int8 AS n FROM deleted",
+            &[&id, &actor],
+        )?;
+        let n: i64 = match rows.first() {
+            Some(row) => row.get_typed_by_name("n")?,
+            None => 0,
+        };
+        Ok(n > 0)
+    }
+
+    /// Which of `ids` are provisioned users.
+    fn existing(executor: &mut dyn Executor, ids: &[String]) -> OrmResult<HashSet<String>> {
+        let ids = ids.to_vec();
+        let rows = executor.query(
+            "SELECT id FROM __chopin_scim_users WHERE id = ANY($1)",
+            &[&ids],
+        )?;
+        rows.iter()
+            .map(|row| Ok(row.get_typed_by_name("id")?))
+            .collect()
+    }
+}
+
+/// The columns of a user, validated from its attributes.
+#[derive(Debug)]
+struct UserFields {
+    user_name: String,
+    external_id: Option<String>,
+    active: bool,
+    attributes: Map<String, Value>,
+}
+
+impl UserFields {
+    /// Drops read-only and write-only attributes and checks the rest.
+    fn from_attributes(mut attributes: Map<String, Value>) -> Result<Self, ScimError> {
+        for key in ["id", "schemas", "meta", "groups", "password"] {
+            remove_ci(&mut attributes, key);
+        }
+        let user_name = match remove_ci(&mut attributes, "userName") {
+            Some(Value::String(name)) if !name.trim().is_empty() => name,
+            _ => return Err(ScimError::invalid_value("userName is required")),
+        };
+        let external_id = match remove_ci(&mut attributes, "externalId") {
+            Some(Value::String(id)) => Some(id),
+            None | Some(Value::Null) => None,
+            Some(_) => return Err(ScimError::invalid_value("externalId must be a string")),
+        };
+        // Azure AD sends `"active": "False"`.
+        let active = match remove_ci(&mut attributes, "active") {
+            None | Some(Value::Null) => true,
+            Some(Value::Bool(active)) => active,
+            Some(Value::String(s)) if s.eq_ignore_ascii_case("true") => true,
+            Some(Value::String(s)) if s.eq_ignore_ascii_case("false") => false,
+            Some(_) => return Err(ScimError::invalid_value("active must be a boolean")),
+        };
+        attributes.insert("userName".into(), json!(user_name));
+        if let Some(id) = &external_id {
+            attributes.insert("externalId".into(), json!(id));
+        }
+        attributes.insert("active".into(), json!(active));
+        Ok(Self {
+            user_name,
+            external_id,
+            active,
+            attributes,
+        })
+    }
+
+    fn resource(&self) -> String {
+        Value::Object(self.attributes.clone()).to_string()
+    }
+}
+
+/// A group as stored; its members come from the role grants.
+#[derive(Debug, Clone, PartialEq)]
+struct ScimGroup {
+    id: String,
+    display_name: String,
+    external_id: Option<String>,
+    meta: Meta,
+}
+
+/// A member's subject and, for provisioned users, `userName`.
+type Member = (String, Option<String>);
+
+struct ScimGroups;
+
+impl ScimGroups {
+    fn select(
+        executor: &mut dyn Executor,
+        condition: Option<(&str, &str)>,
+    ) -> OrmResult<Vec<ScimGroup>> {
+        let select = format!(
+            "SELECT id, display_name, external_id, {META_COLUMNS} FROM __chopin_scim_groups"
+        );
+        let rows = match condition {
+            Some((condition, value)) => executor.query(
+                &format!("{select} WHERE {condition} ORDER BY created_at, id"),
+                &[&value],
+            )?,
+            None => executor.query(&format!("{select} ORDER BY created_at, id"), &[])?,
+        };
+        rows.iter()
+            .map(|row| {
+                Ok(ScimGroup {
+                    id: row.get_typed_by_name("id")?,
+                    display_name: row.get_typed_by_name("display_name")?,
+                    external_id: row.get_typed_by_name("external_id")?,
+                    meta: Meta::from_row(row)?,
+                })
+            })
+            .collect()
+    }
+
+    fn find(executor: &mut dyn Executor, id: &str) -> OrmResult<Option<ScimGroup>> {
+        Ok(Self::select(executor, Some(("id = $1", id)))?.pop())
+    }
+
+    /// (subject, userName) of each group's members, by group id.
+    fn members(
+        executor: &mut dyn Executor,
+        group: Option<&str>,
+    ) -> OrmResult<HashMap<String, Vec<Member>>> {
+        let rows = executor.query(
+            "SELECT g.id, r.subject, u.user_name FROM __chopin_scim_groups g \
+             JOIN __chopin_role_grants r ON r.role = g.display_name \
+             LEFT JOIN __chopin_scim_users u ON u.id = r.subject \
+             WHERE $1::text IS NULL OR g.id = $1 ORDER BY r.granted_at, r.subject",
+            &[&group],
+        )?;
+        let mut members: HashMap<String, Vec<Member>> = HashMap::new();
+        for row in &rows {
+            members
+                .entry(row.get_typed_by_name("id")?)
+                .or_default()
+                .push((
+                    row.get_typed_by_name("subject")?,
+                    row.get_typed_by_name("user_name")?,
+                ));
+        }
+        Ok(members)
+    }
+
+    fn insert(
+        executor: &mut dyn Executor,
+        display_name: &str,
+        external_id: Option<&str>,
+    ) -> OrmResult<String> {
+        let id = new_id();
+        executor.execute(
+            "INSERT INTO __chopin_scim_groups (id, display_name, external_id) VALUES ($1, $2, $3)",
+            &[&id, &display_name, &external_id],
+        )?;
+        Ok(id)
+    }
+
+    /// Updates the group; a new `displayName` moves its grants to the new
+    /// role, logged as a revocation and a grant per member.
+    fn update(
+        executor: &mut dyn Executor,
+        actor: &str,
+        id: &str,
+        display_name: &str,
+        external_id: Option<&str>,
+    ) -> OrmResult<()> {
+        executor.execute(
+            "WITH old AS (SELECT display_name FROM __chopin_scim_groups WHERE id = $1), \
+             updated AS ( \
+                 UPDATE __chopin_scim_groups SET display_name = $2, external_id = $3, \
+                 version = version + 1, updated_at = NOW() WHERE id = $1), \
+             moved AS ( \
+                 UPDATE __chopin_role_grants r SET role = $2 FROM old \
+                 WHERE r.role = old.display_name AND old.display_name <> $2 \
+                 RETURNING r.subject, old.display_name AS old_role), \
+             revoked AS ( \
+                 INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+                 SELECT $4, 'revoke', subject, old_role, NULL FROM moved) \
+             INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+             SELECT $4, 'grant', subject, $2, NULL FROM moved",
+            &[&id, &display_name, &external_id, &actor],
+        )?;
+        Ok(())
+    }
+
+    /// Deletes the group and revokes its role from every member, audited.
+    fn delete(executor: &mut dyn Executor, actor: &str, id: &str) -> OrmResult<bool> {
+        let rows = executor.query(
+            "WITH deleted AS ( \
+                 DELETE FROM __chopin_scim_groups WHERE id = $1 RETURNING display_name), \
+             removed AS ( \
+                 DELETE FROM __chopin_role_grants r USING deleted \
+                 WHERE r.role = deleted.display_name RETURNING r.subject, r.role), \
+             logged AS ( \
+                 INSERT INTO __chopin_rbac_audit (actor, action, subject, role, reason) \
+                 SELECT $2, 'revoke', subject, role, NULL FROM removed) \
+             SELECT count(*)::int8 AS n FROM deleted",
+            &[&id, &actor],
+        )?;
+        let n: i64 = match rows.first() {
+            Some(row) => row.get_typed_by_name("n")?,
+            None => 0,
+        };
+        Ok(n > 0)
+    }
+}
+
+/// The writable parts of a group, validated.
+#[derive(Debug)]
+struct GroupFields {
+    display_name: String,
+    external_id: Option<String>,
+    members: Vec<String>,
+}
+
+impl GroupFields {
+    fn from_attributes(attributes: &Map<String, Value>) -> Result<Self, ScimError> {
+        let display_name = match get_ci(attributes, "displayName") {
+            Some(Value::String(name)) if !name.trim().is_empty() => name.clone(),
+            _ => return Err(ScimError::invalid_value("displayName is required")),
+        };
+        let external_id = match get_ci(attributes, "externalId") {
+            Some(Value::String(id)) => Some(id.clone()),
+            None | Some(Value::Null) => None,
+            Some(_) => return Err(ScimError::invalid_value("externalId must be a string")),
+        };
+        let mut members = Vec::new();
+        match get_ci(attributes, "members") {
+            None | Some(Value::Null) => {}
+            Some(Value::Array(items)) => {
+                for item in items {
+                    let id = match item {
+                        Value::Object(m) => get_ci(m, "value").and_then(Value::as_str),
+                        _ => None,
+                    };
+                    let Some(id) = id else {
+                        return Err(ScimError::invalid_value("members need a value"));
+                    };
+                    if !members.iter().any(|m| m == id) {
+                        members.push(id.to_string());
+                    }
+                }
+            }
+            Some(_) => return Err(ScimError::invalid_value("members must be an array")),
+        }
+        Ok(Self {
+            display_name,
+            external_id,
+            members,
+        })
+    }
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Mounts the SCIM endpoints; see the [module docs](self).
+#[derive(Clone)]
+pub struct ScimModule {
+    token_digest: [u8; 32],
+    prefix: String,
+    base_url: String,
+    actor: String,
+}
+
+impl ScimModule {
+    /// Endpoints accepting `Authorization: Bearer <token>`.
+    pub fn new(token: &str) -> Self {
+        Self {
+            token_digest: sha256(token.as_bytes()),
+            prefix: "/scim/v2".to_string(),
+            base_url: String::new(),
+            actor: "scim".to_string(),
+        }
+    }
+
+    /// Path the endpoints are mounted under (default `/scim/v2`).
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Scheme and host for `meta.location`, e.g. `https://app.example.com`;
+    /// locations are relative without it.
+    pub fn base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Actor recorded in the RBAC audit trail (default `"scim"`).
+    pub fn actor(mut self, actor: &str) -> Self {
+        self.actor = actor.to_string();
+        self
+    }
+
+    fn location(&self, kind: &str, id: &str) -> String {
+        format!("{}{}/{kind}/{id}", self.base_url, self.prefix)
+    }
+
+    fn render_user(&self, user: &ScimUser, groups: &[(String, String)]) -> Value {
+        let mut out = Map::new();
+        let mut schemas = vec![json!(USER_SCHEMA)];
+        schemas.extend(
+            user.attributes
+                .keys()
+                .filter(|k| k.len() > 4 && k[..4].eq_ignore_ascii_case("urn:"))
+                .map(|k| json!(k)),
+        );
+        out.insert("schemas".into(), Value::Array(schemas));
+        out.insert("id".into(), json!(user.id));
+        out.extend(user.attributes.clone());
+        if !groups.is_empty() {
+            let groups: Vec<Value> = groups
+                .iter()
+                .map(|(id, name)| {
+                    json!({
+                        "value": id,
+                        "display": name,
+                        "$ref": self.location("Groups", id),
+                        "type": "direct",
+                    })
+                })
+                .collect();
+            out.insert("groups".into(), Value::Array(groups));
+        }
+        out.insert(
+            "meta".into(),
+            user.meta.to_json("User", self.location("Users", &user.id)),
+        );
+        Value::Object(out)
+    }
+
+    fn render_group(&self, group: &ScimGroup, members: &[Member]) -> Value {
+        let members: Vec<Value> = members
+            .iter()
+            .map(|(id, user_name)| {
+                let mut member = json!({
+                    "value": id,
+                    "$ref": self.location("Users", id),
+                    "type": "User",
+                });
+                if let Some(name) = user_name {
+                    member["display"] = json!(name);
+                }
+                member
+            })
+            .collect();
+        let mut out = json!({
+            "schemas": [GROUP_SCHEMA],
+            "id": group.id,
+            "displayName": group.display_name,
+            "members": members,
+            "meta": group.meta.to_json("Group", self.location("Groups", &group.id)),
+        });
+        if let Some(id) = &group.external_id {
+            out["externalId"] = json!(id);
+        }
+        out
+    }
+
+    fn user(&self, db: &mut dyn Executor, id: &str) -> Result<Value, ScimError> {
+        let user = ScimUsers::find(db, id)?.ok_or_else(|| ScimError::not_found("User"))?;
+        let groups = ScimUsers::groups(db, Some(id))?;
+        Ok(self.render_user(&user, groups.get(id).map_or(&[], Vec::as_slice)))
+    }
+
+    fn group(&self, db: &mut dyn Executor, id: &str) -> Result<Value, ScimError> {
+        let group = ScimGroups::find(db, id)?.ok_or_else(|| ScimError::not_found("Group"))?;
+        let members = ScimGroups::members(db, Some(id))?;
+        Ok(self.render_group(&group, members.get(id).map_or(&[], Vec::as_slice)))
+    }
+
+    /// Grants and revokes the group's role so its members are `members`.
+    fn sync_members(
+        &self,
+        db: &mut dyn Executor,
+        group_id: &str,
+        role: &str,
+        members: &[String],
+    ) -> Result<(), ScimError> {
+        let current: Vec<String> = ScimGroups::members(db, Some(group_id))?
+            .remove(group_id)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect();
+        let added: Vec<String> = members
+            .iter()
+            .filter(|m| !current.contains(m))
+            .cloned()
+            .collect();
+        let known = ScimUsers::existing(db, &added)?;
+        if let Some(unknown) = added.iter().find(|m| !known.contains(*m)) {
+            return Err(ScimError::invalid_value(format!(
+                "unknown member `{unknown}`"
+            )));
+        }
+        for member in &added {
+            RoleGrants::grant(db, &self.actor, member, role, None)?;
+        }
+        for member in current.iter().filter(|m| !members.contains(m)) {
+            RoleGrants::revoke(db, &self.actor, member, role, None)?;
+        }
+        Ok(())
+    }
+}
+
+static SCIM: RwLock<Option<Arc<ScimModule>>> = RwLock::new(None);
+
+fn config() -> Option<Arc<ScimModule>> {
+    SCIM.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+impl ChopinModule for ScimModule {
+    fn name(&self) -> &'static str {
+        "scim"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        let prefix = &self.prefix;
+        router.get(
+            &format!("{prefix}/ServiceProviderConfig"),
+            service_provider_config,
+        );
+        router.get(&format!("{prefix}/ResourceTypes"), resource_types);
+        router.get(&format!("{prefix}/Users"), list_users);
+        router.post(&format!("{prefix}/Users"), create_user);
+        router.get(&format!("{prefix}/Users/:id"), get_user);
+        router.put(&format!("{prefix}/Users/:id"), replace_user);
+        router.patch(&format!("{prefix}/Users/:id"), patch_user);
+        router.delete(&format!("{prefix}/Users/:id"), delete_user);
+        router.get(&format!("{prefix}/Groups"), list_groups);
+        router.post(&format!("{prefix}/Groups"), create_group);
+        router.get(&format!("{prefix}/Groups/:id"), get_group);
+        router.put(&format!("{prefix}/Groups/:id"), replace_group);
+        router.patch(&format!("{prefix}/Groups/:id"), patch_group);
+        router.delete(&format!("{prefix}/Groups/:id"), delete_group);
+        *SCIM.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(self.clone()));
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreateScimTables)]
+    }
+}
+
+struct CreateScimTables;
+
+impl Migration for CreateScimTables {
+    fn name(&self) -> &'static str {
+        "001_create_scim_tables"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        ScimUsers::ensure_tables(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS __chopin_scim_groups", &[])?;
+        executor.execute("DROP TABLE IF EXISTS __chopin_scim_users", &[])?;
+        Ok(())
+    }
+}
+
+// ─── Handlers ────────────────────────────────────────────────────────────────
+
+/// Checks the bearer token, then runs `f`, rendering its error if any.
+fn handle(
+    ctx: &Context,
+    f: impl FnOnce(&ScimModule, &Context) -> Result<Response, ScimError>,
+) -> Response {
+    let Some(config) = config() else {
+        return Response::not_found();
+    };
+    let token = ctx
+        .header("Authorization")
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .unwrap_or("");
+    if !constant_time_eq(&sha256(token.as_bytes()), &config.token_digest) {
+        return ScimError::new(401, None, "invalid bearer token")
+            .to_response()
+            .with_header("WWW-Authenticate", "Bearer realm=\"scim\"");
+    }
+    f(&config, ctx).unwrap_or_else(|e| e.to_response())
+}
+
+fn body<T: serde::de::DeserializeOwned>(ctx: &Context) -> Result<T, ScimError> {
+    serde_json::from_slice(ctx.req.body).map_err(|e| ScimError::invalid_syntax(e.to_string()))
+}
+
+fn attributes_of(ctx: &Context) -> Result<Map<String, Value>, ScimError> {
+    match body(ctx)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(ScimError::invalid_syntax("expected a JSON object")),
+    }
+}
+
+fn id_param(ctx: &Context) -> String {
+    ctx.param("id").unwrap_or_default().to_string()
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ListParams {
+    filter: Option<String>,
+    start_index: Option<usize>,
+    count: Option<usize>,
+    attributes: Option<String>,
+    excluded_attributes: Option<String>,
+}
+
+impl ListParams {
+    fn from_ctx(ctx: &Context) -> Result<Self, ScimError> {
+        match ctx.extract::<Query<ListParams>>() {
+            Ok(Query(params)) => Ok(params),
+            Err(_) => Err(ScimError::invalid_value("invalid query parameters")),
+        }
+    }
+
+    fn filter(&self) -> Result<Option<Filter>, ScimError> {
+        self.filter
+            .as_deref()
+            .filter(|f| !f.trim().is_empty())
+            .map(Filter::parse)
+            .transpose()
+    }
+
+    /// Filters, pages and projects rendered resources into a ListResponse.
+    fn respond(
+        &self,
+        filter: Option<&Filter>,
+        resources: Vec<Value>,
+    ) -> Result<Response, ScimError> {
+        let matching: Vec<Value> = resources
+            .into_iter()
+            .filter(|r| filter.is_none_or(|f| f.matches(r)))
+            .collect();
+        let start = self.start_index.unwrap_or(1).max(1);
+        let count = self.count.unwrap_or(MAX_RESULTS).min(MAX_RESULTS);
+        let page = matching
+            .iter()
+            .skip(start - 1)
+            .take(count)
+            .map(|r| {
+                project(
+                    r.clone(),
+                    self.attributes.as_deref(),
+                    self.excluded_attributes.as_deref(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(scim_response(
+            200,
+            &json!({
+                "schemas": [LIST_SCHEMA],
+                "totalResults": matching.len(),
+                "startIndex": start,
+                "itemsPerPage": page.len(),
+                "Resources": page,
+            }),
+        ))
+    }
+}
+
+fn created(config: &ScimModule, kind: &str, resource: Value) -> Response {
+    let location = config.location(kind, resource["id"].as_str().unwrap_or_default());
+    scim_response(201, &resource).with_header("Location", location)
+}
+
+fn service_provider_config(ctx: Context) -> Response {
+    handle(&ctx, |config, _| {
+        Ok(scim_response(
+            200,
+            &json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ServiceProviderConfig"],
+                "patch": { "supported": true },
+                "bulk": { "supported": false, "maxOperations": 0, "maxPayloadSize": 0 },
+                "filter": { "supported": true, "maxResults": MAX_RESULTS },
+                "changePassword": { "supported": false },
+                "sort": { "supported": false },
+                "etag": { "supported": false },
+                "authenticationSchemes": [{
+                    "type": "oauthbearertoken",
+                    "name": "Bearer token",
+                    "description": "The token configured for this app",
+                }],
+                "meta": {
+                    "resourceType": "ServiceProviderConfig",
+                    "location": format!("{}{}/ServiceProviderConfig", config.base_url, config.prefix),
+                },
+            }),
+        ))
+    })
+}
+
+fn resource_types(ctx: Context) -> Response {
+    handle(&ctx, |_, _| {
+        let types = [
+            ("User", "/Users", USER_SCHEMA),
+            ("Group", "/Groups", GROUP_SCHEMA),
+        ]
+        .map(|(name, endpoint, schema)| {
+            json!({
+                "schemas": ["urn:ietf:params:scim:schemas:core:2.0:ResourceType"],
+                "id": name,
+                "name": name,
+                "endpoint": endpoint,
+                "schema": schema,
+            })
+        });
+        Ok(scim_response(
+            200,
+            &json!({
+                "schemas": [LIST_SCHEMA],
+                "totalResults": types.len(),
+                "startIndex": 1,
+                "itemsPerPage": types.len(),
+                "Resources": types,
+            }),
+        ))
+    })
+}
+
+fn list_users(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let params = ListParams::from_ctx(ctx)?;
+        let filter = params.filter()?;
+        let pushdown = filter.as_ref().and_then(|f| {
+            f.pushdown(&[
+                ("id", "id = $1"),
+                ("userName", "lower(user_name) = lower($1)"),
+                ("externalId", "external_id = $1"),
+            ])
+        });
+        let resources = with_store(|db| {
+            let users = ScimUsers::select(db, pushdown)?;
+            let groups = ScimUsers::groups(db, None)?;
+            Ok(users
+                .iter()
+                .map(|u| config.render_user(u, groups.get(&u.id).map_or(&[], Vec::as_slice)))
+                .collect())
+        })?;
+        params.respond(filter.as_ref(), resources)
+    })
+}
+
+fn create_user(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let fields = UserFields::from_attributes(attributes_of(ctx)?)?;
+        let user = with_store(|db| {
+            let id = ScimUsers::insert(db, &fields)?;
+            config.user(db, &id)
+        })?;
+        Ok(created(config, "Users", user))
+    })
+}
+
+fn get_user(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let params = ListParams::from_ctx(ctx)?;
+        let user = with_store(|db| config.user(db, &id_param(ctx)))?;
+        let user = project(
+            user,
+            params.attributes.as_deref(),
+            params.excluded_attributes.as_deref(),
+        )?;
+        Ok(scim_response(200, &user))
+    })
+}
+
+fn replace_user(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        let fields = UserFields::from_attributes(attributes_of(ctx)?)?;
+        let user = with_store(|db| {
+            if !ScimUsers::update(db, &id, &fields)? {
+                return Err(ScimError::not_found("User"));
+            }
+            config.user(db, &id)
+        })?;
+        Ok(scim_response(200, &user))
+    })
+}
+
+fn patch_user(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        let patch: PatchRequest = body(ctx)?;
+        let user = with_store(|db| {
+            let user = ScimUsers::find(db, &id)?.ok_or_else(|| ScimError::not_found("User"))?;
+            let mut attributes = user.attributes;
+            apply_patch(&mut attributes, patch.operations)?;
+            let fields = UserFields::from_attributes(attributes)?;
+            ScimUsers::update(db, &id, &fields)?;
+            config.user(db, &id)
+        })?;
+        Ok(scim_response(200, &user))
+    })
+}
+
+fn delete_user(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        match with_store(|db| Ok(ScimUsers::delete(db, &config.actor, &id)?))? {
+            true => Ok(Response::new(204)),
+            false => Err(ScimError::not_found("User")),
+        }
+    })
+}
+
+fn list_groups(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let params = ListParams::from_ctx(ctx)?;
+        let filter = params.filter()?;
+        let pushdown = filter.as_ref().and_then(|f| {
+            f.pushdown(&[
+                ("id", "id = $1"),
+                ("displayName", "display_name = $1"),
+                ("externalId", "external_id = $1"),
+            ])
+        });
+        let resources = with_store(|db| {
+            let groups = ScimGroups::select(db, pushdown)?;
+            let members = ScimGroups::members(db, None)?;
+            Ok(groups
+                .iter()
+                .map(|g| config.render_group(g, members.get(&g.id).map_or(&[], Vec::as_slice)))
+                .collect())
+        })?;
+        params.respond(filter.as_ref(), resources)
+    })
+}
+
+fn create_group(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let fields = GroupFields::from_attributes(&attributes_of(ctx)?)?;
+        let group = with_store(|db| {
+            let known = ScimUsers::existing(db, &fields.members)?;
+            if let Some(unknown) = fields.members.iter().find(|m| !known.contains(*m)) {
+                return Err(ScimError::invalid_value(format!(
+                    "unknown member `{unknown}`"
+                )));
+            }
+            let id = ScimGroups::insert(db, &fields.display_name, fields.external_id.as_deref())?;
+            config.sync_members(db, &id, &fields.display_name, &fields.members)?;
+            config.group(db, &id)
+        })?;
+        Ok(created(config, "Groups", group))
+    })
+}
+
+fn get_group(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let params = ListParams::from_ctx(ctx)?;
+        let group = with_store(|db| config.group(db, &id_param(ctx)))?;
+        let group = project(
+            group,
+            params.attributes.as_deref(),
+            params.excluded_attributes.as_deref(),
+        )?;
+        Ok(scim_response(200, &group))
+    })
+}
+
+/// Saves `fields` over the group `id`: renames, then syncs members.
+fn save_group(
+    config: &ScimModule,
+    db: &mut dyn Executor,
+    id: &str,
+    fields: &GroupFields,
+) -> Result<Value, ScimError> {
+    ScimGroups::update(
+        db,
+        &config.actor,
+        id,
+        &fields.display_name,
+        fields.external_id.as_deref(),
+    )?;
+    config.sync_members(db, id, &fields.display_name, &fields.members)?;
+    config.group(db, id)
+}
+
+fn replace_group(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        let fields = GroupFields::from_attributes(&attributes_of(ctx)?)?;
+        let group = with_store(|db| {
+            if ScimGroups::find(db, &id)?.is_none() {
+                return Err(ScimError::not_found("Group"));
+            }
+            save_group(config, db, &id, &fields)
+        })?;
+        Ok(scim_response(200, &group))
+    })
+}
+
+fn patch_group(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        let patch: PatchRequest = body(ctx)?;
+        let group = with_store(|db| {
+            let group = ScimGroups::find(db, &id)?.ok_or_else(|| ScimError::not_found("Group"))?;
+            let members: Vec<Value> = ScimGroups::members(db, Some(&id))?
+                .remove(&id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(member, _)| json!({ "value": member }))
+                .collect();
+            let mut attributes = Map::new();
+            attributes.insert("displayName".into(), json!(group.display_name));
+            if let Some(external_id) = &group.external_id {
+                attributes.insert("externalId".into(), json!(external_id));
+            }
+            attributes.insert("members".into(), Value::Array(members));
+            apply_patch(&mut attributes, patch.operations)?;
+            save_group(config, db, &id, &GroupFields::from_attributes(&attributes)?)
+        })?;
+        Ok(scim_response(200, &group))
+    })
+}
+
+fn delete_group(ctx: Context) -> Response {
+    handle(&ctx, |config, ctx| {
+        let id = id_param(ctx);
+        match with_store(|db| Ok(ScimGroups::delete(db, &config.actor, &id)?))? {
+            true => Ok(Response::new(204)),
+            false => Err(ScimError::not_found("Group")),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> Value {
+        json!({
+            "id": "2819c223",
+            "externalId": "Ext-1",
+            "userName": "Bjensen@example.com",
+            "name": { "givenName": "Barbara", "familyName": "Jensen" },
+            "active": true,
+            "emails": [
+                { "value": "bjensen@example.com", "type": "work", "primary": true },
+                { "value": "babs@jensen.org", "type": "home" }
+            ],
+            "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": {
+                "employeeNumber": "701984",
+                "manager": { "value": "26118915" }
+            },
+            "meta": { "lastModified": "2024-05-13T04:42:34Z" }
+        })
+    }
+
+    fn matches(filter: &str) -> bool {
+        Filter::parse(filter).unwrap().matches(&user())
+    }
+
+    fn patched(doc: Value, ops: Value) -> Result<Value, ScimError> {
+        let Value::Object(mut doc) = doc else {
+            unreachable!()
+        };
+        let patch: PatchRequest = serde_json::from_value(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": ops,
+        }))
+        .unwrap();
+        apply_patch(&mut doc, patch.operations)?;
+        Ok(Value::Object(doc))
+    }
+
+    #[test]
+    fn test_filter_operators() {
+        assert!(matches(r#"userName eq "bjensen@example.com""#));
+        assert!(matches(r#"USERNAME Eq "BJENSEN@EXAMPLE.COM""#));
+        assert!(!matches(r#"externalId eq "ext-1""#));
+        assert!(matches(r#"externalId eq "Ext-1""#));
+        assert!(matches(r#"name.familyName co "ens""#));
+        assert!(matches(r#"userName sw "bj" and userName ew ".com""#));
+        assert!(matches(r#"title pr or name.givenName pr"#));
+        assert!(!matches(r#"title pr"#));
+        assert!(matches(r#"not (active eq false)"#));
+        assert!(matches(r#"meta.lastModified gt "2024-01-01T00:00:00Z""#));
+        assert!(matches(r#"emails eq "babs@jensen.org""#));
+        assert!(matches(r#"emails[type eq "work" and value co "example"]"#));
+        assert!(!matches(
+            r#"emails[type eq "work" and value co "jensen.org"]"#
+        ));
+        assert!(matches(
+            r#"userName ne "someone" and (emails.type eq "home")"#
+        ));
+        assert!(matches(
+            r#"urn:ietf:params:scim:schemas:core:2.0:User:name.givenName eq "barbara""#
+        ));
+        assert!(matches(
+            r#"urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:employeeNumber eq "701984""#
+        ));
+        assert!(matches(r#"title eq null"#));
+    }
+
+    #[test]
+    fn test_filter_errors_and_pushdown() {
+        for bad in [
+            "userName",
+            r#"userName eq "x"#,
+            r#"userName like "x""#,
+            r#"userName eq "x" and"#,
+            r#"emails[type eq "work""#,
+            r#"userName eq "x")"#,
+        ] {
+            let err = Filter::parse(bad).unwrap_err();
+            assert_eq!(err.scim_type, Some("invalidFilter"), "{bad}");
+        }
+        let columns = [("userName", "lower(user_name) = lower($1)")];
+        let filter = Filter::parse(r#"active eq true and userName eq "Alice""#).unwrap();
+        assert_eq!(
+            filter.pushdown(&columns),
+            Some(("lower(user_name) = lower($1)", "Alice"))
+        );
+        let filter = Filter::parse(r#"userName eq "a" or userName eq "b""#).unwrap();
+        assert_eq!(filter.pushdown(&columns), None);
+    }
+
+    #[test]
+    fn test_patch_paths() {
+        let doc = patched(
+            user(),
+            json!([
+                { "op": "Replace", "path": "name.givenName", "value": "Babs" },
+                { "op": "replace", "path": "emails[type eq \"work\"].value", "value": "b@x.com" },
+                { "op": "replace", "path": "phoneNumbers[type eq \"mobile\"].value", "value": "555" },
+                { "op": "remove", "path": "emails[type eq \"home\"]" },
+                { "op": "add", "path": "nickName", "value": "B" },
+                { "op": "remove", "path": "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User:manager" }
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            doc["name"],
+            json!({ "givenName": "Babs", "familyName": "Jensen" })
+        );
+        assert_eq!(
+            doc["emails"],
+            json!([{ "value": "b@x.com", "type": "work", "primary": true }])
+        );
+        assert_eq!(
+            doc["phoneNumbers"],
+            json!([{ "type": "mobile", "value": "555" }])
+        );
+        assert_eq!(doc["nickName"], json!("B"));
+        assert_eq!(
+            doc["urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"],
+            json!({ "employeeNumber": "701984" })
+        );
+    }
+
+    #[test]
+    fn test_patch_without_path_and_members() {
+        // Azure AD style.
+        let doc = patched(
+            user(),
+            json!([{ "op": "Replace", "value": {
+                "active": "False",
+                "name.familyName": "Smith",
+                "urn:ietf:params:scim:schemas:extension:enterprise:2.0:User": { "department": "R&D" }
+            }}]),
+        )
+        .unwrap();
+        assert_eq!(doc["active"], json!("False"));
+        assert_eq!(doc["name"]["familyName"], json!("Smith"));
+        assert_eq!(
+            doc["urn:ietf:params:scim:schemas:extension:enterprise:2.0:User"]["department"],
+            json!("R&D")
+        );
+
+        let group =
+            json!({ "displayName": "eng", "members": [{ "value": "a" }, { "value": "b" }] });
+        let doc = patched(
+            group.clone(),
+            json!([
+                { "op": "add", "path": "members", "value": [{ "value": "b" }, { "value": "c" }] },
+                { "op": "remove", "path": "members", "value": [{ "value": "a" }] },
+                { "op": "remove", "path": "members[value eq \"c\"]" }
+            ]),
+        )
+        .unwrap();
+        assert_eq!(doc["members"], json!([{ "value": "b" }]));
+
+        let err = patched(group.clone(), json!([{ "op": "remove" }])).unwrap_err();
+        assert_eq!(err.scim_type, Some("noTarget"));
+        let err = patched(group, json!([{ "op": "move", "path": "members" }])).unwrap_err();
+        assert_eq!(err.scim_type, Some("invalidSyntax"));
+    }
+
+    #[test]
+    fn test_user_fields_and_projection() {
+        let Value::Object(attrs) = user() else {
+            unreachable!()
+        };
+        let mut attrs = attrs;
+        attrs.insert("password".into(), json!("hunter2"));
+        attrs.insert("active".into(), json!("False"));
+        let fields = UserFields::from_attributes(attrs).unwrap();
+        assert!(!fields.active);
+        assert_eq!(fields.external_id.as_deref(), Some("Ext-1"));
+        for dropped in ["id", "meta", "password"] {
+            assert!(!fields.attributes.contains_key(dropped));
+        }
+        assert!(UserFields::from_attributes(Map::new()).is_err());
+
+        let projected =
+            project(user(), Some("userName,emails.value,name.givenName"), None).unwrap();
+        assert_eq!(
+            projected,
+            json!({
+                "id": "2819c223",
+                "userName": "Bjensen@example.com",
+                "name": { "givenName": "Barbara" },
+                "emails": [{ "value": "bjensen@example.com" }, { "value": "babs@jensen.org" }]
+            })
+        );
+        let excluded = project(user(), None, Some("emails,name.familyName,id")).unwrap();
+        assert!(excluded.get("emails").is_none());
+        assert_eq!(excluded["name"], json!({ "givenName": "Barbara" }));
+        assert_eq!(excluded["id"], json!("2819c223"));
+
+        let id = new_id();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id[14..15], "4");
+    }
+}
@@ -0,0 +1,614 @@
+// src/service_account.rs
+//! Service accounts: non-human principals for service-to-service calls.
+//!
+//! A [`ServiceAccount`] has a generated `client_id`/`client_secret` pair,
+//! the scopes it may request and optional RBAC roles. Internal services
+//! exchange their credentials for a short-lived token at a client
+//! credentials endpoint (RFC 6749 §4.4), or an operator issues a long-lived
+//! token once with [`ServiceAccounts::issue`]:
+//!
+//! ```rust,ignore
+//! use chopin_auth::{ServiceAccounts, ServiceClaims, init_service_accounts, service_accounts};
+//!
+//! let accounts = ServiceAccounts::new().with_denylist(denylist.clone());
+//! let (billing, secret) = accounts.create("billing-worker", &["invoices:read", "invoices:write"]);
+//! init_service_accounts(accounts);
+//!
+//! // POST grant_type=client_credentials&client_id=..&client_secret=..&scope=invoices:read
+//! router.post("/oauth/token", |ctx| service_accounts().token_endpoint(&ctx));
+//!
+//! // Only service tokens are accepted here; `ServiceClaims` implements `ScopeCheck`.
+//! #[get("/internal/invoices")]
+//! #[authorize(ServiceClaims, |c| c.has_scope("invoices:read"))]
+//! fn invoices(ctx: Context) -> Response { .. }
+//! ```
+//!
+//! Tokens carry `sub` and `client_id` (both the client id, as in RFC 9068),
+//! a space-separated `scope`, and the account's roles under the
+//! [`RbacService`](crate::RbacService) claim, so `#[role_required]`,
+//! [`require_scope_middleware!`](crate::require_scope_middleware) and
+//! `#[authorize]` treat them like user tokens. Disabling an account revokes
+//! every token issued to it when a [`TokenDenylist`] is attached.
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::extractor::{Auth, GLOBAL_JWT_MANAGER};
+use crate::jwks::base64url_decode;
+use crate::jwt::{AuthError, HasJti, JwtManager};
+use crate::middleware::ScopeCheck;
+use crate::oauth::{constant_time_eq, random_token, sha256};
+use crate::rbac::rbac;
+use crate::revocation::TokenDenylist;
+use chopin_core::extract::FromRequest;
+use chopin_core::http::{Context, Response};
+use serde::{Deserialize, Serialize};
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Secrets are 256 random bits, so a plain digest is enough; a slow
+/// password hash would only make every token request expensive.
+fn secret_digest(secret: &str) -> [u8; 32] {
+    sha256(secret.as_bytes())
+}
+
+// ─── Accounts ────────────────────────────────────────────────────────────────
+
+/// A non-human principal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServiceAccount {
+    pub client_id: String,
+    /// Human-readable label, e.g. `billing-worker`.
+    pub name: String,
+    /// Scopes the account may request.
+    pub scopes: Vec<String>,
+    /// Roles put into its tokens for `#[role_required]`.
+    pub roles: Vec<String>,
+    /// SHA-256 of the client secret.
+    pub secret_digest: [u8; 32],
+    pub disabled: bool,
+}
+
+impl ServiceAccount {
+    /// Whether `secret` is this account's client secret.
+    pub fn verify_secret(&self, secret: &str) -> bool {
+        constant_time_eq(&secret_digest(secret), &self.secret_digest)
+    }
+
+    /// Whether the account may request `scope`.
+    pub fn allows(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope)
+    }
+}
+
+/// Storage behind [`ServiceAccounts`]. Implement it over a database table
+/// to share accounts between processes.
+pub trait ServiceAccountStore: Send + Sync {
+    fn get(&self, client_id: &str) -> Option<ServiceAccount>;
+
+    /// Insert or replace the account with the same `client_id`.
+    fn put(&self, account: ServiceAccount);
+
+    fn list(&self) -> Vec<ServiceAccount>;
+}
+
+/// In-process [`ServiceAccountStore`]. Cloning shares the accounts.
+#[derive(Clone, Default)]
+pub struct MemoryServiceAccountStore {
+    accounts: Arc<RwLock<HashMap<String, ServiceAccount>>>,
+}
+
+impl MemoryServiceAccountStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ServiceAccountStore for MemoryServiceAccountStore {
+    fn get(&self, client_id: &str) -> Option<ServiceAccount> {
+        self.accounts.read().ok()?.get(client_id).cloned()
+    }
+
+    fn put(&self, account: ServiceAccount) {
+        if let Ok(mut lock) = self.accounts.write() {
+            lock.insert(account.client_id.clone(), account);
+        }
+    }
+
+    fn list(&self) -> Vec<ServiceAccount> {
+        self.accounts
+            .read()
+            .map(|l| l.values().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+// ─── Errors ──────────────────────────────────────────────────────────────────
+
+/// Why a token request was refused.
+#[derive(Debug)]
+pub enum ServiceAccountError {
+    /// Unknown client, wrong secret or disabled account.
+    InvalidClient,
+    /// A requested scope is not granted to the account.
+    InvalidScope(String),
+    /// The grant type is not `client_credentials`.
+    UnsupportedGrantType,
+    /// The request is missing credentials or is malformed.
+    InvalidRequest,
+    /// Signing the token failed.
+    Token(AuthError),
+}
+
+impl ServiceAccountError {
+    /// The RFC 6749 §5.2 `error` code.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::InvalidClient => "invalid_client",
+            Self::InvalidScope(_) => "invalid_scope",
+            Self::UnsupportedGrantType => "unsupported_grant_type",
+            Self::InvalidRequest => "invalid_request",
+            Self::Token(_) => "server_error",
+        }
+    }
+
+    /// The token endpoint's response for this error.
+    pub fn to_response(&self) -> Response {
+        let body = serde_json::json!({ "error": self.code() }).to_string();
+        let mut res = Response::json_bytes(body);
+        match self {
+            Self::InvalidClient => {
+                res.status = 401;
+                res = res.with_header("WWW-Authenticate", "Basic realm=\"token\"");
+            }
+            Self::Token(_) => res.status = 500,
+            _ => res.status = 400,
+        }
+        res
+    }
+}
+
+impl fmt::Display for ServiceAccountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidScope(scope) => write!(f, "invalid_scope: {scope}"),
+            Self::Token(e) => write!(f, "server_error: {e}"),
+            _ => f.write_str(self.code()),
+        }
+    }
+}
+
+impl std::error::Error for ServiceAccountError {}
+
+// ─── Tokens ──────────────────────────────────────────────────────────────────
+
+/// Claims of a service-account token. As an extractor it only admits
+/// service tokens: user tokens lack `client_id` and get `401`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceClaims {
+    pub sub: String,
+    pub client_id: String,
+    /// Space-separated granted scopes.
+    #[serde(default)]
+    pub scope: String,
+    pub iat: u64,
+    pub exp: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+impl ServiceClaims {
+    /// The granted scopes.
+    pub fn scopes(&self) -> impl Iterator<Item = &str> {
+        self.scope.split_ascii_whitespace()
+    }
+
+    /// Whether `scope` was granted.
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes().any(|s| s == scope)
+    }
+}
+
+impl ScopeCheck for ServiceClaims {
+    fn has_scope(&self, scope: &str) -> bool {
+        ServiceClaims::has_scope(self, scope)
+    }
+}
+
+impl HasJti for ServiceClaims {
+    fn jti(&self) -> Option<&str> {
+        self.jti.as_deref()
+    }
+}
+
+impl<'a> FromRequest<'a> for ServiceClaims {
+    type Error = Response;
+
+    #[allow(clippy::result_large_err)]
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        Auth::<ServiceClaims>::from_request(ctx).map(|auth| auth.claims)
+    }
+}
+
+/// A successful token response (RFC 6749 §5.1). There is no refresh token:
+/// clients simply authenticate again.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ServiceToken {
+    pub access_token: String,
+    pub token_type: &'static str,
+    pub expires_in: u64,
+    pub scope: String,
+}
+
+#[derive(Deserialize)]
+struct TokenRequest {
+    grant_type: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    scope: Option<String>,
+}
+
+// ─── Registry ────────────────────────────────────────────────────────────────
+
+/// Creates service accounts, checks their credentials and issues tokens.
+#[derive(Clone)]
+pub struct ServiceAccounts {
+    store: Arc<dyn ServiceAccountStore>,
+    token_ttl: u64,
+    denylist: Option<TokenDenylist>,
+}
+
+impl Default for ServiceAccounts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ServiceAccounts {
+    /// In-memory accounts issuing one-hour tokens.
+    pub fn new() -> Self {
+        Self::with_store(MemoryServiceAccountStore::new())
+    }
+
+    /// Accounts kept in `store`.
+    pub fn with_store(store: impl ServiceAccountStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            token_ttl: 3_600,
+            denylist: None,
+        }
+    }
+
+    /// Lifetime of tokens from the client credentials grant.
+    pub fn with_token_ttl(mut self, ttl: Duration) -> Self {
+        self.token_ttl = ttl.as_secs();
+        self
+    }
+
+    /// Revoke outstanding tokens through `denylist` when an account is
+    /// disabled or its secret rotated. Attach the same denylist to the
+    /// [`JwtManager`].
+    pub fn with_denylist(mut self, denylist: TokenDenylist) -> Self {
+        self.denylist = Some(denylist);
+        self
+    }
+
+    /// Register an account allowed to request `scopes`. Returns the account
+    /// and its client secret, which is not stored and cannot be shown again.
+    pub fn create(&self, name: &str, scopes: &[&str]) -> (ServiceAccount, String) {
+        let secret = random_token(32);
+        let account = ServiceAccount {
+            client_id: format!("svc_{}", random_token(12)),
+            name: name.to_string(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            roles: Vec::new(),
+            secret_digest: secret_digest(&secret),
+            disabled: false,
+        };
+        self.store.put(account.clone());
+        (account, secret)
+    }
+
+    pub fn get(&self, client_id: &str) -> Option<ServiceAccount> {
+        self.store.get(client_id)
+    }
+
+    pub fn list(&self) -> Vec<ServiceAccount> {
+        self.store.list()
+    }
+
+    /// Set the roles put into the account's future tokens.
+    pub fn set_roles(&self, client_id: &str, roles: &[&str]) -> bool {
+        self.update(client_id, |account| {
+            account.roles = roles.iter().map(|r| r.to_string()).collect();
+        })
+    }
+
+    /// Replace the client secret and revoke tokens issued so far. Returns
+    /// the new secret, or `None` for an unknown client.
+    pub fn rotate_secret(&self, client_id: &str) -> Option<String> {
+        let secret = random_token(32);
+        let digest = secret_digest(&secret);
+        self.update(client_id, |account| account.secret_digest = digest)
+            .then(|| {
+                self.revoke_tokens(client_id);
+                secret
+            })
+    }
+
+    /// Refuse new tokens for the account and revoke tokens issued so far.
+    pub fn disable(&self, client_id: &str) -> bool {
+        let found = self.update(client_id, |account| account.disabled = true);
+        if found {
+            self.revoke_tokens(client_id);
+        }
+        found
+    }
+
+    /// Allow the account to request tokens again.
+    pub fn enable(&self, client_id: &str) -> bool {
+        self.update(client_id, |account| account.disabled = false)
+    }
+
+    fn update(&self, client_id: &str, f: impl FnOnce(&mut ServiceAccount)) -> bool {
+        let Some(mut account) = self.store.get(client_id) else {
+            return false;
+        };
+        f(&mut account);
+        self.store.put(account);
+        true
+    }
+
+    fn revoke_tokens(&self, client_id: &str) {
+        if let Some(denylist) = &self.denylist {
+            denylist.revoke_subject(client_id, None);
+        }
+    }
+
+    /// Check a client id and secret.
+    pub fn authenticate(
+        &self,
+        client_id: &str,
+        secret: &str,
+    ) -> Result<ServiceAccount, ServiceAccountError> {
+        match self.store.get(client_id) {
+            Some(account) if !account.disabled && account.verify_secret(secret) => Ok(account),
+            _ => Err(ServiceAccountError::InvalidClient),
+        }
+    }
+
+    /// Sign a token for `account`. `scope` narrows the granted scopes
+    /// (space-separated); `None` grants all of them. Use a long `ttl` for
+    /// tokens handed to services out of band.
+    pub fn issue(
+        &self,
+        manager: &JwtManager,
+        account: &ServiceAccount,
+        scope: Option<&str>,
+        ttl: Duration,
+    ) -> Result<ServiceToken, ServiceAccountError> {
+        if account.disabled {
+            return Err(ServiceAccountError::InvalidClient);
+        }
+        let scope = match scope {
+            Some(requested) => {
+                if let Some(denied) = requested
+                    .split_ascii_whitespace()
+                    .find(|s| !account.allows(s))
+                {
+                    return Err(ServiceAccountError::InvalidScope(denied.to_string()));
+                }
+                requested
+                    .split_ascii_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            }
+            None => account.scopes.join(" "),
+        };
+
+        let now = now_secs();
+        let claims = ServiceClaims {
+            sub: account.client_id.clone(),
+            client_id: account.client_id.clone(),
+            scope: scope.clone(),
+            iat: now,
+            exp: now + ttl.as_secs(),
+            jti: Some(random_token(16)),
+        };
+        let mut payload = match serde_json::to_value(&claims) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => return Err(ServiceAccountError::InvalidRequest),
+        };
+        if !account.roles.is_empty() {
+            payload.insert(rbac().claim().to_string(), account.roles.clone().into());
+        }
+        let access_token = manager
+            .encode(&payload)
+            .map_err(ServiceAccountError::Token)?;
+        Ok(ServiceToken {
+            access_token,
+            token_type: "Bearer",
+            expires_in: ttl.as_secs(),
+            scope,
+        })
+    }
+
+    /// The client credentials grant: authenticate and issue a token with
+    /// the configured lifetime.
+    pub fn client_credentials(
+        &self,
+        manager: &JwtManager,
+        client_id: &str,
+        secret: &str,
+        scope: Option<&str>,
+    ) -> Result<ServiceToken, ServiceAccountError> {
+        let account = self.authenticate(client_id, secret)?;
+        self.issue(
+            manager,
+            &account,
+            scope,
+            Duration::from_secs(self.token_ttl),
+        )
+    }
+
+    /// A token endpoint for the client credentials grant, signing with the
+    /// global [`JwtManager`].
+    ///
+    /// Reads an `application/x-www-form-urlencoded` body. Credentials come
+    /// from HTTP Basic auth or from `client_id`/`client_secret` form fields.
+    /// Errors are RFC 6749 JSON bodies: `401 invalid_client`, `400` for the
+    /// rest.
+    pub fn token_endpoint(&self, ctx: &Context) -> Response {
+        let Some(manager) = GLOBAL_JWT_MANAGER.get() else {
+            return Response::server_error();
+        };
+        match self.handle_token_request(manager, ctx) {
+            Ok(token) => match serde_json::to_vec(&token) {
+                Ok(body) => Response::json_bytes(body)
+                    .with_header("Cache-Control", "no-store")
+                    .with_header("Pragma", "no-cache"),
+                Err(_) => Response::server_error(),
+            },
+            Err(err) => err.to_response(),
+        }
+    }
+
+    fn handle_token_request(
+        &self,
+        manager: &JwtManager,
+        ctx: &Context,
+    ) -> Result<ServiceToken, ServiceAccountError> {
+        let form: TokenRequest = serde_urlencoded::from_bytes(ctx.req.body)
+            .map_err(|_| ServiceAccountError::InvalidRequest)?;
+        if form.grant_type.as_deref() != Some("client_credentials") {
+            return Err(ServiceAccountError::UnsupportedGrantType);
+        }
+        let (client_id, secret) = match basic_credentials(ctx) {
+            Some(credentials) => credentials,
+            None => match (form.client_id, form.client_secret) {
+                (Some(id), Some(secret)) => (id, secret),
+                _ => return Err(ServiceAccountError::InvalidClient),
+            },
+        };
+        self.client_credentials(manager, &client_id, &secret, form.scope.as_deref())
+    }
+}
+
+/// `Authorization: Basic base64(client_id:client_secret)`.
+pub(crate) fn basic_credentials(ctx: &Context) -> Option<(String, String)> {
+    let header = (0..ctx.req.header_count as usize).find_map(|i| {
+        let (k, v) = ctx.req.headers[i];
+        k.eq_ignore_ascii_case("Authorization").then_some(v)
+    })?;
+    let encoded = header.strip_prefix("Basic ")?;
+    let decoded = String::from_utf8(base64url_decode(encoded.trim())?).ok()?;
+    let (id, secret) = decoded.split_once(':')?;
+    Some((id.to_string(), secret.to_string()))
+}
+
+static GLOBAL_SERVICE_ACCOUNTS: OnceLock<ServiceAccounts> = OnceLock::new();
+
+/// Install the global [`ServiceAccounts`].
+///
+/// Call this **once** before starting the server. Panics if called more than once.
+pub fn init_service_accounts(accounts: ServiceAccounts) {
+    if GLOBAL_SERVICE_ACCOUNTS.set(accounts).is_err() {
+        panic!("ServiceAccounts already initialised — call init_service_accounts only once");
+    }
+}
+
+/// The global [`ServiceAccounts`], or an empty in-memory registry if
+/// [`init_service_accounts`] was never called.
+pub fn service_accounts() -> &'static ServiceAccounts {
+    GLOBAL_SERVICE_ACCOUNTS.get_or_init(ServiceAccounts::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manager() -> JwtManager {
+        JwtManager::new(b"service-account-test")
+    }
+
+    #[test]
+    fn test_authenticate_checks_secret_and_status() {
+        let accounts = ServiceAccounts::new();
+        let (account, secret) = accounts.create("worker", &["jobs:run"]);
+        assert!(account.client_id.starts_with("svc_"));
+        assert_eq!(
+            accounts.authenticate(&account.client_id, &secret).unwrap(),
+            account
+        );
+        assert!(matches!(
+            accounts.authenticate(&account.client_id, "nope"),
+            Err(ServiceAccountError::InvalidClient)
+        ));
+        assert!(accounts.authenticate("svc_unknown", &secret).is_err());
+
+        assert!(accounts.disable(&account.client_id));
+        assert!(accounts.authenticate(&account.client_id, &secret).is_err());
+        assert!(accounts.enable(&account.client_id));
+        assert!(accounts.authenticate(&account.client_id, &secret).is_ok());
+    }
+
+    #[test]
+    fn test_issue_narrows_scopes() {
+        let accounts = ServiceAccounts::new();
+        let (account, secret) = accounts.create("worker", &["a:read", "a:write"]);
+        let mgr = manager();
+
+        let token = accounts
+            .client_credentials(&mgr, &account.client_id, &secret, None)
+            .unwrap();
+        assert_eq!(
+            (token.scope.as_str(), token.expires_in),
+            ("a:read a:write", 3_600)
+        );
+
+        let token = accounts
+            .client_credentials(&mgr, &account.client_id, &secret, Some(" a:read "))
+            .unwrap();
+        let claims: ServiceClaims = mgr.decode(&token.access_token).unwrap();
+        assert_eq!(claims.sub, account.client_id);
+        assert!(claims.has_scope("a:read"));
+        assert!(!claims.has_scope("a:write"));
+
+        assert!(matches!(
+            accounts.client_credentials(&mgr, &account.client_id, &secret, Some("a:read b:admin")),
+            Err(ServiceAccountError::InvalidScope(scope)) if scope == "b:admin"
+        ));
+    }
+
+    #[test]
+    fn test_rotate_secret() {
+        let accounts = ServiceAccounts::new();
+        let (account, old) = accounts.create("worker", &[]);
+        let new = accounts.rotate_secret(&account.client_id).unwrap();
+        assert!(accounts.authenticate(&account.client_id, &old).is_err());
+        assert!(accounts.authenticate(&account.client_id, &new).is_ok());
+        assert_eq!(accounts.rotate_secret("svc_unknown"), None);
+    }
+
+    #[test]
+    fn test_error_responses() {
+        let res = ServiceAccountError::InvalidClient.to_response();
+        assert_eq!(res.status, 401);
+        assert_eq!(
+            ServiceAccountError::InvalidScope("x".into())
+                .to_response()
+                .status,
+            400
+        );
+        assert_eq!(
+            ServiceAccountError::UnsupportedGrantType.code(),
+            "unsupported_grant_type"
+        );
+    }
+}
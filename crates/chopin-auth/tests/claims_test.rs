@@ -0,0 +1,77 @@
+use chopin_auth::{Claims, JwtManager, init_jwt_manager};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router, authorize, get};
+use serde::{Deserialize, Serialize};
+use std::sync::Once;
+use std::time::Duration;
+
+const SECRET: &[u8] = b"claims-test";
+
+#[derive(Serialize, Deserialize)]
+struct Tenant {
+    tenant_id: u64,
+    plan: String,
+}
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| init_jwt_manager(JwtManager::new(SECRET)));
+}
+
+fn token(plan: &str) -> String {
+    let claims = Claims::new(
+        "u1",
+        Duration::from_secs(300),
+        Tenant {
+            tenant_id: 42,
+            plan: plan.to_string(),
+        },
+    );
+    JwtManager::new(SECRET).encode(&claims).unwrap()
+}
+
+#[get("/claims/reports/:id")]
+#[authorize(Claims<Tenant>, |c| c.plan == "pro")]
+fn reports(_ctx: Context, id: u32, claims: Claims<Tenant>) -> Response {
+    Response::text(format!(
+        "{id} tenant={} sub={}",
+        claims.tenant_id, claims.sub
+    ))
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.get("/claims/reports/:id", reports);
+    TestApp::new(router)
+}
+
+fn get_with(app: &TestApp, token: Option<String>) -> (u16, String) {
+    let bearer = token.map(|t| format!("Bearer {t}"));
+    let headers: Vec<(&str, &str)> = bearer
+        .iter()
+        .map(|b| ("Authorization", b.as_str()))
+        .collect();
+    let res = app.request(Method::Get, "/claims/reports/3", &headers, b"");
+    (res.status, res.text())
+}
+
+#[test]
+fn test_guard_reads_custom_claims() {
+    let app = app();
+    assert_eq!(
+        get_with(&app, Some(token("pro"))),
+        (200, "3 tenant=42 sub=u1".to_string())
+    );
+    assert_eq!(get_with(&app, Some(token("free"))).0, 403);
+    assert_eq!(get_with(&app, None).0, 401);
+}
+
+#[test]
+fn test_missing_custom_section_is_unauthorized() {
+    let app = app();
+    let bare = JwtManager::new(SECRET)
+        .encode(&Claims::new("u1", Duration::from_secs(300), ()))
+        .unwrap();
+    assert_eq!(get_with(&app, Some(bare)).0, 401);
+}
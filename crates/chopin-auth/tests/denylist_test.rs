@@ -0,0 +1,98 @@
+use chopin_auth::{Auth, HasJti, JwtManager, TokenDenylist, init_jwt_manager};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECRET: &[u8] = b"denylist-test";
+
+/// Claims without `jti`: the denylist must not depend on `HasJti`.
+#[derive(Deserialize)]
+struct Claims {
+    sub: String,
+    sid: String,
+}
+
+impl HasJti for Claims {}
+
+fn denylist() -> &'static TokenDenylist {
+    static DENYLIST: OnceLock<TokenDenylist> = OnceLock::new();
+    DENYLIST.get_or_init(|| {
+        let denylist = TokenDenylist::new();
+        init_jwt_manager(JwtManager::new(SECRET).with_denylist(denylist.clone()));
+        denylist
+    })
+}
+
+fn token(sub: &str, sid: &str, iat: u64) -> String {
+    let claims = json!({ "sub": sub, "sid": sid, "iat": iat, "exp": 253_370_764_800_u64 });
+    JwtManager::new(SECRET).encode(&claims).unwrap()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn me(ctx: Context) -> Response {
+    match ctx.extract::<Auth<Claims>>() {
+        Ok(Auth { claims }) => Response::text(format!("{} {}", claims.sub, claims.sid)),
+        Err(res) => res,
+    }
+}
+
+fn logout(ctx: Context) -> Response {
+    match ctx.extract::<Auth<Claims>>() {
+        Ok(Auth { claims }) => {
+            denylist().revoke_session(&claims.sid, None);
+            Response::text("bye")
+        }
+        Err(res) => res,
+    }
+}
+
+fn app() -> TestApp {
+    denylist();
+    let mut router = Router::new();
+    router.get("/me", me);
+    router.post("/logout", logout);
+    TestApp::new(router)
+}
+
+fn call(app: &TestApp, method: Method, path: &str, token: &str) -> u16 {
+    let bearer = format!("Bearer {token}");
+    app.request(method, path, &[("Authorization", bearer.as_str())], b"")
+        .status
+}
+
+#[test]
+fn test_logout_invalidates_session_tokens() {
+    let app = app();
+    let phone = token("alice", "phone", now());
+    let laptop = token("alice", "laptop", now());
+    assert_eq!(call(&app, Method::Get, "/me", &phone), 200);
+    assert_eq!(call(&app, Method::Post, "/logout", &phone), 200);
+    assert_eq!(call(&app, Method::Get, "/me", &phone), 401);
+    assert_eq!(call(&app, Method::Get, "/me", &laptop), 200);
+}
+
+#[test]
+fn test_logout_everywhere_keeps_later_logins() {
+    let app = app();
+    let old_a = token("bob", "a", now() - 60);
+    let old_b = token("bob", "b", now() - 30);
+    assert_eq!(call(&app, Method::Get, "/me", &old_b), 200);
+
+    // e.g. after a password reset
+    denylist().revoke_subject("bob", Some(now() + 3_600));
+    assert_eq!(call(&app, Method::Get, "/me", &old_a), 401);
+    assert_eq!(call(&app, Method::Get, "/me", &old_b), 401);
+
+    let fresh = token("bob", "c", now() + 1);
+    assert_eq!(call(&app, Method::Get, "/me", &fresh), 200);
+    assert_eq!(call(&app, Method::Get, "/me", &token("carol", "a", 0)), 200);
+}
@@ -0,0 +1,107 @@
+use chopin_auth::{LoginShield, ProofOfWork, init_login_shield, login_shield};
+use chopin_core::testing::{TestApp, TestResponse};
+use chopin_core::{Context, Method, Response, Router};
+use std::sync::Once;
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        init_login_shield(
+            LoginShield::new(2, 4, 900).with_provider(ProofOfWork::new(b"login-test", 6)),
+        );
+    });
+}
+
+/// `/login/:user/:password`; every user's password is `hunter2`.
+fn login(ctx: Context) -> Response {
+    let user = ctx.param("user").unwrap_or_default();
+    let shield = login_shield();
+    if let Err(res) = shield.check(&ctx, user) {
+        return res;
+    }
+    if ctx.param("password") != Some("hunter2") {
+        shield.failed(user);
+        return Response::unauthorized();
+    }
+    shield.succeeded(user);
+    Response::text("welcome")
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.post("/login/:user/:password", login);
+    TestApp::new(router)
+}
+
+fn header(res: &TestResponse, name: &str) -> Option<String> {
+    res.headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.clone())
+}
+
+/// The proof-of-work challenge from a `428` body.
+fn challenge(res: &TestResponse) -> String {
+    assert_eq!(res.status, 428);
+    let body: serde_json::Value = serde_json::from_str(&res.text()).unwrap();
+    assert_eq!(body["type"], "pow");
+    assert_eq!(body["difficulty"], "6");
+    body["challenge"].as_str().unwrap().to_string()
+}
+
+#[test]
+fn test_challenge_after_repeated_failures() {
+    let app = app();
+    let post = |path: &str, headers: &[(&str, &str)]| app.request(Method::Post, path, headers, b"");
+
+    assert_eq!(post("/login/alice/wrong", &[]).status, 401);
+    assert_eq!(post("/login/alice/wrong", &[]).status, 401);
+
+    // Threshold reached: even the right password needs a solved challenge.
+    let res = post("/login/alice/hunter2", &[]);
+    assert!(
+        header(&res, "X-Challenge")
+            .unwrap()
+            .starts_with("pow challenge=\"")
+    );
+    let pow = challenge(&res);
+    let res = post(
+        "/login/alice/hunter2",
+        &[("X-Challenge-Response", "nope:0")],
+    );
+    challenge(&res);
+
+    let answer = ProofOfWork::solve(&pow).unwrap();
+    let res = post("/login/alice/hunter2", &[("X-Challenge-Response", &answer)]);
+    assert_eq!((res.status, res.text()), (200, "welcome".to_string()));
+
+    // Other keys are unaffected.
+    assert_eq!(post("/login/bob/hunter2", &[]).status, 200);
+}
+
+#[test]
+fn test_refused_at_max_attempts() {
+    let app = app();
+    let post = |path: &str, headers: &[(&str, &str)]| app.request(Method::Post, path, headers, b"");
+
+    for _ in 0..2 {
+        assert_eq!(post("/login/carol/wrong", &[]).status, 401);
+    }
+    // Solving challenges keeps guessing possible, but slow and bounded.
+    let answer = ProofOfWork::solve(&challenge(&post("/login/carol/wrong", &[]))).unwrap();
+    let res = post("/login/carol/wrong", &[("X-Challenge-Response", &answer)]);
+    assert_eq!(res.status, 401);
+    // Answers are single-use.
+    let res = post("/login/carol/wrong", &[("X-Challenge-Response", &answer)]);
+    challenge(&res);
+
+    let answer = ProofOfWork::solve(&challenge(&res)).unwrap();
+    let res = post("/login/carol/wrong", &[("X-Challenge-Response", &answer)]);
+    assert_eq!(res.status, 401);
+
+    let res = post("/login/carol/hunter2", &[]);
+    assert_eq!(res.status, 429);
+    assert!(header(&res, "Retry-After").unwrap().parse::<u64>().unwrap() > 0);
+    assert_eq!(login_shield().failures("carol"), 4);
+}
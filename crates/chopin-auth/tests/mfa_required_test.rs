@@ -0,0 +1,117 @@
+use chopin_auth::{
+    JwtManager, MfaService, MfaVerified, init_jwt_manager, init_mfa, mfa, mfa_required,
+    role_required,
+};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router, delete, put};
+use serde_json::json;
+use std::sync::Once;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECRET: &[u8] = b"mfa-required-test";
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        init_jwt_manager(JwtManager::new(SECRET));
+        init_mfa(MfaService::new().with_window(Duration::from_secs(300)));
+    });
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn token(sid: Option<&str>) -> String {
+    let mut claims = json!({ "sub": "u1", "exp": 253_370_764_800_u64, "roles": "admin" });
+    if let Some(sid) = sid {
+        claims["sid"] = json!(sid);
+    }
+    JwtManager::new(SECRET).encode(&claims).unwrap()
+}
+
+#[delete("/mfa/account")]
+#[mfa_required]
+fn delete_account(_ctx: Context) -> Response {
+    Response::text("deleted")
+}
+
+#[mfa_required(max_age = 60)]
+#[role_required("admin")]
+#[put("/mfa/payouts/:id")]
+fn update_payouts(_ctx: Context, id: u32, mfa: MfaVerified) -> Response {
+    Response::text(format!("{id} {}", mfa.session))
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.delete("/mfa/account", delete_account);
+    router.put("/mfa/payouts/:id", update_payouts);
+    TestApp::new(router)
+}
+
+fn call(app: &TestApp, method: Method, path: &str, sid: Option<&str>) -> (u16, String, String) {
+    let bearer = format!("Bearer {}", token(sid));
+    let res = app.request(method, path, &[("Authorization", bearer.as_str())], b"");
+    let challenge = res
+        .headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("WWW-Authenticate"))
+        .map(|(_, v)| v.to_string())
+        .unwrap_or_default();
+    (res.status, res.text(), challenge)
+}
+
+#[test]
+fn test_requires_fresh_verification() {
+    let app = app();
+    let res = app.request(Method::Delete, "/mfa/account", &[], b"");
+    assert_eq!(res.status, 401);
+
+    let (status, _, challenge) = call(&app, Method::Delete, "/mfa/account", Some("s-fresh"));
+    assert_eq!(status, 401);
+    assert_eq!(
+        challenge,
+        r#"Bearer error="insufficient_user_authentication", max_age=300"#
+    );
+
+    mfa().record("s-fresh");
+    let (status, body, _) = call(&app, Method::Delete, "/mfa/account", Some("s-fresh"));
+    assert_eq!((status, body.as_str()), (200, "deleted"));
+
+    // Another session of the same user is not stepped up.
+    assert_eq!(
+        call(&app, Method::Delete, "/mfa/account", Some("s-other")).0,
+        401
+    );
+    // Tokens without a session claim can never satisfy the check.
+    assert_eq!(call(&app, Method::Delete, "/mfa/account", None).0, 401);
+}
+
+#[test]
+fn test_max_age_narrows_window_and_injects() {
+    let app = app();
+    mfa().record_at("s-aging", now() - 120);
+    assert_eq!(
+        call(&app, Method::Delete, "/mfa/account", Some("s-aging")).0,
+        200
+    );
+
+    let (status, _, challenge) = call(&app, Method::Put, "/mfa/payouts/7", Some("s-aging"));
+    assert_eq!(status, 401);
+    assert!(challenge.ends_with("max_age=60"), "{challenge}");
+
+    mfa().record("s-aging");
+    let (status, body, _) = call(&app, Method::Put, "/mfa/payouts/7", Some("s-aging"));
+    assert_eq!((status, body.as_str()), (200, "7 s-aging"));
+
+    mfa().clear("s-aging");
+    assert_eq!(
+        call(&app, Method::Put, "/mfa/payouts/7", Some("s-aging")).0,
+        401
+    );
+}
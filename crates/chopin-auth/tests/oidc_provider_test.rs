@@ -0,0 +1,370 @@
+use chopin_auth::code_challenge_s256;
+use chopin_auth::oidc::{OidcClient, OidcClients, OidcModule, SigningKey};
+use chopin_core::testing::{TestApp, TestResponse};
+use chopin_core::{ChopinModule, Context, Method, Router};
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde_json::{Map, Value, json};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+const ISSUER: &str = "https://id.example.com/oauth";
+const CALLBACK: &str = "https://wiki.example.com/callback";
+const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+
+struct Fixture {
+    app: TestApp,
+    wiki: OidcClient,
+    wiki_secret: String,
+}
+
+fn current_user(ctx: &Context) -> Option<String> {
+    ctx.header("X-Test-User").map(str::to_string)
+}
+
+fn profile(sub: &str, scopes: &[String]) -> Map<String, Value> {
+    let mut claims = Map::new();
+    if scopes.iter().any(|s| s == "email") {
+        claims.insert("email".into(), format!("{sub}@example.com").into());
+    }
+    claims
+}
+
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        let key = SigningKey::from_rsa_pem(include_bytes!("fixtures/oidc_rs256.pem")).unwrap();
+        let clients = OidcClients::new();
+        let (wiki, secret) = clients.register("Wiki", &[CALLBACK], true);
+        let module = OidcModule::new(ISSUER, key, clients)
+            .current_user(current_user)
+            .claims(profile)
+            .dynamic_registration(|ctx| ctx.header("Authorization") == Some("Bearer reg"));
+        let mut router = Router::new();
+        module.routes(&mut router);
+        Fixture {
+            app: TestApp::new(router),
+            wiki,
+            wiki_secret: secret.unwrap(),
+        }
+    })
+}
+
+fn authorize_path(client_id: &str, extra: &[(&str, &str)]) -> String {
+    let challenge = code_challenge_s256(VERIFIER);
+    let mut pairs = vec![
+        ("response_type", "code"),
+        ("client_id", client_id),
+        ("redirect_uri", CALLBACK),
+        ("scope", "openid email"),
+        ("state", "s-123"),
+        ("nonce", "n-456"),
+        ("code_challenge", challenge.as_str()),
+        ("code_challenge_method", "S256"),
+    ];
+    for (k, v) in extra {
+        match pairs.iter_mut().find(|(name, _)| name == k) {
+            Some(pair) => pair.1 = v,
+            None => pairs.push((k, v)),
+        }
+    }
+    pairs.retain(|(_, v)| !v.is_empty());
+    format!(
+        "/oauth/authorize?{}",
+        serde_urlencoded::to_string(&pairs).unwrap()
+    )
+}
+
+fn location(res: &TestResponse) -> (String, HashMap<String, String>) {
+    assert_eq!(res.status, 303, "{}", res.text());
+    let loc = res.header("Location").unwrap();
+    let (base, query) = loc.split_once('?').unwrap_or((loc, ""));
+    (base.to_string(), serde_urlencoded::from_str(query).unwrap())
+}
+
+fn as_user(app: &TestApp, user: &str, method: Method, path: &str, body: &[u8]) -> TestResponse {
+    app.request(method, path, &[("X-Test-User", user)], body)
+}
+
+/// Run the consent form like a browser would: post the hidden fields back.
+fn consent(app: &TestApp, user: &str, page: &TestResponse, decision: &str) -> TestResponse {
+    let html = page.text();
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for input in html.split("<input ").skip(1) {
+        let attr = |name: &str| {
+            let start = input.find(&format!("{name}=\""))? + name.len() + 2;
+            let len = input[start..].find('"')?;
+            Some(input[start..start + len].replace("&amp;", "&"))
+        };
+        pairs.push((attr("name").unwrap(), attr("value").unwrap()));
+    }
+    pairs.push(("decision".into(), decision.into()));
+    let body = serde_urlencoded::to_string(&pairs).unwrap();
+    app.request(
+        Method::Post,
+        "/oauth/authorize",
+        &[
+            ("X-Test-User", user),
+            ("Content-Type", "application/x-www-form-urlencoded"),
+        ],
+        body.as_bytes(),
+    )
+}
+
+fn token(app: &TestApp, headers: &[(&str, &str)], form: &[(&str, &str)]) -> (u16, Value) {
+    let body = serde_urlencoded::to_string(form).unwrap();
+    let res = app.request(Method::Post, "/oauth/token", headers, body.as_bytes());
+    (res.status, serde_json::from_str(&res.text()).unwrap())
+}
+
+fn exchange(code: &str, client_id: &str, secret: Option<&str>) -> (u16, Value) {
+    let mut form = vec![
+        ("grant_type", "authorization_code"),
+        ("code", code),
+        ("redirect_uri", CALLBACK),
+        ("client_id", client_id),
+        ("code_verifier", VERIFIER),
+    ];
+    if let Some(secret) = secret {
+        form.push(("client_secret", secret));
+    }
+    token(&fixture().app, &[], &form)
+}
+
+fn decoding_key(app: &TestApp) -> (String, DecodingKey) {
+    let jwks: Value = serde_json::from_str(
+        &app.request(Method::Get, "/oauth/jwks.json", &[], b"")
+            .text(),
+    )
+    .unwrap();
+    let key = &jwks["keys"][0];
+    (
+        key["kid"].as_str().unwrap().to_string(),
+        DecodingKey::from_rsa_components(key["n"].as_str().unwrap(), key["e"].as_str().unwrap())
+            .unwrap(),
+    )
+}
+
+#[test]
+fn test_discovery_document_points_at_endpoints() {
+    let app = &fixture().app;
+    let res = app.request(
+        Method::Get,
+        "/oauth/.well-known/openid-configuration",
+        &[],
+        b"",
+    );
+    assert_eq!(res.status, 200);
+    let doc: Value = serde_json::from_str(&res.text()).unwrap();
+    assert_eq!(doc["issuer"], ISSUER);
+    assert_eq!(doc["authorization_endpoint"], format!("{ISSUER}/authorize"));
+    assert_eq!(doc["token_endpoint"], format!("{ISSUER}/token"));
+    assert_eq!(doc["jwks_uri"], format!("{ISSUER}/jwks.json"));
+    assert_eq!(doc["registration_endpoint"], format!("{ISSUER}/register"));
+    assert_eq!(doc["code_challenge_methods_supported"], json!(["S256"]));
+    assert_eq!(
+        doc["id_token_signing_alg_values_supported"],
+        json!(["RS256"])
+    );
+
+    let jwks: Value = serde_json::from_str(
+        &app.request(Method::Get, "/oauth/jwks.json", &[], b"")
+            .text(),
+    )
+    .unwrap();
+    assert_eq!(jwks["keys"][0]["kty"], "RSA");
+    assert_eq!(jwks["keys"][0]["alg"], "RS256");
+}
+
+#[test]
+fn test_authorization_code_flow_with_consent() {
+    let f = fixture();
+    let app = &f.app;
+    let path = authorize_path(&f.wiki.client_id, &[]);
+
+    // Not logged in: off to the login page, with a way back.
+    let (login, query) = location(&app.request(Method::Get, &path, &[], b""));
+    assert_eq!(login, "/login");
+    assert_eq!(query["next"], path);
+
+    let page = as_user(app, "alice", Method::Get, &path, b"");
+    assert_eq!(page.status, 200);
+    assert!(page.text().contains("Wiki wants to access your account"));
+
+    let (callback, query) = location(&consent(app, "alice", &page, "allow"));
+    assert_eq!(callback, CALLBACK);
+    assert_eq!(query["state"], "s-123");
+    assert_eq!(query["iss"], ISSUER);
+
+    let (status, tokens) = exchange(&query["code"], &f.wiki.client_id, Some(&f.wiki_secret));
+    assert_eq!(status, 200, "{tokens}");
+    assert_eq!(tokens["token_type"], "Bearer");
+    assert_eq!(tokens["scope"], "openid email");
+
+    let (kid, key) = decoding_key(app);
+    let mut validation = Validation::new(Algorithm::RS256);
+    validation.set_audience(&[&f.wiki.client_id]);
+    validation.set_issuer(&[ISSUER]);
+    let id_token = tokens["id_token"].as_str().unwrap();
+    assert_eq!(
+        jsonwebtoken::decode_header(id_token).unwrap().kid,
+        Some(kid)
+    );
+    let id = jsonwebtoken::decode::<Value>(id_token, &key, &validation)
+        .unwrap()
+        .claims;
+    assert_eq!(id["sub"], "alice");
+    assert_eq!(id["nonce"], "n-456");
+    assert_eq!(id["email"], "alice@example.com");
+
+    let bearer = format!("Bearer {}", tokens["access_token"].as_str().unwrap());
+    let res = app.request(
+        Method::Get,
+        "/oauth/userinfo",
+        &[("Authorization", &bearer)],
+        b"",
+    );
+    assert_eq!(res.status, 200);
+    let info: Value = serde_json::from_str(&res.text()).unwrap();
+    assert_eq!(
+        info,
+        json!({ "sub": "alice", "email": "alice@example.com" })
+    );
+
+    // Codes are single use.
+    let (status, err) = exchange(&query["code"], &f.wiki.client_id, Some(&f.wiki_secret));
+    assert_eq!(
+        (status, err["error"].as_str()),
+        (400, Some("invalid_grant"))
+    );
+
+    // Consent is remembered: straight back to the client next time.
+    let (callback, query) = location(&as_user(app, "alice", Method::Get, &path, b""));
+    assert_eq!(callback, CALLBACK);
+    assert!(query.contains_key("code"));
+}
+
+#[test]
+fn test_token_endpoint_checks_client_and_verifier() {
+    let f = fixture();
+    let app = &f.app;
+    let path = authorize_path(&f.wiki.client_id, &[("prompt", "consent")]);
+    let page = as_user(app, "bob", Method::Get, &path, b"");
+    let (_, query) = location(&consent(app, "bob", &page, "allow"));
+
+    // A caller without the secret cannot spend someone else's code.
+    let (status, err) = exchange(&query["code"], &f.wiki.client_id, Some("wrong"));
+    assert_eq!(
+        (status, err["error"].as_str()),
+        (401, Some("invalid_client"))
+    );
+
+    let (status, err) = token(
+        app,
+        &[],
+        &[
+            ("grant_type", "authorization_code"),
+            ("code", &query["code"]),
+            ("redirect_uri", CALLBACK),
+            ("client_id", &f.wiki.client_id),
+            ("client_secret", &f.wiki_secret),
+            (
+                "code_verifier",
+                "not-the-verifier-used-for-the-challenge-xyz",
+            ),
+        ],
+    );
+    assert_eq!(
+        (status, err["error"].as_str()),
+        (400, Some("invalid_grant"))
+    );
+
+    // A failed verifier burns the code.
+    let (status, err) = exchange(&query["code"], &f.wiki.client_id, Some(&f.wiki_secret));
+    assert_eq!(
+        (status, err["error"].as_str()),
+        (400, Some("invalid_grant"))
+    );
+
+    let (status, err) = token(app, &[], &[("grant_type", "password")]);
+    assert_eq!(
+        (status, err["error"].as_str()),
+        (400, Some("unsupported_grant_type"))
+    );
+}
+
+#[test]
+fn test_authorize_rejects_bad_requests() {
+    let f = fixture();
+    let app = &f.app;
+
+    // Unverified redirect targets get a page, never a redirect.
+    let path = authorize_path("unknown", &[]);
+    assert_eq!(as_user(app, "carol", Method::Get, &path, b"").status, 400);
+    let path = authorize_path(
+        &f.wiki.client_id,
+        &[("redirect_uri", "https://evil.example/cb")],
+    );
+    assert_eq!(as_user(app, "carol", Method::Get, &path, b"").status, 400);
+
+    let path = authorize_path(&f.wiki.client_id, &[("code_challenge", "")]);
+    let (callback, query) = location(&as_user(app, "carol", Method::Get, &path, b""));
+    assert_eq!(callback, CALLBACK);
+    assert_eq!(query["error"], "invalid_request");
+    assert_eq!(query["state"], "s-123");
+
+    let path = authorize_path(&f.wiki.client_id, &[("scope", "email")]);
+    let (_, query) = location(&as_user(app, "carol", Method::Get, &path, b""));
+    assert_eq!(query["error"], "invalid_scope");
+
+    let path = authorize_path(&f.wiki.client_id, &[("prompt", "none")]);
+    let (_, query) = location(&app.request(Method::Get, &path, &[], b""));
+    assert_eq!(query["error"], "login_required");
+    let (_, query) = location(&as_user(app, "carol", Method::Get, &path, b""));
+    assert_eq!(query["error"], "consent_required");
+
+    let path = authorize_path(&f.wiki.client_id, &[]);
+    let page = as_user(app, "carol", Method::Get, &path, b"");
+    let (_, query) = location(&consent(app, "carol", &page, "deny"));
+    assert_eq!(query["error"], "access_denied");
+    assert!(!query.contains_key("code"));
+}
+
+#[test]
+fn test_dynamic_registration_creates_public_client() {
+    let f = fixture();
+    let app = &f.app;
+    let register = |auth: &str, body: Value| {
+        app.request(
+            Method::Post,
+            "/oauth/register",
+            &[
+                ("Authorization", auth),
+                ("Content-Type", "application/json"),
+            ],
+            body.to_string().as_bytes(),
+        )
+    };
+
+    let body = json!({
+        "client_name": "CLI",
+        "redirect_uris": [CALLBACK],
+        "token_endpoint_auth_method": "none",
+    });
+    assert_eq!(register("Bearer nope", body.clone()).status, 403);
+    let bad = json!({ "redirect_uris": ["http://example.com/cb"] });
+    assert_eq!(register("Bearer reg", bad).status, 400);
+
+    let res = register("Bearer reg", body);
+    assert_eq!(res.status, 201, "{}", res.text());
+    let client: Value = serde_json::from_str(&res.text()).unwrap();
+    assert!(client.get("client_secret").is_none());
+    let client_id = client["client_id"].as_str().unwrap();
+
+    // Public clients prove themselves with PKCE alone.
+    let path = authorize_path(client_id, &[]);
+    let page = as_user(app, "dave", Method::Get, &path, b"");
+    assert!(page.text().contains("CLI wants to access"));
+    let (_, query) = location(&consent(app, "dave", &page, "allow"));
+    let (status, tokens) = exchange(&query["code"], client_id, None);
+    assert_eq!(status, 200, "{tokens}");
+}
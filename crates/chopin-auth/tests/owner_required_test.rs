@@ -0,0 +1,131 @@
+#![cfg(feature = "orm")]
+
+use chopin_auth::{JwtManager, RbacService, init_jwt_manager, init_rbac, owner_required};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router, put};
+use chopin_orm::{Executor, Model, OrmResult, mock_row};
+use chopin_pg::Row;
+use chopin_pg::types::{PgValue, ToSql};
+use serde_json::json;
+use std::sync::Once;
+
+const SECRET: &[u8] = b"owner-required-test";
+
+#[derive(Model, Debug, Clone)]
+#[model(table_name = "posts")]
+struct Post {
+    #[model(primary_key)]
+    id: i64,
+    author_id: i64,
+    title: String,
+}
+impl chopin_orm::Validate for Post {}
+
+/// Posts 1 (by user 10) and 2 (by user 20).
+struct Posts;
+
+impl Executor for Posts {
+    fn execute(&mut self, _query: &str, _params: &[&dyn ToSql]) -> OrmResult<u64> {
+        Ok(0)
+    }
+
+    fn query(&mut self, query: &str, params: &[&dyn ToSql]) -> OrmResult<Vec<Row>> {
+        assert!(query.contains("FROM posts WHERE id = $1"), "{query}");
+        let row = match params[0].to_sql() {
+            PgValue::Text(id) if id == "1" => {
+                mock_row!("id" => 1_i64, "author_id" => 10_i64, "title" => "first")
+            }
+            PgValue::Text(id) if id == "2" => {
+                mock_row!("id" => 2_i64, "author_id" => 20_i64, "title" => "second")
+            }
+            _ => return Ok(Vec::new()),
+        };
+        Ok(vec![row])
+    }
+}
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        init_jwt_manager(JwtManager::new(SECRET));
+        init_rbac(RbacService::hierarchy(&["admin", "moderator", "user"]));
+        chopin_core::db::init_database(|| Ok(Box::new(Posts))).ok();
+    });
+}
+
+#[put("/owner/posts/:id")]
+#[owner_required(Post, param = "id", field = "author_id", roles("moderator"))]
+fn update_post(_ctx: Context, post: Post) -> Response {
+    Response::text(format!("{} {}", post.id, post.title))
+}
+
+#[owner_required(Post, field = "author_id")]
+#[put("/owner/strict/:id")]
+fn strict(_ctx: Context) -> Response {
+    Response::text("ok")
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.put("/owner/posts/:id", update_post);
+    router.put("/owner/strict/:id", strict);
+    TestApp::new(router)
+}
+
+fn put_as(app: &TestApp, path: &str, claims: Option<serde_json::Value>) -> (u16, String) {
+    let bearer = claims.map(|c| format!("Bearer {}", JwtManager::new(SECRET).encode(&c).unwrap()));
+    let headers: Vec<(&str, &str)> = bearer
+        .iter()
+        .map(|b| ("Authorization", b.as_str()))
+        .collect();
+    let res = app.request(Method::Put, path, &headers, b"");
+    (res.status, res.text())
+}
+
+fn user(sub: serde_json::Value, roles: &[&str]) -> Option<serde_json::Value> {
+    Some(json!({ "sub": sub, "exp": 253_370_764_800_u64, "roles": roles }))
+}
+
+#[test]
+fn test_owner_gets_loaded_model() {
+    let app = app();
+    assert_eq!(
+        put_as(&app, "/owner/posts/1", user(json!("10"), &[])),
+        (200, "1 first".to_string())
+    );
+    // Numeric `sub` claims compare the same way.
+    assert_eq!(put_as(&app, "/owner/posts/2", user(json!(20), &[])).0, 200);
+}
+
+#[test]
+fn test_owner_rejections() {
+    let app = app();
+    assert_eq!(put_as(&app, "/owner/posts/1", None).0, 401);
+    assert_eq!(
+        put_as(&app, "/owner/posts/2", user(json!("10"), &[])).0,
+        403
+    );
+    assert_eq!(
+        put_as(&app, "/owner/posts/9", user(json!("10"), &[])).0,
+        404
+    );
+}
+
+#[test]
+fn test_bypass_roles_follow_hierarchy() {
+    let app = app();
+    let path = "/owner/posts/2";
+    assert_eq!(put_as(&app, path, user(json!("10"), &["user"])).0, 403);
+    assert_eq!(put_as(&app, path, user(json!("10"), &["moderator"])).0, 200);
+    assert_eq!(put_as(&app, path, user(json!("10"), &["admin"])).0, 200);
+    // No roles configured: only the owner passes.
+    assert_eq!(
+        put_as(&app, "/owner/strict/2", user(json!("10"), &["admin"])).0,
+        403
+    );
+    assert_eq!(
+        put_as(&app, "/owner/strict/2", user(json!("20"), &[])).0,
+        200
+    );
+}
@@ -0,0 +1,156 @@
+use chopin_auth::{PasswordConfig, PasswordPolicy, init_password_policy, password_policy};
+use chopin_core::testing::{TestApp, TestResponse};
+use chopin_core::{Context, Method, Response, Router};
+use serde_json::{Value, json};
+use std::sync::{Arc, Once};
+
+/// Every account's current password.
+const CURRENT: &str = "lantern-orbit-saffron";
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let cfg = PasswordConfig {
+            min_length: 10,
+            require_digit: true,
+            min_score: 3,
+            check_breached: true,
+            ..PasswordConfig::default()
+        };
+        // A range API that knows one breached password, "copper-violin-harbor-8",
+        // whose SHA-1 is DC00481E107AB5B93D38DF88711C0BBF21483DFC.
+        let get = Arc::new(|url: &str| match url {
+            "https://api.pwnedpasswords.com/range/DC004" => {
+                Ok("81E107AB5B93D38DF88711C0BBF21483DFC:3\r\n\
+                 81E2C5D0F83C9D21BD86F5AC9B1B6D03B27:0\r\n"
+                    .to_string())
+            }
+            _ => Ok(String::new()),
+        });
+        init_password_policy(PasswordPolicy::from_config(&cfg, Some(get)).unwrap());
+    });
+}
+
+fn form(ctx: &Context) -> Value {
+    serde_json::from_slice(ctx.req.body).unwrap_or_default()
+}
+
+fn field<'a>(form: &'a Value, name: &str) -> &'a str {
+    form[name].as_str().unwrap_or_default()
+}
+
+fn signup(ctx: Context) -> Response {
+    let form = form(&ctx);
+    match password_policy().check(field(&form, "password"), &[field(&form, "email")]) {
+        Ok(_) => Response::new(201),
+        Err(e) => e.response(),
+    }
+}
+
+fn reset(ctx: Context) -> Response {
+    let form = form(&ctx);
+    match password_policy().check(field(&form, "password"), &[]) {
+        Ok(_) => Response::new(204),
+        Err(e) => e.response(),
+    }
+}
+
+fn change(ctx: Context) -> Response {
+    let form = form(&ctx);
+    match password_policy().check_change(CURRENT, field(&form, "password"), &[]) {
+        Ok(_) => Response::new(204),
+        Err(e) => e.response(),
+    }
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.post("/signup", signup);
+    router.post("/reset", reset);
+    router.post("/account/password", change);
+    TestApp::new(router)
+}
+
+fn post(app: &TestApp, path: &str, body: Value) -> TestResponse {
+    app.request(Method::Post, path, &[], body.to_string().as_bytes())
+}
+
+fn codes(res: &TestResponse) -> Vec<String> {
+    assert_eq!(res.status, 422, "{}", res.text());
+    res.json().unwrap()["violations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|v| v["code"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[test]
+fn test_every_endpoint_enforces_the_same_policy() {
+    let app = app();
+    for path in ["/signup", "/reset", "/account/password"] {
+        let res = post(&app, path, json!({ "password": "password" }));
+        assert_eq!(
+            codes(&res),
+            ["too_short", "missing_digit", "too_weak"],
+            "{path}"
+        );
+        let body = res.json().unwrap();
+        assert_eq!(body["error"], "weak_password");
+        assert_eq!(body["violations"][0]["min"], 10);
+        assert_eq!(
+            body["strength"]["warning"],
+            "This is a top-10 common password."
+        );
+
+        let res = post(&app, path, json!({ "password": "ember-quartz-71-hollow" }));
+        assert!((200..300).contains(&res.status), "{path}: {}", res.text());
+    }
+}
+
+#[test]
+fn test_breached_password_is_refused() {
+    let app = app();
+    let res = post(
+        &app,
+        "/reset",
+        json!({ "password": "copper-violin-harbor-8" }),
+    );
+    assert_eq!(codes(&res), ["breached"]);
+    assert_eq!(res.json().unwrap()["violations"][0]["count"], 3);
+    let res = post(
+        &app,
+        "/reset",
+        json!({ "password": "copper-violin-harbor-9" }),
+    );
+    assert_eq!(res.status, 204, "{}", res.text());
+}
+
+#[test]
+fn test_signup_uses_the_account_details() {
+    let app = app();
+    let res = post(
+        &app,
+        "/signup",
+        json!({ "email": "Quintessa.Whitlock@example.com", "password": "whitlock-quintessa1" }),
+    );
+    assert_eq!(codes(&res), ["too_weak"]);
+    assert_eq!(
+        res.json().unwrap()["strength"]["warning"],
+        "This contains your personal details."
+    );
+    let res = post(
+        &app,
+        "/signup",
+        json!({ "email": "someone@example.com", "password": "whitlock-quintessa1" }),
+    );
+    assert_eq!(res.status, 201, "{}", res.text());
+}
+
+#[test]
+fn test_change_password_must_differ() {
+    let app = app();
+    let res = post(&app, "/account/password", json!({ "password": CURRENT }));
+    assert_eq!(codes(&res), ["same_as_current"]);
+}
@@ -0,0 +1,76 @@
+use chopin_auth::{JwtManager, RbacService, Roles, init_jwt_manager, init_rbac, role_required};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router, get};
+use serde_json::json;
+use std::sync::Once;
+
+const SECRET: &[u8] = b"role-required-test";
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        init_jwt_manager(JwtManager::new(SECRET));
+        init_rbac(RbacService::hierarchy(&["admin", "staff", "user"]));
+    });
+}
+
+fn token(roles: serde_json::Value) -> String {
+    let claims = json!({ "sub": "u1", "exp": 253_370_764_800_u64, "roles": roles });
+    JwtManager::new(SECRET).encode(&claims).unwrap()
+}
+
+#[get("/rbac/reports")]
+#[role_required("staff")]
+fn reports(_ctx: Context) -> Response {
+    Response::text("reports")
+}
+
+#[role_required("billing", "admin")]
+#[get("/rbac/invoices/:id")]
+fn invoice(_ctx: Context, id: u32, roles: Roles) -> Response {
+    Response::text(format!("{id} {}", roles.0.join(",")))
+}
+
+fn status(app: &TestApp, path: &str, roles: Option<serde_json::Value>) -> (u16, String) {
+    let bearer = roles.map(|r| format!("Bearer {}", token(r)));
+    let headers: Vec<(&str, &str)> = bearer
+        .iter()
+        .map(|b| ("Authorization", b.as_str()))
+        .collect();
+    let res = app.request(Method::Get, path, &headers, b"");
+    (res.status, res.text())
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.get("/rbac/reports", reports);
+    router.get("/rbac/invoices/:id", invoice);
+    TestApp::new(router)
+}
+
+#[test]
+fn test_role_hierarchy_admits_higher_roles() {
+    let app = app();
+    assert_eq!(status(&app, "/rbac/reports", None).0, 401);
+    assert_eq!(status(&app, "/rbac/reports", Some(json!("user"))).0, 403);
+    assert_eq!(status(&app, "/rbac/reports", Some(json!("staff"))).0, 200);
+    assert_eq!(status(&app, "/rbac/reports", Some(json!(["admin"]))).0, 200);
+}
+
+#[test]
+fn test_any_listed_role_and_roles_injection() {
+    let app = app();
+    assert_eq!(
+        status(&app, "/rbac/invoices/4", Some(json!(["billing"]))),
+        (200, "4 billing".to_string())
+    );
+    assert_eq!(
+        status(&app, "/rbac/invoices/4", Some(json!(["staff"]))).0,
+        403
+    );
+    assert_eq!(
+        status(&app, "/rbac/invoices/4", Some(json!(["admin"]))).0,
+        200
+    );
+}
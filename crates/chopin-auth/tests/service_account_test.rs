@@ -0,0 +1,210 @@
+use chopin_auth::{
+    JwtManager, ServiceAccounts, ServiceClaims, TokenDenylist, init_jwt_manager,
+    init_service_accounts, service_accounts,
+};
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Method, Response, Router, authorize, get, role_required};
+use serde_json::json;
+use std::sync::Once;
+
+const SECRET: &[u8] = b"service-account-test";
+
+fn setup() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let denylist = TokenDenylist::new();
+        init_jwt_manager(JwtManager::new(SECRET).with_denylist(denylist.clone()));
+        init_service_accounts(ServiceAccounts::new().with_denylist(denylist));
+    });
+}
+
+fn token_endpoint(ctx: Context) -> Response {
+    service_accounts().token_endpoint(&ctx)
+}
+
+#[get("/internal/invoices")]
+#[authorize(ServiceClaims, |c| c.has_scope("invoices:read"))]
+fn invoices(_ctx: Context, claims: ServiceClaims) -> Response {
+    Response::text(format!("invoices for {}", claims.client_id))
+}
+
+#[get("/internal/reindex")]
+#[role_required("indexer")]
+fn reindex(_ctx: Context) -> Response {
+    Response::text("reindexing")
+}
+
+fn app() -> TestApp {
+    setup();
+    let mut router = Router::new();
+    router.post("/oauth/token", token_endpoint);
+    router.get("/internal/invoices", invoices);
+    router.get("/internal/reindex", reindex);
+    TestApp::new(router)
+}
+
+fn base64(input: &str) -> String {
+    const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::new();
+    for chunk in input.as_bytes().chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(CHARS[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn request_token(app: &TestApp, headers: &[(&str, &str)], form: &str) -> (u16, serde_json::Value) {
+    let res = app.request(Method::Post, "/oauth/token", headers, form.as_bytes());
+    (res.status, serde_json::from_str(&res.text()).unwrap())
+}
+
+fn get_with(app: &TestApp, path: &str, token: &str) -> u16 {
+    let bearer = format!("Bearer {token}");
+    app.request(
+        Method::Get,
+        path,
+        &[("Authorization", bearer.as_str())],
+        b"",
+    )
+    .status
+}
+
+#[test]
+fn test_client_credentials_grant() {
+    let app = app();
+    let (account, secret) =
+        service_accounts().create("billing", &["invoices:read", "invoices:write"]);
+
+    let basic = format!(
+        "Basic {}",
+        base64(&format!("{}:{secret}", account.client_id))
+    );
+    let (status, body) = request_token(
+        &app,
+        &[("Authorization", basic.as_str())],
+        "grant_type=client_credentials&scope=invoices%3Aread",
+    );
+    assert_eq!(status, 200);
+    assert_eq!(
+        (body["token_type"].as_str(), body["scope"].as_str()),
+        (Some("Bearer"), Some("invoices:read"))
+    );
+    let token = body["access_token"].as_str().unwrap();
+
+    let res = app.request(
+        Method::Get,
+        "/internal/invoices",
+        &[("Authorization", format!("Bearer {token}").as_str())],
+        b"",
+    );
+    assert_eq!(
+        (res.status, res.text()),
+        (200, format!("invoices for {}", account.client_id))
+    );
+
+    // Credentials in the form body, narrowed to a scope the route doesn't accept.
+    let form = format!(
+        "grant_type=client_credentials&client_id={}&client_secret={secret}&scope=invoices%3Awrite",
+        account.client_id
+    );
+    let (status, body) = request_token(&app, &[], &form);
+    assert_eq!(status, 200);
+    assert_eq!(
+        get_with(
+            &app,
+            "/internal/invoices",
+            body["access_token"].as_str().unwrap()
+        ),
+        403
+    );
+
+    // Errors follow RFC 6749.
+    let wrong = format!(
+        "grant_type=client_credentials&client_id={}&client_secret=nope",
+        account.client_id
+    );
+    assert_eq!(
+        request_token(&app, &[], &wrong),
+        (401, json!({ "error": "invalid_client" }))
+    );
+    let (status, body) = request_token(
+        &app,
+        &[("Authorization", basic.as_str())],
+        "grant_type=password",
+    );
+    assert_eq!(
+        (status, body["error"].as_str()),
+        (400, Some("unsupported_grant_type"))
+    );
+    let (status, body) = request_token(
+        &app,
+        &[("Authorization", basic.as_str())],
+        "grant_type=client_credentials&scope=admin",
+    );
+    assert_eq!(
+        (status, body["error"].as_str()),
+        (400, Some("invalid_scope"))
+    );
+}
+
+#[test]
+fn test_roles_and_user_tokens() {
+    let app = app();
+    let accounts = service_accounts();
+    let (account, _) = accounts.create("search", &[]);
+    accounts.set_roles(&account.client_id, &["indexer"]);
+
+    // A long-lived token handed to the service out of band.
+    let account = accounts.get(&account.client_id).unwrap();
+    let manager = JwtManager::new(SECRET);
+    let token = accounts
+        .issue(
+            &manager,
+            &account,
+            None,
+            std::time::Duration::from_secs(365 * 86_400),
+        )
+        .unwrap()
+        .access_token;
+    assert_eq!(get_with(&app, "/internal/reindex", &token), 200);
+    assert_eq!(get_with(&app, "/internal/invoices", &token), 403);
+
+    // User tokens pass role checks but are not service principals.
+    let user = manager
+        .encode(&json!({ "sub": "u1", "roles": ["indexer"], "scope": "invoices:read", "exp": 253_370_764_800_u64 }))
+        .unwrap();
+    assert_eq!(get_with(&app, "/internal/reindex", &user), 200);
+    assert_eq!(get_with(&app, "/internal/invoices", &user), 401);
+}
+
+#[test]
+fn test_disable_revokes_issued_tokens() {
+    let app = app();
+    let accounts = service_accounts();
+    let (account, secret) = accounts.create("reporter", &["invoices:read"]);
+    let manager = JwtManager::new(SECRET);
+    let old = accounts
+        .client_credentials(&manager, &account.client_id, &secret, None)
+        .unwrap()
+        .access_token;
+    assert_eq!(get_with(&app, "/internal/invoices", &old), 200);
+
+    // Revocation compares whole seconds; make sure `iat` of the old token is in the past.
+    std::thread::sleep(std::time::Duration::from_millis(1_100));
+    assert!(accounts.disable(&account.client_id));
+    assert_eq!(get_with(&app, "/internal/invoices", &old), 401);
+    assert!(
+        accounts
+            .client_credentials(&manager, &account.client_id, &secret, None)
+            .is_err()
+    );
+}
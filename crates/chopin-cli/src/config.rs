@@ -9,6 +9,9 @@ pub struct ChopinConfig {
     pub server: ServerConfig,
     #[serde(default)]
     pub database: DatabaseConfig,
+    /// Deployment environment, e.g. "development" or "production".
+    #[serde(default = "default_environment")]
+    pub environment: String,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -31,6 +34,9 @@ pub struct DatabaseConfig {
     pub pool_size: usize,
 }
 
+fn default_environment() -> String {
+    "development".to_string()
+}
 fn default_host() -> String {
     "0.0.0.0".to_string()
 }
@@ -79,6 +85,7 @@ impl ChopinConfig {
             ChopinConfig {
                 server: ServerConfig::default(),
                 database: DatabaseConfig::default(),
+                environment: default_environment(),
             }
         };
 
@@ -94,9 +101,17 @@ impl ChopinConfig {
         if let Ok(host) = std::env::var("HOST") {
             config.server.host = host;
         }
+        if let Ok(env) = std::env::var("CHOPIN_ENV") {
+            config.environment = env;
+        }
 
         Ok(config)
     }
+
+    /// `true` when `environment` (or `CHOPIN_ENV`) is `production` or `prod`.
+    pub fn is_production(&self) -> bool {
+        matches!(self.environment.as_str(), "production" | "prod")
+    }
 }
 
 /// Replace `${VAR_NAME}` patterns with environment variable values.
@@ -133,8 +148,10 @@ mod tests {
         let config = ChopinConfig {
             server: ServerConfig::default(),
             database: DatabaseConfig::default(),
+            environment: default_environment(),
         };
         assert_eq!(config.server.port, 8080);
+        assert!(!config.is_production());
         assert_eq!(config.server.host, "0.0.0.0");
         assert_eq!(config.database.pool_size, 5);
     }
@@ -153,6 +170,10 @@ pool_size = 10
         let config: ChopinConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.server.port, 3000);
         assert_eq!(config.database.pool_size, 10);
+        assert_eq!(config.environment, "development");
+
+        let config: ChopinConfig = toml::from_str("environment = \"production\"").unwrap();
+        assert!(config.is_production());
     }
 
     #[test]
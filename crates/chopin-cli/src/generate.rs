@@ -210,12 +210,20 @@ fn map_field_type(t: &str) -> (&'static str, &'static str) {
     }
 }
 
+/// Fields left out of the generated `{Model}Response` DTO.
+fn is_sensitive_field(name: &str) -> bool {
+    name.contains("password")
+        || name.ends_with("_hash")
+        || name.ends_with("secret")
+        || name.ends_with("token")
+}
+
 /// Generate a model struct + up/down migrations from field definitions.
 ///
 /// Usage: `chopin generate model User name:string email:string age:i32`
 pub fn generate_model(project_dir: &Path, name: &str, field_defs: &[String]) -> Result<()> {
     let struct_name = to_pascal_case(name);
-    let table_name = to_snake_case(name) + "s"; // simple pluralization
+    let table_name = chopin_orm::naming::pluralize(&to_snake_case(name));
 
     // Parse field definitions.
     let mut fields: Vec<(&str, &'static str, &'static str)> = Vec::new(); // (name, rust_type, sql_type)
@@ -233,10 +241,10 @@ pub fn generate_model(project_dir: &Path, name: &str, field_defs: &[String]) ->
 
     // ─── Generate model struct ───────────────────────────────────────────
     let mut model_code = format!(
-        r#"use chopin_orm::Model;
+        r#"use chopin_orm::{{Model, ModelDto}};
 use serde::{{Deserialize, Serialize}};
 
-#[derive(Debug, Clone, Model, Serialize, Deserialize)]
+#[derive(Debug, Clone, Model, ModelDto, Serialize, Deserialize)]
 #[model(table_name = "{}")]
 pub struct {} {{
     #[model(primary_key)]
@@ -246,6 +254,9 @@ pub struct {} {{
     );
 
     for (fname, rust_ty, _) in &fields {
+        if is_sensitive_field(fname) {
+            model_code.push_str("    #[model(hidden)]\n");
+        }
         model_code.push_str(&format!("    pub {}: {},\n", fname, rust_ty));
     }
     model_code.push_str("}\n");
@@ -288,6 +299,125 @@ pub struct {} {{
     Ok(())
 }
 
+/// Scaffold a `ChopinModule` in `src/modules/<name>.rs`, optionally wired to a
+/// built-in integration.
+///
+/// Usage: `chopin generate module payments --with stripe`
+pub fn generate_module(project_dir: &Path, name: &str, with: Option<&str>) -> Result<()> {
+    let module_path = project_dir.join("src/modules").join(format!("{}.rs", name));
+
+    if module_path.exists() {
+        anyhow::bail!(
+            "Module '{}' already exists at {}",
+            name,
+            module_path.display()
+        );
+    }
+
+    let (code, feature) = match with {
+        None => (plain_module_template(name), None),
+        Some("stripe") => (stripe_module_template(name), Some("payments")),
+        Some(other) => anyhow::bail!(
+            "Unknown integration '{}'. Available integrations: stripe",
+            other
+        ),
+    };
+
+    std::fs::create_dir_all(module_path.parent().unwrap())?;
+    std::fs::write(&module_path, code)?;
+
+    println!("{} Generated module: {}", "✓".green().bold(), name.cyan());
+    println!("  Created: src/modules/{}.rs", name);
+    if let Some(feature) = feature {
+        println!(
+            "  Enable the {} feature: {}",
+            feature.cyan(),
+            format!(
+                "chopin-core = {{ version = \"*\", features = [\"{}\"] }}",
+                feature
+            )
+            .yellow()
+        );
+        println!(
+            "  Set {} from the Stripe dashboard.",
+            "STRIPE_WEBHOOK_SECRET".yellow()
+        );
+    }
+    println!(
+        "  Next: {}",
+        format!("Chopin::new().mount_module(modules::{}::module())", name).yellow()
+    );
+
+    Ok(())
+}
+
+fn plain_module_template(name: &str) -> String {
+    format!(
+        r#"use chopin_core::{{ChopinModule, Context, Response, Router}};
+
+/// The `{name}` module.
+pub struct {type_name}Module;
+
+pub fn module() -> {type_name}Module {{
+    {type_name}Module
+}}
+
+impl ChopinModule for {type_name}Module {{
+    fn name(&self) -> &'static str {{
+        "{name}"
+    }}
+
+    fn routes(&self, router: &mut Router) {{
+        router.get("/{name}", index);
+    }}
+}}
+
+fn index(_ctx: Context) -> Response {{
+    Response::text("Hello from {name}")
+}}
+"#,
+        name = name,
+        type_name = to_pascal_case(name)
+    )
+}
+
+fn stripe_module_template(name: &str) -> String {
+    format!(
+        r#"//! Stripe payments. Webhooks are received at
+//! `POST /api/{name}/stripe/webhook`; point the Stripe dashboard there.
+use chopin_core::payments::{{self, PaymentEvent, PaymentsModule}};
+
+pub fn module() -> PaymentsModule {{
+    let secret = std::env::var("STRIPE_WEBHOOK_SECRET")
+        .expect("STRIPE_WEBHOOK_SECRET must be set");
+    payments::set_webhook_secret(secret);
+    payments::on_payment_event(on_payment_event);
+    PaymentsModule::new("/api/{name}")
+}}
+
+/// Called once per processed webhook event; redeliveries are filtered out.
+fn on_payment_event(event: &PaymentEvent) {{
+    match event {{
+        PaymentEvent::CheckoutCompleted {{ customer, .. }} => {{
+            // TODO: link the Stripe customer to your user
+            let _ = customer;
+        }}
+        PaymentEvent::SubscriptionChanged(sub) => {{
+            // TODO: grant or revoke access based on sub.status
+            let _ = sub;
+        }}
+        PaymentEvent::PaymentFailed(payment) => {{
+            // TODO: notify the customer
+            let _ = payment;
+        }}
+        _ => {{}}
+    }}
+}}
+"#,
+        name = name
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -362,4 +492,50 @@ mod tests {
         let result = generate_app(dir.path(), "product");
         assert!(result.is_err(), "duplicate app should fail");
     }
+
+    #[test]
+    fn test_generate_module_with_stripe() {
+        let dir = tempfile::tempdir().unwrap();
+        generate_module(dir.path(), "payments", Some("stripe")).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/modules/payments.rs")).unwrap();
+        assert!(code.contains("PaymentsModule::new(\"/api/payments\")"));
+        assert!(code.contains("STRIPE_WEBHOOK_SECRET"));
+        assert!(generate_module(dir.path(), "payments", None).is_err());
+    }
+
+    #[test]
+    fn test_generate_module_plain_and_unknown_integration() {
+        let dir = tempfile::tempdir().unwrap();
+        generate_module(dir.path(), "billing", None).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/modules/billing.rs")).unwrap();
+        assert!(code.contains("impl ChopinModule for BillingModule"));
+        assert!(generate_module(dir.path(), "shop", Some("paypal")).is_err());
+        assert!(!dir.path().join("src/modules/shop.rs").exists());
+    }
+
+    #[test]
+    fn test_generate_model_hides_sensitive_fields() {
+        let dir = tempfile::tempdir().unwrap();
+        let fields = [
+            "email:string".to_string(),
+            "password_hash:string".to_string(),
+        ];
+        generate_model(dir.path(), "user", &fields).unwrap();
+        let code = std::fs::read_to_string(dir.path().join("src/models/user.rs")).unwrap();
+        assert!(code.contains("Model, ModelDto,"));
+        assert!(
+            code.contains("    pub email: String,\n    #[model(hidden)]\n    pub password_hash")
+        );
+        assert!(!is_sensitive_field("token_count"));
+        assert!(is_sensitive_field("api_token"));
+    }
+
+    #[test]
+    fn test_generate_model_pluralizes_table() {
+        let dir = tempfile::tempdir().unwrap();
+        generate_model(dir.path(), "ProductCategory", &["name:string".to_string()]).unwrap();
+        let code =
+            std::fs::read_to_string(dir.path().join("src/models/product_category.rs")).unwrap();
+        assert!(code.contains(r#"#[model(table_name = "product_categories")]"#));
+    }
 }
@@ -0,0 +1,177 @@
+use anyhow::Result;
+use chopin_pg::{PgConfig, PgPool, Row};
+use colored::*;
+use std::path::Path;
+
+use crate::JobsCommands;
+
+/// The same requeue the `/retry` admin endpoint performs: fresh attempts,
+/// due now.
+const RETRY_SQL: &str = "UPDATE chopin_jobs SET state = 'pending', attempts = 0, progress = 0, \
+     run_at = NOW(), started_at = NULL, finished_at = NULL";
+
+pub fn run_jobs_command(project_dir: &Path, command: JobsCommands) -> Result<()> {
+    let cfg = crate::config::ChopinConfig::load(project_dir)?;
+    let mut pool = PgPool::connect(PgConfig::from_url(&cfg.database.url)?, 1)?;
+    let mut conn = pool.get()?;
+    let exists = conn.query("SELECT to_regclass('chopin_jobs')::TEXT", &[])?;
+    if exists[0].get_typed::<Option<String>>(0)?.is_none() {
+        println!(
+            "{} No chopin_jobs table. Is JobsModule mounted and migrated?",
+            "ℹ".blue()
+        );
+        return Ok(());
+    }
+
+    match command {
+        JobsCommands::List { state, kind, limit } => {
+            let state = (state != "all").then_some(state);
+            let rows = conn.query(
+                "SELECT id, kind, state, attempts, max_attempts, error, \
+                 to_char(created_at, 'YYYY-MM-DD HH24:MI:SS') FROM chopin_jobs \
+                 WHERE ($1::TEXT IS NULL OR state = $1) AND ($2::TEXT IS NULL OR kind = $2) \
+                 ORDER BY created_at DESC LIMIT $3",
+                &[&state, &kind, &limit.clamp(1, 1000)],
+            )?;
+            if rows.is_empty() {
+                println!("{} No matching jobs.", "✅".green());
+            }
+            for row in &rows {
+                print_summary(row)?;
+            }
+        }
+        JobsCommands::Show { id } => {
+            let rows = conn.query(
+                "SELECT id, kind, state, attempts, max_attempts, error, \
+                 to_char(created_at, 'YYYY-MM-DD HH24:MI:SS'), payload, result, \
+                 backoff_ms, timeout_ms, to_char(run_at, 'YYYY-MM-DD HH24:MI:SS') \
+                 FROM chopin_jobs WHERE id = $1",
+                &[&id],
+            )?;
+            let Some(row) = rows.first() else {
+                anyhow::bail!("No job with id {}", id);
+            };
+            print_summary(row)?;
+            let timeout = match row.get_typed::<Option<i64>>(10)? {
+                Some(ms) => format!("{ms} ms"),
+                None => "none".into(),
+            };
+            println!(
+                "    backoff {} ms, timeout {}, next run {}",
+                row.get_typed::<i64>(9)?,
+                timeout,
+                row.get_typed::<String>(11)?
+            );
+            println!("    payload: {}", row.get_typed::<String>(7)?);
+            if let Some(result) = row.get_typed::<Option<String>>(8)? {
+                println!("    result:  {}", result);
+            }
+            if let Some(error) = row.get_typed::<Option<String>>(5)? {
+                println!("    error:\n{}", error.red());
+            }
+        }
+        JobsCommands::Retry {
+            id,
+            all_failed,
+            kind,
+        } => {
+            let n = match (id, all_failed) {
+                (Some(id), false) => conn.execute(
+                    &format!("{RETRY_SQL} WHERE id = $1 AND state = 'failed'"),
+                    &[&id],
+                )?,
+                (None, true) => conn.execute(
+                    &format!(
+                        "{RETRY_SQL} WHERE state = 'failed' AND ($1::TEXT IS NULL OR kind = $1)"
+                    ),
+                    &[&kind],
+                )?,
+                _ => anyhow::bail!("Pass a job id or --all-failed"),
+            };
+            println!("{} Requeued {} failed job(s).", "🔁".bold(), n);
+        }
+        JobsCommands::Discard { id } => {
+            let n = conn.execute(
+                "DELETE FROM chopin_jobs WHERE id = $1 AND state = 'failed'",
+                &[&id],
+            )?;
+            if n == 0 {
+                anyhow::bail!("No failed job with id {}", id);
+            }
+            println!("{} Discarded job {}.", "🗑".bold(), id.yellow());
+        }
+        JobsCommands::Policy {
+            kind,
+            max_attempts,
+            backoff_ms,
+            timeout_ms,
+        } => {
+            let n = conn.execute(
+                "UPDATE chopin_jobs SET \
+                 max_attempts = COALESCE($2, max_attempts), \
+                 backoff_ms = COALESCE($3, backoff_ms), \
+                 timeout_ms = CASE WHEN $4::BIGINT IS NULL THEN timeout_ms \
+                                   WHEN $4 = 0 THEN NULL ELSE $4 END \
+                 WHERE kind = $1 AND state IN ('pending', 'failed')",
+                &[
+                    &kind,
+                    &max_attempts.map(|n| n.max(1)),
+                    &backoff_ms,
+                    &timeout_ms,
+                ],
+            )?;
+            println!(
+                "{} Updated the policy of {} stored {} job(s).",
+                "⚙".bold(),
+                n,
+                kind.yellow()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// One line per job: id, kind, state, attempts and the first line of the
+/// error.
+fn print_summary(row: &Row) -> Result<()> {
+    let state: String = row.get_typed(2)?;
+    let colored_state = match state.as_str() {
+        "failed" => state.red(),
+        "succeeded" => state.green(),
+        _ => state.yellow(),
+    };
+    let error: Option<String> = row.get_typed(5)?;
+    println!(
+        "  {}  {:<24} {:<9} {}/{}  {}  {}",
+        row.get_typed::<String>(0)?,
+        row.get_typed::<String>(1)?,
+        colored_state,
+        row.get_typed::<i32>(3)?,
+        row.get_typed::<i32>(4)?,
+        row.get_typed::<String>(6)?,
+        error.as_deref().map(first_line).unwrap_or_default()
+    );
+    Ok(())
+}
+
+/// The first line of `error`, cut to 80 characters.
+fn first_line(error: &str) -> String {
+    let line = error.lines().next().unwrap_or("");
+    match line.char_indices().nth(80) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_first_line_trims_errors() {
+        assert_eq!(first_line("boom\nat line 2"), "boom");
+        assert_eq!(first_line(""), "");
+        let long = "é".repeat(100);
+        assert_eq!(first_line(&long), format!("{}…", "é".repeat(80)));
+    }
+}
@@ -0,0 +1,382 @@
+//! Static checks for SQL migrations.
+//!
+//! Flags destructive operations (dropped tables and columns, type changes
+//! that may narrow data, unbounded deletes) and foreign keys added without an
+//! index on the referencing columns.
+use crate::migrations::split_statements;
+use colored::*;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Loses data; blocked in production without `--allow-destructive`.
+    Destructive,
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.severity {
+            Severity::Destructive => {
+                write!(f, "{} {}", "✗ destructive:".red().bold(), self.message)
+            }
+            Severity::Warning => write!(f, "{} {}", "⚠ warning:".yellow(), self.message),
+        }
+    }
+}
+
+/// Column types a conversion can target without losing information.
+const WIDE_TYPES: &[&str] = &[
+    "TEXT",
+    "BIGINT",
+    "INT8",
+    "DOUBLE PRECISION",
+    "FLOAT8",
+    "NUMERIC",
+    "DECIMAL",
+    "VARCHAR",
+    "CHARACTER VARYING",
+    "JSONB",
+    "TIMESTAMPTZ",
+];
+
+/// Lint one migration script.
+pub fn lint_sql(sql: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut foreign_keys: Vec<(String, String)> = Vec::new();
+    let mut indexed: Vec<(String, String)> = Vec::new();
+
+    for stmt in split_statements(sql) {
+        let tokens = tokenize(&stmt);
+        let upper: Vec<String> = tokens.iter().map(|t| t.to_ascii_uppercase()).collect();
+        let kw = |i: usize, word: &str| upper.get(i).is_some_and(|t| t == word);
+        let mut destructive = |message: String| {
+            findings.push(Finding {
+                severity: Severity::Destructive,
+                message,
+            })
+        };
+
+        if kw(0, "DROP") && (kw(1, "TABLE") || kw(1, "SCHEMA") || kw(1, "DATABASE")) {
+            let name = name_after_if_exists(&tokens, &upper, 2);
+            destructive(format!("drops {} {}", upper[1].to_lowercase(), name));
+        } else if kw(0, "TRUNCATE") {
+            destructive(format!(
+                "truncates {}",
+                name_after_if_exists(&tokens, &upper, skip(&upper, 1, &["TABLE", "ONLY"]))
+            ));
+        } else if kw(0, "DELETE") && !upper.iter().any(|t| t == "WHERE") {
+            destructive(format!(
+                "deletes every row of {}",
+                tokens.get(2).map_or("?", |t| t.as_str())
+            ));
+        } else if kw(0, "UPDATE") && !upper.iter().any(|t| t == "WHERE") {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                message: format!(
+                    "updates every row of {}",
+                    tokens.get(1).map_or("?", |t| t.as_str())
+                ),
+            });
+        } else if kw(0, "ALTER") && kw(1, "TABLE") {
+            let t = skip(&upper, 2, &["ONLY"]);
+            let t = if kw(t, "IF") && kw(t + 1, "EXISTS") {
+                t + 2
+            } else {
+                t
+            };
+            let table = unquote(tokens.get(t).map_or("?", |s| s.as_str()));
+            for clause in split_top_level(&tokens[(t + 1).min(tokens.len())..]) {
+                let cu: Vec<String> = clause.iter().map(|t| t.to_ascii_uppercase()).collect();
+                let ck = |i: usize, w: &str| cu.get(i).is_some_and(|t| t == w);
+                if ck(0, "DROP")
+                    && !matches!(
+                        cu.get(1).map(String::as_str),
+                        Some("CONSTRAINT" | "DEFAULT" | "NOT" | "IDENTITY" | "EXPRESSION")
+                    )
+                {
+                    let c = skip(&cu, 1, &["COLUMN"]);
+                    let c = if ck(c, "IF") && ck(c + 1, "EXISTS") {
+                        c + 2
+                    } else {
+                        c
+                    };
+                    destructive(format!(
+                        "drops column {}.{}",
+                        table,
+                        unquote(clause.get(c).map_or("?", |s| s.as_str()))
+                    ));
+                } else if ck(0, "ALTER")
+                    && let Some(ty) = cu.iter().position(|t| t == "TYPE")
+                {
+                    let c = skip(&cu, 1, &["COLUMN"]);
+                    let target = type_name(&cu[ty + 1..]);
+                    if !WIDE_TYPES.contains(&target.as_str()) {
+                        destructive(format!(
+                            "changes type of {}.{} to {}; narrowing conversions lose data",
+                            table,
+                            unquote(clause.get(c).map_or("?", |s| s.as_str())),
+                            target.to_lowercase()
+                        ));
+                    }
+                } else if ck(0, "ADD") {
+                    let c = skip(&cu, 1, &["COLUMN"]);
+                    let c = if ck(c, "IF") && ck(c + 1, "NOT") && ck(c + 2, "EXISTS") {
+                        c + 3
+                    } else {
+                        c
+                    };
+                    collect_keys(&table, &clause[c..], &mut foreign_keys, &mut indexed);
+                }
+            }
+        } else if kw(0, "CREATE") && upper.iter().take(3).any(|t| t == "TABLE") {
+            let t = upper.iter().position(|t| t == "TABLE").unwrap_or(1) + 1;
+            let t = if kw(t, "IF") && kw(t + 1, "NOT") && kw(t + 2, "EXISTS") {
+                t + 3
+            } else {
+                t
+            };
+            let table = unquote(tokens.get(t).map_or("?", |s| s.as_str()));
+            if let Some(body) = parenthesized(&tokens[(t + 1).min(tokens.len())..]) {
+                for def in split_top_level(body) {
+                    collect_keys(&table, def, &mut foreign_keys, &mut indexed);
+                }
+            }
+        } else if kw(0, "CREATE")
+            && let Some(on) = upper.iter().position(|t| t == "ON")
+            && upper[..on].iter().any(|t| t == "INDEX")
+        {
+            let n = skip(&upper, on + 1, &["ONLY"]);
+            let table = unquote(tokens.get(n).map_or("?", |s| s.as_str()));
+            // `USING method (cols)` or `(cols)`.
+            let rest = &tokens[(n + 1).min(tokens.len())..];
+            if let Some(first) = parenthesized(rest)
+                .or_else(|| parenthesized(rest.get(2..).unwrap_or(&[])))
+                .and_then(|cols| cols.first())
+            {
+                indexed.push((table, unquote(first)));
+            }
+        }
+    }
+
+    for (table, column) in foreign_keys {
+        if !indexed.contains(&(table.clone(), column.clone())) {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                message: format!(
+                    "foreign key {table}.{column} has no index; joins and cascading deletes will scan {table}"
+                ),
+            });
+        }
+    }
+    findings
+}
+
+/// Record foreign keys and indexed leading columns from one column
+/// definition or table constraint.
+fn collect_keys(
+    table: &str,
+    def: &[String],
+    foreign_keys: &mut Vec<(String, String)>,
+    indexed: &mut Vec<(String, String)>,
+) {
+    let upper: Vec<String> = def.iter().map(|t| t.to_ascii_uppercase()).collect();
+    let start = if upper.first().is_some_and(|t| t == "CONSTRAINT") {
+        2
+    } else {
+        0
+    };
+    let first_col = |from: usize| {
+        parenthesized(def.get(from..).unwrap_or(&[]))
+            .and_then(|cols| cols.first())
+            .map(|c| (table.to_string(), unquote(c)))
+    };
+    match upper.get(start).map(String::as_str) {
+        Some("FOREIGN") => foreign_keys.extend(first_col(start + 2)),
+        Some("PRIMARY") => indexed.extend(first_col(start + 2)),
+        Some("UNIQUE") => indexed.extend(first_col(start + 1)),
+        Some("CHECK" | "EXCLUDE" | "LIKE") | None => {}
+        Some(_) => {
+            let column = (table.to_string(), unquote(&def[start]));
+            if upper.iter().any(|t| t == "REFERENCES") {
+                foreign_keys.push(column.clone());
+            }
+            if upper.iter().any(|t| t == "PRIMARY" || t == "UNIQUE") {
+                indexed.push(column);
+            }
+        }
+    }
+}
+
+/// Split on whitespace, keeping `(`, `)` and `,` as separate tokens and
+/// dropping `--` comments.
+fn tokenize(stmt: &str) -> Vec<String> {
+    stmt.lines()
+        .map(|l| l.split("--").next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('(', " ( ")
+        .replace(')', " ) ")
+        .replace(',', " , ")
+        .split_whitespace()
+        .map(str::to_string)
+        .collect()
+}
+
+/// Tokens inside the first parenthesized group, if `tokens` starts with one.
+fn parenthesized(tokens: &[String]) -> Option<&[String]> {
+    if tokens.first()? != "(" {
+        return None;
+    }
+    let mut depth = 0;
+    for (i, t) in tokens.iter().enumerate() {
+        match t.as_str() {
+            "(" => depth += 1,
+            ")" => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(&tokens[1..i]);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split on commas outside parentheses.
+fn split_top_level(tokens: &[String]) -> Vec<&[String]> {
+    let mut parts = Vec::new();
+    let (mut depth, mut start) = (0i32, 0);
+    for (i, t) in tokens.iter().enumerate() {
+        match t.as_str() {
+            "(" => depth += 1,
+            ")" => depth -= 1,
+            "," if depth == 0 => {
+                parts.push(&tokens[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts.retain(|p| !p.is_empty());
+    parts
+}
+
+fn skip(upper: &[String], mut i: usize, words: &[&str]) -> usize {
+    while upper.get(i).is_some_and(|t| words.contains(&t.as_str())) {
+        i += 1;
+    }
+    i
+}
+
+fn name_after_if_exists(tokens: &[String], upper: &[String], i: usize) -> String {
+    let i = if upper.get(i).is_some_and(|t| t == "IF") {
+        i + 2
+    } else {
+        i
+    };
+    tokens.get(i).map_or("?".to_string(), |t| unquote(t))
+}
+
+/// The target type of `ALTER ... TYPE <type> [(args)] [USING ...]`,
+/// upper-cased, e.g. `VARCHAR(50)` or `DOUBLE PRECISION`.
+fn type_name(upper: &[String]) -> String {
+    let mut name = String::new();
+    for (i, t) in upper.iter().enumerate() {
+        match t.as_str() {
+            "(" => {
+                let args: Vec<&str> = upper[i + 1..]
+                    .iter()
+                    .take_while(|t| *t != ")")
+                    .map(String::as_str)
+                    .collect();
+                name.push_str(&format!("({})", args.join("")));
+                break;
+            }
+            "PRECISION" | "VARYING" if i > 0 => name.push_str(&format!(" {t}")),
+            _ if i > 0 => break,
+            _ => name.push_str(t),
+        }
+    }
+    name
+}
+
+fn unquote(s: &str) -> String {
+    s.trim_matches('"').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages(sql: &str, severity: Severity) -> Vec<String> {
+        lint_sql(sql)
+            .into_iter()
+            .filter(|f| f.severity == severity)
+            .map(|f| f.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_flags_destructive_operations() {
+        let found = messages(
+            "DROP TABLE IF EXISTS old_users;
+             ALTER TABLE users DROP COLUMN legacy, DROP CONSTRAINT users_pkey;
+             ALTER TABLE users ALTER COLUMN age TYPE smallint;
+             ALTER TABLE users ALTER COLUMN bio TYPE text, ALTER COLUMN name TYPE varchar(50);
+             TRUNCATE TABLE sessions;
+             DELETE FROM audit_log;
+             DELETE FROM audit_log WHERE created_at < NOW();",
+            Severity::Destructive,
+        );
+        assert_eq!(
+            found,
+            [
+                "drops table old_users",
+                "drops column users.legacy",
+                "changes type of users.age to smallint; narrowing conversions lose data",
+                "changes type of users.name to varchar(50); narrowing conversions lose data",
+                "truncates sessions",
+                "deletes every row of audit_log",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_safe_migrations_pass() {
+        assert!(
+            lint_sql(
+                "CREATE TABLE posts (id SERIAL PRIMARY KEY, title TEXT NOT NULL);
+                 ALTER TABLE posts ADD COLUMN body TEXT, ALTER COLUMN title DROP NOT NULL;
+                 ALTER TABLE posts ALTER COLUMN id TYPE bigint;"
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_warns_about_unindexed_foreign_keys() {
+        let found = messages(
+            "CREATE TABLE comments (
+                 id SERIAL PRIMARY KEY,
+                 post_id INT NOT NULL REFERENCES posts(id),
+                 author_id INT,
+                 CONSTRAINT fk_author FOREIGN KEY (author_id) REFERENCES users (id)
+             );
+             CREATE INDEX comments_post_idx ON comments (post_id);
+             ALTER TABLE likes ADD COLUMN user_id INT REFERENCES users(id);",
+            Severity::Warning,
+        );
+        assert_eq!(found.len(), 2, "{found:?}");
+        assert!(found[0].starts_with("foreign key comments.author_id"));
+        assert!(found[1].starts_with("foreign key likes.user_id"));
+    }
+}
@@ -6,8 +6,11 @@ mod check;
 mod config;
 mod deploy;
 mod generate;
+mod jobs;
+mod lint;
 mod migrations;
 mod openapi;
+mod plugins;
 
 #[derive(Parser)]
 #[command(name = "chopin")]
@@ -54,6 +57,81 @@ enum Commands {
     },
     /// Scrape the routes to generate an OpenAPI spec
     Openapi,
+    /// API documentation utilities
+    Docs {
+        #[command(subcommand)]
+        command: DocsCommands,
+    },
+    /// Inspect and administer background jobs
+    Jobs {
+        #[command(subcommand)]
+        command: JobsCommands,
+    },
+    /// List the plugins mounted by the app
+    Plugins,
+    /// Run a plugin command, e.g. `chopin run cms:import ./pages`
+    Run {
+        /// <plugin>:<command>
+        command: String,
+        /// Arguments passed to the command
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum JobsCommands {
+    /// List jobs, newest first
+    List {
+        /// pending, running, succeeded, failed or all
+        #[arg(long, default_value = "failed")]
+        state: String,
+        /// Only jobs of this kind
+        #[arg(long)]
+        kind: Option<String>,
+        #[arg(long, default_value_t = 50)]
+        limit: i64,
+    },
+    /// Show one job with its payload and full error
+    Show { id: String },
+    /// Requeue failed jobs with fresh attempts
+    ///
+    /// Usage: chopin jobs retry <id> | chopin jobs retry --all-failed [--kind send_email]
+    Retry {
+        id: Option<String>,
+        /// Requeue every failed job
+        #[arg(long, conflicts_with = "id")]
+        all_failed: bool,
+        /// With --all-failed, only jobs of this kind
+        #[arg(long, requires = "all_failed")]
+        kind: Option<String>,
+    },
+    /// Delete a failed job
+    Discard { id: String },
+    /// Change the retry policy of stored pending and failed jobs of a kind
+    ///
+    /// New jobs take their policy from `Job::policy` or `jobs::set_policy`.
+    Policy {
+        kind: String,
+        #[arg(long)]
+        max_attempts: Option<i32>,
+        /// Delay before the first retry, doubled for each later one
+        #[arg(long)]
+        backoff_ms: Option<i64>,
+        /// Per-attempt timeout; 0 removes it
+        #[arg(long)]
+        timeout_ms: Option<i64>,
+    },
+}
+
+#[derive(Subcommand)]
+enum DocsCommands {
+    /// Build and run the app with --print-openapi and save its full spec
+    Export {
+        /// Output file path
+        #[arg(short, long, default_value = "openapi.json")]
+        output: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -80,6 +158,16 @@ enum GenerateCommands {
         #[arg(required = true)]
         fields: Vec<String>,
     },
+    /// Generate a framework module wired into `Chopin::mount_module`
+    ///
+    /// Usage: chopin generate module payments --with stripe
+    Module {
+        /// Module name (e.g., "payments")
+        name: String,
+        /// Built-in integration to scaffold (e.g., "stripe")
+        #[arg(long)]
+        with: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -98,6 +186,18 @@ enum DbCommands {
         #[arg(short, long)]
         file: String,
     },
+    /// Collapse all applied migrations into a single baseline migration
+    Squash {
+        /// Name of the baseline migration
+        #[arg(long, default_value = "baseline")]
+        name: String,
+    },
+    /// Adopt an existing database: snapshot its schema as an applied baseline
+    Baseline {
+        /// Name of the baseline migration
+        #[arg(long, default_value = "baseline")]
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -105,14 +205,30 @@ enum MigrateCommands {
     /// Show migration status
     Status,
     /// Run pending migrations
-    Up,
+    Up {
+        /// Apply destructive migrations in the production environment
+        #[arg(long)]
+        allow_destructive: bool,
+    },
+    /// Check migrations for destructive changes and unindexed foreign keys
+    Lint,
     /// Rollback migrations
     Down {
         #[arg(default_value_t = 1)]
         steps: u32,
     },
     /// Generate a new migration
-    Generate { name: String },
+    ///
+    /// Usage: chopin migrate generate add_index --sql "CREATE INDEX ..."
+    Generate {
+        name: String,
+        /// Inline SQL for the up migration
+        #[arg(long, conflicts_with = "from_sql")]
+        sql: Option<String>,
+        /// Read the up migration from a .sql file
+        #[arg(long, value_name = "FILE")]
+        from_sql: Option<std::path::PathBuf>,
+    },
 }
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -211,6 +327,12 @@ async fn main() -> Result<()> {
                     cmd.arg("-f").arg(&file);
                     cmd.spawn()?.wait()?;
                 }
+                DbCommands::Squash { name } => {
+                    migrations::squash(&project_dir, db_url, &name)?;
+                }
+                DbCommands::Baseline { name } => {
+                    migrations::baseline(&project_dir, db_url, &name)?;
+                }
             }
         }
         Commands::Generate { command } => match command {
@@ -226,6 +348,10 @@ async fn main() -> Result<()> {
                 let project_dir = std::env::current_dir()?;
                 generate::generate_model(&project_dir, &name, &fields)?;
             }
+            GenerateCommands::Module { name, with } => {
+                let project_dir = std::env::current_dir()?;
+                generate::generate_module(&project_dir, &name, with.as_deref())?;
+            }
         },
         Commands::Check => {
             let project_dir = std::env::current_dir()?;
@@ -247,6 +373,24 @@ async fn main() -> Result<()> {
             let project_dir = std::env::current_dir()?;
             openapi::generate_openapi(&project_dir)?;
         }
+        Commands::Jobs { command } => {
+            let project_dir = std::env::current_dir()?;
+            jobs::run_jobs_command(&project_dir, command)?;
+        }
+        Commands::Plugins => {
+            let project_dir = std::env::current_dir()?;
+            plugins::list_plugins(&project_dir)?;
+        }
+        Commands::Run { command, args } => {
+            let project_dir = std::env::current_dir()?;
+            plugins::run_plugin_command(&project_dir, &command, &args)?;
+        }
+        Commands::Docs { command } => match command {
+            DocsCommands::Export { output } => {
+                let project_dir = std::env::current_dir()?;
+                openapi::export_openapi(&project_dir, &output)?;
+            }
+        },
         Commands::Bench => {
             println!("{} Running benchmarks...", "🔥".bold());
         }
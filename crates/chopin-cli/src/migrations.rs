@@ -3,21 +3,50 @@ use chopin_pg::{PgConfig, PgPool};
 use chrono::Local;
 use colored::*;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub fn run_migration_command(project_dir: &Path, command: crate::MigrateCommands) -> Result<()> {
+    // Generating files does not need a database connection.
+    if let crate::MigrateCommands::Generate {
+        name,
+        sql,
+        from_sql,
+    } = command
+    {
+        let sql = match from_sql {
+            Some(path) => Some(
+                fs::read_to_string(&path)
+                    .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?,
+            ),
+            None => sql,
+        };
+        return generate_migration(project_dir, &name, sql.as_deref());
+    }
+    if let crate::MigrateCommands::Lint = command {
+        return lint_migrations(project_dir);
+    }
+
     let cfg = crate::config::ChopinConfig::load(project_dir)?;
     let db_url = &cfg.database.url;
+    let production = cfg.is_production();
     let mut pool = PgPool::connect(PgConfig::from_url(db_url)?, 1)?;
 
     match command {
         crate::MigrateCommands::Status => show_status(project_dir, &mut pool),
-        crate::MigrateCommands::Up => run_up(project_dir, &mut pool),
+        crate::MigrateCommands::Up { allow_destructive } => {
+            run_up(project_dir, &mut pool, production && !allow_destructive)
+        }
         crate::MigrateCommands::Down { steps } => run_down(project_dir, &mut pool, steps),
-        crate::MigrateCommands::Generate { name } => generate_migration(project_dir, &name),
+        crate::MigrateCommands::Generate { .. } | crate::MigrateCommands::Lint => {
+            unreachable!("handled above")
+        }
     }
 }
 
+/// Header line marking a migration produced by `chopin db squash` or
+/// `chopin db baseline`, followed by the migrations it replaces.
+const BASELINE_MARKER: &str = "-- chopin:baseline replaces:";
+
 fn ensure_migration_table(pool: &mut PgPool) -> Result<()> {
     let mut conn = pool.get()?;
     conn.execute(
@@ -28,30 +57,86 @@ fn ensure_migration_table(pool: &mut PgPool) -> Result<()> {
         )",
         &[],
     )?;
+    conn.execute(
+        "ALTER TABLE chopin_orm_migrations ADD COLUMN IF NOT EXISTS checksum TEXT",
+        &[],
+    )?;
     Ok(())
 }
 
 fn get_applied_migrations(pool: &mut PgPool) -> Result<Vec<String>> {
+    Ok(get_applied_checksums(pool)?
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect())
+}
+
+/// Applied migrations in order, with the checksum recorded when they were
+/// applied (`None` for migrations applied before checksums were tracked).
+fn get_applied_checksums(pool: &mut PgPool) -> Result<Vec<(String, Option<String>)>> {
     let mut conn = pool.get()?;
     let rows = conn.query(
-        "SELECT name FROM chopin_orm_migrations ORDER BY id ASC",
+        "SELECT name, checksum FROM chopin_orm_migrations ORDER BY id ASC",
         &[],
     )?;
-    let mut names = Vec::new();
+    let mut applied = Vec::new();
     for row in rows {
-        let val = row.get(0)?;
-        let name = match val {
+        let name = match row.get(0)? {
             chopin_pg::PgValue::Text(s) => s,
             _ => return Err(anyhow::anyhow!("Expected text for migration name")),
         };
-        names.push(name);
+        let checksum = match row.get(1)? {
+            chopin_pg::PgValue::Text(s) => Some(s),
+            _ => None,
+        };
+        applied.push((name, checksum));
     }
-    Ok(names)
+    Ok(applied)
+}
+
+/// `(name, path)` of every `*.up.sql` file, sorted by name.
+fn migration_files(migrations_dir: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut files: Vec<(String, PathBuf)> = fs::read_dir(migrations_dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter_map(|p| {
+            let name = p
+                .file_name()?
+                .to_str()?
+                .strip_suffix(".up.sql")?
+                .to_string();
+            Some((name, p))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// Hex SHA-256 of a migration's SQL, used to detect edits after applying.
+fn checksum(sql: &str) -> String {
+    chopin_pg::auth::sha256(sql.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Migrations replaced by a baseline, read from its marker line.
+fn baseline_replaces(sql: &str) -> Option<Vec<String>> {
+    let list = sql
+        .lines()
+        .find_map(|l| l.trim().strip_prefix(BASELINE_MARKER))?;
+    Some(
+        list.split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .collect(),
+    )
 }
 
 fn show_status(project_dir: &Path, pool: &mut PgPool) -> Result<()> {
     ensure_migration_table(pool)?;
-    let applied = get_applied_migrations(pool)?;
+    let applied = get_applied_checksums(pool)?;
     let migrations_dir = project_dir.join("migrations");
 
     if !migrations_dir.exists() {
@@ -60,85 +145,121 @@ fn show_status(project_dir: &Path, pool: &mut PgPool) -> Result<()> {
     }
 
     println!("{} Migration Status:", "📊".bold());
-    let mut files: Vec<_> = fs::read_dir(migrations_dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.extension().is_some_and(|ext| ext == "sql")
-                && p.file_name().unwrap().to_str().unwrap().contains(".up")
-        })
-        .collect();
-    files.sort();
-
-    for file in files {
-        let full_name = file
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .replace(".up", "");
-        let status = if applied.contains(&full_name) {
-            "Applied".green()
-        } else {
-            "Pending".yellow()
+    for (name, path) in migration_files(&migrations_dir)? {
+        let status = match applied.iter().find(|(n, _)| *n == name) {
+            Some((_, Some(sum))) if *sum != checksum(&fs::read_to_string(&path)?) => {
+                "Applied, modified since".red()
+            }
+            Some(_) => "Applied".green(),
+            None => "Pending".yellow(),
         };
-        println!("  - {:<40} [{}]", full_name, status);
+        println!("  - {:<40} [{}]", name, status);
     }
 
     Ok(())
 }
 
-fn run_up(project_dir: &Path, pool: &mut PgPool) -> Result<()> {
+/// Apply pending migrations. With `block_destructive` (production without
+/// `--allow-destructive`) nothing is applied if any pending migration has a
+/// destructive lint finding.
+fn run_up(project_dir: &Path, pool: &mut PgPool, block_destructive: bool) -> Result<()> {
     ensure_migration_table(pool)?;
-    let applied = get_applied_migrations(pool)?;
+    let mut applied = get_applied_migrations(pool)?;
     let migrations_dir = project_dir.join("migrations");
 
     if !migrations_dir.exists() {
         return Err(anyhow::anyhow!("Migrations directory not found."));
     }
 
-    let mut files: Vec<_> = fs::read_dir(migrations_dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter(|p| {
-            p.extension().is_some_and(|ext| ext == "sql")
-                && p.file_name().unwrap().to_str().unwrap().contains(".up")
-        })
-        .collect();
-    files.sort();
+    let files = migration_files(&migrations_dir)?;
+    let mut blocked = 0;
+    for (name, file) in files.iter().filter(|(n, _)| !applied.contains(n)) {
+        let sql = fs::read_to_string(file)?;
+        // Baselines replay migrations that were already linted.
+        if baseline_replaces(&sql).is_some() {
+            continue;
+        }
+        let findings = crate::lint::lint_sql(&sql);
+        if !findings.is_empty() {
+            println!("{}", name.bold());
+        }
+        for finding in findings {
+            if finding.severity == crate::lint::Severity::Destructive {
+                blocked += 1;
+            }
+            println!("  {}", finding);
+        }
+    }
+    if block_destructive && blocked > 0 {
+        return Err(anyhow::anyhow!(
+            "Refusing to apply {} destructive change(s) in production; \
+             re-run with --allow-destructive after reviewing them",
+            blocked
+        ));
+    }
 
     let mut count = 0;
-    for file in files {
-        let full_name = file
-            .file_stem()
-            .unwrap()
-            .to_str()
-            .unwrap()
-            .replace(".up", "");
-        if !applied.contains(&full_name) {
-            println!("{} Applying migration: {}", "↑".green(), full_name);
-            let sql = fs::read_to_string(&file)?;
-            let mut conn = pool.get()?;
-
-            // Execute in transaction
-            conn.execute("BEGIN", &[])?;
-            match conn.execute(&sql, &[]) {
-                Ok(_) => {
-                    conn.execute(
-                        "INSERT INTO chopin_orm_migrations (name) VALUES ($1)",
-                        &[&full_name],
-                    )?;
-                    conn.execute("COMMIT", &[])?;
-                    count += 1;
-                }
-                Err(e) => {
-                    conn.execute("ROLLBACK", &[])?;
-                    return Err(anyhow::anyhow!(
-                        "Failed to apply migration {}: {}",
-                        full_name,
-                        e
-                    ));
+    for (full_name, file) in files {
+        if applied.contains(&full_name) {
+            continue;
+        }
+        let sql = fs::read_to_string(&file)?;
+        let sum = checksum(&sql);
+        let mut conn = pool.get()?;
+
+        // A squashed baseline is only executed on a fresh database; where the
+        // migrations it replaces are already applied it is just recorded.
+        if let Some(replaces) = baseline_replaces(&sql) {
+            let done = replaces.iter().filter(|n| applied.contains(n)).count();
+            if done == replaces.len() && done > 0 {
+                println!("{} Recording baseline: {}", "≡".cyan(), full_name);
+                conn.execute("BEGIN", &[])?;
+                for name in &replaces {
+                    conn.execute("DELETE FROM chopin_orm_migrations WHERE name = $1", &[name])?;
                 }
+                conn.execute(
+                    "INSERT INTO chopin_orm_migrations (name, checksum) VALUES ($1, $2)",
+                    &[&full_name, &sum],
+                )?;
+                conn.execute("COMMIT", &[])?;
+                applied.retain(|n| !replaces.contains(n));
+                applied.push(full_name);
+                count += 1;
+                continue;
+            }
+            if done > 0 {
+                return Err(anyhow::anyhow!(
+                    "Baseline {} replaces migrations that are only partly applied here ({}/{}); \
+                     apply the rest from migrations/squashed/{} first",
+                    full_name,
+                    done,
+                    replaces.len(),
+                    full_name
+                ));
+            }
+        }
+
+        println!("{} Applying migration: {}", "↑".green(), full_name);
+
+        // Execute in transaction
+        conn.execute("BEGIN", &[])?;
+        match conn.execute(&sql, &[]) {
+            Ok(_) => {
+                conn.execute(
+                    "INSERT INTO chopin_orm_migrations (name, checksum) VALUES ($1, $2)",
+                    &[&full_name, &sum],
+                )?;
+                conn.execute("COMMIT", &[])?;
+                applied.push(full_name);
+                count += 1;
+            }
+            Err(e) => {
+                conn.execute("ROLLBACK", &[])?;
+                return Err(anyhow::anyhow!(
+                    "Failed to apply migration {}: {}",
+                    full_name,
+                    e
+                ));
             }
         }
     }
@@ -207,7 +328,32 @@ fn run_down(project_dir: &Path, pool: &mut PgPool, steps: u32) -> Result<()> {
     Ok(())
 }
 
-fn generate_migration(project_dir: &Path, name: &str) -> Result<()> {
+/// `chopin migrate lint`: report findings for every migration file.
+fn lint_migrations(project_dir: &Path) -> Result<()> {
+    let migrations_dir = project_dir.join("migrations");
+    if !migrations_dir.exists() {
+        println!("{} No migrations directory found.", "ℹ".blue());
+        return Ok(());
+    }
+    let mut total = 0;
+    for (name, file) in migration_files(&migrations_dir)? {
+        let findings = crate::lint::lint_sql(&fs::read_to_string(&file)?);
+        if findings.is_empty() {
+            continue;
+        }
+        println!("{}", name.bold());
+        for finding in &findings {
+            println!("  {}", finding);
+        }
+        total += findings.len();
+    }
+    if total == 0 {
+        println!("{} No issues found.", "✓".green());
+    }
+    Ok(())
+}
+
+fn generate_migration(project_dir: &Path, name: &str, sql: Option<&str>) -> Result<()> {
     let migrations_dir = project_dir.join("migrations");
     if !migrations_dir.exists() {
         fs::create_dir_all(&migrations_dir)?;
@@ -219,8 +365,54 @@ fn generate_migration(project_dir: &Path, name: &str) -> Result<()> {
     let up_file = migrations_dir.join(format!("{}.up.sql", base_name));
     let down_file = migrations_dir.join(format!("{}.down.sql", base_name));
 
-    fs::write(&up_file, "-- Write your UP migration here\n")?;
-    fs::write(&down_file, "-- Write your DOWN migration here\n")?;
+    let (up, down) = match sql {
+        None => (
+            "-- Write your UP migration here\n".to_string(),
+            "-- Write your DOWN migration here\n".to_string(),
+        ),
+        Some(sql) => {
+            let statements = split_statements(sql);
+            if statements.is_empty() {
+                return Err(anyhow::anyhow!("No SQL statements given"));
+            }
+            if statements
+                .iter()
+                .any(|s| s.to_ascii_uppercase().contains("CONCURRENTLY"))
+            {
+                println!(
+                    "{} Migrations run inside a transaction; CONCURRENTLY statements will fail.",
+                    "⚠".yellow()
+                );
+            }
+            for finding in crate::lint::lint_sql(sql) {
+                println!("  {}", finding);
+            }
+            let up = format!(
+                "-- Migration: {}\n\n{}\n",
+                base_name,
+                statements
+                    .iter()
+                    .map(|s| format!("{};", s))
+                    .collect::<Vec<_>>()
+                    .join("\n\n")
+            );
+            let down = match derive_down(&statements) {
+                Some(down) => format!("-- Derived from {}.up.sql\n\n{}\n", base_name, down),
+                None => {
+                    println!(
+                        "{} Could not derive a DOWN migration; edit {} by hand.",
+                        "⚠".yellow(),
+                        down_file.display()
+                    );
+                    "-- Write your DOWN migration here\n".to_string()
+                }
+            };
+            (up, down)
+        }
+    };
+
+    fs::write(&up_file, up)?;
+    fs::write(&down_file, down)?;
 
     println!("{} Generated migration files:", "✨".bold());
     println!("  - {}", up_file.display());
@@ -228,3 +420,542 @@ fn generate_migration(project_dir: &Path, name: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// A squash of applied migrations into one baseline.
+#[derive(Debug)]
+struct SquashPlan {
+    replaces: Vec<String>,
+    up: String,
+    down: String,
+}
+
+/// Build the baseline from `files` (`name`, up SQL, down SQL), which must all
+/// be applied with matching checksums. `applied` is in application order.
+fn plan_squash(
+    base_name: &str,
+    files: &[(String, String, Option<String>)],
+    applied: &[(String, Option<String>)],
+) -> Result<SquashPlan> {
+    if let Some((name, _, _)) = files
+        .iter()
+        .find(|(n, _, _)| !applied.iter().any(|(a, _)| a == n))
+    {
+        return Err(anyhow::anyhow!(
+            "Migration {} is pending; run `chopin migrate up` before squashing",
+            name
+        ));
+    }
+    if let Some((name, _)) = applied
+        .iter()
+        .find(|(a, _)| !files.iter().any(|(n, _, _)| n == a))
+    {
+        return Err(anyhow::anyhow!(
+            "Applied migration {} has no file in migrations/",
+            name
+        ));
+    }
+    for (name, up, _) in files {
+        let recorded = applied
+            .iter()
+            .find(|(a, _)| a == name)
+            .and_then(|(_, sum)| sum.as_ref());
+        match recorded {
+            Some(sum) if *sum != checksum(up) => {
+                return Err(anyhow::anyhow!(
+                    "Checksum mismatch for {}: the file was edited after it was applied",
+                    name
+                ));
+            }
+            Some(_) => {}
+            None => println!(
+                "{} {} was applied without a checksum; trusting the file",
+                "⚠".yellow(),
+                name
+            ),
+        }
+    }
+    if files.len() < 2 {
+        return Err(anyhow::anyhow!(
+            "Nothing to squash: fewer than two migrations"
+        ));
+    }
+
+    let replaces: Vec<String> = files.iter().map(|(n, _, _)| n.clone()).collect();
+    let mut up = format!(
+        "-- Migration: {}\n{} {}\n",
+        base_name,
+        BASELINE_MARKER,
+        replaces.join(", ")
+    );
+    for (name, sql, _) in files {
+        // Nested baselines keep their SQL but not their marker.
+        let sql: Vec<&str> = sql
+            .lines()
+            .filter(|l| !l.trim().starts_with(BASELINE_MARKER))
+            .collect();
+        up.push_str(&format!("\n-- ── {} ──\n{}\n", name, sql.join("\n").trim()));
+    }
+    let mut down = format!("-- Migration: {}\n", base_name);
+    for (name, _, sql) in files.iter().rev() {
+        match sql {
+            Some(sql) => down.push_str(&format!("\n-- ── {} ──\n{}\n", name, sql.trim())),
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Down migration file not found for {}",
+                    name
+                ));
+            }
+        }
+    }
+    Ok(SquashPlan { replaces, up, down })
+}
+
+/// `chopin db squash`: collapse every applied migration into one baseline.
+/// The originals are moved to `migrations/squashed/<baseline>/`.
+pub fn squash(project_dir: &Path, db_url: &str, name: &str) -> Result<()> {
+    let migrations_dir = project_dir.join("migrations");
+    if !migrations_dir.exists() {
+        return Err(anyhow::anyhow!("Migrations directory not found."));
+    }
+    let mut pool = PgPool::connect(PgConfig::from_url(db_url)?, 1)?;
+    ensure_migration_table(&mut pool)?;
+    let applied = get_applied_checksums(&mut pool)?;
+
+    let mut files = Vec::new();
+    for (file_name, path) in migration_files(&migrations_dir)? {
+        let down_path = migrations_dir.join(format!("{}.down.sql", file_name));
+        let down = fs::read_to_string(&down_path).ok();
+        files.push((file_name, fs::read_to_string(&path)?, down));
+    }
+
+    let base_name = format!("{}_{}", Local::now().format("%Y%m%d%H%M%S"), name);
+    let plan = plan_squash(&base_name, &files, &applied)?;
+
+    fs::write(
+        migrations_dir.join(format!("{}.up.sql", base_name)),
+        &plan.up,
+    )?;
+    fs::write(
+        migrations_dir.join(format!("{}.down.sql", base_name)),
+        &plan.down,
+    )?;
+
+    let mut conn = pool.get()?;
+    conn.execute("BEGIN", &[])?;
+    for replaced in &plan.replaces {
+        conn.execute(
+            "DELETE FROM chopin_orm_migrations WHERE name = $1",
+            &[replaced],
+        )?;
+    }
+    conn.execute(
+        "INSERT INTO chopin_orm_migrations (name, checksum) VALUES ($1, $2)",
+        &[&base_name, &checksum(&plan.up)],
+    )?;
+    conn.execute("COMMIT", &[])?;
+
+    let archive = migrations_dir.join("squashed").join(&base_name);
+    fs::create_dir_all(&archive)?;
+    for replaced in &plan.replaces {
+        for suffix in ["up.sql", "down.sql"] {
+            let file = format!("{}.{}", replaced, suffix);
+            let from = migrations_dir.join(&file);
+            if from.exists() {
+                fs::rename(&from, archive.join(&file))?;
+            }
+        }
+    }
+
+    println!(
+        "{} Squashed {} migrations into {}",
+        "✓".green(),
+        plan.replaces.len(),
+        base_name.cyan()
+    );
+    println!("  Originals moved to {}", archive.display());
+    println!(
+        "  Other databases record the baseline on their next {} if fully migrated.",
+        "chopin migrate up".yellow()
+    );
+    Ok(())
+}
+
+/// `chopin db baseline`: snapshot the schema of an existing database with
+/// `pg_dump` as a migration and mark it applied, so later migrations can be
+/// managed by Chopin.
+pub fn baseline(project_dir: &Path, db_url: &str, name: &str) -> Result<()> {
+    let migrations_dir = project_dir.join("migrations");
+    fs::create_dir_all(&migrations_dir)?;
+
+    let mut pool = PgPool::connect(PgConfig::from_url(db_url)?, 1)?;
+    ensure_migration_table(&mut pool)?;
+    if !get_applied_migrations(&mut pool)?.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Database already has applied migrations; use `chopin db squash` instead"
+        ));
+    }
+
+    println!("{} Dumping schema with pg_dump...", "💾".bold());
+    let output = std::process::Command::new("pg_dump")
+        .args([
+            "--schema-only",
+            "--no-owner",
+            "--no-privileges",
+            "--exclude-table=chopin_orm_migrations*",
+        ])
+        .arg(db_url)
+        .output()
+        .map_err(|e| anyhow::anyhow!("Failed to run pg_dump: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "pg_dump failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let base_name = format!("{}_{}", Local::now().format("%Y%m%d%H%M%S"), name);
+    let up = format!(
+        "-- Migration: {}\n-- Schema snapshot of an existing database.\n\n{}",
+        base_name,
+        String::from_utf8_lossy(&output.stdout)
+    );
+    fs::write(migrations_dir.join(format!("{}.up.sql", base_name)), &up)?;
+    fs::write(
+        migrations_dir.join(format!("{}.down.sql", base_name)),
+        "DO $$ BEGIN RAISE EXCEPTION 'a schema baseline cannot be rolled back'; END $$;\n",
+    )?;
+
+    let mut conn = pool.get()?;
+    conn.execute(
+        "INSERT INTO chopin_orm_migrations (name, checksum) VALUES ($1, $2)",
+        &[&base_name, &checksum(&up)],
+    )?;
+
+    println!(
+        "{} Recorded {} as applied; new migrations will run on top of it.",
+        "✓".green(),
+        base_name.cyan()
+    );
+    Ok(())
+}
+
+/// Split a SQL script into statements on top-level `;`, keeping quoted
+/// strings, quoted identifiers, dollar-quoted bodies and comments intact.
+/// Returned statements are trimmed and have no trailing `;`.
+pub(crate) fn split_statements(sql: &str) -> Vec<String> {
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' | b'"' => {
+                let quote = bytes[i];
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    i += 1;
+                }
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                // `$tag$ ... $tag$`; a lone `$1` parameter has no closing `$`.
+                let tag_end = sql[i + 1..]
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .map(|n| i + 1 + n);
+                if let Some(end) = tag_end.filter(|&e| bytes[e] == b'$') {
+                    let tag = &sql[i..=end];
+                    match sql[end + 1..].find(tag) {
+                        Some(close) => i = end + 1 + close + tag.len() - 1,
+                        None => i = bytes.len(),
+                    }
+                }
+            }
+            b';' => {
+                push_statement(&mut statements, &sql[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    push_statement(&mut statements, &sql[start.min(sql.len())..]);
+    statements
+}
+
+fn push_statement(statements: &mut Vec<String>, stmt: &str) {
+    let has_code = stmt
+        .lines()
+        .map(str::trim)
+        .any(|l| !l.is_empty() && !l.starts_with("--"));
+    if has_code {
+        statements.push(stmt.trim().to_string());
+    }
+}
+
+/// Build the DOWN script for `statements`, undoing them in reverse order.
+/// Returns `None` if any statement has no mechanical inverse.
+fn derive_down(statements: &[String]) -> Option<String> {
+    let mut down = Vec::with_capacity(statements.len());
+    for stmt in statements.iter().rev() {
+        down.push(invert_statement(stmt)?);
+    }
+    Some(down.join("\n"))
+}
+
+fn invert_statement(stmt: &str) -> Option<String> {
+    // Drop comment lines, then tokenize; `(` ends a name (`CREATE TABLE t(...)`).
+    let code: String = stmt
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("--"))
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('(', " ( ");
+    let tokens: Vec<&str> = code.split_whitespace().collect();
+    let upper: Vec<String> = tokens.iter().map(|t| t.to_ascii_uppercase()).collect();
+    let kw = |i: usize, word: &str| upper.get(i).is_some_and(|t| t == word);
+
+    // Skip optional keywords starting at `i`, returning the next index.
+    let skip = |mut i: usize, words: &[&str]| {
+        while words.iter().any(|w| kw(i, w)) {
+            i += 1;
+        }
+        i
+    };
+    let if_not_exists = |i: usize| {
+        if kw(i, "IF") && kw(i + 1, "NOT") && kw(i + 2, "EXISTS") {
+            i + 3
+        } else {
+            i
+        }
+    };
+    let name_at = |i: usize| tokens.get(i).filter(|t| **t != "(").copied();
+
+    if kw(0, "CREATE") {
+        let i = skip(
+            1,
+            &["UNIQUE", "MATERIALIZED", "TEMP", "TEMPORARY", "UNLOGGED"],
+        );
+        let object = upper.get(i)?.as_str();
+        let drop_kind = match object {
+            "TABLE" => "TABLE",
+            "INDEX" => "INDEX",
+            "VIEW" if kw(i - 1, "MATERIALIZED") => "MATERIALIZED VIEW",
+            "VIEW" => "VIEW",
+            "SEQUENCE" => "SEQUENCE",
+            "TYPE" => "TYPE",
+            "EXTENSION" => "EXTENSION",
+            "SCHEMA" => "SCHEMA",
+            _ => return None,
+        };
+        let mut n = skip(i + 1, &["CONCURRENTLY"]);
+        n = if_not_exists(n);
+        let name = name_at(n)?;
+        // `CREATE INDEX ON t (...)` has no name to drop.
+        if upper[n] == "ON" {
+            return None;
+        }
+        return Some(format!("DROP {} IF EXISTS {};", drop_kind, name));
+    }
+
+    if kw(0, "ALTER") && kw(1, "TABLE") {
+        let i = skip(2, &["ONLY"]);
+        let i = if kw(i, "IF") && kw(i + 1, "EXISTS") {
+            i + 2
+        } else {
+            i
+        };
+        let table = name_at(i)?;
+        let rest = i + 1;
+        if kw(rest, "ADD") {
+            let c = skip(rest + 1, &["COLUMN"]);
+            // `ADD CONSTRAINT name ...` is reversible by name too.
+            if kw(c, "CONSTRAINT") {
+                let name = name_at(c + 1)?;
+                return Some(format!(
+                    "ALTER TABLE {} DROP CONSTRAINT IF EXISTS {};",
+                    table, name
+                ));
+            }
+            let c = if_not_exists(c);
+            let column = name_at(c)?;
+            if matches!(
+                upper[c].as_str(),
+                "PRIMARY" | "UNIQUE" | "CHECK" | "FOREIGN" | "EXCLUDE"
+            ) {
+                return None;
+            }
+            if upper.iter().filter(|t| *t == "ADD").count() > 1 {
+                // Several ADD clauses in one statement; keep it simple.
+                return None;
+            }
+            return Some(format!(
+                "ALTER TABLE {} DROP COLUMN IF EXISTS {};",
+                table, column
+            ));
+        }
+        if kw(rest, "RENAME") {
+            if kw(rest + 1, "TO") {
+                let new = name_at(rest + 2)?;
+                return Some(format!("ALTER TABLE {} RENAME TO {};", new, table));
+            }
+            let c = skip(rest + 1, &["COLUMN"]);
+            if kw(c + 1, "TO") {
+                let (old, new) = (name_at(c)?, name_at(c + 2)?);
+                return Some(format!(
+                    "ALTER TABLE {} RENAME COLUMN {} TO {};",
+                    table, new, old
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements_respects_quotes_and_dollar_bodies() {
+        let sql = "CREATE TABLE a (x TEXT DEFAULT ';');\n\
+                   -- comment; not a statement\n\
+                   CREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;\n\
+                   SELECT $1;";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].ends_with("DEFAULT ';')"));
+        assert!(statements[1].contains("SELECT 1; $$"));
+        assert_eq!(statements[2], "SELECT $1");
+        assert!(split_statements("  -- only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn test_derive_down_reverses_statements() {
+        let statements = split_statements(
+            "CREATE TABLE IF NOT EXISTS posts(id SERIAL PRIMARY KEY);\n\
+             CREATE UNIQUE INDEX CONCURRENTLY posts_slug_idx ON posts (slug);\n\
+             ALTER TABLE posts ADD COLUMN IF NOT EXISTS slug TEXT;\n\
+             ALTER TABLE posts ADD price NUMERIC(10, 2);\n\
+             ALTER TABLE posts RENAME COLUMN title TO headline;",
+        );
+        assert_eq!(
+            derive_down(&statements).unwrap(),
+            "ALTER TABLE posts RENAME COLUMN headline TO title;\n\
+             ALTER TABLE posts DROP COLUMN IF EXISTS price;\n\
+             ALTER TABLE posts DROP COLUMN IF EXISTS slug;\n\
+             DROP INDEX IF EXISTS posts_slug_idx;\n\
+             DROP TABLE IF EXISTS posts;"
+        );
+    }
+
+    #[test]
+    fn test_derive_down_gives_up_on_irreversible_sql() {
+        for sql in [
+            "UPDATE users SET active = true",
+            "DROP TABLE users",
+            "CREATE INDEX ON users (email)",
+            "ALTER TABLE users ADD PRIMARY KEY (id)",
+            "CREATE TABLE a (id INT); DELETE FROM b",
+            "ALTER TABLE users ADD a INT, ADD b INT",
+        ] {
+            assert!(derive_down(&split_statements(sql)).is_none(), "{sql}");
+        }
+        assert_eq!(
+            derive_down(&split_statements(
+                "ALTER TABLE users ADD CONSTRAINT users_email_key UNIQUE (email)"
+            ))
+            .unwrap(),
+            "ALTER TABLE users DROP CONSTRAINT IF EXISTS users_email_key;"
+        );
+    }
+
+    #[test]
+    fn test_generate_migration_from_sql() {
+        let dir = tempfile::tempdir().unwrap();
+        generate_migration(
+            dir.path(),
+            "add_index",
+            Some("CREATE INDEX users_email_idx ON users (email)"),
+        )
+        .unwrap();
+        let mut files: Vec<_> = fs::read_dir(dir.path().join("migrations"))
+            .unwrap()
+            .map(|e| e.unwrap().path())
+            .collect();
+        files.sort();
+        let down = fs::read_to_string(&files[0]).unwrap();
+        let up = fs::read_to_string(&files[1]).unwrap();
+        assert!(up.contains("CREATE INDEX users_email_idx ON users (email);"));
+        assert!(down.contains("DROP INDEX IF EXISTS users_email_idx;"));
+    }
+
+    fn file(name: &str, up: &str) -> (String, String, Option<String>) {
+        (
+            name.to_string(),
+            up.to_string(),
+            Some(format!("-- undo {}", name)),
+        )
+    }
+
+    #[test]
+    fn test_plan_squash_builds_baseline() {
+        let files = [
+            file("001_a", "CREATE TABLE a ();"),
+            file("002_b", "CREATE TABLE b ();"),
+        ];
+        let applied = [
+            ("001_a".to_string(), Some(checksum("CREATE TABLE a ();"))),
+            ("002_b".to_string(), None),
+        ];
+        let plan = plan_squash("003_baseline", &files, &applied).unwrap();
+        assert_eq!(plan.replaces, ["001_a", "002_b"]);
+        assert_eq!(baseline_replaces(&plan.up).unwrap(), ["001_a", "002_b"]);
+        assert!(plan.up.find("TABLE a").unwrap() < plan.up.find("TABLE b").unwrap());
+        assert!(plan.down.find("undo 002_b").unwrap() < plan.down.find("undo 001_a").unwrap());
+    }
+
+    #[test]
+    fn test_plan_squash_rejects_edits_and_pending() {
+        let files = [
+            file("001_a", "CREATE TABLE a ();"),
+            file("002_b", "CREATE TABLE b ();"),
+        ];
+        let edited = [
+            ("001_a".to_string(), Some(checksum("CREATE TABLE x ();"))),
+            ("002_b".to_string(), None),
+        ];
+        let err = plan_squash("b", &files, &edited).unwrap_err().to_string();
+        assert!(err.contains("Checksum mismatch for 001_a"), "{err}");
+
+        let pending = [("001_a".to_string(), None)];
+        let err = plan_squash("b", &files, &pending).unwrap_err().to_string();
+        assert!(err.contains("002_b is pending"), "{err}");
+    }
+
+    #[test]
+    fn test_migration_files_sorted_up_only() {
+        let dir = tempfile::tempdir().unwrap();
+        for f in ["002_b.up.sql", "001_a.up.sql", "001_a.down.sql", "notes.md"] {
+            fs::write(dir.path().join(f), "").unwrap();
+        }
+        let names: Vec<String> = migration_files(dir.path())
+            .unwrap()
+            .into_iter()
+            .map(|(n, _)| n)
+            .collect();
+        assert_eq!(names, ["001_a", "002_b"]);
+    }
+}
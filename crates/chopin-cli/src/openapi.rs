@@ -118,3 +118,75 @@ pub fn generate_openapi(project_dir: &Path) -> Result<()> {
 
     Ok(())
 }
+
+/// Exports the OpenAPI spec of the user's application.
+///
+/// Unlike [`generate_openapi`], which scrapes `handlers.rs` files, this runs
+/// the app with the hidden `--print-openapi` flag so the exported document
+/// contains every route the app actually registers.
+pub fn export_openapi(project_dir: &Path, output: &str) -> Result<()> {
+    println!("{} Building app to export its OpenAPI spec...", "📜".bold());
+
+    let out = std::process::Command::new("cargo")
+        .args(["run", "--quiet", "--", "--print-openapi"])
+        .current_dir(project_dir)
+        .stderr(std::process::Stdio::inherit())
+        .output()?;
+    if !out.status.success() {
+        anyhow::bail!("`cargo run -- --print-openapi` exited with {}", out.status);
+    }
+
+    let spec = extract_spec(&String::from_utf8_lossy(&out.stdout))?;
+    let output_path = project_dir.join(output);
+    std::fs::write(&output_path, serde_json::to_string_pretty(&spec)?)?;
+
+    let path_count = spec["paths"].as_object().map(|p| p.len()).unwrap_or(0);
+    println!(
+        "{} Saved {} paths to {}",
+        "✓".green().bold(),
+        path_count,
+        output.cyan()
+    );
+    Ok(())
+}
+
+/// Finds the JSON spec in the app's stdout, skipping any banner lines the app
+/// printed before `Chopin::serve` was reached.
+fn extract_spec(stdout: &str) -> Result<serde_json::Value> {
+    let start = stdout
+        .lines()
+        .scan(0usize, |offset, line| {
+            let at = *offset;
+            *offset += line.len() + 1;
+            Some((at, line))
+        })
+        .find(|(_, line)| line.trim_end() == "{")
+        .map(|(at, _)| at)
+        .ok_or_else(|| {
+            anyhow::anyhow!("No OpenAPI spec in output. Does the app call Chopin::serve?")
+        })?;
+
+    let spec: serde_json::Value = serde_json::from_str(&stdout[start..])?;
+    if spec.get("openapi").is_none() {
+        anyhow::bail!("Output is JSON but not an OpenAPI document");
+    }
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_spec_skips_banner() {
+        let stdout = "🎹 Starting app server on 0.0.0.0:8080\n{\n  \"openapi\": \"3.0.0\",\n  \"paths\": {}\n}\n";
+        let spec = extract_spec(stdout).unwrap();
+        assert_eq!(spec["openapi"], "3.0.0");
+    }
+
+    #[test]
+    fn test_extract_spec_missing() {
+        assert!(extract_spec("listening on :8080\n").is_err());
+        assert!(extract_spec("{\n  \"a\": 1\n}\n").is_err());
+    }
+}
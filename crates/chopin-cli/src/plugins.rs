@@ -0,0 +1,41 @@
+use anyhow::Result;
+use colored::*;
+use std::path::Path;
+use std::process::Command;
+
+/// Runs the user's application with `app_args`, passing its output through.
+///
+/// Plugins register themselves when their crate is linked into the app, so
+/// only the app binary knows which are installed; the CLI forwards to it the
+/// same way `chopin docs export` does.
+fn run_app(project_dir: &Path, app_args: &[String]) -> Result<()> {
+    let status = Command::new("cargo")
+        .args(["run", "--quiet", "--"])
+        .args(app_args)
+        .current_dir(project_dir)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!(
+            "`cargo run -- {}` exited with {}",
+            app_args.join(" "),
+            status
+        );
+    }
+    Ok(())
+}
+
+/// `chopin plugins`: list the plugins mounted by the app.
+pub fn list_plugins(project_dir: &Path) -> Result<()> {
+    println!("{} Building app to list its plugins...", "🧩".bold());
+    run_app(project_dir, &["--print-plugins".to_string()])
+}
+
+/// `chopin run <plugin>:<command> [args...]`: run a plugin command.
+pub fn run_plugin_command(project_dir: &Path, command: &str, args: &[String]) -> Result<()> {
+    if !command.contains(':') {
+        anyhow::bail!("plugin commands are written <plugin>:<command>, e.g. cms:import");
+    }
+    let mut app_args = vec!["--run".to_string(), command.to_string()];
+    app_args.extend_from_slice(args);
+    run_app(project_dir, &app_args)
+}
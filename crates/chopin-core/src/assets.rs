@@ -0,0 +1,316 @@
+// src/assets.rs
+//! Static files compiled into the binary, for single-binary deployments.
+//!
+//! [`embed_assets!`](crate::embed_assets) reads a directory at build time and
+//! yields an [`Assets`] table. Every file is listed under its own path and
+//! under a content-hashed name (`css/app.css` → `css/app.3f9a1c2e.css`), and
+//! text formats carry a gzip copy compressed at build time. [`AssetsModule`]
+//! serves the table:
+//!
+//! ```rust,ignore
+//! static PUBLIC: Assets = embed_assets!("./public");
+//!
+//! Chopin::new().mount_module(AssetsModule::new("/assets", &PUBLIC));
+//!
+//! // in a template
+//! let css = assets::url("css/app.css").unwrap(); // "/assets/css/app.3f9a1c2e.css"
+//! ```
+//!
+//! Each file gets an exact route, so lookups take the router's static fast
+//! path instead of a wildcard match. Hashed URLs are cached for a year as
+//! `immutable`; plain URLs must be revalidated, which the `ETag` makes a
+//! cheap `304`. Clients sending `Accept-Encoding: gzip` get the gzip copy.
+use crate::cache::CachePolicy;
+use crate::http::{Body, Context, Response, mime_from_path};
+use crate::module::ChopinModule;
+use crate::router::Router;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+/// Lifetime of hashed URLs, whose content can never change.
+pub const IMMUTABLE_MAX_AGE: u32 = 365 * 24 * 60 * 60;
+
+/// One embedded file. Built by [`embed_assets!`](crate::embed_assets).
+#[derive(Debug)]
+pub struct Asset {
+    /// Path relative to the embedded directory, with `/` separators.
+    pub path: &'static str,
+    /// `path` with a content hash before the extension.
+    pub hashed: &'static str,
+    /// Quoted strong ETag derived from the content.
+    pub etag: &'static str,
+    pub body: &'static [u8],
+    /// Gzip-compressed body, for compressible types where it is smaller.
+    pub gzip: Option<&'static [u8]>,
+}
+
+impl Asset {
+    pub fn content_type(&self) -> &'static str {
+        mime_from_path(self.path)
+    }
+}
+
+/// The files embedded by one [`embed_assets!`](crate::embed_assets) call.
+#[derive(Debug)]
+pub struct Assets {
+    files: &'static [Asset],
+}
+
+impl Assets {
+    #[doc(hidden)]
+    pub const fn new(files: &'static [Asset]) -> Self {
+        Self { files }
+    }
+
+    pub fn files(&self) -> &'static [Asset] {
+        self.files
+    }
+
+    /// Look a file up by its original or hashed path.
+    pub fn get(&self, path: &str) -> Option<&'static Asset> {
+        let path = path.trim_start_matches('/');
+        self.files
+            .iter()
+            .find(|a| a.path == path || a.hashed == path)
+    }
+
+    /// Hashed path of a file, relative to the embedded directory.
+    pub fn hashed(&self, path: &str) -> Option<&'static str> {
+        self.get(path).map(|a| a.hashed)
+    }
+
+    /// `{"css/app.css": "css/app.3f9a1c2e.css", ...}`, for handing the
+    /// mapping to a frontend build or template engine.
+    pub fn manifest(&self) -> serde_json::Value {
+        self.files
+            .iter()
+            .map(|a| (a.path.to_string(), serde_json::Value::from(a.hashed)))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+struct Route {
+    asset: &'static Asset,
+    immutable: bool,
+}
+
+#[derive(Default)]
+struct Mounted {
+    /// Request path → file.
+    routes: HashMap<String, Route>,
+    /// Original path → public hashed URL, for [`url`].
+    urls: HashMap<String, String>,
+}
+
+static MOUNTED: RwLock<Option<Mounted>> = RwLock::new(None);
+
+/// Public URL of the hashed copy of `path`, e.g. `css/app.css` →
+/// `/assets/css/app.3f9a1c2e.css`. `None` until an [`AssetsModule`] holding
+/// the file has been mounted. When several modules hold the same path, the
+/// one mounted last wins.
+pub fn url(path: &str) -> Option<String> {
+    let mounted = MOUNTED.read().unwrap_or_else(|e| e.into_inner());
+    mounted
+        .as_ref()?
+        .urls
+        .get(path.trim_start_matches('/'))
+        .cloned()
+}
+
+fn accepts_gzip(ctx: &Context) -> bool {
+    ctx.header("accept-encoding").is_some_and(|v| {
+        v.split(',').any(|enc| {
+            let mut parts = enc.split(';');
+            let name = parts.next().unwrap_or("").trim();
+            let refused = parts.any(|p| {
+                p.trim()
+                    .strip_prefix("q=")
+                    .and_then(|q| q.parse::<f32>().ok())
+                    == Some(0.0)
+            });
+            (name.eq_ignore_ascii_case("gzip") || name == "*") && !refused
+        })
+    })
+}
+
+fn etag_matches(ctx: &Context, etag: &str) -> bool {
+    ctx.header("if-none-match").is_some_and(|v| {
+        v.split(',')
+            .map(|t| t.trim().trim_start_matches("W/"))
+            .any(|t| t == etag || t == "*")
+    })
+}
+
+fn serve(asset: &'static Asset, immutable: bool, ctx: &Context) -> Response {
+    let mut policy = if immutable {
+        CachePolicy::public().max_age(IMMUTABLE_MAX_AGE).immutable()
+    } else {
+        CachePolicy::public().max_age(0).must_revalidate()
+    };
+    if asset.gzip.is_some() {
+        policy = policy.vary("Accept-Encoding");
+    }
+
+    if etag_matches(ctx, asset.etag) {
+        let res = Response::new(304).with_header("ETag", asset.etag);
+        return policy.apply(res);
+    }
+
+    let mut res = Response::new(200);
+    res.content_type = asset.content_type();
+    res.body = Body::Static(asset.body);
+    if let Some(gz) = asset.gzip.filter(|_| accepts_gzip(ctx)) {
+        res.body = Body::Static(gz);
+        res = res.with_header("Content-Encoding", "gzip");
+    }
+    policy.apply(res.with_header("ETag", asset.etag))
+}
+
+fn asset_handler(ctx: Context) -> Response {
+    let mounted = MOUNTED.read().unwrap_or_else(|e| e.into_inner());
+    match mounted.as_ref().and_then(|m| m.routes.get(ctx.req.path)) {
+        Some(route) => serve(route.asset, route.immutable, &ctx),
+        None => Response::not_found(),
+    }
+}
+
+/// Serves an [`Assets`] table under `prefix`, at both the original and the
+/// hashed path of every file.
+pub struct AssetsModule {
+    prefix: &'static str,
+    assets: &'static Assets,
+}
+
+impl AssetsModule {
+    pub fn new(prefix: &'static str, assets: &'static Assets) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+            assets,
+        }
+    }
+}
+
+impl ChopinModule for AssetsModule {
+    fn name(&self) -> &'static str {
+        "assets"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        let mut mounted = MOUNTED.write().unwrap_or_else(|e| e.into_inner());
+        let mounted = mounted.get_or_insert_with(Mounted::default);
+        for asset in self.assets.files {
+            let plain = format!("{}/{}", self.prefix, asset.path);
+            let hashed = format!("{}/{}", self.prefix, asset.hashed);
+            for (path, immutable) in [(&plain, false), (&hashed, true)] {
+                router.get(path, asset_handler);
+                router.head(path, asset_handler);
+                mounted
+                    .routes
+                    .insert(path.clone(), Route { asset, immutable });
+            }
+            mounted.urls.insert(asset.path.to_string(), hashed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+
+    static FILES: Assets = Assets::new(&[
+        Asset {
+            path: "css/app.css",
+            hashed: "css/app.0badf00d.css",
+            etag: "\"0badf00d00000001\"",
+            body: b"body { color: red }",
+            gzip: Some(b"gzipped"),
+        },
+        Asset {
+            path: "logo.png",
+            hashed: "logo.12345678.png",
+            etag: "\"1234567800000002\"",
+            body: b"\x89PNG",
+            gzip: None,
+        },
+    ]);
+
+    fn app() -> TestApp {
+        let mut router = Router::new();
+        AssetsModule::new("/static-test/", &FILES).routes(&mut router);
+        TestApp::new(router)
+    }
+
+    #[test]
+    fn test_serves_plain_and_hashed_paths() {
+        let app = app();
+        let plain = app.get("/static-test/css/app.css");
+        assert_eq!(plain.status, 200);
+        assert_eq!(plain.content_type, "text/css; charset=utf-8");
+        assert_eq!(plain.text(), "body { color: red }");
+        assert_eq!(plain.header("ETag"), Some("\"0badf00d00000001\""));
+        assert_eq!(
+            plain.header("Cache-Control"),
+            Some("public, max-age=0, must-revalidate")
+        );
+        assert_eq!(plain.header("Vary"), Some("Accept-Encoding"));
+
+        let hashed = app.get("/static-test/logo.12345678.png");
+        assert_eq!(hashed.body, b"\x89PNG");
+        assert_eq!(
+            hashed.header("Cache-Control"),
+            Some("public, max-age=31536000, immutable")
+        );
+        assert_eq!(hashed.header("Vary"), None);
+
+        assert_eq!(app.get("/static-test/missing.css").status, 404);
+        assert_eq!(
+            url("css/app.css").as_deref(),
+            Some("/static-test/css/app.0badf00d.css")
+        );
+    }
+
+    #[test]
+    fn test_gzip_and_conditional_requests() {
+        let app = app();
+        let gz = app.request(
+            crate::http::Method::Get,
+            "/static-test/css/app.css",
+            &[("Accept-Encoding", "br, gzip;q=0.8")],
+            b"",
+        );
+        assert_eq!(gz.body, b"gzipped");
+        assert_eq!(gz.header("Content-Encoding"), Some("gzip"));
+
+        let refused = app.request(
+            crate::http::Method::Get,
+            "/static-test/css/app.css",
+            &[("Accept-Encoding", "gzip;q=0")],
+            b"",
+        );
+        assert_eq!(refused.header("Content-Encoding"), None);
+
+        let cached = app.request(
+            crate::http::Method::Get,
+            "/static-test/css/app.css",
+            &[("If-None-Match", "W/\"0badf00d00000001\"")],
+            b"",
+        );
+        assert_eq!(cached.status, 304);
+        assert!(cached.body.is_empty());
+    }
+
+    #[test]
+    fn test_manifest_maps_paths_to_hashed_names() {
+        assert_eq!(
+            FILES.manifest(),
+            serde_json::json!({
+                "css/app.css": "css/app.0badf00d.css",
+                "logo.png": "logo.12345678.png",
+            })
+        );
+        assert_eq!(FILES.get("/logo.12345678.png").unwrap().path, "logo.png");
+        assert_eq!(FILES.hashed("logo.png"), Some("logo.12345678.png"));
+    }
+}
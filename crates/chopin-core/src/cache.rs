@@ -0,0 +1,355 @@
+// src/cache.rs
+//! HTTP caching headers for browsers, CDNs and reverse proxies.
+//!
+//! [`CachePolicy`] builds a consistent set of `Cache-Control`,
+//! `Surrogate-Control` and `Vary` headers:
+//!
+//! ```rust,ignore
+//! CachePolicy::public()
+//!     .max_age(300)
+//!     .stale_while_revalidate(60)
+//!     .vary("Accept-Encoding")
+//!     .apply(Response::json(&articles))
+//! ```
+//!
+//! Defaults per route prefix can be declared in `Chopin.toml` and applied by
+//! the [`cache_defaults`] middleware to responses that did not set
+//! `Cache-Control` themselves. The longest matching prefix wins:
+//!
+//! ```toml
+//! [cache."/static"]
+//! visibility = "public"
+//! max_age = 86400
+//! immutable = true
+//!
+//! [cache."/api"]
+//! visibility = "no-store"
+//! ```
+//!
+//! Call [`load_cache_defaults`] at startup, and again from a
+//! [`config::subscribe`](crate::config::subscribe) listener to pick up reloads.
+use crate::config::Config;
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Method, Response};
+use crate::router::BoxedHandler;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// Config section read by [`load_cache_defaults`].
+pub const CACHE_SECTION: &str = "cache";
+
+/// Who may store the response.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Visibility {
+    Public,
+    Private,
+    NoStore,
+}
+
+/// A caching policy rendered into response headers.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CachePolicy {
+    pub visibility: Visibility,
+    #[serde(default)]
+    pub max_age: Option<u32>,
+    /// Shared-cache lifetime (`s-maxage`).
+    #[serde(default)]
+    pub s_maxage: Option<u32>,
+    #[serde(default)]
+    pub stale_while_revalidate: Option<u32>,
+    #[serde(default)]
+    pub stale_if_error: Option<u32>,
+    #[serde(default)]
+    pub immutable: bool,
+    #[serde(default)]
+    pub must_revalidate: bool,
+    /// Lifetime for CDN edge caches, sent as `Surrogate-Control`.
+    #[serde(default)]
+    pub surrogate_max_age: Option<u32>,
+    #[serde(default)]
+    pub vary: Vec<String>,
+}
+
+impl CachePolicy {
+    fn with_visibility(visibility: Visibility) -> Self {
+        Self {
+            visibility,
+            max_age: None,
+            s_maxage: None,
+            stale_while_revalidate: None,
+            stale_if_error: None,
+            immutable: false,
+            must_revalidate: false,
+            surrogate_max_age: None,
+            vary: Vec::new(),
+        }
+    }
+
+    /// Cacheable by browsers and shared caches.
+    pub fn public() -> Self {
+        Self::with_visibility(Visibility::Public)
+    }
+
+    /// Cacheable by the browser only.
+    pub fn private() -> Self {
+        Self::with_visibility(Visibility::Private)
+    }
+
+    /// Never stored by any cache.
+    pub fn no_store() -> Self {
+        Self::with_visibility(Visibility::NoStore)
+    }
+
+    pub fn max_age(mut self, secs: u32) -> Self {
+        self.max_age = Some(secs);
+        self
+    }
+
+    pub fn s_maxage(mut self, secs: u32) -> Self {
+        self.s_maxage = Some(secs);
+        self
+    }
+
+    pub fn stale_while_revalidate(mut self, secs: u32) -> Self {
+        self.stale_while_revalidate = Some(secs);
+        self
+    }
+
+    pub fn stale_if_error(mut self, secs: u32) -> Self {
+        self.stale_if_error = Some(secs);
+        self
+    }
+
+    /// The response never changes at this URL (fingerprinted assets).
+    pub fn immutable(mut self) -> Self {
+        self.immutable = true;
+        self
+    }
+
+    pub fn must_revalidate(mut self) -> Self {
+        self.must_revalidate = true;
+        self
+    }
+
+    pub fn surrogate_max_age(mut self, secs: u32) -> Self {
+        self.surrogate_max_age = Some(secs);
+        self
+    }
+
+    /// Add a request header the response varies on.
+    pub fn vary(mut self, header: &str) -> Self {
+        if !self.vary.iter().any(|h| h.eq_ignore_ascii_case(header)) {
+            self.vary.push(header.to_string());
+        }
+        self
+    }
+
+    /// The `Cache-Control` value. For `no-store` every other directive is
+    /// dropped, since none of them apply.
+    pub fn cache_control(&self) -> String {
+        let mut parts: Vec<String> = Vec::new();
+        match self.visibility {
+            Visibility::NoStore => return "no-store".to_string(),
+            Visibility::Public => parts.push("public".into()),
+            Visibility::Private => parts.push("private".into()),
+        }
+        if let Some(s) = self.max_age {
+            parts.push(format!("max-age={s}"));
+        }
+        if let Some(s) = self
+            .s_maxage
+            .filter(|_| self.visibility == Visibility::Public)
+        {
+            parts.push(format!("s-maxage={s}"));
+        }
+        if let Some(s) = self.stale_while_revalidate {
+            parts.push(format!("stale-while-revalidate={s}"));
+        }
+        if let Some(s) = self.stale_if_error {
+            parts.push(format!("stale-if-error={s}"));
+        }
+        if self.must_revalidate {
+            parts.push("must-revalidate".into());
+        }
+        if self.immutable {
+            parts.push("immutable".into());
+        }
+        parts.join(", ")
+    }
+
+    /// Set the policy's headers on `res`.
+    pub fn apply(&self, res: Response) -> Response {
+        let mut res = res.with_header("Cache-Control", self.cache_control());
+        if self.visibility == Visibility::Public
+            && let Some(secs) = self.surrogate_max_age
+        {
+            res = res.with_header("Surrogate-Control", format!("max-age={secs}"));
+        }
+        if !self.vary.is_empty() {
+            res = res.with_header("Vary", self.vary.join(", "));
+        }
+        res
+    }
+}
+
+static DEFAULTS: RwLock<Vec<(String, CachePolicy)>> = RwLock::new(Vec::new());
+
+/// Replace the per-prefix defaults used by [`cache_defaults`].
+pub fn set_cache_defaults(rules: impl IntoIterator<Item = (String, CachePolicy)>) {
+    let mut rules: Vec<_> = rules.into_iter().collect();
+    // Longest prefix first so the first match is the most specific.
+    rules.sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+    if let Ok(mut defaults) = DEFAULTS.write() {
+        *defaults = rules;
+    }
+}
+
+/// Load defaults from the `[cache]` section of `config`. A missing section
+/// clears them.
+pub fn load_cache_defaults(config: &Config) -> ChopinResult<()> {
+    if !config.has_section(CACHE_SECTION) {
+        set_cache_defaults(Vec::new());
+        return Ok(());
+    }
+    let rules: BTreeMap<String, CachePolicy> = config.extension(CACHE_SECTION)?;
+    if let Some(bad) = rules.keys().find(|p| !p.starts_with('/')) {
+        return Err(ChopinError::Other(format!(
+            "[cache] prefix `{bad}` must start with `/`"
+        )));
+    }
+    set_cache_defaults(rules);
+    Ok(())
+}
+
+/// Default policy for `path`, if any prefix matches.
+pub fn default_policy(path: &str) -> Option<CachePolicy> {
+    let defaults = DEFAULTS.read().ok()?;
+    defaults
+        .iter()
+        .find(|(prefix, _)| prefix_matches(prefix, path))
+        .map(|(_, policy)| policy.clone())
+}
+
+pub(crate) fn prefix_matches(prefix: &str, path: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    prefix.is_empty()
+        || path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Middleware applying the configured default policy to `GET`/`HEAD`
+/// responses that have no `Cache-Control` header of their own.
+pub fn cache_defaults(ctx: Context, next: BoxedHandler) -> Response {
+    let policy = match ctx.req.method {
+        Method::Get | Method::Head => default_policy(ctx.req.path),
+        _ => None,
+    };
+    let res = next(ctx);
+    match policy {
+        Some(policy)
+            if !res
+                .headers
+                .iter()
+                .any(|h| h.name.eq_ignore_ascii_case("Cache-Control")) =>
+        {
+            policy.apply(res)
+        }
+        _ => res,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header<'a>(res: &'a Response, name: &str) -> Option<&'a str> {
+        res.headers
+            .iter()
+            .find(|h| h.name == name)
+            .map(|h| h.value.as_str())
+    }
+
+    #[test]
+    fn test_cache_control_rendering() {
+        let policy = CachePolicy::public()
+            .max_age(300)
+            .s_maxage(600)
+            .stale_while_revalidate(60)
+            .immutable();
+        assert_eq!(
+            policy.cache_control(),
+            "public, max-age=300, s-maxage=600, stale-while-revalidate=60, immutable"
+        );
+        assert_eq!(
+            CachePolicy::private()
+                .max_age(10)
+                .s_maxage(99)
+                .cache_control(),
+            "private, max-age=10"
+        );
+        assert_eq!(
+            CachePolicy::no_store().max_age(10).cache_control(),
+            "no-store"
+        );
+    }
+
+    #[test]
+    fn test_apply_sets_surrogate_and_vary() {
+        let res = CachePolicy::public()
+            .max_age(60)
+            .surrogate_max_age(3600)
+            .vary("Accept-Encoding")
+            .vary("accept-encoding")
+            .vary("Accept-Language")
+            .apply(Response::text("x"));
+        assert_eq!(header(&res, "Cache-Control"), Some("public, max-age=60"));
+        assert_eq!(header(&res, "Surrogate-Control"), Some("max-age=3600"));
+        assert_eq!(
+            header(&res, "Vary"),
+            Some("Accept-Encoding, Accept-Language")
+        );
+
+        let res = CachePolicy::private()
+            .surrogate_max_age(3600)
+            .apply(Response::text("x"));
+        assert_eq!(header(&res, "Surrogate-Control"), None);
+    }
+
+    #[test]
+    fn test_config_defaults_longest_prefix() {
+        let config = Config::parse(
+            r#"
+            [cache."/static"]
+            visibility = "public"
+            max_age = 86400
+            immutable = true
+
+            [cache."/static/private"]
+            visibility = "no-store"
+            "#,
+        )
+        .unwrap();
+        load_cache_defaults(&config).unwrap();
+
+        assert_eq!(
+            default_policy("/static/app.css").unwrap().cache_control(),
+            "public, max-age=86400, immutable"
+        );
+        assert_eq!(
+            default_policy("/static/private/x").unwrap().cache_control(),
+            "no-store"
+        );
+        assert!(default_policy("/staticfile").is_none());
+
+        let bad = Config::parse("[cache.static]\nvisibility = \"public\"").unwrap();
+        assert!(load_cache_defaults(&bad).is_err());
+
+        load_cache_defaults(&Config::default()).unwrap();
+        assert!(default_policy("/static/app.css").is_none());
+    }
+}
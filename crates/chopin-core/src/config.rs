@@ -0,0 +1,399 @@
+// src/config.rs
+//! Application configuration loaded from `Chopin.toml`.
+//!
+//! Besides the built-in `[server]` and `[database]` tables used by the CLI,
+//! modules can declare their own strongly-typed sections:
+//!
+//! ```toml
+//! [stripe]
+//! api_key = "${STRIPE_API_KEY}"
+//! webhook_secret = "${STRIPE_WEBHOOK_SECRET}"
+//! ```
+//!
+//! ```rust,ignore
+//! #[derive(Clone, Deserialize)]
+//! struct StripeSettings { api_key: String, webhook_secret: String }
+//!
+//! impl SettingsSection for StripeSettings {
+//!     const SECTION: &'static str = "stripe";
+//! }
+//!
+//! // At startup:
+//! chopin_core::config::init_config(Config::load(".")?);
+//!
+//! // In a handler:
+//! let Settings(stripe) = ctx.extract::<Settings<StripeSettings>>()?;
+//! ```
+//!
+//! `${VAR}` references are replaced with environment variables when the file
+//! is loaded, so secrets never need to be committed.
+//!
+//! ## Hot reload
+//!
+//! The installed config can be swapped at runtime with [`replace_config`] or
+//! [`reload_config`], on `SIGHUP` via [`watch_sighup`], or from an admin route
+//! mounted with [`reload_handler`]. Each swap bumps a generation counter; the
+//! per-worker [`Settings`] caches notice it and re-read their sections on the
+//! next request, and functions registered with [`subscribe`] are called with
+//! the new config. A file that fails to parse leaves the old config in place.
+use crate::error::{ChopinError, ChopinResult};
+use crate::extract::FromRequest;
+use crate::http::{Context, Response};
+use serde::de::DeserializeOwned;
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Name of the configuration file looked up by [`Config::load`].
+pub const CONFIG_FILE: &str = "Chopin.toml";
+
+static GLOBAL_CONFIG: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+static CONFIG_GENERATION: AtomicU64 = AtomicU64::new(0);
+static SUBSCRIBERS: Mutex<Vec<fn(&Config)>> = Mutex::new(Vec::new());
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Per-worker cache of deserialized sections, tagged with the config generation.
+struct SettingsCache {
+    generation: u64,
+    values: HashMap<TypeId, Box<dyn Any>>,
+}
+
+thread_local! {
+    static SETTINGS_CACHE: RefCell<SettingsCache> = RefCell::new(SettingsCache {
+        generation: 0,
+        values: HashMap::new(),
+    });
+}
+
+/// Parsed `Chopin.toml`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    table: toml::Table,
+    source: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load `Chopin.toml` from `dir`. A missing file yields an empty config.
+    pub fn load(dir: impl AsRef<Path>) -> ChopinResult<Self> {
+        let path = dir.as_ref().join(CONFIG_FILE);
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        let mut config = Self::parse(&content)?;
+        config.source = Some(dir.as_ref().to_path_buf());
+        Ok(config)
+    }
+
+    /// Parse TOML source, interpolating `${VAR}` environment references.
+    pub fn parse(source: &str) -> ChopinResult<Self> {
+        let table = interpolate_env_vars(source)
+            .parse::<toml::Table>()
+            .map_err(|e| ChopinError::Other(format!("invalid {CONFIG_FILE}: {e}")))?;
+        Ok(Self {
+            table,
+            source: None,
+        })
+    }
+
+    /// Directory this config was loaded from, used by [`reload_config`].
+    pub fn source_dir(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    /// Returns `true` if the top-level `section` exists.
+    pub fn has_section(&self, section: &str) -> bool {
+        self.table.contains_key(section)
+    }
+
+    /// Deserialize the top-level `section` table into `T`.
+    pub fn extension<T: DeserializeOwned>(&self, section: &str) -> ChopinResult<T> {
+        let value =
+            self.table.get(section).cloned().ok_or_else(|| {
+                ChopinError::Other(format!("missing [{section}] in {CONFIG_FILE}"))
+            })?;
+        value
+            .try_into()
+            .map_err(|e| ChopinError::Other(format!("invalid [{section}] in {CONFIG_FILE}: {e}")))
+    }
+}
+
+/// Install the process-wide config read by [`Settings`].
+///
+/// Fails with the rejected value if a config is already installed; use
+/// [`replace_config`] to swap it at runtime.
+pub fn init_config(config: Config) -> Result<(), Config> {
+    let Ok(mut slot) = GLOBAL_CONFIG.write() else {
+        return Err(config);
+    };
+    if slot.is_some() {
+        return Err(config);
+    }
+    *slot = Some(Arc::new(config));
+    CONFIG_GENERATION.fetch_add(1, Ordering::Release);
+    Ok(())
+}
+
+/// The installed config, if any.
+pub fn config() -> Option<Arc<Config>> {
+    GLOBAL_CONFIG.read().ok().and_then(|c| c.clone())
+}
+
+/// Current config generation; incremented on every install or swap.
+pub fn config_generation() -> u64 {
+    CONFIG_GENERATION.load(Ordering::Acquire)
+}
+
+/// Swap in a new config and notify subscribers.
+pub fn replace_config(config: Config) {
+    let config = Arc::new(config);
+    if let Ok(mut slot) = GLOBAL_CONFIG.write() {
+        *slot = Some(config.clone());
+    }
+    CONFIG_GENERATION.fetch_add(1, Ordering::Release);
+
+    let subscribers = SUBSCRIBERS.lock().map(|s| s.clone()).unwrap_or_default();
+    for notify in subscribers {
+        notify(&config);
+    }
+}
+
+/// Re-read `Chopin.toml` from the directory the current config was loaded from.
+///
+/// On error the current config stays installed.
+pub fn reload_config() -> ChopinResult<()> {
+    let dir = config()
+        .and_then(|c| c.source.clone())
+        .ok_or_else(|| ChopinError::Other("config was not loaded from a file".to_string()))?;
+    replace_config(Config::load(dir)?);
+    Ok(())
+}
+
+/// Register a function called with the new config after every swap.
+pub fn subscribe(listener: fn(&Config)) {
+    if let Ok(mut subscribers) = SUBSCRIBERS.lock() {
+        subscribers.push(listener);
+    }
+}
+
+extern "C" fn on_sighup(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::Release);
+}
+
+/// Reload the config whenever the process receives `SIGHUP`.
+///
+/// The signal handler only sets a flag; a background thread performs the
+/// reload so no parsing happens in signal context.
+pub fn watch_sighup() -> ChopinResult<()> {
+    let handler = on_sighup as extern "C" fn(libc::c_int);
+    if unsafe { libc::signal(libc::SIGHUP, handler as libc::sighandler_t) } == libc::SIG_ERR {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    std::thread::Builder::new()
+        .name("chopin-config-reload".into())
+        .spawn(|| {
+            loop {
+                std::thread::sleep(std::time::Duration::from_millis(200));
+                if RELOAD_REQUESTED.swap(false, Ordering::AcqRel)
+                    && let Err(e) = reload_config()
+                {
+                    eprintln!("[chopin] config reload failed: {e}");
+                }
+            }
+        })?;
+    Ok(())
+}
+
+/// Admin handler that reloads the config, e.g. `POST /admin/config/reload`.
+///
+/// Mount it behind an authentication middleware.
+pub fn reload_handler(_ctx: Context) -> Response {
+    match reload_config() {
+        Ok(()) => Response::text(format!("reloaded (generation {})", config_generation())),
+        Err(e) => {
+            let mut res = Response::text(format!("reload failed: {e}"));
+            res.status = 500;
+            res
+        }
+    }
+}
+
+/// A config section that can be extracted with [`Settings`].
+pub trait SettingsSection: DeserializeOwned + Clone + 'static {
+    /// Top-level table name in `Chopin.toml`.
+    const SECTION: &'static str;
+}
+
+/// Typed settings extractor.
+///
+/// Deserializes `T::SECTION` from the global config once per worker thread
+/// (and again after each reload) and hands out clones afterwards. Responds `500 Internal Server Error` if
+/// no config was installed or the section is missing or invalid.
+pub struct Settings<T>(pub T);
+
+impl<T: SettingsSection> Settings<T> {
+    /// Load the section without a request, e.g. during startup validation.
+    pub fn load() -> ChopinResult<T> {
+        SETTINGS_CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            let generation = config_generation();
+            if cache.generation != generation {
+                cache.values.clear();
+                cache.generation = generation;
+            }
+            if let Some(v) = cache
+                .values
+                .get(&TypeId::of::<T>())
+                .and_then(|v| v.downcast_ref::<T>())
+            {
+                return Ok(v.clone());
+            }
+            let cfg =
+                config().ok_or_else(|| ChopinError::Other("config not initialized".to_string()))?;
+            let value: T = cfg.extension(T::SECTION)?;
+            cache
+                .values
+                .insert(TypeId::of::<T>(), Box::new(value.clone()));
+            Ok(value)
+        })
+    }
+}
+
+impl<'a, T: SettingsSection> FromRequest<'a> for Settings<T> {
+    type Error = Response;
+
+    fn from_request(_ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        Settings::<T>::load()
+            .map(Settings)
+            .map_err(|_| Response::server_error())
+    }
+}
+
+/// Replace `${VAR_NAME}` patterns with environment variable values.
+fn interpolate_env_vars(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        let Some(end) = rest[start..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let var_name = &rest[start + 2..start + end];
+        result.push_str(&std::env::var(var_name).unwrap_or_default());
+        rest = &rest[start + end + 1..];
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Clone, Deserialize, PartialEq)]
+    struct Stripe {
+        api_key: String,
+        #[serde(default)]
+        retries: u32,
+    }
+
+    #[test]
+    fn test_extension_deserializes_section() {
+        let cfg = Config::parse(
+            r#"
+            [server]
+            port = 8080
+
+            [stripe]
+            api_key = "sk_test"
+            retries = 3
+            "#,
+        )
+        .unwrap();
+        assert!(cfg.has_section("server"));
+        let stripe: Stripe = cfg.extension("stripe").unwrap();
+        assert_eq!(
+            stripe,
+            Stripe {
+                api_key: "sk_test".to_string(),
+                retries: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_extension_missing_and_invalid() {
+        let cfg = Config::parse("[stripe]\nretries = 1\n").unwrap();
+        let missing = cfg.extension::<Stripe>("mailer").unwrap_err().to_string();
+        assert!(missing.contains("missing [mailer]"), "{missing}");
+        let invalid = cfg.extension::<Stripe>("stripe").unwrap_err().to_string();
+        assert!(invalid.contains("invalid [stripe]"), "{invalid}");
+    }
+
+    #[test]
+    fn test_env_interpolation() {
+        // SAFETY: test-local variable name, not read by other tests.
+        unsafe { std::env::set_var("CHOPIN_CONFIG_TEST_KEY", "sk_env") };
+        let cfg = Config::parse("[stripe]\napi_key = \"${CHOPIN_CONFIG_TEST_KEY}\"\n").unwrap();
+        let stripe: Stripe = cfg.extension("stripe").unwrap();
+        assert_eq!(stripe.api_key, "sk_env");
+        assert_eq!(
+            interpolate_env_vars("a ${ unterminated"),
+            "a ${ unterminated"
+        );
+    }
+
+    #[derive(Debug, Clone, Deserialize)]
+    struct Flags {
+        maintenance: bool,
+    }
+
+    impl SettingsSection for Flags {
+        const SECTION: &'static str = "flags";
+    }
+
+    static NOTIFIED: AtomicU64 = AtomicU64::new(0);
+
+    fn on_change(cfg: &Config) {
+        if cfg.has_section("flags") {
+            NOTIFIED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    // The global config is process-wide, so the whole reload lifecycle lives in
+    // one test.
+    #[test]
+    fn test_hot_reload_invalidates_settings_and_notifies() {
+        let dir = std::env::temp_dir().join(format!("chopin-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(CONFIG_FILE), "[flags]\nmaintenance = false\n").unwrap();
+
+        subscribe(on_change);
+        replace_config(Config::load(&dir).unwrap());
+        assert!(!Settings::<Flags>::load().unwrap().maintenance);
+        assert!(init_config(Config::default()).is_err());
+
+        std::fs::write(dir.join(CONFIG_FILE), "[flags]\nmaintenance = true\n").unwrap();
+        let before = config_generation();
+        reload_config().unwrap();
+        assert!(config_generation() > before);
+        assert!(Settings::<Flags>::load().unwrap().maintenance);
+        assert!(NOTIFIED.load(Ordering::SeqCst) >= 2);
+
+        // A broken file keeps the previous config.
+        std::fs::write(dir.join(CONFIG_FILE), "[flags\n").unwrap();
+        assert!(reload_config().is_err());
+        assert!(Settings::<Flags>::load().unwrap().maintenance);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_toml() {
+        assert!(Config::parse("[stripe\n").is_err());
+    }
+}
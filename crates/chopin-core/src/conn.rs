@@ -1,4 +1,5 @@
 // src/conn.rs
+use std::task::Poll;
 
 pub const DEFAULT_READ_BUF_SIZE: usize = 8192;
 pub const DEFAULT_WRITE_BUF_SIZE: usize = 32768;
@@ -6,6 +7,7 @@ pub const DEFAULT_WRITE_BUF_SIZE: usize = 32768;
 /// Connection flags (bit field)
 pub const CONN_KEEP_ALIVE: u8 = 1;
 pub const CONN_EPOLLOUT: u8 = 2;
+pub const CONN_FEED_WAIT: u8 = 4; // parked until its Body::Feed has the next chunk
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(u8)]
@@ -44,6 +46,15 @@ pub struct Conn {
     pub body_sent: u32,  // bytes already flushed
     pub body_owned: Option<Box<[u8]>>, // owns Body::Bytes allocation; None for Static/empty
 
+    // Body::Stream still being sent; pulled one chunk at a time as the socket drains
+    pub body_stream: Option<Box<dyn Iterator<Item = Vec<u8>> + Send>>,
+
+    // Body::Feed still being sent; like body_stream, but the next chunk may not be there yet
+    pub body_feed: Option<Box<crate::longpoll::Feed>>,
+
+    // Long-poll waiting on its event; the worker writes its response when it fires
+    pub parked: Option<Box<crate::longpoll::Parked>>,
+
     // io_uring: tracks which operation is currently in-flight for this connection.
     // Prevents double-submission (e.g. submitting OP_READ while previous OP_READ pending).
     // 0 = no pending op.
@@ -78,6 +89,9 @@ impl Conn {
             body_total: 0,
             body_sent: 0,
             body_owned: None,
+            body_stream: None,
+            body_feed: None,
+            parked: None,
             #[cfg(feature = "io-uring")]
             pending_op: 0,
             read_buf: vec![0u8; read_size].into_boxed_slice(),
@@ -105,6 +119,78 @@ impl Conn {
         self.body_total = 0;
         self.body_sent = 0;
         self.body_owned = None;
+        self.body_stream = None;
+        self.body_feed = None;
+    }
+
+    /// Whether a `Body::Stream` or `Body::Feed` is still being sent.
+    #[inline]
+    pub fn is_streaming(&self) -> bool {
+        self.body_stream.is_some() || self.body_feed.is_some()
+    }
+
+    /// Queue the next chunk of a pending `Body::Stream` or `Body::Feed` with
+    /// chunked transfer-encoding framing. Call only once `write_buf` and any
+    /// writev body are fully flushed. A chunk that does not fit in
+    /// `write_buf` goes out through the writev body path. When the stream
+    /// ends the terminating zero-length chunk is queued and the stream
+    /// dropped. Returns `false`, queuing nothing, if the feed has no chunk
+    /// ready yet.
+    pub fn refill_stream(&mut self) -> bool {
+        // An empty chunk would terminate the body early; skip it.
+        let next = if let Some(feed) = self.body_feed.as_mut() {
+            loop {
+                match feed.poll() {
+                    Poll::Ready(Some(chunk)) if chunk.is_empty() => {}
+                    Poll::Ready(chunk) => break chunk,
+                    Poll::Pending => return false,
+                }
+            }
+        } else if let Some(stream) = self.body_stream.as_mut() {
+            stream.find(|c| !c.is_empty())
+        } else {
+            return true;
+        };
+        self.write_pos = 0;
+        self.body_ptr = 0;
+        self.body_total = 0;
+        self.body_sent = 0;
+        self.body_owned = None;
+        let Some(mut chunk) = next else {
+            self.body_stream = None;
+            self.body_feed = None;
+            self.write_buf[..5].copy_from_slice(b"0\r\n\r\n");
+            self.write_len = 5;
+            return true;
+        };
+        // Size line: uppercase hex length then CRLF, built on the stack.
+        let mut size_buf = [0u8; 18];
+        size_buf[16..].copy_from_slice(b"\r\n");
+        let mut i = 16;
+        let mut n = chunk.len();
+        while n > 0 {
+            let d = (n % 16) as u8;
+            i -= 1;
+            size_buf[i] = if d < 10 { b'0' + d } else { b'A' + d - 10 };
+            n /= 16;
+        }
+        let size = &size_buf[i..];
+        self.write_buf[..size.len()].copy_from_slice(size);
+        let framed = size.len() + chunk.len() + 2;
+        if framed <= self.write_buf.len() {
+            let end = size.len() + chunk.len();
+            self.write_buf[size.len()..end].copy_from_slice(&chunk);
+            self.write_buf[end..framed].copy_from_slice(b"\r\n");
+            self.write_len = framed as u16;
+        } else {
+            chunk.extend_from_slice(b"\r\n");
+            let boxed = chunk.into_boxed_slice();
+            self.write_len = size.len() as u16;
+            self.body_ptr = boxed.as_ptr() as usize;
+            self.body_total = boxed.len() as u32;
+            self.body_owned = Some(boxed);
+        }
+        true
     }
 }
 
@@ -127,4 +213,41 @@ mod tests {
         let total_size = std::mem::size_of::<Conn>();
         assert_eq!(total_size % 64, 0, "Conn struct size not a multiple of 64!");
     }
+
+    #[test]
+    fn test_refill_stream_frames_chunks_in_hex() {
+        let mut conn = Conn::with_buf_sizes(64, 64);
+        conn.body_stream = Some(Box::new(vec![vec![b'a'; 10], vec![b'b'; 300]].into_iter()));
+
+        conn.refill_stream();
+        let queued = &conn.write_buf[..conn.write_len as usize];
+        assert_eq!(queued, b"A\r\naaaaaaaaaa\r\n");
+
+        // Too big for the write buffer: the size line is queued and the chunk
+        // goes out as the writev body.
+        conn.refill_stream();
+        assert_eq!(&conn.write_buf[..conn.write_len as usize], b"12C\r\n");
+        assert_eq!(conn.body_total, 302);
+
+        conn.refill_stream();
+        assert_eq!(&conn.write_buf[..conn.write_len as usize], b"0\r\n\r\n");
+        assert!(conn.body_stream.is_none());
+    }
+
+    #[test]
+    fn test_refill_waits_for_feed() {
+        let mut conn = Conn::with_buf_sizes(64, 64);
+        let (tx, feed) = crate::longpoll::feed(1);
+        conn.body_feed = Some(Box::new(feed));
+
+        assert!(!conn.refill_stream());
+        tx.send(b"hi".to_vec()).unwrap();
+        assert!(conn.refill_stream());
+        assert_eq!(&conn.write_buf[..conn.write_len as usize], b"2\r\nhi\r\n");
+
+        drop(tx);
+        assert!(conn.refill_stream());
+        assert_eq!(&conn.write_buf[..conn.write_len as usize], b"0\r\n\r\n");
+        assert!(!conn.is_streaming());
+    }
 }
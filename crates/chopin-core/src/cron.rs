@@ -0,0 +1,860 @@
+// src/cron.rs
+//! Scheduled tasks (`orm` feature).
+//!
+//! A [`CronTask`] pairs a five-field cron expression (UTC) with a function.
+//! [`CronModule`] runs due tasks on a scheduler thread and keeps one row per
+//! task in the `chopin_cron` table: when it runs next, when it last ran, how
+//! long that took and how it ended. Every process may mount the same tasks;
+//! each occurrence is claimed by exactly one of them.
+//!
+//! ```rust,ignore
+//! Chopin::new()
+//!     .mount_module(
+//!         CronModule::new()
+//!             .task(CronTask::new("purge_sessions", "*/15 * * * *", |_| purge_sessions()))
+//!             .task(
+//!                 CronTask::new("daily_report", "0 6 * * mon-fri", |run| report_for(run.scheduled_at))
+//!                     .catch_up(CatchUp::RunAll),
+//!             )
+//!             .admin("/admin/cron", require_operator),
+//!     )
+//!     .serve("0.0.0.0:8080")?;
+//! ```
+//!
+//! Expressions take numbers, ranges (`1-5`), lists (`1,15`), steps (`*/10`,
+//! `8-18/2`), month and weekday names, and the `@hourly`, `@daily`,
+//! `@weekly`, `@monthly` and `@yearly` shorthands. As in Vixie cron, a day
+//! matches if either day field does when both are restricted.
+//!
+//! ## Missed runs
+//!
+//! A task that falls due while no process is running is late, and runs as
+//! soon as the scheduler sees it. If more than one occurrence went by, say
+//! across a long deploy or outage, its [`CatchUp`] policy decides: skip
+//! them all, run once, or run every missed occurrence in order (the most
+//! recent [`MAX_CATCH_UP`]). Runs that do not happen are counted in
+//! `missed`.
+//!
+//! Tasks run one at a time on the scheduler thread, so a long task delays
+//! the others. Enqueue a [`jobs`](crate::jobs) job from the task for heavy
+//! work.
+//!
+//! Routes mounted by [`CronModule::admin`] under its prefix:
+//!
+//! | Method | Path     | Description                        |
+//! |--------|----------|------------------------------------|
+//! | GET    | `/`      | Every task's [`CronStatus`]        |
+//! | GET    | `/:name` | One task                           |
+use crate::db;
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{MiddlewareFn, Router};
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// ─── Schedules ───────────────────────────────────────────────────────────────
+
+/// A parsed cron expression, evaluated in UTC at minute resolution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    expr: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// How far ahead [`CronSchedule::next_after`] looks: long enough for a
+/// February 29th.
+const SEARCH_DAYS: i64 = 8 * 366;
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> ChopinResult<Self> {
+        let invalid = |why: String| ChopinError::Other(format!("cron `{expr}`: {why}"));
+        let expanded = match expr.trim() {
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            "@monthly" => "0 0 1 * *",
+            "@weekly" => "0 0 * * 0",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@hourly" => "0 * * * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(invalid(format!("expected 5 fields, got {}", fields.len())));
+        };
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAYS).map_err(invalid)?;
+        Ok(Self {
+            expr: expr.trim().to_string(),
+            minutes: parse_field(minute, 0, 59, &[]).map_err(invalid)?,
+            hours: parse_field(hour, 0, 23, &[]).map_err(invalid)? as u32,
+            days: parse_field(day, 1, 31, &[]).map_err(invalid)? as u32,
+            months: parse_field(month, 1, 12, &MONTHS).map_err(invalid)? as u16,
+            // Both 0 and 7 mean Sunday.
+            weekdays: ((weekdays | weekdays >> 7) & 0x7f) as u8,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+
+    /// The expression as written.
+    pub fn as_str(&self) -> &str {
+        &self.expr
+    }
+
+    /// First matching minute strictly after `unix` (seconds), or `None` if
+    /// the expression never matches, e.g. February 30th.
+    pub fn next_after(&self, unix: i64) -> Option<i64> {
+        let mut t = unix.div_euclid(60) * 60 + 60;
+        let limit = t + SEARCH_DAYS * 86_400;
+        while t <= limit {
+            let days = t.div_euclid(86_400);
+            let secs = t.rem_euclid(86_400);
+            let (year, month, day) = civil_from_days(days);
+            if self.months & (1 << month) == 0 {
+                let (year, month) = if month == 12 {
+                    (year + 1, 1)
+                } else {
+                    (year, month + 1)
+                };
+                t = days_from_civil(year, month, 1) * 86_400;
+                continue;
+            }
+            let weekday = (days + 4).rem_euclid(7) as u32; // 1970-01-01 was a Thursday
+            if !self.matches_day(day, weekday) {
+                t = (days + 1) * 86_400;
+                continue;
+            }
+            let hour = (secs / 3600) as u32;
+            if self.hours & (1 << hour) == 0 {
+                t = days * 86_400 + (hour as i64 + 1) * 3600;
+                continue;
+            }
+            if self.minutes & (1 << ((secs % 3600) / 60)) == 0 {
+                t += 60;
+                continue;
+            }
+            return Some(t);
+        }
+        None
+    }
+
+    fn matches_day(&self, day: u32, weekday: u32) -> bool {
+        let by_day = self.days & (1 << day) != 0;
+        let by_weekday = self.weekdays & (1 << weekday) != 0;
+        if self.days_restricted && self.weekdays_restricted {
+            by_day || by_weekday
+        } else {
+            by_day && by_weekday
+        }
+    }
+}
+
+impl std::str::FromStr for CronSchedule {
+    type Err = ChopinError;
+
+    fn from_str(s: &str) -> ChopinResult<Self> {
+        Self::parse(s)
+    }
+}
+
+/// Bit `n` set for every value `n` the field allows.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |s: &str| -> Result<u32, String> {
+        let lower = s.to_ascii_lowercase();
+        let n = match names.iter().position(|n| *n == lower) {
+            Some(i) => min + i as u32,
+            None => s.parse().map_err(|_| format!("`{s}` is not a number"))?,
+        };
+        if n < min || n > max {
+            return Err(format!("{n} is outside {min}-{max}"));
+        }
+        Ok(n)
+    };
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("bad step in `{part}`")),
+            },
+            None => (part, 1),
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((lo, hi)) => (value(lo)?, value(hi)?),
+                // `5/15` means from 5 to the end, every 15.
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if lo > hi {
+            return Err(format!("empty range `{range}`"));
+        }
+        for n in (lo..=hi).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// ─── Tasks ───────────────────────────────────────────────────────────────────
+
+/// What to do when more than one occurrence of a task was missed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CatchUp {
+    /// Run none of them and wait for the next occurrence.
+    Skip,
+    /// Run once, for the most recent occurrence.
+    #[default]
+    RunOnce,
+    /// Run each missed occurrence, oldest first, up to [`MAX_CATCH_UP`].
+    RunAll,
+}
+
+impl CatchUp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::RunOnce => "run_once",
+            Self::RunAll => "run_all",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skip" => Some(Self::Skip),
+            "run_once" => Some(Self::RunOnce),
+            "run_all" => Some(Self::RunAll),
+            _ => None,
+        }
+    }
+}
+
+/// Most missed occurrences [`CatchUp::RunAll`] replays.
+pub const MAX_CATCH_UP: usize = 100;
+
+/// The occurrence a task is running for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronRun {
+    pub name: &'static str,
+    /// Unix seconds. Earlier than now when catching up.
+    pub scheduled_at: i64,
+}
+
+type TaskFn = Arc<dyn Fn(&CronRun) -> ChopinResult<()> + Send + Sync>;
+
+/// A named function and when to call it.
+#[derive(Clone)]
+pub struct CronTask {
+    name: &'static str,
+    schedule: CronSchedule,
+    catch_up: CatchUp,
+    run: TaskFn,
+}
+
+impl CronTask {
+    /// `name` identifies the task's row and must stay stable across deploys.
+    ///
+    /// # Panics
+    ///
+    /// If `expr` does not parse or never matches. Tasks are declared in
+    /// code, so this is a programming error caught at startup.
+    pub fn new(
+        name: &'static str,
+        expr: &str,
+        run: impl Fn(&CronRun) -> ChopinResult<()> + Send + Sync + 'static,
+    ) -> Self {
+        let schedule = CronSchedule::parse(expr).unwrap_or_else(|e| panic!("{e}"));
+        assert!(
+            schedule.next_after(0).is_some(),
+            "cron `{expr}` never matches"
+        );
+        Self {
+            name,
+            schedule,
+            catch_up: CatchUp::default(),
+            run: Arc::new(run),
+        }
+    }
+
+    pub fn catch_up(mut self, policy: CatchUp) -> Self {
+        self.catch_up = policy;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn schedule(&self) -> &CronSchedule {
+        &self.schedule
+    }
+}
+
+impl std::fmt::Debug for CronTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CronTask")
+            .field("name", &self.name)
+            .field("schedule", &self.schedule.expr)
+            .field("catch_up", &self.catch_up)
+            .finish()
+    }
+}
+
+/// Occurrences of `schedule` from `next` up to `now`: the most recent
+/// [`MAX_CATCH_UP`] of them, and how many there were in all.
+fn due_occurrences(schedule: &CronSchedule, next: i64, now: i64) -> (Vec<i64>, u64) {
+    let mut recent = VecDeque::with_capacity(MAX_CATCH_UP);
+    let mut total = 0u64;
+    let mut at = Some(next);
+    while let Some(t) = at.filter(|&t| t <= now) {
+        if recent.len() == MAX_CATCH_UP {
+            recent.pop_front();
+        }
+        recent.push_back(t);
+        total += 1;
+        at = schedule.next_after(t);
+    }
+    (recent.into(), total)
+}
+
+/// Which of the due occurrences to run under `policy`, and how many are
+/// missed instead.
+fn plan(recent: Vec<i64>, total: u64, policy: CatchUp) -> (Vec<i64>, u64) {
+    if total <= 1 {
+        return (recent, 0);
+    }
+    let runs = match policy {
+        CatchUp::Skip => Vec::new(),
+        CatchUp::RunOnce => recent.last().copied().into_iter().collect(),
+        CatchUp::RunAll => recent,
+    };
+    let missed = total - runs.len() as u64;
+    (runs, missed)
+}
+
+fn unix_now() -> ChopinResult<i64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .map_err(|_| ChopinError::ClockError)
+}
+
+// ─── Storage ─────────────────────────────────────────────────────────────────
+
+/// How a task's last run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CronOutcome {
+    Succeeded,
+    Failed,
+}
+
+/// One task as reported by the admin endpoints. Times are Unix seconds.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CronStatus {
+    pub name: String,
+    pub schedule: String,
+    pub catch_up: CatchUp,
+    pub next_run_at: i64,
+    /// When the last run started.
+    pub last_run_at: Option<i64>,
+    /// The occurrence the last run was for.
+    pub last_scheduled_at: Option<i64>,
+    pub last_duration_ms: Option<i64>,
+    pub last_outcome: Option<CronOutcome>,
+    pub last_error: Option<String>,
+    pub runs: i64,
+    pub failures: i64,
+    /// Occurrences not run because of the catch-up policy.
+    pub missed: i64,
+}
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_cron (
+        name TEXT PRIMARY KEY,
+        schedule TEXT NOT NULL,
+        catch_up TEXT NOT NULL,
+        next_run_at TIMESTAMPTZ NOT NULL,
+        last_run_at TIMESTAMPTZ,
+        last_scheduled_at TIMESTAMPTZ,
+        last_duration_ms BIGINT,
+        last_outcome TEXT,
+        last_error TEXT,
+        runs BIGINT NOT NULL DEFAULT 0,
+        failures BIGINT NOT NULL DEFAULT 0,
+        missed BIGINT NOT NULL DEFAULT 0
+    )
+"#;
+
+const SELECT_COLUMNS: &str = "name, schedule, catch_up, \
+     EXTRACT(EPOCH FROM next_run_at)::BIGINT AS next_run_at, \
+     EXTRACT(EPOCH FROM last_run_at)::BIGINT AS last_run_at, \
+     EXTRACT(EPOCH FROM last_scheduled_at)::BIGINT AS last_scheduled_at, \
+     last_duration_ms, last_outcome, last_error, runs, failures, missed";
+
+/// Storage operations on `chopin_cron`.
+pub struct CronStore;
+
+impl CronStore {
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        Ok(())
+    }
+
+    /// Add `task`, first due at `next_run_at`, or update its row. An
+    /// existing row keeps its next run, and with it any missed occurrences,
+    /// unless the schedule changed.
+    pub fn register(
+        executor: &mut dyn Executor,
+        task: &CronTask,
+        next_run_at: i64,
+    ) -> OrmResult<()> {
+        executor.execute(
+            "INSERT INTO chopin_cron (name, schedule, catch_up, next_run_at) \
+             VALUES ($1, $2, $3, to_timestamp($4::BIGINT)) \
+             ON CONFLICT (name) DO UPDATE SET \
+                 next_run_at = CASE WHEN chopin_cron.schedule = EXCLUDED.schedule \
+                     THEN chopin_cron.next_run_at ELSE EXCLUDED.next_run_at END, \
+                 schedule = EXCLUDED.schedule, \
+                 catch_up = EXCLUDED.catch_up",
+            &[
+                &task.name,
+                &task.schedule.as_str(),
+                &task.catch_up.as_str(),
+                &next_run_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// `(name, next_run_at)` of every task due at `now`.
+    pub fn due(executor: &mut dyn Executor, now: i64) -> OrmResult<Vec<(String, i64)>> {
+        executor
+            .query(
+                "SELECT name, EXTRACT(EPOCH FROM next_run_at)::BIGINT FROM chopin_cron \
+                 WHERE next_run_at <= to_timestamp($1::BIGINT) ORDER BY next_run_at",
+                &[&now],
+            )?
+            .iter()
+            .map(|row| Ok((row.get_typed(0)?, row.get_typed(1)?)))
+            .collect()
+    }
+
+    /// Move `name` from the occurrence at `from` to `to`, adding `missed`.
+    /// Returns `false` if another process got there first.
+    pub fn advance(
+        executor: &mut dyn Executor,
+        name: &str,
+        from: i64,
+        to: i64,
+        missed: u64,
+    ) -> OrmResult<bool> {
+        let missed = missed as i64;
+        let n = executor.execute(
+            "UPDATE chopin_cron SET next_run_at = to_timestamp($3::BIGINT), missed = missed + $4 \
+             WHERE name = $1 AND EXTRACT(EPOCH FROM next_run_at)::BIGINT = $2",
+            &[&name, &from, &to, &missed],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Record a run of `name` for `scheduled_at` that started at `started_at`.
+    pub fn record(
+        executor: &mut dyn Executor,
+        name: &str,
+        scheduled_at: i64,
+        started_at: i64,
+        duration_ms: i64,
+        error: Option<&str>,
+    ) -> OrmResult<()> {
+        executor.execute(
+            "UPDATE chopin_cron SET \
+                 last_scheduled_at = to_timestamp($2::BIGINT), last_run_at = to_timestamp($3::BIGINT), \
+                 last_duration_ms = $4, last_error = $5, \
+                 last_outcome = CASE WHEN $5::TEXT IS NULL THEN 'succeeded' ELSE 'failed' END, \
+                 runs = runs + 1, \
+                 failures = failures + CASE WHEN $5::TEXT IS NULL THEN 0 ELSE 1 END \
+             WHERE name = $1",
+            &[&name, &scheduled_at, &started_at, &duration_ms, &error],
+        )?;
+        Ok(())
+    }
+
+    pub fn list(executor: &mut dyn Executor) -> OrmResult<Vec<CronStatus>> {
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM chopin_cron ORDER BY name");
+        executor.query(&sql, &[])?.iter().map(from_row).collect()
+    }
+
+    pub fn get(executor: &mut dyn Executor, name: &str) -> OrmResult<Option<CronStatus>> {
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM chopin_cron WHERE name = $1");
+        executor
+            .query(&sql, &[&name])?
+            .first()
+            .map(from_row)
+            .transpose()
+    }
+}
+
+fn from_row(row: &chopin_orm::Row) -> OrmResult<CronStatus> {
+    let catch_up: String = row.get_typed_by_name("catch_up")?;
+    let outcome: Option<String> = row.get_typed_by_name("last_outcome")?;
+    Ok(CronStatus {
+        name: row.get_typed_by_name("name")?,
+        schedule: row.get_typed_by_name("schedule")?,
+        catch_up: CatchUp::parse(&catch_up).unwrap_or_default(),
+        next_run_at: row.get_typed_by_name("next_run_at")?,
+        last_run_at: row.get_typed_by_name("last_run_at")?,
+        last_scheduled_at: row.get_typed_by_name("last_scheduled_at")?,
+        last_duration_ms: row.get_typed_by_name("last_duration_ms")?,
+        last_outcome: outcome.map(|o| {
+            if o == "succeeded" {
+                CronOutcome::Succeeded
+            } else {
+                CronOutcome::Failed
+            }
+        }),
+        last_error: row.get_typed_by_name("last_error")?,
+        runs: row.get_typed_by_name("runs")?,
+        failures: row.get_typed_by_name("failures")?,
+        missed: row.get_typed_by_name("missed")?,
+    })
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+fn json<T: Serialize>(result: ChopinResult<T>) -> Response {
+    match result.map(|value| serde_json::to_vec(&value)) {
+        Ok(Ok(body)) => Response::json_bytes(body),
+        _ => Response::server_error(),
+    }
+}
+
+/// `GET {admin}`
+pub fn admin_list_handler(_ctx: Context) -> Response {
+    json(db::with_db(CronStore::list))
+}
+
+/// `GET {admin}/:name`
+pub fn admin_detail_handler(ctx: Context) -> Response {
+    let Some(name) = ctx.param("name") else {
+        return Response::bad_request();
+    };
+    match db::with_db(|db| CronStore::get(db, name)) {
+        Ok(None) => Response::not_found(),
+        result => json(result),
+    }
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Runs [`CronTask`]s from the moment the server starts, and mounts the
+/// admin endpoints if configured.
+#[derive(Debug, Clone)]
+pub struct CronModule {
+    tasks: Vec<CronTask>,
+    poll_interval: Duration,
+    admin: Option<(&'static str, MiddlewareFn)>,
+}
+
+impl Default for CronModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CronModule {
+    /// No tasks, checking for due ones every second.
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            poll_interval: Duration::from_secs(1),
+            admin: None,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If a task with the same name was already added.
+    pub fn task(mut self, task: CronTask) -> Self {
+        assert!(
+            self.tasks.iter().all(|t| t.name != task.name),
+            "cron task `{}` added twice",
+            task.name
+        );
+        self.tasks.push(task);
+        self
+    }
+
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Mount the status endpoints under `prefix`, behind `guard`.
+    pub fn admin(mut self, prefix: &'static str, guard: MiddlewareFn) -> Self {
+        self.admin = Some((prefix.trim_end_matches('/'), guard));
+        self
+    }
+
+    /// Create or update every task's row. The scheduler does this when it
+    /// starts.
+    pub fn register(&self) -> ChopinResult<()> {
+        let now = unix_now()?;
+        db::with_db(|db| {
+            for task in &self.tasks {
+                if let Some(next) = task.schedule.next_after(now) {
+                    CronStore::register(db, task, next)?;
+                }
+            }
+            Ok(())
+        })
+    }
+
+    /// Run every task that is due now, on the calling thread, applying
+    /// catch-up policies. Returns how many runs happened, failed or not.
+    pub fn run_due(&self) -> ChopinResult<usize> {
+        let now = unix_now()?;
+        let due = db::with_db(|db| CronStore::due(db, now))?;
+        let mut ran = 0;
+        for (name, next) in due {
+            if let Some(task) = self.tasks.iter().find(|t| t.name == name) {
+                ran += Self::run_task(task, next, now)?;
+            }
+        }
+        Ok(ran)
+    }
+
+    fn run_task(task: &CronTask, next: i64, now: i64) -> ChopinResult<usize> {
+        let Some(following) = task.schedule.next_after(now) else {
+            return Ok(0);
+        };
+        let (recent, total) = due_occurrences(&task.schedule, next, now);
+        let (runs, missed) = plan(recent, total, task.catch_up);
+        if !db::with_db(|db| CronStore::advance(db, task.name, next, following, missed))? {
+            return Ok(0);
+        }
+        if missed > 0 {
+            eprintln!(
+                "[chopin] cron {}: {missed} missed run(s) not caught up",
+                task.name
+            );
+        }
+        for &scheduled_at in &runs {
+            let run = CronRun {
+                name: task.name,
+                scheduled_at,
+            };
+            let started_at = unix_now()?;
+            let clock = Instant::now();
+            let result = crate::jobs::catch_panic(|| (task.run)(&run));
+            let duration_ms = clock.elapsed().as_millis() as i64;
+            let error = result.err().map(|e| e.to_string());
+            if let Some(e) = &error {
+                eprintln!("[chopin] cron {} failed: {e}", task.name);
+            }
+            db::with_db(|db| {
+                CronStore::record(
+                    db,
+                    task.name,
+                    scheduled_at,
+                    started_at,
+                    duration_ms,
+                    error.as_deref(),
+                )
+            })?;
+        }
+        Ok(runs.len())
+    }
+
+    fn scheduler_loop(self) {
+        let mut registered = false;
+        loop {
+            let result = if registered {
+                self.run_due().map(drop)
+            } else {
+                self.register().map(|()| registered = true)
+            };
+            if let Err(e) = result {
+                eprintln!("[chopin] cron scheduler: {e}");
+            }
+            std::thread::sleep(self.poll_interval);
+        }
+    }
+}
+
+impl ChopinModule for CronModule {
+    fn name(&self) -> &'static str {
+        "cron"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        if let Some((a, guard)) = self.admin {
+            router.get(if a.is_empty() { "/" } else { a }, admin_list_handler);
+            router.get(&format!("{a}/:name"), admin_detail_handler);
+            router.layer_path(a, guard);
+        }
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreateCronTable)]
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        if self.tasks.is_empty() {
+            return Ok(());
+        }
+        let module = self.clone();
+        std::thread::Builder::new()
+            .name("chopin-cron".into())
+            .spawn(move || module.scheduler_loop())
+            .map_err(ChopinError::Io)?;
+        Ok(())
+    }
+}
+
+struct CreateCronTable;
+
+impl Migration for CreateCronTable {
+    fn name(&self) -> &'static str {
+        "001_create_cron"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        CronStore::ensure_table(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_cron", &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chopin_orm::MockExecutor;
+
+    const MON_2024_01_01_0007: i64 = 1_704_067_620;
+
+    fn next(expr: &str, after: i64) -> Option<i64> {
+        CronSchedule::parse(expr).unwrap().next_after(after)
+    }
+
+    #[test]
+    fn test_next_after() {
+        let t = MON_2024_01_01_0007;
+        assert_eq!(next("*/15 * * * *", t), Some(1_704_067_200 + 15 * 60));
+        assert_eq!(next("@hourly", t), Some(1_704_067_200 + 3600));
+        assert_eq!(next("0 3 * * *", t), Some(1_704_067_200 + 3 * 3600));
+        // Friday 10:00 to Monday 09:00.
+        assert_eq!(next("0 9 * * mon-fri", 1_704_448_800), Some(1_704_704_400));
+        // Either the 13th or a Friday: Friday 2024-01-05 comes first.
+        assert_eq!(next("0 0 13 * fri", t), Some(1_704_412_800));
+        // Sunday as 7, and the next February 29th.
+        assert_eq!(next("0 0 * * 7", t), next("0 0 * * sun", t));
+        assert_eq!(next("0 0 29 feb *", 1_677_628_800), Some(1_709_164_800));
+        assert_eq!(next("0 0 1 * *", 1_735_689_540), Some(1_735_689_600));
+        assert_eq!(next("0 0 30 2 *", t), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "0 0 0 * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "0 0 * foo *",
+        ] {
+            assert!(CronSchedule::parse(bad).is_err(), "{bad}");
+        }
+        let s: CronSchedule = "5/20 8-18/5 * * *".parse().unwrap();
+        assert_eq!(s.minutes, (1 << 5) | (1 << 25) | (1 << 45));
+        assert_eq!(s.hours, (1 << 8) | (1 << 13) | (1 << 18));
+    }
+
+    #[test]
+    fn test_catch_up_plans() {
+        let every_minute = CronSchedule::parse("* * * * *").unwrap();
+        let (recent, total) = due_occurrences(&every_minute, 600, 600 + 4 * 60 + 30);
+        assert_eq!((recent.clone(), total), (vec![600, 660, 720, 780, 840], 5));
+
+        assert_eq!(plan(vec![600], 1, CatchUp::Skip), (vec![600], 0));
+        assert_eq!(plan(recent.clone(), total, CatchUp::Skip), (vec![], 5));
+        assert_eq!(
+            plan(recent.clone(), total, CatchUp::RunOnce),
+            (vec![840], 4)
+        );
+        assert_eq!(plan(recent.clone(), total, CatchUp::RunAll), (recent, 0));
+
+        let (recent, total) = due_occurrences(&every_minute, 0, 999 * 60);
+        assert_eq!(
+            (recent.len(), recent[0], total),
+            (MAX_CATCH_UP, 900 * 60, 1000)
+        );
+        assert_eq!(plan(recent, total, CatchUp::RunAll).1, 900);
+    }
+
+    #[test]
+    fn test_register_keeps_next_run_of_unchanged_schedule() {
+        let mut db = MockExecutor::new();
+        let task = CronTask::new("tidy", "@daily", |_| Ok(())).catch_up(CatchUp::Skip);
+        CronStore::register(&mut db, &task, 86_400).unwrap();
+        let (sql, params) = &db.executed_queries[0];
+        assert!(
+            sql.contains("ON CONFLICT (name)")
+                && sql.contains("chopin_cron.schedule = EXCLUDED.schedule")
+        );
+        assert_eq!(*params, 4);
+    }
+
+    #[test]
+    #[should_panic(expected = "never matches")]
+    fn test_task_rejects_impossible_schedule() {
+        CronTask::new("never", "0 0 31 4 *", |_| Ok(()));
+    }
+}
@@ -0,0 +1,419 @@
+// src/crud.rs
+//! Generated CRUD endpoints for admin-style resources (`orm` feature).
+//!
+//! ```rust,ignore
+//! impl CreateRequest<Post> for CreatePostRequest {
+//!     fn into_model(self) -> Post { Post { id: 0, title: self.title, .. } }
+//! }
+//! impl UpdateRequest<Post> for UpdatePostRequest {
+//!     fn apply(self, post: &mut Post) { post.title = self.title; }
+//! }
+//!
+//! crud_routes!(
+//!     Post,
+//!     base = "/api/posts",
+//!     create = CreatePostRequest,
+//!     update = UpdatePostRequest,
+//!     authorize = |ctx, action| if is_admin(ctx) { Ok(()) } else { Err(Response::forbidden()) },
+//! );
+//! ```
+//!
+//! Routes registered under `base`, documented in the OpenAPI spec and
+//! tagged with the model name (or `tag = "..."`) for
+//! [`Router::mount_tagged`](crate::Router::mount_tagged):
+//!
+//! | Method | Path       | Description                                          |
+//! |--------|------------|------------------------------------------------------|
+//! | GET    | `/`        | Page of rows (`?page=1&per_page=20`), by primary key |
+//! | POST   | `/`        | Create from the `create` body, `201`                 |
+//! | GET    | `/:id`     | One row                                              |
+//! | PUT    | `/:id`     | Apply the `update` body, returns the row             |
+//! | DELETE | `/:id`     | `204`                                                |
+//!
+//! Bodies are validated with [`chopin_orm::Validate`]; failures return
+//! `422` with `{"errors": [...]}`. Writes that clash with existing rows
+//! ([`OrmError::is_conflict`]) return `409` with
+//! `{"error": "conflict", "constraint": "..."}`. `authorize` runs before anything else
+//! and may reject with any response. Models need a single-column primary key.
+use crate::db;
+use crate::extract::{Json, Query};
+use crate::http::{Context, Response};
+use chopin_orm::{Executor, Model, OrmError, OrmResult, PgValue, ToSql, Validate};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Largest accepted `per_page`.
+pub const MAX_PER_PAGE: usize = 100;
+
+/// `per_page` when the query string has none.
+pub const DEFAULT_PER_PAGE: usize = 20;
+
+/// The endpoint being called, for [`Resource::authorize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    List,
+    Get,
+    Create,
+    Update,
+    Delete,
+}
+
+/// Body of the create endpoint.
+pub trait CreateRequest<M>: DeserializeOwned + Validate {
+    fn into_model(self) -> M;
+}
+
+/// Body of the update endpoint, applied to the stored row.
+pub trait UpdateRequest<M>: DeserializeOwned + Validate {
+    fn apply(self, model: &mut M);
+}
+
+/// A resource served by the generic handlers below. Implemented by
+/// [`crud_routes!`](crate::crud_routes); the handlers are public so a
+/// hand-written `Resource` can be routed manually.
+pub trait Resource {
+    type Model: Model + Serialize;
+    type Create: CreateRequest<Self::Model>;
+    type Update: UpdateRequest<Self::Model>;
+
+    /// Permission check run first by every endpoint.
+    #[allow(clippy::result_large_err)]
+    fn authorize(_ctx: &Context, _action: Action) -> Result<(), Response> {
+        Ok(())
+    }
+}
+
+/// `{"items": [...], "page": 1, "per_page": 20, "total": 42, "total_pages": 3}`
+#[derive(Debug, Serialize)]
+pub struct Page<M> {
+    pub items: Vec<M>,
+    pub page: usize,
+    pub per_page: usize,
+    pub total: i64,
+    pub total_pages: usize,
+}
+
+#[derive(Deserialize)]
+struct PageParams {
+    #[serde(default = "first_page")]
+    page: usize,
+    #[serde(default = "default_per_page")]
+    per_page: usize,
+}
+
+fn first_page() -> usize {
+    1
+}
+
+fn default_per_page() -> usize {
+    DEFAULT_PER_PAGE
+}
+
+/// The `:id` segment, bound without a type so the server reads it as the
+/// primary key column's type.
+struct IdParam<'a>(&'a str);
+
+impl ToSql for IdParam<'_> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Text(self.0.to_string())
+    }
+}
+
+fn json<T: Serialize>(status: u16, value: &T) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => {
+            let mut res = Response::json_bytes(body);
+            res.status = status;
+            res
+        }
+        Err(_) => Response::server_error(),
+    }
+}
+
+fn invalid(errors: Vec<String>) -> Response {
+    json(422, &serde_json::json!({ "errors": errors }))
+}
+
+fn conflict(e: &OrmError) -> Response {
+    json(
+        409,
+        &serde_json::json!({ "error": "conflict", "constraint": e.constraint() }),
+    )
+}
+
+/// Keep a conflicting write's `409` as a response, passing other errors on.
+fn or_conflict<T>(result: OrmResult<T>) -> OrmResult<Result<T, Response>> {
+    match result {
+        Ok(value) => Ok(Ok(value)),
+        Err(e) if e.is_conflict() => Ok(Err(conflict(&e))),
+        Err(e) => Err(e),
+    }
+}
+
+fn primary_key<M: Model>() -> Option<&'static str> {
+    match M::primary_key_columns() {
+        [pk] => Some(pk),
+        _ => None,
+    }
+}
+
+/// Fetch the row named by `:id`.
+fn load<M: Model>(db: &mut dyn Executor, id: &str) -> OrmResult<Option<M>> {
+    let Some(pk) = primary_key::<M>() else {
+        return Err(OrmError::ModelError(
+            "crud routes need a single-column primary key".into(),
+        ));
+    };
+    let sql = format!(
+        "SELECT {} FROM {} WHERE {} = $1 LIMIT 1",
+        M::select_clause(),
+        M::table_name(),
+        pk
+    );
+    db.query(&sql, &[&IdParam(id)])?
+        .first()
+        .map(M::from_row)
+        .transpose()
+}
+
+fn respond<T: Serialize>(status: u16, result: crate::error::ChopinResult<T>) -> Response {
+    match result {
+        Ok(value) => json(status, &value),
+        Err(_) => Response::server_error(),
+    }
+}
+
+macro_rules! try_response {
+    ($e:expr) => {
+        match $e {
+            Ok(value) => value,
+            Err(res) => return res,
+        }
+    };
+}
+
+/// `GET {base}`
+pub fn list<R: Resource>(ctx: Context) -> Response {
+    try_response!(R::authorize(&ctx, Action::List));
+    let Query(params) = try_response!(ctx.extract::<Query<PageParams>>());
+    let page = params.page.max(1);
+    let per_page = params.per_page.clamp(1, MAX_PER_PAGE);
+    let Some(pk) = primary_key::<R::Model>() else {
+        return Response::server_error();
+    };
+    respond(
+        200,
+        db::with_model_db::<R::Model, _>(|mut db| {
+            let page = R::Model::find()
+                .order_by(pk)
+                .paginate(per_page)
+                .page(page)
+                .fetch(&mut db)?;
+            Ok(Page {
+                items: page.items,
+                page: page.page,
+                per_page: page.page_size,
+                total: page.total,
+                total_pages: page.total_pages,
+            })
+        }),
+    )
+}
+
+/// `GET {base}/:id`
+pub fn get<R: Resource>(ctx: Context) -> Response {
+    try_response!(R::authorize(&ctx, Action::Get));
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    match db::with_model_db::<R::Model, _>(|db| load::<R::Model>(db, id)) {
+        Ok(Some(model)) => json(200, &model),
+        Ok(None) => Response::not_found(),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `POST {base}`
+pub fn create<R: Resource>(ctx: Context) -> Response {
+    try_response!(R::authorize(&ctx, Action::Create));
+    let Json(body) = try_response!(ctx.extract::<Json<R::Create>>());
+    if let Err(errors) = body.validate() {
+        return invalid(errors);
+    }
+    let mut model = body.into_model();
+    if let Err(errors) = model.validate() {
+        return invalid(errors);
+    }
+    let result = db::with_model_db::<R::Model, _>(|mut db| {
+        or_conflict(model.insert(&mut db).map(|()| model))
+    });
+    match result {
+        Ok(Ok(model)) => json(201, &model),
+        Ok(Err(res)) => res,
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `PUT {base}/:id`
+pub fn update<R: Resource>(ctx: Context) -> Response {
+    try_response!(R::authorize(&ctx, Action::Update));
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    let Json(body) = try_response!(ctx.extract::<Json<R::Update>>());
+    if let Err(errors) = body.validate() {
+        return invalid(errors);
+    }
+    let result = db::with_model_db::<R::Model, _>(|mut db| {
+        let Some(mut model) = load::<R::Model>(db, id)? else {
+            return Ok(Err(Response::not_found()));
+        };
+        body.apply(&mut model);
+        if let Err(errors) = model.validate() {
+            return Ok(Err(invalid(errors)));
+        }
+        or_conflict(model.update(&mut db).map(|()| model))
+    });
+    match result {
+        Ok(Ok(model)) => json(200, &model),
+        Ok(Err(res)) => res,
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `DELETE {base}/:id`
+pub fn delete<R: Resource>(ctx: Context) -> Response {
+    try_response!(R::authorize(&ctx, Action::Delete));
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    let Some(pk) = primary_key::<R::Model>() else {
+        return Response::server_error();
+    };
+    let sql = format!("DELETE FROM {} WHERE {} = $1", R::Model::table_name(), pk);
+    match db::with_model_db::<R::Model, _>(|db| or_conflict(db.execute(&sql, &[&IdParam(id)]))) {
+        Ok(Ok(0)) => Response::not_found(),
+        Ok(Ok(_)) => Response::new(204),
+        Ok(Err(res)) => res,
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Registers list/get/create/update/delete routes for a model; see the
+/// [`crud`](crate::crud) module.
+///
+/// ```rust,ignore
+/// crud_routes!(Post, base = "/api/posts", create = CreatePostRequest, update = UpdatePostRequest);
+/// crud_routes!(
+///     Tag,
+///     base = "/admin/tags",
+///     create = NewTag,
+///     update = NewTag,
+///     authorize = require_admin,
+///     tag = "admin",
+/// );
+/// ```
+///
+/// `authorize` is any `fn(&Context, Action) -> Result<(), Response>` or
+/// closure of that shape.
+#[macro_export]
+macro_rules! crud_routes {
+    (
+        $model:ty,
+        base = $base:literal,
+        create = $create:ty,
+        update = $update:ty
+        $(, authorize = $authorize:expr)?
+        $(, tag = $tag:literal)?
+        $(,)?
+    ) => {
+        const _: () = {
+            struct __ChopinCrud;
+
+            impl $crate::crud::Resource for __ChopinCrud {
+                type Model = $model;
+                type Create = $create;
+                type Update = $update;
+
+                $(
+                    #[allow(clippy::result_large_err)]
+                    fn authorize(
+                        ctx: &$crate::http::Context,
+                        action: $crate::crud::Action,
+                    ) -> ::core::result::Result<(), $crate::http::Response> {
+                        let authorize: fn(
+                            &$crate::http::Context,
+                            $crate::crud::Action,
+                        ) -> ::core::result::Result<(), $crate::http::Response> = $authorize;
+                        authorize(ctx, action)
+                    }
+                )?
+            }
+
+            const TAGS: &[&str] = &[$crate::crud_routes!(@tag $model $(, $tag)?)];
+            const ID: &[$crate::PathParamDef] = &[$crate::PathParamDef {
+                name: "id",
+                openapi_type: "string",
+                openapi_format: ::core::option::Option::None,
+            }];
+
+            $crate::inventory::submit! {
+                $crate::RouteDef {
+                    method: $crate::http::Method::Get,
+                    path: $base,
+                    handler: $crate::crud::list::<__ChopinCrud>,
+                    summary: concat!("List ", stringify!($model)),
+                    description: "Paginated with `?page=` and `?per_page=`, ordered by primary key.",
+                    params: &[],
+                    tags: TAGS,
+                }
+            }
+            $crate::inventory::submit! {
+                $crate::RouteDef {
+                    method: $crate::http::Method::Post,
+                    path: $base,
+                    handler: $crate::crud::create::<__ChopinCrud>,
+                    summary: concat!("Create ", stringify!($model)),
+                    description: concat!("Body: `", stringify!($create), "`. Returns 201, or 422 when validation fails."),
+                    params: &[],
+                    tags: TAGS,
+                }
+            }
+            $crate::inventory::submit! {
+                $crate::RouteDef {
+                    method: $crate::http::Method::Get,
+                    path: concat!($base, "/:id"),
+                    handler: $crate::crud::get::<__ChopinCrud>,
+                    summary: concat!("Get ", stringify!($model)),
+                    description: "Returns 404 when no row has this primary key.",
+                    params: ID,
+                    tags: TAGS,
+                }
+            }
+            $crate::inventory::submit! {
+                $crate::RouteDef {
+                    method: $crate::http::Method::Put,
+                    path: concat!($base, "/:id"),
+                    handler: $crate::crud::update::<__ChopinCrud>,
+                    summary: concat!("Update ", stringify!($model)),
+                    description: concat!("Body: `", stringify!($update), "`. Returns 404 or 422 when validation fails."),
+                    params: ID,
+                    tags: TAGS,
+                }
+            }
+            $crate::inventory::submit! {
+                $crate::RouteDef {
+                    method: $crate::http::Method::Delete,
+                    path: concat!($base, "/:id"),
+                    handler: $crate::crud::delete::<__ChopinCrud>,
+                    summary: concat!("Delete ", stringify!($model)),
+                    description: "Returns 204, or 404 when no row has this primary key.",
+                    params: ID,
+                    tags: TAGS,
+                }
+            }
+        };
+    };
+    (@tag $model:ty) => { stringify!($model) };
+    (@tag $model:ty, $tag:literal) => { $tag };
+}
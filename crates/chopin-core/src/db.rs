@@ -0,0 +1,494 @@
+// src/db.rs
+//! Per-worker database access for handlers (`orm` feature).
+//!
+//! Workers share nothing, so each worker thread lazily opens its own
+//! executor through the connector installed with [`init_database`] and
+//! reuses it for every request it serves:
+//!
+//! ```rust,ignore
+//! chopin_core::db::init_database(|| Ok(Box::new(PgPool::connect(db_config(), 1)?)));
+//!
+//! fn list_users(_ctx: Context) -> Response {
+//!     match db::with_db(|db| User::find().all(db)) {
+//!         Ok(users) => Response::json(&users),
+//!         Err(_) => Response::server_error(),
+//!     }
+//! }
+//! ```
+//!
+//! Further databases are registered by name, each with its own executor per
+//! worker. [`load_databases`] registers every connection configured in
+//! `Chopin.toml` or the environment:
+//!
+//! ```toml
+//! [databases]
+//! default = "${DATABASE_URL}"
+//! analytics = "postgres://reporting@replica/analytics"
+//! ```
+//!
+//! `DATABASE_URL` and `<NAME>_DATABASE_URL` (e.g. `ANALYTICS_DATABASE_URL`)
+//! override the file. Models declared with `#[model(connection = "analytics")]`
+//! are reached through [`with_model_db`].
+//!
+//! [`transaction_per_request`] wraps whole requests under configured path
+//! prefixes in a transaction on the worker's executor:
+//!
+//! ```rust,ignore
+//! db::set_transaction_prefixes(db::DEFAULT_CONNECTION, ["/api/orders"]);
+//! router.layer(db::transaction_per_request);
+//! ```
+use crate::config::Config;
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Response};
+use crate::router::BoxedHandler;
+pub use chopin_orm::DEFAULT_CONNECTION;
+use chopin_orm::{Executor, Model, OrmError, OrmResult, PgError, PgPool};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// Opens a database executor for the calling worker.
+pub type Connector = fn() -> OrmResult<Box<dyn Executor>>;
+
+type DynConnector = Arc<dyn Fn() -> OrmResult<Box<dyn Executor>> + Send + Sync>;
+
+/// Config section read by [`load_databases`].
+pub const DATABASES_SECTION: &str = "databases";
+
+static CONNECTORS: RwLock<BTreeMap<String, DynConnector>> = RwLock::new(BTreeMap::new());
+
+enum Slot {
+    Idle(Box<dyn Executor>),
+    InUse,
+}
+
+thread_local! {
+    static EXECUTORS: RefCell<HashMap<String, Slot>> = RefCell::new(HashMap::new());
+    static IN_REQUEST_TX: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Install the connector for the default connection. Fails with the rejected
+/// connector if one is already installed.
+pub fn init_database(connector: Connector) -> Result<(), Connector> {
+    register(DEFAULT_CONNECTION, Arc::new(connector)).map_err(|_| connector)
+}
+
+/// Install the connector for the connection called `name`. Fails if that
+/// name is already registered.
+pub fn add_database(
+    name: &str,
+    connector: impl Fn() -> OrmResult<Box<dyn Executor>> + Send + Sync + 'static,
+) -> ChopinResult<()> {
+    register(name, Arc::new(connector))
+        .map_err(|_| ChopinError::Other(format!("database `{name}` is already registered")))
+}
+
+/// Register `name` as a connection to the Postgres database at `url`.
+pub fn add_database_url(name: &str, url: &str) -> ChopinResult<()> {
+    let config = chopin_orm::PgConfig::from_url(url)
+        .map_err(|e| ChopinError::Other(format!("database `{name}`: {e}")))?;
+    add_database(name, move || {
+        Ok(Box::new(PgPool::connect(config.clone(), 1)?) as Box<dyn Executor>)
+    })
+}
+
+fn register(name: &str, connector: DynConnector) -> Result<(), DynConnector> {
+    let mut connectors = CONNECTORS.write().unwrap_or_else(|e| e.into_inner());
+    if connectors.contains_key(name) {
+        return Err(connector);
+    }
+    connectors.insert(name.to_string(), connector);
+    Ok(())
+}
+
+/// Register the connections from the `[databases]` section of `config` and
+/// from `DATABASE_URL` / `<NAME>_DATABASE_URL` environment variables, which
+/// take precedence. Names already registered are left alone. Returns the
+/// names registered by this call.
+pub fn load_databases(config: &Config) -> ChopinResult<Vec<String>> {
+    let mut urls: BTreeMap<String, String> = if config.has_section(DATABASES_SECTION) {
+        config.extension(DATABASES_SECTION)?
+    } else {
+        BTreeMap::new()
+    };
+    urls.extend(env_database_urls(std::env::vars()));
+
+    let mut added = Vec::new();
+    for (name, url) in urls {
+        if url.is_empty() || is_registered(&name) {
+            continue;
+        }
+        add_database_url(&name, &url)?;
+        added.push(name);
+    }
+    Ok(added)
+}
+
+/// Map `DATABASE_URL` to the default connection and `<NAME>_DATABASE_URL` to
+/// the lower-cased `<name>`.
+fn env_database_urls(vars: impl Iterator<Item = (String, String)>) -> Vec<(String, String)> {
+    vars.filter_map(|(key, url)| {
+        let name = if key == "DATABASE_URL" {
+            DEFAULT_CONNECTION.to_string()
+        } else {
+            key.strip_suffix("_DATABASE_URL")
+                .filter(|n| !n.is_empty())?
+                .to_ascii_lowercase()
+        };
+        Some((name, url))
+    })
+    .collect()
+}
+
+/// Returns `true` once the default connection has been configured.
+pub fn is_configured() -> bool {
+    is_registered(DEFAULT_CONNECTION)
+}
+
+pub fn is_registered(name: &str) -> bool {
+    CONNECTORS
+        .read()
+        .map(|c| c.contains_key(name))
+        .unwrap_or(false)
+}
+
+/// Names of all registered connections.
+pub fn connection_names() -> Vec<String> {
+    CONNECTORS
+        .read()
+        .map(|c| c.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Open a fresh executor for `name`, outside the per-worker cache. Used for
+/// one-off work such as migrations.
+pub fn connect(name: &str) -> ChopinResult<Box<dyn Executor>> {
+    let connector = CONNECTORS
+        .read()
+        .ok()
+        .and_then(|c| c.get(name).cloned())
+        .ok_or_else(|| not_configured(name))?;
+    connector().map_err(orm_error)
+}
+
+fn not_configured(name: &str) -> ChopinError {
+    if name == DEFAULT_CONNECTION {
+        ChopinError::Other("database not configured; call init_database".into())
+    } else {
+        ChopinError::Other(format!("database `{name}` is not registered"))
+    }
+}
+
+/// Run `f` with this worker's executor for the default connection,
+/// connecting on first use.
+///
+/// A database-level error drops the cached executor so the next call
+/// reconnects. Calls for the same connection must not be nested.
+pub fn with_db<R>(f: impl FnOnce(&mut dyn Executor) -> OrmResult<R>) -> ChopinResult<R> {
+    with_named_db(DEFAULT_CONNECTION, f)
+}
+
+/// [`with_db`] for the connection called `name`.
+pub fn with_named_db<R>(
+    name: &str,
+    f: impl FnOnce(&mut dyn Executor) -> OrmResult<R>,
+) -> ChopinResult<R> {
+    let taken = EXECUTORS.with(|slots| {
+        let mut slots = slots.borrow_mut();
+        match slots.insert(name.to_string(), Slot::InUse) {
+            Some(Slot::Idle(executor)) => Ok(Some(executor)),
+            Some(Slot::InUse) => Err(ChopinError::Other(format!(
+                "nested with_db call for `{name}`"
+            ))),
+            None => Ok(None),
+        }
+    })?;
+
+    let mut executor = match taken {
+        Some(executor) => executor,
+        None => match connect(name) {
+            Ok(executor) => executor,
+            Err(e) => {
+                EXECUTORS.with(|slots| slots.borrow_mut().remove(name));
+                return Err(e);
+            }
+        },
+    };
+
+    // Frees the slot if `f` panics (the catch-panic feature keeps the worker
+    // alive), so the next request reconnects instead of seeing a nested call.
+    struct Release<'a>(&'a str);
+    impl Drop for Release<'_> {
+        fn drop(&mut self) {
+            let _ = EXECUTORS.try_with(|slots| slots.borrow_mut().remove(self.0));
+        }
+    }
+    let release = Release(name);
+    let result = f(executor.as_mut());
+    std::mem::forget(release);
+    CHECKOUTS.fetch_add(1, Ordering::Relaxed);
+    if let Err(OrmError::Database(PgError::PoolExhausted | PgError::PoolTimeout)) = &result {
+        POOL_FAILURES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    EXECUTORS.with(|slots| {
+        let mut slots = slots.borrow_mut();
+        if let Err(OrmError::Database(_)) = &result {
+            slots.remove(name);
+        } else {
+            slots.insert(name.to_string(), Slot::Idle(executor));
+        }
+    });
+    result.map_err(orm_error)
+}
+
+static CHECKOUTS: AtomicU64 = AtomicU64::new(0);
+static POOL_FAILURES: AtomicU64 = AtomicU64::new(0);
+
+/// Process-wide count of [`with_db`] calls so far, and of those that failed
+/// because a pool had no connection to hand out (exhausted or checkout
+/// timeout).
+pub fn checkout_counts() -> (u64, u64) {
+    (
+        CHECKOUTS.load(Ordering::Relaxed),
+        POOL_FAILURES.load(Ordering::Relaxed),
+    )
+}
+
+/// [`with_db`] for the connection model `M` is bound to with
+/// `#[model(connection = "...")]`.
+pub fn with_model_db<M: Model, R>(
+    f: impl FnOnce(&mut dyn Executor) -> OrmResult<R>,
+) -> ChopinResult<R> {
+    with_named_db(M::connection(), f)
+}
+
+/// Convert an ORM error for handlers that return [`ChopinResult`].
+pub fn orm_error(e: OrmError) -> ChopinError {
+    ChopinError::Other(format!("database: {e}"))
+}
+
+// ─── Transaction per request ─────────────────────────────────────────────────
+
+struct RequestTransactions {
+    connection: String,
+    prefixes: Vec<String>,
+}
+
+static REQUEST_TX: RwLock<Option<RequestTransactions>> = RwLock::new(None);
+
+/// Run requests whose path falls under one of `prefixes` in a transaction on
+/// `connection`, once [`transaction_per_request`] is layered on the router.
+/// Replaces any previous setting; an empty list turns it off.
+pub fn set_transaction_prefixes<S: Into<String>>(
+    connection: &str,
+    prefixes: impl IntoIterator<Item = S>,
+) {
+    let prefixes: Vec<String> = prefixes.into_iter().map(Into::into).collect();
+    let mut slot = REQUEST_TX.write().unwrap_or_else(|e| e.into_inner());
+    *slot = (!prefixes.is_empty()).then(|| RequestTransactions {
+        connection: connection.to_string(),
+        prefixes,
+    });
+}
+
+/// Whether the current request is running inside a
+/// [`transaction_per_request`] transaction.
+pub fn in_request_transaction() -> bool {
+    IN_REQUEST_TX.with(Cell::get)
+}
+
+/// Middleware wrapping matching requests in a transaction.
+///
+/// `BEGIN` is issued on this worker's executor for the configured connection
+/// before the handler runs, so every [`with_db`] call the handler makes sees
+/// the same transaction. The transaction commits when the response status is
+/// below 400 (redirects included) and rolls back otherwise. If the handler
+/// panics the executor is discarded, which makes the server roll back.
+///
+/// The connector must hand out a single connection (a `PgConnection` or a
+/// pool of size 1, as [`add_database_url`] registers); a larger pool may run
+/// the handler's queries outside the transaction.
+pub fn transaction_per_request(ctx: Context, next: BoxedHandler) -> Response {
+    let connection = {
+        let config = REQUEST_TX.read().unwrap_or_else(|e| e.into_inner());
+        match config.as_ref() {
+            Some(c)
+                if c.prefixes
+                    .iter()
+                    .any(|p| crate::cache::prefix_matches(p, ctx.req.path)) =>
+            {
+                c.connection.clone()
+            }
+            _ => return next(ctx),
+        }
+    };
+    if in_request_transaction() {
+        return next(ctx);
+    }
+    if with_named_db(&connection, |db| db.execute("BEGIN", &[])).is_err() {
+        return Response::server_error();
+    }
+
+    // Discards the executor, and with it the open transaction, if the
+    // handler panics.
+    struct Abandon<'a>(&'a str);
+    impl Drop for Abandon<'_> {
+        fn drop(&mut self) {
+            IN_REQUEST_TX.with(|f| f.set(false));
+            let _ = EXECUTORS.try_with(|slots| slots.borrow_mut().remove(self.0));
+        }
+    }
+    let abandon = Abandon(&connection);
+    IN_REQUEST_TX.with(|f| f.set(true));
+    let res = next(ctx);
+    IN_REQUEST_TX.with(|f| f.set(false));
+    std::mem::forget(abandon);
+
+    let end = if res.status < 400 {
+        "COMMIT"
+    } else {
+        "ROLLBACK"
+    };
+    match with_named_db(&connection, |db| db.execute(end, &[])) {
+        Ok(_) => res,
+        Err(_) if end == "ROLLBACK" => res,
+        Err(_) => Response::server_error(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chopin_orm::{MockExecutor, mock_row};
+
+    fn mock() -> OrmResult<Box<dyn Executor>> {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![mock_row!("n" => 1i64)]);
+        Ok(Box::new(db))
+    }
+
+    #[test]
+    fn test_named_connections_are_independent() {
+        add_database("test_reports", mock).unwrap();
+        assert!(add_database("test_reports", mock).is_err());
+        assert!(connection_names().contains(&"test_reports".to_string()));
+
+        // Different connections may nest; the same one may not.
+        let n = with_named_db("test_reports", |db| {
+            let rows = db.query("SELECT 1 AS n", &[])?;
+            let nested = with_named_db("test_reports", |_| Ok(()));
+            assert!(nested.is_err());
+            Ok(rows.len())
+        })
+        .unwrap();
+        assert_eq!(n, 1);
+
+        // The cached executor is reused: its one queued result is gone.
+        let n = with_named_db("test_reports", |db| Ok(db.query("SELECT 1", &[])?.len())).unwrap();
+        assert_eq!(n, 0);
+
+        assert!(with_named_db("test_missing", |_| Ok(())).is_err());
+    }
+
+    #[derive(chopin_orm::Model, Debug, Clone)]
+    #[model(table_name = "page_views", connection = "test_analytics")]
+    #[allow(dead_code)]
+    struct PageView {
+        #[model(primary_key)]
+        id: i64,
+        path: String,
+    }
+    impl chopin_orm::Validate for PageView {}
+
+    #[test]
+    fn test_model_connection_binding() {
+        assert_eq!(PageView::connection(), "test_analytics");
+        add_database("test_analytics", mock).unwrap();
+        let n = with_model_db::<PageView, _>(|db| Ok(db.query("SELECT 1", &[])?.len())).unwrap();
+        assert_eq!(n, 1);
+    }
+
+    fn fails(ctx: Context) -> Response {
+        assert!(in_request_transaction());
+        with_named_db("test_tx", |db| db.execute("INSERT INTO t VALUES (1)", &[])).unwrap();
+        if ctx.req.path.ends_with("/bad") {
+            Response::bad_request()
+        } else {
+            Response::text("ok")
+        }
+    }
+
+    fn queries(_ctx: Context) -> Response {
+        assert!(!in_request_transaction());
+        Response::text("ok")
+    }
+
+    thread_local! {
+        static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct Logging;
+    impl Executor for Logging {
+        fn execute(&mut self, query: &str, _: &[&dyn chopin_orm::ToSql]) -> OrmResult<u64> {
+            LOG.with(|l| l.borrow_mut().push(query.to_string()));
+            Ok(1)
+        }
+        fn query(
+            &mut self,
+            _: &str,
+            _: &[&dyn chopin_orm::ToSql],
+        ) -> OrmResult<Vec<chopin_orm::Row>> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_transaction_per_request() {
+        add_database("test_tx", || Ok(Box::new(Logging) as Box<dyn Executor>)).unwrap();
+        set_transaction_prefixes("test_tx", ["/tx"]);
+        let mut router = crate::router::Router::new();
+        router.layer(transaction_per_request);
+        router.get("/tx/ok", fails);
+        router.get("/tx/bad", fails);
+        router.get("/other", queries);
+        let app = crate::testing::TestApp::new(router);
+
+        assert_eq!(app.get("/tx/ok").status, 200);
+        assert_eq!(app.get("/tx/bad").status, 400);
+        assert_eq!(app.get("/other").status, 200);
+        set_transaction_prefixes::<String>("test_tx", []);
+
+        let log = LOG.with(|l| l.borrow().clone());
+        assert_eq!(
+            log,
+            [
+                "BEGIN",
+                "INSERT INTO t VALUES (1)",
+                "COMMIT",
+                "BEGIN",
+                "INSERT INTO t VALUES (1)",
+                "ROLLBACK",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_env_database_urls() {
+        let vars = [
+            ("DATABASE_URL", "postgres://a"),
+            ("ANALYTICS_DATABASE_URL", "postgres://b"),
+            ("_DATABASE_URL", "ignored"),
+            ("HOME", "/root"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        assert_eq!(
+            env_database_urls(vars.into_iter()),
+            [
+                ("default".to_string(), "postgres://a".to_string()),
+                ("analytics".to_string(), "postgres://b".to_string()),
+            ]
+        );
+    }
+}
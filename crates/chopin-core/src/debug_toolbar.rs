@@ -0,0 +1,176 @@
+// src/debug_toolbar.rs
+//! Per-request database profiling for development (`orm` feature).
+//!
+//! [`DebugToolbarModule`] layers [`query_counter`] over every route. Each
+//! response gets an `X-DB-Queries: 7 (12ms)` header, and the most recent
+//! requests are listed at `GET {prefix}/last-requests` with their query
+//! count, query time, model-cache hits and total duration:
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(DebugToolbarModule::default());
+//! ```
+//!
+//! The module is only enabled in debug builds: it exposes request paths and
+//! timings, and the history takes a process-wide lock per request.
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use chopin_orm::stats;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Header carrying the request's query count and query time.
+pub const QUERIES_HEADER: &str = "X-DB-Queries";
+
+/// Requests kept for [`last_requests`].
+pub const HISTORY_LEN: usize = 50;
+
+/// Database activity of one finished request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RequestProfile {
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+    /// Unix time the request finished, in milliseconds.
+    pub finished_at_ms: u64,
+    pub duration_us: u64,
+    pub queries: u32,
+    pub query_time_us: u64,
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+}
+
+static HISTORY: Mutex<VecDeque<RequestProfile>> = Mutex::new(VecDeque::new());
+
+/// Most recent requests, newest first.
+pub fn last_requests() -> Vec<RequestProfile> {
+    let history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    history.iter().rev().cloned().collect()
+}
+
+pub fn clear_history() {
+    HISTORY.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+fn record(profile: RequestProfile) {
+    let mut history = HISTORY.lock().unwrap_or_else(|e| e.into_inner());
+    if history.len() == HISTORY_LEN {
+        history.pop_front();
+    }
+    history.push_back(profile);
+}
+
+/// Middleware counting the queries a request runs on this worker.
+pub fn query_counter(ctx: Context, next: BoxedHandler) -> Response {
+    let method = crate::openapi::method_name(ctx.req.method).to_uppercase();
+    let path = ctx.req.path.to_string();
+    stats::reset();
+    let start = Instant::now();
+
+    let res = next(ctx);
+
+    let elapsed = start.elapsed();
+    let db = stats::take();
+    let finished_at_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    record(RequestProfile {
+        method,
+        path,
+        status: res.status,
+        finished_at_ms,
+        duration_us: elapsed.as_micros() as u64,
+        queries: db.queries,
+        query_time_us: db.query_time.as_micros() as u64,
+        cache_hits: db.cache_hits,
+        cache_misses: db.cache_misses,
+    });
+    res.with_header(
+        QUERIES_HEADER,
+        format!("{} ({}ms)", db.queries, db.query_time.as_millis()),
+    )
+}
+
+fn last_requests_handler(_ctx: Context) -> Response {
+    match serde_json::to_vec(&last_requests()) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Mounts [`query_counter`] globally and `GET {prefix}/last-requests`.
+pub struct DebugToolbarModule {
+    prefix: &'static str,
+}
+
+impl DebugToolbarModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+        }
+    }
+}
+
+impl Default for DebugToolbarModule {
+    fn default() -> Self {
+        Self::new("/api/_debug")
+    }
+}
+
+impl ChopinModule for DebugToolbarModule {
+    fn name(&self) -> &'static str {
+        "debug_toolbar"
+    }
+
+    fn enabled(&self) -> bool {
+        cfg!(debug_assertions)
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.layer(query_counter);
+        router.get(
+            &format!("{}/last-requests", self.prefix),
+            last_requests_handler,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chopin_orm::{Executor, MockExecutor};
+
+    fn two_queries(_ctx: Context) -> Response {
+        let mut db = MockExecutor::new();
+        let _ = db.query("SELECT 1", &[]);
+        let _ = db.execute("UPDATE t SET a = 1", &[]);
+        Response::text("ok")
+    }
+
+    #[test]
+    fn test_counts_queries_and_lists_requests() {
+        let mut router = Router::new();
+        DebugToolbarModule::default().routes(&mut router);
+        router.get("/toolbar-test/work", two_queries);
+        let app = crate::testing::TestApp::new(router);
+
+        let res = app.get("/toolbar-test/work");
+        assert_eq!(res.status, 200);
+        let header = res.header(QUERIES_HEADER).unwrap();
+        assert!(header.starts_with("2 ("), "{header}");
+
+        let json = app.get("/api/_debug/last-requests").json().unwrap();
+        let entry = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|r| r["path"] == "/toolbar-test/work")
+            .unwrap();
+        assert_eq!(entry["method"], "GET");
+        assert_eq!(entry["queries"], 2);
+        assert_eq!(entry["status"], 200);
+    }
+}
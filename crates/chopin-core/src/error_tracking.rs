@@ -0,0 +1,359 @@
+// src/error_tracking.rs
+//! Error reporting to Sentry (`sentry` feature).
+//!
+//! [`SentryModule`] is configured entirely from the environment and stays
+//! unmounted when `SENTRY_DSN` is unset, so the same binary runs with or
+//! without a tracker:
+//!
+//! ```rust,ignore
+//! Chopin::new()
+//!     .mount_module(LoggingModule::new())
+//!     .mount_module(SentryModule::from_env());
+//! ```
+//!
+//! | Variable             | Meaning                                          |
+//! |----------------------|--------------------------------------------------|
+//! | `SENTRY_DSN`         | Project DSN; required                            |
+//! | `SENTRY_ENVIRONMENT` | Environment name, e.g. `production`              |
+//! | `SENTRY_RELEASE`     | Release the events are tagged with               |
+//! | `SENTRY_SAMPLE_RATE` | Share of events sent, 0.0–1.0 (default 1.0)      |
+//! | `SENTRY_PII`         | [`PiiPolicy`]: `off` (default), `user-id`, `full` |
+//!
+//! Once started, the module reports:
+//!
+//! - panics in handlers, through Sentry's panic hook, while
+//!   [`capture_errors`] has the request on the scope;
+//! - handlers that answer `500`;
+//! - [`logging`](crate::logging) lines at `error` level. Lines at lower
+//!   levels become breadcrumbs on the next event from the same request;
+//! - any error passed to [`capture_error`], such as a [`ChopinError`] a
+//!   handler decided to swallow.
+//!
+//! Events carry the method, path and request id, and are tagged with the
+//! chopin version. What else leaves the process depends on the
+//! [`PiiPolicy`].
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Response};
+use crate::logging::Level;
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use sentry::protocol::{Breadcrumb, Request, User};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// How much about the user and request may be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PiiPolicy {
+    /// Method, path and request id only.
+    #[default]
+    Off,
+    /// Also the id given to [`logging::set_user_id`](crate::logging::set_user_id).
+    UserId,
+    /// Also the query string and request headers, minus those the global
+    /// [`Redactor`](crate::Redactor) treats as sensitive.
+    Full,
+}
+
+impl PiiPolicy {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" | "none" | "false" => Some(PiiPolicy::Off),
+            "user-id" | "user_id" => Some(PiiPolicy::UserId),
+            "full" | "true" => Some(PiiPolicy::Full),
+            _ => None,
+        }
+    }
+}
+
+/// Settings read from `SENTRY_*` variables.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentryConfig {
+    pub dsn: Option<String>,
+    pub environment: Option<String>,
+    pub release: Option<String>,
+    pub sample_rate: f32,
+    pub pii: PiiPolicy,
+}
+
+impl SentryConfig {
+    pub fn from_env() -> ChopinResult<Self> {
+        Self::from_lookup(|key| std::env::var(key).ok())
+    }
+
+    /// Build from any variable source. Empty values count as unset.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> ChopinResult<Self> {
+        let var = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+        let sample_rate = match var("SENTRY_SAMPLE_RATE") {
+            Some(v) => v
+                .trim()
+                .parse::<f32>()
+                .ok()
+                .filter(|r| (0.0..=1.0).contains(r))
+                .ok_or_else(|| {
+                    ChopinError::Other(format!("SENTRY_SAMPLE_RATE must be 0.0–1.0, got '{v}'"))
+                })?,
+            None => 1.0,
+        };
+        let pii = match var("SENTRY_PII") {
+            Some(v) => PiiPolicy::parse(v.trim()).ok_or_else(|| {
+                ChopinError::Other(format!(
+                    "SENTRY_PII must be off, user-id or full, got '{v}'"
+                ))
+            })?,
+            None => PiiPolicy::Off,
+        };
+        Ok(Self {
+            dsn: var("SENTRY_DSN"),
+            environment: var("SENTRY_ENVIRONMENT"),
+            release: var("SENTRY_RELEASE"),
+            sample_rate,
+            pii,
+        })
+    }
+}
+
+static PII: RwLock<PiiPolicy> = RwLock::new(PiiPolicy::Off);
+static GUARD: Mutex<Option<sentry::ClientInitGuard>> = Mutex::new(None);
+
+fn pii() -> PiiPolicy {
+    *PII.read().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Report `error` with the current request's context, if any.
+pub fn capture_error<E: std::error::Error + ?Sized>(error: &E) {
+    sentry::capture_error(error);
+}
+
+/// Turn a [`logging`](crate::logging) line into an event or breadcrumb.
+pub(crate) fn record_log(level: Level, target: &str, msg: &str, fields: &Map<String, Value>) {
+    if level == Level::Error {
+        sentry::with_scope(
+            |scope| {
+                scope.set_tag("logger", target);
+                for (key, value) in fields {
+                    scope.set_extra(key, value.clone());
+                }
+            },
+            || sentry::capture_message(msg, sentry::Level::Error),
+        );
+        return;
+    }
+    sentry::add_breadcrumb(Breadcrumb {
+        category: Some(target.to_string()),
+        message: Some(msg.to_string()),
+        level: match level {
+            Level::Error => sentry::Level::Error,
+            Level::Warn => sentry::Level::Warning,
+            Level::Info => sentry::Level::Info,
+            Level::Debug => sentry::Level::Debug,
+        },
+        data: fields.clone().into_iter().collect(),
+        ..Default::default()
+    });
+}
+
+/// Flush queued events, waiting at most `timeout`.
+pub fn flush(timeout: Duration) {
+    if let Some(client) = sentry::Hub::current().client() {
+        client.flush(Some(timeout));
+    }
+}
+
+/// Request context for events, limited by `policy`.
+fn request_context(ctx: &Context, policy: PiiPolicy) -> Request {
+    let mut req = Request {
+        method: Some(crate::openapi::method_name(ctx.req.method).to_uppercase()),
+        url: ctx
+            .header("Host")
+            .and_then(|host| format!("http://{host}{}", ctx.req.path).parse().ok()),
+        ..Default::default()
+    };
+    if policy == PiiPolicy::Full {
+        let redactor = crate::redact::global();
+        req.query_string = ctx.req.query.map(|q| redactor.redact_line(q));
+        req.headers = ctx.req.headers[..ctx.req.header_count as usize]
+            .iter()
+            .filter(|(name, _)| !redactor.is_sensitive(name))
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+    }
+    req
+}
+
+/// Middleware putting the request on the Sentry scope while the handler
+/// runs, and reporting `500` responses. Mount it inside
+/// [`request_logger`](crate::logging::request_logger) so events carry the
+/// request id.
+pub fn capture_errors(ctx: Context, next: BoxedHandler) -> Response {
+    let policy = pii();
+    let request = request_context(&ctx, policy);
+    let path = ctx.req.path.to_string();
+    sentry::with_scope(
+        move |scope| {
+            scope.set_transaction(Some(&path));
+            if let Some(id) = crate::logging::request_id() {
+                scope.set_tag("request_id", id);
+            }
+            scope.add_event_processor(move |mut event| {
+                if policy != PiiPolicy::Off {
+                    event.user = crate::logging::user_id().map(|id| User {
+                        id: Some(id),
+                        ..Default::default()
+                    });
+                }
+                event.request.get_or_insert_with(|| request.clone());
+                Some(event)
+            });
+        },
+        || {
+            let res = next(ctx);
+            if res.status == 500 {
+                sentry::capture_message("handler returned 500", sentry::Level::Error);
+            }
+            res
+        },
+    )
+}
+
+/// Starts the Sentry client on start and mounts [`capture_errors`].
+pub struct SentryModule {
+    config: ChopinResult<SentryConfig>,
+}
+
+impl SentryModule {
+    pub fn from_env() -> Self {
+        Self {
+            config: SentryConfig::from_env(),
+        }
+    }
+
+    pub fn with_config(config: SentryConfig) -> Self {
+        Self { config: Ok(config) }
+    }
+}
+
+impl ChopinModule for SentryModule {
+    fn name(&self) -> &'static str {
+        "sentry"
+    }
+
+    /// Mounted when a DSN is set, or when the variables are invalid so
+    /// that [`on_start`](Self::on_start) can report them.
+    fn enabled(&self) -> bool {
+        self.config.as_ref().map_or(true, |c| c.dsn.is_some())
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.layer(capture_errors);
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        let config = self
+            .config
+            .as_ref()
+            .map_err(|e| ChopinError::Other(e.to_string()))?;
+        let dsn = config
+            .dsn
+            .as_deref()
+            .map(str::parse)
+            .transpose()
+            .map_err(|e| ChopinError::Other(format!("SENTRY_DSN: {e}")))?;
+        let guard = sentry::init(sentry::ClientOptions {
+            dsn,
+            environment: config.environment.clone().map(Cow::Owned),
+            release: config.release.clone().map(Cow::Owned),
+            sample_rate: config.sample_rate,
+            send_default_pii: config.pii == PiiPolicy::Full,
+            attach_stacktrace: true,
+            ..Default::default()
+        });
+        sentry::configure_scope(|scope| scope.set_tag("chopin.version", env!("CARGO_PKG_VERSION")));
+        *PII.write().unwrap_or_else(|e| e.into_inner()) = config.pii;
+        *GUARD.lock().unwrap_or_else(|e| e.into_inner()) = Some(guard);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{MAX_HEADERS, MAX_PARAMS, Method, Request as HttpRequest};
+
+    fn lookup(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: Vec<(String, String)> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |key| vars.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_config_from_env() {
+        let cfg = SentryConfig::from_lookup(lookup(&[
+            ("SENTRY_DSN", "https://key@o1.ingest.sentry.io/2"),
+            ("SENTRY_RELEASE", "shop@1.4.0"),
+            ("SENTRY_SAMPLE_RATE", "0.25"),
+            ("SENTRY_PII", "user-id"),
+            ("SENTRY_ENVIRONMENT", ""),
+        ]))
+        .unwrap();
+        assert_eq!(
+            cfg.dsn.as_deref(),
+            Some("https://key@o1.ingest.sentry.io/2")
+        );
+        assert_eq!(cfg.release.as_deref(), Some("shop@1.4.0"));
+        assert_eq!(cfg.environment, None);
+        assert_eq!(cfg.sample_rate, 0.25);
+        assert_eq!(cfg.pii, PiiPolicy::UserId);
+
+        let unset = SentryConfig::from_lookup(lookup(&[])).unwrap();
+        assert_eq!((unset.dsn, unset.pii), (None, PiiPolicy::Off));
+        assert!(
+            !SentryModule::with_config(SentryConfig::from_lookup(lookup(&[])).unwrap()).enabled()
+        );
+
+        assert!(SentryConfig::from_lookup(lookup(&[("SENTRY_SAMPLE_RATE", "2")])).is_err());
+        assert!(SentryConfig::from_lookup(lookup(&[("SENTRY_PII", "some")])).is_err());
+    }
+
+    #[test]
+    fn test_request_context_follows_pii_policy() {
+        let mut headers = [("", ""); MAX_HEADERS];
+        headers[0] = ("Host", "shop.example");
+        headers[1] = ("Authorization", "Bearer abc");
+        headers[2] = ("Accept", "text/html");
+        let ctx = Context {
+            req: HttpRequest {
+                method: Method::Post,
+                path: "/orders",
+                query: Some("token=abc&page=2"),
+                headers,
+                header_count: 3,
+                body: &[],
+            },
+            params: [("", ""); MAX_PARAMS],
+            param_count: 0,
+        };
+
+        let off = request_context(&ctx, PiiPolicy::Off);
+        assert_eq!(off.method.as_deref(), Some("POST"));
+        assert_eq!(
+            off.url.map(|u| u.to_string()).as_deref(),
+            Some("http://shop.example/orders")
+        );
+        assert_eq!((off.query_string, off.headers.len()), (None, 0));
+
+        let full = request_context(&ctx, PiiPolicy::Full);
+        assert_eq!(
+            full.query_string.as_deref(),
+            Some("token=[REDACTED]&page=2")
+        );
+        assert_eq!(
+            full.headers.get("Accept").map(String::as_str),
+            Some("text/html")
+        );
+        assert!(!full.headers.contains_key("Authorization"));
+    }
+}
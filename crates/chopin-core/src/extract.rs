@@ -5,6 +5,14 @@
 //! let Json(body) = ctx.extract::<Json<MyPayload>>()?;
 //! let Query(params) = ctx.extract::<Query<Pagination>>()?;
 //! ```
+//!
+//! Path parameters are typed in the handler signature of a route macro; the
+//! argument names must match the `:name` segments, which is checked at
+//! compile time:
+//! ```rust,ignore
+//! #[get("/users/:id/posts/:slug")]
+//! fn show(ctx: Context, id: u64, slug: String) -> Response { .. }
+//! ```
 
 use crate::http::{Context, Response};
 use serde::Deserialize;
@@ -60,3 +68,51 @@ where
         }
     }
 }
+
+/// A type a path parameter can be parsed into. A value that fails to parse
+/// is answered with `400 Bad Request` before the handler runs.
+pub trait PathParam: Sized {
+    /// OpenAPI schema `type`.
+    const OPENAPI_TYPE: &'static str;
+    /// OpenAPI schema `format`, if any.
+    const OPENAPI_FORMAT: Option<&'static str> = None;
+
+    fn parse_param(value: &str) -> Option<Self>;
+}
+
+macro_rules! path_param {
+    ($($ty:ty => $openapi_type:literal $(, $format:literal)?;)*) => {$(
+        impl PathParam for $ty {
+            const OPENAPI_TYPE: &'static str = $openapi_type;
+            $(const OPENAPI_FORMAT: Option<&'static str> = Some($format);)?
+
+            fn parse_param(value: &str) -> Option<Self> {
+                value.parse().ok()
+            }
+        }
+    )*};
+}
+
+path_param! {
+    i8 => "integer", "int32";
+    i16 => "integer", "int32";
+    i32 => "integer", "int32";
+    i64 => "integer", "int64";
+    isize => "integer", "int64";
+    u8 => "integer", "int32";
+    u16 => "integer", "int32";
+    u32 => "integer", "int64";
+    u64 => "integer", "int64";
+    usize => "integer", "int64";
+    f32 => "number", "float";
+    f64 => "number", "double";
+    bool => "boolean";
+    char => "string";
+    String => "string";
+}
+
+/// Parse the path parameter `name` for a typed route handler. `None` if it
+/// is missing or does not parse.
+pub fn path_param<T: PathParam>(ctx: &Context, name: &str) -> Option<T> {
+    ctx.param(name).and_then(T::parse_param)
+}
@@ -0,0 +1,522 @@
+// src/form.rs
+//! HTML form posts: the [`Form`] extractor, CSRF protection, and helpers for
+//! re-rendering a rejected form.
+//!
+//! [`Form<T>`] parses an `application/x-www-form-urlencoded` body into `T`.
+//! Once [`CsrfModule`] is mounted, every `Form` extraction also checks the
+//! submitted `_csrf` field (or `X-CSRF-Token` header) against the CSRF
+//! cookie and answers `403 Forbidden` on a mismatch, so handlers cannot
+//! forget the check:
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(CsrfModule::new());
+//!
+//! #[get("/signup")]
+//! fn signup_page(_ctx: Context) -> Response {
+//!     render_signup(&OldInput::default(), &FormErrors::new())
+//! }
+//!
+//! #[post("/signup")]
+//! #[validate(Form<Signup>, |Form(s)| s.terms == "on")]
+//! fn signup(ctx: Context, form: Form<Signup>) -> Response {
+//!     let mut errors = FormErrors::new();
+//!     if !form.email.contains('@') {
+//!         errors.add("email", "is not an email address");
+//!     }
+//!     if !errors.is_empty() {
+//!         let old = ctx.extract::<OldInput>().unwrap_or_default();
+//!         let mut res = render_signup(&old, &errors);
+//!         res.status = 422;
+//!         return res;
+//!     }
+//!     ..
+//! }
+//! ```
+//!
+//! Templates put [`csrf_field`] inside each `<form method="post">` and fill
+//! inputs back in with [`OldInput::escaped`].
+//!
+//! The token is a random value kept in a `SameSite=Lax` cookie and echoed by
+//! the form (double-submit), so it needs no server-side state.
+use crate::extract::FromRequest;
+use crate::http::{Context, Method, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use serde::de::DeserializeOwned;
+use std::cell::RefCell;
+use std::sync::RwLock;
+
+/// Form field carrying the CSRF token.
+pub const CSRF_FIELD: &str = "_csrf";
+
+/// Header carrying the CSRF token, for scripts posting forms.
+pub const CSRF_HEADER: &str = "X-CSRF-Token";
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// urlencoded form body extractor.
+///
+/// Answers `415 Unsupported Media Type` for other content types (read
+/// `multipart/form-data` uploads with [`Context::multipart`]), `403
+/// Forbidden` when CSRF protection is on and the token does not match, and
+/// `400 Bad Request` when the fields do not deserialize into `T`. The CSRF
+/// field is removed before deserializing, so `T` need not declare it.
+pub struct Form<T>(pub T);
+
+impl<T> std::ops::Deref for Form<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<'a, T> FromRequest<'a> for Form<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = Response;
+
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        let is_form = ctx.header("content-type").is_some_and(|ct| {
+            ct.split(';')
+                .next()
+                .is_some_and(|t| t.trim().eq_ignore_ascii_case(FORM_CONTENT_TYPE))
+        });
+        if !is_form {
+            return Err(Response::new(415));
+        }
+        let pairs: Vec<(String, String)> =
+            serde_urlencoded::from_bytes(ctx.req.body).map_err(|_| Response::bad_request())?;
+        if let Some(settings) = csrf_settings() {
+            let submitted = pairs
+                .iter()
+                .find(|(k, _)| k == CSRF_FIELD)
+                .map(|(_, v)| v.as_str())
+                .or_else(|| ctx.header(CSRF_HEADER));
+            if !token_matches(ctx.cookie(&settings.cookie), submitted) {
+                return Err(Response::forbidden());
+            }
+        }
+        let fields: Vec<_> = pairs.into_iter().filter(|(k, _)| k != CSRF_FIELD).collect();
+        let encoded = serde_urlencoded::to_string(&fields).map_err(|_| Response::bad_request())?;
+        serde_urlencoded::from_str(&encoded)
+            .map(Form)
+            .map_err(|_| Response::bad_request())
+    }
+}
+
+/// Field-level validation messages for re-rendering a form.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormErrors {
+    errors: Vec<(String, String)>,
+}
+
+impl FormErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.errors.push((field.to_string(), message.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// First message for `field`.
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.errors
+            .iter()
+            .find(|(f, _)| f == field)
+            .map(|(_, m)| m.as_str())
+    }
+
+    pub fn all<'s>(&'s self, field: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.errors
+            .iter()
+            .filter(move |(f, _)| f == field)
+            .map(|(_, m)| m.as_str())
+    }
+
+    /// `{"email": ["is required"], ...}`, for script-driven forms.
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut map = serde_json::Map::new();
+        for (field, message) in &self.errors {
+            if let serde_json::Value::Array(list) = map
+                .entry(field.clone())
+                .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+            {
+                list.push(message.clone().into());
+            }
+        }
+        map.into()
+    }
+}
+
+/// The submitted fields of a urlencoded form, for filling inputs back in
+/// after a rejected submission. The CSRF field is left out.
+#[derive(Debug, Clone, Default)]
+pub struct OldInput {
+    fields: Vec<(String, String)>,
+}
+
+impl OldInput {
+    /// Raw value of `field`, or `""` if it was not submitted.
+    pub fn get(&self, field: &str) -> &str {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == field)
+            .map_or("", |(_, v)| v.as_str())
+    }
+
+    /// Every value of a repeated field (checkboxes, multi-selects).
+    pub fn values<'s>(&'s self, field: &'s str) -> impl Iterator<Item = &'s str> + 's {
+        self.fields
+            .iter()
+            .filter(move |(k, _)| k == field)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Value of `field` escaped for an HTML attribute or text node.
+    pub fn escaped(&self, field: &str) -> String {
+        escape_html(self.get(field))
+    }
+
+    /// Whether `value` was submitted for `field`, for `checked`/`selected`.
+    pub fn has(&self, field: &str, value: &str) -> bool {
+        self.fields.iter().any(|(k, v)| k == field && v == value)
+    }
+}
+
+impl<'a> FromRequest<'a> for OldInput {
+    type Error = Response;
+
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        let fields: Vec<(String, String)> =
+            serde_urlencoded::from_bytes(ctx.req.body).map_err(|_| Response::bad_request())?;
+        Ok(Self {
+            fields: fields
+                .into_iter()
+                .filter(|(k, _)| k != CSRF_FIELD)
+                .collect(),
+        })
+    }
+}
+
+/// Escape `&`, `<`, `>`, `"` and `'` for HTML.
+pub fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// ─── CSRF ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone)]
+struct CsrfSettings {
+    cookie: String,
+    secure: bool,
+}
+
+static CSRF: RwLock<Option<CsrfSettings>> = RwLock::new(None);
+
+thread_local! {
+    /// Token of the request being handled on this thread, set by
+    /// [`csrf_cookie`] for [`csrf_token`].
+    static TOKEN: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn csrf_settings() -> Option<CsrfSettings> {
+    CSRF.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn token_matches(cookie: Option<&str>, submitted: Option<&str>) -> bool {
+    match (cookie, submitted) {
+        (Some(c), Some(s)) if !c.is_empty() => constant_time_eq(c.as_bytes(), s.as_bytes()),
+        _ => false,
+    }
+}
+
+/// Compare secrets without leaking where they differ. Only the length
+/// comparison returns early.
+pub(crate) fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+pub(crate) fn new_token() -> String {
+    let mut bytes = [0u8; 32];
+    if let Err(e) = crate::logging::random_bytes(&mut bytes) {
+        // Without randomness the token is guessable; refuse to hand one out.
+        panic!("[chopin] cannot read /dev/urandom for a token: {e}");
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn is_token(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// CSRF token of the current request, for rendering into a form. Empty when
+/// [`CsrfModule`] is not mounted.
+pub fn csrf_token() -> String {
+    TOKEN.with(|t| t.borrow().clone()).unwrap_or_default()
+}
+
+/// `<input type="hidden" name="_csrf" value="...">` for the current request.
+pub fn csrf_field() -> String {
+    format!(
+        r#"<input type="hidden" name="{CSRF_FIELD}" value="{}">"#,
+        csrf_token()
+    )
+}
+
+/// Middleware making the CSRF token available to [`csrf_token`], and
+/// issuing the cookie to clients that have none. Installed by
+/// [`CsrfModule`].
+pub fn csrf_cookie(ctx: Context, next: BoxedHandler) -> Response {
+    let Some(settings) = csrf_settings() else {
+        return next(ctx);
+    };
+    let existing = ctx.cookie(&settings.cookie).filter(|t| is_token(t));
+    let token = existing.map_or_else(new_token, str::to_string);
+    let issue = existing.is_none();
+    let safe = matches!(ctx.req.method, Method::Get | Method::Head);
+    TOKEN.with(|t| *t.borrow_mut() = Some(token.clone()));
+
+    let res = next(ctx);
+
+    TOKEN.with(|t| t.borrow_mut().take());
+    // Only pages that can render a form get a new cookie; issuing one while
+    // rejecting a POST would just let a retry through.
+    if issue && safe {
+        let secure = if settings.secure { "; Secure" } else { "" };
+        return res.with_header(
+            "Set-Cookie",
+            format!(
+                "{}={token}; Path=/; HttpOnly; SameSite=Lax{secure}",
+                settings.cookie
+            ),
+        );
+    }
+    res
+}
+
+/// Turns on CSRF checking for every [`Form`] extraction and installs
+/// [`csrf_cookie`].
+pub struct CsrfModule {
+    cookie: String,
+    secure: bool,
+}
+
+impl CsrfModule {
+    pub fn new() -> Self {
+        Self {
+            cookie: "chopin_csrf".to_string(),
+            secure: true,
+        }
+    }
+
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie = name.to_string();
+        self
+    }
+
+    /// Mark the cookie `Secure` (the default). Turn off for plain-HTTP
+    /// development servers, where browsers would drop it.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+}
+
+impl Default for CsrfModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChopinModule for CsrfModule {
+    fn name(&self) -> &'static str {
+        "csrf"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        *CSRF.write().unwrap_or_else(|e| e.into_inner()) = Some(CsrfSettings {
+            cookie: self.cookie.clone(),
+            secure: self.secure,
+        });
+        router.layer(csrf_cookie);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TestApp;
+    use serde::Deserialize;
+    use std::sync::Mutex;
+
+    /// Serializes tests that depend on whether CSRF is on.
+    static CSRF_LOCK: Mutex<()> = Mutex::new(());
+
+    const FORM: (&str, &str) = ("Content-Type", "application/x-www-form-urlencoded");
+
+    #[derive(Deserialize)]
+    #[serde(deny_unknown_fields)]
+    struct Signup {
+        email: String,
+        tags: Option<String>,
+    }
+
+    fn signup(ctx: Context) -> Response {
+        match ctx.extract::<Form<Signup>>() {
+            Ok(form) => Response::text(format!("{}|{:?}", form.email, form.tags)),
+            Err(res) => res,
+        }
+    }
+
+    fn page(_ctx: Context) -> Response {
+        Response::text(csrf_field())
+    }
+
+    fn app() -> TestApp {
+        let mut router = Router::new();
+        router.get("/form-test/page", page);
+        router.post("/form-test/signup", signup);
+        TestApp::new(router)
+    }
+
+    #[test]
+    fn test_form_parses_urlencoded_body() {
+        let _lock = CSRF_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        *CSRF.write().unwrap() = None;
+        let app = app();
+        let res = app.request(
+            Method::Post,
+            "/form-test/signup",
+            &[FORM],
+            b"email=a%40b.c&tags=x+y",
+        );
+        assert_eq!(res.text(), r#"a@b.c|Some("x y")"#);
+
+        let json = app.request(
+            Method::Post,
+            "/form-test/signup",
+            &[("Content-Type", "application/json")],
+            b"{}",
+        );
+        assert_eq!(json.status, 415);
+        let missing = app.request(Method::Post, "/form-test/signup", &[FORM], b"tags=x");
+        assert_eq!(missing.status, 400);
+    }
+
+    #[test]
+    fn test_csrf_cookie_and_form_check() {
+        let _lock = CSRF_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = Router::new();
+        CsrfModule::new().secure(false).routes(&mut router);
+        router.get("/form-test/page", page);
+        router.post("/form-test/signup", signup);
+        let app = TestApp::new(router);
+
+        let page = app.get("/form-test/page");
+        let cookie = page.header("Set-Cookie").unwrap().to_string();
+        assert!(cookie.ends_with("; Path=/; HttpOnly; SameSite=Lax"));
+        let token = cookie
+            .strip_prefix("chopin_csrf=")
+            .and_then(|c| c.split(';').next())
+            .unwrap()
+            .to_string();
+        assert_eq!(
+            page.text(),
+            format!(r#"<input type="hidden" name="_csrf" value="{token}">"#)
+        );
+
+        let cookie_header = format!("chopin_csrf={token}");
+        let post = |headers: &[(&str, &str)], body: String| {
+            app.request(Method::Post, "/form-test/signup", headers, body.as_bytes())
+        };
+        let ok = post(
+            &[FORM, ("Cookie", &cookie_header)],
+            format!("email=a&_csrf={token}"),
+        );
+        assert_eq!(ok.status, 200);
+        assert_eq!(ok.text(), "a|None");
+
+        let via_header = post(
+            &[FORM, ("Cookie", &cookie_header), (CSRF_HEADER, &token)],
+            "email=a".to_string(),
+        );
+        assert_eq!(via_header.status, 200);
+
+        let forged = post(
+            &[FORM, ("Cookie", &cookie_header)],
+            format!("email=a&_csrf={}", "0".repeat(64)),
+        );
+        assert_eq!(forged.status, 403);
+        let no_cookie = post(&[FORM], format!("email=a&_csrf={token}"));
+        assert_eq!(no_cookie.status, 403);
+        assert!(no_cookie.header("Set-Cookie").is_none());
+
+        // A client that already has a cookie is not sent a new one.
+        let again = app.request(
+            Method::Get,
+            "/form-test/page",
+            &[("Cookie", &cookie_header)],
+            b"",
+        );
+        assert!(again.header("Set-Cookie").is_none());
+        assert!(again.text().contains(&token));
+
+        *CSRF.write().unwrap() = None;
+    }
+
+    fn echo_old(ctx: Context) -> Response {
+        let old = ctx.extract::<OldInput>().unwrap_or_default();
+        Response::text(format!(
+            "{}|{}|{}|{}",
+            old.escaped("name"),
+            old.get("_csrf"),
+            old.has("color", "blue"),
+            old.has("color", "green"),
+        ))
+    }
+
+    #[test]
+    fn test_old_input_and_errors() {
+        let mut router = Router::new();
+        router.post("/form-test/old", echo_old);
+        let res = TestApp::new(router).request(
+            Method::Post,
+            "/form-test/old",
+            &[FORM],
+            b"name=%3Cb%3E&_csrf=t&color=red&color=blue",
+        );
+        assert_eq!(res.text(), "&lt;b&gt;||true|false");
+
+        let mut errors = FormErrors::new();
+        assert!(errors.is_empty());
+        errors.add("email", "is required");
+        errors.add("email", "is too short");
+        assert_eq!(errors.get("email"), Some("is required"));
+        assert_eq!(errors.get("name"), None);
+        assert_eq!(
+            errors.to_json(),
+            serde_json::json!({"email": ["is required", "is too short"]})
+        );
+    }
+}
@@ -0,0 +1,41 @@
+// src/guard.rs
+//! Runtime for the `#[authorize]` and `#[validate]` handler attributes.
+//!
+//! Both attributes take an extractor type and an optional predicate, and
+//! any number of them can be stacked on one handler, above or below its
+//! route attribute:
+//!
+//! ```rust,ignore
+//! #[post("/api/posts/:id/comments")]
+//! #[authorize(Auth<Claims>)]
+//! #[authorize(Auth<Claims>, |auth| auth.claims.has_scope("comments:write"))]
+//! #[validate(Json<NewComment>, |Json(c)| !c.body.is_empty())]
+//! fn comment(ctx: Context, id: u64, auth: Auth<Claims>, body: Json<NewComment>) -> Response { .. }
+//! ```
+//!
+//! All guards of a handler expand into one chain:
+//!
+//! - `#[authorize]` guards run before `#[validate]` guards, each kind in
+//!   source order, so unauthenticated callers never see validation errors.
+//! - Each distinct extractor type is extracted once, by the first guard
+//!   naming it. A failed extraction returns the extractor's error response.
+//! - A failed predicate returns `403 Forbidden` for `#[authorize]` and
+//!   `422 Unprocessable Entity` for `#[validate]`.
+//! - A handler argument whose type is a guarded extractor receives the
+//!   extracted value; the remaining arguments are path parameters. Injected
+//!   extractors must own their data (e.g. `Json<T>` with `T: DeserializeOwned`).
+//!
+//! Typed path parameters are parsed before the chain runs.
+//! `#[role_required("staff")]` from `chopin_auth` is an `#[authorize]` guard
+//! on its `Roles` extractor and follows the same rules, as are
+//! `#[owner_required(Post, ..)]`, whose "extraction" loads the `Post` row,
+//! and `#[mfa_required]`, which extracts `MfaVerified`.
+
+/// Applies a guard predicate to an extracted value. Taking the predicate
+/// as `impl FnOnce(&T)` lets closures in guard attributes infer their
+/// argument type.
+#[doc(hidden)]
+#[inline]
+pub fn check<T>(value: &T, pred: impl FnOnce(&T) -> bool) -> bool {
+    pred(value)
+}
@@ -0,0 +1,295 @@
+// src/health.rs
+//! Liveness and load-aware readiness probes.
+//!
+//! [`HealthModule`] mounts `GET {prefix}/live`, which answers `200` while the
+//! process serves requests at all, and `GET {prefix}/ready`, which answers
+//! `503` once the server is saturated, before requests start failing, so an
+//! orchestrator moves traffic elsewhere while this instance catches up:
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(
+//!     HealthModule::default()
+//!         .max_loop_lag(Duration::from_millis(100))
+//!         .max_pool_failure_rate(0.02),
+//! );
+//! ```
+//!
+//! Readiness looks at three signals, each with its own threshold:
+//!
+//! - **event-loop lag** — the longest batch any worker's event loop spent on
+//!   its last round of ready sockets;
+//! - **accept queue** — how full the kernel's listen backlog is;
+//! - **pool failures** (`orm` feature) — the share of
+//!   [`with_db`](crate::db::with_db) calls over the last
+//!   [`POOL_WINDOW`] that failed because a pool had no connection to give.
+//!
+//! `/ready` answers with the JSON [`Readiness`] report either way.
+use crate::http::{Context, Response};
+use crate::metrics::MetricsSnapshot;
+use crate::module::ChopinModule;
+use crate::router::Router;
+use serde::Serialize;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// Span over which the pool failure rate is measured.
+pub const POOL_WINDOW: Duration = Duration::from_secs(10);
+
+/// Fewer checkouts than this in a window are too few to judge the pool by.
+pub const MIN_POOL_CHECKOUTS: u64 = 20;
+
+/// Saturation limits past which `/ready` reports not ready.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Thresholds {
+    pub max_loop_lag: Duration,
+    /// Fill level of the accept queue, 0.0–1.0.
+    pub max_accept_queue: f64,
+    /// Share of failed checkouts, 0.0–1.0.
+    pub max_pool_failure_rate: f64,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Self {
+            max_loop_lag: Duration::from_millis(250),
+            max_accept_queue: 0.5,
+            max_pool_failure_rate: 0.05,
+        }
+    }
+}
+
+static THRESHOLDS: RwLock<Option<Thresholds>> = RwLock::new(None);
+
+/// Outcome of one readiness check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    pub loop_lag_us: usize,
+    pub accept_queue: f64,
+    pub pool_failure_rate: f64,
+    /// One line per exceeded threshold.
+    pub reasons: Vec<String>,
+}
+
+/// Judge saturation from worker snapshots and the pool failure rate.
+pub fn evaluate(
+    workers: &[MetricsSnapshot],
+    pool_failure_rate: f64,
+    limits: &Thresholds,
+) -> Readiness {
+    let loop_lag_us = workers.iter().map(|w| w.loop_lag_us).max().unwrap_or(0);
+    let accept_queue = workers
+        .iter()
+        .filter(|w| w.accept_queue_max > 0)
+        .map(|w| w.accept_queue as f64 / w.accept_queue_max as f64)
+        .fold(0.0, f64::max);
+
+    let mut reasons = Vec::new();
+    if loop_lag_us as u128 >= limits.max_loop_lag.as_micros() {
+        reasons.push(format!(
+            "event-loop lag {} ms (limit {} ms)",
+            loop_lag_us / 1000,
+            limits.max_loop_lag.as_millis()
+        ));
+    }
+    if accept_queue >= limits.max_accept_queue {
+        reasons.push(format!(
+            "accept queue {:.0}% full (limit {:.0}%)",
+            accept_queue * 100.0,
+            limits.max_accept_queue * 100.0
+        ));
+    }
+    if pool_failure_rate >= limits.max_pool_failure_rate {
+        reasons.push(format!(
+            "{:.1}% of database checkouts failed (limit {:.1}%)",
+            pool_failure_rate * 100.0,
+            limits.max_pool_failure_rate * 100.0
+        ));
+    }
+    Readiness {
+        ready: reasons.is_empty(),
+        loop_lag_us,
+        accept_queue,
+        pool_failure_rate,
+        reasons,
+    }
+}
+
+/// Failure rate over the last complete [`POOL_WINDOW`]. Windows roll over
+/// when a probe finds the current one has run its length, so the figure
+/// is at most two windows old.
+#[cfg(feature = "orm")]
+fn pool_failure_rate() -> f64 {
+    use std::sync::Mutex;
+    use std::time::Instant;
+
+    struct Window {
+        started: Instant,
+        checkouts: u64,
+        failures: u64,
+        rate: f64,
+    }
+    static WINDOW: Mutex<Option<Window>> = Mutex::new(None);
+
+    let (checkouts, failures) = crate::db::checkout_counts();
+    let mut window = WINDOW.lock().unwrap_or_else(|e| e.into_inner());
+    let w = window.get_or_insert_with(|| Window {
+        started: Instant::now(),
+        checkouts,
+        failures,
+        rate: 0.0,
+    });
+    if w.started.elapsed() >= POOL_WINDOW {
+        let n = checkouts - w.checkouts;
+        w.rate = if n >= MIN_POOL_CHECKOUTS {
+            (failures - w.failures) as f64 / n as f64
+        } else {
+            0.0
+        };
+        (w.started, w.checkouts, w.failures) = (Instant::now(), checkouts, failures);
+    }
+    w.rate
+}
+
+#[cfg(not(feature = "orm"))]
+fn pool_failure_rate() -> f64 {
+    0.0
+}
+
+/// Readiness of the running server against the [`HealthModule`] thresholds.
+pub fn readiness() -> Readiness {
+    let limits = THRESHOLDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .unwrap_or_default();
+    evaluate(
+        &crate::metrics::worker_snapshots(),
+        pool_failure_rate(),
+        &limits,
+    )
+}
+
+fn report(readiness: &Readiness) -> Response {
+    let mut res = match serde_json::to_vec(readiness) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => return Response::server_error(),
+    };
+    if !readiness.ready {
+        res.status = 503;
+    }
+    res
+}
+
+fn live_handler(_ctx: Context) -> Response {
+    Response::json_static(b"{\"live\":true}")
+}
+
+fn ready_handler(_ctx: Context) -> Response {
+    report(&readiness())
+}
+
+/// Mounts `GET {prefix}/live` and `GET {prefix}/ready`.
+pub struct HealthModule {
+    prefix: &'static str,
+    thresholds: Thresholds,
+}
+
+impl HealthModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+            thresholds: Thresholds::default(),
+        }
+    }
+
+    pub fn max_loop_lag(mut self, lag: Duration) -> Self {
+        self.thresholds.max_loop_lag = lag;
+        self
+    }
+
+    /// Accept-queue fill level (0.0–1.0) at which to stop taking traffic.
+    pub fn max_accept_queue(mut self, fill: f64) -> Self {
+        self.thresholds.max_accept_queue = fill;
+        self
+    }
+
+    /// Share of failed database checkouts (0.0–1.0) at which to stop
+    /// taking traffic.
+    pub fn max_pool_failure_rate(mut self, rate: f64) -> Self {
+        self.thresholds.max_pool_failure_rate = rate;
+        self
+    }
+}
+
+impl Default for HealthModule {
+    fn default() -> Self {
+        Self::new("/health")
+    }
+}
+
+impl ChopinModule for HealthModule {
+    fn name(&self) -> &'static str {
+        "health"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        *THRESHOLDS.write().unwrap_or_else(|e| e.into_inner()) = Some(self.thresholds);
+        router.get(&format!("{}/live", self.prefix), live_handler);
+        router.get(&format!("{}/ready", self.prefix), ready_handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn worker(lag_ms: usize, queue: usize) -> MetricsSnapshot {
+        MetricsSnapshot {
+            loop_lag_us: lag_ms * 1000,
+            accept_queue: queue,
+            accept_queue_max: 1000,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_evaluate_reports_each_exceeded_threshold() {
+        let limits = Thresholds::default();
+        let calm = evaluate(&[worker(5, 10), worker(20, 0)], 0.0, &limits);
+        assert!(calm.ready);
+        assert_eq!(calm.loop_lag_us, 20_000);
+        assert!(calm.reasons.is_empty());
+
+        let busy = evaluate(&[worker(5, 10), worker(300, 600)], 0.1, &limits);
+        assert!(!busy.ready);
+        assert_eq!(busy.reasons.len(), 3);
+        assert!(busy.reasons[0].starts_with("event-loop lag 300 ms"));
+        assert!(busy.reasons[1].starts_with("accept queue 60% full"));
+        assert!(busy.reasons[2].starts_with("10.0% of database checkouts"));
+
+        assert!(evaluate(&[], 0.0, &limits).ready);
+    }
+
+    #[test]
+    fn test_ready_endpoint_answers_503_when_saturated() {
+        let mut router = Router::new();
+        HealthModule::new("/health-test")
+            .max_loop_lag(Duration::from_millis(50))
+            .routes(&mut router);
+        let app = crate::testing::TestApp::new(router);
+
+        assert_eq!(app.get("/health-test/live").status, 200);
+        assert_eq!(app.get("/health-test/ready").status, 200);
+
+        let lagging = std::sync::Arc::new(crate::metrics::WorkerMetrics::new());
+        lagging.record_loop_lag(80_000);
+        crate::metrics::publish(vec![lagging]);
+        let res = app.get("/health-test/ready");
+        crate::metrics::publish(Vec::new());
+
+        assert_eq!(res.status, 503);
+        let json = res.json().unwrap();
+        assert_eq!(json["ready"], false);
+        assert_eq!(json["loop_lag_us"], 80_000);
+    }
+}
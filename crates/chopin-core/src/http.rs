@@ -176,6 +176,14 @@ pub enum Body {
     /// Use [`Response::raw`] to construct. You are responsible for producing a
     /// valid HTTP/1.1 response including "\r\n\r\n" and the body.
     Raw(&'static [u8]),
+    /// A long-poll response still waiting on its event. The worker parks
+    /// the connection and writes the response built once the event fires.
+    /// Created by [`longpoll::respond`](crate::longpoll::respond).
+    Parked(Box<crate::longpoll::Parked>),
+    /// Chunked body filled from another thread. The worker parks the
+    /// connection while it waits for the next chunk. Created by
+    /// [`Response::feed`].
+    Feed(Box<crate::longpoll::Feed>),
 }
 
 impl Body {
@@ -188,6 +196,8 @@ impl Body {
             Body::Stream(_) => 0, // unknown until streamed
             Body::File { len, .. } => *len as usize,
             Body::Raw(b) => b.len(), // full response bytes
+            Body::Parked(_) => 0,    // not built yet
+            Body::Feed(_) => 0,      // unknown until streamed
         }
     }
 
@@ -205,6 +215,8 @@ impl Body {
             Body::Stream(_) => &[], // Streams must be polled/chunked iteratively
             Body::File { .. } => &[], // File data lives on disk, sent via sendfile
             Body::Raw(b) => b,      // raw full response
+            Body::Parked(_) => &[],
+            Body::Feed(_) => &[],
         }
     }
 
@@ -271,6 +283,18 @@ impl Response {
         self
     }
 
+    /// Take the waiting part of a long-poll response, if this is one.
+    #[inline]
+    pub(crate) fn take_parked(&mut self) -> Option<Box<crate::longpoll::Parked>> {
+        if !matches!(self.body, Body::Parked(_)) {
+            return None;
+        }
+        match std::mem::replace(&mut self.body, Body::Empty) {
+            Body::Parked(parked) => Some(parked),
+            _ => None,
+        }
+    }
+
     /// 200 OK with a plain-text body.
     pub fn text(body: impl Into<Vec<u8>>) -> Self {
         Self {
@@ -417,6 +441,16 @@ impl Response {
         }
     }
 
+    /// 422 Unprocessable Entity.
+    pub fn unprocessable() -> Self {
+        Self {
+            status: 422,
+            body: Body::Static(b"Unprocessable Entity"),
+            content_type: "text/plain",
+            headers: Headers::new(),
+        }
+    }
+
     /// Chunked streaming response with `application/octet-stream` content type.
     pub fn stream(iter: impl Iterator<Item = Vec<u8>> + Send + 'static) -> Self {
         Self {
@@ -427,6 +461,17 @@ impl Response {
         }
     }
 
+    /// Chunked response streaming what `feed`'s sender produces, with
+    /// `application/octet-stream` content type.
+    pub fn feed(feed: crate::longpoll::Feed) -> Self {
+        Self {
+            status: 200,
+            body: Body::Feed(Box::new(feed)),
+            content_type: "application/octet-stream",
+            headers: Headers::new(),
+        }
+    }
+
     /// Serve a file using zero-copy `sendfile`. Content-Type is inferred from the
     /// file extension. Returns 404 if the file does not exist or cannot be opened.
     pub fn file(path: &str) -> Self {
@@ -515,7 +560,7 @@ impl Response {
 
 /// Infer a Content-Type from a file path's extension.
 /// Returns a `&'static str` so it can be stored directly in Response.
-fn mime_from_path(path: &str) -> &'static str {
+pub(crate) fn mime_from_path(path: &str) -> &'static str {
     let ext = match path.rsplit('.').next() {
         Some(e) => e,
         None => return "application/octet-stream",
@@ -640,6 +685,18 @@ impl<'a> Context<'a> {
         None
     }
 
+    /// Value of the cookie `name` from the `Cookie` header(s), if present.
+    pub fn cookie(&self, name: &str) -> Option<&'a str> {
+        self.req.headers[..self.req.header_count as usize]
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("cookie"))
+            .flat_map(|(_, v)| v.split(';'))
+            .find_map(|pair| {
+                let (k, v) = pair.split_once('=')?;
+                (k.trim() == name).then(|| v.trim().trim_matches('"'))
+            })
+    }
+
     /// Parse the request body as a multipart/form-data stream.
     /// Returns `None` if the `Content-Type` header is not `multipart/form-data`.
     #[allow(clippy::collapsible_if)]
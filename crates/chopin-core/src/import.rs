@@ -0,0 +1,965 @@
+// src/import.rs
+//! Bulk CSV / JSON Lines import endpoints (`orm` feature).
+//!
+//! Rows are parsed one at a time from the upload, validated as a
+//! [`CreateRequest`], turned into models and written in batches, either as
+//! multi-row `INSERT`s on an [`Executor`] or through `COPY` on a dedicated
+//! connection. Rows that fail to parse or validate are collected into an
+//! [`ImportReport`] with their line numbers:
+//!
+//! ```rust,ignore
+//! #[post("/api/contacts/import")]
+//! fn import_contacts(ctx: Context) -> Response {
+//!     let (format, data) = match import::upload(&ctx) {
+//!         Ok(upload) => upload,
+//!         Err(res) => return res,
+//!     };
+//!     match db::with_db(|db| Importer::new().run::<NewContact, Contact>(db, format, data, None)) {
+//!         Ok(report) => report.to_response(),
+//!         Err(_) => Response::server_error(),
+//!     }
+//! }
+//! ```
+//!
+//! By default an import is all-or-nothing: it runs in a transaction (a
+//! savepoint under [`db::transaction_per_request`]) and inserts nothing if
+//! any row is rejected, while still checking the remaining rows so the
+//! report lists every problem. [`Importer::skip_invalid`] inserts the good
+//! rows instead.
+//!
+//! Large files can be imported off the worker with [`spawn`], which returns
+//! an [`ImportHandle`] for a `202 Accepted` response. Progress is then read
+//! by polling [`status_endpoint`] or followed with [`events_endpoint`]:
+//!
+//! ```rust,ignore
+//! #[post("/api/contacts/import")]
+//! fn import_contacts(ctx: Context) -> Response {
+//!     let (format, data) = match import::upload(&ctx) { .. };
+//!     import::spawn::<NewContact, Contact>(Importer::new(), format, data.to_vec())
+//!         .accepted("/api/imports")
+//! }
+//!
+//! router.get("/api/imports/:id", |ctx| import::status_endpoint(&ctx));
+//! router.get("/api/imports/:id/events", |ctx| import::events_endpoint(&ctx));
+//! ```
+//!
+//! Uploads arrive whole in the request body, so their size is bounded by
+//! the server's read buffer.
+//!
+//! CSV files need a header row. Each column fills the field of the same
+//! name, parsed the way query strings are, so fields must be flat; empty
+//! cells are left out and read as `None` or the serde default.
+use crate::crud::CreateRequest;
+use crate::db;
+use crate::http::{Context, Response};
+use crate::longpoll::Event;
+use chopin_orm::{Executor, Model, OrmError, OrmResult, PgConnection, PgValue, ToSql};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Rows written per statement (or per `COPY` chunk) unless
+/// [`Importer::batch_size`] is set.
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Row errors listed in a report unless [`Importer::max_errors`] is set.
+pub const DEFAULT_MAX_ERRORS: usize = 100;
+
+/// How long finished imports stay visible to [`status`].
+pub const FINISHED_TTL: Duration = Duration::from_secs(3600);
+
+/// Upload encoding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// Comma-separated values with a header row (RFC 4180 quoting).
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+impl ImportFormat {
+    /// Format named by a `Content-Type`, ignoring parameters.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let mime = content_type.split(';').next().unwrap_or("").trim();
+        match mime.to_ascii_lowercase().as_str() {
+            "text/csv" | "application/csv" => Some(Self::Csv),
+            "application/x-ndjson"
+            | "application/jsonl"
+            | "application/x-jsonlines"
+            | "application/jsonlines" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+
+    /// Format implied by a file extension.
+    pub fn from_filename(filename: &str) -> Option<Self> {
+        let ext = filename.rsplit_once('.')?.1.to_ascii_lowercase();
+        match ext.as_str() {
+            "csv" => Some(Self::Csv),
+            "jsonl" | "ndjson" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+/// The uploaded file of a request: the first multipart part with a
+/// filename, or the whole body when it is sent as `text/csv` or
+/// `application/x-ndjson`. Anything else is rejected with `415`, and a
+/// malformed multipart body with `400`.
+#[allow(clippy::result_large_err)]
+pub fn upload<'a>(ctx: &Context<'a>) -> Result<(ImportFormat, &'a [u8]), Response> {
+    if let Some(parts) = ctx.multipart() {
+        for part in parts {
+            let part = part.map_err(|_| Response::bad_request())?;
+            let Some(filename) = part.filename else {
+                continue;
+            };
+            let format = part
+                .content_type
+                .and_then(ImportFormat::from_content_type)
+                .or_else(|| ImportFormat::from_filename(filename))
+                .ok_or_else(|| Response::new(415))?;
+            return Ok((format, part.body));
+        }
+        return Err(Response::bad_request());
+    }
+    ctx.header("content-type")
+        .and_then(ImportFormat::from_content_type)
+        .map(|format| (format, ctx.req.body))
+        .ok_or_else(|| Response::new(415))
+}
+
+/// Cells of one CSV record, or why it could not be read.
+type CsvCells<'a> = Result<Vec<Cow<'a, str>>, String>;
+
+/// Lazily parsed rows of an upload, each with the line it starts on.
+pub struct Records<'a, R> {
+    format: ImportFormat,
+    data: &'a [u8],
+    pos: usize,
+    line: usize,
+    headers: Option<Vec<String>>,
+    _row: PhantomData<fn() -> R>,
+}
+
+impl<'a, R: DeserializeOwned> Records<'a, R> {
+    pub fn new(format: ImportFormat, data: &'a [u8]) -> Self {
+        let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+        Self {
+            format,
+            data,
+            pos: 0,
+            line: 1,
+            headers: None,
+            _row: PhantomData,
+        }
+    }
+
+    /// Bytes consumed so far, for progress reporting.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Total bytes of the upload.
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Next non-blank CSV record as cells, with its first line.
+    fn csv_record(&mut self) -> Option<(usize, CsvCells<'a>)> {
+        loop {
+            if self.pos >= self.data.len() {
+                return None;
+            }
+            let start_line = self.line;
+            let (cells, next, lines) = parse_csv_record(&self.data[self.pos..]);
+            self.pos += next;
+            self.line += lines;
+            match cells {
+                Ok(cells) if cells.len() == 1 && cells[0].is_empty() => continue,
+                cells => return Some((start_line, cells)),
+            }
+        }
+    }
+}
+
+impl<R: DeserializeOwned> Iterator for Records<'_, R> {
+    type Item = (usize, Result<R, String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.format {
+            ImportFormat::JsonLines => loop {
+                let rest = self.data.get(self.pos..).filter(|r| !r.is_empty())?;
+                let end = memchr::memchr(b'\n', rest).map_or(rest.len(), |i| i + 1);
+                let line = self.line;
+                self.pos += end;
+                self.line += 1;
+                let text = rest[..end].trim_ascii();
+                if text.is_empty() {
+                    continue;
+                }
+                return Some((
+                    line,
+                    serde_json::from_slice(text).map_err(|e| e.to_string()),
+                ));
+            },
+            ImportFormat::Csv => {
+                if self.headers.is_none() {
+                    let headers = match self.csv_record()? {
+                        (_, Ok(cells)) => cells.iter().map(|c| c.trim().to_string()).collect(),
+                        (line, Err(e)) => return Some((line, Err(e))),
+                    };
+                    self.headers = Some(headers);
+                }
+                let (line, cells) = self.csv_record()?;
+                let headers = self.headers.as_deref().unwrap_or_default();
+                Some((line, cells.and_then(|cells| csv_row(headers, &cells))))
+            }
+        }
+    }
+}
+
+/// Parse one CSV record from the start of `data`. Returns the cells, the
+/// bytes consumed and the number of line breaks consumed.
+fn parse_csv_record(data: &[u8]) -> (CsvCells<'_>, usize, usize) {
+    let mut cells = Vec::new();
+    let mut i = 0;
+    let mut lines = 0;
+    loop {
+        let cell: Cow<'_, [u8]>;
+        if data.get(i) == Some(&b'"') {
+            let mut owned = Vec::new();
+            let mut j = i + 1;
+            loop {
+                match data.get(j) {
+                    None => {
+                        let consumed = data.len();
+                        return (Err("unterminated quoted field".into()), consumed, lines);
+                    }
+                    Some(b'"') if data.get(j + 1) == Some(&b'"') => {
+                        owned.push(b'"');
+                        j += 2;
+                    }
+                    Some(b'"') => {
+                        j += 1;
+                        break;
+                    }
+                    Some(&b) => {
+                        if b == b'\n' {
+                            lines += 1;
+                        }
+                        owned.push(b);
+                        j += 1;
+                    }
+                }
+            }
+            cell = Cow::Owned(owned);
+            i = j;
+        } else {
+            let end = data[i..]
+                .iter()
+                .position(|&b| b == b',' || b == b'\n')
+                .map_or(data.len(), |p| i + p);
+            let raw = &data[i..end];
+            cell = Cow::Borrowed(raw.strip_suffix(b"\r").unwrap_or(raw));
+            i = end;
+        }
+        let cell = match cell {
+            Cow::Borrowed(b) => std::str::from_utf8(b).map(Cow::Borrowed),
+            Cow::Owned(b) => String::from_utf8(b)
+                .map(Cow::Owned)
+                .map_err(|e| e.utf8_error()),
+        };
+        match cell {
+            Ok(cell) => cells.push(cell),
+            Err(_) => {
+                let skip = memchr::memchr(b'\n', &data[i..]).map_or(data.len(), |p| i + p + 1);
+                return (Err("invalid UTF-8".into()), skip, lines + 1);
+            }
+        }
+        match data.get(i) {
+            Some(b',') => i += 1,
+            Some(b'\n') => return (Ok(cells), i + 1, lines + 1),
+            Some(b'\r') if data.get(i + 1) == Some(&b'\n') => {
+                return (Ok(cells), i + 2, lines + 1);
+            }
+            None => return (Ok(cells), i, lines),
+            Some(_) => {
+                let skip = memchr::memchr(b'\n', &data[i..]).map_or(data.len(), |p| i + p + 1);
+                return (
+                    Err("unexpected character after quoted field".into()),
+                    skip,
+                    lines + 1,
+                );
+            }
+        }
+    }
+}
+
+/// Deserialize a CSV row through the query-string deserializer, which
+/// parses numbers and booleans out of strings.
+fn csv_row<R: DeserializeOwned>(headers: &[String], cells: &[Cow<'_, str>]) -> Result<R, String> {
+    if cells.len() != headers.len() {
+        return Err(format!(
+            "expected {} columns, found {}",
+            headers.len(),
+            cells.len()
+        ));
+    }
+    let pairs: Vec<(&str, &str)> = headers
+        .iter()
+        .zip(cells)
+        .filter(|(_, cell)| !cell.is_empty())
+        .map(|(h, c)| (h.as_str(), c.as_ref()))
+        .collect();
+    let encoded = serde_urlencoded::to_string(&pairs).map_err(|e| e.to_string())?;
+    serde_urlencoded::from_str(&encoded).map_err(|e| e.to_string())
+}
+
+/// A rejected row.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RowError {
+    /// Line of the upload the row starts on, counting the CSV header.
+    pub line: usize,
+    pub errors: Vec<String>,
+}
+
+/// Outcome of an import.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    /// Rows read, valid or not.
+    pub processed: u64,
+    pub inserted: u64,
+    pub rejected: u64,
+    /// The first rejected rows, up to [`Importer::max_errors`].
+    pub errors: Vec<RowError>,
+    /// Set when more rows were rejected than `errors` lists.
+    pub errors_truncated: bool,
+}
+
+impl ImportReport {
+    /// `200` with the report, or `422` when rows were rejected.
+    pub fn to_response(&self) -> Response {
+        match serde_json::to_vec(self) {
+            Ok(body) => {
+                let mut res = Response::json_bytes(body);
+                if self.rejected > 0 {
+                    res.status = 422;
+                }
+                res
+            }
+            Err(_) => Response::server_error(),
+        }
+    }
+
+    fn reject(&mut self, line: usize, errors: Vec<String>, max: usize) {
+        self.rejected += 1;
+        if self.errors.len() < max {
+            self.errors.push(RowError { line, errors });
+        } else {
+            self.errors_truncated = true;
+        }
+    }
+}
+
+/// Where accepted rows go.
+trait Sink<M> {
+    fn write(&mut self, rows: &[M]) -> OrmResult<u64>;
+}
+
+/// Non-generated columns of `M`, with their indexes in `get_values()`.
+fn insert_columns<M: Model>() -> Vec<(usize, &'static str)> {
+    let generated = M::generated_columns();
+    M::columns()
+        .iter()
+        .enumerate()
+        .filter(|(_, col)| !generated.contains(col))
+        .map(|(i, col)| (i, *col))
+        .collect()
+}
+
+/// Multi-row `INSERT` statements, split to stay under the protocol's
+/// 65535 parameter limit.
+struct InsertSink<'a, M> {
+    executor: &'a mut dyn Executor,
+    columns: Vec<(usize, &'static str)>,
+    _model: PhantomData<fn(M)>,
+}
+
+impl<M: Model> Sink<M> for InsertSink<'_, M> {
+    fn write(&mut self, rows: &[M]) -> OrmResult<u64> {
+        let width = self.columns.len().max(1);
+        let mut written = 0;
+        for chunk in rows.chunks((u16::MAX as usize / width).max(1)) {
+            let mut sql = format!(
+                "INSERT INTO {} ({}) VALUES ",
+                M::table_name(),
+                self.columns
+                    .iter()
+                    .map(|(_, c)| *c)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+            let mut values: Vec<PgValue> = Vec::with_capacity(chunk.len() * width);
+            for (r, row) in chunk.iter().enumerate() {
+                let all = row.get_values();
+                sql.push_str(if r == 0 { "(" } else { ", (" });
+                for (c, (i, _)) in self.columns.iter().enumerate() {
+                    if c > 0 {
+                        sql.push_str(", ");
+                    }
+                    values.push(all[*i].clone());
+                    sql.push_str(&format!("${}", values.len()));
+                }
+                sql.push(')');
+            }
+            let params: Vec<&dyn ToSql> = values.iter().map(|v| v as &dyn ToSql).collect();
+            written += self.executor.execute(&sql, &params)?;
+        }
+        Ok(written)
+    }
+}
+
+/// `COPY ... FROM STDIN` in text format.
+struct CopySink<'a, 'c, M> {
+    writer: &'a mut chopin_orm::CopyWriter<'c>,
+    columns: Vec<(usize, &'static str)>,
+    _model: PhantomData<fn(M)>,
+}
+
+impl<M: Model> Sink<M> for CopySink<'_, '_, M> {
+    fn write(&mut self, rows: &[M]) -> OrmResult<u64> {
+        let mut buf = Vec::new();
+        for row in rows {
+            let all = row.get_values();
+            for (c, (i, _)) in self.columns.iter().enumerate() {
+                if c > 0 {
+                    buf.push(b'\t');
+                }
+                copy_text(&mut buf, &all[*i]);
+            }
+            buf.push(b'\n');
+        }
+        self.writer.write_data(&buf)?;
+        Ok(rows.len() as u64)
+    }
+}
+
+/// Append `value` in `COPY` text format.
+fn copy_text(buf: &mut Vec<u8>, value: &PgValue) {
+    let Some(text) = value.to_text_bytes() else {
+        buf.extend_from_slice(b"\\N");
+        return;
+    };
+    for b in text {
+        match b {
+            b'\\' => buf.extend_from_slice(b"\\\\"),
+            b'\t' => buf.extend_from_slice(b"\\t"),
+            b'\n' => buf.extend_from_slice(b"\\n"),
+            b'\r' => buf.extend_from_slice(b"\\r"),
+            b => buf.push(b),
+        }
+    }
+}
+
+/// Import settings.
+#[derive(Debug, Clone)]
+pub struct Importer {
+    batch_size: usize,
+    max_errors: usize,
+    skip_invalid: bool,
+}
+
+impl Default for Importer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Importer {
+    pub fn new() -> Self {
+        Self {
+            batch_size: DEFAULT_BATCH_SIZE,
+            max_errors: DEFAULT_MAX_ERRORS,
+            skip_invalid: false,
+        }
+    }
+
+    /// Rows written per statement.
+    pub fn batch_size(mut self, rows: usize) -> Self {
+        self.batch_size = rows.max(1);
+        self
+    }
+
+    /// Rejected rows listed in the report; the rest are only counted.
+    pub fn max_errors(mut self, max: usize) -> Self {
+        self.max_errors = max;
+        self
+    }
+
+    /// Insert valid rows even when others are rejected.
+    pub fn skip_invalid(mut self, skip: bool) -> Self {
+        self.skip_invalid = skip;
+        self
+    }
+
+    /// Import `data` into `M`'s table with multi-row `INSERT`s on `executor`.
+    pub fn run<R: CreateRequest<M>, M: Model>(
+        &self,
+        executor: &mut dyn Executor,
+        format: ImportFormat,
+        data: &[u8],
+        progress: Option<&ImportProgress>,
+    ) -> OrmResult<ImportReport> {
+        let nested = db::in_request_transaction();
+        executor.execute(
+            if nested {
+                "SAVEPOINT chopin_import"
+            } else {
+                "BEGIN"
+            },
+            &[],
+        )?;
+        let mut sink = InsertSink::<M> {
+            executor: &mut *executor,
+            columns: insert_columns::<M>(),
+            _model: PhantomData,
+        };
+        let result = self.load::<R, M>(&mut sink, format, data, progress);
+        let keep = matches!(&result, Ok(report) if self.skip_invalid || report.rejected == 0);
+        let end = match (keep, nested) {
+            (true, true) => "RELEASE SAVEPOINT chopin_import",
+            (true, false) => "COMMIT",
+            (false, true) => "ROLLBACK TO SAVEPOINT chopin_import",
+            (false, false) => "ROLLBACK",
+        };
+        let ended = executor.execute(end, &[]);
+        let mut report = result?;
+        ended?;
+        if !keep {
+            report.inserted = 0;
+        }
+        Ok(report)
+    }
+
+    /// Import `data` into `M`'s table with `COPY` on `conn`, which must not
+    /// be in a transaction. Faster than [`run`](Self::run) for large files;
+    /// every column must have a text form `COPY` accepts.
+    pub fn run_copy<R: CreateRequest<M>, M: Model>(
+        &self,
+        conn: &mut PgConnection,
+        format: ImportFormat,
+        data: &[u8],
+        progress: Option<&ImportProgress>,
+    ) -> OrmResult<ImportReport> {
+        let columns = insert_columns::<M>();
+        let sql = format!(
+            "COPY {} ({}) FROM STDIN",
+            M::table_name(),
+            columns
+                .iter()
+                .map(|(_, c)| *c)
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        conn.begin()?;
+        let mut writer = conn.copy_in(&sql)?;
+        let mut sink = CopySink::<M> {
+            writer: &mut writer,
+            columns,
+            _model: PhantomData,
+        };
+        let result = self.load::<R, M>(&mut sink, format, data, progress);
+        let keep = matches!(&result, Ok(report) if self.skip_invalid || report.rejected == 0);
+        if keep {
+            writer.finish()?;
+            conn.commit()?;
+        } else {
+            writer.fail("import rejected")?;
+            conn.rollback()?;
+        }
+        let mut report = result?;
+        if !keep {
+            report.inserted = 0;
+        }
+        Ok(report)
+    }
+
+    fn load<R: CreateRequest<M>, M: Model>(
+        &self,
+        sink: &mut dyn Sink<M>,
+        format: ImportFormat,
+        data: &[u8],
+        progress: Option<&ImportProgress>,
+    ) -> OrmResult<ImportReport> {
+        let mut report = ImportReport::default();
+        let mut records = Records::<R>::new(format, data);
+        let mut batch = Vec::with_capacity(self.batch_size);
+        let mut flush = |batch: &mut Vec<M>, report: &mut ImportReport, pos: usize| {
+            // Once a row is rejected an all-or-nothing import stops writing
+            // and only validates the rest.
+            if !batch.is_empty() && (self.skip_invalid || report.rejected == 0) {
+                report.inserted += sink.write(batch)?;
+            }
+            batch.clear();
+            if let Some(progress) = progress {
+                progress.update(report, pos as u64);
+            }
+            Ok::<_, OrmError>(())
+        };
+        while let Some((line, row)) = records.next() {
+            report.processed += 1;
+            let model = row.map_err(|e| vec![e]).and_then(|row| {
+                row.validate()?;
+                let model = row.into_model();
+                model.validate()?;
+                Ok(model)
+            });
+            match model {
+                Ok(model) => batch.push(model),
+                Err(errors) => report.reject(line, errors, self.max_errors),
+            }
+            if batch.len() >= self.batch_size {
+                flush(&mut batch, &mut report, records.position())?;
+            }
+        }
+        flush(&mut batch, &mut report, records.position())?;
+        Ok(report)
+    }
+}
+
+/// Lifecycle of an import started with [`spawn`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportState {
+    Running,
+    Succeeded,
+    /// Finished with rejected rows (and, unless skipping, nothing inserted).
+    Rejected,
+    Failed,
+}
+
+/// Snapshot served by [`status_endpoint`].
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportStatus {
+    pub id: String,
+    pub state: ImportState,
+    /// Share of the upload read so far, 0–100.
+    pub percent: u8,
+    pub processed: u64,
+    pub inserted: u64,
+    pub rejected: u64,
+    /// The final report once finished.
+    pub report: Option<ImportReport>,
+    /// Database error of a failed import.
+    pub error: Option<String>,
+}
+
+/// Live counters of a running import, shared with status readers.
+pub struct ImportProgress {
+    id: String,
+    total_bytes: u64,
+    read_bytes: AtomicU64,
+    processed: AtomicU64,
+    inserted: AtomicU64,
+    rejected: AtomicU64,
+    outcome: Mutex<Option<(Instant, Result<ImportReport, String>)>>,
+    changed: Event,
+}
+
+impl ImportProgress {
+    pub fn new(id: impl Into<String>, total_bytes: usize) -> Self {
+        Self {
+            id: id.into(),
+            total_bytes: total_bytes as u64,
+            read_bytes: AtomicU64::new(0),
+            processed: AtomicU64::new(0),
+            inserted: AtomicU64::new(0),
+            rejected: AtomicU64::new(0),
+            outcome: Mutex::new(None),
+            changed: Event::new(),
+        }
+    }
+
+    fn update(&self, report: &ImportReport, read_bytes: u64) {
+        self.read_bytes.store(read_bytes, Ordering::Relaxed);
+        self.processed.store(report.processed, Ordering::Relaxed);
+        self.inserted.store(report.inserted, Ordering::Relaxed);
+        self.rejected.store(report.rejected, Ordering::Relaxed);
+        self.changed.notify();
+    }
+
+    fn finish(&self, outcome: Result<ImportReport, String>) {
+        if let Ok(report) = &outcome {
+            self.update(report, self.total_bytes);
+        }
+        *self.outcome.lock().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), outcome));
+        self.changed.notify();
+    }
+
+    fn finished_at(&self) -> Option<Instant> {
+        let outcome = self.outcome.lock().unwrap_or_else(|e| e.into_inner());
+        outcome.as_ref().map(|(at, _)| *at)
+    }
+
+    pub fn status(&self) -> ImportStatus {
+        let outcome = self.outcome.lock().unwrap_or_else(|e| e.into_inner());
+        let (state, report, error) = match outcome.as_ref().map(|(_, o)| o) {
+            None => (ImportState::Running, None, None),
+            Some(Ok(report)) if report.rejected > 0 => {
+                (ImportState::Rejected, Some(report.clone()), None)
+            }
+            Some(Ok(report)) => (ImportState::Succeeded, Some(report.clone()), None),
+            Some(Err(e)) => (ImportState::Failed, None, Some(e.clone())),
+        };
+        let read = self.read_bytes.load(Ordering::Relaxed);
+        let percent = if state != ImportState::Running || self.total_bytes == 0 {
+            100
+        } else {
+            (read.min(self.total_bytes) * 100 / self.total_bytes) as u8
+        };
+        ImportStatus {
+            id: self.id.clone(),
+            state,
+            percent,
+            processed: self.processed.load(Ordering::Relaxed),
+            inserted: report
+                .as_ref()
+                .map_or_else(|| self.inserted.load(Ordering::Relaxed), |r| r.inserted),
+            rejected: self.rejected.load(Ordering::Relaxed),
+            report,
+            error,
+        }
+    }
+}
+
+static IMPORTS: RwLock<BTreeMap<String, Arc<ImportProgress>>> = RwLock::new(BTreeMap::new());
+
+/// An import running in the background.
+pub struct ImportHandle {
+    progress: Arc<ImportProgress>,
+}
+
+impl ImportHandle {
+    pub fn id(&self) -> &str {
+        &self.progress.id
+    }
+
+    pub fn status(&self) -> ImportStatus {
+        self.progress.status()
+    }
+
+    /// `202 Accepted` pointing at `{status_base}/{id}`, with the URL in
+    /// `Location` and the body `{"id": .., "status_url": ..}`.
+    pub fn accepted(&self, status_base: &str) -> Response {
+        let url = format!("{}/{}", status_base.trim_end_matches('/'), self.id());
+        let body = serde_json::json!({ "id": self.id(), "status_url": url });
+        let mut res = Response::json_bytes(body.to_string().into_bytes());
+        res.status = 202;
+        res.with_header("Location", url)
+    }
+}
+
+/// Run an import on its own thread through [`db::with_model_db`], which
+/// opens a connection for that thread. Poll it with [`status`].
+pub fn spawn<R, M>(importer: Importer, format: ImportFormat, data: Vec<u8>) -> ImportHandle
+where
+    R: CreateRequest<M> + 'static,
+    M: Model + 'static,
+{
+    let progress = Arc::new(ImportProgress::new(new_id(), data.len()));
+    {
+        let mut imports = IMPORTS.write().unwrap_or_else(|e| e.into_inner());
+        imports.retain(|_, p| p.finished_at().is_none_or(|at| at.elapsed() < FINISHED_TTL));
+        imports.insert(progress.id.clone(), progress.clone());
+    }
+    let worker = progress.clone();
+    let spawned = std::thread::Builder::new()
+        .name("chopin-import".to_string())
+        .spawn(move || {
+            let outcome = db::with_model_db::<M, _>(|db| {
+                importer.run::<R, M>(db, format, &data, Some(&worker))
+            });
+            if let Err(e) = &outcome {
+                eprintln!("[chopin] import {} failed: {e}", worker.id);
+            }
+            worker.finish(outcome.map_err(|e| e.to_string()));
+        });
+    if let Err(e) = spawned {
+        progress.finish(Err(e.to_string()));
+    }
+    ImportHandle { progress }
+}
+
+/// Status of a spawned import, until [`FINISHED_TTL`] after it finished.
+pub fn status(id: &str) -> Option<ImportStatus> {
+    progress(id).map(|p| p.status())
+}
+
+fn progress(id: &str) -> Option<Arc<ImportProgress>> {
+    IMPORTS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(id)
+        .cloned()
+}
+
+/// `GET .../:id` handler body: the [`ImportStatus`] as JSON, or `404`.
+pub fn status_endpoint(ctx: &Context) -> Response {
+    match ctx.param("id").and_then(status) {
+        Some(status) => match serde_json::to_vec(&status) {
+            Ok(body) => Response::json_bytes(body),
+            Err(_) => Response::server_error(),
+        },
+        None => Response::not_found(),
+    }
+}
+
+/// `GET .../:id/events` handler body: server-sent events carrying the
+/// [`ImportStatus`] after every batch, ending once the import finishes.
+/// The stream waits for the next batch on the worker thread, which stalls
+/// the worker's other connections, so prefer [`status_endpoint`] on busy
+/// servers.
+pub fn events_endpoint(ctx: &Context) -> Response {
+    let Some(progress) = ctx.param("id").and_then(progress) else {
+        return Response::not_found();
+    };
+    let mut version = 0;
+    let mut done = false;
+    let events = std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        if let Some(next) = progress
+            .changed
+            .wait_since(version, crate::longpoll::MAX_WAIT)
+        {
+            version = next;
+        } else if progress.finished_at().is_none() {
+            return Some(b": waiting\n\n".to_vec());
+        }
+        let status = progress.status();
+        done = status.state != ImportState::Running;
+        let json = serde_json::to_string(&status).ok()?;
+        Some(format!("data: {json}\n\n").into_bytes())
+    });
+    let mut res = Response::stream(events).with_header("Cache-Control", "no-cache");
+    res.content_type = "text/event-stream";
+    res
+}
+
+/// A random 24-hex-character id, also used for jobs and mail.
+pub(crate) fn new_id() -> String {
+    let mut bytes = [0u8; 12];
+    if let Err(e) = crate::logging::random_bytes(&mut bytes) {
+        // Ids appear in status URLs; a guessable one would expose them.
+        panic!("[chopin] cannot read /dev/urandom for an id: {e}");
+    }
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Contact {
+        name: String,
+        age: Option<i32>,
+    }
+
+    fn rows(format: ImportFormat, data: &str) -> Vec<(usize, Result<Contact, String>)> {
+        Records::new(format, data.as_bytes()).collect()
+    }
+
+    #[test]
+    fn test_csv_quoting_and_lines() {
+        let data =
+            "\u{feff}name,age\r\n\"Smith, Jo\",41\n\n\"multi\nline \"\"q\"\"\",\nbad,x\nshort\n";
+        let rows = rows(ImportFormat::Csv, data);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(
+            rows[0],
+            (
+                2,
+                Ok(Contact {
+                    name: "Smith, Jo".into(),
+                    age: Some(41)
+                })
+            )
+        );
+        assert_eq!(
+            rows[1],
+            (
+                4,
+                Ok(Contact {
+                    name: "multi\nline \"q\"".into(),
+                    age: None
+                })
+            )
+        );
+        assert_eq!(rows[2].0, 6);
+        assert!(rows[2].1.is_err());
+        assert_eq!(rows[3], (7, Err("expected 2 columns, found 1".to_string())));
+    }
+
+    #[test]
+    fn test_csv_unterminated_quote() {
+        let rows = rows(ImportFormat::Csv, "name,age\n\"open,1\n");
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].1, Err("unterminated quoted field".to_string()));
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let rows = rows(
+            ImportFormat::JsonLines,
+            "{\"name\":\"a\",\"age\":1}\n\n{\"name\":2}\n{\"name\":\"c\"}",
+        );
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].0, 1);
+        assert!(rows[1].1.is_err());
+        assert_eq!(
+            rows[2],
+            (
+                4,
+                Ok(Contact {
+                    name: "c".into(),
+                    age: None
+                })
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_detection() {
+        assert_eq!(
+            ImportFormat::from_content_type("text/csv; charset=utf-8"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            ImportFormat::from_filename("export.NDJSON"),
+            Some(ImportFormat::JsonLines)
+        );
+        assert_eq!(ImportFormat::from_filename("notes.txt"), None);
+    }
+
+    #[test]
+    fn test_copy_text_escaping() {
+        let mut buf = Vec::new();
+        copy_text(&mut buf, &PgValue::Text("a\tb\\c\nd".into()));
+        buf.push(b'|');
+        copy_text(&mut buf, &PgValue::Null);
+        assert_eq!(buf, b"a\\tb\\\\c\\nd|\\N");
+    }
+}
@@ -0,0 +1,1449 @@
+// src/jobs.rs
+//! Background jobs (`orm` feature).
+//!
+//! Jobs are rows in the `chopin_jobs` table. [`enqueue`] stores one and
+//! returns a [`JobHandle`]; runner threads claim pending rows with
+//! `FOR UPDATE SKIP LOCKED`, so any number of processes can share the queue.
+//! A job reports progress while it runs, and its result or error is kept on
+//! the row for the status endpoint.
+//!
+//! ```rust,ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct ExportReport { shop_id: i64 }
+//!
+//! impl Job for ExportReport {
+//!     const KIND: &'static str = "export_report";
+//!
+//!     fn run(&self, job: &JobContext) -> ChopinResult<Value> {
+//!         for (i, chunk) in chunks(self.shop_id).enumerate() {
+//!             write_chunk(chunk)?;
+//!             job.progress((i * 100 / total) as u8)?;
+//!         }
+//!         Ok(json!({ "url": report_url(self.shop_id) }))
+//!     }
+//! }
+//!
+//! jobs::register::<ExportReport>();
+//! Chopin::new()
+//!     .mount_module(JobsModule::new("/api/jobs").runner(JobRunner::new().threads(2)))
+//!     .serve("0.0.0.0:8080")?;
+//!
+//! #[post("/api/reports")]
+//! fn start_export(ctx: Context) -> Response {
+//!     match jobs::enqueue(&ExportReport { shop_id: shop_id(&ctx) }) {
+//!         Ok(job) => job.accepted("/api/jobs"),
+//!         Err(_) => Response::server_error(),
+//!     }
+//! }
+//! ```
+//!
+//! `GET /api/jobs/:id` then answers with the [`JobStatus`]. Job ids are
+//! random, so a status URL is only known to whoever enqueued the job.
+//!
+//! [`enqueue`] goes through [`db::with_db`], so a job enqueued inside a
+//! [`transaction_per_request`](db::transaction_per_request) transaction only
+//! becomes visible to runners if the request commits.
+//!
+//! ## Priorities and scheduling
+//!
+//! Jobs run in one of three [`Priority`] lanes, from [`Job::priority`] or
+//! per call through [`enqueue_with`], which can also hold a job back:
+//!
+//! ```rust,ignore
+//! jobs::enqueue_with(
+//!     &SendReminder { user_id },
+//!     EnqueueOptions::new().delay(Duration::from_secs(24 * 60 * 60)),
+//! )?;
+//! jobs::enqueue_with(&Reindex { shop_id }, EnqueueOptions::new().priority(Priority::Low))?;
+//! ```
+//!
+//! Runners prefer lanes in a fixed 4:2:1 cycle (high, normal, low) and fall
+//! back to the most urgent due job when the preferred lane is empty. A
+//! busy high lane therefore slows batch work down but never stops it.
+//! Delayed jobs are picked up on the next poll after they fall due, and
+//! [`cancel`] drops one that has not started.
+//!
+//! ## Retries and the dead-letter queue
+//!
+//! Each kind has a [`RetryPolicy`] from [`Job::policy`], which
+//! [`set_policy`] can override. It is copied onto the row at enqueue time,
+//! so every process treats the job the same way. A failed attempt goes
+//! back to `pending` after an exponential backoff until `max_attempts` is
+//! used up. After that the job stays `failed`, which is the dead-letter
+//! queue. An attempt that outlives the policy's `timeout` counts as
+//! failed. If its runner disappeared (a crash or a deploy), any runner
+//! requeues it once twice the timeout has passed.
+//!
+//! Failed jobs are inspected, retried or discarded through the admin routes
+//! that [`JobsModule::admin`] mounts behind a guard, or with the
+//! `chopin jobs` CLI:
+//!
+//! | Method | Path                    | Description                                  |
+//! |--------|-------------------------|----------------------------------------------|
+//! | GET    | `/`                     | List (`?state=failed&kind=..&limit=50`)      |
+//! | GET    | `/:id`                  | One job with its payload and error           |
+//! | POST   | `/:id/retry`            | Requeue a failed job with fresh attempts     |
+//! | POST   | `/retry-failed`         | Requeue every failed job (`?kind=..`)        |
+//! | DELETE | `/:id`                  | Discard a failed job                         |
+use crate::db;
+use crate::error::{ChopinError, ChopinResult};
+use crate::extract::Query;
+use crate::http::{Context, Response};
+use crate::longpoll::Event;
+use crate::module::ChopinModule;
+use crate::router::{MiddlewareFn, Router};
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Bumped by every [`enqueue`] so idle runners in this process start at once
+/// instead of waiting for their next poll.
+pub static QUEUED: Event = Event::new();
+
+/// A unit of background work, stored as its JSON serialization.
+pub trait Job: Serialize + DeserializeOwned + 'static {
+    /// Stored in the `kind` column and used to find the job's handler.
+    /// Must be unique among registered jobs and stable across deploys.
+    const KIND: &'static str;
+
+    /// Do the work. The returned value is kept as the job's result.
+    fn run(&self, job: &JobContext) -> ChopinResult<Value>;
+
+    /// Retries and timeout for this kind, unless overridden with
+    /// [`set_policy`]. The default runs a job once, without a timeout.
+    fn policy() -> RetryPolicy {
+        RetryPolicy::default()
+    }
+
+    /// Lane for jobs of this kind, unless [`EnqueueOptions::priority`]
+    /// says otherwise.
+    fn priority() -> Priority {
+        Priority::Normal
+    }
+}
+
+/// Queue lane. Stored as 0 (high) to 2 (low).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    /// Interactive work a user is waiting for.
+    High,
+    #[default]
+    Normal,
+    /// Batch work that can wait.
+    Low,
+}
+
+impl Priority {
+    fn as_i16(self) -> i16 {
+        self as i16
+    }
+
+    fn from_i16(v: i16) -> Self {
+        match v {
+            i16::MIN..=0 => Self::High,
+            1 => Self::Normal,
+            _ => Self::Low,
+        }
+    }
+}
+
+/// The order runners prefer lanes in; see the module docs.
+const LANE_CYCLE: [Priority; 7] = [
+    Priority::High,
+    Priority::Normal,
+    Priority::High,
+    Priority::Low,
+    Priority::High,
+    Priority::Normal,
+    Priority::High,
+];
+
+static NEXT_LANE: AtomicUsize = AtomicUsize::new(0);
+
+/// When a job becomes due.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Schedule {
+    #[default]
+    Now,
+    In(Duration),
+    At(SystemTime),
+}
+
+/// Lane and start time for [`enqueue_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EnqueueOptions {
+    priority: Option<Priority>,
+    schedule: Schedule,
+}
+
+impl EnqueueOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn priority(mut self, priority: Priority) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// Not before `delay` from now, by the database clock.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.schedule = Schedule::In(delay);
+        self
+    }
+
+    /// Not before `at`. A time in the past makes the job due at once.
+    pub fn at(mut self, at: SystemTime) -> Self {
+        self.schedule = Schedule::At(at);
+        self
+    }
+}
+
+/// How often a kind of job is attempted and how long each attempt may take.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts before the job is left failed; at least 1.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each later one.
+    pub backoff: Duration,
+    /// Longest a single attempt may run.
+    pub timeout: Option<Duration>,
+}
+
+/// Longest delay between two attempts, however many have failed.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            backoff: Duration::from_secs(30),
+            timeout: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    pub fn backoff(mut self, delay: Duration) -> Self {
+        self.backoff = delay;
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Delay before attempt `failed + 1`, after `failed` failed attempts.
+    pub fn delay_after(&self, failed: u32) -> Duration {
+        let factor = 1u32 << failed.saturating_sub(1).min(20);
+        self.backoff.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+/// Lifecycle of a job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl JobState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(Self::Pending),
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether the job has finished, successfully or not.
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed)
+    }
+}
+
+/// A job as reported by the status endpoint. The payload is not included.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobStatus {
+    pub id: String,
+    pub kind: String,
+    pub state: JobState,
+    pub priority: Priority,
+    /// 0–100, as last reported by the job; 100 once it succeeded.
+    pub progress: u8,
+    /// What [`Job::run`] returned, once succeeded.
+    pub result: Option<Value>,
+    /// Why the job failed.
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    /// Unix seconds.
+    pub created_at: i64,
+    /// When a pending job becomes due: its scheduled time, or the end of a
+    /// retry backoff.
+    pub run_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+}
+
+/// A job as shown to administrators: the status plus the payload.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JobDetail {
+    #[serde(flatten)]
+    pub status: JobStatus,
+    pub payload: Value,
+}
+
+/// Filter for [`JobStore::list`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JobQuery {
+    pub state: Option<JobState>,
+    pub kind: Option<String>,
+    pub limit: i64,
+}
+
+/// A job claimed by a runner.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaimedJob {
+    pub id: String,
+    pub kind: String,
+    pub payload: String,
+    /// This attempt's number, starting at 1.
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub backoff_ms: i64,
+    pub timeout_ms: Option<i64>,
+}
+
+impl ClaimedJob {
+    fn policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.max(1) as u32,
+            backoff: Duration::from_millis(self.backoff_ms.max(0) as u64),
+            timeout: self
+                .timeout_ms
+                .map(|ms| Duration::from_millis(ms.max(0) as u64)),
+        }
+    }
+}
+
+/// Maximum page size for [`JobStore::list`].
+pub const MAX_LIST_LIMIT: i64 = 200;
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_jobs (
+        id TEXT PRIMARY KEY,
+        kind TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        state TEXT NOT NULL DEFAULT 'pending',
+        progress INT NOT NULL DEFAULT 0,
+        result TEXT,
+        error TEXT,
+        attempts INT NOT NULL DEFAULT 0,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        started_at TIMESTAMPTZ,
+        finished_at TIMESTAMPTZ
+    )
+"#;
+
+/// Retry policy columns and the indexes runners and admins query by.
+const UPGRADE_SQL: [&str; 8] = [
+    "ALTER TABLE chopin_jobs ADD COLUMN IF NOT EXISTS run_at TIMESTAMPTZ NOT NULL DEFAULT NOW()",
+    "ALTER TABLE chopin_jobs ADD COLUMN IF NOT EXISTS max_attempts INT NOT NULL DEFAULT 1",
+    "ALTER TABLE chopin_jobs ADD COLUMN IF NOT EXISTS backoff_ms BIGINT NOT NULL DEFAULT 0",
+    "ALTER TABLE chopin_jobs ADD COLUMN IF NOT EXISTS timeout_ms BIGINT",
+    "DROP INDEX IF EXISTS chopin_jobs_pending_idx",
+    "CREATE INDEX IF NOT EXISTS chopin_jobs_due_idx \
+     ON chopin_jobs (run_at) WHERE state = 'pending'",
+    "CREATE INDEX IF NOT EXISTS chopin_jobs_running_idx \
+     ON chopin_jobs (started_at) WHERE state = 'running'",
+    "CREATE INDEX IF NOT EXISTS chopin_jobs_state_idx ON chopin_jobs (state, created_at DESC)",
+];
+
+/// Priority lanes; the index serves both the per-lane and the fallback claim.
+const PRIORITY_SQL: [&str; 2] = [
+    "ALTER TABLE chopin_jobs ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 1",
+    "CREATE INDEX IF NOT EXISTS chopin_jobs_lane_idx \
+     ON chopin_jobs (priority, run_at) WHERE state = 'pending'",
+];
+
+const SELECT_COLUMNS: &str = "id, kind, state, priority, progress, result, error, attempts, max_attempts, \
+     EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at, \
+     EXTRACT(EPOCH FROM run_at)::BIGINT AS run_at, \
+     EXTRACT(EPOCH FROM started_at)::BIGINT AS started_at, \
+     EXTRACT(EPOCH FROM finished_at)::BIGINT AS finished_at";
+
+/// Storage operations on `chopin_jobs`.
+pub struct JobStore;
+
+impl JobStore {
+    /// Creates the table and its indexes if they do not exist, and adds
+    /// columns missing from tables created by older versions.
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        Self::upgrade(executor)?;
+        Self::add_priority(executor)
+    }
+
+    fn upgrade(executor: &mut dyn Executor) -> OrmResult<()> {
+        for sql in UPGRADE_SQL {
+            executor.execute(sql, &[])?;
+        }
+        Ok(())
+    }
+
+    fn add_priority(executor: &mut dyn Executor) -> OrmResult<()> {
+        for sql in PRIORITY_SQL {
+            executor.execute(sql, &[])?;
+        }
+        Ok(())
+    }
+
+    /// Store a pending job with a new random id, to be run under `policy`
+    /// in the lane and at the time `options` ask for (normal and now, when
+    /// they do not say).
+    pub fn insert(
+        executor: &mut dyn Executor,
+        kind: &str,
+        payload: &str,
+        policy: &RetryPolicy,
+        options: &EnqueueOptions,
+    ) -> OrmResult<JobStatus> {
+        let id = crate::import::new_id();
+        let max_attempts = policy.max_attempts.max(1) as i32;
+        let backoff_ms = policy.backoff.as_millis() as i64;
+        let timeout_ms = policy.timeout.map(|t| t.as_millis() as i64);
+        let priority = options.priority.unwrap_or_default().as_i16();
+        let (at_ms, delay_ms) = match options.schedule {
+            Schedule::Now => (None, 0i64),
+            Schedule::In(delay) => (None, delay.as_millis() as i64),
+            Schedule::At(at) => {
+                let ms = match at.duration_since(UNIX_EPOCH) {
+                    Ok(since) => since.as_millis() as i64,
+                    Err(before) => -(before.duration().as_millis() as i64),
+                };
+                (Some(ms), 0)
+            }
+        };
+        let sql = format!(
+            "INSERT INTO chopin_jobs \
+                 (id, kind, payload, max_attempts, backoff_ms, timeout_ms, priority, run_at) \
+             VALUES ($1, $2, $3, $4, $5, $6, $7, \
+                 COALESCE(to_timestamp($8::BIGINT / 1000.0), \
+                     NOW() + $9::BIGINT * INTERVAL '1 millisecond')) \
+             RETURNING {SELECT_COLUMNS}"
+        );
+        let rows = executor.query(
+            &sql,
+            &[
+                &id,
+                &kind,
+                &payload,
+                &max_attempts,
+                &backoff_ms,
+                &timeout_ms,
+                &priority,
+                &at_ms,
+                &delay_ms,
+            ],
+        )?;
+        let row = rows.first().ok_or(chopin_orm::OrmError::RecordNotFound)?;
+        from_row(row)
+    }
+
+    pub fn get(executor: &mut dyn Executor, id: &str) -> OrmResult<Option<JobStatus>> {
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM chopin_jobs WHERE id = $1");
+        executor
+            .query(&sql, &[&id])?
+            .first()
+            .map(from_row)
+            .transpose()
+    }
+
+    /// Mark the job that has been due longest in the `preferred` lane
+    /// running and return it. If that lane has nothing due, take the most
+    /// urgent due job of any lane instead. Rows locked by another runner are
+    /// skipped rather than waited for.
+    pub fn claim(
+        executor: &mut dyn Executor,
+        preferred: Priority,
+    ) -> OrmResult<Option<ClaimedJob>> {
+        match Self::claim_from(executor, Some(preferred))? {
+            Some(job) => Ok(Some(job)),
+            None => Self::claim_from(executor, None),
+        }
+    }
+
+    fn claim_from(
+        executor: &mut dyn Executor,
+        lane: Option<Priority>,
+    ) -> OrmResult<Option<ClaimedJob>> {
+        let lane = lane.map(Priority::as_i16);
+        let sql = format!(
+            "UPDATE chopin_jobs SET state = 'running', started_at = NOW(), \
+                 attempts = attempts + 1 \
+             WHERE id = (SELECT id FROM chopin_jobs \
+                 WHERE state = 'pending' {} AND run_at <= NOW() \
+                 ORDER BY priority, run_at LIMIT 1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, kind, payload, attempts, max_attempts, backoff_ms, timeout_ms",
+            if lane.is_some() {
+                "AND priority = $1"
+            } else {
+                ""
+            }
+        );
+        let rows = match &lane {
+            Some(lane) => executor.query(&sql, &[lane])?,
+            None => executor.query(&sql, &[])?,
+        };
+        rows.first()
+            .map(|row| {
+                Ok(ClaimedJob {
+                    id: row.get_typed_by_name("id")?,
+                    kind: row.get_typed_by_name("kind")?,
+                    payload: row.get_typed_by_name("payload")?,
+                    attempts: row.get_typed_by_name("attempts")?,
+                    max_attempts: row.get_typed_by_name("max_attempts")?,
+                    backoff_ms: row.get_typed_by_name("backoff_ms")?,
+                    timeout_ms: row.get_typed_by_name("timeout_ms")?,
+                })
+            })
+            .transpose()
+    }
+
+    /// Requeue, or fail once out of attempts, running jobs that have
+    /// exceeded twice their timeout. Their runner is assumed gone: a live
+    /// runner settles its own timeouts after one. Returns how many
+    /// were touched.
+    pub fn requeue_stale(executor: &mut dyn Executor) -> OrmResult<u64> {
+        executor.execute(
+            "UPDATE chopin_jobs SET \
+                 state = CASE WHEN attempts >= max_attempts THEN 'failed' ELSE 'pending' END, \
+                 error = 'attempt ' || attempts || ' abandoned after ' || timeout_ms || ' ms', \
+                 run_at = NOW(), \
+                 finished_at = CASE WHEN attempts >= max_attempts THEN NOW() END \
+             WHERE state = 'running' AND timeout_ms IS NOT NULL \
+                 AND started_at + timeout_ms * 2 * INTERVAL '1 millisecond' < NOW()",
+            &[],
+        )
+    }
+
+    /// Record progress of a running job, clamped to 0–100.
+    pub fn set_progress(executor: &mut dyn Executor, id: &str, percent: u8) -> OrmResult<()> {
+        let percent = i32::from(percent.min(100));
+        executor.execute(
+            "UPDATE chopin_jobs SET progress = $2 WHERE id = $1 AND state = 'running'",
+            &[&id, &percent],
+        )?;
+        Ok(())
+    }
+
+    /// Finish attempt `attempt` of a running job with its JSON-encoded
+    /// result. Returns `false` if that attempt was already settled, e.g.
+    /// timed out.
+    pub fn succeed(
+        executor: &mut dyn Executor,
+        id: &str,
+        attempt: i32,
+        result: &str,
+    ) -> OrmResult<bool> {
+        let n = executor.execute(
+            "UPDATE chopin_jobs SET state = 'succeeded', progress = 100, result = $3, \
+                 error = NULL, finished_at = NOW() \
+             WHERE id = $1 AND attempts = $2 AND state = 'running'",
+            &[&id, &attempt, &result],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Leave attempt `attempt` failed for good.
+    pub fn fail(
+        executor: &mut dyn Executor,
+        id: &str,
+        attempt: i32,
+        error: &str,
+    ) -> OrmResult<bool> {
+        let n = executor.execute(
+            "UPDATE chopin_jobs SET state = 'failed', error = $3, finished_at = NOW() \
+             WHERE id = $1 AND attempts = $2 AND state = 'running'",
+            &[&id, &attempt, &error],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Put a failed attempt back in the queue, due after `delay`.
+    pub fn retry_later(
+        executor: &mut dyn Executor,
+        id: &str,
+        attempt: i32,
+        error: &str,
+        delay: Duration,
+    ) -> OrmResult<bool> {
+        let delay_ms = delay.as_millis() as i64;
+        let n = executor.execute(
+            "UPDATE chopin_jobs SET state = 'pending', error = $3, progress = 0, \
+                 run_at = NOW() + $4 * INTERVAL '1 millisecond' \
+             WHERE id = $1 AND attempts = $2 AND state = 'running'",
+            &[&id, &attempt, &error, &delay_ms],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Newest first, at most `query.limit` (capped at [`MAX_LIST_LIMIT`]).
+    pub fn list(executor: &mut dyn Executor, query: &JobQuery) -> OrmResult<Vec<JobStatus>> {
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM chopin_jobs \
+             WHERE ($1::TEXT IS NULL OR state = $1) AND ($2::TEXT IS NULL OR kind = $2) \
+             ORDER BY created_at DESC LIMIT $3"
+        );
+        let state = query.state.map(JobState::as_str);
+        let limit = query.limit.clamp(1, MAX_LIST_LIMIT);
+        executor
+            .query(&sql, &[&state, &query.kind, &limit])?
+            .iter()
+            .map(from_row)
+            .collect()
+    }
+
+    /// Status and payload of job `id`.
+    pub fn detail(executor: &mut dyn Executor, id: &str) -> OrmResult<Option<JobDetail>> {
+        let sql = format!("SELECT {SELECT_COLUMNS}, payload FROM chopin_jobs WHERE id = $1");
+        executor
+            .query(&sql, &[&id])?
+            .first()
+            .map(|row| {
+                let payload: String = row.get_typed_by_name("payload")?;
+                Ok(JobDetail {
+                    status: from_row(row)?,
+                    payload: serde_json::from_str(&payload).unwrap_or(Value::String(payload)),
+                })
+            })
+            .transpose()
+    }
+
+    /// Requeue a failed job with its attempts reset. Returns `false` unless
+    /// the job exists and is failed.
+    pub fn retry(executor: &mut dyn Executor, id: &str) -> OrmResult<bool> {
+        let n = executor.execute(
+            &format!("{RETRY_SQL} WHERE id = $1 AND state = 'failed'"),
+            &[&id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Requeue every failed job, or those of one kind.
+    pub fn retry_failed(executor: &mut dyn Executor, kind: Option<&str>) -> OrmResult<u64> {
+        executor.execute(
+            &format!("{RETRY_SQL} WHERE state = 'failed' AND ($1::TEXT IS NULL OR kind = $1)"),
+            &[&kind],
+        )
+    }
+
+    /// Delete a job that has not started yet. Returns `false` unless it
+    /// existed and was pending.
+    pub fn cancel(executor: &mut dyn Executor, id: &str) -> OrmResult<bool> {
+        let n = executor.execute(
+            "DELETE FROM chopin_jobs WHERE id = $1 AND state = 'pending'",
+            &[&id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Delete a failed job. Returns `false` unless it existed and was failed.
+    pub fn discard(executor: &mut dyn Executor, id: &str) -> OrmResult<bool> {
+        let n = executor.execute(
+            "DELETE FROM chopin_jobs WHERE id = $1 AND state = 'failed'",
+            &[&id],
+        )?;
+        Ok(n > 0)
+    }
+}
+
+/// Requeue with a fresh set of attempts; the last error is kept until the
+/// next attempt settles.
+const RETRY_SQL: &str = "UPDATE chopin_jobs SET state = 'pending', attempts = 0, progress = 0, \
+     run_at = NOW(), started_at = NULL, finished_at = NULL";
+
+fn from_row(row: &chopin_orm::Row) -> OrmResult<JobStatus> {
+    let state: String = row.get_typed_by_name("state")?;
+    let progress: i32 = row.get_typed_by_name("progress")?;
+    let priority: i16 = row.get_typed_by_name("priority")?;
+    let result: Option<String> = row.get_typed_by_name("result")?;
+    Ok(JobStatus {
+        id: row.get_typed_by_name("id")?,
+        kind: row.get_typed_by_name("kind")?,
+        state: JobState::parse(&state).ok_or_else(|| {
+            chopin_orm::OrmError::Extraction(format!("unknown job state `{state}`"))
+        })?,
+        priority: Priority::from_i16(priority),
+        progress: progress.clamp(0, 100) as u8,
+        result: result.map(|r| serde_json::from_str(&r).unwrap_or(Value::String(r))),
+        error: row.get_typed_by_name("error")?,
+        attempts: row.get_typed_by_name("attempts")?,
+        max_attempts: row.get_typed_by_name("max_attempts")?,
+        created_at: row.get_typed_by_name("created_at")?,
+        run_at: row.get_typed_by_name("run_at")?,
+        started_at: row.get_typed_by_name("started_at")?,
+        finished_at: row.get_typed_by_name("finished_at")?,
+    })
+}
+
+// ─── Registry and execution ──────────────────────────────────────────────────
+
+type RunFn = fn(&str, &JobContext) -> ChopinResult<Value>;
+
+static HANDLERS: RwLock<BTreeMap<&'static str, RunFn>> = RwLock::new(BTreeMap::new());
+static POLICIES: RwLock<BTreeMap<String, RetryPolicy>> = RwLock::new(BTreeMap::new());
+
+/// Make `J` runnable by this process's runners.
+pub fn register<J: Job>() {
+    fn run<J: Job>(payload: &str, job: &JobContext) -> ChopinResult<Value> {
+        let job_data: J = serde_json::from_str(payload)
+            .map_err(|e| ChopinError::Other(format!("invalid payload: {e}")))?;
+        job_data.run(job)
+    }
+    let mut handlers = HANDLERS.write().unwrap_or_else(|e| e.into_inner());
+    handlers.insert(J::KIND, run::<J>);
+}
+
+/// Override the [`RetryPolicy`] of `kind` for jobs enqueued by this
+/// process from now on. Jobs already stored keep the policy they were
+/// enqueued with.
+pub fn set_policy(kind: &str, policy: RetryPolicy) {
+    let mut policies = POLICIES.write().unwrap_or_else(|e| e.into_inner());
+    policies.insert(kind.to_string(), policy);
+}
+
+/// The policy [`enqueue`] stores for `J`.
+pub fn policy_of<J: Job>() -> RetryPolicy {
+    POLICIES
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(J::KIND)
+        .copied()
+        .unwrap_or_else(J::policy)
+}
+
+fn handler(kind: &str) -> Option<RunFn> {
+    HANDLERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(kind)
+        .copied()
+}
+
+/// What a running job knows about itself.
+pub struct JobContext {
+    id: String,
+    kind: String,
+}
+
+impl JobContext {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn kind(&self) -> &str {
+        &self.kind
+    }
+
+    /// Report progress (0–100) to the status endpoint. Must not be called
+    /// from inside a [`db::with_db`] closure.
+    pub fn progress(&self, percent: u8) -> ChopinResult<()> {
+        db::with_db(|db| JobStore::set_progress(db, &self.id, percent))
+    }
+}
+
+/// Store `job` for a runner to pick up now, in its kind's lane.
+pub fn enqueue<J: Job>(job: &J) -> ChopinResult<JobHandle> {
+    enqueue_with(job, EnqueueOptions::new())
+}
+
+/// Store `job` for a runner to pick up in the lane and at the time
+/// `options` ask for.
+pub fn enqueue_with<J: Job>(job: &J, mut options: EnqueueOptions) -> ChopinResult<JobHandle> {
+    let payload =
+        serde_json::to_string(job).map_err(|e| ChopinError::Other(format!("job payload: {e}")))?;
+    let policy = policy_of::<J>();
+    options.priority.get_or_insert_with(J::priority);
+    let stored = db::with_db(|db| JobStore::insert(db, J::KIND, &payload, &policy, &options))?;
+    if options.schedule == Schedule::Now {
+        QUEUED.notify();
+    }
+    Ok(JobHandle { id: stored.id })
+}
+
+/// Drop job `id` if it has not started yet, e.g. a reminder that is no
+/// longer wanted. Returns `false` if it is unknown, running or finished.
+pub fn cancel(id: &str) -> ChopinResult<bool> {
+    db::with_db(|db| JobStore::cancel(db, id))
+}
+
+/// Current status of job `id`, or `None` if there is no such job.
+pub fn status(id: &str) -> ChopinResult<Option<JobStatus>> {
+    db::with_db(|db| JobStore::get(db, id))
+}
+
+/// Claim one due job and run it on the calling thread. Returns `false` if
+/// no job was due.
+///
+/// A job whose kind has no [`register`]ed handler, whose payload does not
+/// deserialize, or which returns an error, panics or exceeds its timeout
+/// has failed this attempt. It is retried per its policy, or else left
+/// failed. Jobs abandoned by a lost runner are requeued first.
+pub fn run_next() -> ChopinResult<bool> {
+    let lane = LANE_CYCLE[NEXT_LANE.fetch_add(1, Ordering::Relaxed) % LANE_CYCLE.len()];
+    let claimed = db::with_db(|db| {
+        JobStore::requeue_stale(db)?;
+        JobStore::claim(db, lane)
+    })?;
+    let Some(claimed) = claimed else {
+        return Ok(false);
+    };
+    let policy = claimed.policy();
+    let outcome = match handler(&claimed.kind) {
+        Some(run) => {
+            let ctx = JobContext {
+                id: claimed.id.clone(),
+                kind: claimed.kind.clone(),
+            };
+            match policy.timeout {
+                Some(timeout) => run_with_timeout(run, claimed.payload.clone(), ctx, timeout),
+                None => run_caught(run, &claimed.payload, &ctx),
+            }
+        }
+        None => Err(ChopinError::Other(format!(
+            "no handler registered for job kind `{}`",
+            claimed.kind
+        ))),
+    };
+    let (id, attempt) = (claimed.id.as_str(), claimed.attempts);
+    match outcome {
+        Ok(value) => {
+            let result = value.to_string();
+            db::with_db(|db| JobStore::succeed(db, id, attempt, &result))?;
+        }
+        Err(e) => {
+            let error = e.to_string();
+            let retry = attempt < claimed.max_attempts;
+            eprintln!(
+                "[chopin] job {id} ({}) attempt {attempt}/{} failed{}: {error}",
+                claimed.kind,
+                claimed.max_attempts,
+                if retry { ", will retry" } else { "" },
+            );
+            db::with_db(|db| {
+                if retry {
+                    let delay = policy.delay_after(attempt as u32);
+                    JobStore::retry_later(db, id, attempt, &error, delay)
+                } else {
+                    JobStore::fail(db, id, attempt, &error)
+                }
+            })?;
+        }
+    }
+    Ok(true)
+}
+
+fn run_caught(run: RunFn, payload: &str, ctx: &JobContext) -> ChopinResult<Value> {
+    catch_panic(|| run(payload, ctx))
+}
+
+/// Run `f`, turning a panic into [`ChopinError::WorkerPanic`].
+pub(crate) fn catch_panic<T>(f: impl FnOnce() -> ChopinResult<T>) -> ChopinResult<T> {
+    std::panic::catch_unwind(std::panic::AssertUnwindSafe(f)).unwrap_or_else(|panic| {
+        let msg = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".into());
+        Err(ChopinError::WorkerPanic(msg))
+    })
+}
+
+/// Run the attempt on its own thread and stop waiting after `timeout`.
+/// Threads cannot be killed, so a timed-out attempt keeps running in the
+/// background; whatever it returns later is discarded, because its attempt
+/// number no longer matches the row.
+fn run_with_timeout(
+    run: RunFn,
+    payload: String,
+    ctx: JobContext,
+    timeout: Duration,
+) -> ChopinResult<Value> {
+    let (tx, rx) = std::sync::mpsc::sync_channel(1);
+    std::thread::Builder::new()
+        .name(format!("chopin-job-{}", ctx.id))
+        .spawn(move || {
+            let _ = tx.send(run_caught(run, &payload, &ctx));
+        })
+        .map_err(ChopinError::Io)?;
+    rx.recv_timeout(timeout).unwrap_or_else(|_| {
+        Err(ChopinError::Other(format!(
+            "timed out after {} ms",
+            timeout.as_millis()
+        )))
+    })
+}
+
+/// Threads that work through the queue.
+#[derive(Debug, Clone)]
+pub struct JobRunner {
+    threads: usize,
+    poll_interval: Duration,
+}
+
+impl Default for JobRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JobRunner {
+    /// One thread, polling every second when idle.
+    pub fn new() -> Self {
+        Self {
+            threads: 1,
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// How long an idle runner sleeps before checking the table again.
+    /// Jobs enqueued by this process wake it early; jobs enqueued elsewhere
+    /// wait for the poll.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Spawn the runner threads. Each opens its own connection through
+    /// [`db::with_db`] and runs until the process exits.
+    pub fn start(&self) -> ChopinResult<()> {
+        for i in 0..self.threads {
+            let poll = self.poll_interval;
+            std::thread::Builder::new()
+                .name(format!("chopin-jobs-{i}"))
+                .spawn(move || {
+                    loop {
+                        let seen = QUEUED.version();
+                        match run_next() {
+                            Ok(true) => {}
+                            Ok(false) => {
+                                QUEUED.wait_since(seen, poll);
+                            }
+                            Err(e) => {
+                                eprintln!("[chopin] job runner: {e}");
+                                std::thread::sleep(poll);
+                            }
+                        }
+                    }
+                })
+                .map_err(ChopinError::Io)?;
+        }
+        Ok(())
+    }
+}
+
+/// A stored job, as returned by [`enqueue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobHandle {
+    id: String,
+}
+
+impl JobHandle {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn status(&self) -> ChopinResult<Option<JobStatus>> {
+        status(&self.id)
+    }
+
+    /// `202 Accepted` pointing at `{status_base}/{id}`, with the URL in
+    /// `Location` and the body `{"id": .., "status_url": ..}`.
+    pub fn accepted(&self, status_base: &str) -> Response {
+        let url = format!("{}/{}", status_base.trim_end_matches('/'), self.id);
+        let body = serde_json::json!({ "id": self.id, "status_url": url });
+        let mut res = Response::json_bytes(body.to_string().into_bytes());
+        res.status = 202;
+        res.with_header("Location", url)
+    }
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+fn json<T: Serialize>(value: &T) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+fn found<T: Serialize>(result: ChopinResult<Option<T>>) -> Response {
+    match result {
+        Ok(Some(value)) => json(&value),
+        Ok(None) => Response::not_found(),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `GET {prefix}/:id`
+pub fn status_handler(ctx: Context) -> Response {
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    found(status(id))
+}
+
+#[derive(Deserialize)]
+struct AdminListParams {
+    #[serde(default = "default_admin_state")]
+    state: String,
+    kind: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_admin_state() -> String {
+    JobState::Failed.as_str().to_string()
+}
+
+fn default_limit() -> i64 {
+    50
+}
+
+#[derive(Deserialize)]
+struct KindParam {
+    kind: Option<String>,
+}
+
+/// `GET {admin}`: failed jobs by default; `?state=all` lists every state.
+pub fn admin_list_handler(ctx: Context) -> Response {
+    let params = match ctx.extract::<Query<AdminListParams>>() {
+        Ok(Query(p)) => p,
+        Err(res) => return res,
+    };
+    let state = match params.state.as_str() {
+        "all" => None,
+        s => match JobState::parse(s) {
+            Some(state) => Some(state),
+            None => return Response::bad_request(),
+        },
+    };
+    let query = JobQuery {
+        state,
+        kind: params.kind,
+        limit: params.limit,
+    };
+    match db::with_db(|db| JobStore::list(db, &query)) {
+        Ok(jobs) => json(&jobs),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `GET {admin}/:id`
+pub fn admin_detail_handler(ctx: Context) -> Response {
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    found(db::with_db(|db| JobStore::detail(db, id)))
+}
+
+/// `POST {admin}/:id/retry`
+pub fn admin_retry_handler(ctx: Context) -> Response {
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    match db::with_db(|db| JobStore::retry(db, id)) {
+        Ok(true) => {
+            QUEUED.notify();
+            Response::new(204)
+        }
+        Ok(false) => Response::not_found(),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `POST {admin}/retry-failed`, returning `{"retried": n}`.
+pub fn admin_retry_failed_handler(ctx: Context) -> Response {
+    let params = match ctx.extract::<Query<KindParam>>() {
+        Ok(Query(p)) => p,
+        Err(res) => return res,
+    };
+    match db::with_db(|db| JobStore::retry_failed(db, params.kind.as_deref())) {
+        Ok(retried) => {
+            QUEUED.notify();
+            json(&serde_json::json!({ "retried": retried }))
+        }
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `DELETE {admin}/:id`
+pub fn admin_discard_handler(ctx: Context) -> Response {
+    let Some(id) = ctx.param("id") else {
+        return Response::bad_request();
+    };
+    match db::with_db(|db| JobStore::discard(db, id)) {
+        Ok(true) => Response::new(204),
+        Ok(false) => Response::not_found(),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Mounts the status endpoint and, if configured, the admin endpoints. Owns
+/// the table migrations and optionally starts a [`JobRunner`] with the
+/// server.
+pub struct JobsModule {
+    prefix: &'static str,
+    runner: Option<JobRunner>,
+    admin: Option<(&'static str, MiddlewareFn)>,
+}
+
+impl JobsModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+            runner: None,
+            admin: None,
+        }
+    }
+
+    /// Mount the dead-letter admin endpoints under `prefix`, behind `guard`.
+    /// The guard is layered on the whole prefix and should reject anyone who
+    /// is not an operator, since job payloads and errors may hold private
+    /// data.
+    pub fn admin(mut self, prefix: &'static str, guard: MiddlewareFn) -> Self {
+        self.admin = Some((prefix.trim_end_matches('/'), guard));
+        self
+    }
+
+    /// Start `runner` before the server accepts connections. Without one,
+    /// jobs are only stored, for runners in another process.
+    pub fn runner(mut self, runner: JobRunner) -> Self {
+        self.runner = Some(runner);
+        self
+    }
+}
+
+impl Default for JobsModule {
+    fn default() -> Self {
+        Self::new("/api/jobs")
+    }
+}
+
+impl ChopinModule for JobsModule {
+    fn name(&self) -> &'static str {
+        "jobs"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.get(&format!("{}/:id", self.prefix), status_handler);
+        if let Some((a, guard)) = self.admin {
+            router.get(if a.is_empty() { "/" } else { a }, admin_list_handler);
+            router.get(&format!("{a}/:id"), admin_detail_handler);
+            router.delete(&format!("{a}/:id"), admin_discard_handler);
+            router.post(&format!("{a}/:id/retry"), admin_retry_handler);
+            router.post(&format!("{a}/retry-failed"), admin_retry_failed_handler);
+            router.layer_path(a, guard);
+        }
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![
+            Box::new(CreateJobsTable),
+            Box::new(AddJobRetries),
+            Box::new(AddJobPriority),
+        ]
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        match &self.runner {
+            Some(runner) => runner.start(),
+            None => Ok(()),
+        }
+    }
+}
+
+struct CreateJobsTable;
+
+impl Migration for CreateJobsTable {
+    fn name(&self) -> &'static str {
+        "001_create_jobs"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        JobStore::ensure_table(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_jobs", &[])?;
+        Ok(())
+    }
+}
+
+struct AddJobRetries;
+
+impl Migration for AddJobRetries {
+    fn name(&self) -> &'static str {
+        "002_add_job_retries"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        JobStore::upgrade(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        for sql in [
+            "DROP INDEX IF EXISTS chopin_jobs_due_idx",
+            "DROP INDEX IF EXISTS chopin_jobs_running_idx",
+            "DROP INDEX IF EXISTS chopin_jobs_state_idx",
+            "ALTER TABLE chopin_jobs DROP COLUMN IF EXISTS run_at, \
+             DROP COLUMN IF EXISTS max_attempts, DROP COLUMN IF EXISTS backoff_ms, \
+             DROP COLUMN IF EXISTS timeout_ms",
+            "CREATE INDEX IF NOT EXISTS chopin_jobs_pending_idx \
+             ON chopin_jobs (created_at) WHERE state = 'pending'",
+        ] {
+            executor.execute(sql, &[])?;
+        }
+        Ok(())
+    }
+}
+
+struct AddJobPriority;
+
+impl Migration for AddJobPriority {
+    fn name(&self) -> &'static str {
+        "003_add_job_priority"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        JobStore::add_priority(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP INDEX IF EXISTS chopin_jobs_lane_idx", &[])?;
+        executor.execute(
+            "ALTER TABLE chopin_jobs DROP COLUMN IF EXISTS priority",
+            &[],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Method;
+    use chopin_orm::{MockExecutor, mock_row};
+
+    fn status_row(state: &str, result: Option<&str>) -> chopin_orm::Row {
+        mock_row!(
+            "id" => "abc123",
+            "kind" => "export",
+            "state" => state,
+            "priority" => 1i16,
+            "progress" => 40i32,
+            "result" => result.map(str::to_string),
+            "error" => None::<String>,
+            "attempts" => 1i32,
+            "max_attempts" => 3i32,
+            "created_at" => 1_700_000_000i64,
+            "run_at" => 1_700_000_000i64,
+            "started_at" => Some(1_700_000_001i64),
+            "finished_at" => None::<i64>,
+        )
+    }
+
+    #[test]
+    fn test_insert_and_status_json() {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![status_row("pending", None)]);
+        let policy = RetryPolicy::new().max_attempts(3);
+        let options = EnqueueOptions::new().priority(Priority::Low);
+        let stored = JobStore::insert(&mut db, "export", "{}", &policy, &options).unwrap();
+        assert_eq!(stored.state, JobState::Pending);
+        assert_eq!(db.executed_queries[0].1, 9);
+
+        db.push_result(vec![status_row("succeeded", Some(r#"{"url":"/r.csv"}"#))]);
+        let status = JobStore::get(&mut db, "abc123").unwrap().unwrap();
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["state"], "succeeded");
+        assert_eq!(json["progress"], 40);
+        assert_eq!(json["priority"], "normal");
+        assert_eq!(json["result"]["url"], "/r.csv");
+        assert!(json.get("payload").is_none());
+
+        assert!(JobStore::get(&mut db, "missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_claim_skips_locked_rows() {
+        let mut db = MockExecutor::new();
+        assert!(JobStore::claim(&mut db, Priority::Low).unwrap().is_none());
+        let [(lane, 1), (any, 0)] = &db.executed_queries[..] else {
+            panic!("expected a lane claim and a fallback");
+        };
+        assert!(lane.contains("priority = $1") && lane.contains("FOR UPDATE SKIP LOCKED"));
+        assert!(!any.contains("priority = $1") && any.contains("ORDER BY priority, run_at"));
+
+        db.push_result(vec![mock_row!(
+            "id" => "j1",
+            "kind" => "export",
+            "payload" => "{}",
+            "attempts" => 2i32,
+            "max_attempts" => 3i32,
+            "backoff_ms" => 1_000i64,
+            "timeout_ms" => Some(60_000i64),
+        )]);
+        let claimed = JobStore::claim(&mut db, Priority::High).unwrap().unwrap();
+        assert_eq!(
+            (claimed.id.as_str(), claimed.kind.as_str()),
+            ("j1", "export")
+        );
+    }
+
+    #[test]
+    fn test_lane_cycle_and_priority_round_trip() {
+        let share = |p| LANE_CYCLE.iter().filter(|&&l| l == p).count();
+        assert_eq!(
+            (
+                share(Priority::High),
+                share(Priority::Normal),
+                share(Priority::Low)
+            ),
+            (4, 2, 1)
+        );
+        for p in [Priority::High, Priority::Normal, Priority::Low] {
+            assert_eq!(Priority::from_i16(p.as_i16()), p);
+        }
+        assert_eq!(Priority::from_i16(7), Priority::Low);
+    }
+
+    #[test]
+    fn test_retry_policy_backoff_doubles_and_caps() {
+        let policy = RetryPolicy::new()
+            .max_attempts(0)
+            .backoff(Duration::from_secs(10));
+        assert_eq!(policy.max_attempts, 1);
+        assert_eq!(policy.delay_after(1), Duration::from_secs(10));
+        assert_eq!(policy.delay_after(2), Duration::from_secs(20));
+        assert_eq!(policy.delay_after(4), Duration::from_secs(80));
+        assert_eq!(policy.delay_after(40), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_policy_override_and_admin_queries() {
+        #[derive(Serialize, Deserialize)]
+        struct Noop;
+        impl Job for Noop {
+            const KIND: &'static str = "unit_noop";
+            fn run(&self, _job: &JobContext) -> ChopinResult<Value> {
+                Ok(Value::Null)
+            }
+            fn policy() -> RetryPolicy {
+                RetryPolicy::new().max_attempts(2)
+            }
+        }
+        assert_eq!(policy_of::<Noop>().max_attempts, 2);
+        set_policy("unit_noop", RetryPolicy::new().max_attempts(7));
+        assert_eq!(policy_of::<Noop>().max_attempts, 7);
+
+        let mut db = MockExecutor::new();
+        let query = JobQuery {
+            state: Some(JobState::Failed),
+            kind: None,
+            limit: 10_000,
+        };
+        assert!(JobStore::list(&mut db, &query).unwrap().is_empty());
+        assert_eq!(db.executed_queries[0].1, 3);
+        JobStore::retry(&mut db, "j1").unwrap();
+        JobStore::discard(&mut db, "j1").unwrap();
+        for (sql, _) in &db.executed_queries[1..] {
+            assert!(sql.contains("state = 'failed'"), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_state_round_trip() {
+        for state in [
+            JobState::Pending,
+            JobState::Running,
+            JobState::Succeeded,
+            JobState::Failed,
+        ] {
+            assert_eq!(JobState::parse(state.as_str()), Some(state));
+        }
+        assert!(JobState::Failed.is_finished() && !JobState::Running.is_finished());
+    }
+
+    #[test]
+    fn test_accepted_response() {
+        let res = JobHandle { id: "abc".into() }.accepted("/api/jobs/");
+        assert_eq!(res.status, 202);
+        assert!(
+            res.headers
+                .iter()
+                .any(|h| h.name == "Location" && h.value.as_str() == "/api/jobs/abc")
+        );
+    }
+
+    #[test]
+    fn test_module_route() {
+        let mut router = Router::new();
+        fn deny(_: Context, _: crate::router::BoxedHandler) -> Response {
+            Response::forbidden()
+        }
+        JobsModule::default()
+            .admin("/admin/jobs", deny)
+            .routes(&mut router);
+        router.finalize();
+        assert!(router.match_route(Method::Get, "/api/jobs/abc").is_some());
+        for (method, path) in [
+            (Method::Get, "/admin/jobs"),
+            (Method::Get, "/admin/jobs/abc"),
+            (Method::Delete, "/admin/jobs/abc"),
+            (Method::Post, "/admin/jobs/abc/retry"),
+            (Method::Post, "/admin/jobs/retry-failed"),
+        ] {
+            assert!(router.match_route(method, path).is_some(), "{path}");
+        }
+        let app = crate::testing::TestApp::new(router);
+        assert_eq!(app.get("/admin/jobs").status, 403);
+    }
+}
@@ -32,34 +32,94 @@ use mimalloc::MiMalloc;
 #[global_allocator]
 static GLOBAL: MiMalloc = MiMalloc;
 
+pub mod assets;
+pub mod cache;
+pub mod config;
 pub mod conn;
+#[cfg(feature = "orm")]
+pub mod cron;
+#[cfg(feature = "orm")]
+pub mod crud;
+#[cfg(feature = "orm")]
+pub mod db;
+#[cfg(feature = "orm")]
+pub mod debug_toolbar;
 pub mod error;
+#[cfg(feature = "sentry")]
+pub mod error_tracking;
 pub mod extract;
+pub mod form;
+pub mod guard;
 pub mod headers;
+pub mod health;
 pub mod http;
 pub mod http2;
 pub mod http_date;
+#[cfg(feature = "orm")]
+pub mod import;
+#[cfg(feature = "orm")]
+pub mod jobs;
 pub mod json;
+pub mod logging;
+pub mod longpoll;
+#[cfg(feature = "orm")]
+pub mod mail;
+#[cfg(feature = "orm")]
+pub mod memo;
+#[cfg(feature = "orm")]
+pub mod metering;
 pub mod metrics;
+pub mod module;
 pub mod multipart;
+#[cfg(feature = "orm")]
+pub mod notifications;
 pub mod openapi;
 pub mod parser;
+#[cfg(feature = "payments")]
+pub mod payments;
+pub mod plugin;
+pub mod presence;
+#[cfg(feature = "profiler")]
+pub mod profiler;
+#[cfg(feature = "orm")]
+pub mod quota;
+pub mod range;
+pub mod recorder;
+pub mod redact;
+pub mod response;
+pub mod rollout;
 pub mod router;
+#[cfg(feature = "orm")]
+pub mod row_stream;
 pub mod server;
+pub mod session;
 pub mod slab;
 pub mod syscalls;
+pub mod testing;
 pub mod timer;
 pub mod websocket;
 pub mod worker;
 
 // Re-exports for users
+pub use assets::{Assets, AssetsModule};
+pub use cache::CachePolicy;
+pub use config::{Config, Settings, SettingsSection};
 pub use error::{ChopinError, ChopinResult};
-pub use extract::{FromRequest, Json, Query};
+pub use extract::{FromRequest, Json, PathParam, Query};
+pub use form::{CsrfModule, Form, FormErrors, OldInput};
 pub use headers::{Header, HeaderValue, Headers, IntoHeaderValue};
 pub use http::{Body, Context, Method, OwnedFd, Request, Response};
 pub use json::KJson;
-pub use router::{RouteDef, Router};
-pub use server::{Chopin, Server};
+pub use module::ChopinModule;
+pub use openapi::DocsConfig;
+pub use plugin::Plugin;
+pub use redact::{Redacted, Redactor};
+pub use rollout::{Rollout, Split};
+pub use router::{PathParamDef, RouteDef, Router};
+#[cfg(feature = "orm")]
+pub use server::MigrationsExecutor;
+pub use server::{Chopin, Server, StartupCommand};
+pub use session::{MemoryStore, SessionModule, SessionStore};
 
 // Re-export for macros
 pub use chopin_macros::*;
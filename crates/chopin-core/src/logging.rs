@@ -0,0 +1,771 @@
+// src/logging.rs
+//! Structured JSON logging to stderr or rotating files.
+//!
+//! [`LoggingModule`] installs a process-wide logger when the server starts and
+//! layers [`request_logger`] over every route. The middleware gives each
+//! request an id (the incoming `X-Request-Id` when it is sane, a fresh one
+//! otherwise), echoes it in the response, and writes one access line per
+//! request. Lines logged while a handler runs carry the same id:
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(
+//!     LoggingModule::new()
+//!         .level(Level::Info)
+//!         .file(FileSink::new("logs/app.log").max_bytes(64 << 20).keep(10)),
+//! );
+//!
+//! fn checkout(ctx: Context) -> Response {
+//!     logging::set_user_id(user.id.to_string());
+//!     logging::info("order placed").field("order_id", order.id).emit();
+//!     // ...
+//! }
+//! ```
+//!
+//! Lines are serialized on the calling thread and handed to a writer thread
+//! through a bounded queue, so a slow disk never stalls a worker. When the
+//! queue is full the line is dropped and counted in [`dropped`].
+//!
+//! ## Line schema (version 1)
+//!
+//! Every line is one JSON object followed by `\n`:
+//!
+//! | Key          | Type           | Meaning                                        |
+//! |--------------|----------------|------------------------------------------------|
+//! | `v`          | number         | Schema version, `1`                            |
+//! | `ts`         | string         | RFC 3339 UTC time with milliseconds            |
+//! | `level`      | string         | `error`, `warn`, `info` or `debug`             |
+//! | `target`     | string         | Component that logged the line, e.g. `http`    |
+//! | `msg`        | string         | Human-readable message                         |
+//! | `request_id` | string or null | Id of the request being handled                |
+//! | `user_id`    | string or null | Id set with [`set_user_id`] for that request   |
+//! | `fields`     | object         | Extra key/value pairs; sensitive keys redacted |
+//!
+//! ```json
+//! {"v":1,"ts":"2026-10-18T09:14:03.512Z","level":"info","target":"http","msg":"request","request_id":"5f2c0e9a41d8000000000003","user_id":"42","fields":{"duration_us":1840,"method":"POST","path":"/orders","status":201}}
+//! ```
+//!
+//! Within version 1 keys are only ever added, never renamed, removed or
+//! given another type. Access lines use target `http` and message
+//! `request`, with `method`, `path`, `status` and `duration_us` fields.
+//! Field values whose key the global [`Redactor`](crate::Redactor) treats
+//! as sensitive are written as `[REDACTED]`.
+//!
+//! Before a logger is installed, lines at `info` and above go straight to
+//! stderr in the same format.
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::cell::RefCell;
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Current version of the line schema, written as `v`.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Header carrying the request id in both directions.
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Lines the writer thread may fall behind by before new ones are dropped.
+pub const DEFAULT_CAPACITY: usize = 8192;
+
+/// Rotated files kept next to the live one by default.
+pub const DEFAULT_KEEP: usize = 7;
+
+/// Severity of a line. Lower is more severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+}
+
+// ─── Request scope ───────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct Scope {
+    request_id: Option<String>,
+    user_id: Option<String>,
+}
+
+thread_local! {
+    static SCOPE: RefCell<Scope> = RefCell::new(Scope::default());
+}
+
+/// Clears the request scope when the request ends, also on panic.
+struct ScopeGuard;
+
+impl ScopeGuard {
+    fn enter(request_id: String) -> Self {
+        SCOPE.with(|s| {
+            *s.borrow_mut() = Scope {
+                request_id: Some(request_id),
+                user_id: None,
+            }
+        });
+        ScopeGuard
+    }
+}
+
+impl Drop for ScopeGuard {
+    fn drop(&mut self) {
+        SCOPE.with(|s| *s.borrow_mut() = Scope::default());
+    }
+}
+
+/// Id of the request this thread is handling, if any.
+pub fn request_id() -> Option<String> {
+    SCOPE.with(|s| s.borrow().request_id.clone())
+}
+
+/// User id set for the request this thread is handling, if any.
+pub fn user_id() -> Option<String> {
+    SCOPE.with(|s| s.borrow().user_id.clone())
+}
+
+/// Attach a user id to the remaining lines of the current request,
+/// typically right after authentication.
+pub fn set_user_id(id: impl Into<String>) {
+    SCOPE.with(|s| s.borrow_mut().user_id = Some(id.into()));
+}
+
+// ─── Events ──────────────────────────────────────────────────────────────────
+
+/// One line being built. Nothing is written until [`emit`](Event::emit).
+#[must_use = "an event is only written by `emit`"]
+#[derive(Debug, Clone)]
+pub struct Event {
+    level: Level,
+    target: &'static str,
+    msg: String,
+    request_id: Option<String>,
+    user_id: Option<String>,
+    fields: Map<String, Value>,
+}
+
+pub fn event(level: Level, msg: impl Into<String>) -> Event {
+    Event {
+        level,
+        target: "app",
+        msg: msg.into(),
+        request_id: None,
+        user_id: None,
+        fields: Map::new(),
+    }
+}
+
+pub fn error(msg: impl Into<String>) -> Event {
+    event(Level::Error, msg)
+}
+
+pub fn warn(msg: impl Into<String>) -> Event {
+    event(Level::Warn, msg)
+}
+
+pub fn info(msg: impl Into<String>) -> Event {
+    event(Level::Info, msg)
+}
+
+pub fn debug(msg: impl Into<String>) -> Event {
+    event(Level::Debug, msg)
+}
+
+#[derive(Serialize)]
+struct Line<'a> {
+    v: u32,
+    ts: &'a str,
+    level: Level,
+    target: &'a str,
+    msg: &'a str,
+    request_id: Option<&'a str>,
+    user_id: Option<&'a str>,
+    fields: &'a Map<String, Value>,
+}
+
+impl Event {
+    /// Defaults to `app`.
+    pub fn target(mut self, target: &'static str) -> Self {
+        self.target = target;
+        self
+    }
+
+    /// Add a field. Values that fail to serialize are logged as `null`.
+    pub fn field(mut self, key: &str, value: impl Serialize) -> Self {
+        let redactor = crate::redact::global();
+        let value = if redactor.is_sensitive(key) {
+            Value::String(crate::redact::REDACTED.to_string())
+        } else {
+            let mut value = serde_json::to_value(value).unwrap_or(Value::Null);
+            redactor.redact_value(&mut value);
+            value
+        };
+        self.fields.insert(key.to_string(), value);
+        self
+    }
+
+    /// Override the request id taken from the current request.
+    pub fn request_id(mut self, id: impl Into<String>) -> Self {
+        self.request_id = Some(id.into());
+        self
+    }
+
+    /// Override the user id taken from the current request.
+    pub fn user_id(mut self, id: impl Into<String>) -> Self {
+        self.user_id = Some(id.into());
+        self
+    }
+
+    /// The line as written at `ts`, without the trailing newline.
+    pub fn to_json(&self, ts: SystemTime) -> String {
+        let ts = timestamp(ts);
+        let line = Line {
+            v: SCHEMA_VERSION,
+            ts: &ts,
+            level: self.level,
+            target: self.target,
+            msg: &self.msg,
+            request_id: self.request_id.as_deref(),
+            user_id: self.user_id.as_deref(),
+            fields: &self.fields,
+        };
+        serde_json::to_string(&line).unwrap_or_default()
+    }
+
+    /// Write the line if its level is enabled.
+    pub fn emit(mut self) {
+        if !enabled(self.level) {
+            return;
+        }
+        if self.request_id.is_none() || self.user_id.is_none() {
+            SCOPE.with(|s| {
+                let s = s.borrow();
+                self.request_id = self.request_id.take().or_else(|| s.request_id.clone());
+                self.user_id = self.user_id.take().or_else(|| s.user_id.clone());
+            });
+        }
+        #[cfg(feature = "sentry")]
+        crate::error_tracking::record_log(self.level, self.target, &self.msg, &self.fields);
+        let mut line = self.to_json(SystemTime::now());
+        line.push('\n');
+        match LOGGER.get() {
+            Some(logger) => {
+                if logger.tx.try_send(Msg::Line(line)).is_err() {
+                    DROPPED.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            None => {
+                let _ = std::io::stderr().write_all(line.as_bytes());
+            }
+        }
+    }
+}
+
+/// RFC 3339 UTC with milliseconds (`2024-01-31T12:00:00.250Z`).
+fn timestamp(t: SystemTime) -> String {
+    let millis = t
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_millis())
+        .unwrap_or(0);
+    let secs = crate::response::rfc3339(t);
+    format!("{}.{millis:03}Z", secs.trim_end_matches('Z'))
+}
+
+// ─── Logger ──────────────────────────────────────────────────────────────────
+
+enum Msg {
+    Line(String),
+    Flush(mpsc::Sender<()>),
+}
+
+struct Logger {
+    level: Level,
+    tx: SyncSender<Msg>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+static ACCESS_LOG: AtomicBool = AtomicBool::new(true);
+
+/// Whether a line at `level` would be written.
+pub fn enabled(level: Level) -> bool {
+    level <= LOGGER.get().map_or(Level::Info, |l| l.level)
+}
+
+/// Lines dropped because the writer thread had fallen behind.
+pub fn dropped() -> u64 {
+    DROPPED.load(Ordering::Relaxed)
+}
+
+/// Block until every line emitted so far has been written and flushed.
+pub fn flush() {
+    if let Some(logger) = LOGGER.get() {
+        let (tx, rx) = mpsc::channel();
+        if logger.tx.send(Msg::Flush(tx)).is_ok() {
+            let _ = rx.recv();
+        }
+    }
+}
+
+/// Rotating file destination.
+///
+/// The live file keeps its name; on rotation it becomes `<path>.1`, the
+/// previous `<path>.1` becomes `<path>.2`, and so on up to [`keep`](Self::keep).
+#[derive(Debug, Clone)]
+pub struct FileSink {
+    path: PathBuf,
+    max_bytes: Option<u64>,
+    every: Option<Duration>,
+    keep: usize,
+}
+
+impl FileSink {
+    /// Append to `path`, creating it and its directory as needed.
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes: None,
+            every: None,
+            keep: DEFAULT_KEEP,
+        }
+    }
+
+    /// Rotate before a line would take the file past `bytes`.
+    pub fn max_bytes(mut self, bytes: u64) -> Self {
+        self.max_bytes = Some(bytes.max(1));
+        self
+    }
+
+    /// Rotate when the UTC clock crosses a multiple of `interval`, so
+    /// `Duration::from_secs(86_400)` rotates at midnight UTC.
+    pub fn rotate_every(mut self, interval: Duration) -> Self {
+        self.every = Some(interval.max(Duration::from_secs(1)));
+        self
+    }
+
+    /// Number of rotated files to keep; older ones are deleted.
+    pub fn keep(mut self, files: usize) -> Self {
+        self.keep = files;
+        self
+    }
+}
+
+struct FileWriter {
+    sink: FileSink,
+    out: Option<BufWriter<File>>,
+    size: u64,
+    period: u64,
+}
+
+impl FileWriter {
+    fn new(sink: FileSink) -> Self {
+        Self {
+            sink,
+            out: None,
+            size: 0,
+            period: 0,
+        }
+    }
+
+    fn period_of(&self, unix: u64) -> u64 {
+        self.sink.every.map_or(0, |d| unix / d.as_secs())
+    }
+
+    fn write(&mut self, line: &[u8], unix: u64) -> std::io::Result<()> {
+        if self.out.is_none() {
+            self.open()?;
+        }
+        let period = self.period_of(unix);
+        let full = self
+            .sink
+            .max_bytes
+            .is_some_and(|max| self.size > 0 && self.size + line.len() as u64 > max);
+        if full || period != self.period {
+            self.rotate()?;
+            self.open()?;
+            self.period = period;
+        }
+        if let Some(out) = &mut self.out {
+            out.write_all(line)?;
+        }
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// Open the live file. An existing file counts towards the size limit
+    /// and belongs to the period it was last written in.
+    fn open(&mut self) -> std::io::Result<()> {
+        if let Some(dir) = self
+            .sink
+            .path
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(dir)?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.sink.path)?;
+        let meta = file.metadata()?;
+        self.size = meta.len();
+        let modified = meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        self.period = self.period_of(modified);
+        self.out = Some(BufWriter::new(file));
+        Ok(())
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if let Some(mut out) = self.out.take() {
+            out.flush()?;
+        }
+        let path = &self.sink.path;
+        if self.sink.keep == 0 {
+            return std::fs::remove_file(path);
+        }
+        let _ = std::fs::remove_file(rotated(path, self.sink.keep));
+        for n in (1..self.sink.keep).rev() {
+            let from = rotated(path, n);
+            if from.exists() {
+                std::fs::rename(from, rotated(path, n + 1))?;
+            }
+        }
+        std::fs::rename(path, rotated(path, 1))
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.out {
+            Some(out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+fn run_writer(rx: Receiver<Msg>, stderr: bool, mut files: Vec<FileWriter>) {
+    let report = |w: &FileWriter, e: std::io::Error| {
+        eprintln!("[chopin] logging: {}: {e}", w.sink.path.display());
+    };
+    let mut next = rx.recv().ok();
+    while let Some(msg) = next {
+        match msg {
+            Msg::Line(line) => {
+                if stderr {
+                    let _ = std::io::stderr().write_all(line.as_bytes());
+                }
+                let unix = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |d| d.as_secs());
+                for w in &mut files {
+                    if let Err(e) = w.write(line.as_bytes(), unix) {
+                        report(w, e);
+                    }
+                }
+            }
+            Msg::Flush(done) => {
+                for w in &mut files {
+                    if let Err(e) = w.flush() {
+                        report(w, e);
+                    }
+                }
+                let _ = done.send(());
+            }
+        }
+        // Flush once the queue is drained rather than after every line.
+        next = match rx.try_recv() {
+            Ok(msg) => Some(msg),
+            Err(_) => {
+                for w in &mut files {
+                    if let Err(e) = w.flush() {
+                        report(w, e);
+                    }
+                }
+                rx.recv().ok()
+            }
+        };
+    }
+}
+
+// ─── Middleware ──────────────────────────────────────────────────────────────
+
+fn valid_request_id(id: &str) -> bool {
+    (1..=128).contains(&id.len())
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Fill `buf` from the OS random source.
+pub(crate) fn random_bytes(buf: &mut [u8]) -> std::io::Result<()> {
+    use std::io::Read;
+    std::fs::File::open("/dev/urandom").and_then(|mut f| f.read_exact(buf))
+}
+
+/// A per-process random prefix followed by a counter.
+fn new_request_id() -> String {
+    static PREFIX: OnceLock<String> = OnceLock::new();
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let prefix = PREFIX.get_or_init(|| {
+        let mut bytes = [0u8; 6];
+        if random_bytes(&mut bytes).is_err() {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos() as u64);
+            bytes.copy_from_slice(&nanos.to_le_bytes()[..6]);
+        }
+        bytes.iter().map(|b| format!("{b:02x}")).collect()
+    });
+    format!("{prefix}{:012x}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Middleware assigning the request id, logging one access line per
+/// request and returning the id in `X-Request-Id`.
+pub fn request_logger(ctx: Context, next: BoxedHandler) -> Response {
+    let id = ctx
+        .header(REQUEST_ID_HEADER)
+        .filter(|id| valid_request_id(id))
+        .map_or_else(new_request_id, str::to_string);
+    let _scope = ScopeGuard::enter(id.clone());
+    let method = crate::openapi::method_name(ctx.req.method).to_uppercase();
+    let path = ctx.req.path.to_string();
+    let start = Instant::now();
+    let res = next(ctx);
+    if ACCESS_LOG.load(Ordering::Relaxed) && enabled(Level::Info) {
+        info("request")
+            .target("http")
+            .field("method", method)
+            .field("path", path)
+            .field("status", res.status)
+            .field("duration_us", start.elapsed().as_micros() as u64)
+            .emit();
+    }
+    res.with_header(REQUEST_ID_HEADER, id)
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Installs the logger on start and mounts [`request_logger`] globally.
+pub struct LoggingModule {
+    level: Level,
+    stderr: bool,
+    files: Vec<FileSink>,
+    capacity: usize,
+    access_log: bool,
+}
+
+impl LoggingModule {
+    /// Log at `info` and above to stderr until sinks are added.
+    pub fn new() -> Self {
+        Self {
+            level: Level::Info,
+            stderr: false,
+            files: Vec::new(),
+            capacity: DEFAULT_CAPACITY,
+            access_log: true,
+        }
+    }
+
+    pub fn level(mut self, level: Level) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Also write to stderr when file sinks are configured.
+    pub fn stderr(mut self) -> Self {
+        self.stderr = true;
+        self
+    }
+
+    pub fn file(mut self, sink: FileSink) -> Self {
+        self.files.push(sink);
+        self
+    }
+
+    /// Queue length between workers and the writer thread.
+    pub fn capacity(mut self, lines: usize) -> Self {
+        self.capacity = lines.max(1);
+        self
+    }
+
+    /// Whether [`request_logger`] writes access lines. Request ids are
+    /// assigned either way.
+    pub fn access_log(mut self, enabled: bool) -> Self {
+        self.access_log = enabled;
+        self
+    }
+
+    /// Start the writer thread and route every later line to it. Only the
+    /// first call in a process succeeds.
+    pub fn install(&self) -> ChopinResult<()> {
+        if LOGGER.get().is_some() {
+            return Err(ChopinError::Other("logging is already installed".into()));
+        }
+        let (tx, rx) = mpsc::sync_channel(self.capacity);
+        let stderr = self.stderr || self.files.is_empty();
+        let files = self.files.iter().cloned().map(FileWriter::new).collect();
+        LOGGER
+            .set(Logger {
+                level: self.level,
+                tx,
+            })
+            .map_err(|_| ChopinError::Other("logging is already installed".into()))?;
+        ACCESS_LOG.store(self.access_log, Ordering::Relaxed);
+        std::thread::Builder::new()
+            .name("chopin-log".into())
+            .spawn(move || run_writer(rx, stderr, files))?;
+        Ok(())
+    }
+}
+
+impl Default for LoggingModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChopinModule for LoggingModule {
+    fn name(&self) -> &'static str {
+        "logging"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.layer(request_logger);
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        self.install()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("chopin-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_line_schema() {
+        let ts = UNIX_EPOCH + Duration::from_millis(1_700_000_000_250);
+        let line = warn("disk almost full")
+            .target("storage")
+            .field("free_mb", 120)
+            .field("password", "hunter2")
+            .request_id("abc")
+            .to_json(ts);
+        let json: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["v"], 1);
+        assert_eq!(json["ts"], "2023-11-14T22:13:20.250Z");
+        assert_eq!(json["level"], "warn");
+        assert_eq!(json["target"], "storage");
+        assert_eq!(json["msg"], "disk almost full");
+        assert_eq!(json["request_id"], "abc");
+        assert_eq!(json["user_id"], Value::Null);
+        assert_eq!(json["fields"]["free_mb"], 120);
+        assert_eq!(json["fields"]["password"], crate::redact::REDACTED);
+    }
+
+    #[test]
+    fn test_file_rotates_by_size_and_keeps_n() {
+        let dir = temp_dir("log-size");
+        let path = dir.join("app.log");
+        let mut w = FileWriter::new(FileSink::new(&path).max_bytes(10).keep(2));
+        for line in ["aaaaaa\n", "bbbbbb\n", "cccccc\n", "dddddd\n"] {
+            w.write(line.as_bytes(), 0).unwrap();
+        }
+        w.flush().unwrap();
+
+        let read = |p: PathBuf| std::fs::read_to_string(p).unwrap();
+        assert_eq!(read(path.clone()), "dddddd\n");
+        assert_eq!(read(rotated(&path, 1)), "cccccc\n");
+        assert_eq!(read(rotated(&path, 2)), "bbbbbb\n");
+        assert!(!rotated(&path, 3).exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_file_rotates_on_period_boundary() {
+        let dir = temp_dir("log-time");
+        let path = dir.join("app.log");
+        let mut w = FileWriter::new(FileSink::new(&path).rotate_every(Duration::from_secs(3600)));
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        w.write(b"one\n", now).unwrap();
+        w.write(b"two\n", now).unwrap();
+        w.write(b"three\n", now + 3600).unwrap();
+        w.flush().unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "three\n");
+        assert_eq!(
+            std::fs::read_to_string(rotated(&path, 1)).unwrap(),
+            "one\ntwo\n"
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    fn whoami(_ctx: Context) -> Response {
+        set_user_id("42");
+        Response::text(request_id().unwrap_or_default())
+    }
+
+    #[test]
+    fn test_request_id_is_propagated() {
+        let mut router = Router::new();
+        router.layer(request_logger);
+        router.get("/logging-test/whoami", whoami);
+        let app = crate::testing::TestApp::new(router);
+
+        let res = app.request(
+            crate::http::Method::Get,
+            "/logging-test/whoami",
+            &[(REQUEST_ID_HEADER, "req-123")],
+            b"",
+        );
+        assert_eq!(res.text(), "req-123");
+        assert_eq!(res.header(REQUEST_ID_HEADER), Some("req-123"));
+
+        let res = app.request(
+            crate::http::Method::Get,
+            "/logging-test/whoami",
+            &[(REQUEST_ID_HEADER, "bad id\r\n")],
+            b"",
+        );
+        let id = res.text();
+        assert_eq!(id.len(), 24);
+        assert_eq!(res.header(REQUEST_ID_HEADER), Some(id.as_str()));
+        assert_eq!(request_id(), None);
+    }
+}
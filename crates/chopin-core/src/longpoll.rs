@@ -0,0 +1,568 @@
+// src/longpoll.rs
+//! Long-polling for clients that cannot use SSE or WebSockets.
+//!
+//! An [`Event`] is a version counter that producers bump with
+//! [`notify`](Event::notify). A handler answers with [`respond`], which waits
+//! until the version moves past the one the client last saw, or until a
+//! timeout, and then builds the response:
+//!
+//! ```rust,ignore
+//! static MESSAGES: Event = Event::new();
+//!
+//! #[get("/messages/poll/:since")]
+//! fn poll(ctx: Context) -> Response {
+//!     let since = ctx.param("since").and_then(|s| s.parse().ok()).unwrap_or(0);
+//!     longpoll::respond(&MESSAGES, since, Duration::from_secs(25), |version| match version {
+//!         Some(version) => Response::text(format!("{version}")),
+//!         None => Response::new(204),
+//!     })
+//! }
+//! ```
+//!
+//! The handler returns at once. If the client is not behind yet, the worker
+//! parks the connection and serves its other connections in the meantime;
+//! `notify` wakes every worker with a connection parked on the event, from
+//! any thread. Waits are capped at [`MAX_WAIT`]. Requests pipelined behind a
+//! parked one are answered after it.
+//!
+//! Background threads, which have nothing else to do, can block instead
+//! with [`Event::wait_since`]. Never call it from a handler.
+//!
+//! A [`Feed`] carries a response body produced on another thread. The
+//! connection is parked the same way until the first chunk arrives, and
+//! again whenever the socket has taken everything sent so far.
+use crate::http::{Body, Response};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, SyncSender, TryRecvError, sync_channel};
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/// Upper bound applied to every wait.
+pub const MAX_WAIT: Duration = Duration::from_secs(30);
+
+/// A notifiable version counter.
+pub struct Event {
+    version: AtomicU64,
+    lock: Mutex<()>,
+    changed: Condvar,
+    /// Workers with a connection parked on this event.
+    wakers: Mutex<Vec<Arc<Waker>>>,
+}
+
+impl Default for Event {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Event {
+    pub const fn new() -> Self {
+        Self {
+            version: AtomicU64::new(0),
+            lock: Mutex::new(()),
+            changed: Condvar::new(),
+            wakers: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Current version.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Bump the version and wake every waiter. Returns the new version.
+    pub fn notify(&self) -> u64 {
+        let version = self.version.fetch_add(1, Ordering::AcqRel) + 1;
+        drop(self.lock.lock().unwrap_or_else(|e| e.into_inner()));
+        self.changed.notify_all();
+        let wakers = std::mem::take(&mut *self.wakers.lock().unwrap_or_else(|e| e.into_inner()));
+        for waker in wakers {
+            waker.wake();
+        }
+        version
+    }
+
+    /// Block until the version is greater than `since`, returning the new
+    /// version, or `None` on timeout. Returns immediately if the client is
+    /// already behind, so no notification is missed between polls.
+    ///
+    /// This parks the calling thread: use it from background threads, and
+    /// [`respond`] from handlers.
+    pub fn wait_since(&self, since: u64, timeout: Duration) -> Option<u64> {
+        let deadline = Instant::now() + timeout.min(MAX_WAIT);
+        let mut guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            let version = self.version();
+            if version > since {
+                return Some(version);
+            }
+            let remaining = deadline.checked_duration_since(Instant::now())?;
+            guard = self
+                .changed
+                .wait_timeout(guard, remaining)
+                .unwrap_or_else(|e| e.into_inner())
+                .0;
+        }
+    }
+
+    /// Have the next [`notify`](Self::notify) wake `waker`.
+    fn subscribe(&self, waker: &Arc<Waker>) {
+        let mut wakers = self.wakers.lock().unwrap_or_else(|e| e.into_inner());
+        if !wakers.iter().any(|w| Arc::ptr_eq(w, waker)) {
+            wakers.push(waker.clone());
+        }
+    }
+}
+
+/// Block until the next notification of `event`, up to `timeout`. Returns
+/// `true` if the event fired. Like [`Event::wait_since`], not for handlers.
+pub fn wait_for(event: &Event, timeout: Duration) -> bool {
+    let since = event.version();
+    event.wait_since(since, timeout).is_some()
+}
+
+/// Answer once `event` moves past `since`, or after `timeout`.
+///
+/// `respond` gets the new version, or `None` if the wait timed out. If the
+/// client is already behind, it runs right away. Otherwise the returned
+/// response parks the connection and `respond` runs on the worker when the
+/// event fires.
+pub fn respond(
+    event: &'static Event,
+    since: u64,
+    timeout: Duration,
+    respond: impl FnOnce(Option<u64>) -> Response + Send + 'static,
+) -> Response {
+    let version = event.version();
+    if version > since {
+        return respond(Some(version));
+    }
+    park(Parked {
+        wait: Wait::Event {
+            event,
+            since,
+            deadline: Instant::now() + timeout.min(MAX_WAIT),
+            respond: Box::new(respond),
+        },
+    })
+}
+
+fn park(parked: Parked) -> Response {
+    let mut res = Response::new(200);
+    res.body = Body::Parked(Box::new(parked));
+    res
+}
+
+/// A response waiting on an [`Event`], from [`respond`], or on the first
+/// chunk of a [`Feed`], from [`Feed::respond`].
+pub struct Parked {
+    wait: Wait,
+}
+
+enum Wait {
+    Event {
+        event: &'static Event,
+        since: u64,
+        deadline: Instant,
+        respond: Box<dyn FnOnce(Option<u64>) -> Response + Send>,
+    },
+    Feed {
+        feed: Feed,
+        respond: Box<dyn FnOnce(Option<Feed>) -> Response + Send>,
+    },
+}
+
+impl Parked {
+    /// Have `waker` woken when the event fires or the feed sends.
+    pub(crate) fn subscribe(&self, waker: &Arc<Waker>) {
+        match &self.wait {
+            Wait::Event { event, .. } => event.subscribe(waker),
+            Wait::Feed { feed, .. } => feed.subscribe(waker),
+        }
+    }
+
+    /// When the wait times out. A feed waits for as long as its sender
+    /// lives.
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        match &self.wait {
+            Wait::Event { deadline, .. } => Some(*deadline),
+            Wait::Feed { .. } => None,
+        }
+    }
+
+    /// Whether the wait is over by `now`.
+    pub(crate) fn is_ready(&mut self, now: Instant) -> bool {
+        match &mut self.wait {
+            Wait::Event {
+                event,
+                since,
+                deadline,
+                ..
+            } => event.version() > *since || now >= *deadline,
+            Wait::Feed { feed, .. } => feed.is_ready(),
+        }
+    }
+
+    /// Build the response for whatever happened so far.
+    pub(crate) fn resolve(self) -> Response {
+        match self.wait {
+            Wait::Event {
+                event,
+                since,
+                respond,
+                ..
+            } => {
+                let version = event.version();
+                respond((version > since).then_some(version))
+            }
+            Wait::Feed { mut feed, respond } => {
+                let hung_up = feed.is_ready() && feed.next.is_none();
+                respond((!hung_up).then_some(feed))
+            }
+        }
+    }
+
+    /// Block until the wait is over, then build the response. For callers
+    /// outside the event loop, such as the test client.
+    pub(crate) fn wait(mut self) -> Response {
+        match &mut self.wait {
+            Wait::Event {
+                event,
+                since,
+                deadline,
+                ..
+            } => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                event.wait_since(*since, remaining);
+            }
+            Wait::Feed { feed, .. } => {
+                if feed.next.is_none() && !feed.done {
+                    feed.next = feed.rx.recv().ok();
+                    feed.done = feed.next.is_none();
+                }
+            }
+        }
+        self.resolve()
+    }
+}
+
+/// A bounded channel of body chunks from a producer thread to a streamed
+/// response. The producer blocks while `buffer` chunks wait for the socket.
+pub fn feed(buffer: usize) -> (FeedSender, Feed) {
+    let (tx, rx) = sync_channel(buffer);
+    let waker = Arc::new(Mutex::new(None));
+    let sender = FeedSender {
+        tx: Some(tx),
+        waker: waker.clone(),
+    };
+    let feed = Feed {
+        rx,
+        next: None,
+        done: false,
+        waker,
+    };
+    (sender, feed)
+}
+
+/// Producer side of a [`Feed`]. Dropping it ends the body.
+pub struct FeedSender {
+    tx: Option<SyncSender<Vec<u8>>>,
+    waker: Arc<Mutex<Option<Arc<Waker>>>>,
+}
+
+impl FeedSender {
+    /// Send the next chunk, blocking while the buffer is full. Fails with
+    /// the chunk once the response is gone.
+    pub fn send(&self, chunk: Vec<u8>) -> Result<(), Vec<u8>> {
+        let Some(tx) = &self.tx else {
+            return Err(chunk);
+        };
+        tx.send(chunk).map_err(|e| e.0)?;
+        self.wake();
+        Ok(())
+    }
+
+    fn wake(&self) {
+        if let Some(waker) = &*self.waker.lock().unwrap_or_else(|e| e.into_inner()) {
+            waker.wake();
+        }
+    }
+}
+
+impl Drop for FeedSender {
+    fn drop(&mut self) {
+        self.tx = None;
+        self.wake();
+    }
+}
+
+/// A streamed response body filled by a [`FeedSender`] on another thread.
+pub struct Feed {
+    rx: Receiver<Vec<u8>>,
+    /// Taken off the channel by `is_ready`, not yet sent.
+    next: Option<Vec<u8>>,
+    /// The sender is gone and every chunk has been taken.
+    done: bool,
+    /// Woken by the sender; set by the worker holding the connection.
+    waker: Arc<Mutex<Option<Arc<Waker>>>>,
+}
+
+impl Feed {
+    /// Answer once the first chunk arrives. `respond` gets the feed to
+    /// stream as the body, or `None` if the sender hung up without sending
+    /// anything. If a chunk is already there it runs right away; otherwise
+    /// the returned response parks the connection until one is.
+    pub fn respond(
+        self,
+        respond: impl FnOnce(Option<Feed>) -> Response + Send + 'static,
+    ) -> Response {
+        let mut parked = Parked {
+            wait: Wait::Feed {
+                feed: self,
+                respond: Box::new(respond),
+            },
+        };
+        if parked.is_ready(Instant::now()) {
+            return parked.resolve();
+        }
+        park(parked)
+    }
+
+    /// The next chunk, `Ready(None)` once the body is complete, or
+    /// `Pending` if the sender has not caught up.
+    pub(crate) fn poll(&mut self) -> Poll<Option<Vec<u8>>> {
+        if let Some(chunk) = self.next.take() {
+            return Poll::Ready(Some(chunk));
+        }
+        if self.done {
+            return Poll::Ready(None);
+        }
+        match self.rx.try_recv() {
+            Ok(chunk) => Poll::Ready(Some(chunk)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => {
+                self.done = true;
+                Poll::Ready(None)
+            }
+        }
+    }
+
+    /// Whether [`poll`](Self::poll) would not be `Pending`.
+    pub(crate) fn is_ready(&mut self) -> bool {
+        match self.poll() {
+            Poll::Ready(Some(chunk)) => {
+                self.next = Some(chunk);
+                true
+            }
+            Poll::Ready(None) => true,
+            Poll::Pending => false,
+        }
+    }
+
+    /// Have the sender wake `waker` after every chunk and when it hangs up.
+    pub(crate) fn subscribe(&self, waker: &Arc<Waker>) {
+        *self.waker.lock().unwrap_or_else(|e| e.into_inner()) = Some(waker.clone());
+    }
+}
+
+/// Blocks for each chunk. For callers outside the event loop, such as the
+/// test client.
+impl Iterator for Feed {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if let Some(chunk) = self.next.take() {
+            return Some(chunk);
+        }
+        if self.done {
+            return None;
+        }
+        let chunk = self.rx.recv().ok();
+        self.done = chunk.is_none();
+        chunk
+    }
+}
+
+/// Wakes a worker's event loop from another thread: a non-blocking pipe
+/// whose read end the loop watches.
+pub(crate) struct Waker {
+    read_fd: i32,
+    write_fd: i32,
+    /// A byte is in the pipe and the loop has not drained it yet.
+    pending: AtomicBool,
+}
+
+impl Waker {
+    pub(crate) fn new() -> std::io::Result<Arc<Self>> {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let waker = Self {
+            read_fd: fds[0],
+            write_fd: fds[1],
+            pending: AtomicBool::new(false),
+        };
+        for fd in fds {
+            unsafe {
+                let flags = libc::fcntl(fd, libc::F_GETFL);
+                libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK);
+                libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+            }
+        }
+        Ok(Arc::new(waker))
+    }
+
+    /// The descriptor that turns readable on [`wake`](Self::wake).
+    pub(crate) fn fd(&self) -> i32 {
+        self.read_fd
+    }
+
+    pub(crate) fn wake(&self) {
+        if !self.pending.swap(true, Ordering::AcqRel) {
+            unsafe {
+                libc::write(self.write_fd, [1u8].as_ptr() as *const libc::c_void, 1);
+            }
+        }
+    }
+
+    /// Empty the pipe. Wakes that arrive from here on are seen again.
+    pub(crate) fn drain(&self) {
+        self.pending.store(false, Ordering::Release);
+        let mut buf = [0u8; 64];
+        while unsafe {
+            libc::read(
+                self.read_fd,
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+            )
+        } > 0
+        {}
+    }
+}
+
+impl Drop for Waker {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.read_fd);
+            libc::close(self.write_fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn test_wait_since_returns_immediately_when_behind() {
+        let event = Event::new();
+        event.notify();
+        assert_eq!(event.wait_since(0, Duration::from_secs(1)), Some(1));
+    }
+
+    #[test]
+    fn test_wait_times_out() {
+        let event = Event::new();
+        let start = Instant::now();
+        assert!(!wait_for(&event, Duration::from_millis(20)));
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn test_wait_wakes_on_notify() {
+        let event = Arc::new(Event::new());
+        let producer = {
+            let event = event.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                event.notify()
+            })
+        };
+        assert_eq!(event.wait_since(0, Duration::from_secs(5)), Some(1));
+        assert_eq!(producer.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_respond_runs_at_once_when_behind() {
+        static EVENT: Event = Event::new();
+        EVENT.notify();
+        let res = respond(&EVENT, 0, Duration::from_secs(5), |v| {
+            Response::text(format!("{v:?}"))
+        });
+        assert_eq!(res.body.as_bytes(), b"Some(1)");
+    }
+
+    #[test]
+    fn test_parked_resolves_on_notify_or_deadline() {
+        static EVENT: Event = Event::new();
+        let mut res = respond(&EVENT, 0, Duration::from_millis(50), |v| {
+            Response::text(format!("{v:?}"))
+        });
+        let mut parked = res.take_parked().expect("parked");
+        assert!(!parked.is_ready(Instant::now()));
+        let deadline = parked.deadline().expect("deadline");
+        assert!(parked.is_ready(deadline));
+        EVENT.notify();
+        assert!(parked.is_ready(Instant::now()));
+        assert_eq!(parked.resolve().body.as_bytes(), b"Some(1)");
+
+        let mut res = respond(&EVENT, 1, Duration::from_millis(10), |v| {
+            Response::text(format!("{v:?}"))
+        });
+        let parked = res.take_parked().expect("parked");
+        assert_eq!(parked.wait().body.as_bytes(), b"None");
+    }
+
+    #[test]
+    fn test_feed_parks_until_first_chunk_or_hang_up() {
+        let (tx, rx) = feed(2);
+        let mut res = rx.respond(|feed| feed.map_or_else(Response::server_error, Response::feed));
+        let mut parked = res.take_parked().expect("parked");
+        assert_eq!(parked.deadline(), None);
+        assert!(!parked.is_ready(Instant::now()));
+
+        let waker = Waker::new().unwrap();
+        parked.subscribe(&waker);
+        tx.send(b"a".to_vec()).unwrap();
+        let mut buf = [0u8; 8];
+        let n = unsafe { libc::read(waker.fd(), buf.as_mut_ptr() as *mut libc::c_void, 8) };
+        assert_eq!(n, 1);
+        assert!(parked.is_ready(Instant::now()));
+
+        let Body::Feed(mut body) = parked.resolve().body else {
+            panic!("expected a fed body");
+        };
+        assert_eq!(body.poll(), Poll::Ready(Some(b"a".to_vec())));
+        assert_eq!(body.poll(), Poll::Pending);
+        drop(tx);
+        assert_eq!(body.poll(), Poll::Ready(None));
+
+        // Hanging up before the first chunk is an error.
+        let (tx, rx) = feed(2);
+        drop(tx);
+        let res = rx.respond(|feed| feed.map_or_else(Response::server_error, Response::feed));
+        assert_eq!(res.status, 500);
+    }
+
+    #[test]
+    fn test_notify_wakes_subscribed_waker_once() {
+        static EVENT: Event = Event::new();
+        let waker = Waker::new().unwrap();
+        EVENT.subscribe(&waker);
+        EVENT.subscribe(&waker);
+        EVENT.notify();
+
+        let mut buf = [0u8; 8];
+        let n = unsafe { libc::read(waker.fd(), buf.as_mut_ptr() as *mut libc::c_void, 8) };
+        assert_eq!(n, 1);
+        waker.drain();
+
+        // Notifying drops the subscription.
+        EVENT.notify();
+        let n = unsafe { libc::read(waker.fd(), buf.as_mut_ptr() as *mut libc::c_void, 8) };
+        assert_eq!(n, -1);
+    }
+}
@@ -0,0 +1,1794 @@
+// src/mail.rs
+//! Outbound email (`orm` feature).
+//!
+//! Messages are rows in the `chopin_mail` table. [`queue`] stores one and
+//! returns its id; a sender thread started by [`MailModule`] claims due rows
+//! with `FOR UPDATE SKIP LOCKED` and hands them to a [`MailTransport`]. A
+//! request that queues mail therefore never waits on an SMTP server, and a
+//! message queued inside a rolled-back transaction is never sent.
+//!
+//! ```rust,ignore
+//! mail::register_template(
+//!     "welcome",
+//!     "en",
+//!     MailTemplate::new("Welcome, {{name}}", "Hi {{name}}, thanks for joining.")?
+//!         .html(include_str!("../mail/welcome.en.html"))?,
+//! )?;
+//! mail::register_template("welcome", "pt", MailTemplate::new("Bem-vindo, {{name}}", "…")?)?;
+//!
+//! Chopin::new()
+//!     .mount_module(
+//!         MailModule::new(SmtpTransport::new("127.0.0.1:25"), "Shop <hello@shop.example>")
+//!             .rate_limit(5, Duration::from_secs(60 * 60))
+//!             .bounce_webhook("/hooks/mail", &env::var("MAIL_WEBHOOK_SECRET")?),
+//!     )
+//!     .serve("0.0.0.0:8080")?;
+//!
+//! mail::queue_template("welcome", &user.locale, &user.email, &json!({ "name": user.name }))?;
+//! ```
+//!
+//! ## Templates
+//!
+//! [`MailTemplate`] understands the common Handlebars subset: `{{name}}`
+//! (HTML-escaped in the HTML body), `{{{name}}}` (raw), dotted paths,
+//! `{{#if}}`, `{{#unless}}` and `{{#each}}` with `{{else}}`, `this`,
+//! `@index`, `../` and `{{! comments }}`. Templates are parsed when they are
+//! built, so a typo fails at startup rather than on the first send.
+//!
+//! MJML is a build step, not a runtime one: compile it with
+//! `mjml welcome.mjml -o welcome.html` and register the output, which may
+//! use the Handlebars tags above.
+//!
+//! Each template is registered per locale. A lookup for `pt-BR` tries
+//! `pt-BR`, then `pt`, then the module's default locale (`en` unless
+//! [`MailModule::default_locale`] says otherwise).
+//!
+//! ## Delivery
+//!
+//! A failed send is retried with exponential backoff until
+//! [`MailModule::max_attempts`] is used up, then the row stays `failed`.
+//! A recipient the server rejects outright ([`MailError::Rejected`]) is
+//! failed at once and added to the suppression list.
+//!
+//! [`MailModule::rate_limit`] caps how many messages one recipient gets in a
+//! window. A message over the cap is not dropped: it waits until the oldest
+//! send in the window ages out. Two senders may both pass the check for the
+//! same recipient at the same moment, so the cap can be exceeded by at most
+//! the number of sender threads.
+//!
+//! ## Suppressions and bounces
+//!
+//! Mail to an address on the [`Suppressions`] list is stored as `suppressed`
+//! and never sent. Addresses land there from rejections, from
+//! [`Suppressions::add`], and from the bounce webhook, which accepts the
+//! notifications of Amazon SES (through SNS), SendGrid, Postmark and
+//! Mailgun, as well as a plain `{"email": "..", "type": "bounce"}`. Only
+//! hard bounces and complaints suppress; soft bounces are left to retries.
+//! The webhook wants its secret as `?token=` or in `X-Webhook-Token`.
+use crate::db;
+use crate::error::{ChopinError, ChopinResult};
+use crate::extract::Query;
+use crate::form::{constant_time_eq, escape_html};
+use crate::http::{Context, Response};
+use crate::longpoll::Event;
+use crate::module::ChopinModule;
+use crate::router::Router;
+use crate::websocket::base64_encode;
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, SystemTime};
+
+/// Bumped by every [`queue`] so an idle sender in this process starts at once
+/// instead of waiting for its next poll.
+pub static QUEUED: Event = Event::new();
+
+// ─── Messages ────────────────────────────────────────────────────────────────
+
+/// One message to one recipient.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Email {
+    /// `user@example.com` or `Name <user@example.com>`.
+    pub to: String,
+    /// Defaults to the module's sender when `None`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reply_to: Option<String>,
+    pub subject: String,
+    #[serde(default)]
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub html: Option<String>,
+    /// Extra headers, such as `List-Unsubscribe`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+}
+
+impl Email {
+    pub fn new(to: impl Into<String>, subject: impl Into<String>) -> Self {
+        Self {
+            to: to.into(),
+            from: None,
+            reply_to: None,
+            subject: subject.into(),
+            text: String::new(),
+            html: None,
+            headers: Vec::new(),
+        }
+    }
+
+    pub fn text(mut self, text: impl Into<String>) -> Self {
+        self.text = text.into();
+        self
+    }
+
+    pub fn html(mut self, html: impl Into<String>) -> Self {
+        self.html = Some(html.into());
+        self
+    }
+
+    pub fn from(mut self, from: impl Into<String>) -> Self {
+        self.from = Some(from.into());
+        self
+    }
+
+    pub fn reply_to(mut self, reply_to: impl Into<String>) -> Self {
+        self.reply_to = Some(reply_to.into());
+        self
+    }
+
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// The bare address of the recipient, lowercased. Rate limits and
+    /// suppressions are keyed by it.
+    pub fn recipient(&self) -> String {
+        address_of(&self.to).to_ascii_lowercase()
+    }
+
+    /// Reject messages that would be unsafe or impossible to send: a
+    /// recipient without an `@`, or a line break in any header, which would
+    /// let user input add headers of its own.
+    fn validate(&self) -> ChopinResult<()> {
+        let address = address_of(&self.to);
+        if address.is_empty() || !address.contains('@') || address.contains(char::is_whitespace) {
+            return Err(ChopinError::Other(format!(
+                "mail: invalid recipient {:?}",
+                self.to
+            )));
+        }
+        let headers = [Some(&self.to), self.from.as_ref(), self.reply_to.as_ref()]
+            .into_iter()
+            .flatten()
+            .chain(std::iter::once(&self.subject))
+            .chain(self.headers.iter().flat_map(|(k, v)| [k, v]));
+        for value in headers {
+            if value.contains(['\r', '\n']) {
+                return Err(ChopinError::Other(
+                    "mail: line break in a header".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The address inside `Name <address>`, or the whole string trimmed.
+fn address_of(mailbox: &str) -> &str {
+    match (mailbox.rfind('<'), mailbox.rfind('>')) {
+        (Some(open), Some(close)) if open < close => mailbox[open + 1..close].trim(),
+        _ => mailbox.trim(),
+    }
+}
+
+// ─── Transports ──────────────────────────────────────────────────────────────
+
+/// Why a transport could not deliver a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MailError {
+    /// Worth retrying later: the server was unreachable or answered 4xx.
+    Temporary(String),
+    /// The recipient was refused for good (a 5xx reply to `RCPT TO`).
+    /// The message fails and the address is suppressed.
+    Rejected(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::Temporary(e) => write!(f, "temporary failure: {e}"),
+            MailError::Rejected(e) => write!(f, "rejected: {e}"),
+        }
+    }
+}
+
+/// Delivers one message. `email.from` is always set by the time a transport
+/// sees it.
+pub trait MailTransport: Send + Sync {
+    fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Keeps sent messages in memory instead of delivering them, for tests.
+/// Clones share the same outbox.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryTransport {
+    sent: Arc<Mutex<Vec<Email>>>,
+    rejected: Arc<Mutex<Vec<String>>>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer every later send to `address` with [`MailError::Rejected`].
+    pub fn reject(&self, address: &str) {
+        self.rejected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(address.to_ascii_lowercase());
+    }
+
+    /// Everything sent so far.
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Everything sent so far, emptying the outbox.
+    pub fn take(&self) -> Vec<Email> {
+        std::mem::take(&mut *self.sent.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+impl MailTransport for MemoryTransport {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let recipient = email.recipient();
+        if self
+            .rejected
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&recipient)
+        {
+            return Err(MailError::Rejected(format!(
+                "550 {recipient}: no such user"
+            )));
+        }
+        self.sent
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(email.clone());
+        Ok(())
+    }
+}
+
+/// Plain SMTP to a relay, one connection per message.
+///
+/// There is no TLS: point it at a relay on the same host or network (a
+/// local Postfix, an SES or Postmark sidecar), never at a server across the
+/// internet, since `AUTH PLAIN` credentials travel in the clear.
+#[derive(Debug, Clone)]
+pub struct SmtpTransport {
+    addr: String,
+    helo: String,
+    credentials: Option<(String, String)>,
+    timeout: Duration,
+}
+
+impl SmtpTransport {
+    pub fn new(addr: impl Into<String>) -> Self {
+        Self {
+            addr: addr.into(),
+            helo: "localhost".to_string(),
+            credentials: None,
+            timeout: Duration::from_secs(30),
+        }
+    }
+
+    /// The name this client gives in `EHLO`.
+    pub fn helo(mut self, name: impl Into<String>) -> Self {
+        self.helo = name.into();
+        self
+    }
+
+    /// Log in with `AUTH PLAIN` after `EHLO`.
+    pub fn credentials(mut self, user: impl Into<String>, password: impl Into<String>) -> Self {
+        self.credentials = Some((user.into(), password.into()));
+        self
+    }
+
+    /// Connect, read and write timeout. Defaults to 30 seconds.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> std::io::Result<SmtpConnection> {
+        let addr = self.addr.to_socket_addrs()?.next().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "no address for relay")
+        })?;
+        let stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+        Ok(SmtpConnection {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+        })
+    }
+}
+
+impl MailTransport for SmtpTransport {
+    fn send(&self, email: &Email) -> Result<(), MailError> {
+        let from = email.from.as_deref().unwrap_or_default();
+        let message = to_mime(email, from, SystemTime::now());
+        let mut conn = self.connect().map_err(temporary)?;
+        conn.expect(None, 220)?;
+        conn.expect(Some(&format!("EHLO {}", self.helo)), 250)?;
+        if let Some((user, password)) = &self.credentials {
+            let token = base64_encode(format!("\0{user}\0{password}").as_bytes());
+            conn.expect(Some(&format!("AUTH PLAIN {token}")), 235)?;
+        }
+        conn.expect(Some(&format!("MAIL FROM:<{}>", address_of(from))), 250)?;
+        let (code, reply) = conn.command(Some(&format!("RCPT TO:<{}>", address_of(&email.to))))?;
+        match code {
+            250 | 251 => {}
+            500.. => return Err(MailError::Rejected(reply)),
+            _ => return Err(MailError::Temporary(reply)),
+        }
+        conn.expect(Some("DATA"), 354)?;
+        conn.expect(Some(&format!("{}\r\n.", dot_stuff(&message))), 250)?;
+        let _ = conn.command(Some("QUIT"));
+        Ok(())
+    }
+}
+
+struct SmtpConnection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl SmtpConnection {
+    /// Send `line` (if any) and read the reply, joining continuation lines.
+    fn command(&mut self, line: Option<&str>) -> Result<(u16, String), MailError> {
+        if let Some(line) = line {
+            self.writer
+                .write_all(format!("{line}\r\n").as_bytes())
+                .map_err(temporary)?;
+        }
+        let mut reply = String::new();
+        loop {
+            let mut buf = String::new();
+            if self.reader.read_line(&mut buf).map_err(temporary)? == 0 {
+                return Err(MailError::Temporary("connection closed".to_string()));
+            }
+            let buf = buf.trim_end();
+            let code = buf
+                .get(..3)
+                .and_then(|c| c.parse::<u16>().ok())
+                .ok_or_else(|| MailError::Temporary(format!("bad reply {buf:?}")))?;
+            if !reply.is_empty() {
+                reply.push(' ');
+            }
+            reply.push_str(buf);
+            if buf.as_bytes().get(3) != Some(&b'-') {
+                return Ok((code, reply));
+            }
+        }
+    }
+
+    fn expect(&mut self, line: Option<&str>, want: u16) -> Result<(), MailError> {
+        let (code, reply) = self.command(line)?;
+        if code == want {
+            Ok(())
+        } else {
+            Err(MailError::Temporary(reply))
+        }
+    }
+}
+
+fn temporary(e: std::io::Error) -> MailError {
+    MailError::Temporary(e.to_string())
+}
+
+/// Double leading dots and normalise line endings to CRLF.
+fn dot_stuff(message: &str) -> String {
+    let mut out = String::with_capacity(message.len() + 16);
+    for (i, line) in message.split('\n').enumerate() {
+        if i > 0 {
+            out.push_str("\r\n");
+        }
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        if line.starts_with('.') {
+            out.push('.');
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+/// RFC 5322 message with base64 bodies, as `multipart/alternative` when
+/// there is an HTML part.
+fn to_mime(email: &Email, from: &str, now: SystemTime) -> String {
+    let id = crate::import::new_id();
+    let domain = address_of(from)
+        .rsplit_once('@')
+        .map_or("localhost", |(_, d)| d);
+    let mut out = String::new();
+    let mut header = |name: &str, value: &str| {
+        out.push_str(name);
+        out.push_str(": ");
+        out.push_str(value);
+        out.push_str("\r\n");
+    };
+    header("From", &encode_mailbox(from));
+    header("To", &encode_mailbox(&email.to));
+    if let Some(reply_to) = &email.reply_to {
+        header("Reply-To", &encode_mailbox(reply_to));
+    }
+    header("Subject", &encode_word(&email.subject));
+    header("Date", &httpdate::fmt_http_date(now));
+    header("Message-ID", &format!("<{id}@{domain}>"));
+    for (name, value) in &email.headers {
+        header(name, value);
+    }
+    header("MIME-Version", "1.0");
+    match &email.html {
+        None => {
+            push_part(&mut out, "text/plain", &email.text);
+        }
+        Some(html) => {
+            let boundary = format!("=_chopin_{id}");
+            out.push_str(&format!(
+                "Content-Type: multipart/alternative; boundary=\"{boundary}\"\r\n\r\n"
+            ));
+            for (kind, body) in [("text/plain", &email.text), ("text/html", html)] {
+                out.push_str(&format!("--{boundary}\r\n"));
+                push_part(&mut out, kind, body);
+            }
+            out.push_str(&format!("--{boundary}--\r\n"));
+        }
+    }
+    out
+}
+
+fn push_part(out: &mut String, kind: &str, body: &str) {
+    out.push_str(&format!(
+        "Content-Type: {kind}; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n"
+    ));
+    let encoded = base64_encode(body.as_bytes());
+    for line in encoded.as_bytes().chunks(76) {
+        out.push_str(std::str::from_utf8(line).unwrap_or_default());
+        out.push_str("\r\n");
+    }
+}
+
+/// RFC 2047 encoded word for non-ASCII header text.
+fn encode_word(text: &str) -> String {
+    if text.is_ascii() {
+        text.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", base64_encode(text.as_bytes()))
+    }
+}
+
+/// Encode the display name of `Name <address>`; the address stays as is.
+fn encode_mailbox(mailbox: &str) -> String {
+    match mailbox.rfind('<') {
+        Some(open) if open > 0 => {
+            let name = mailbox[..open].trim().trim_matches('"');
+            format!("{} {}", encode_word(name), &mailbox[open..])
+        }
+        _ => mailbox.to_string(),
+    }
+}
+
+// ─── Templates ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Var {
+        path: String,
+        raw: bool,
+    },
+    If {
+        path: String,
+        negate: bool,
+        then: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+    Each {
+        path: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+enum End {
+    Eof,
+    Else,
+    Close(String),
+}
+
+fn parse(src: &str) -> Result<Vec<Node>, String> {
+    let mut rest = src;
+    match parse_nodes(&mut rest)? {
+        (nodes, End::Eof) => Ok(nodes),
+        (_, End::Else) => Err("{{else}} outside a block".to_string()),
+        (_, End::Close(name)) => Err(format!("{{{{/{name}}}}} without an opening block")),
+    }
+}
+
+fn parse_nodes(rest: &mut &str) -> Result<(Vec<Node>, End), String> {
+    let mut nodes = Vec::new();
+    loop {
+        let Some(start) = rest.find("{{") else {
+            if !rest.is_empty() {
+                nodes.push(Node::Text(rest.to_string()));
+            }
+            *rest = "";
+            return Ok((nodes, End::Eof));
+        };
+        if start > 0 {
+            nodes.push(Node::Text(rest[..start].to_string()));
+        }
+        let tag = &rest[start..];
+        if let Some(inner) = tag.strip_prefix("{{{") {
+            let close = inner.find("}}}").ok_or("unclosed {{{")?;
+            nodes.push(Node::Var {
+                path: inner[..close].trim().to_string(),
+                raw: true,
+            });
+            *rest = &inner[close + 3..];
+            continue;
+        }
+        let inner = &tag[2..];
+        if let Some(comment) = inner.strip_prefix("!--") {
+            let close = comment.find("--}}").ok_or("unclosed {{!-- comment")?;
+            *rest = &comment[close + 4..];
+            continue;
+        }
+        let close = inner.find("}}").ok_or("unclosed {{")?;
+        let tag = inner[..close].trim();
+        *rest = &inner[close + 2..];
+        if tag.starts_with('!') {
+            continue;
+        }
+        if tag == "else" {
+            return Ok((nodes, End::Else));
+        }
+        if let Some(name) = tag.strip_prefix('/') {
+            return Ok((nodes, End::Close(name.trim().to_string())));
+        }
+        let Some(block) = tag.strip_prefix('#') else {
+            if tag.is_empty() {
+                return Err("empty {{}}".to_string());
+            }
+            nodes.push(Node::Var {
+                path: tag.to_string(),
+                raw: false,
+            });
+            continue;
+        };
+        let (helper, path) = block
+            .split_once(char::is_whitespace)
+            .map_or((block, ""), |(h, p)| (h, p.trim()));
+        if path.is_empty() {
+            return Err(format!("#{helper} needs an argument"));
+        }
+        let (body, end) = parse_nodes(rest)?;
+        let (otherwise, end) = match end {
+            End::Else => parse_nodes(rest)?,
+            end => (Vec::new(), end),
+        };
+        match end {
+            End::Close(name) if name == helper => {}
+            _ => return Err(format!("unclosed #{helper} block")),
+        }
+        let path = path.to_string();
+        nodes.push(match helper {
+            "if" | "unless" => Node::If {
+                path,
+                negate: helper == "unless",
+                then: body,
+                otherwise,
+            },
+            "each" => Node::Each {
+                path,
+                body,
+                otherwise,
+            },
+            _ => return Err(format!("unknown block helper #{helper}")),
+        });
+    }
+}
+
+struct Scope {
+    value: Value,
+    index: Option<usize>,
+}
+
+fn lookup(scopes: &[Scope], path: &str) -> Value {
+    let mut depth = scopes.len() - 1;
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("../") {
+        depth = depth.saturating_sub(1);
+        path = rest;
+    }
+    let scope = &scopes[depth];
+    match path {
+        "@index" => return scope.index.map_or(Value::Null, Value::from),
+        "this" | "." => return scope.value.clone(),
+        _ => {}
+    }
+    let path = path.strip_prefix("this.").unwrap_or(path);
+    // Names not found in an `#each` item fall back to the enclosing data,
+    // so `{{shop}}` still works inside a loop over line items.
+    scopes[..=depth]
+        .iter()
+        .rev()
+        .find_map(|scope| {
+            path.split('.').try_fold(&scope.value, |v, seg| match v {
+                Value::Object(map) => map.get(seg),
+                Value::Array(items) => seg.parse::<usize>().ok().and_then(|i| items.get(i)),
+                _ => None,
+            })
+        })
+        .cloned()
+        .unwrap_or(Value::Null)
+}
+
+fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+fn render_nodes(nodes: &[Node], scopes: &mut Vec<Scope>, html: bool, out: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Var { path, raw } => {
+                let text = match lookup(scopes, path) {
+                    Value::Null => continue,
+                    Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                if html && !raw {
+                    out.push_str(&escape_html(&text));
+                } else {
+                    out.push_str(&text);
+                }
+            }
+            Node::If {
+                path,
+                negate,
+                then,
+                otherwise,
+            } => {
+                let branch = if truthy(&lookup(scopes, path)) != *negate {
+                    then
+                } else {
+                    otherwise
+                };
+                render_nodes(branch, scopes, html, out);
+            }
+            Node::Each {
+                path,
+                body,
+                otherwise,
+            } => {
+                let items: Vec<Value> = match lookup(scopes, path) {
+                    Value::Array(items) => items,
+                    Value::Object(map) => map.into_iter().map(|(_, v)| v).collect(),
+                    _ => Vec::new(),
+                };
+                if items.is_empty() {
+                    render_nodes(otherwise, scopes, html, out);
+                }
+                for (i, value) in items.into_iter().enumerate() {
+                    scopes.push(Scope {
+                        value,
+                        index: Some(i),
+                    });
+                    render_nodes(body, scopes, html, out);
+                    scopes.pop();
+                }
+            }
+        }
+    }
+}
+
+fn render(nodes: &[Node], data: &Value, html: bool) -> String {
+    let mut out = String::new();
+    let mut scopes = vec![Scope {
+        value: data.clone(),
+        index: None,
+    }];
+    render_nodes(nodes, &mut scopes, html, &mut out);
+    out
+}
+
+/// A subject, text body and optional HTML body, each a Handlebars template.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailTemplate {
+    subject: Vec<Node>,
+    text: Vec<Node>,
+    html: Option<Vec<Node>>,
+}
+
+impl MailTemplate {
+    pub fn new(subject: &str, text: &str) -> ChopinResult<Self> {
+        Ok(Self {
+            subject: compile("subject", subject)?,
+            text: compile("text", text)?,
+            html: None,
+        })
+    }
+
+    /// Add an HTML body, for example the output of `mjml`.
+    pub fn html(mut self, html: &str) -> ChopinResult<Self> {
+        self.html = Some(compile("html", html)?);
+        Ok(self)
+    }
+
+    /// Render a message to `to`. Line breaks in the subject become spaces.
+    pub fn render(&self, to: &str, data: &Value) -> Email {
+        let subject = render(&self.subject, data, false);
+        Email {
+            subject: subject.split(['\r', '\n']).collect::<Vec<_>>().join(" "),
+            text: render(&self.text, data, false),
+            html: self.html.as_ref().map(|html| render(html, data, true)),
+            ..Email::new(to, "")
+        }
+    }
+}
+
+fn compile(part: &str, src: &str) -> ChopinResult<Vec<Node>> {
+    parse(src).map_err(|e| ChopinError::Other(format!("mail template {part}: {e}")))
+}
+
+static TEMPLATES: RwLock<BTreeMap<(String, String), MailTemplate>> = RwLock::new(BTreeMap::new());
+
+fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_ascii_lowercase()
+}
+
+/// `pt-br` → `["pt-br", "pt", default]`, without duplicates.
+fn locale_chain(locale: &str, default: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut locale = normalize_locale(locale);
+    while !locale.is_empty() {
+        chain.push(locale.clone());
+        match locale.rfind('-') {
+            Some(i) => locale.truncate(i),
+            None => break,
+        }
+    }
+    let default = normalize_locale(default);
+    if !chain.contains(&default) {
+        chain.push(default);
+    }
+    chain
+}
+
+/// Register `template` as `name` for `locale` (`en`, `pt-BR`, …), replacing
+/// any earlier one.
+pub fn register_template(name: &str, locale: &str, template: MailTemplate) -> ChopinResult<()> {
+    TEMPLATES
+        .write()
+        .map_err(|_| ChopinError::Other("mail templates lock poisoned".to_string()))?
+        .insert((name.to_string(), normalize_locale(locale)), template);
+    Ok(())
+}
+
+/// Render template `name` in the closest registered locale.
+pub fn render_template(name: &str, locale: &str, to: &str, data: &Value) -> ChopinResult<Email> {
+    let default = MAILER
+        .read()
+        .ok()
+        .and_then(|m| m.as_ref().map(|m| m.default_locale.clone()))
+        .unwrap_or_else(|| "en".to_string());
+    let templates = TEMPLATES
+        .read()
+        .map_err(|_| ChopinError::Other("mail templates lock poisoned".to_string()))?;
+    locale_chain(locale, &default)
+        .into_iter()
+        .find_map(|locale| templates.get(&(name.to_string(), locale)))
+        .map(|template| template.render(to, data))
+        .ok_or_else(|| ChopinError::Other(format!("mail template {name:?} is not registered")))
+}
+
+// ─── Queue ───────────────────────────────────────────────────────────────────
+
+/// Where a queued message is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MailState {
+    Pending,
+    Sending,
+    Sent,
+    Failed,
+    Suppressed,
+}
+
+impl MailState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MailState::Pending => "pending",
+            MailState::Sending => "sending",
+            MailState::Sent => "sent",
+            MailState::Failed => "failed",
+            MailState::Suppressed => "suppressed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "pending" => MailState::Pending,
+            "sending" => MailState::Sending,
+            "sent" => MailState::Sent,
+            "failed" => MailState::Failed,
+            "suppressed" => MailState::Suppressed,
+            _ => return None,
+        })
+    }
+}
+
+/// A queued message's delivery record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MailStatus {
+    pub id: i64,
+    pub recipient: String,
+    pub state: MailState,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub send_after: i64,
+    pub sent_at: Option<i64>,
+}
+
+/// A row claimed by a sender.
+#[derive(Debug, Clone)]
+pub struct ClaimedMail {
+    pub id: i64,
+    pub recipient: String,
+    pub message: String,
+    /// This attempt's number, starting at 1.
+    pub attempts: i32,
+}
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_mail (
+        id BIGSERIAL PRIMARY KEY,
+        recipient TEXT NOT NULL,
+        message TEXT NOT NULL,
+        state TEXT NOT NULL DEFAULT 'pending',
+        attempts INT NOT NULL DEFAULT 0,
+        last_error TEXT,
+        send_after TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        claimed_at TIMESTAMPTZ,
+        sent_at TIMESTAMPTZ
+    )
+"#;
+
+/// The sender's due queue, stale claims, and the per-recipient window.
+const INDEX_SQL: [&str; 3] = [
+    "CREATE INDEX IF NOT EXISTS chopin_mail_due_idx \
+     ON chopin_mail (send_after) WHERE state = 'pending'",
+    "CREATE INDEX IF NOT EXISTS chopin_mail_sending_idx \
+     ON chopin_mail (claimed_at) WHERE state = 'sending'",
+    "CREATE INDEX IF NOT EXISTS chopin_mail_sent_idx \
+     ON chopin_mail (recipient, sent_at) WHERE state = 'sent'",
+];
+
+const CREATE_SUPPRESSIONS_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_mail_suppressions (
+        email TEXT PRIMARY KEY,
+        reason TEXT NOT NULL,
+        detail TEXT,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )
+"#;
+
+const SELECT_COLUMNS: &str = "id, recipient, state, attempts, last_error, \
+     EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at, \
+     EXTRACT(EPOCH FROM send_after)::BIGINT AS send_after, \
+     EXTRACT(EPOCH FROM sent_at)::BIGINT AS sent_at";
+
+/// A claim older than this belongs to a sender that died mid-send; the
+/// message goes back to the queue, so it may be delivered twice.
+const STALE_CLAIM: &str = "10 minutes";
+
+/// Storage operations on `chopin_mail`.
+pub struct MailStore;
+
+impl MailStore {
+    /// Creates the queue and suppression tables and their indexes if they
+    /// do not exist.
+    pub fn ensure_tables(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        for sql in INDEX_SQL {
+            executor.execute(sql, &[])?;
+        }
+        executor.execute(CREATE_SUPPRESSIONS_SQL, &[])?;
+        Ok(())
+    }
+
+    /// Store a message, as `suppressed` if its recipient is on the list.
+    pub fn insert(executor: &mut dyn Executor, email: &Email) -> OrmResult<MailStatus> {
+        let recipient = email.recipient();
+        let message = serde_json::to_string(email)
+            .map_err(|e| chopin_orm::OrmError::Extraction(e.to_string()))?;
+        let sql = format!(
+            "INSERT INTO chopin_mail (recipient, message, state) \
+             VALUES ($1, $2, CASE WHEN EXISTS \
+                 (SELECT 1 FROM chopin_mail_suppressions WHERE email = $1) \
+                 THEN 'suppressed' ELSE 'pending' END) \
+             RETURNING {SELECT_COLUMNS}"
+        );
+        let rows = executor.query(&sql, &[&recipient, &message])?;
+        let row = rows.first().ok_or(chopin_orm::OrmError::RecordNotFound)?;
+        from_row(row)
+    }
+
+    pub fn get(executor: &mut dyn Executor, id: i64) -> OrmResult<Option<MailStatus>> {
+        let sql = format!("SELECT {SELECT_COLUMNS} FROM chopin_mail WHERE id = $1");
+        executor
+            .query(&sql, &[&id])?
+            .first()
+            .map(from_row)
+            .transpose()
+    }
+
+    /// Mark the message that has been due longest `sending` and return it,
+    /// skipping rows locked by another sender. Claims abandoned by a dead
+    /// sender are requeued first.
+    pub fn claim(executor: &mut dyn Executor) -> OrmResult<Option<ClaimedMail>> {
+        executor.execute(
+            &format!(
+                "UPDATE chopin_mail SET state = 'pending' \
+                 WHERE state = 'sending' AND claimed_at < NOW() - INTERVAL '{STALE_CLAIM}'"
+            ),
+            &[],
+        )?;
+        let rows = executor.query(
+            "UPDATE chopin_mail SET state = 'sending', claimed_at = NOW(), \
+                 attempts = attempts + 1 \
+             WHERE id = (SELECT id FROM chopin_mail \
+                 WHERE state = 'pending' AND send_after <= NOW() \
+                 ORDER BY send_after, id LIMIT 1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, recipient, message, attempts",
+            &[],
+        )?;
+        rows.first()
+            .map(|row| {
+                Ok(ClaimedMail {
+                    id: row.get_typed_by_name("id")?,
+                    recipient: row.get_typed_by_name("recipient")?,
+                    message: row.get_typed_by_name("message")?,
+                    attempts: row.get_typed_by_name("attempts")?,
+                })
+            })
+            .transpose()
+    }
+
+    /// Put a claimed message back without counting the attempt if its
+    /// recipient already had `max` messages sent within `per`. It waits
+    /// until the oldest of those ages out. Returns whether it was deferred.
+    pub fn defer_if_limited(
+        executor: &mut dyn Executor,
+        mail: &ClaimedMail,
+        max: u32,
+        per: Duration,
+    ) -> OrmResult<bool> {
+        let per_ms = per.as_millis() as i64;
+        let max = max as i64;
+        let deferred = executor.execute(
+            "WITH recent AS (SELECT sent_at FROM chopin_mail \
+                 WHERE recipient = $2 AND state = 'sent' \
+                 AND sent_at > NOW() - $3::BIGINT * INTERVAL '1 millisecond') \
+             UPDATE chopin_mail SET state = 'pending', attempts = attempts - 1, \
+                 send_after = (SELECT MIN(sent_at) FROM recent) \
+                     + $3::BIGINT * INTERVAL '1 millisecond' \
+             WHERE id = $1 AND (SELECT COUNT(*) FROM recent) >= $4",
+            &[&mail.id, &mail.recipient, &per_ms, &max],
+        )?;
+        Ok(deferred > 0)
+    }
+
+    pub fn mark_sent(executor: &mut dyn Executor, id: i64) -> OrmResult<()> {
+        executor.execute(
+            "UPDATE chopin_mail SET state = 'sent', sent_at = NOW(), last_error = NULL \
+             WHERE id = $1",
+            &[&id],
+        )?;
+        Ok(())
+    }
+
+    /// Settle a claimed message without sending it.
+    pub fn mark(
+        executor: &mut dyn Executor,
+        id: i64,
+        state: MailState,
+        error: Option<&str>,
+    ) -> OrmResult<()> {
+        executor.execute(
+            "UPDATE chopin_mail SET state = $2, last_error = $3 WHERE id = $1",
+            &[&id, &state.as_str(), &error],
+        )?;
+        Ok(())
+    }
+
+    /// Requeue a failed attempt to run after `delay`.
+    pub fn retry_later(
+        executor: &mut dyn Executor,
+        id: i64,
+        delay: Duration,
+        error: &str,
+    ) -> OrmResult<()> {
+        let delay_ms = delay.as_millis() as i64;
+        executor.execute(
+            "UPDATE chopin_mail SET state = 'pending', last_error = $3, \
+                 send_after = NOW() + $2::BIGINT * INTERVAL '1 millisecond' \
+             WHERE id = $1",
+            &[&id, &delay_ms, &error],
+        )?;
+        Ok(())
+    }
+}
+
+fn from_row(row: &chopin_orm::Row) -> OrmResult<MailStatus> {
+    let state: String = row.get_typed_by_name("state")?;
+    Ok(MailStatus {
+        id: row.get_typed_by_name("id")?,
+        recipient: row.get_typed_by_name("recipient")?,
+        state: MailState::parse(&state)
+            .ok_or_else(|| chopin_orm::OrmError::Extraction(format!("mail state {state:?}")))?,
+        attempts: row.get_typed_by_name("attempts")?,
+        last_error: row.get_typed_by_name("last_error")?,
+        created_at: row.get_typed_by_name("created_at")?,
+        send_after: row.get_typed_by_name("send_after")?,
+        sent_at: row.get_typed_by_name("sent_at")?,
+    })
+}
+
+/// Store `email` for the sender and return its id. Goes through
+/// [`db::with_db`], so it takes part in the request's transaction.
+pub fn queue(email: &Email) -> ChopinResult<i64> {
+    email.validate()?;
+    let status = db::with_db(|db| MailStore::insert(db, email))?;
+    if status.state == MailState::Pending {
+        QUEUED.notify();
+    }
+    Ok(status.id)
+}
+
+/// Render template `name` for `locale` and [`queue`] it to `to`.
+pub fn queue_template(name: &str, locale: &str, to: &str, data: &Value) -> ChopinResult<i64> {
+    queue(&render_template(name, locale, to, data)?)
+}
+
+pub fn status(id: i64) -> ChopinResult<Option<MailStatus>> {
+    db::with_db(|db| MailStore::get(db, id))
+}
+
+struct Mailer {
+    transport: Arc<dyn MailTransport>,
+    from: String,
+    rate_limit: Option<(u32, Duration)>,
+    max_attempts: u32,
+    backoff: Duration,
+    default_locale: String,
+    webhook_secret: Option<String>,
+}
+
+static MAILER: RwLock<Option<Arc<Mailer>>> = RwLock::new(None);
+
+fn mailer() -> Option<Arc<Mailer>> {
+    MAILER.read().ok().and_then(|m| m.clone())
+}
+
+/// Claim one due message and try to deliver it. Returns `false` when
+/// nothing was due. Sender threads call this in a loop; tests call it
+/// directly.
+pub fn send_next() -> ChopinResult<bool> {
+    let mailer = mailer()
+        .ok_or_else(|| ChopinError::Other("mail: MailModule is not mounted".to_string()))?;
+    let Some(claimed) = db::with_db(MailStore::claim)? else {
+        return Ok(false);
+    };
+    if db::with_db(|db| Suppressions::get(db, &claimed.recipient))?.is_some() {
+        db::with_db(|db| MailStore::mark(db, claimed.id, MailState::Suppressed, None))?;
+        return Ok(true);
+    }
+    if let Some((max, per)) = mailer.rate_limit
+        && db::with_db(|db| MailStore::defer_if_limited(db, &claimed, max, per))?
+    {
+        return Ok(true);
+    }
+    let mut email: Email = match serde_json::from_str(&claimed.message) {
+        Ok(email) => email,
+        Err(e) => {
+            let error = format!("unreadable message: {e}");
+            db::with_db(|db| MailStore::mark(db, claimed.id, MailState::Failed, Some(&error)))?;
+            return Ok(true);
+        }
+    };
+    if email.from.is_none() {
+        email.from = Some(mailer.from.clone());
+    }
+    match mailer.transport.send(&email) {
+        Ok(()) => db::with_db(|db| MailStore::mark_sent(db, claimed.id))?,
+        Err(MailError::Rejected(reason)) => db::with_db(|db| {
+            MailStore::mark(db, claimed.id, MailState::Failed, Some(&reason))?;
+            Suppressions::add(
+                db,
+                &claimed.recipient,
+                SuppressionReason::Rejected,
+                Some(&reason),
+            )
+            .map(drop)
+        })?,
+        Err(MailError::Temporary(reason)) => {
+            if claimed.attempts as u32 >= mailer.max_attempts {
+                db::with_db(|db| {
+                    MailStore::mark(db, claimed.id, MailState::Failed, Some(&reason))
+                })?;
+            } else {
+                let exp = (claimed.attempts.max(1) - 1).min(16) as u32;
+                let delay = mailer.backoff.saturating_mul(1 << exp);
+                db::with_db(|db| MailStore::retry_later(db, claimed.id, delay, &reason))?;
+            }
+        }
+    }
+    Ok(true)
+}
+
+// ─── Suppressions ────────────────────────────────────────────────────────────
+
+/// Why an address is suppressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuppressionReason {
+    /// A hard bounce reported by the provider.
+    Bounce,
+    /// The recipient marked a message as spam.
+    Complaint,
+    /// The relay refused the recipient at send time.
+    Rejected,
+    /// Added by the application, for example on unsubscribe.
+    Manual,
+}
+
+impl SuppressionReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SuppressionReason::Bounce => "bounce",
+            SuppressionReason::Complaint => "complaint",
+            SuppressionReason::Rejected => "rejected",
+            SuppressionReason::Manual => "manual",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Some(match s {
+            "bounce" => SuppressionReason::Bounce,
+            "complaint" => SuppressionReason::Complaint,
+            "rejected" => SuppressionReason::Rejected,
+            "manual" => SuppressionReason::Manual,
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Suppression {
+    pub email: String,
+    pub reason: SuppressionReason,
+    pub detail: Option<String>,
+    pub created_at: i64,
+}
+
+/// The `chopin_mail_suppressions` list. Addresses are compared lowercased.
+pub struct Suppressions;
+
+impl Suppressions {
+    /// Suppress `email`. Returns `false` if it already was, keeping the
+    /// first reason.
+    pub fn add(
+        executor: &mut dyn Executor,
+        email: &str,
+        reason: SuppressionReason,
+        detail: Option<&str>,
+    ) -> OrmResult<bool> {
+        let email = address_of(email).to_ascii_lowercase();
+        let added = executor.execute(
+            "INSERT INTO chopin_mail_suppressions (email, reason, detail) VALUES ($1, $2, $3) \
+             ON CONFLICT (email) DO NOTHING",
+            &[&email, &reason.as_str(), &detail],
+        )?;
+        Ok(added > 0)
+    }
+
+    /// Lift the suppression of `email`. Returns whether there was one.
+    pub fn remove(executor: &mut dyn Executor, email: &str) -> OrmResult<bool> {
+        let email = address_of(email).to_ascii_lowercase();
+        let removed = executor.execute(
+            "DELETE FROM chopin_mail_suppressions WHERE email = $1",
+            &[&email],
+        )?;
+        Ok(removed > 0)
+    }
+
+    pub fn get(executor: &mut dyn Executor, email: &str) -> OrmResult<Option<Suppression>> {
+        let email = address_of(email).to_ascii_lowercase();
+        let rows = executor.query(
+            "SELECT email, reason, detail, EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at \
+             FROM chopin_mail_suppressions WHERE email = $1",
+            &[&email],
+        )?;
+        rows.first().map(suppression_from_row).transpose()
+    }
+
+    /// The newest `limit` suppressions.
+    pub fn list(executor: &mut dyn Executor, limit: i64) -> OrmResult<Vec<Suppression>> {
+        executor
+            .query(
+                "SELECT email, reason, detail, \
+                     EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at \
+                 FROM chopin_mail_suppressions ORDER BY created_at DESC, email LIMIT $1",
+                &[&limit],
+            )?
+            .iter()
+            .map(suppression_from_row)
+            .collect()
+    }
+}
+
+fn suppression_from_row(row: &chopin_orm::Row) -> OrmResult<Suppression> {
+    let reason: String = row.get_typed_by_name("reason")?;
+    Ok(Suppression {
+        email: row.get_typed_by_name("email")?,
+        reason: SuppressionReason::parse(&reason).unwrap_or(SuppressionReason::Manual),
+        detail: row.get_typed_by_name("detail")?,
+        created_at: row.get_typed_by_name("created_at")?,
+    })
+}
+
+// ─── Bounce webhook ──────────────────────────────────────────────────────────
+
+/// An address a provider reported as undeliverable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bounce {
+    email: String,
+    reason: SuppressionReason,
+    detail: String,
+}
+
+/// Hard bounces and complaints in a provider notification. Soft bounces
+/// and other events yield nothing.
+fn parse_bounces(body: &Value) -> Vec<Bounce> {
+    let mut bounces = provider_bounces(body);
+    bounces.retain(|b| b.email.contains('@'));
+    bounces
+}
+
+fn provider_bounces(body: &Value) -> Vec<Bounce> {
+    fn str_of<'a>(v: &'a Value, key: &str) -> &'a str {
+        v.get(key).and_then(Value::as_str).unwrap_or_default()
+    }
+    let bounce = |email: &str, reason, detail: String| Bounce {
+        email: email.to_string(),
+        reason,
+        detail,
+    };
+    // SendGrid posts an array of events.
+    if let Value::Array(events) = body {
+        return events.iter().flat_map(provider_bounces).collect();
+    }
+    // SNS wraps the SES notification in a string.
+    if str_of(body, "Type") == "Notification"
+        && let Ok(inner) = serde_json::from_str::<Value>(str_of(body, "Message"))
+    {
+        return provider_bounces(&inner);
+    }
+    // Amazon SES.
+    let ses_type = match str_of(body, "notificationType") {
+        "" => str_of(body, "eventType"),
+        t => t,
+    };
+    match ses_type {
+        "Bounce" => {
+            let info = &body["bounce"];
+            if str_of(info, "bounceType") != "Permanent" {
+                return Vec::new();
+            }
+            let detail = format!("ses {}", str_of(info, "bounceSubType"));
+            return info["bouncedRecipients"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|r| {
+                    bounce(
+                        str_of(r, "emailAddress"),
+                        SuppressionReason::Bounce,
+                        detail.clone(),
+                    )
+                })
+                .collect();
+        }
+        "Complaint" => {
+            return body["complaint"]["complainedRecipients"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|r| {
+                    bounce(
+                        str_of(r, "emailAddress"),
+                        SuppressionReason::Complaint,
+                        "ses complaint".to_string(),
+                    )
+                })
+                .collect();
+        }
+        _ => {}
+    }
+    // Postmark.
+    match str_of(body, "RecordType") {
+        "Bounce" => {
+            let kind = str_of(body, "Type");
+            return match kind {
+                "HardBounce" | "BadEmailAddress" | "ManuallyDeactivated" => vec![bounce(
+                    str_of(body, "Email"),
+                    SuppressionReason::Bounce,
+                    format!("postmark {kind}"),
+                )],
+                _ => Vec::new(),
+            };
+        }
+        "SpamComplaint" => {
+            return vec![bounce(
+                str_of(body, "Email"),
+                SuppressionReason::Complaint,
+                "postmark complaint".to_string(),
+            )];
+        }
+        _ => {}
+    }
+    // Mailgun.
+    if let Some(data) = body.get("event-data") {
+        let email = str_of(data, "recipient");
+        return match (str_of(data, "event"), str_of(data, "severity")) {
+            ("failed", "permanent") => vec![bounce(
+                email,
+                SuppressionReason::Bounce,
+                format!("mailgun {}", str_of(data, "reason")),
+            )],
+            ("complained", _) => vec![bounce(
+                email,
+                SuppressionReason::Complaint,
+                "mailgun complaint".to_string(),
+            )],
+            _ => Vec::new(),
+        };
+    }
+    // SendGrid events, and the generic `{"email", "type"}` shape.
+    let email = str_of(body, "email");
+    match (str_of(body, "event"), str_of(body, "type")) {
+        ("bounce", "bounce") | ("dropped", _) | ("", "bounce") => vec![bounce(
+            email,
+            SuppressionReason::Bounce,
+            match str_of(body, "reason") {
+                "" => "bounce".to_string(),
+                reason => reason.to_string(),
+            },
+        )],
+        ("spamreport", _) | ("", "complaint") => vec![bounce(
+            email,
+            SuppressionReason::Complaint,
+            "complaint".to_string(),
+        )],
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TokenParam {
+    token: Option<String>,
+}
+
+/// `POST {bounce_webhook}`, returning `{"suppressed": n}`.
+pub fn bounce_handler(ctx: Context) -> Response {
+    let Some(secret) = mailer().and_then(|m| m.webhook_secret.clone()) else {
+        return Response::not_found();
+    };
+    let query = match ctx.extract::<Query<TokenParam>>() {
+        Ok(Query(q)) => q,
+        Err(res) => return res,
+    };
+    let token = query
+        .token
+        .as_deref()
+        .or_else(|| ctx.header("x-webhook-token"))
+        .unwrap_or_default();
+    if !constant_time_eq(token.as_bytes(), secret.as_bytes()) {
+        return Response::unauthorized();
+    }
+    let Ok(body) = serde_json::from_slice::<Value>(ctx.req.body) else {
+        return Response::bad_request();
+    };
+    if body.get("Type").and_then(Value::as_str) == Some("SubscriptionConfirmation") {
+        // Confirming means fetching a URL; leave that to an operator rather
+        // than have the server make requests on a caller's behalf.
+        eprintln!(
+            "[chopin] mail: confirm the SNS subscription by visiting {}",
+            body["SubscribeURL"].as_str().unwrap_or_default()
+        );
+        return Response::new(200);
+    }
+    let bounces = parse_bounces(&body);
+    let result = db::with_db(|db| {
+        let mut added = 0u64;
+        for b in &bounces {
+            if Suppressions::add(db, &b.email, b.reason, Some(&b.detail))? {
+                added += 1;
+            }
+        }
+        Ok(added)
+    });
+    match result {
+        Ok(added) => match serde_json::to_vec(&serde_json::json!({ "suppressed": added })) {
+            Ok(body) => Response::json_bytes(body),
+            Err(_) => Response::server_error(),
+        },
+        Err(_) => Response::server_error(),
+    }
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Sets the transport and delivery policy, owns the table migration, mounts
+/// the bounce webhook if configured, and starts a sender thread with the
+/// server.
+pub struct MailModule {
+    transport: Arc<dyn MailTransport>,
+    from: String,
+    rate_limit: Option<(u32, Duration)>,
+    max_attempts: u32,
+    backoff: Duration,
+    default_locale: String,
+    webhook: Option<(&'static str, String)>,
+    poll_interval: Option<Duration>,
+}
+
+impl MailModule {
+    /// Deliver through `transport`, from `from` unless a message says
+    /// otherwise.
+    pub fn new(transport: impl MailTransport + 'static, from: &str) -> Self {
+        Self {
+            transport: Arc::new(transport),
+            from: from.to_string(),
+            rate_limit: None,
+            max_attempts: 5,
+            backoff: Duration::from_secs(60),
+            default_locale: "en".to_string(),
+            webhook: None,
+            poll_interval: Some(Duration::from_secs(1)),
+        }
+    }
+
+    /// Send at most `max` messages to one recipient per `per`.
+    pub fn rate_limit(mut self, max: u32, per: Duration) -> Self {
+        self.rate_limit = Some((max.max(1), per));
+        self
+    }
+
+    /// Attempts before a message is failed. Defaults to 5.
+    pub fn max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled for each one after. Defaults
+    /// to one minute.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Locale tried last by [`render_template`]. Defaults to `en`.
+    pub fn default_locale(mut self, locale: &str) -> Self {
+        self.default_locale = locale.to_string();
+        self
+    }
+
+    /// Accept provider bounce notifications at `POST path`, authenticated by
+    /// `secret`.
+    pub fn bounce_webhook(mut self, path: &'static str, secret: &str) -> Self {
+        self.webhook = Some((path, secret.to_string()));
+        self
+    }
+
+    /// How often an idle sender checks for due messages. Defaults to one
+    /// second; messages queued in this process wake it at once.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = Some(interval);
+        self
+    }
+
+    /// Only queue mail; a sender in another process delivers it.
+    pub fn without_sender(mut self) -> Self {
+        self.poll_interval = None;
+        self
+    }
+
+    fn install(&self) {
+        let mailer = Mailer {
+            transport: self.transport.clone(),
+            from: self.from.clone(),
+            rate_limit: self.rate_limit,
+            max_attempts: self.max_attempts,
+            backoff: self.backoff,
+            default_locale: self.default_locale.clone(),
+            webhook_secret: self.webhook.as_ref().map(|(_, secret)| secret.clone()),
+        };
+        if let Ok(mut slot) = MAILER.write() {
+            *slot = Some(Arc::new(mailer));
+        }
+    }
+}
+
+impl ChopinModule for MailModule {
+    fn name(&self) -> &'static str {
+        "mail"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        self.install();
+        if let Some((path, _)) = &self.webhook {
+            router.post(path, bounce_handler);
+        }
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreateMailTables)]
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        self.install();
+        let Some(poll) = self.poll_interval else {
+            return Ok(());
+        };
+        std::thread::Builder::new()
+            .name("chopin-mail".into())
+            .spawn(move || {
+                loop {
+                    let seen = QUEUED.version();
+                    match send_next() {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            QUEUED.wait_since(seen, poll);
+                        }
+                        Err(e) => {
+                            eprintln!("[chopin] mail sender: {e}");
+                            std::thread::sleep(poll);
+                        }
+                    }
+                }
+            })
+            .map_err(ChopinError::Io)?;
+        Ok(())
+    }
+}
+
+struct CreateMailTables;
+
+impl Migration for CreateMailTables {
+    fn name(&self) -> &'static str {
+        "001_create_mail"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        MailStore::ensure_tables(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_mail_suppressions", &[])?;
+        executor.execute("DROP TABLE IF EXISTS chopin_mail", &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tpl(src: &str, data: Value) -> String {
+        render(&parse(src).unwrap(), &data, true)
+    }
+
+    #[test]
+    fn test_template_variables_and_escaping() {
+        let data =
+            json!({ "name": "<Ann>", "shop": { "url": "https://s.example/?a=1&b=2" }, "n": 3 });
+        assert_eq!(tpl("Hi {{ name }}!", data.clone()), "Hi &lt;Ann&gt;!");
+        assert_eq!(tpl("{{{name}}} {{n}}", data.clone()), "<Ann> 3");
+        assert_eq!(
+            tpl("{{shop.url}}{{missing}}", data.clone()),
+            "https://s.example/?a=1&amp;b=2"
+        );
+        assert_eq!(tpl("a{{! note }}b{{!-- {{x}} --}}c", data.clone()), "abc");
+        let text = render(&parse("{{name}}").unwrap(), &data, false);
+        assert_eq!(text, "<Ann>");
+    }
+
+    #[test]
+    fn test_template_blocks() {
+        let data = json!({
+            "shop": "Acme",
+            "vip": false,
+            "items": [{ "title": "Tea" }, { "title": "Cup" }],
+            "none": [],
+        });
+        assert_eq!(
+            tpl("{{#if vip}}VIP{{else}}regular{{/if}}", data.clone()),
+            "regular"
+        );
+        assert_eq!(
+            tpl("{{#unless vip}}upgrade{{/unless}}", data.clone()),
+            "upgrade"
+        );
+        assert_eq!(
+            tpl(
+                "{{#each items}}{{@index}}.{{title}}@{{shop}} {{/each}}",
+                data.clone()
+            ),
+            "0.Tea@Acme 1.Cup@Acme "
+        );
+        assert_eq!(
+            tpl("{{#each none}}x{{else}}empty{{/each}}", data.clone()),
+            "empty"
+        );
+        assert_eq!(
+            tpl(
+                "{{#each items}}{{#if @index}}, {{/if}}{{this.title}}{{/each}}",
+                data
+            ),
+            "Tea, Cup"
+        );
+    }
+
+    #[test]
+    fn test_template_errors() {
+        for src in [
+            "{{#if x}}open",
+            "{{/if}}",
+            "{{else}}",
+            "{{#if}}{{/if}}",
+            "{{#with x}}{{/with}}",
+            "{{name",
+            "{{#if a}}{{/each}}",
+        ] {
+            assert!(parse(src).is_err(), "{src}");
+        }
+        assert!(MailTemplate::new("ok", "{{#each}}").is_err());
+    }
+
+    #[test]
+    fn test_locale_chain() {
+        assert_eq!(locale_chain("pt_BR", "en"), vec!["pt-br", "pt", "en"]);
+        assert_eq!(locale_chain("EN-gb", "en"), vec!["en-gb", "en"]);
+        assert_eq!(locale_chain("", "de"), vec!["de"]);
+    }
+
+    #[test]
+    fn test_render_flattens_subject() {
+        let template = MailTemplate::new("Order {{id}}", "Thanks")
+            .unwrap()
+            .html("<b>{{id}}</b>")
+            .unwrap();
+        let email = template.render("a@b.example", &json!({ "id": "1\r\nBcc: x@evil" }));
+        assert_eq!(email.subject, "Order 1  Bcc: x@evil");
+        assert!(email.validate().is_ok());
+        assert_eq!(email.html.as_deref(), Some("<b>1\r\nBcc: x@evil</b>"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(Email::new("Ann <ann@x.example>", "hi").validate().is_ok());
+        assert!(Email::new("nobody", "hi").validate().is_err());
+        assert!(
+            Email::new("a@x.example", "hi\nBcc: b@y")
+                .validate()
+                .is_err()
+        );
+        let email = Email::new("a@x.example", "hi").header("X-Tag", "a\r\nb");
+        assert!(email.validate().is_err());
+        assert_eq!(
+            Email::new("Ann <Ann@X.example>", "").recipient(),
+            "ann@x.example"
+        );
+    }
+
+    #[test]
+    fn test_mime() {
+        let email = Email::new("Zoë <zoe@x.example>", "Olá")
+            .text("plain")
+            .html("<p>hi</p>")
+            .header("List-Unsubscribe", "<https://s.example/u>");
+        let mime = to_mime(&email, "Shop <hi@shop.example>", SystemTime::UNIX_EPOCH);
+        assert!(mime.contains("To: =?UTF-8?B?Wm/Dqw==?= <zoe@x.example>\r\n"));
+        assert!(mime.contains("Subject: =?UTF-8?B?T2zDoQ==?=\r\n"));
+        assert!(mime.contains("Date: Thu, 01 Jan 1970 00:00:00 GMT\r\n"));
+        assert!(mime.contains("@shop.example>\r\n"));
+        assert!(mime.contains("List-Unsubscribe: <https://s.example/u>\r\n"));
+        assert!(mime.contains("multipart/alternative"));
+        assert!(mime.contains(&base64_encode(b"<p>hi</p>")));
+        assert_eq!(dot_stuff(".a\n..b\r\nc"), "..a\r\n...b\r\nc");
+    }
+
+    #[test]
+    fn test_parse_bounces() {
+        let ses = json!({
+            "notificationType": "Bounce",
+            "bounce": {
+                "bounceType": "Permanent",
+                "bounceSubType": "General",
+                "bouncedRecipients": [{ "emailAddress": "a@x.example" }]
+            }
+        });
+        let sns = json!({ "Type": "Notification", "Message": ses.to_string() });
+        let got = parse_bounces(&sns);
+        assert_eq!(got.len(), 1);
+        assert_eq!(got[0].email, "a@x.example");
+        assert_eq!(got[0].reason, SuppressionReason::Bounce);
+
+        let soft = json!({ "notificationType": "Bounce", "bounce": { "bounceType": "Transient", "bouncedRecipients": [{ "emailAddress": "a@x.example" }] } });
+        assert!(parse_bounces(&soft).is_empty());
+
+        let sendgrid = json!([
+            { "email": "b@x.example", "event": "bounce", "type": "bounce" },
+            { "email": "c@x.example", "event": "bounce", "type": "blocked" },
+            { "email": "d@x.example", "event": "spamreport" },
+            { "email": "e@x.example", "event": "delivered" }
+        ]);
+        let got: Vec<_> = parse_bounces(&sendgrid)
+            .into_iter()
+            .map(|b| (b.email, b.reason))
+            .collect();
+        assert_eq!(
+            got,
+            vec![
+                ("b@x.example".to_string(), SuppressionReason::Bounce),
+                ("d@x.example".to_string(), SuppressionReason::Complaint)
+            ]
+        );
+
+        let postmark =
+            json!({ "RecordType": "Bounce", "Type": "HardBounce", "Email": "f@x.example" });
+        assert_eq!(parse_bounces(&postmark)[0].email, "f@x.example");
+        let mailgun = json!({ "event-data": { "event": "failed", "severity": "temporary", "recipient": "g@x.example" } });
+        assert!(parse_bounces(&mailgun).is_empty());
+        let mailgun =
+            json!({ "event-data": { "event": "complained", "recipient": "g@x.example" } });
+        assert_eq!(
+            parse_bounces(&mailgun)[0].reason,
+            SuppressionReason::Complaint
+        );
+        assert!(parse_bounces(&json!({ "type": "bounce", "email": "nope" })).is_empty());
+    }
+}
@@ -0,0 +1,52 @@
+// src/memo.rs
+//! Runtime for the `#[cached]` attribute (`orm` feature).
+//!
+//! `#[cached]` memoizes a function's return value, serialized as JSON, in the
+//! process-wide [`CacheService`](chopin_orm::CacheService):
+//!
+//! ```rust,ignore
+//! #[cached(ttl = "30s")]
+//! fn trending(limit: u32) -> Vec<Post> { .. }
+//!
+//! #[cached(ttl = "5m", key = format!("profile:{id}"))]
+//! fn profile(id: i64) -> ChopinResult<Profile> { .. }
+//!
+//! memo::invalidate_prefix("profile:");
+//! ```
+//!
+//! Without `key`, the key is the function's path followed by the `Debug`
+//! form of its arguments, so [`fn_key_prefix`] of that path invalidates
+//! every cached call. Functions returning a `Result` only cache `Ok` values.
+use chopin_orm::cache::cache_service;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::time::Duration;
+
+/// Cached value for `key`, if present and decodable.
+pub fn get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let bytes = cache_service().get(key)?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+/// Store `value` under `key` for `ttl`. Values that fail to serialize are
+/// not cached.
+pub fn put<T: Serialize + ?Sized>(key: &str, value: &T, ttl: Duration) {
+    if let Ok(bytes) = serde_json::to_vec(value) {
+        cache_service().set(key, bytes, ttl);
+    }
+}
+
+pub fn invalidate(key: &str) {
+    cache_service().delete(key);
+}
+
+/// Drop every cached value whose key starts with `prefix`.
+pub fn invalidate_prefix(prefix: &str) {
+    cache_service().delete_prefix(prefix);
+}
+
+/// Prefix shared by the default keys of the `#[cached]` function at `path`
+/// (e.g. `"my_app::posts::trending"`).
+pub fn fn_key_prefix(path: &str) -> String {
+    format!("chopin:memo:{path}(")
+}
@@ -0,0 +1,666 @@
+// src/metering.rs
+//! Usage metering per API key, user or tenant (`orm` feature).
+//!
+//! [`MeteringModule`] layers [`meter_requests`] over every route. Each
+//! request is charged to the [`Subject`]s it belongs to: those the
+//! configured resolvers find in the request, plus any a handler or guard
+//! names with [`attribute`] once it knows who is calling. Per subject and
+//! period the module counts requests, bytes received and sent, and time
+//! spent in database queries.
+//!
+//! ```rust,ignore
+//! fn tenant(ctx: &Context) -> Option<Subject> {
+//!     ctx.header("X-Tenant").map(Subject::tenant)
+//! }
+//!
+//! Chopin::new().mount_module(
+//!     MeteringModule::new()
+//!         .resolve(tenant)
+//!         .granularity(Duration::from_secs(3600))
+//!         .admin("/admin/usage", require_operator),
+//! );
+//!
+//! // In an auth guard, after the key is verified:
+//! metering::attribute(Subject::api_key(key.id.to_string()));
+//! ```
+//!
+//! Counts are kept in memory and added to the `chopin_usage` table, one row
+//! per subject and period, every [`flush_interval`](MeteringModule::flush_interval).
+//! [`usage`] reads the table and adds what has not been flushed yet, so
+//! quota checks see the current figures. Identify API keys by their id or a
+//! hash, never the secret itself: subject ids are stored in the clear.
+//!
+//! Routes mounted by [`MeteringModule::admin`] under its prefix:
+//!
+//! | Method | Path         | Description                                          |
+//! |--------|--------------|------------------------------------------------------|
+//! | GET    | `/:kind/:id` | [`UsageReport`] for `?from=&to=` (Unix seconds; last 24 h by default) |
+use crate::db;
+use crate::error::{ChopinError, ChopinResult};
+use crate::extract::Query;
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, MiddlewareFn, Router};
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// What a subject id identifies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubjectKind {
+    ApiKey,
+    User,
+    Tenant,
+}
+
+impl SubjectKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SubjectKind::ApiKey => "api_key",
+            SubjectKind::User => "user",
+            SubjectKind::Tenant => "tenant",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "api_key" => Some(SubjectKind::ApiKey),
+            "user" => Some(SubjectKind::User),
+            "tenant" => Some(SubjectKind::Tenant),
+            _ => None,
+        }
+    }
+}
+
+/// Who a request is charged to.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub struct Subject {
+    pub kind: SubjectKind,
+    pub id: String,
+}
+
+impl Subject {
+    pub fn new(kind: SubjectKind, id: impl Into<String>) -> Self {
+        Self {
+            kind,
+            id: id.into(),
+        }
+    }
+
+    pub fn api_key(id: impl Into<String>) -> Self {
+        Self::new(SubjectKind::ApiKey, id)
+    }
+
+    pub fn user(id: impl Into<String>) -> Self {
+        Self::new(SubjectKind::User, id)
+    }
+
+    pub fn tenant(id: impl Into<String>) -> Self {
+        Self::new(SubjectKind::Tenant, id)
+    }
+}
+
+/// Counters for one subject over some span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub requests: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// Wall time spent waiting on database queries.
+    pub db_time_us: u64,
+}
+
+impl Usage {
+    fn add(&mut self, other: &Usage) {
+        self.requests += other.requests;
+        self.bytes_in += other.bytes_in;
+        self.bytes_out += other.bytes_out;
+        self.db_time_us += other.db_time_us;
+    }
+
+    fn sub(&mut self, other: &Usage) {
+        self.requests = self.requests.saturating_sub(other.requests);
+        self.bytes_in = self.bytes_in.saturating_sub(other.bytes_in);
+        self.bytes_out = self.bytes_out.saturating_sub(other.bytes_out);
+        self.db_time_us = self.db_time_us.saturating_sub(other.db_time_us);
+    }
+}
+
+/// One stored period.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UsagePeriod {
+    /// Unix seconds, a multiple of the granularity.
+    pub period_start: i64,
+    #[serde(flatten)]
+    pub usage: Usage,
+}
+
+// ─── Recording ───────────────────────────────────────────────────────────────
+
+/// Finds a subject in a request before its handler runs.
+pub type Resolver = fn(&Context) -> Option<Subject>;
+
+pub const DEFAULT_GRANULARITY: Duration = Duration::from_secs(3600);
+pub const DEFAULT_FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+struct Settings {
+    resolvers: Vec<Resolver>,
+    granularity: i64,
+}
+
+static SETTINGS: RwLock<Option<Arc<Settings>>> = RwLock::new(None);
+
+/// Bumped after every flush that wrote rows.
+static FLUSHES: AtomicU64 = AtomicU64::new(0);
+
+type PendingKey = (Subject, i64);
+
+fn pending() -> &'static Mutex<HashMap<PendingKey, Usage>> {
+    static PENDING: OnceLock<Mutex<HashMap<PendingKey, Usage>>> = OnceLock::new();
+    PENDING.get_or_init(Default::default)
+}
+
+thread_local! {
+    static ATTRIBUTED: RefCell<Vec<Subject>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Charge the current request to `subject` as well.
+pub fn attribute(subject: Subject) {
+    ATTRIBUTED.with(|a| a.borrow_mut().push(subject));
+}
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64)
+}
+
+/// Start of the period of length `granularity` (seconds) holding `unix`.
+pub fn period_start(unix: i64, granularity: i64) -> i64 {
+    unix - unix.rem_euclid(granularity.max(1))
+}
+
+/// Add `usage` for `subject` to the period holding `at` (Unix seconds),
+/// e.g. for work done outside a request. Uses the module's granularity,
+/// or [`DEFAULT_GRANULARITY`] before it is mounted.
+pub fn record(subject: &Subject, usage: Usage, at: i64) {
+    let granularity = SETTINGS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .map_or(DEFAULT_GRANULARITY.as_secs() as i64, |s| s.granularity);
+    let key = (subject.clone(), period_start(at, granularity));
+    pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(key)
+        .or_default()
+        .add(&usage);
+}
+
+/// Whether a [`MeteringModule`] has been mounted.
+pub(crate) fn is_installed() -> bool {
+    SETTINGS.read().unwrap_or_else(|e| e.into_inner()).is_some()
+}
+
+/// Middleware charging each request to its subjects. Requests without a
+/// subject are not counted.
+///
+/// With a [`QuotaModule`](crate::quota::QuotaModule) mounted, the subjects
+/// found by the resolvers are checked against their quotas first; a
+/// rejected request is answered `429` and not charged.
+pub fn meter_requests(ctx: Context, next: BoxedHandler) -> Response {
+    let settings = SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(settings) = settings else {
+        return next(ctx);
+    };
+    let mut subjects: Vec<Subject> = settings.resolvers.iter().filter_map(|r| r(&ctx)).collect();
+    let quota = match crate::quota::check(&subjects) {
+        Ok(quota) => quota,
+        Err(res) => return res,
+    };
+    let bytes_in = ctx.req.body.len() as u64;
+    ATTRIBUTED.with(|a| a.borrow_mut().clear());
+    let db_before = chopin_orm::stats::current().query_time;
+
+    let mut res = next(ctx);
+    if let Some(quota) = quota {
+        quota.after_request().apply(&mut res);
+    }
+
+    let db_time = chopin_orm::stats::current()
+        .query_time
+        .saturating_sub(db_before);
+    subjects.extend(ATTRIBUTED.with(|a| a.take()));
+    subjects.sort();
+    subjects.dedup();
+    let usage = Usage {
+        requests: 1,
+        bytes_in,
+        bytes_out: res.body.len() as u64,
+        db_time_us: db_time.as_micros() as u64,
+    };
+    let now = unix_now();
+    for subject in &subjects {
+        record(subject, usage, now);
+    }
+    res
+}
+
+// ─── Storage ─────────────────────────────────────────────────────────────────
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_usage (
+        subject_kind TEXT NOT NULL,
+        subject_id TEXT NOT NULL,
+        period_start TIMESTAMPTZ NOT NULL,
+        requests BIGINT NOT NULL DEFAULT 0,
+        bytes_in BIGINT NOT NULL DEFAULT 0,
+        bytes_out BIGINT NOT NULL DEFAULT 0,
+        db_time_us BIGINT NOT NULL DEFAULT 0,
+        PRIMARY KEY (subject_kind, subject_id, period_start)
+    )
+"#;
+
+/// Storage operations on `chopin_usage`.
+pub struct UsageStore;
+
+impl UsageStore {
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        Ok(())
+    }
+
+    /// Add `usage` to the row for `subject` and `period_start`.
+    pub fn add(
+        executor: &mut dyn Executor,
+        subject: &Subject,
+        period_start: i64,
+        usage: &Usage,
+    ) -> OrmResult<()> {
+        executor.execute(
+            "INSERT INTO chopin_usage \
+                 (subject_kind, subject_id, period_start, requests, bytes_in, bytes_out, db_time_us) \
+             VALUES ($1, $2, to_timestamp($3::BIGINT), $4, $5, $6, $7) \
+             ON CONFLICT (subject_kind, subject_id, period_start) DO UPDATE SET \
+                 requests = chopin_usage.requests + EXCLUDED.requests, \
+                 bytes_in = chopin_usage.bytes_in + EXCLUDED.bytes_in, \
+                 bytes_out = chopin_usage.bytes_out + EXCLUDED.bytes_out, \
+                 db_time_us = chopin_usage.db_time_us + EXCLUDED.db_time_us",
+            &[
+                &subject.kind.as_str(),
+                &subject.id,
+                &period_start,
+                &(usage.requests as i64),
+                &(usage.bytes_in as i64),
+                &(usage.bytes_out as i64),
+                &(usage.db_time_us as i64),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Stored periods for `subject` starting in `from..to`, oldest first.
+    pub fn periods(
+        executor: &mut dyn Executor,
+        subject: &Subject,
+        from: i64,
+        to: i64,
+    ) -> OrmResult<Vec<UsagePeriod>> {
+        executor
+            .query(
+                "SELECT EXTRACT(EPOCH FROM period_start)::BIGINT, \
+                     requests, bytes_in, bytes_out, db_time_us \
+                 FROM chopin_usage \
+                 WHERE subject_kind = $1 AND subject_id = $2 \
+                     AND period_start >= to_timestamp($3::BIGINT) \
+                     AND period_start < to_timestamp($4::BIGINT) \
+                 ORDER BY period_start",
+                &[&subject.kind.as_str(), &subject.id, &from, &to],
+            )?
+            .iter()
+            .map(|row| {
+                Ok(UsagePeriod {
+                    period_start: row.get_typed(0)?,
+                    usage: Usage {
+                        requests: row.get_typed::<i64>(1)? as u64,
+                        bytes_in: row.get_typed::<i64>(2)? as u64,
+                        bytes_out: row.get_typed::<i64>(3)? as u64,
+                        db_time_us: row.get_typed::<i64>(4)? as u64,
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+/// Write the in-memory counters to `chopin_usage`. Counters that could not
+/// be written are kept for the next flush. Returns the rows written.
+///
+/// Counters stay in memory until their row is written, so [`usage`] never
+/// misses requests that are on their way to the table.
+pub fn flush() -> ChopinResult<usize> {
+    let batch: Vec<(PendingKey, Usage)> = pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|(key, usage)| (key.clone(), *usage))
+        .collect();
+    let mut written = 0;
+    let result = db::with_db(|db| {
+        for ((subject, start), usage) in &batch {
+            UsageStore::add(db, subject, *start, usage)?;
+            written += 1;
+        }
+        Ok(())
+    });
+    if written > 0 {
+        let mut pending = pending().lock().unwrap_or_else(|e| e.into_inner());
+        for (key, usage) in &batch[..written] {
+            if let Some(left) = pending.get_mut(key) {
+                left.sub(usage);
+                if *left == Usage::default() {
+                    pending.remove(key);
+                }
+            }
+        }
+        FLUSHES.fetch_add(1, Ordering::Release);
+    }
+    result.map(|()| written)
+}
+
+/// Number of flushes that wrote rows so far; stored totals read before a
+/// change of this number may be stale.
+pub(crate) fn flushes() -> u64 {
+    FLUSHES.load(Ordering::Acquire)
+}
+
+/// Stored usage of `subject` over the periods starting in `from..to`,
+/// without the counters not flushed yet.
+pub(crate) fn stored_usage(subject: &Subject, from: i64, to: i64) -> ChopinResult<Usage> {
+    let periods = db::with_db(|db| UsageStore::periods(db, subject, from, to))?;
+    Ok(sum(&periods))
+}
+
+/// Counters of `subject` not flushed yet, over the periods starting in
+/// `from..to`.
+pub(crate) fn pending_usage(subject: &Subject, from: i64, to: i64) -> Usage {
+    sum(&pending_periods(subject, from, to))
+}
+
+fn sum(periods: &[UsagePeriod]) -> Usage {
+    periods.iter().fold(Usage::default(), |mut total, p| {
+        total.add(&p.usage);
+        total
+    })
+}
+
+/// Unflushed periods for `subject` starting in `from..to`.
+fn pending_periods(subject: &Subject, from: i64, to: i64) -> Vec<UsagePeriod> {
+    pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter(|((s, start), _)| s == subject && (from..to).contains(start))
+        .map(|((_, start), usage)| UsagePeriod {
+            period_start: *start,
+            usage: *usage,
+        })
+        .collect()
+}
+
+/// Every period of `subject` starting in `from..to` (Unix seconds), stored
+/// and not yet flushed, oldest first.
+pub fn periods(subject: &Subject, from: i64, to: i64) -> ChopinResult<Vec<UsagePeriod>> {
+    let mut periods = db::with_db(|db| UsageStore::periods(db, subject, from, to))?;
+    for extra in pending_periods(subject, from, to) {
+        match periods
+            .iter_mut()
+            .find(|p| p.period_start == extra.period_start)
+        {
+            Some(p) => p.usage.add(&extra.usage),
+            None => periods.push(extra),
+        }
+    }
+    periods.sort_by_key(|p| p.period_start);
+    Ok(periods)
+}
+
+/// Total usage of `subject` over the periods starting in `from..to`.
+pub fn usage(subject: &Subject, from: i64, to: i64) -> ChopinResult<Usage> {
+    Ok(sum(&periods(subject, from, to)?))
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+/// Response of the admin endpoint.
+#[derive(Debug, Serialize)]
+pub struct UsageReport {
+    pub subject: Subject,
+    pub from: i64,
+    pub to: i64,
+    pub total: Usage,
+    pub periods: Vec<UsagePeriod>,
+}
+
+#[derive(Deserialize)]
+struct RangeParams {
+    from: Option<i64>,
+    to: Option<i64>,
+}
+
+/// `GET {admin}/:kind/:id`
+pub fn admin_usage_handler(ctx: Context) -> Response {
+    let (Some(kind), Some(id)) = (
+        ctx.param("kind").and_then(SubjectKind::parse),
+        ctx.param("id"),
+    ) else {
+        return Response::bad_request();
+    };
+    let range = match ctx.extract::<Query<RangeParams>>() {
+        Ok(Query(r)) => r,
+        Err(res) => return res,
+    };
+    let to = range.to.unwrap_or_else(|| unix_now() + 1);
+    let from = range.from.unwrap_or(to - 86_400);
+    let subject = Subject::new(kind, id);
+    let periods = match periods(&subject, from, to) {
+        Ok(p) => p,
+        Err(_) => return Response::server_error(),
+    };
+    let report = UsageReport {
+        subject,
+        from,
+        to,
+        total: sum(&periods),
+        periods,
+    };
+    match serde_json::to_vec(&report) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Meters requests and flushes the counters on a background thread.
+#[derive(Clone)]
+pub struct MeteringModule {
+    resolvers: Vec<Resolver>,
+    granularity: Duration,
+    flush_interval: Duration,
+    admin: Option<(&'static str, MiddlewareFn)>,
+}
+
+impl Default for MeteringModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MeteringModule {
+    /// Hourly periods, flushed every minute, no resolvers.
+    pub fn new() -> Self {
+        Self {
+            resolvers: Vec::new(),
+            granularity: DEFAULT_GRANULARITY,
+            flush_interval: DEFAULT_FLUSH_INTERVAL,
+            admin: None,
+        }
+    }
+
+    pub fn resolve(mut self, resolver: Resolver) -> Self {
+        self.resolvers.push(resolver);
+        self
+    }
+
+    /// Length of a period, rounded down to whole seconds.
+    pub fn granularity(mut self, period: Duration) -> Self {
+        self.granularity = period.max(Duration::from_secs(1));
+        self
+    }
+
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    /// Mount the usage endpoint under `prefix`, behind `guard`.
+    pub fn admin(mut self, prefix: &'static str, guard: MiddlewareFn) -> Self {
+        self.admin = Some((prefix.trim_end_matches('/'), guard));
+        self
+    }
+}
+
+impl ChopinModule for MeteringModule {
+    fn name(&self) -> &'static str {
+        "metering"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Settings {
+            resolvers: self.resolvers.clone(),
+            granularity: self.granularity.as_secs() as i64,
+        }));
+        router.layer(meter_requests);
+        if let Some((a, guard)) = self.admin {
+            router.get(&format!("{a}/:kind/:id"), admin_usage_handler);
+            router.layer_path(a, guard);
+        }
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreateUsageTable)]
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        let interval = self.flush_interval;
+        std::thread::Builder::new()
+            .name("chopin-metering".into())
+            .spawn(move || {
+                loop {
+                    std::thread::sleep(interval);
+                    if let Err(e) = flush() {
+                        eprintln!("[chopin] metering flush: {e}");
+                    }
+                }
+            })
+            .map_err(ChopinError::Io)?;
+        Ok(())
+    }
+}
+
+struct CreateUsageTable;
+
+impl Migration for CreateUsageTable {
+    fn name(&self) -> &'static str {
+        "001_create_usage"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        UsageStore::ensure_table(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_usage", &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use chopin_orm::MockExecutor;
+
+    /// Held by tests that mount modules with global settings.
+    pub(crate) static INSTALL_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_period_start() {
+        assert_eq!(period_start(7_205, 3600), 7_200);
+        assert_eq!(period_start(7_200, 3600), 7_200);
+        assert_eq!(period_start(-1, 60), -60);
+    }
+
+    fn tenant(ctx: &Context) -> Option<Subject> {
+        ctx.header("X-Tenant").map(Subject::tenant)
+    }
+
+    fn upload(_ctx: Context) -> Response {
+        attribute(Subject::user("metering-u1"));
+        Response::text("stored")
+    }
+
+    #[test]
+    fn test_requests_are_charged_to_every_subject() {
+        let _installed = INSTALL_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = Router::new();
+        MeteringModule::new().resolve(tenant).routes(&mut router);
+        router.post("/metering-test/upload", upload);
+        let app = crate::testing::TestApp::new(router);
+
+        for _ in 0..2 {
+            app.request(
+                crate::http::Method::Post,
+                "/metering-test/upload",
+                &[("X-Tenant", "metering-acme")],
+                b"0123456789",
+            );
+        }
+
+        let (from, to) = (0, i64::MAX);
+        let acme = pending_periods(&Subject::tenant("metering-acme"), from, to);
+        assert_eq!(acme.len(), 1);
+        assert_eq!(acme[0].period_start % 3600, 0);
+        assert_eq!(
+            (
+                acme[0].usage.requests,
+                acme[0].usage.bytes_in,
+                acme[0].usage.bytes_out
+            ),
+            (2, 20, 12)
+        );
+        let user = pending_periods(&Subject::user("metering-u1"), from, to);
+        assert_eq!(user[0].usage.requests, 2);
+        assert!(pending_periods(&Subject::api_key("metering-acme"), from, to).is_empty());
+    }
+
+    #[test]
+    fn test_store_adds_to_existing_period() {
+        let mut db = MockExecutor::new();
+        let usage = Usage {
+            requests: 3,
+            ..Default::default()
+        };
+        UsageStore::add(&mut db, &Subject::api_key("k1"), 3600, &usage).unwrap();
+        let (sql, params) = &db.executed_queries[0];
+        assert!(sql.contains("ON CONFLICT (subject_kind, subject_id, period_start)"));
+        assert!(sql.contains("requests = chopin_usage.requests + EXCLUDED.requests"));
+        assert_eq!(*params, 7);
+    }
+}
@@ -1,19 +1,81 @@
 // src/metrics.rs
 use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, RwLock};
 
 #[repr(C, align(64))]
 pub struct WorkerMetrics {
     pub req_count: AtomicUsize,
     pub active_conns: AtomicUsize,
     pub bytes_sent: AtomicUsize,
+    /// Time spent processing the last sampled batch of ready events (µs).
+    /// While a batch runs, every other ready socket waits — this is the loop lag.
+    pub loop_lag_us: AtomicUsize,
+    /// Peak sampled loop lag since the last [`take_snapshot`](Self::take_snapshot) (µs).
+    pub loop_lag_max_us: AtomicUsize,
+    /// Connections waiting in the kernel accept queue at the last sample.
+    pub accept_queue: AtomicUsize,
+    /// Accept-queue capacity (listen backlog) at the last sample.
+    pub accept_queue_max: AtomicUsize,
 }
 
+/// Point-in-time copy of one worker's metrics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MetricsSnapshot {
+    pub req_count: usize,
+    pub active_conns: usize,
+    pub bytes_sent: usize,
+    pub loop_lag_us: usize,
+    pub loop_lag_max_us: usize,
+    pub accept_queue: usize,
+    pub accept_queue_max: usize,
+}
+
+/// Loop lag above this is reported as blocking work in handlers.
+pub const LAG_WARN_US: usize = 10_000;
+
 impl WorkerMetrics {
     pub fn new() -> Self {
         Self {
             req_count: AtomicUsize::new(0),
             active_conns: AtomicUsize::new(0),
             bytes_sent: AtomicUsize::new(0),
+            loop_lag_us: AtomicUsize::new(0),
+            loop_lag_max_us: AtomicUsize::new(0),
+            accept_queue: AtomicUsize::new(0),
+            accept_queue_max: AtomicUsize::new(0),
+        }
+    }
+
+    /// Record how long one batch of events took to process.
+    pub fn record_loop_lag(&self, micros: usize) {
+        self.loop_lag_us.store(micros, Ordering::Relaxed);
+        self.loop_lag_max_us.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    /// Record the listener's accept-queue length and capacity.
+    pub fn set_accept_queue(&self, depth: usize, capacity: usize) {
+        self.accept_queue.store(depth, Ordering::Relaxed);
+        self.accept_queue_max.store(capacity, Ordering::Relaxed);
+    }
+
+    /// Copy all counters, resetting the peak loop lag.
+    pub fn take_snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            loop_lag_max_us: self.loop_lag_max_us.swap(0, Ordering::Relaxed),
+            ..self.snapshot()
+        }
+    }
+
+    /// Copy all counters without resetting anything.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        MetricsSnapshot {
+            req_count: self.req_count.load(Ordering::Relaxed),
+            active_conns: self.active_conns.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            loop_lag_us: self.loop_lag_us.load(Ordering::Relaxed),
+            loop_lag_max_us: self.loop_lag_max_us.load(Ordering::Relaxed),
+            accept_queue: self.accept_queue.load(Ordering::Relaxed),
+            accept_queue_max: self.accept_queue_max.load(Ordering::Relaxed),
         }
     }
 
@@ -40,6 +102,161 @@ impl Default for WorkerMetrics {
     }
 }
 
+static WORKERS: RwLock<Vec<Arc<WorkerMetrics>>> = RwLock::new(Vec::new());
+
+/// Make the running server's worker metrics readable through
+/// [`worker_snapshots`].
+pub(crate) fn publish(workers: Vec<Arc<WorkerMetrics>>) {
+    *WORKERS.write().unwrap_or_else(|e| e.into_inner()) = workers;
+}
+
+/// Current metrics of every worker of the running server, in worker order.
+/// Peaks are left in place. Empty when no server is running.
+pub fn worker_snapshots() -> Vec<MetricsSnapshot> {
+    WORKERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .map(|m| m.snapshot())
+        .collect()
+}
+
+/// Tuning advice derived from per-worker snapshots.
+///
+/// `slab_capacity` is the per-worker connection limit the server runs with.
+pub fn recommendations(snapshots: &[MetricsSnapshot], slab_capacity: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    for (i, s) in snapshots.iter().enumerate() {
+        if s.accept_queue_max > 0 && s.accept_queue * 2 >= s.accept_queue_max {
+            out.push(format!(
+                "worker-{i}: accept queue {}/{} is over half full; add workers or raise net.core.somaxconn",
+                s.accept_queue, s.accept_queue_max
+            ));
+        }
+        if s.loop_lag_max_us >= LAG_WARN_US {
+            out.push(format!(
+                "worker-{i}: event-loop lag peaked at {} ms; move blocking work out of handlers",
+                s.loop_lag_max_us / 1000
+            ));
+        }
+        if slab_capacity > 0 && s.active_conns * 10 >= slab_capacity * 9 {
+            out.push(format!(
+                "worker-{i}: {} of {} connection slots in use; raise CHOPIN_SLAB_CAPACITY",
+                s.active_conns, slab_capacity
+            ));
+        }
+    }
+    out
+}
+
+/// A table taking at least this share (percent) of model query time in one
+/// auto-tune interval is reported.
+#[cfg(feature = "orm-metrics")]
+pub const ORM_HOTSPOT_PERCENT: u64 = 50;
+
+/// Model query time per table between two
+/// [`chopin_orm::metrics::snapshot`] calls, flagging any table at or above
+/// [`ORM_HOTSPOT_PERCENT`]. Quiet intervals (under 100 statements) are
+/// ignored.
+#[cfg(feature = "orm-metrics")]
+pub fn orm_recommendations(
+    prev: &[chopin_orm::metrics::OpMetrics],
+    cur: &[chopin_orm::metrics::OpMetrics],
+) -> Vec<String> {
+    let mut tables: Vec<(&str, u64, u128)> = Vec::new();
+    for m in cur {
+        let before = prev.iter().find(|p| p.table == m.table && p.op == m.op);
+        let count = m.count - before.map_or(0, |p| p.count.min(m.count));
+        let time = m
+            .total
+            .saturating_sub(before.map_or(Default::default(), |p| p.total));
+        match tables.iter_mut().find(|t| t.0 == m.table) {
+            Some(t) => {
+                t.1 += count;
+                t.2 += time.as_micros();
+            }
+            None => tables.push((m.table, count, time.as_micros())),
+        }
+    }
+    let queries: u64 = tables.iter().map(|t| t.1).sum();
+    let total: u128 = tables.iter().map(|t| t.2).sum();
+    if queries < 100 || total == 0 {
+        return Vec::new();
+    }
+    tables
+        .into_iter()
+        .filter_map(|(table, count, time)| {
+            let percent = (time * 100 / total) as u64;
+            (percent >= ORM_HOTSPOT_PERCENT).then(|| {
+                format!(
+                    "orm: {table} took {percent}% of model query time ({count} statements); \
+                     check its indexes or enable #[model(cache(...))]"
+                )
+            })
+        })
+        .collect()
+}
+
+/// Prometheus text exposition of the per-table ORM counters, for serving
+/// from a `/metrics` route.
+#[cfg(feature = "orm-metrics")]
+pub fn render_orm_metrics(snapshot: &[chopin_orm::metrics::OpMetrics]) -> String {
+    use chopin_orm::metrics::BUCKETS_US;
+    use std::fmt::Write;
+
+    let mut out = String::new();
+    out.push_str("# HELP chopin_orm_queries_total Statements issued by model methods.\n");
+    out.push_str("# TYPE chopin_orm_queries_total counter\n");
+    for m in snapshot {
+        let _ = writeln!(
+            out,
+            "chopin_orm_queries_total{{table=\"{}\",op=\"{}\"}} {}",
+            m.table,
+            m.op.as_str(),
+            m.count
+        );
+    }
+    out.push_str("# HELP chopin_orm_query_errors_total Model statements that returned an error.\n");
+    out.push_str("# TYPE chopin_orm_query_errors_total counter\n");
+    for m in snapshot {
+        let _ = writeln!(
+            out,
+            "chopin_orm_query_errors_total{{table=\"{}\",op=\"{}\"}} {}",
+            m.table,
+            m.op.as_str(),
+            m.errors
+        );
+    }
+    out.push_str("# HELP chopin_orm_query_duration_seconds Model statement latency.\n");
+    out.push_str("# TYPE chopin_orm_query_duration_seconds histogram\n");
+    for m in snapshot {
+        let labels = format!("table=\"{}\",op=\"{}\"", m.table, m.op.as_str());
+        let mut cumulative = 0;
+        for (i, n) in m.buckets.iter().enumerate() {
+            cumulative += n;
+            let le = match BUCKETS_US.get(i) {
+                Some(us) => format!("{}", *us as f64 / 1e6),
+                None => "+Inf".into(),
+            };
+            let _ = writeln!(
+                out,
+                "chopin_orm_query_duration_seconds_bucket{{{labels},le=\"{le}\"}} {cumulative}"
+            );
+        }
+        let _ = writeln!(
+            out,
+            "chopin_orm_query_duration_seconds_sum{{{labels}}} {}",
+            m.total.as_secs_f64()
+        );
+        let _ = writeln!(
+            out,
+            "chopin_orm_query_duration_seconds_count{{{labels}}} {}",
+            m.count
+        );
+    }
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +322,86 @@ mod tests {
         assert_eq!(m.bytes_sent.load(Ordering::Relaxed), 0);
     }
 
+    // ─── lag / accept queue ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_loop_lag_peak_resets_on_snapshot() {
+        let m = WorkerMetrics::new();
+        m.record_loop_lag(500);
+        m.record_loop_lag(20_000);
+        m.record_loop_lag(100);
+        let s = m.take_snapshot();
+        assert_eq!(s.loop_lag_us, 100);
+        assert_eq!(s.loop_lag_max_us, 20_000);
+        assert_eq!(m.take_snapshot().loop_lag_max_us, 0);
+
+        m.record_loop_lag(700);
+        assert_eq!(m.snapshot().loop_lag_max_us, 700);
+        assert_eq!(m.snapshot().loop_lag_max_us, 700);
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let healthy = MetricsSnapshot {
+            accept_queue: 1,
+            accept_queue_max: 8192,
+            active_conns: 10,
+            loop_lag_max_us: 200,
+            ..Default::default()
+        };
+        assert!(recommendations(&[healthy], 10_000).is_empty());
+
+        let loaded = MetricsSnapshot {
+            accept_queue: 5000,
+            accept_queue_max: 8192,
+            active_conns: 9500,
+            loop_lag_max_us: 25_000,
+            ..Default::default()
+        };
+        let recs = recommendations(&[healthy, loaded], 10_000);
+        assert_eq!(recs.len(), 3);
+        assert!(recs.iter().all(|r| r.starts_with("worker-1")));
+        assert!(recs[1].contains("25 ms"));
+    }
+
+    // ─── ORM metrics ──────────────────────────────────────────────────────────
+
+    #[cfg(feature = "orm-metrics")]
+    fn op(table: &'static str, count: u64, millis: u64) -> chopin_orm::metrics::OpMetrics {
+        let mut buckets = [0; chopin_orm::metrics::BUCKETS_US.len() + 1];
+        buckets[3] = count;
+        chopin_orm::metrics::OpMetrics {
+            table,
+            op: chopin_orm::metrics::Op::Select,
+            count,
+            errors: 0,
+            total: std::time::Duration::from_millis(millis),
+            buckets,
+        }
+    }
+
+    #[cfg(feature = "orm-metrics")]
+    #[test]
+    fn test_orm_recommendations_use_the_interval() {
+        let prev = [op("users", 1000, 9000), op("posts", 10, 10)];
+        let cur = [op("users", 1050, 9050), op("posts", 200, 500)];
+        let recs = orm_recommendations(&prev, &cur);
+        assert_eq!(recs.len(), 1);
+        assert!(recs[0].starts_with("orm: posts took 90%"), "{}", recs[0]);
+        assert!(orm_recommendations(&cur, &cur).is_empty());
+    }
+
+    #[cfg(feature = "orm-metrics")]
+    #[test]
+    fn test_render_orm_metrics() {
+        let text = render_orm_metrics(&[op("users", 3, 2)]);
+        assert!(text.contains("chopin_orm_queries_total{table=\"users\",op=\"select\"} 3\n"));
+        assert!(text.contains("_bucket{table=\"users\",op=\"select\",le=\"0.0005\"} 0\n"));
+        assert!(text.contains("_bucket{table=\"users\",op=\"select\",le=\"0.001\"} 3\n"));
+        assert!(text.contains("_bucket{table=\"users\",op=\"select\",le=\"+Inf\"} 3\n"));
+        assert!(text.contains("_sum{table=\"users\",op=\"select\"} 0.002\n"));
+    }
+
     // ─── alignment (cache-line isolation) ─────────────────────────────────────
 
     #[test]
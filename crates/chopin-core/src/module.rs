@@ -0,0 +1,193 @@
+// src/module.rs
+//! Self-contained application modules.
+//!
+//! A [`ChopinModule`] bundles routes together with the maintenance tasks that
+//! belong to them — schema migrations and seed data — so an application can be
+//! assembled from independent pieces:
+//!
+//! ```rust,ignore
+//! struct Blog;
+//!
+//! impl ChopinModule for Blog {
+//!     fn name(&self) -> &'static str { "blog" }
+//!     fn routes(&self, router: &mut Router) { router.get("/posts", list_posts); }
+//!     fn migrate(&self) -> ChopinResult<()> { blog::db::migrate() }
+//! }
+//!
+//! Chopin::new().mount_module(Blog).serve("0.0.0.0:8080")?;
+//! ```
+//!
+//! Modules may depend on each other through
+//! [`depends_on`](ChopinModule::depends_on). Hooks run in dependency order
+//! (dependencies first, ties broken by mount order); rollbacks run in the
+//! reverse order. A missing dependency or a cycle is reported before any hook
+//! runs.
+use crate::error::{ChopinError, ChopinResult};
+use crate::router::Router;
+
+/// A mountable piece of an application.
+///
+/// Every hook except [`name`](ChopinModule::name) has a no-op default.
+pub trait ChopinModule: Send + Sync {
+    /// Unique module name, used in logs and error messages.
+    fn name(&self) -> &'static str;
+
+    /// Names of modules that must be mounted and run before this one.
+    fn depends_on(&self) -> &[&'static str] {
+        &[]
+    }
+
+    /// Whether the module should be mounted at all, e.g.
+    /// `cfg!(feature = "billing")` or a check against [`Config`](crate::Config).
+    /// [`Chopin::mount_module`](crate::Chopin::mount_module) skips disabled
+    /// modules entirely.
+    fn enabled(&self) -> bool {
+        true
+    }
+
+    /// Register the module's routes.
+    fn routes(&self, _router: &mut Router) {}
+
+    /// Apply pending migrations. Run by `--migrate`.
+    fn migrate(&self) -> ChopinResult<()> {
+        Ok(())
+    }
+
+    /// chopin-orm migrations owned by this module, in application order.
+    ///
+    /// Applied by `--migrate` through the executor given to
+    /// [`Chopin::migrations_executor`](crate::Chopin::migrations_executor) and
+    /// tracked per module in `_chopin_module_migrations`.
+    #[cfg(feature = "orm")]
+    fn migrations(&self) -> Vec<Box<dyn chopin_orm::Migration>> {
+        Vec::new()
+    }
+
+    /// Database the module's [`migrations`](Self::migrations) run against.
+    /// Each connection keeps its own migration ledger; anything other than
+    /// the default must be registered with [`db`](crate::db).
+    #[cfg(feature = "orm")]
+    fn connection(&self) -> &'static str {
+        chopin_orm::DEFAULT_CONNECTION
+    }
+
+    /// Revert the last `steps` migrations. Run by `--rollback N`.
+    fn rollback(&self, _steps: u32) -> ChopinResult<()> {
+        Ok(())
+    }
+
+    /// Insert seed data. Run by `--seed`.
+    fn seed(&self) -> ChopinResult<()> {
+        Ok(())
+    }
+
+    /// Called once before the server starts accepting connections.
+    fn on_start(&self) -> ChopinResult<()> {
+        Ok(())
+    }
+}
+
+/// Order `modules` so every module comes after its dependencies, returning
+/// indices into `modules`.
+///
+/// Independent modules keep their mount order. Fails on duplicate names,
+/// unknown dependencies and dependency cycles.
+pub fn dependency_order(modules: &[Box<dyn ChopinModule>]) -> ChopinResult<Vec<usize>> {
+    let index_of = |name: &str| modules.iter().position(|m| m.name() == name);
+
+    let mut deps: Vec<Vec<usize>> = Vec::with_capacity(modules.len());
+    for (i, module) in modules.iter().enumerate() {
+        if index_of(module.name()) != Some(i) {
+            return Err(ChopinError::Other(format!(
+                "module `{}` is mounted twice",
+                module.name()
+            )));
+        }
+        let mut resolved = Vec::new();
+        for dep in module.depends_on() {
+            let j = index_of(dep).ok_or_else(|| {
+                ChopinError::Other(format!(
+                    "module `{}` depends on `{dep}`, which is not mounted",
+                    module.name()
+                ))
+            })?;
+            resolved.push(j);
+        }
+        deps.push(resolved);
+    }
+
+    let mut order = Vec::with_capacity(modules.len());
+    let mut placed = vec![false; modules.len()];
+    while order.len() < modules.len() {
+        let next = (0..modules.len())
+            .find(|&i| !placed[i] && deps[i].iter().all(|&d| placed[d]))
+            .ok_or_else(|| {
+                let stuck: Vec<_> = (0..modules.len())
+                    .filter(|&i| !placed[i])
+                    .map(|i| modules[i].name())
+                    .collect();
+                ChopinError::Other(format!(
+                    "module dependency cycle among: {}",
+                    stuck.join(", ")
+                ))
+            })?;
+        placed[next] = true;
+        order.push(next);
+    }
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct M(&'static str, &'static [&'static str]);
+
+    impl ChopinModule for M {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+        fn depends_on(&self) -> &[&'static str] {
+            self.1
+        }
+    }
+
+    fn names(modules: &[Box<dyn ChopinModule>]) -> ChopinResult<Vec<&'static str>> {
+        Ok(dependency_order(modules)?
+            .into_iter()
+            .map(|i| modules[i].name())
+            .collect())
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let modules: Vec<Box<dyn ChopinModule>> = vec![
+            Box::new(M("billing", &["users", "audit"])),
+            Box::new(M("users", &[])),
+            Box::new(M("audit", &["users"])),
+            Box::new(M("blog", &[])),
+        ];
+        assert_eq!(
+            names(&modules).unwrap(),
+            ["users", "audit", "billing", "blog"]
+        );
+    }
+
+    #[test]
+    fn test_dependency_errors() {
+        let missing: Vec<Box<dyn ChopinModule>> = vec![Box::new(M("blog", &["users"]))];
+        let err = names(&missing).unwrap_err().to_string();
+        assert!(err.contains("depends on `users`"), "{err}");
+
+        let cycle: Vec<Box<dyn ChopinModule>> = vec![
+            Box::new(M("base", &[])),
+            Box::new(M("a", &["b"])),
+            Box::new(M("b", &["a"])),
+        ];
+        let err = names(&cycle).unwrap_err().to_string();
+        assert!(err.contains("cycle among: a, b"), "{err}");
+
+        let dup: Vec<Box<dyn ChopinModule>> = vec![Box::new(M("a", &[])), Box::new(M("a", &[]))];
+        assert!(names(&dup).unwrap_err().to_string().contains("twice"));
+    }
+}
@@ -0,0 +1,494 @@
+// src/notifications.rs
+//! In-app notifications (`orm` feature).
+//!
+//! Notifications are stored in the `chopin_notifications` table and read back
+//! through a small JSON API. Every stored notification is also handed to the
+//! registered [`NotificationChannel`]s, which is where email, webhook or push
+//! delivery plugs in, and bumps [`UPDATES`] so clients long-polling it with
+//! [`longpoll::respond`](crate::longpoll::respond) wake up.
+//!
+//! ```rust,ignore
+//! db::init_database(connect);
+//! notifications::set_current_user(|ctx| ctx.header("X-User-Id").map(str::to_string));
+//! notifications::add_channel(Box::new(EmailChannel::new(mailer)));
+//!
+//! Chopin::new()
+//!     .mount_module(NotificationsModule::new("/api/notifications"))
+//!     .serve("0.0.0.0:8080")?;
+//!
+//! // Anywhere in a handler:
+//! notifications::notify("42", &Notification::new("comment", "New reply").link("/posts/7"))?;
+//! ```
+//!
+//! Routes mounted by [`NotificationsModule`] under its prefix:
+//!
+//! | Method | Path            | Description                               |
+//! |--------|-----------------|-------------------------------------------|
+//! | GET    | `/`             | List (`?unread=true&limit=50`)            |
+//! | GET    | `/unread-count` | `{"unread": n}`                           |
+//! | POST   | `/:id/read`     | Mark one notification read                |
+//! | POST   | `/read-all`     | Mark all read, returns `{"updated": n}`   |
+use crate::db;
+use crate::error::ChopinResult;
+use crate::extract::Query;
+use crate::http::{Context, Response};
+use crate::longpoll::Event;
+use crate::module::ChopinModule;
+use crate::router::Router;
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+/// Bumped after every stored notification.
+pub static UPDATES: Event = Event::new();
+
+/// Maximum page size for the list endpoint; the default is half of it.
+pub const MAX_LIST_LIMIT: i64 = 100;
+
+/// A notification to deliver to one user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Application-defined category, e.g. `"comment"` or `"billing"`.
+    pub kind: String,
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    /// Where the client should navigate when the notification is opened.
+    #[serde(default)]
+    pub link: Option<String>,
+}
+
+impl Notification {
+    pub fn new(kind: impl Into<String>, title: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            title: title.into(),
+            body: String::new(),
+            link: None,
+        }
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn link(mut self, link: impl Into<String>) -> Self {
+        self.link = Some(link.into());
+        self
+    }
+}
+
+/// A notification as stored.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StoredNotification {
+    pub id: i64,
+    pub user_id: String,
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub read: bool,
+    /// Unix seconds.
+    pub created_at: i64,
+}
+
+/// A delivery target for new notifications (email, webhook, push, ...).
+pub trait NotificationChannel: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn deliver(&self, notification: &StoredNotification) -> ChopinResult<()>;
+}
+
+static CHANNELS: RwLock<Vec<Box<dyn NotificationChannel>>> = RwLock::new(Vec::new());
+static CURRENT_USER: OnceLock<fn(&Context) -> Option<String>> = OnceLock::new();
+
+/// Register a delivery channel.
+pub fn add_channel(channel: Box<dyn NotificationChannel>) {
+    if let Ok(mut channels) = CHANNELS.write() {
+        channels.push(channel);
+    }
+}
+
+/// Set how the endpoints identify the requesting user, typically from the
+/// authenticated token. Requests resolving to `None` get `401`.
+pub fn set_current_user(resolve: fn(&Context) -> Option<String>) {
+    let _ = CURRENT_USER.set(resolve);
+}
+
+/// Hand `stored` to every channel. A failing channel is logged and does not
+/// stop delivery to the others.
+pub fn fan_out(stored: &StoredNotification) {
+    UPDATES.notify();
+    let Ok(channels) = CHANNELS.read() else {
+        return;
+    };
+    for channel in channels.iter() {
+        if let Err(e) = channel.deliver(stored) {
+            eprintln!(
+                "[chopin] notification {} via {} failed: {e}",
+                stored.id,
+                channel.name()
+            );
+        }
+    }
+}
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_notifications (
+        id BIGSERIAL PRIMARY KEY,
+        user_id TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL DEFAULT '',
+        link TEXT,
+        read_at TIMESTAMPTZ,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    )
+"#;
+
+const CREATE_INDEX_SQL: &str = "CREATE INDEX IF NOT EXISTS chopin_notifications_user_idx \
+     ON chopin_notifications (user_id, read_at, id DESC)";
+
+const SELECT_COLUMNS: &str = "id, user_id, kind, title, body, link, read_at IS NOT NULL AS read, \
+     EXTRACT(EPOCH FROM created_at)::BIGINT AS created_at";
+
+/// Storage operations on `chopin_notifications`.
+pub struct NotificationStore;
+
+impl NotificationStore {
+    /// Creates the table and its index if they do not exist.
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        executor.execute(CREATE_INDEX_SQL, &[])?;
+        Ok(())
+    }
+
+    /// Store a notification for `user_id`. Does not fan out; see [`notify`].
+    pub fn insert(
+        executor: &mut dyn Executor,
+        user_id: &str,
+        n: &Notification,
+    ) -> OrmResult<StoredNotification> {
+        let sql = format!(
+            "INSERT INTO chopin_notifications (user_id, kind, title, body, link) \
+             VALUES ($1, $2, $3, $4, $5) RETURNING {SELECT_COLUMNS}"
+        );
+        let rows = executor.query(&sql, &[&user_id, &n.kind, &n.title, &n.body, &n.link])?;
+        let row = rows.first().ok_or(chopin_orm::OrmError::RecordNotFound)?;
+        from_row(row)
+    }
+
+    /// Newest first, at most `limit` (capped at [`MAX_LIST_LIMIT`]).
+    pub fn list(
+        executor: &mut dyn Executor,
+        user_id: &str,
+        unread_only: bool,
+        limit: i64,
+    ) -> OrmResult<Vec<StoredNotification>> {
+        let filter = if unread_only {
+            " AND read_at IS NULL"
+        } else {
+            ""
+        };
+        let sql = format!(
+            "SELECT {SELECT_COLUMNS} FROM chopin_notifications \
+             WHERE user_id = $1{filter} ORDER BY id DESC LIMIT $2"
+        );
+        let limit = limit.clamp(1, MAX_LIST_LIMIT);
+        executor
+            .query(&sql, &[&user_id, &limit])?
+            .iter()
+            .map(from_row)
+            .collect()
+    }
+
+    pub fn unread_count(executor: &mut dyn Executor, user_id: &str) -> OrmResult<i64> {
+        let rows = executor.query(
+            "SELECT COUNT(*) AS unread FROM chopin_notifications \
+             WHERE user_id = $1 AND read_at IS NULL",
+            &[&user_id],
+        )?;
+        match rows.first() {
+            Some(row) => Ok(row.get_typed_by_name("unread")?),
+            None => Ok(0),
+        }
+    }
+
+    /// Mark one of `user_id`'s notifications read. Returns `false` if it does
+    /// not exist, belongs to someone else, or was already read.
+    pub fn mark_read(executor: &mut dyn Executor, user_id: &str, id: i64) -> OrmResult<bool> {
+        let n = executor.execute(
+            "UPDATE chopin_notifications SET read_at = NOW() \
+             WHERE id = $1 AND user_id = $2 AND read_at IS NULL",
+            &[&id, &user_id],
+        )?;
+        Ok(n > 0)
+    }
+
+    /// Mark every unread notification of `user_id` read.
+    pub fn mark_all_read(executor: &mut dyn Executor, user_id: &str) -> OrmResult<u64> {
+        executor.execute(
+            "UPDATE chopin_notifications SET read_at = NOW() \
+             WHERE user_id = $1 AND read_at IS NULL",
+            &[&user_id],
+        )
+    }
+}
+
+fn from_row(row: &chopin_orm::Row) -> OrmResult<StoredNotification> {
+    Ok(StoredNotification {
+        id: row.get_typed_by_name("id")?,
+        user_id: row.get_typed_by_name("user_id")?,
+        notification: Notification {
+            kind: row.get_typed_by_name("kind")?,
+            title: row.get_typed_by_name("title")?,
+            body: row.get_typed_by_name("body")?,
+            link: row.get_typed_by_name("link")?,
+        },
+        read: row.get_typed_by_name("read")?,
+        created_at: row.get_typed_by_name("created_at")?,
+    })
+}
+
+/// Store a notification on this worker's connection and fan it out.
+pub fn notify(user_id: &str, notification: &Notification) -> ChopinResult<StoredNotification> {
+    let stored = db::with_db(|db| NotificationStore::insert(db, user_id, notification))?;
+    fan_out(&stored);
+    Ok(stored)
+}
+
+// ─── Endpoints ───────────────────────────────────────────────────────────────
+
+fn current_user(ctx: &Context) -> Option<String> {
+    CURRENT_USER.get().and_then(|resolve| resolve(ctx))
+}
+
+fn json<T: Serialize>(value: &T) -> Response {
+    match serde_json::to_vec(value) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+fn respond<T: Serialize>(result: ChopinResult<T>) -> Response {
+    match result {
+        Ok(value) => json(&value),
+        Err(_) => Response::server_error(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ListParams {
+    #[serde(default)]
+    unread: bool,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_limit() -> i64 {
+    MAX_LIST_LIMIT / 2
+}
+
+/// `GET {prefix}`
+pub fn list_handler(ctx: Context) -> Response {
+    let Some(user) = current_user(&ctx) else {
+        return Response::unauthorized();
+    };
+    let params = match ctx.extract::<Query<ListParams>>() {
+        Ok(Query(p)) => p,
+        Err(res) => return res,
+    };
+    respond(db::with_db(|db| {
+        NotificationStore::list(db, &user, params.unread, params.limit)
+    }))
+}
+
+/// `GET {prefix}/unread-count`
+pub fn unread_count_handler(ctx: Context) -> Response {
+    let Some(user) = current_user(&ctx) else {
+        return Response::unauthorized();
+    };
+    respond(
+        db::with_db(|db| NotificationStore::unread_count(db, &user))
+            .map(|unread| serde_json::json!({ "unread": unread })),
+    )
+}
+
+/// `POST {prefix}/:id/read`
+pub fn mark_read_handler(ctx: Context) -> Response {
+    let Some(user) = current_user(&ctx) else {
+        return Response::unauthorized();
+    };
+    let Some(id) = ctx.param("id").and_then(|id| id.parse::<i64>().ok()) else {
+        return Response::bad_request();
+    };
+    match db::with_db(|db| NotificationStore::mark_read(db, &user, id)) {
+        Ok(true) => Response::new(204),
+        Ok(false) => Response::not_found(),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// `POST {prefix}/read-all`
+pub fn mark_all_read_handler(ctx: Context) -> Response {
+    let Some(user) = current_user(&ctx) else {
+        return Response::unauthorized();
+    };
+    respond(
+        db::with_db(|db| NotificationStore::mark_all_read(db, &user))
+            .map(|updated| serde_json::json!({ "updated": updated })),
+    )
+}
+
+/// Mounts the notification endpoints and owns the table migration.
+pub struct NotificationsModule {
+    prefix: &'static str,
+}
+
+impl NotificationsModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+        }
+    }
+}
+
+impl Default for NotificationsModule {
+    fn default() -> Self {
+        Self::new("/api/notifications")
+    }
+}
+
+impl ChopinModule for NotificationsModule {
+    fn name(&self) -> &'static str {
+        "notifications"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        let p = self.prefix;
+        router.get(if p.is_empty() { "/" } else { p }, list_handler);
+        router.get(&format!("{p}/unread-count"), unread_count_handler);
+        router.post(&format!("{p}/:id/read"), mark_read_handler);
+        router.post(&format!("{p}/read-all"), mark_all_read_handler);
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreateNotificationsTable)]
+    }
+}
+
+struct CreateNotificationsTable;
+
+impl Migration for CreateNotificationsTable {
+    fn name(&self) -> &'static str {
+        "001_create_notifications"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        NotificationStore::ensure_table(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_notifications", &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Method;
+    use chopin_orm::{MockExecutor, mock_row};
+
+    fn stored_row() -> chopin_orm::Row {
+        mock_row!(
+            "id" => 7i64,
+            "user_id" => "42",
+            "kind" => "comment",
+            "title" => "New reply",
+            "body" => "",
+            "link" => Some("/posts/7".to_string()),
+            "read" => false,
+            "created_at" => 1_700_000_000i64,
+        )
+    }
+
+    #[test]
+    fn test_insert_and_list() {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![stored_row()]);
+        let n = Notification::new("comment", "New reply").link("/posts/7");
+        let stored = NotificationStore::insert(&mut db, "42", &n).unwrap();
+        assert_eq!(stored.id, 7);
+        assert_eq!(stored.notification, n);
+        assert!(!stored.read);
+        assert_eq!(db.executed_queries[0].1, 5);
+
+        db.push_result(vec![stored_row()]);
+        let list = NotificationStore::list(&mut db, "42", true, 1_000).unwrap();
+        assert_eq!(list.len(), 1);
+        let (sql, params) = &db.executed_queries[1];
+        assert!(sql.contains("read_at IS NULL"), "{sql}");
+        assert_eq!(*params, 2);
+
+        let json = serde_json::to_value(&list[0]).unwrap();
+        assert_eq!(json["kind"], "comment");
+        assert_eq!(json["link"], "/posts/7");
+    }
+
+    #[test]
+    fn test_unread_count_and_mark_read() {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![mock_row!("unread" => 3i64)]);
+        assert_eq!(NotificationStore::unread_count(&mut db, "42").unwrap(), 3);
+        assert!(NotificationStore::mark_read(&mut db, "42", 7).unwrap());
+        assert!(
+            db.executed_queries
+                .last()
+                .unwrap()
+                .0
+                .contains("user_id = $2")
+        );
+    }
+
+    struct Recorder;
+    static DELIVERED: std::sync::Mutex<Vec<i64>> = std::sync::Mutex::new(Vec::new());
+
+    impl NotificationChannel for Recorder {
+        fn name(&self) -> &'static str {
+            "recorder"
+        }
+        fn deliver(&self, n: &StoredNotification) -> ChopinResult<()> {
+            DELIVERED.lock().unwrap().push(n.id);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fan_out_reaches_channels_and_bumps_updates() {
+        add_channel(Box::new(Recorder));
+        let before = UPDATES.version();
+        let mut db = MockExecutor::new();
+        db.push_result(vec![stored_row()]);
+        let stored =
+            NotificationStore::insert(&mut db, "42", &Notification::new("k", "t")).unwrap();
+        fan_out(&stored);
+        assert!(DELIVERED.lock().unwrap().contains(&7));
+        assert!(UPDATES.version() > before);
+    }
+
+    #[test]
+    fn test_module_routes_require_user() {
+        let mut router = Router::new();
+        NotificationsModule::default().routes(&mut router);
+        router.finalize();
+        for (method, path) in [
+            (Method::Get, "/api/notifications"),
+            (Method::Get, "/api/notifications/unread-count"),
+            (Method::Post, "/api/notifications/3/read"),
+            (Method::Post, "/api/notifications/read-all"),
+        ] {
+            assert!(router.match_route(method, path).is_some(), "{path}");
+        }
+        let app = crate::testing::TestApp::new(router);
+        assert_eq!(app.get("/api/notifications").status, 401);
+    }
+}
@@ -1,57 +1,185 @@
 use crate::http::{Context, Method, Response};
-use crate::router::RouteDef;
+use crate::router::{MiddlewareFn, RouteDef};
 use serde_json::{Value, json};
 use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+static DOCS_CONFIG: OnceLock<DocsConfig> = OnceLock::new();
+static DEFAULT_DOCS_CONFIG: OnceLock<DocsConfig> = OnceLock::new();
+
+/// A named subset of the API served as its own spec, e.g. `admin` for `/admin`.
+#[derive(Clone, Debug)]
+pub struct DocsGroup {
+    pub name: String,
+    pub prefix: String,
+}
+
+/// Configuration for the built-in OpenAPI spec and Scalar docs pages.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// Chopin::new()
+///     .mount_all_routes()
+///     .with_docs(
+///         DocsConfig::from_env()
+///             .docs_path("/reference")
+///             .theme("purple")
+///             .group("v1", "/api/v1")
+///             .group("admin", "/admin")
+///             .guard(require_admin),
+///     )
+///     .serve("0.0.0.0:8080")?;
+/// ```
+#[derive(Clone)]
+pub struct DocsConfig {
+    /// Path of the full JSON spec. Default: `/openapi.json`.
+    pub spec_path: String,
+    /// Path of the Scalar UI. Default: `/docs`.
+    pub docs_path: String,
+    /// Base path of per-group specs (`{groups_path}/{name}`). Default: `/api-docs`.
+    pub groups_path: String,
+    /// `info.title` of the spec and `<title>` of the docs page.
+    pub title: String,
+    /// Scalar theme name (e.g. `"purple"`, `"moon"`). `None` uses Scalar's default.
+    pub theme: Option<String>,
+    /// When `false`, no docs routes are mounted at all.
+    pub enabled: bool,
+    /// Middleware applied to every docs route, e.g. an auth check for production.
+    pub guard: Option<MiddlewareFn>,
+    /// Route groups served as separate specs.
+    pub groups: Vec<DocsGroup>,
+}
+
+impl Default for DocsConfig {
+    fn default() -> Self {
+        Self {
+            spec_path: "/openapi.json".to_string(),
+            docs_path: "/docs".to_string(),
+            groups_path: "/api-docs".to_string(),
+            title: "Chopin API".to_string(),
+            theme: None,
+            enabled: true,
+            guard: None,
+            groups: Vec::new(),
+        }
+    }
+}
+
+impl DocsConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Default configuration, disabled when `CHOPIN_DOCS` is `off`, `false` or `0`.
+    pub fn from_env() -> Self {
+        let enabled = match std::env::var("CHOPIN_DOCS") {
+            Ok(v) => !matches!(v.to_ascii_lowercase().as_str(), "off" | "false" | "0"),
+            Err(_) => true,
+        };
+        Self::default().enabled(enabled)
+    }
+
+    pub fn spec_path(mut self, path: &str) -> Self {
+        self.spec_path = path.to_string();
+        self
+    }
+
+    pub fn docs_path(mut self, path: &str) -> Self {
+        self.docs_path = path.to_string();
+        self
+    }
+
+    pub fn groups_path(mut self, path: &str) -> Self {
+        self.groups_path = path.to_string();
+        self
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn theme(mut self, theme: &str) -> Self {
+        self.theme = Some(theme.to_string());
+        self
+    }
+
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn guard(mut self, mw: MiddlewareFn) -> Self {
+        self.guard = Some(mw);
+        self
+    }
+
+    /// Serve routes under `prefix` as a separate spec at `{groups_path}/{name}`,
+    /// with its own docs page at `{docs_path}/{name}`.
+    pub fn group(mut self, name: &str, prefix: &str) -> Self {
+        self.groups.push(DocsGroup {
+            name: name.to_string(),
+            prefix: prefix.to_string(),
+        });
+        self
+    }
+
+    /// Look up a configured group by name.
+    pub fn find_group(&self, name: &str) -> Option<&DocsGroup> {
+        self.groups.iter().find(|g| g.name == name)
+    }
+}
+
+/// Install the process-wide docs configuration read by the docs handlers.
+///
+/// Only the first call takes effect; [`Chopin::with_docs`](crate::Chopin::with_docs)
+/// calls this for you.
+pub fn set_docs_config(config: DocsConfig) {
+    let _ = DOCS_CONFIG.set(config);
+}
+
+/// The installed docs configuration, or the default one.
+pub fn docs_config() -> &'static DocsConfig {
+    DOCS_CONFIG
+        .get()
+        .unwrap_or_else(|| DEFAULT_DOCS_CONFIG.get_or_init(DocsConfig::default))
+}
 
 /// Generates the OpenAPI 3.0.0 JSON specification for all registered routes.
 pub fn generate_spec() -> Value {
-    let mut paths: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+    build_spec(&docs_config().title, None)
+}
 
-    for route in inventory::iter::<RouteDef> {
-        let method = match route.method {
-            Method::Get => "get",
-            Method::Post => "post",
-            Method::Put => "put",
-            Method::Delete => "delete",
-            Method::Patch => "patch",
-            Method::Head => "head",
-            Method::Options => "options",
-            Method::Trace => "trace",
-            Method::Connect => "connect",
-            Method::Unknown => "unknown",
-        };
+/// Generates the spec for a single configured group, or `None` if it does not exist.
+pub fn generate_group_spec(name: &str) -> Option<Value> {
+    let config = docs_config();
+    let group = config.find_group(name)?;
+    Some(build_spec(
+        &format!("{} ({})", config.title, group.name),
+        Some(&group.prefix),
+    ))
+}
 
-        // Convert Chopin path format (/users/:id) to OpenAPI format (/users/{id})
-        let mut openapi_path = String::new();
-        let mut parameters = Vec::new();
+fn build_spec(title: &str, prefix: Option<&str>) -> Value {
+    let mut paths: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
 
-        for segment in route.path.split('/') {
-            if segment.is_empty() {
-                continue;
-            }
-            if let Some(param) = segment.strip_prefix(':') {
-                openapi_path.push_str(&format!("/{{{}}}", param));
-                parameters.push(json!({
-                    "name": param,
-                    "in": "path",
-                    "required": true,
-                    "schema": { "type": "string" }
-                }));
-            } else if let Some(wildcard) = segment.strip_prefix('*') {
-                openapi_path.push_str(&format!("/{{{}}}", wildcard));
-                parameters.push(json!({
-                    "name": wildcard,
-                    "in": "path",
-                    "required": true,
-                    "schema": { "type": "string" }
-                }));
-            } else {
-                openapi_path.push_str(&format!("/{}", segment));
-            }
+    for route in inventory::iter::<RouteDef> {
+        if let Some(prefix) = prefix
+            && !path_has_prefix(route.path, prefix)
+        {
+            continue;
         }
 
-        if openapi_path.is_empty() {
-            openapi_path = "/".to_string();
+        let method = method_name(route.method);
+        let (openapi_path, mut parameters) = convert_path(route.path);
+        for typed in route.params {
+            if let Some(param) = parameters.iter_mut().find(|p| p["name"] == typed.name) {
+                param["schema"] = match typed.openapi_format {
+                    Some(format) => json!({ "type": typed.openapi_type, "format": format }),
+                    None => json!({ "type": typed.openapi_type }),
+                };
+            }
         }
 
         let mut operation = json!({
@@ -64,6 +192,13 @@ pub fn generate_spec() -> Value {
             }
         });
 
+        if !route.tags.is_empty() {
+            operation
+                .as_object_mut()
+                .unwrap()
+                .insert("tags".to_string(), json!(route.tags));
+        }
+
         if !parameters.is_empty() {
             operation
                 .as_object_mut()
@@ -77,10 +212,12 @@ pub fn generate_spec() -> Value {
             .insert(method.to_string(), operation);
     }
 
+    crate::recorder::merge_into(&mut paths);
+
     json!({
         "openapi": "3.0.0",
         "info": {
-            "title": "Chopin API",
+            "title": title,
             "version": "1.0.0",
             "description": "High-fidelity API documentation for the Chopin framework."
         },
@@ -88,6 +225,63 @@ pub fn generate_spec() -> Value {
     })
 }
 
+/// Lower-case OpenAPI operation key for `method`.
+pub(crate) fn method_name(method: Method) -> &'static str {
+    match method {
+        Method::Get => "get",
+        Method::Post => "post",
+        Method::Put => "put",
+        Method::Delete => "delete",
+        Method::Patch => "patch",
+        Method::Head => "head",
+        Method::Options => "options",
+        Method::Trace => "trace",
+        Method::Connect => "connect",
+        Method::Unknown => "unknown",
+    }
+}
+
+/// Convert Chopin path format (/users/:id) to OpenAPI format (/users/{id}),
+/// collecting the path parameters along the way.
+pub(crate) fn convert_path(path: &str) -> (String, Vec<Value>) {
+    let mut openapi_path = String::new();
+    let mut parameters = Vec::new();
+
+    for segment in path.split('/') {
+        if segment.is_empty() {
+            continue;
+        }
+        if let Some(param) = segment
+            .strip_prefix(':')
+            .or_else(|| segment.strip_prefix('*'))
+        {
+            openapi_path.push_str(&format!("/{{{}}}", param));
+            parameters.push(json!({
+                "name": param,
+                "in": "path",
+                "required": true,
+                "schema": { "type": "string" }
+            }));
+        } else {
+            openapi_path.push_str(&format!("/{}", segment));
+        }
+    }
+
+    if openapi_path.is_empty() {
+        openapi_path = "/".to_string();
+    }
+    (openapi_path, parameters)
+}
+
+/// Segment-aware prefix check: `/admin` matches `/admin` and `/admin/x`, not `/administrator`.
+fn path_has_prefix(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    match path.strip_prefix(prefix) {
+        Some(rest) => rest.is_empty() || rest.starts_with('/'),
+        None => false,
+    }
+}
+
 /// Handler for openapi.json
 pub fn openapi_json_handler(_ctx: Context) -> Response {
     let spec = generate_spec();
@@ -95,25 +289,81 @@ pub fn openapi_json_handler(_ctx: Context) -> Response {
     Response::json_bytes(bytes)
 }
 
+/// Handler for `{groups_path}/:group`.
+pub fn group_spec_handler(ctx: Context) -> Response {
+    match ctx.param("group").and_then(generate_group_spec) {
+        Some(spec) => Response::json_bytes(serde_json::to_vec(&spec).unwrap_or_default()),
+        None => Response::not_found(),
+    }
+}
+
 /// Handler for Scalar API Reference (at /docs)
 pub fn scalar_docs_handler(_ctx: Context) -> Response {
-    let html = r#"<!doctype html>
+    let config = docs_config();
+    html_response(render_docs_html(config, &config.spec_path))
+}
+
+/// Handler for the Scalar page of a single group (at `{docs_path}/:group`).
+pub fn group_docs_handler(ctx: Context) -> Response {
+    let config = docs_config();
+    match ctx.param("group").and_then(|g| config.find_group(g)) {
+        Some(group) => html_response(render_docs_html(
+            config,
+            &format!(
+                "{}/{}",
+                config.groups_path.trim_end_matches('/'),
+                group.name
+            ),
+        )),
+        None => Response::not_found(),
+    }
+}
+
+fn html_response(html: String) -> Response {
+    Response::text(html).with_header("Content-Type", "text/html; charset=utf-8")
+}
+
+/// Renders the Scalar page pointing at `spec_url`.
+pub fn render_docs_html(config: &DocsConfig, spec_url: &str) -> String {
+    let scalar_config = match &config.theme {
+        Some(theme) => json!({ "theme": theme }).to_string(),
+        None => "{}".to_string(),
+    };
+    format!(
+        r#"<!doctype html>
 <html>
   <head>
-    <title>Chopin API Reference</title>
+    <title>{title}</title>
     <meta charset="utf-8" />
     <meta name="viewport" content="width=device-width, initial-scale=1" />
     <style>
-      body { margin: 0; }
+      body {{ margin: 0; }}
     </style>
   </head>
   <body>
-    <script id="api-reference" data-url="/openapi.json"></script>
+    <script id="api-reference" data-url="{url}" data-configuration="{configuration}"></script>
     <script src="https://cdn.jsdelivr.net/npm/@scalar/api-reference"></script>
   </body>
-</html>"#;
+</html>"#,
+        title = escape_html(&config.title),
+        url = escape_html(spec_url),
+        configuration = escape_html(&scalar_config),
+    )
+}
 
-    Response::text(html.to_string()).with_header("Content-Type", "text/html; charset=utf-8")
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
 }
 
 #[cfg(test)]
@@ -127,4 +377,42 @@ mod tests {
         assert_eq!(spec["info"]["title"], "Chopin API");
         assert!(spec["paths"].is_object());
     }
+
+    #[test]
+    fn test_path_has_prefix() {
+        assert!(path_has_prefix("/admin", "/admin"));
+        assert!(path_has_prefix("/admin/users/:id", "/admin/"));
+        assert!(!path_has_prefix("/administrator", "/admin"));
+        assert!(!path_has_prefix("/api/v1", "/admin"));
+    }
+
+    #[test]
+    fn test_docs_config_builder() {
+        let cfg = DocsConfig::new()
+            .docs_path("/reference")
+            .theme("purple")
+            .enabled(false)
+            .group("admin", "/admin");
+        assert_eq!(cfg.docs_path, "/reference");
+        assert_eq!(cfg.spec_path, "/openapi.json");
+        assert!(!cfg.enabled);
+        assert_eq!(cfg.find_group("admin").unwrap().prefix, "/admin");
+        assert!(cfg.find_group("v2").is_none());
+    }
+
+    #[test]
+    fn test_render_docs_html_theme_and_escaping() {
+        let cfg = DocsConfig::new().title("<Shop> API").theme("moon");
+        let html = render_docs_html(&cfg, "/api-docs/v1");
+        assert!(html.contains("<title>&lt;Shop&gt; API</title>"));
+        assert!(html.contains(r#"data-url="/api-docs/v1""#));
+        assert!(html.contains("{&quot;theme&quot;:&quot;moon&quot;}"));
+    }
+
+    #[test]
+    fn test_build_spec_filters_by_prefix() {
+        let spec = build_spec("Test", Some("/__no_such_prefix"));
+        assert_eq!(spec["info"]["title"], "Test");
+        assert!(spec["paths"].as_object().unwrap().is_empty());
+    }
 }
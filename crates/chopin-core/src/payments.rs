@@ -0,0 +1,608 @@
+// src/payments.rs
+//! Stripe payments scaffold (`payments` feature).
+//!
+//! [`PaymentsModule`] mounts a webhook endpoint that verifies the
+//! `Stripe-Signature` header, records each event id so redeliveries are
+//! ignored, keeps the `chopin_payments` and `chopin_subscriptions` tables in
+//! sync and hands a typed [`PaymentEvent`] to every listener registered with
+//! [`on_payment_event`]:
+//!
+//! ```rust,ignore
+//! db::init_database(connect);
+//! payments::set_webhook_secret(std::env::var("STRIPE_WEBHOOK_SECRET")?);
+//! payments::on_payment_event(|event| {
+//!     if let PaymentEvent::SubscriptionChanged(sub) = event {
+//!         grant_plan(&sub.customer, &sub.status);
+//!     }
+//! });
+//!
+//! Chopin::new().mount_module(PaymentsModule::default());
+//! // POST /api/payments/stripe/webhook
+//! ```
+//!
+//! Events may arrive out of order; a row is only overwritten by an event
+//! created at the same time or later than the one that last wrote it.
+use crate::db;
+use crate::form::constant_time_eq;
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::Router;
+use chopin_orm::{Executor, Migration, OrmResult};
+use chopin_pg::auth::hmac_sha256;
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Header carrying the webhook signature.
+pub const SIGNATURE_HEADER: &str = "Stripe-Signature";
+
+/// Maximum age of a signed webhook, as recommended by Stripe.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(300);
+
+// ─── Signature verification ──────────────────────────────────────────────────
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The header has no `t=` timestamp.
+    MissingTimestamp,
+    /// The header has no `v1=` signature.
+    MissingSignature,
+    /// The timestamp is outside the tolerance window.
+    Expired,
+    /// No `v1` signature matches the payload.
+    Mismatch,
+}
+
+impl std::fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignatureError::MissingTimestamp => write!(f, "signature header has no timestamp"),
+            SignatureError::MissingSignature => write!(f, "signature header has no v1 signature"),
+            SignatureError::Expired => write!(f, "signature timestamp outside tolerance"),
+            SignatureError::Mismatch => write!(f, "signature does not match payload"),
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+/// Verify a `Stripe-Signature` header (`t=<unix>,v1=<hex>[,v1=...]`) for
+/// `payload` against the endpoint's signing `secret`.
+pub fn verify_signature(
+    payload: &[u8],
+    header: &str,
+    secret: &str,
+    tolerance: Duration,
+    now: SystemTime,
+) -> Result<(), SignatureError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", t)) => timestamp = t.parse::<u64>().ok(),
+            Some(("v1", sig)) => signatures.extend(decode_hex(sig)),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or(SignatureError::MissingTimestamp)?;
+    if signatures.is_empty() {
+        return Err(SignatureError::MissingSignature);
+    }
+
+    let now = now.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    if now.abs_diff(timestamp) > tolerance.as_secs() {
+        return Err(SignatureError::Expired);
+    }
+
+    let mut signed = Vec::with_capacity(payload.len() + 12);
+    signed.extend_from_slice(timestamp.to_string().as_bytes());
+    signed.push(b'.');
+    signed.extend_from_slice(payload);
+    let expected = hmac_sha256(secret.as_bytes(), &signed);
+
+    if signatures
+        .iter()
+        .any(|sig| constant_time_eq(sig, &expected))
+    {
+        Ok(())
+    } else {
+        Err(SignatureError::Mismatch)
+    }
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+// ─── Events ──────────────────────────────────────────────────────────────────
+
+/// The envelope of a Stripe webhook event.
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Unix seconds.
+    pub created: i64,
+    #[serde(default)]
+    pub livemode: bool,
+    pub data: StripeEventData,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StripeEventData {
+    pub object: serde_json::Value,
+}
+
+/// A one-off payment (Stripe payment intent).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PaymentRecord {
+    pub provider_id: String,
+    pub customer: Option<String>,
+    /// In the currency's smallest unit.
+    pub amount: i64,
+    pub currency: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SubscriptionRecord {
+    pub provider_id: String,
+    pub customer: String,
+    /// Stripe status, e.g. `active`, `past_due` or `canceled`.
+    pub status: String,
+    /// Unix seconds.
+    pub current_period_end: Option<i64>,
+}
+
+/// A webhook event mapped onto the records this module stores.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PaymentEvent {
+    PaymentSucceeded(PaymentRecord),
+    PaymentFailed(PaymentRecord),
+    CheckoutCompleted {
+        session_id: String,
+        customer: Option<String>,
+        subscription: Option<String>,
+    },
+    SubscriptionChanged(SubscriptionRecord),
+    /// Any event type not mapped above.
+    Other {
+        event_type: String,
+    },
+}
+
+impl PaymentEvent {
+    pub fn from_stripe(event: &StripeEvent) -> Self {
+        let obj = &event.data.object;
+        let str_field = |name: &str| obj.get(name).and_then(|v| v.as_str()).map(str::to_string);
+        let payment = || PaymentRecord {
+            provider_id: str_field("id").unwrap_or_default(),
+            customer: str_field("customer"),
+            amount: obj.get("amount").and_then(|v| v.as_i64()).unwrap_or(0),
+            currency: str_field("currency").unwrap_or_default(),
+            status: str_field("status").unwrap_or_default(),
+        };
+
+        match event.event_type.as_str() {
+            "payment_intent.succeeded" => PaymentEvent::PaymentSucceeded(payment()),
+            "payment_intent.payment_failed" => PaymentEvent::PaymentFailed(payment()),
+            "checkout.session.completed" => PaymentEvent::CheckoutCompleted {
+                session_id: str_field("id").unwrap_or_default(),
+                customer: str_field("customer"),
+                subscription: str_field("subscription"),
+            },
+            "customer.subscription.created"
+            | "customer.subscription.updated"
+            | "customer.subscription.deleted" => {
+                PaymentEvent::SubscriptionChanged(SubscriptionRecord {
+                    provider_id: str_field("id").unwrap_or_default(),
+                    customer: str_field("customer").unwrap_or_default(),
+                    status: str_field("status").unwrap_or_default(),
+                    current_period_end: obj.get("current_period_end").and_then(|v| v.as_i64()),
+                })
+            }
+            other => PaymentEvent::Other {
+                event_type: other.to_string(),
+            },
+        }
+    }
+}
+
+static LISTENERS: RwLock<Vec<fn(&PaymentEvent)>> = RwLock::new(Vec::new());
+static WEBHOOK_SECRET: OnceLock<String> = OnceLock::new();
+
+/// Register a listener for processed events. Redelivered events are not
+/// passed on again.
+pub fn on_payment_event(listener: fn(&PaymentEvent)) {
+    if let Ok(mut listeners) = LISTENERS.write() {
+        listeners.push(listener);
+    }
+}
+
+/// Set the webhook signing secret (`whsec_...`). The webhook endpoint
+/// answers `500` until it is set.
+pub fn set_webhook_secret(secret: impl Into<String>) {
+    let _ = WEBHOOK_SECRET.set(secret.into());
+}
+
+fn publish(event: &PaymentEvent) {
+    let Ok(listeners) = LISTENERS.read() else {
+        return;
+    };
+    for listener in listeners.iter() {
+        listener(event);
+    }
+}
+
+// ─── Storage ─────────────────────────────────────────────────────────────────
+
+/// Storage for payments, subscriptions and processed event ids.
+pub struct PaymentStore;
+
+impl PaymentStore {
+    pub fn ensure_tables(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(
+            "CREATE TABLE IF NOT EXISTS chopin_payment_events (
+                event_id TEXT PRIMARY KEY,
+                event_type TEXT NOT NULL,
+                received_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            &[],
+        )?;
+        executor.execute(
+            "CREATE TABLE IF NOT EXISTS chopin_payments (
+                provider_id TEXT PRIMARY KEY,
+                customer TEXT,
+                amount BIGINT NOT NULL,
+                currency TEXT NOT NULL,
+                status TEXT NOT NULL,
+                event_created BIGINT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            &[],
+        )?;
+        executor.execute(
+            "CREATE TABLE IF NOT EXISTS chopin_subscriptions (
+                provider_id TEXT PRIMARY KEY,
+                customer TEXT NOT NULL,
+                status TEXT NOT NULL,
+                current_period_end BIGINT,
+                event_created BIGINT NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )",
+            &[],
+        )?;
+        executor.execute(
+            "CREATE INDEX IF NOT EXISTS chopin_subscriptions_customer_idx \
+             ON chopin_subscriptions (customer)",
+            &[],
+        )?;
+        Ok(())
+    }
+
+    /// Apply `event` once. Returns `None` if its id was already processed.
+    ///
+    /// The upserts are idempotent, so a concurrent redelivery can at worst
+    /// write the same rows twice; only the delivery that records the event id
+    /// gets `Some`.
+    pub fn process(
+        executor: &mut dyn Executor,
+        event: &StripeEvent,
+    ) -> OrmResult<Option<PaymentEvent>> {
+        let seen = executor.query(
+            "SELECT 1 FROM chopin_payment_events WHERE event_id = $1",
+            &[&event.id],
+        )?;
+        if !seen.is_empty() {
+            return Ok(None);
+        }
+
+        let typed = PaymentEvent::from_stripe(event);
+        match &typed {
+            PaymentEvent::PaymentSucceeded(p) | PaymentEvent::PaymentFailed(p) => {
+                executor.execute(
+                    "INSERT INTO chopin_payments \
+                     (provider_id, customer, amount, currency, status, event_created) \
+                     VALUES ($1, $2, $3, $4, $5, $6) \
+                     ON CONFLICT (provider_id) DO UPDATE SET customer = EXCLUDED.customer, \
+                     amount = EXCLUDED.amount, currency = EXCLUDED.currency, \
+                     status = EXCLUDED.status, event_created = EXCLUDED.event_created, \
+                     updated_at = NOW() \
+                     WHERE chopin_payments.event_created <= EXCLUDED.event_created",
+                    &[
+                        &p.provider_id,
+                        &p.customer,
+                        &p.amount,
+                        &p.currency,
+                        &p.status,
+                        &event.created,
+                    ],
+                )?;
+            }
+            PaymentEvent::SubscriptionChanged(s) => {
+                executor.execute(
+                    "INSERT INTO chopin_subscriptions \
+                     (provider_id, customer, status, current_period_end, event_created) \
+                     VALUES ($1, $2, $3, $4, $5) \
+                     ON CONFLICT (provider_id) DO UPDATE SET customer = EXCLUDED.customer, \
+                     status = EXCLUDED.status, current_period_end = EXCLUDED.current_period_end, \
+                     event_created = EXCLUDED.event_created, updated_at = NOW() \
+                     WHERE chopin_subscriptions.event_created <= EXCLUDED.event_created",
+                    &[
+                        &s.provider_id,
+                        &s.customer,
+                        &s.status,
+                        &s.current_period_end,
+                        &event.created,
+                    ],
+                )?;
+            }
+            PaymentEvent::CheckoutCompleted { .. } | PaymentEvent::Other { .. } => {}
+        }
+
+        let recorded = executor.execute(
+            "INSERT INTO chopin_payment_events (event_id, event_type) VALUES ($1, $2) \
+             ON CONFLICT (event_id) DO NOTHING",
+            &[&event.id, &event.event_type],
+        )?;
+        Ok((recorded > 0).then_some(typed))
+    }
+
+    /// The customer's subscriptions, most recently updated first.
+    pub fn subscriptions(
+        executor: &mut dyn Executor,
+        customer: &str,
+    ) -> OrmResult<Vec<SubscriptionRecord>> {
+        let rows = executor.query(
+            "SELECT provider_id, customer, status, current_period_end \
+             FROM chopin_subscriptions WHERE customer = $1 ORDER BY updated_at DESC",
+            &[&customer],
+        )?;
+        rows.iter()
+            .map(|row| {
+                Ok(SubscriptionRecord {
+                    provider_id: row.get_typed_by_name("provider_id")?,
+                    customer: row.get_typed_by_name("customer")?,
+                    status: row.get_typed_by_name("status")?,
+                    current_period_end: row.get_typed_by_name("current_period_end")?,
+                })
+            })
+            .collect()
+    }
+}
+
+// ─── Endpoint ────────────────────────────────────────────────────────────────
+
+/// `POST {prefix}/stripe/webhook`
+pub fn stripe_webhook_handler(ctx: Context) -> Response {
+    let Some(secret) = WEBHOOK_SECRET.get() else {
+        eprintln!("[chopin] stripe webhook received but no secret is set");
+        return Response::server_error();
+    };
+    let Some(header) = ctx.header(SIGNATURE_HEADER) else {
+        return Response::bad_request();
+    };
+    let payload = ctx.req.body;
+    if verify_signature(
+        payload,
+        header,
+        secret,
+        DEFAULT_TOLERANCE,
+        SystemTime::now(),
+    )
+    .is_err()
+    {
+        return Response::bad_request();
+    }
+    let Ok(event) = serde_json::from_slice::<StripeEvent>(payload) else {
+        return Response::bad_request();
+    };
+
+    match db::with_db(|db| PaymentStore::process(db, &event)) {
+        Ok(Some(typed)) => {
+            publish(&typed);
+            Response::new(200)
+        }
+        // Already processed: acknowledge so Stripe stops retrying.
+        Ok(None) => Response::new(200),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Mounts the Stripe webhook and owns the payment tables.
+pub struct PaymentsModule {
+    prefix: &'static str,
+}
+
+impl PaymentsModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+        }
+    }
+}
+
+impl Default for PaymentsModule {
+    fn default() -> Self {
+        Self::new("/api/payments")
+    }
+}
+
+impl ChopinModule for PaymentsModule {
+    fn name(&self) -> &'static str {
+        "payments"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.post(
+            &format!("{}/stripe/webhook", self.prefix),
+            stripe_webhook_handler,
+        );
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreatePaymentTables)]
+    }
+}
+
+struct CreatePaymentTables;
+
+impl Migration for CreatePaymentTables {
+    fn name(&self) -> &'static str {
+        "001_create_payment_tables"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        PaymentStore::ensure_tables(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(
+            "DROP TABLE IF EXISTS chopin_subscriptions, chopin_payments, chopin_payment_events",
+            &[],
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chopin_orm::MockExecutor;
+
+    const SECRET: &str = "whsec_test";
+
+    fn sign(payload: &[u8], t: u64) -> String {
+        let mut signed = format!("{t}.").into_bytes();
+        signed.extend_from_slice(payload);
+        let mac = hmac_sha256(SECRET.as_bytes(), &signed);
+        let hex: String = mac.iter().map(|b| format!("{b:02x}")).collect();
+        format!("t={t},v1={hex}")
+    }
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    fn event(json: &str) -> StripeEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let payload = br#"{"id":"evt_1"}"#;
+        let header = sign(payload, 1_700_000_000);
+        let now = at(1_700_000_010);
+        assert_eq!(
+            verify_signature(payload, &header, SECRET, DEFAULT_TOLERANCE, now),
+            Ok(())
+        );
+        // An extra, stale v1 signature (secret rotation) does not hurt.
+        let rotated = format!("{header},v1=00ff");
+        assert!(verify_signature(payload, &rotated, SECRET, DEFAULT_TOLERANCE, now).is_ok());
+
+        assert_eq!(
+            verify_signature(b"{}", &header, SECRET, DEFAULT_TOLERANCE, now),
+            Err(SignatureError::Mismatch)
+        );
+        assert_eq!(
+            verify_signature(
+                payload,
+                &header,
+                SECRET,
+                DEFAULT_TOLERANCE,
+                at(1_700_001_000)
+            ),
+            Err(SignatureError::Expired)
+        );
+        assert_eq!(
+            verify_signature(payload, "v1=00", SECRET, DEFAULT_TOLERANCE, now),
+            Err(SignatureError::MissingTimestamp)
+        );
+        assert_eq!(
+            verify_signature(payload, "t=1700000000", SECRET, DEFAULT_TOLERANCE, now),
+            Err(SignatureError::MissingSignature)
+        );
+    }
+
+    #[test]
+    fn test_typed_events() {
+        let e = event(
+            r#"{"id":"evt_1","type":"payment_intent.succeeded","created":10,
+                "data":{"object":{"id":"pi_1","amount":1999,"currency":"usd",
+                "customer":"cus_1","status":"succeeded"}}}"#,
+        );
+        assert_eq!(
+            PaymentEvent::from_stripe(&e),
+            PaymentEvent::PaymentSucceeded(PaymentRecord {
+                provider_id: "pi_1".into(),
+                customer: Some("cus_1".into()),
+                amount: 1999,
+                currency: "usd".into(),
+                status: "succeeded".into(),
+            })
+        );
+
+        let e = event(
+            r#"{"id":"evt_2","type":"customer.subscription.deleted","created":10,
+                "data":{"object":{"id":"sub_1","customer":"cus_1","status":"canceled"}}}"#,
+        );
+        let PaymentEvent::SubscriptionChanged(sub) = PaymentEvent::from_stripe(&e) else {
+            panic!("expected subscription event");
+        };
+        assert_eq!(sub.status, "canceled");
+        assert_eq!(sub.current_period_end, None);
+
+        let e =
+            event(r#"{"id":"evt_3","type":"charge.refunded","created":1,"data":{"object":{}}}"#);
+        assert_eq!(
+            PaymentEvent::from_stripe(&e),
+            PaymentEvent::Other {
+                event_type: "charge.refunded".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_process_is_idempotent() {
+        let e = event(
+            r#"{"id":"evt_9","type":"customer.subscription.updated","created":10,
+                "data":{"object":{"id":"sub_1","customer":"cus_1","status":"active",
+                "current_period_end":1800000000}}}"#,
+        );
+
+        let mut db = MockExecutor::new();
+        db.push_result(vec![]); // not seen yet
+        assert!(matches!(
+            PaymentStore::process(&mut db, &e).unwrap(),
+            Some(PaymentEvent::SubscriptionChanged(_))
+        ));
+        assert!(db.executed_queries[1].0.contains("chopin_subscriptions"));
+        assert!(db.executed_queries[1].0.contains("event_created <="));
+
+        let mut db = MockExecutor::new();
+        db.push_result(vec![chopin_orm::mock_row!("?column?" => 1i32)]);
+        assert_eq!(PaymentStore::process(&mut db, &e).unwrap(), None);
+        assert_eq!(db.executed_queries.len(), 1);
+    }
+
+    #[test]
+    fn test_module_route() {
+        let mut router = Router::new();
+        PaymentsModule::default().routes(&mut router);
+        router.finalize();
+        assert!(
+            router
+                .match_route(crate::http::Method::Post, "/api/payments/stripe/webhook")
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,388 @@
+// src/plugin.rs
+//! Plugins: reusable modules shipped as their own crates.
+//!
+//! A [`Plugin`] bundles what a third-party crate adds to an application —
+//! [`ChopinModule`]s with their routes and migrations, config sections it
+//! reads, and maintenance commands — and registers itself with
+//! [`register_plugin!`](crate::register_plugin). The application picks up
+//! every plugin linked into it with [`Chopin::mount_plugins`](crate::Chopin::mount_plugins):
+//!
+//! ```rust,ignore
+//! // In the `chopin-cms` crate:
+//! pub struct Cms;
+//!
+//! impl Plugin for Cms {
+//!     fn name(&self) -> &'static str { "cms" }
+//!     fn modules(&self) -> Vec<Box<dyn ChopinModule>> { vec![Box::new(CmsModule)] }
+//!     fn config_sections(&self) -> Vec<ConfigSection> {
+//!         vec![ConfigSection::of::<CmsSettings>().required()]
+//!     }
+//!     fn commands(&self) -> Vec<PluginCommand> {
+//!         vec![PluginCommand::new("import", "Import pages from a directory", import_pages)]
+//!     }
+//! }
+//!
+//! chopin_core::register_plugin!(Cms);
+//!
+//! // In the application:
+//! use chopin_cms as _; // keep the crate linked so its registration is seen
+//!
+//! Chopin::new().mount_plugins().serve("0.0.0.0:8080")?;
+//! ```
+//!
+//! Plugin modules are mounted like any other, so they take part in
+//! `--migrate`, `--rollback`, `--seed` and dependency ordering. Before the
+//! server starts, each declared config section present in the config is
+//! deserialized, and a missing [`required`](ConfigSection::required) one is
+//! an error. Commands run as `app --run cms:import ./pages` (or
+//! `chopin run cms:import ./pages`) instead of the server;
+//! `--print-plugins` (`chopin plugins`) lists what is installed.
+//!
+//! Every hook of [`Plugin`] but [`name`](Plugin::name) has a default, so
+//! hooks added in later releases do not break existing plugins.
+use crate::config::{Config, SettingsSection};
+use crate::error::{ChopinError, ChopinResult};
+use crate::module::ChopinModule;
+
+/// Command-line flag running a plugin command instead of the server.
+pub const RUN_FLAG: &str = "--run";
+
+/// A third-party extension registered with
+/// [`register_plugin!`](crate::register_plugin).
+pub trait Plugin: Send + Sync {
+    /// Unique plugin name; prefixes its commands.
+    fn name(&self) -> &'static str;
+
+    /// Modules to mount with the plugin.
+    fn modules(&self) -> Vec<Box<dyn ChopinModule>> {
+        Vec::new()
+    }
+
+    /// Config sections the plugin reads, checked at startup.
+    fn config_sections(&self) -> Vec<ConfigSection> {
+        Vec::new()
+    }
+
+    /// Maintenance commands, run with `--run <plugin>:<command>`.
+    fn commands(&self) -> Vec<PluginCommand> {
+        Vec::new()
+    }
+}
+
+/// Link-time registration record emitted by
+/// [`register_plugin!`](crate::register_plugin).
+pub struct PluginDef {
+    /// Version of the crate that registered the plugin.
+    pub version: &'static str,
+    pub create: fn() -> Box<dyn Plugin>,
+}
+
+inventory::collect!(PluginDef);
+
+/// Register a [`Plugin`] value so that
+/// [`Chopin::mount_plugins`](crate::Chopin::mount_plugins) finds it.
+///
+/// ```rust,ignore
+/// chopin_core::register_plugin!(Payments::default());
+/// ```
+#[macro_export]
+macro_rules! register_plugin {
+    ($plugin:expr $(,)?) => {
+        $crate::inventory::submit! {
+            $crate::plugin::PluginDef {
+                version: env!("CARGO_PKG_VERSION"),
+                create: {
+                    fn __chopin_create_plugin() -> ::std::boxed::Box<dyn $crate::plugin::Plugin> {
+                        ::std::boxed::Box::new($plugin)
+                    }
+                    __chopin_create_plugin
+                },
+            }
+        }
+    };
+}
+
+/// A plugin found at link time.
+pub struct Registered {
+    pub version: &'static str,
+    pub plugin: Box<dyn Plugin>,
+}
+
+/// Every registered plugin, sorted by name.
+pub fn discover() -> Vec<Registered> {
+    let mut plugins: Vec<Registered> = inventory::iter::<PluginDef>
+        .into_iter()
+        .map(|def| Registered {
+            version: def.version,
+            plugin: (def.create)(),
+        })
+        .collect();
+    plugins.sort_by_key(|p| p.plugin.name());
+    plugins
+}
+
+/// A config section a plugin reads.
+#[derive(Clone, Copy)]
+pub struct ConfigSection {
+    pub name: &'static str,
+    pub required: bool,
+    validate: fn(&Config) -> ChopinResult<()>,
+}
+
+impl ConfigSection {
+    /// The section of a [`SettingsSection`] type.
+    pub fn of<T: SettingsSection>() -> Self {
+        Self {
+            name: T::SECTION,
+            required: false,
+            validate: validate_section::<T>,
+        }
+    }
+
+    /// Fail startup when the section is missing.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+
+    /// Check the section against `config` (`None` when no config is
+    /// installed).
+    pub fn check(&self, config: Option<&Config>) -> ChopinResult<()> {
+        match config {
+            Some(config) if config.has_section(self.name) => (self.validate)(config),
+            _ if self.required => Err(ChopinError::Other(format!(
+                "missing required config section [{}]",
+                self.name
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn validate_section<T: SettingsSection>(config: &Config) -> ChopinResult<()> {
+    config.extension::<T>(T::SECTION).map(drop)
+}
+
+/// Body of a plugin command; gets the arguments after the command name.
+pub type CommandFn = fn(&[String]) -> ChopinResult<()>;
+
+/// A maintenance command contributed by a plugin.
+#[derive(Clone, Copy)]
+pub struct PluginCommand {
+    pub name: &'static str,
+    /// One-line description shown by `--print-plugins`.
+    pub about: &'static str,
+    pub run: CommandFn,
+}
+
+impl PluginCommand {
+    pub fn new(name: &'static str, about: &'static str, run: CommandFn) -> Self {
+        Self { name, about, run }
+    }
+}
+
+/// The command and its arguments if `args` (excluding the program name)
+/// hold `--run <plugin>:<command> [args...]`.
+pub fn run_args<I, S>(args: I) -> ChopinResult<Option<(String, Vec<String>)>>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let arg = arg.as_ref();
+        let command = if arg == RUN_FLAG {
+            match args.next() {
+                Some(command) => command.as_ref().to_string(),
+                None => {
+                    return Err(ChopinError::Other(format!(
+                        "{RUN_FLAG} requires a <plugin>:<command> name"
+                    )));
+                }
+            }
+        } else if let Some(command) = arg.strip_prefix("--run=") {
+            command.to_string()
+        } else {
+            continue;
+        };
+        let rest = args.map(|a| a.as_ref().to_string()).collect();
+        return Ok(Some((command, rest)));
+    }
+    Ok(None)
+}
+
+/// Check `plugins` before they are used: names must be unique and every
+/// declared config section must load from `config`.
+pub fn check(plugins: &[Registered], config: Option<&Config>) -> ChopinResult<()> {
+    for (i, p) in plugins.iter().enumerate() {
+        let name = p.plugin.name();
+        if plugins[..i].iter().any(|q| q.plugin.name() == name) {
+            return Err(ChopinError::Other(format!(
+                "plugin `{name}` is registered twice"
+            )));
+        }
+        for section in p.plugin.config_sections() {
+            section
+                .check(config)
+                .map_err(|e| ChopinError::Other(format!("plugin `{name}`: {e}")))?;
+        }
+    }
+    Ok(())
+}
+
+/// Run `plugin:command` from `plugins` with `args`.
+pub fn run_command(plugins: &[Registered], command: &str, args: &[String]) -> ChopinResult<()> {
+    let (plugin_name, command_name) = command.split_once(':').ok_or_else(|| {
+        ChopinError::Other(format!(
+            "plugin command `{command}` must be written <plugin>:<command>"
+        ))
+    })?;
+    let plugin = plugins
+        .iter()
+        .find(|p| p.plugin.name() == plugin_name)
+        .ok_or_else(|| ChopinError::Other(format!("no plugin named `{plugin_name}`")))?;
+    let cmd = plugin
+        .plugin
+        .commands()
+        .into_iter()
+        .find(|c| c.name == command_name)
+        .ok_or_else(|| {
+            ChopinError::Other(format!(
+                "plugin `{plugin_name}` has no command `{command_name}`"
+            ))
+        })?;
+    (cmd.run)(args).map_err(|e| ChopinError::Other(format!("{command}: {e}")))
+}
+
+/// Human-readable listing of `plugins` for `--print-plugins`.
+pub fn describe(plugins: &[Registered]) -> String {
+    let mut out = String::new();
+    for p in plugins {
+        let plugin = &p.plugin;
+        out.push_str(&format!("{} {}\n", plugin.name(), p.version));
+        for module in plugin.modules() {
+            out.push_str(&format!("  module   {}\n", module.name()));
+        }
+        for section in plugin.config_sections() {
+            let required = if section.required { " (required)" } else { "" };
+            out.push_str(&format!("  config   [{}]{required}\n", section.name));
+        }
+        for cmd in plugin.commands() {
+            let name = format!("{}:{}", plugin.name(), cmd.name);
+            out.push_str(&format!("  command  {name:<24}{}\n", cmd.about));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Clone, Deserialize)]
+    struct ShopSettings {
+        #[allow(dead_code)]
+        currency: String,
+    }
+
+    impl SettingsSection for ShopSettings {
+        const SECTION: &'static str = "shop";
+    }
+
+    struct ShopModule;
+
+    impl ChopinModule for ShopModule {
+        fn name(&self) -> &'static str {
+            "shop"
+        }
+    }
+
+    static IMPORTED: AtomicUsize = AtomicUsize::new(0);
+
+    fn import(args: &[String]) -> ChopinResult<()> {
+        IMPORTED.fetch_add(args.len(), Ordering::SeqCst);
+        Ok(())
+    }
+
+    struct Shop;
+
+    impl Plugin for Shop {
+        fn name(&self) -> &'static str {
+            "test-shop"
+        }
+
+        fn modules(&self) -> Vec<Box<dyn ChopinModule>> {
+            vec![Box::new(ShopModule)]
+        }
+
+        fn config_sections(&self) -> Vec<ConfigSection> {
+            vec![ConfigSection::of::<ShopSettings>().required()]
+        }
+
+        fn commands(&self) -> Vec<PluginCommand> {
+            vec![PluginCommand::new("import", "Import products", import)]
+        }
+    }
+
+    crate::register_plugin!(Shop);
+
+    fn shop() -> Vec<Registered> {
+        discover()
+            .into_iter()
+            .filter(|p| p.plugin.name() == "test-shop")
+            .collect()
+    }
+
+    #[test]
+    fn test_registered_plugins_are_discovered() {
+        let plugins = shop();
+        assert_eq!(plugins.len(), 1);
+        assert_eq!(plugins[0].version, env!("CARGO_PKG_VERSION"));
+        let listing = describe(&plugins);
+        assert!(listing.contains("  module   shop\n"), "{listing}");
+        assert!(
+            listing.contains("  config   [shop] (required)\n"),
+            "{listing}"
+        );
+        assert!(listing.contains("test-shop:import"), "{listing}");
+    }
+
+    #[test]
+    fn test_config_sections_are_checked() {
+        let plugins = shop();
+        let ok = Config::parse("[shop]\ncurrency = \"EUR\"\n").unwrap();
+        assert!(check(&plugins, Some(&ok)).is_ok());
+
+        let err = check(&plugins, None).unwrap_err().to_string();
+        assert!(
+            err.contains("missing required config section [shop]"),
+            "{err}"
+        );
+        let invalid = Config::parse("[shop]\ncurrency = 3\n").unwrap();
+        assert!(check(&plugins, Some(&invalid)).is_err());
+
+        let twice: Vec<Registered> = shop().into_iter().chain(shop()).collect();
+        let err = check(&twice, Some(&ok)).unwrap_err().to_string();
+        assert!(err.contains("registered twice"), "{err}");
+    }
+
+    #[test]
+    fn test_commands_run_with_their_arguments() {
+        let (command, args) = run_args(["--port", "1", "--run", "test-shop:import", "a", "--b"])
+            .unwrap()
+            .unwrap();
+        assert_eq!(command, "test-shop:import");
+        assert_eq!(args, ["a", "--b"]);
+        assert_eq!(run_args(["--migrate"]).unwrap(), None);
+        assert!(run_args(["--run"]).is_err());
+
+        let plugins = shop();
+        run_command(&plugins, &command, &args).unwrap();
+        assert_eq!(IMPORTED.load(Ordering::SeqCst), 2);
+        let err = run_command(&plugins, "test-shop:export", &[]).unwrap_err();
+        assert!(err.to_string().contains("no command `export`"));
+        assert!(run_command(&plugins, "import", &[]).is_err());
+    }
+}
@@ -0,0 +1,505 @@
+// src/presence.rs
+//! Presence tracking for long-lived connections.
+//!
+//! Handlers serving a WebSocket, long-poll or streaming client call [`join`]
+//! and keep the returned [`PresenceGuard`] for as long as the connection is
+//! open; dropping it leaves the channel. A user with several connections
+//! (tabs, devices) stays present until the last one closes.
+//!
+//! ```rust,ignore
+//! presence::on_change(|e| println!("{} {:?} {}", e.user, e.kind, e.channel));
+//!
+//! fn chat_socket(ctx: Context) -> Response {
+//!     let _presence = presence::join("room:7", user_id(&ctx));
+//!     // ... serve the connection ...
+//! }
+//!
+//! Chopin::new().mount_module(PresenceModule::new("/api/presence"));
+//! ```
+//!
+//! State is kept per worker: each thread records the connections it serves
+//! in its own shard, so joins and leaves never touch another worker's state.
+//! Reads ([`members`], [`is_present`], the endpoint) merge every shard. A
+//! join or leave is published as an event only when it changes the merged
+//! view; when two workers race on the same user, an event may be reported
+//! twice or not at all, but the merged member list is always exact.
+//!
+//! Clients that cannot signal a disconnect should call [`heartbeat`]
+//! periodically so that [`sweep`] can expire them.
+use crate::http::{Context, Response};
+use crate::longpoll::Event;
+use crate::module::ChopinModule;
+use crate::router::Router;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::time::{Duration, Instant};
+
+/// Bumped on every join and leave.
+pub static UPDATES: Event = Event::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceKind {
+    Joined,
+    Left,
+}
+
+/// A user's first connection to a channel opened, or their last one closed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PresenceEvent {
+    pub channel: String,
+    pub user: String,
+    pub kind: PresenceKind,
+}
+
+struct Member {
+    /// Ids of the guards holding this member, from [`ShardState::next_id`].
+    connections: Vec<u64>,
+    last_seen: Instant,
+}
+
+#[derive(Default)]
+struct ShardState {
+    channels: HashMap<String, BTreeMap<String, Member>>,
+    next_id: u64,
+}
+
+/// One worker's presence state. Only its own thread writes to it, except
+/// for guards dropped elsewhere and [`sweep`]; other threads read it to
+/// merge the view, so the lock is practically uncontended.
+#[derive(Default)]
+struct Shard {
+    state: Mutex<ShardState>,
+}
+
+impl Shard {
+    fn lock(&self) -> std::sync::MutexGuard<'_, ShardState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn contains(&self, channel: &str, user: &str) -> bool {
+        self.lock()
+            .channels
+            .get(channel)
+            .is_some_and(|members| members.contains_key(user))
+    }
+}
+
+/// Every live shard, registered once per thread on its first join.
+static SHARDS: RwLock<Vec<Weak<Shard>>> = RwLock::new(Vec::new());
+
+thread_local! {
+    static SHARD: Arc<Shard> = {
+        let shard = Arc::new(Shard::default());
+        let mut shards = SHARDS.write().unwrap_or_else(|e| e.into_inner());
+        shards.retain(|s| s.strong_count() > 0);
+        shards.push(Arc::downgrade(&shard));
+        shard
+    };
+}
+
+fn shards() -> Vec<Arc<Shard>> {
+    SHARDS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect()
+}
+
+/// Whether any shard other than `except` holds `user` on `channel`.
+fn present_elsewhere(except: &Arc<Shard>, channel: &str, user: &str) -> bool {
+    shards()
+        .iter()
+        .any(|s| !Arc::ptr_eq(s, except) && s.contains(channel, user))
+}
+
+type Listener = fn(&PresenceEvent);
+
+static LISTENERS: RwLock<Vec<Listener>> = RwLock::new(Vec::new());
+/// Bumped by [`on_change`] so workers know to refresh their copy.
+static LISTENERS_VERSION: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static LOCAL_LISTENERS: RefCell<(u64, Vec<Listener>)> =
+        const { RefCell::new((0, Vec::new())) };
+}
+
+/// Register a listener for join and leave events. Listeners run on the
+/// thread that caused the change, after the state has been updated.
+pub fn on_change(listener: Listener) {
+    let mut listeners = LISTENERS.write().unwrap_or_else(|e| e.into_inner());
+    listeners.push(listener);
+    LISTENERS_VERSION.fetch_add(1, Ordering::Release);
+}
+
+fn publish(events: Vec<PresenceEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    UPDATES.notify();
+    LOCAL_LISTENERS.with(|local| {
+        let mut local = local.borrow_mut();
+        let version = LISTENERS_VERSION.load(Ordering::Acquire);
+        if local.0 != version {
+            local.1 = LISTENERS.read().unwrap_or_else(|e| e.into_inner()).clone();
+            local.0 = version;
+        }
+        for event in &events {
+            for listener in &local.1 {
+                listener(event);
+            }
+        }
+    });
+}
+
+/// Add a connection for `user` on `channel`, on the calling worker.
+pub fn join(channel: &str, user: impl Into<String>) -> PresenceGuard {
+    let user = user.into();
+    let shard = SHARD.with(Arc::clone);
+    let (id, first_here) = {
+        let mut state = shard.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        let member = state
+            .channels
+            .entry(channel.to_string())
+            .or_default()
+            .entry(user.clone())
+            .or_insert(Member {
+                connections: Vec::new(),
+                last_seen: Instant::now(),
+            });
+        member.connections.push(id);
+        member.last_seen = Instant::now();
+        (id, member.connections.len() == 1)
+    };
+    if first_here && !present_elsewhere(&shard, channel, &user) {
+        publish(vec![PresenceEvent {
+            channel: channel.to_string(),
+            user: user.clone(),
+            kind: PresenceKind::Joined,
+        }]);
+    }
+    PresenceGuard {
+        channel: channel.to_string(),
+        user,
+        shard,
+        id,
+    }
+}
+
+/// Release connection `id`. Ids that are gone (swept) are ignored, so a
+/// stale guard cannot release a connection opened after it.
+fn leave(shard: &Arc<Shard>, channel: &str, user: &str, id: u64) {
+    let gone_here = {
+        let mut state = shard.lock();
+        let Some(members) = state.channels.get_mut(channel) else {
+            return;
+        };
+        let Some(member) = members.get_mut(user) else {
+            return;
+        };
+        let Some(pos) = member.connections.iter().position(|&c| c == id) else {
+            return;
+        };
+        member.connections.swap_remove(pos);
+        let gone = member.connections.is_empty();
+        if gone {
+            members.remove(user);
+            if members.is_empty() {
+                state.channels.remove(channel);
+            }
+        }
+        gone
+    };
+    if gone_here && !present_elsewhere(shard, channel, user) {
+        publish(vec![PresenceEvent {
+            channel: channel.to_string(),
+            user: user.to_string(),
+            kind: PresenceKind::Left,
+        }]);
+    }
+}
+
+/// Keeps a connection present; leaves the channel on drop. It may be
+/// dropped on any thread.
+pub struct PresenceGuard {
+    channel: String,
+    user: String,
+    shard: Arc<Shard>,
+    id: u64,
+}
+
+impl std::fmt::Debug for PresenceGuard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresenceGuard")
+            .field("channel", &self.channel)
+            .field("user", &self.user)
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl PresenceGuard {
+    pub fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    pub fn user(&self) -> &str {
+        &self.user
+    }
+
+    /// Mark this connection's user as seen now.
+    pub fn heartbeat(&self) {
+        touch(&self.shard, &self.channel, &self.user);
+    }
+}
+
+impl Drop for PresenceGuard {
+    fn drop(&mut self) {
+        leave(&self.shard, &self.channel, &self.user, self.id);
+    }
+}
+
+fn touch(shard: &Shard, channel: &str, user: &str) {
+    if let Some(m) = shard
+        .lock()
+        .channels
+        .get_mut(channel)
+        .and_then(|members| members.get_mut(user))
+    {
+        m.last_seen = Instant::now();
+    }
+}
+
+/// Mark `user` as seen on `channel` now, on every worker.
+pub fn heartbeat(channel: &str, user: &str) {
+    for shard in shards() {
+        touch(&shard, channel, user);
+    }
+}
+
+/// Remove members not seen for `max_idle`, regardless of open connections.
+/// Returns the number removed, counting a user once per worker holding
+/// them. Dropping a swept member's guard afterwards does nothing, even if
+/// the user has rejoined since.
+pub fn sweep(max_idle: Duration) -> usize {
+    let mut removed = BTreeSet::new();
+    let mut n = 0;
+    for shard in shards() {
+        let mut state = shard.lock();
+        for (channel, members) in state.channels.iter_mut() {
+            members.retain(|user, m| {
+                let keep = m.last_seen.elapsed() < max_idle;
+                if !keep {
+                    n += 1;
+                    removed.insert((channel.clone(), user.clone()));
+                }
+                keep
+            });
+        }
+        state.channels.retain(|_, members| !members.is_empty());
+    }
+    let events = removed
+        .into_iter()
+        .filter(|(channel, user)| !is_present(channel, user))
+        .map(|(channel, user)| PresenceEvent {
+            channel,
+            user,
+            kind: PresenceKind::Left,
+        })
+        .collect();
+    publish(events);
+    n
+}
+
+/// Users present on `channel` on any worker, sorted.
+pub fn members(channel: &str) -> Vec<String> {
+    let mut users = BTreeSet::new();
+    for shard in shards() {
+        if let Some(members) = shard.lock().channels.get(channel) {
+            users.extend(members.keys().cloned());
+        }
+    }
+    users.into_iter().collect()
+}
+
+pub fn is_present(channel: &str, user: &str) -> bool {
+    shards().iter().any(|s| s.contains(channel, user))
+}
+
+// ─── Endpoint ────────────────────────────────────────────────────────────────
+
+#[derive(Serialize)]
+struct ChannelPresence<'a> {
+    channel: &'a str,
+    users: Vec<String>,
+}
+
+fn channel_handler(ctx: Context) -> Response {
+    let channel = ctx.param("channel").unwrap_or("");
+    let body = ChannelPresence {
+        channel,
+        users: members(channel),
+    };
+    match serde_json::to_vec(&body) {
+        Ok(body) => Response::json_bytes(body),
+        Err(_) => Response::server_error(),
+    }
+}
+
+/// Mounts `GET {prefix}/:channel`, returning `{"channel": .., "users": [..]}`.
+pub struct PresenceModule {
+    prefix: &'static str,
+}
+
+impl PresenceModule {
+    pub fn new(prefix: &'static str) -> Self {
+        Self {
+            prefix: prefix.trim_end_matches('/'),
+        }
+    }
+}
+
+impl Default for PresenceModule {
+    fn default() -> Self {
+        Self::new("/api/presence")
+    }
+}
+
+impl ChopinModule for PresenceModule {
+    fn name(&self) -> &'static str {
+        "presence"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        router.get(&format!("{}/:channel", self.prefix), channel_handler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EVENTS: Mutex<Vec<PresenceEvent>> = Mutex::new(Vec::new());
+    // `sweep` sees every channel, so tests touching presence state run one at a time.
+    static SERIAL: Mutex<()> = Mutex::new(());
+
+    fn record(e: &PresenceEvent) {
+        if e.channel.starts_with("test:") {
+            EVENTS.lock().unwrap().push(e.clone());
+        }
+    }
+
+    fn listen() {
+        static ONCE: std::sync::Once = std::sync::Once::new();
+        ONCE.call_once(|| on_change(record));
+    }
+
+    fn events_for(channel: &str) -> Vec<(String, PresenceKind)> {
+        EVENTS
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|e| e.channel == channel)
+            .map(|e| (e.user.clone(), e.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_join_leave_counts_connections() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        listen();
+        let a1 = join("test:room", "alice");
+        let a2 = join("test:room", "alice");
+        let b = join("test:room", "bob");
+        assert_eq!(members("test:room"), ["alice", "bob"]);
+
+        drop(a1);
+        assert!(is_present("test:room", "alice"));
+        drop(a2);
+        drop(b);
+        assert!(members("test:room").is_empty());
+
+        assert_eq!(
+            events_for("test:room"),
+            [
+                ("alice".to_string(), PresenceKind::Joined),
+                ("bob".to_string(), PresenceKind::Joined),
+                ("alice".to_string(), PresenceKind::Left),
+                ("bob".to_string(), PresenceKind::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sweep_expires_idle_members() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let guard = join("test:sweep", "carol");
+        let before = UPDATES.version();
+        assert_eq!(sweep(Duration::ZERO), 1);
+        assert!(!is_present("test:sweep", "carol"));
+        assert!(UPDATES.version() > before);
+        // Dropping the guard of a swept member is a no-op.
+        drop(guard);
+        assert!(!is_present("test:sweep", "carol"));
+    }
+
+    #[test]
+    fn test_stale_guard_does_not_release_rejoined_member() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let stale = join("test:rejoin", "erin");
+        sweep(Duration::ZERO);
+        let fresh = join("test:rejoin", "erin");
+        drop(stale);
+        assert!(is_present("test:rejoin", "erin"));
+        drop(fresh);
+        assert!(!is_present("test:rejoin", "erin"));
+    }
+
+    #[test]
+    fn test_members_merge_across_workers() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        listen();
+        let here = join("test:merge", "frank");
+        let there =
+            std::thread::spawn(|| (join("test:merge", "frank"), join("test:merge", "gina")))
+                .join()
+                .unwrap();
+        assert_eq!(members("test:merge"), ["frank", "gina"]);
+
+        // The other worker's guards can be dropped from here.
+        drop(there);
+        assert_eq!(members("test:merge"), ["frank"]);
+        drop(here);
+        assert!(members("test:merge").is_empty());
+
+        assert_eq!(
+            events_for("test:merge"),
+            [
+                ("frank".to_string(), PresenceKind::Joined),
+                ("gina".to_string(), PresenceKind::Joined),
+                ("gina".to_string(), PresenceKind::Left),
+                ("frank".to_string(), PresenceKind::Left),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_channel_endpoint() {
+        let _serial = SERIAL.lock().unwrap_or_else(|e| e.into_inner());
+        let mut router = Router::new();
+        PresenceModule::default().routes(&mut router);
+        let app = crate::testing::TestApp::new(router);
+        let _g = join("test:lobby", "dave");
+        let res = app.get("/api/presence/test:lobby");
+        assert_eq!(res.status, 200);
+        let json = res.json().unwrap();
+        assert_eq!(json["channel"], "test:lobby");
+        assert_eq!(json["users"], serde_json::json!(["dave"]));
+    }
+}
@@ -0,0 +1,299 @@
+// src/profiler.rs
+//! Flamegraphs of slow requests for development (`profiler` feature).
+//!
+//! [`ProfilerModule`] layers [`slow_request_profiler`] over every route. Each
+//! request is sampled with pprof while its handler runs; if it takes longer
+//! than the threshold, the samples taken on its worker thread are written as
+//! an SVG flamegraph named after the route, and the response names the file
+//! in an `X-Profile` header:
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(
+//!     ProfilerModule::new("target/flamegraphs").threshold(Duration::from_millis(50)),
+//! );
+//! // GET /users/42 → target/flamegraphs/GET-users-@id-1760790000123.svg
+//! ```
+//!
+//! pprof drives a single process-wide timer, so one request is sampled at a
+//! time and requests on other workers meanwhile run unsampled. Samples are
+//! taken on CPU time: a request that is slow because it waits on the
+//! database shows up with few samples. The module is only enabled in debug
+//! builds.
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Header naming the flamegraph written for a slow request.
+pub const PROFILE_HEADER: &str = "X-Profile";
+
+pub const DEFAULT_THRESHOLD: Duration = Duration::from_millis(100);
+
+/// Samples per second of CPU time. Odd so it doesn't beat with periodic work.
+pub const DEFAULT_FREQUENCY: i32 = 999;
+
+struct Settings {
+    dir: PathBuf,
+    threshold: Duration,
+    frequency: i32,
+}
+
+static SETTINGS: RwLock<Option<Arc<Settings>>> = RwLock::new(None);
+
+/// Set while a request is being sampled.
+static SAMPLING: AtomicBool = AtomicBool::new(false);
+
+/// Releases [`SAMPLING`], also when the handler panics.
+struct SamplingSlot;
+
+impl Drop for SamplingSlot {
+    fn drop(&mut self) {
+        SAMPLING.store(false, Ordering::Release);
+    }
+}
+
+/// File-name-safe route key: the method, then the path segments with
+/// captured parameters replaced by `@name`, joined by `-`.
+pub fn route_key(ctx: &Context) -> String {
+    let mut parts = vec![crate::openapi::method_name(ctx.req.method).to_uppercase()];
+    let mut params = ctx.params[..ctx.param_count as usize].iter().peekable();
+    let mut rest = ctx.req.path.trim_matches('/');
+    while !rest.is_empty() {
+        let (segment, tail) = rest.split_once('/').unwrap_or((rest, ""));
+        match params.peek() {
+            Some((name, value)) if *value == segment => {
+                parts.push(format!("@{}", sanitize(name)));
+                params.next();
+            }
+            _ => parts.push(sanitize(segment)),
+        }
+        rest = tail;
+    }
+    parts.join("-")
+}
+
+fn sanitize(segment: &str) -> String {
+    segment
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Middleware sampling each request and writing a flamegraph for those
+/// slower than the [`ProfilerModule`] threshold.
+pub fn slow_request_profiler(ctx: Context, next: BoxedHandler) -> Response {
+    let settings = SETTINGS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let Some(settings) = settings else {
+        return next(ctx);
+    };
+    if SAMPLING
+        .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+        .is_err()
+    {
+        return next(ctx);
+    }
+    let _slot = SamplingSlot;
+    let guard = match pprof::ProfilerGuardBuilder::default()
+        .frequency(settings.frequency)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()
+    {
+        Ok(guard) => guard,
+        Err(e) => {
+            eprintln!("[chopin] profiler: could not start sampling: {e}");
+            return next(ctx);
+        }
+    };
+
+    let key = route_key(&ctx);
+    let start = Instant::now();
+    let res = next(ctx);
+    if start.elapsed() < settings.threshold {
+        return res;
+    }
+
+    match write_flamegraph(&guard, &settings.dir, &key) {
+        Ok(Some(path)) => res.with_header(PROFILE_HEADER, path.display().to_string()),
+        Ok(None) => res,
+        Err(e) => {
+            eprintln!("[chopin] profiler: {key}: {e}");
+            res
+        }
+    }
+}
+
+/// Write the samples taken on this thread. `None` when there were none,
+/// e.g. because the request was slow while blocked rather than on CPU.
+fn write_flamegraph(
+    guard: &pprof::ProfilerGuard<'_>,
+    dir: &Path,
+    key: &str,
+) -> ChopinResult<Option<PathBuf>> {
+    let mut report = guard
+        .report()
+        .build()
+        .map_err(|e| ChopinError::Other(format!("building profile: {e}")))?;
+    let thread = unsafe { libc::pthread_self() } as u64;
+    report.data.retain(|frames, _| frames.thread_id == thread);
+    if report.data.is_empty() {
+        return Ok(None);
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or(0);
+    let path = dir.join(format!("{key}-{millis}.svg"));
+    let file = std::fs::File::create(&path)?;
+    report
+        .flamegraph(std::io::BufWriter::new(file))
+        .map_err(|e| ChopinError::Other(format!("writing flamegraph: {e}")))?;
+    Ok(Some(path))
+}
+
+/// Mounts [`slow_request_profiler`] globally.
+pub struct ProfilerModule {
+    dir: PathBuf,
+    threshold: Duration,
+    frequency: i32,
+}
+
+impl ProfilerModule {
+    /// Write flamegraphs into `dir`, created on the first capture.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            threshold: DEFAULT_THRESHOLD,
+            frequency: DEFAULT_FREQUENCY,
+        }
+    }
+
+    /// Requests taking at least this long are captured.
+    pub fn threshold(mut self, threshold: Duration) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    /// Sampling rate in Hz.
+    pub fn frequency(mut self, hz: i32) -> Self {
+        self.frequency = hz.max(1);
+        self
+    }
+}
+
+impl Default for ProfilerModule {
+    fn default() -> Self {
+        Self::new("target/flamegraphs")
+    }
+}
+
+impl ChopinModule for ProfilerModule {
+    fn name(&self) -> &'static str {
+        "profiler"
+    }
+
+    fn enabled(&self) -> bool {
+        cfg!(debug_assertions)
+    }
+
+    fn routes(&self, router: &mut Router) {
+        *SETTINGS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Settings {
+            dir: self.dir.clone(),
+            threshold: self.threshold,
+            frequency: self.frequency,
+        }));
+        router.layer(slow_request_profiler);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{MAX_HEADERS, MAX_PARAMS, Method, Request};
+
+    fn ctx<'a>(method: Method, path: &'a str, params: &[(&'a str, &'a str)]) -> Context<'a> {
+        let mut ctx = Context {
+            req: Request {
+                method,
+                path,
+                query: None,
+                headers: [("", ""); MAX_HEADERS],
+                header_count: 0,
+                body: &[],
+            },
+            params: [("", ""); MAX_PARAMS],
+            param_count: params.len() as u8,
+        };
+        ctx.params[..params.len()].copy_from_slice(params);
+        ctx
+    }
+
+    #[test]
+    fn test_route_key() {
+        assert_eq!(route_key(&ctx(Method::Get, "/", &[])), "GET");
+        assert_eq!(
+            route_key(&ctx(Method::Get, "/users/42", &[("id", "42")])),
+            "GET-users-@id"
+        );
+        assert_eq!(
+            route_key(&ctx(
+                Method::Post,
+                "/orgs/7/users/7/",
+                &[("org", "7"), ("user", "7")]
+            )),
+            "POST-orgs-@org-users-@user"
+        );
+        assert_eq!(
+            route_key(&ctx(Method::Get, "/files/a b.txt", &[])),
+            "GET-files-a_b.txt"
+        );
+    }
+
+    fn busy(_ctx: Context) -> Response {
+        let start = Instant::now();
+        let mut x = 0u64;
+        while start.elapsed() < Duration::from_millis(300) {
+            x = std::hint::black_box(x.wrapping_mul(31).wrapping_add(7));
+        }
+        Response::text(x.to_string())
+    }
+
+    fn quick(_ctx: Context) -> Response {
+        Response::text("ok")
+    }
+
+    #[test]
+    fn test_writes_flamegraphs_for_slow_requests() {
+        let dir = std::env::temp_dir().join(format!("chopin-profiler-{}", std::process::id()));
+        let mut router = Router::new();
+        ProfilerModule::new(&dir)
+            .threshold(Duration::from_millis(100))
+            .routes(&mut router);
+        router.get("/profiler-test/busy/:n", busy);
+        router.get("/profiler-test/quick", quick);
+        let app = crate::testing::TestApp::new(router);
+
+        assert!(
+            app.get("/profiler-test/quick")
+                .header(PROFILE_HEADER)
+                .is_none()
+        );
+
+        let res = app.get("/profiler-test/busy/3");
+        assert_eq!(res.status, 200);
+        let path = PathBuf::from(res.header(PROFILE_HEADER).unwrap());
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("GET-profiler-test-busy-@n-"), "{name}");
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains("<svg"));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,580 @@
+// src/quota.rs
+//! Daily request quotas per plan, enforced from the metering counters
+//! (`orm` feature).
+//!
+//! Each [`Subject`] found by the [`MeteringModule`] resolvers is on a plan,
+//! and each plan allows a number of requests per UTC day. Once a subject has
+//! used its allowance, further requests are answered `429 Too Many Requests`
+//! until midnight UTC. Every metered response carries the usual headers for
+//! the subject closest to its limit:
+//!
+//! | Header                  | Value                                      |
+//! |-------------------------|--------------------------------------------|
+//! | `X-RateLimit-Limit`     | requests allowed today                     |
+//! | `X-RateLimit-Remaining` | requests left after this one               |
+//! | `X-RateLimit-Reset`     | Unix seconds at which the count starts over |
+//! | `Retry-After`           | seconds until then (`429` responses only)  |
+//!
+//! Plans come from code, from the `[quotas]` section of `Chopin.toml`, or
+//! from the `chopin_plans` table:
+//!
+//! ```toml
+//! [quotas]
+//! default_plan = "free"
+//!
+//! [quotas.plans.free]
+//! requests_per_day = 1000
+//!
+//! [quotas.plans.pro]
+//! requests_per_day = 100000
+//! ```
+//!
+//! ```rust,ignore
+//! fn plan_of(subject: &Subject) -> Option<String> {
+//!     tenants::plan(&subject.id)
+//! }
+//!
+//! Chopin::new()
+//!     .mount_module(MeteringModule::new().resolve(tenant))
+//!     .mount_module(QuotaModule::from_config().plan_for(plan_of));
+//! ```
+//!
+//! Quotas are checked before the handler runs, so only subjects found by the
+//! resolvers are limited; subjects named later with
+//! [`attribute`](crate::metering::attribute) are metered but not checked.
+//! Rejected requests are not charged. Counts combine the stored usage with
+//! this process's unflushed counters; other instances' unflushed requests
+//! show up after their next flush. The metering granularity must divide a
+//! day for the counts to line up with the quota day.
+use crate::config::{Settings, SettingsSection};
+use crate::db;
+use crate::error::{ChopinError, ChopinResult};
+use crate::http::Response;
+use crate::metering::{self, Subject};
+use crate::module::ChopinModule;
+use crate::router::Router;
+use chopin_orm::{Executor, Migration, OrmResult};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// Length of a quota period, in seconds.
+pub const DAY: i64 = 86_400;
+
+/// Allowances of one plan.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Plan {
+    /// `None` for no limit.
+    #[serde(default)]
+    pub requests_per_day: Option<u64>,
+}
+
+impl Plan {
+    pub fn daily(requests: u64) -> Self {
+        Self {
+            requests_per_day: Some(requests),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::default()
+    }
+}
+
+/// The `[quotas]` section of `Chopin.toml`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QuotaSettings {
+    /// Plan of subjects the [`plan_for`](QuotaModule::plan_for) resolver
+    /// does not place.
+    #[serde(default)]
+    pub default_plan: Option<String>,
+    #[serde(default)]
+    pub plans: HashMap<String, Plan>,
+}
+
+impl SettingsSection for QuotaSettings {
+    const SECTION: &'static str = "quotas";
+}
+
+/// Names the plan a subject is on.
+pub type PlanResolver = fn(&Subject) -> Option<String>;
+
+/// A subject's standing against its plan today.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QuotaStatus {
+    pub subject: Subject,
+    pub plan: String,
+    pub limit: u64,
+    pub used: u64,
+    /// Unix seconds at which the count starts over.
+    pub reset: i64,
+}
+
+impl QuotaStatus {
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.used)
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.used >= self.limit
+    }
+
+    /// The status once the request being served is counted.
+    pub(crate) fn after_request(mut self) -> Self {
+        self.used += 1;
+        self
+    }
+
+    /// Add the `X-RateLimit-*` headers to `res`.
+    pub fn apply(&self, res: &mut Response) {
+        res.headers.add("X-RateLimit-Limit", self.limit);
+        res.headers.add("X-RateLimit-Remaining", self.remaining());
+        res.headers.add("X-RateLimit-Reset", self.reset);
+    }
+
+    /// `429` answer for a subject that has used up its quota.
+    fn rejection(&self, now: i64) -> Response {
+        let body = serde_json::json!({
+            "error": "quota_exceeded",
+            "message": format!(
+                "Daily quota of {} requests for plan '{}' exhausted",
+                self.limit, self.plan
+            ),
+            "subject": self.subject,
+            "plan": self.plan,
+            "limit": self.limit,
+            "used": self.used,
+            "reset": self.reset,
+        });
+        let mut res = match serde_json::to_vec(&body) {
+            Ok(body) => Response::json_bytes(body),
+            Err(_) => Response::new(429),
+        };
+        res.status = 429;
+        self.apply(&mut res);
+        res.headers.add("Retry-After", (self.reset - now).max(0));
+        res
+    }
+}
+
+// ─── Plan storage ────────────────────────────────────────────────────────────
+
+const CREATE_TABLE_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS chopin_plans (
+        name TEXT PRIMARY KEY,
+        requests_per_day BIGINT
+    )
+"#;
+
+/// Storage operations on `chopin_plans`.
+pub struct PlanStore;
+
+impl PlanStore {
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(CREATE_TABLE_SQL, &[])?;
+        Ok(())
+    }
+
+    pub fn list(executor: &mut dyn Executor) -> OrmResult<Vec<(String, Plan)>> {
+        executor
+            .query(
+                "SELECT name, requests_per_day FROM chopin_plans ORDER BY name",
+                &[],
+            )?
+            .iter()
+            .map(|row| {
+                let limit: Option<i64> = row.get_typed(1)?;
+                Ok((
+                    row.get_typed(0)?,
+                    Plan {
+                        requests_per_day: limit.map(|n| n.max(0) as u64),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Create `name` or replace its allowances.
+    pub fn set(executor: &mut dyn Executor, name: &str, plan: &Plan) -> OrmResult<()> {
+        let limit = plan.requests_per_day.map(|n| n.min(i64::MAX as u64) as i64);
+        executor.execute(
+            "INSERT INTO chopin_plans (name, requests_per_day) VALUES ($1, $2) \
+             ON CONFLICT (name) DO UPDATE SET requests_per_day = EXCLUDED.requests_per_day",
+            &[&name, &limit],
+        )?;
+        Ok(())
+    }
+
+    /// Returns `false` if there was no such plan.
+    pub fn remove(executor: &mut dyn Executor, name: &str) -> OrmResult<bool> {
+        Ok(executor.execute("DELETE FROM chopin_plans WHERE name = $1", &[&name])? > 0)
+    }
+}
+
+/// Plan definitions by name.
+type Plans = Arc<HashMap<String, Plan>>;
+
+/// Plans from `chopin_plans`, re-read at most every `refresh`. While the
+/// table cannot be read the last good copy stays in use.
+fn stored_plans(refresh: Duration) -> ChopinResult<Plans> {
+    static CACHE: Mutex<Option<(Instant, Plans)>> = Mutex::new(None);
+
+    if let Some((loaded, plans)) = &*CACHE.lock().unwrap_or_else(|e| e.into_inner())
+        && loaded.elapsed() < refresh
+    {
+        return Ok(plans.clone());
+    }
+    match db::with_db(PlanStore::list) {
+        Ok(list) => {
+            let plans = Arc::new(list.into_iter().collect::<HashMap<_, _>>());
+            *CACHE.lock().unwrap_or_else(|e| e.into_inner()) =
+                Some((Instant::now(), plans.clone()));
+            Ok(plans)
+        }
+        Err(e) => match &*CACHE.lock().unwrap_or_else(|e| e.into_inner()) {
+            Some((_, plans)) => Ok(plans.clone()),
+            None => Err(e),
+        },
+    }
+}
+
+// ─── Enforcement ─────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+enum PlanSource {
+    Fixed(Plans),
+    Config,
+    Database(Duration),
+}
+
+static QUOTAS: RwLock<Option<Arc<QuotaModule>>> = RwLock::new(None);
+
+fn installed() -> Option<Arc<QuotaModule>> {
+    QUOTAS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+/// Requests `subject` has made in the quota day starting at `day`. The
+/// stored part is cached until the next metering flush.
+fn used(subject: &Subject, day: i64) -> ChopinResult<u64> {
+    type Stored = HashMap<Subject, (i64, u64, u64)>;
+    static STORED: OnceLock<Mutex<Stored>> = OnceLock::new();
+    let stored_cache = STORED.get_or_init(Default::default);
+
+    let generation = metering::flushes();
+    let cached = stored_cache
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(subject)
+        .filter(|(d, g, _)| *d == day && *g == generation)
+        .map(|&(_, _, n)| n);
+    let stored = match cached {
+        Some(n) => n,
+        None => {
+            let n = if db::is_configured() {
+                metering::stored_usage(subject, day, day + DAY)?.requests
+            } else {
+                0
+            };
+            stored_cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(subject.clone(), (day, generation, n));
+            n
+        }
+    };
+    Ok(stored + metering::pending_usage(subject, day, day + DAY).requests)
+}
+
+/// Today's standing of `subject`, or `None` if no [`QuotaModule`] is
+/// mounted or the subject's plan has no limit.
+pub fn status(subject: &Subject) -> ChopinResult<Option<QuotaStatus>> {
+    match installed() {
+        Some(quotas) => quotas.status_at(subject, metering::unix_now()),
+        None => Ok(None),
+    }
+}
+
+/// Check `subjects` before a request. `Err` holds the `429` for the first
+/// subject over quota; otherwise the status of the one closest to its
+/// limit, if any is limited. A subject whose usage cannot be read is let
+/// through.
+#[allow(clippy::result_large_err)]
+pub(crate) fn check(subjects: &[Subject]) -> Result<Option<QuotaStatus>, Response> {
+    if subjects.is_empty() {
+        return Ok(None);
+    }
+    let Some(quotas) = installed() else {
+        return Ok(None);
+    };
+    let now = metering::unix_now();
+    let mut tightest: Option<QuotaStatus> = None;
+    for subject in subjects {
+        let status = match quotas.status_at(subject, now) {
+            Ok(Some(status)) => status,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!(
+                    "[chopin] quota check for {} {}: {e}",
+                    subject.kind.as_str(),
+                    subject.id
+                );
+                continue;
+            }
+        };
+        if status.exceeded() {
+            return Err(status.rejection(now));
+        }
+        if tightest
+            .as_ref()
+            .is_none_or(|t| status.remaining() < t.remaining())
+        {
+            tightest = Some(status);
+        }
+    }
+    Ok(tightest)
+}
+
+// ─── Module ──────────────────────────────────────────────────────────────────
+
+/// Enforces plan quotas on metered requests. Mount it together with a
+/// [`MeteringModule`](crate::metering::MeteringModule).
+#[derive(Clone)]
+pub struct QuotaModule {
+    source: PlanSource,
+    default_plan: Option<String>,
+    plan_for: Option<PlanResolver>,
+}
+
+impl QuotaModule {
+    /// Plans defined in code with [`plan`](Self::plan).
+    pub fn new() -> Self {
+        Self {
+            source: PlanSource::Fixed(Arc::default()),
+            default_plan: None,
+            plan_for: None,
+        }
+    }
+
+    /// Plans read from the `[quotas]` section, following config reloads.
+    pub fn from_config() -> Self {
+        Self {
+            source: PlanSource::Config,
+            ..Self::new()
+        }
+    }
+
+    /// Plans read from `chopin_plans`, re-read every `refresh`.
+    pub fn from_db(refresh: Duration) -> Self {
+        Self {
+            source: PlanSource::Database(refresh),
+            ..Self::new()
+        }
+    }
+
+    /// Define a plan. Switches a module built with another source to
+    /// plans from code.
+    pub fn plan(mut self, name: &str, plan: Plan) -> Self {
+        let mut plans = match self.source {
+            PlanSource::Fixed(plans) => Arc::unwrap_or_clone(plans),
+            _ => HashMap::new(),
+        };
+        plans.insert(name.to_string(), plan);
+        self.source = PlanSource::Fixed(Arc::new(plans));
+        self
+    }
+
+    /// Plan of subjects the resolver does not place. Takes precedence over
+    /// `default_plan` in the config.
+    pub fn default_plan(mut self, name: &str) -> Self {
+        self.default_plan = Some(name.to_string());
+        self
+    }
+
+    /// Look up the plan of each subject, e.g. from the tenant's record.
+    /// Names with no plan defined are not limited.
+    pub fn plan_for(mut self, resolver: PlanResolver) -> Self {
+        self.plan_for = Some(resolver);
+        self
+    }
+
+    fn plans(&self) -> ChopinResult<(Plans, Option<String>)> {
+        match &self.source {
+            PlanSource::Fixed(plans) => Ok((plans.clone(), None)),
+            PlanSource::Config => {
+                let settings = Settings::<QuotaSettings>::load()?;
+                Ok((Arc::new(settings.plans), settings.default_plan))
+            }
+            PlanSource::Database(refresh) => Ok((stored_plans(*refresh)?, None)),
+        }
+    }
+
+    fn status_at(&self, subject: &Subject, now: i64) -> ChopinResult<Option<QuotaStatus>> {
+        let (plans, config_default) = self.plans()?;
+        let Some(name) = self
+            .plan_for
+            .and_then(|f| f(subject))
+            .or_else(|| self.default_plan.clone())
+            .or(config_default)
+        else {
+            return Ok(None);
+        };
+        let Some(limit) = plans.get(&name).and_then(|p| p.requests_per_day) else {
+            return Ok(None);
+        };
+        let day = metering::period_start(now, DAY);
+        Ok(Some(QuotaStatus {
+            used: used(subject, day)?,
+            subject: subject.clone(),
+            plan: name,
+            limit,
+            reset: day + DAY,
+        }))
+    }
+}
+
+impl Default for QuotaModule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChopinModule for QuotaModule {
+    fn name(&self) -> &'static str {
+        "quota"
+    }
+
+    fn routes(&self, _router: &mut Router) {
+        *QUOTAS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(self.clone()));
+    }
+
+    fn migrations(&self) -> Vec<Box<dyn Migration>> {
+        vec![Box::new(CreatePlansTable)]
+    }
+
+    fn on_start(&self) -> ChopinResult<()> {
+        if !metering::is_installed() {
+            return Err(ChopinError::Other(
+                "QuotaModule needs a MeteringModule to be mounted".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+struct CreatePlansTable;
+
+impl Migration for CreatePlansTable {
+    fn name(&self) -> &'static str {
+        "001_create_plans"
+    }
+
+    fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        PlanStore::ensure_table(executor)
+    }
+
+    fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute("DROP TABLE IF EXISTS chopin_plans", &[])?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::http::Context;
+    use crate::metering::MeteringModule;
+
+    #[test]
+    fn test_plans_from_config() {
+        let config = Config::parse(
+            r#"
+            [quotas]
+            default_plan = "free"
+
+            [quotas.plans.free]
+            requests_per_day = 1000
+
+            [quotas.plans.internal]
+            "#,
+        )
+        .unwrap();
+        let settings: QuotaSettings = config.extension("quotas").unwrap();
+        assert_eq!(settings.default_plan.as_deref(), Some("free"));
+        assert_eq!(settings.plans["free"], Plan::daily(1000));
+        assert_eq!(settings.plans["internal"], Plan::unlimited());
+    }
+
+    fn api_key(ctx: &Context) -> Option<Subject> {
+        ctx.header("X-Key").map(Subject::api_key)
+    }
+
+    fn plan_of(subject: &Subject) -> Option<String> {
+        (subject.id == "quota-vip").then(|| "vip".to_string())
+    }
+
+    fn hello(_ctx: Context) -> Response {
+        Response::text("hello")
+    }
+
+    #[test]
+    fn test_requests_over_quota_are_rejected() {
+        let _installed = metering::tests::INSTALL_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        let mut router = Router::new();
+        MeteringModule::new().resolve(api_key).routes(&mut router);
+        QuotaModule::new()
+            .plan("free", Plan::daily(2))
+            .plan("vip", Plan::unlimited())
+            .default_plan("free")
+            .plan_for(plan_of)
+            .routes(&mut router);
+        router.get("/quota-test", hello);
+        let app = crate::testing::TestApp::new(router);
+        let call = |key| {
+            app.request(
+                crate::http::Method::Get,
+                "/quota-test",
+                &[("X-Key", key)],
+                b"",
+            )
+        };
+
+        let first = call("quota-k1");
+        assert_eq!(first.status, 200);
+        assert_eq!(first.header("X-RateLimit-Limit"), Some("2"));
+        assert_eq!(first.header("X-RateLimit-Remaining"), Some("1"));
+        assert_eq!(call("quota-k1").header("X-RateLimit-Remaining"), Some("0"));
+
+        let rejected = call("quota-k1");
+        assert_eq!(rejected.status, 429);
+        assert!(rejected.header("Retry-After").is_some());
+        let json = rejected.json().unwrap();
+        assert_eq!(json["error"], "quota_exceeded");
+        assert_eq!(
+            (json["plan"].as_str(), json["used"].as_u64()),
+            (Some("free"), Some(2))
+        );
+        // Rejected requests are not charged.
+        let status = status(&Subject::api_key("quota-k1")).unwrap().unwrap();
+        assert_eq!(status.used, 2);
+
+        for _ in 0..3 {
+            let vip = call("quota-vip");
+            assert_eq!(vip.status, 200);
+            assert!(vip.header("X-RateLimit-Limit").is_none());
+        }
+        *QUOTAS.write().unwrap() = None;
+    }
+
+    #[test]
+    fn test_plan_store_upserts() {
+        let mut db = chopin_orm::MockExecutor::new();
+        PlanStore::set(&mut db, "pro", &Plan::daily(5000)).unwrap();
+        let (sql, params) = &db.executed_queries[0];
+        assert!(sql.contains("ON CONFLICT (name) DO UPDATE"));
+        assert_eq!(*params, 2);
+    }
+}
@@ -0,0 +1,253 @@
+// src/range.rs
+//! `Range` / `If-Range` support for resumable downloads.
+//!
+//! [`serve_file`] is a drop-in replacement for [`Response::file`] that
+//! advertises `Accept-Ranges: bytes`, sends `ETag` and `Last-Modified`
+//! validators, and answers single-range requests with `206 Partial Content`
+//! (still via zero-copy `sendfile`). A client resuming a download sends the
+//! validator back in `If-Range`; if the file changed in the meantime the whole
+//! file is sent instead of a stale slice.
+//!
+//! ```rust,ignore
+//! #[get("/downloads/*file")]
+//! fn download(ctx: Context) -> Response {
+//!     let path = format!("./files/{}", ctx.param("file").unwrap_or(""));
+//!     chopin_core::range::serve_file(&ctx, &path)
+//! }
+//! ```
+use crate::http::{Context, Response};
+use crate::syscalls;
+use std::time::SystemTime;
+
+/// Result of evaluating a `Range` header against a resource length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    /// No usable range: send the whole resource.
+    Full,
+    /// Send bytes `start..=end`.
+    Partial { start: u64, end: u64 },
+    /// The range lies outside the resource: respond `416`.
+    Unsatisfiable,
+}
+
+/// Parse a `Range` header value for a resource of `len` bytes.
+///
+/// Only single `bytes=` ranges are honoured (`a-b`, `a-` and `-n`); anything
+/// else, including multi-range requests, yields [`ByteRange::Full`], which
+/// RFC 9110 permits.
+pub fn parse_range(header: &str, len: u64) -> ByteRange {
+    let Some(spec) = header.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+    let (start, end) = (start.trim(), end.trim());
+
+    let (start, end) = if start.is_empty() {
+        // Suffix range: the last `n` bytes.
+        let Ok(n) = end.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        if n == 0 || len == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        (len.saturating_sub(n), len - 1)
+    } else {
+        let Ok(start) = start.parse::<u64>() else {
+            return ByteRange::Full;
+        };
+        let end = if end.is_empty() {
+            len.saturating_sub(1)
+        } else {
+            match end.parse::<u64>() {
+                Ok(e) if e >= start => e.min(len.saturating_sub(1)),
+                _ => return ByteRange::Full,
+            }
+        };
+        if start >= len {
+            return ByteRange::Unsatisfiable;
+        }
+        (start, end)
+    };
+    ByteRange::Partial { start, end }
+}
+
+/// Strong ETag derived from a file's size and modification time.
+pub fn file_etag(size: u64, modified: SystemTime) -> String {
+    let nanos = modified
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("\"{size:x}-{nanos:x}\"")
+}
+
+/// Whether an `If-Range` value still matches the current representation.
+///
+/// Entity tags must match exactly (weak tags never match); dates must equal
+/// the `Last-Modified` value.
+pub fn if_range_matches(if_range: &str, etag: &str, last_modified: &str) -> bool {
+    let if_range = if_range.trim();
+    if if_range.starts_with('"') {
+        if_range == etag
+    } else {
+        !if_range.starts_with("W/") && if_range == last_modified
+    }
+}
+
+/// Serve a file honouring `Range` and `If-Range`. Returns 404 if the file
+/// cannot be opened.
+pub fn serve_file(ctx: &Context, path: &str) -> Response {
+    let Ok(fd) = syscalls::open_file_readonly(path) else {
+        return Response::not_found();
+    };
+    let (size, modified) = match syscalls::file_size(fd).and_then(|size| {
+        let modified = syscalls::file_mtime(fd)?;
+        Ok((size, modified))
+    }) {
+        Ok(meta) => meta,
+        Err(_) => {
+            unsafe {
+                libc::close(fd);
+            }
+            return Response::not_found();
+        }
+    };
+    let content_type = crate::http::mime_from_path(path);
+    let etag = file_etag(size, modified);
+    let last_modified = httpdate::fmt_http_date(modified);
+
+    let range = match ctx.header("Range") {
+        Some(range)
+            if ctx
+                .header("If-Range")
+                .is_none_or(|v| if_range_matches(v, &etag, &last_modified)) =>
+        {
+            parse_range(range, size)
+        }
+        _ => ByteRange::Full,
+    };
+
+    let res = match range {
+        ByteRange::Full => Response::sendfile(fd, 0, size, content_type),
+        ByteRange::Partial { start, end } => {
+            let mut res = Response::sendfile(fd, start, end - start + 1, content_type);
+            res.status = 206;
+            res.with_header("Content-Range", format!("bytes {start}-{end}/{size}"))
+        }
+        ByteRange::Unsatisfiable => {
+            unsafe {
+                libc::close(fd);
+            }
+            Response::new(416).with_header("Content-Range", format!("bytes */{size}"))
+        }
+    };
+    res.with_header("Accept-Ranges", "bytes")
+        .with_header("ETag", etag)
+        .with_header("Last-Modified", last_modified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{Body, MAX_HEADERS, MAX_PARAMS, Method, Request};
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(
+            parse_range("bytes=0-9", 100),
+            ByteRange::Partial { start: 0, end: 9 }
+        );
+        assert_eq!(
+            parse_range("bytes=90-", 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=-10", 100),
+            ByteRange::Partial { start: 90, end: 99 }
+        );
+        assert_eq!(
+            parse_range("bytes=50-500", 100),
+            ByteRange::Partial { start: 50, end: 99 }
+        );
+        assert_eq!(parse_range("bytes=100-", 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 100), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 100), ByteRange::Full);
+        assert_eq!(parse_range("bytes=9-1", 100), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 100), ByteRange::Full);
+    }
+
+    #[test]
+    fn test_if_range_matches() {
+        let date = "Wed, 21 Oct 2015 07:28:00 GMT";
+        assert!(if_range_matches("\"abc\"", "\"abc\"", date));
+        assert!(!if_range_matches("\"old\"", "\"abc\"", date));
+        assert!(!if_range_matches("W/\"abc\"", "\"abc\"", date));
+        assert!(if_range_matches(date, "\"abc\"", date));
+    }
+
+    fn ctx<'a>(headers: &[(&'a str, &'a str)]) -> Context<'a> {
+        let mut h = [("", ""); MAX_HEADERS];
+        h[..headers.len()].copy_from_slice(headers);
+        Context {
+            req: Request {
+                method: Method::Get,
+                path: "/",
+                query: None,
+                headers: h,
+                header_count: headers.len() as u8,
+                body: b"",
+            },
+            params: [("", ""); MAX_PARAMS],
+            param_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_serve_file_ranges() {
+        let path = std::env::temp_dir().join(format!("chopin_range_{}.txt", std::process::id()));
+        std::fs::write(&path, b"0123456789").unwrap();
+        let path = path.to_str().unwrap();
+
+        let res = serve_file(&ctx(&[("Range", "bytes=2-4")]), path);
+        assert_eq!(res.status, 206);
+        assert!(matches!(
+            res.body,
+            Body::File {
+                offset: 2,
+                len: 3,
+                ..
+            }
+        ));
+        let etag = res
+            .headers
+            .iter()
+            .find(|h| h.name == "ETag")
+            .map(|h| h.value.as_str().to_string())
+            .unwrap();
+
+        // Matching validator: range honoured. Stale validator: full body.
+        let res = serve_file(&ctx(&[("Range", "bytes=5-"), ("If-Range", &etag)]), path);
+        assert_eq!(res.status, 206);
+        let res = serve_file(&ctx(&[("Range", "bytes=5-"), ("If-Range", "\"x\"")]), path);
+        assert_eq!(res.status, 200);
+        assert!(matches!(
+            res.body,
+            Body::File {
+                offset: 0,
+                len: 10,
+                ..
+            }
+        ));
+
+        assert_eq!(
+            serve_file(&ctx(&[("Range", "bytes=20-")]), path).status,
+            416
+        );
+        assert_eq!(serve_file(&ctx(&[]), "/nonexistent/chopin").status, 404);
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,235 @@
+// src/recorder.rs
+//! Development middleware that records real request/response pairs and merges
+//! them into the generated OpenAPI spec as examples.
+//!
+//! The first JSON request body and the first JSON response body seen for each
+//! route (and each response status) are kept. Sensitive fields are masked with
+//! the global [`Redactor`](crate::redact::Redactor) before being stored.
+//!
+//! Recording takes a process-wide lock per request, so mount it in
+//! development builds only:
+//!
+//! ```rust,ignore
+//! let mut router = Router::new();
+//! if cfg!(debug_assertions) {
+//!     router.layer(chopin_core::recorder::record_examples);
+//! }
+//! ```
+use crate::http::{Body, Context, Method, Response};
+use crate::openapi::{convert_path, method_name};
+use crate::router::{BoxedHandler, RouteDef};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+/// Bodies larger than this are not recorded.
+pub const MAX_EXAMPLE_BYTES: usize = 64 * 1024;
+
+/// Examples captured for one operation.
+#[derive(Debug, Clone, Default)]
+pub struct RecordedExample {
+    pub request: Option<Value>,
+    pub responses: BTreeMap<u16, Value>,
+}
+
+/// Keyed by (OpenAPI path, method).
+type ExampleMap = BTreeMap<(String, &'static str), RecordedExample>;
+
+static EXAMPLES: Mutex<ExampleMap> = Mutex::new(BTreeMap::new());
+
+/// Middleware recording request/response examples for the OpenAPI spec.
+pub fn record_examples(ctx: Context, next: BoxedHandler) -> Response {
+    let method = ctx.req.method;
+    let path = ctx.req.path.to_string();
+    let request = json_example(ctx.req.body);
+
+    let res = next(ctx);
+
+    let response = match &res.body {
+        Body::Bytes(b) if res.content_type.starts_with("application/json") => json_example(b),
+        Body::Static(b) if res.content_type.starts_with("application/json") => json_example(b),
+        _ => None,
+    };
+    if request.is_some() || response.is_some() {
+        record(method, &path, request, res.status, response);
+    }
+    res
+}
+
+/// Store an example for the route matching `method` and `path`.
+pub fn record(
+    method: Method,
+    path: &str,
+    request: Option<Value>,
+    status: u16,
+    response: Option<Value>,
+) {
+    let key = (route_template(method, path), method_name(method));
+    let Ok(mut examples) = EXAMPLES.lock() else {
+        return;
+    };
+    let entry = examples.entry(key).or_default();
+    if entry.request.is_none() {
+        entry.request = request;
+    }
+    if let Some(response) = response {
+        entry.responses.entry(status).or_insert(response);
+    }
+}
+
+/// Snapshot of everything recorded so far, keyed by (OpenAPI path, method).
+pub fn recorded() -> Vec<((String, &'static str), RecordedExample)> {
+    EXAMPLES
+        .lock()
+        .map(|m| m.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+/// Discard all recorded examples.
+pub fn clear() {
+    if let Ok(mut examples) = EXAMPLES.lock() {
+        examples.clear();
+    }
+}
+
+/// Attach recorded examples to matching operations in `paths`.
+pub(crate) fn merge_into(paths: &mut BTreeMap<String, BTreeMap<String, Value>>) {
+    let Ok(examples) = EXAMPLES.lock() else {
+        return;
+    };
+    for ((path, method), example) in examples.iter() {
+        let Some(operation) = paths.get_mut(path).and_then(|m| m.get_mut(*method)) else {
+            continue;
+        };
+        let Some(op) = operation.as_object_mut() else {
+            continue;
+        };
+
+        if let Some(req) = &example.request {
+            op.insert(
+                "requestBody".to_string(),
+                json!({ "content": { "application/json": { "example": req } } }),
+            );
+        }
+
+        let responses = op
+            .entry("responses")
+            .or_insert_with(|| json!({}))
+            .as_object_mut();
+        let Some(responses) = responses else {
+            continue;
+        };
+        for (status, body) in &example.responses {
+            let entry = responses
+                .entry(status.to_string())
+                .or_insert_with(|| json!({ "description": status_description(*status) }));
+            if let Some(entry) = entry.as_object_mut() {
+                entry.insert(
+                    "content".to_string(),
+                    json!({ "application/json": { "example": body } }),
+                );
+            }
+        }
+    }
+}
+
+/// Resolve the registered route pattern for a concrete request path, falling
+/// back to the literal path for routes not registered through the macros.
+fn route_template(method: Method, path: &str) -> String {
+    inventory::iter::<RouteDef>
+        .into_iter()
+        .find(|r| r.method == method && pattern_matches(r.path, path))
+        .map(|r| convert_path(r.path).0)
+        .unwrap_or_else(|| convert_path(path).0)
+}
+
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let mut pat = pattern.split('/').filter(|s| !s.is_empty());
+    let mut segs = path.split('/').filter(|s| !s.is_empty());
+    loop {
+        match (pat.next(), segs.next()) {
+            (None, None) => return true,
+            (Some(p), _) if p.starts_with('*') => return true,
+            (Some(p), Some(s)) if p.starts_with(':') || p == s => continue,
+            _ => return false,
+        }
+    }
+}
+
+fn json_example(body: &[u8]) -> Option<Value> {
+    if body.is_empty() || body.len() > MAX_EXAMPLE_BYTES {
+        return None;
+    }
+    let mut value: Value = serde_json::from_slice(body).ok()?;
+    crate::redact::global().redact_value(&mut value);
+    Some(value)
+}
+
+fn status_description(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        422 => "Unprocessable Entity",
+        500 => "Internal Server Error",
+        _ => "Response",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matches() {
+        assert!(pattern_matches("/users/:id", "/users/42"));
+        assert!(pattern_matches("/static/*file", "/static/css/app.css"));
+        assert!(!pattern_matches("/users/:id", "/users/42/posts"));
+        assert!(!pattern_matches("/users", "/posts"));
+    }
+
+    #[test]
+    fn test_json_example_redacts() {
+        let v = json_example(br#"{"name":"a","password":"p"}"#).unwrap();
+        assert_eq!(v["name"], "a");
+        assert_eq!(v["password"], crate::redact::REDACTED);
+        assert!(json_example(b"plain text").is_none());
+        assert!(json_example(b"").is_none());
+    }
+
+    #[test]
+    fn test_record_and_merge() {
+        let path = "/__recorder_test/7";
+        record(
+            Method::Post,
+            path,
+            Some(json!({"title": "x"})),
+            201,
+            Some(json!({"id": 7})),
+        );
+        // Later examples for the same status do not overwrite the first one.
+        record(Method::Post, path, None, 201, Some(json!({"id": 8})));
+
+        let mut paths: BTreeMap<String, BTreeMap<String, Value>> = BTreeMap::new();
+        paths
+            .entry(path.to_string())
+            .or_default()
+            .insert("post".to_string(), json!({ "responses": {} }));
+        merge_into(&mut paths);
+
+        let op = &paths[path]["post"];
+        assert_eq!(
+            op["requestBody"]["content"]["application/json"]["example"]["title"],
+            "x"
+        );
+        assert_eq!(op["responses"]["201"]["description"], "Created");
+        assert_eq!(
+            op["responses"]["201"]["content"]["application/json"]["example"]["id"],
+            7
+        );
+    }
+}
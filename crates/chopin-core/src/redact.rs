@@ -0,0 +1,303 @@
+// src/redact.rs
+//! PII redaction for log lines, JSON payloads and `Debug` output.
+//!
+//! A [`Redactor`] holds a list of sensitive field names (case-insensitive).
+//! It can mask the matching values in structured `key=value` log lines and
+//! in JSON documents before they are written anywhere.
+//!
+//! For DTOs, `#[derive(Redact)]` generates a `Debug` impl that prints
+//! [`REDACTED`] in place of every field marked `#[redact]`, so error messages
+//! built with `{:?}` never leak the original value.
+//!
+//! ```rust,ignore
+//! use chopin_core::Redact;
+//!
+//! #[derive(Redact)]
+//! struct Login {
+//!     username: String,
+//!     #[redact]
+//!     password: String,
+//! }
+//! // Login { username: "alice", password: [REDACTED] }
+//! ```
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Placeholder written in place of a redacted value.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names masked by [`Redactor::new`].
+pub const DEFAULT_SENSITIVE_FIELDS: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "token",
+    "access_token",
+    "refresh_token",
+    "api_key",
+    "authorization",
+    "cookie",
+    "email",
+];
+
+static GLOBAL_REDACTOR: OnceLock<Redactor> = OnceLock::new();
+
+/// Zero-sized stand-in whose `Debug` and `Display` output is [`REDACTED`].
+///
+/// Used by `#[derive(Redact)]`; also handy in hand-written `Debug` impls.
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+impl fmt::Display for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+/// Masks values of configured field names.
+#[derive(Debug, Clone)]
+pub struct Redactor {
+    fields: Vec<String>,
+}
+
+impl Redactor {
+    /// A redactor masking [`DEFAULT_SENSITIVE_FIELDS`].
+    pub fn new() -> Self {
+        Self {
+            fields: DEFAULT_SENSITIVE_FIELDS
+                .iter()
+                .map(|f| f.to_string())
+                .collect(),
+        }
+    }
+
+    /// A redactor with no configured fields.
+    pub fn empty() -> Self {
+        Self { fields: Vec::new() }
+    }
+
+    /// Add a field name to mask (matched case-insensitively).
+    pub fn field(mut self, name: impl Into<String>) -> Self {
+        let name = name.into().to_ascii_lowercase();
+        if !self.fields.contains(&name) {
+            self.fields.push(name);
+        }
+        self
+    }
+
+    /// Returns `true` if `name` is one of the configured sensitive fields.
+    pub fn is_sensitive(&self, name: &str) -> bool {
+        self.fields.iter().any(|f| f.eq_ignore_ascii_case(name))
+    }
+
+    /// Mask sensitive values in a structured log line.
+    ///
+    /// Recognises `key=value` pairs separated by whitespace, `&`, `,` or `;`,
+    /// with optional double quotes around the value (`key="a b"`).
+    pub fn redact_line(&self, line: &str) -> String {
+        let bytes = line.as_bytes();
+        let mut out = String::with_capacity(line.len());
+        let mut copied = 0;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            if bytes[i] != b'=' {
+                i += 1;
+                continue;
+            }
+
+            let mut key_start = i;
+            while key_start > 0 && is_key_byte(bytes[key_start - 1]) {
+                key_start -= 1;
+            }
+            let key = &line[key_start..i];
+            let value_start = i + 1;
+
+            if key.is_empty() || !self.is_sensitive(key) {
+                i = value_start;
+                continue;
+            }
+
+            let value_end = if bytes.get(value_start) == Some(&b'"') {
+                match line[value_start + 1..].find('"') {
+                    Some(p) => value_start + 1 + p + 1,
+                    None => bytes.len(),
+                }
+            } else {
+                let mut end = value_start;
+                while end < bytes.len() && !is_pair_separator(bytes[end]) {
+                    end += 1;
+                }
+                end
+            };
+
+            out.push_str(&line[copied..value_start]);
+            out.push_str(REDACTED);
+            copied = value_end;
+            i = value_end;
+        }
+
+        out.push_str(&line[copied..]);
+        out
+    }
+
+    /// Mask sensitive keys in a JSON value, recursing into objects and arrays.
+    pub fn redact_value(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (k, v) in map.iter_mut() {
+                    if self.is_sensitive(k) {
+                        *v = serde_json::Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(v);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => {
+                for v in items {
+                    self.redact_value(v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Mask sensitive keys in a JSON document.
+    ///
+    /// Returns `None` if `body` is not valid JSON.
+    pub fn redact_json(&self, body: &[u8]) -> Option<String> {
+        let mut value: serde_json::Value = serde_json::from_slice(body).ok()?;
+        self.redact_value(&mut value);
+        serde_json::to_string(&value).ok()
+    }
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Install the process-wide redactor used by [`redact_line`] and [`redact_json`].
+///
+/// Must be called before the first use; later calls return the rejected value.
+pub fn init_redactor(redactor: Redactor) -> Result<(), Redactor> {
+    GLOBAL_REDACTOR.set(redactor)
+}
+
+/// The process-wide redactor, falling back to [`Redactor::new`] if none was installed.
+pub fn global() -> &'static Redactor {
+    GLOBAL_REDACTOR.get_or_init(Redactor::new)
+}
+
+/// Mask sensitive values in a log line using the global redactor.
+pub fn redact_line(line: &str) -> String {
+    global().redact_line(line)
+}
+
+/// Mask sensitive keys in a JSON document using the global redactor.
+pub fn redact_json(body: &[u8]) -> Option<String> {
+    global().redact_json(body)
+}
+
+#[inline]
+fn is_key_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_' || b == b'-' || b == b'.'
+}
+
+#[inline]
+fn is_pair_separator(b: u8) -> bool {
+    b.is_ascii_whitespace() || b == b'&' || b == b',' || b == b';'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ─── Redacted ────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_redacted_debug_and_display() {
+        assert_eq!(format!("{:?}", Redacted), REDACTED);
+        assert_eq!(format!("{}", Redacted), REDACTED);
+    }
+
+    // ─── Field matching ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_default_fields_case_insensitive() {
+        let r = Redactor::new();
+        assert!(r.is_sensitive("password"));
+        assert!(r.is_sensitive("Password"));
+        assert!(r.is_sensitive("EMAIL"));
+        assert!(!r.is_sensitive("username"));
+    }
+
+    #[test]
+    fn test_custom_field() {
+        let r = Redactor::empty().field("SSN");
+        assert!(r.is_sensitive("ssn"));
+        assert!(!r.is_sensitive("password"));
+    }
+
+    // ─── Log lines ───────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_redact_line_pairs() {
+        let r = Redactor::new();
+        assert_eq!(
+            r.redact_line("login user=alice password=hunter2 ok=true"),
+            "login user=alice password=[REDACTED] ok=true"
+        );
+    }
+
+    #[test]
+    fn test_redact_line_query_string() {
+        let r = Redactor::new();
+        assert_eq!(
+            r.redact_line("GET /cb?code=1&token=abc.def&state=x"),
+            "GET /cb?code=1&token=[REDACTED]&state=x"
+        );
+    }
+
+    #[test]
+    fn test_redact_line_quoted_value() {
+        let r = Redactor::new();
+        assert_eq!(
+            r.redact_line(r#"email="a b@example.com" id=7"#),
+            "email=[REDACTED] id=7"
+        );
+    }
+
+    #[test]
+    fn test_redact_line_untouched() {
+        let r = Redactor::new();
+        let line = "GET /health status=200 a==b";
+        assert_eq!(r.redact_line(line), line);
+    }
+
+    // ─── JSON ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_redact_json_nested() {
+        let r = Redactor::new();
+        let out = r
+            .redact_json(br#"{"user":{"email":"a@b.c","name":"A"},"items":[{"token":"t"}]}"#)
+            .unwrap();
+        let v: serde_json::Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(v["user"]["email"], REDACTED);
+        assert_eq!(v["user"]["name"], "A");
+        assert_eq!(v["items"][0]["token"], REDACTED);
+    }
+
+    #[test]
+    fn test_redact_json_invalid() {
+        assert!(Redactor::new().redact_json(b"not json").is_none());
+    }
+}
@@ -0,0 +1,337 @@
+// src/response.rs
+//! Ready-made response types.
+//!
+//! [`Feed`] renders an RSS 2.0 or Atom 1.0 document from any iterator of
+//! [`FeedEntry`] values:
+//!
+//! ```rust,ignore
+//! impl FeedEntry for Post {
+//!     fn title(&self) -> &str { &self.title }
+//!     fn link(&self) -> &str { &self.url }
+//!     fn updated(&self) -> SystemTime { self.published_at }
+//!     fn summary(&self) -> Option<&str> { Some(&self.excerpt) }
+//! }
+//!
+//! #[get("/feed.xml")]
+//! fn feed(_ctx: Context) -> Response {
+//!     let posts = load_recent_posts();
+//!     Feed::atom("My blog", "https://example.com/").render(posts.iter())
+//! }
+//! ```
+use crate::http::Response;
+use std::fmt::Write;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const RSS_CONTENT_TYPE: &str = "application/rss+xml; charset=utf-8";
+pub const ATOM_CONTENT_TYPE: &str = "application/atom+xml; charset=utf-8";
+
+/// One item of a [`Feed`].
+pub trait FeedEntry {
+    fn title(&self) -> &str;
+    /// Absolute URL of the entry.
+    fn link(&self) -> &str;
+    /// Last modification time.
+    fn updated(&self) -> SystemTime;
+    /// Stable unique id. Defaults to the link.
+    fn id(&self) -> &str {
+        self.link()
+    }
+    /// Plain-text summary.
+    fn summary(&self) -> Option<&str> {
+        None
+    }
+    fn author(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedFormat {
+    Rss,
+    Atom,
+}
+
+/// Feed-level metadata; call [`render`](Feed::render) with the entries.
+#[derive(Debug, Clone)]
+pub struct Feed {
+    format: FeedFormat,
+    title: String,
+    link: String,
+    description: String,
+    self_link: Option<String>,
+}
+
+impl Feed {
+    /// An RSS 2.0 feed for the site at `link`.
+    pub fn rss(title: impl Into<String>, link: impl Into<String>) -> Self {
+        Self::new(FeedFormat::Rss, title, link)
+    }
+
+    /// An Atom 1.0 feed for the site at `link`.
+    pub fn atom(title: impl Into<String>, link: impl Into<String>) -> Self {
+        Self::new(FeedFormat::Atom, title, link)
+    }
+
+    fn new(format: FeedFormat, title: impl Into<String>, link: impl Into<String>) -> Self {
+        Self {
+            format,
+            title: title.into(),
+            link: link.into(),
+            description: String::new(),
+            self_link: None,
+        }
+    }
+
+    /// Channel description (RSS) or subtitle (Atom).
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+
+    /// URL the feed itself is served from. Atom uses it as the feed id.
+    pub fn self_link(mut self, url: impl Into<String>) -> Self {
+        self.self_link = Some(url.into());
+        self
+    }
+
+    /// Render the document for `entries`.
+    pub fn to_xml<'a, E: FeedEntry + 'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a E>,
+    ) -> String {
+        match self.format {
+            FeedFormat::Rss => self.rss_xml(entries),
+            FeedFormat::Atom => self.atom_xml(entries),
+        }
+    }
+
+    /// Render `entries` into a response with the format's content type.
+    pub fn render<'a, E: FeedEntry + 'a>(
+        &self,
+        entries: impl IntoIterator<Item = &'a E>,
+    ) -> Response {
+        let mut res = Response::text(self.to_xml(entries));
+        res.content_type = match self.format {
+            FeedFormat::Rss => RSS_CONTENT_TYPE,
+            FeedFormat::Atom => ATOM_CONTENT_TYPE,
+        };
+        res
+    }
+
+    fn rss_xml<'a, E: FeedEntry + 'a>(&self, entries: impl IntoIterator<Item = &'a E>) -> String {
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<rss version=\"2.0\"><channel>");
+        let _ = write!(
+            out,
+            "<title>{}</title><link>{}</link><description>{}</description>",
+            escape_xml(&self.title),
+            escape_xml(&self.link),
+            escape_xml(&self.description)
+        );
+        for entry in entries {
+            let _ = write!(
+                out,
+                "<item><title>{}</title><link>{}</link><guid isPermaLink=\"{}\">{}</guid><pubDate>{}</pubDate>",
+                escape_xml(entry.title()),
+                escape_xml(entry.link()),
+                entry.id() == entry.link(),
+                escape_xml(entry.id()),
+                httpdate::fmt_http_date(entry.updated())
+            );
+            if let Some(summary) = entry.summary() {
+                let _ = write!(out, "<description>{}</description>", escape_xml(summary));
+            }
+            if let Some(author) = entry.author() {
+                let _ = write!(out, "<author>{}</author>", escape_xml(author));
+            }
+            out.push_str("</item>");
+        }
+        out.push_str("</channel></rss>\n");
+        out
+    }
+
+    fn atom_xml<'a, E: FeedEntry + 'a>(&self, entries: impl IntoIterator<Item = &'a E>) -> String {
+        let mut body = String::new();
+        let mut latest = UNIX_EPOCH;
+        for entry in entries {
+            latest = latest.max(entry.updated());
+            let _ = write!(
+                body,
+                "<entry><title>{}</title><link href=\"{}\"/><id>{}</id><updated>{}</updated>",
+                escape_xml(entry.title()),
+                escape_xml(entry.link()),
+                escape_xml(entry.id()),
+                rfc3339(entry.updated())
+            );
+            if let Some(summary) = entry.summary() {
+                let _ = write!(body, "<summary>{}</summary>", escape_xml(summary));
+            }
+            if let Some(author) = entry.author() {
+                let _ = write!(body, "<author><name>{}</name></author>", escape_xml(author));
+            }
+            body.push_str("</entry>");
+        }
+
+        let id = self.self_link.as_deref().unwrap_or(&self.link);
+        let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">");
+        let _ = write!(
+            out,
+            "<title>{}</title><link href=\"{}\"/><id>{}</id><updated>{}</updated>",
+            escape_xml(&self.title),
+            escape_xml(&self.link),
+            escape_xml(id),
+            rfc3339(latest)
+        );
+        if let Some(self_link) = &self.self_link {
+            let _ = write!(
+                out,
+                "<link rel=\"self\" href=\"{}\"/>",
+                escape_xml(self_link)
+            );
+        }
+        if !self.description.is_empty() {
+            let _ = write!(
+                out,
+                "<subtitle>{}</subtitle>",
+                escape_xml(&self.description)
+            );
+        }
+        out.push_str(&body);
+        out.push_str("</feed>\n");
+        out
+    }
+}
+
+/// Escape text for XML element content and attribute values.
+pub fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than tab/newline are not allowed in XML 1.0.
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Format a timestamp as RFC 3339 in UTC (`2024-01-31T12:00:00Z`).
+pub(crate) fn rfc3339(t: SystemTime) -> String {
+    let secs = t
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // Civil-from-days (Howard Hinnant), valid for dates after 1970.
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3600,
+        rem % 3600 / 60,
+        rem % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    struct Post {
+        title: &'static str,
+        url: &'static str,
+        at: u64,
+    }
+
+    impl FeedEntry for Post {
+        fn title(&self) -> &str {
+            self.title
+        }
+        fn link(&self) -> &str {
+            self.url
+        }
+        fn updated(&self) -> SystemTime {
+            UNIX_EPOCH + Duration::from_secs(self.at)
+        }
+        fn summary(&self) -> Option<&str> {
+            Some("a <b>bold</b> move")
+        }
+    }
+
+    fn posts() -> Vec<Post> {
+        vec![
+            Post {
+                title: "Tom & Jerry",
+                url: "https://example.com/a?x=1&y=2",
+                at: 1_700_000_000,
+            },
+            Post {
+                title: "Second",
+                url: "https://example.com/b",
+                at: 1_600_000_000,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            "2023-11-14T22:13:20Z"
+        );
+        assert_eq!(
+            rfc3339(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    }
+
+    #[test]
+    fn test_atom_feed() {
+        let res = Feed::atom("Blog", "https://example.com/")
+            .self_link("https://example.com/feed.xml")
+            .render(posts().iter());
+        assert_eq!(res.content_type, ATOM_CONTENT_TYPE);
+        let xml = Feed::atom("Blog", "https://example.com/").to_xml(posts().iter());
+        assert!(xml.contains("<feed xmlns=\"http://www.w3.org/2005/Atom\">"));
+        assert!(xml.contains("<title>Tom &amp; Jerry</title>"));
+        assert!(xml.contains("href=\"https://example.com/a?x=1&amp;y=2\""));
+        assert!(xml.contains("<summary>a &lt;b&gt;bold&lt;/b&gt; move</summary>"));
+        // Feed-level <updated> is the newest entry.
+        assert!(xml.contains("<id>https://example.com/</id><updated>2023-11-14T22:13:20Z"));
+    }
+
+    #[test]
+    fn test_rss_feed() {
+        let res = Feed::rss("Blog", "https://example.com/")
+            .description("News")
+            .render(posts().iter());
+        assert_eq!(res.content_type, RSS_CONTENT_TYPE);
+        let xml = Feed::rss("Blog", "https://example.com/").to_xml(posts().iter());
+        assert!(xml.contains("<rss version=\"2.0\"><channel>"));
+        assert!(xml.contains("<guid isPermaLink=\"true\">https://example.com/b</guid>"));
+        assert!(xml.contains("<pubDate>Tue, 14 Nov 2023 22:13:20 GMT</pubDate>"));
+        assert_eq!(xml.matches("<item>").count(), 2);
+    }
+
+    #[test]
+    fn test_escape_xml_strips_control_chars() {
+        assert_eq!(escape_xml("a\u{1}b\n'\""), "ab\n&apos;&quot;");
+    }
+}
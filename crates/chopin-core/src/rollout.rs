@@ -0,0 +1,296 @@
+// src/rollout.rs
+//! Gradual rollout of a replacement handler.
+//!
+//! A [`Rollout`] splits traffic for one route between the current (`control`)
+//! handler and a `candidate`, and counts requests, server errors and latency
+//! per variant so the two can be compared before switching over.
+//!
+//! Rollouts are `const`-constructible, so they live in a `static` and the
+//! route handler simply delegates to them:
+//!
+//! ```rust,ignore
+//! static CHECKOUT: Rollout =
+//!     Rollout::new("checkout", checkout_v1, checkout_v2, Split::Percent(10))
+//!         .sticky_header("X-User-Id");
+//!
+//! #[post("/checkout")]
+//! fn checkout(ctx: Context) -> Response {
+//!     CHECKOUT.dispatch(ctx)
+//! }
+//!
+//! // Later, e.g. from an admin handler:
+//! CHECKOUT.set_percent(50);
+//! ```
+use crate::http::{Context, Response};
+use crate::router::Handler;
+use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::time::Instant;
+
+/// Request header that forces a variant (`control` or `candidate`), e.g. for
+/// QA. Honoured by every split strategy.
+pub const VARIANT_HEADER: &str = "X-Chopin-Variant";
+
+/// How a [`Rollout`] chooses the candidate.
+#[derive(Clone, Copy)]
+pub enum Split {
+    /// Send this percentage of requests to the candidate. Adjustable at
+    /// runtime with [`Rollout::set_percent`].
+    Percent(u8),
+    /// Send requests carrying this header to the candidate.
+    Header(&'static str),
+    /// Send requests for which the flag returns `true` to the candidate.
+    Flag(fn(&Context) -> bool),
+}
+
+/// Which handler served a request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Control,
+    Candidate,
+}
+
+/// Per-variant counters.
+struct Counters {
+    requests: AtomicU64,
+    errors: AtomicU64,
+    latency_us: AtomicU64,
+}
+
+impl Counters {
+    const fn new() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            latency_us: AtomicU64::new(0),
+        }
+    }
+
+    fn snapshot(&self) -> VariantStats {
+        VariantStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            latency_us: self.latency_us.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Counters for one variant, as returned by [`Rollout::stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VariantStats {
+    pub requests: u64,
+    /// Responses with a 5xx status.
+    pub errors: u64,
+    /// Total handler time in microseconds.
+    pub latency_us: u64,
+}
+
+impl VariantStats {
+    /// Mean handler time in microseconds.
+    pub fn mean_latency_us(&self) -> u64 {
+        self.latency_us.checked_div(self.requests).unwrap_or(0)
+    }
+}
+
+/// A traffic split between two handlers for the same route.
+pub struct Rollout {
+    name: &'static str,
+    control: Handler,
+    candidate: Handler,
+    split: Split,
+    percent: AtomicU8,
+    sticky_header: Option<&'static str>,
+    seq: AtomicU64,
+    counters: [Counters; 2],
+}
+
+impl Rollout {
+    pub const fn new(
+        name: &'static str,
+        control: Handler,
+        candidate: Handler,
+        split: Split,
+    ) -> Self {
+        let percent = match split {
+            Split::Percent(p) if p > 100 => 100,
+            Split::Percent(p) => p,
+            _ => 0,
+        };
+        Self {
+            name,
+            control,
+            candidate,
+            split,
+            percent: AtomicU8::new(percent),
+            sticky_header: None,
+            seq: AtomicU64::new(0),
+            counters: [Counters::new(), Counters::new()],
+        }
+    }
+
+    /// For [`Split::Percent`], bucket requests by the value of this header
+    /// (e.g. a user id) so the same caller always sees the same variant.
+    /// Requests without the header are spread round-robin.
+    pub const fn sticky_header(mut self, header: &'static str) -> Self {
+        self.sticky_header = Some(header);
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Change the candidate percentage of a [`Split::Percent`] rollout.
+    /// `0` stops the rollout, `100` completes it.
+    pub fn set_percent(&self, percent: u8) {
+        self.percent.store(percent.min(100), Ordering::Relaxed);
+    }
+
+    pub fn percent(&self) -> u8 {
+        self.percent.load(Ordering::Relaxed)
+    }
+
+    /// Pick the variant for a request without running it.
+    pub fn choose(&self, ctx: &Context) -> Variant {
+        match ctx.header(VARIANT_HEADER) {
+            Some(v) if v.eq_ignore_ascii_case("candidate") => return Variant::Candidate,
+            Some(v) if v.eq_ignore_ascii_case("control") => return Variant::Control,
+            _ => {}
+        }
+
+        let candidate = match self.split {
+            Split::Percent(_) => {
+                let percent = self.percent() as u64;
+                let bucket = match self.sticky_header.and_then(|h| ctx.header(h)) {
+                    Some(key) => fnv1a(key.as_bytes()),
+                    None => self.seq.fetch_add(1, Ordering::Relaxed),
+                };
+                bucket % 100 < percent
+            }
+            Split::Header(name) => ctx.header(name).is_some(),
+            Split::Flag(flag) => flag(ctx),
+        };
+        if candidate {
+            Variant::Candidate
+        } else {
+            Variant::Control
+        }
+    }
+
+    /// Route the request to one of the variants and record the outcome.
+    pub fn dispatch(&self, ctx: Context) -> Response {
+        let variant = self.choose(&ctx);
+        let handler = match variant {
+            Variant::Control => self.control,
+            Variant::Candidate => self.candidate,
+        };
+
+        let start = Instant::now();
+        let res = handler(ctx);
+        let elapsed = start.elapsed().as_micros() as u64;
+
+        let counters = &self.counters[variant as usize];
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        counters.latency_us.fetch_add(elapsed, Ordering::Relaxed);
+        if res.status >= 500 {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        res
+    }
+
+    /// Counters for `(control, candidate)`.
+    pub fn stats(&self) -> (VariantStats, VariantStats) {
+        (self.counters[0].snapshot(), self.counters[1].snapshot())
+    }
+}
+
+/// FNV-1a, used to bucket sticky keys.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{MAX_HEADERS, MAX_PARAMS, Method, Request};
+
+    fn old(_ctx: Context) -> Response {
+        Response::text("old")
+    }
+
+    fn new(_ctx: Context) -> Response {
+        Response::server_error()
+    }
+
+    fn ctx<'a>(headers: &[(&'a str, &'a str)]) -> Context<'a> {
+        let mut h = [("", ""); MAX_HEADERS];
+        h[..headers.len()].copy_from_slice(headers);
+        Context {
+            req: Request {
+                method: Method::Get,
+                path: "/",
+                query: None,
+                headers: h,
+                header_count: headers.len() as u8,
+                body: b"",
+            },
+            params: [("", ""); MAX_PARAMS],
+            param_count: 0,
+        }
+    }
+
+    #[test]
+    fn test_percent_split_and_stats() {
+        let rollout = Rollout::new("t", old, new, Split::Percent(25));
+        for _ in 0..100 {
+            rollout.dispatch(ctx(&[]));
+        }
+        let (control, candidate) = rollout.stats();
+        assert_eq!(control.requests, 75);
+        assert_eq!(candidate.requests, 25);
+        assert_eq!(candidate.errors, 25);
+        assert_eq!(control.errors, 0);
+
+        rollout.set_percent(0);
+        assert_eq!(rollout.choose(&ctx(&[])), Variant::Control);
+        rollout.set_percent(250);
+        assert_eq!(rollout.percent(), 100);
+    }
+
+    #[test]
+    fn test_sticky_header_is_stable() {
+        let rollout = Rollout::new("t", old, new, Split::Percent(50)).sticky_header("X-User");
+        let first = rollout.choose(&ctx(&[("X-User", "42")]));
+        for _ in 0..10 {
+            assert_eq!(rollout.choose(&ctx(&[("X-User", "42")])), first);
+        }
+    }
+
+    #[test]
+    fn test_header_flag_and_override() {
+        let by_header = Rollout::new("t", old, new, Split::Header("X-Beta"));
+        assert_eq!(by_header.choose(&ctx(&[])), Variant::Control);
+        assert_eq!(
+            by_header.choose(&ctx(&[("X-Beta", "1")])),
+            Variant::Candidate
+        );
+
+        let by_flag = Rollout::new(
+            "t",
+            old,
+            new,
+            Split::Flag(|c| c.header("X-Staff").is_some()),
+        );
+        assert_eq!(
+            by_flag.choose(&ctx(&[("X-Staff", "y")])),
+            Variant::Candidate
+        );
+
+        let off = Rollout::new("t", old, new, Split::Percent(0));
+        assert_eq!(
+            off.choose(&ctx(&[(VARIANT_HEADER, "candidate")])),
+            Variant::Candidate
+        );
+    }
+}
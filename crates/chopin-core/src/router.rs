@@ -16,6 +16,20 @@ pub const MAX_MIDDLEWARE: usize = 8;
 pub const MAX_SEGMENTS: usize = 16;
 const METHOD_COUNT: usize = 10;
 
+/// Methods in `method_index` order.
+const METHODS: [Method; METHOD_COUNT] = [
+    Method::Get,
+    Method::Post,
+    Method::Put,
+    Method::Delete,
+    Method::Patch,
+    Method::Head,
+    Method::Options,
+    Method::Trace,
+    Method::Connect,
+    Method::Unknown,
+];
+
 /// Result of a successful route match.
 pub type RouteMatch<'a> = (
     &'a Handler,
@@ -34,10 +48,25 @@ pub struct RouteDef {
     pub handler: Handler,
     pub summary: &'static str,
     pub description: &'static str,
+    /// Path parameters bound to typed handler arguments. Empty for handlers
+    /// that only take a [`Context`].
+    pub params: &'static [PathParamDef],
+    /// OpenAPI tags, set with `#[handler(.., tag = "...")]`.
+    pub tags: &'static [&'static str],
 }
 
 inventory::collect!(RouteDef);
 
+/// A typed path parameter of a [`RouteDef`], as declared in the handler
+/// signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PathParamDef {
+    pub name: &'static str,
+    /// OpenAPI `type` of the parameter's schema.
+    pub openapi_type: &'static str,
+    pub openapi_format: Option<&'static str>,
+}
+
 #[derive(Clone)]
 pub(crate) struct RouteNode {
     pub(crate) path: String,
@@ -169,6 +198,18 @@ impl Router {
         current.handlers[method_index(method)] = Some(handler);
     }
 
+    /// Register every route declared with `#[handler(.., tag = "...")]`
+    /// carrying `tag`. Meant for [`ChopinModule::routes`](crate::ChopinModule::routes);
+    /// don't combine it with [`Chopin::mount_all_routes`](crate::Chopin::mount_all_routes),
+    /// which already registers them.
+    pub fn mount_tagged(&mut self, tag: &str) {
+        for route in inventory::iter::<RouteDef> {
+            if route.tags.contains(&tag) {
+                self.add(route.method, route.path, route.handler);
+            }
+        }
+    }
+
     /// Look up a handler for the given method and URL path.
     ///
     /// Returns the matched handler, captured path parameters, parameter count,
@@ -570,6 +611,36 @@ impl Router {
         }
     }
 
+    /// List every registered `(method, path)` pair, using the same `:param`
+    /// and `*wildcard` syntax the routes were registered with.
+    pub fn routes(&self) -> Vec<(Method, String)> {
+        let mut out = Vec::new();
+        Self::collect_routes(&self.root, String::new(), &mut out);
+        out.sort_by(|a, b| a.1.cmp(&b.1).then((a.0 as usize).cmp(&(b.0 as usize))));
+        out
+    }
+
+    fn collect_routes(node: &RouteNode, prefix: String, out: &mut Vec<(Method, String)>) {
+        let path = if prefix.is_empty() {
+            "/".to_string()
+        } else {
+            prefix.clone()
+        };
+        for (i, handler) in node.handlers.iter().enumerate() {
+            if handler.is_some() {
+                out.push((METHODS[i], path.clone()));
+            }
+        }
+        for child in &node.children {
+            let segment = match (&child.param_name, child.is_param, child.is_wildcard) {
+                (Some(name), true, _) => format!(":{name}"),
+                (Some(name), _, true) => format!("*{name}"),
+                _ => child.path.clone(),
+            };
+            Self::collect_routes(child, format!("{prefix}/{segment}"), out);
+        }
+    }
+
     // Convenience methods for common HTTP methods.
     /// Register a `GET` handler.
     pub fn get(&mut self, path: &str, handler: Handler) {
@@ -633,6 +704,32 @@ mod tests {
         assert!(router.match_route(Method::Post, "/hello/world").is_none());
     }
 
+    #[test]
+    fn test_router_routes_listing() {
+        let mut router = Router::new();
+        router.get("/", test_handler);
+        router.get("/users/:id", test_handler);
+        router.delete("/users/:id", test_handler);
+        router.get("/static/*file", test_handler);
+        let api = {
+            let mut r = Router::new();
+            r.post("/items", test_handler);
+            r
+        };
+        let router = router.nest("/api", api);
+
+        assert_eq!(
+            router.routes(),
+            vec![
+                (Method::Get, "/".to_string()),
+                (Method::Post, "/api/items".to_string()),
+                (Method::Get, "/static/*file".to_string()),
+                (Method::Get, "/users/:id".to_string()),
+                (Method::Delete, "/users/:id".to_string()),
+            ]
+        );
+    }
+
     #[test]
     fn test_router_params() {
         let mut router = Router::new();
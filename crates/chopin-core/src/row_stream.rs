@@ -0,0 +1,359 @@
+// src/row_stream.rs
+//! Streaming query results as HTTP responses (`orm` feature).
+//!
+//! A [`RowStream`] runs a query on one of a few producer threads, through
+//! the connection registered with [`db`](crate::db) (each thread keeps its
+//! own executor, as workers do). It reads the result `batch_size` rows at a
+//! time and sends each encoded batch to the response through a bounded
+//! [`Feed`]. The worker parks the connection while it waits for a batch and
+//! serves its other connections meanwhile. It pulls the next batch only once
+//! the socket has taken the previous one, so a slow client stalls the query
+//! instead of growing a buffer, and memory stays at a few batches however
+//! large the export is.
+//!
+//! ```rust,ignore
+//! #[get("/export/orders.ndjson")]
+//! fn export(ctx: Context) -> Response {
+//!     RowStream::new("SELECT id, total, placed_at FROM orders WHERE shop_id = $1")
+//!         .bind(&shop_id(&ctx))
+//!         .batch_size(1_000)
+//!         .ndjson(Order::from_row)
+//! }
+//! ```
+//!
+//! [`json_array`](RowStream::json_array) and [`sse`](RowStream::sse) take
+//! the same mapping. Errors before the first row produce a 500. After that
+//! the status line is gone, so a failure ends the body early: NDJSON gets a
+//! final `{"error":..}` line, SSE an `error` event, and a JSON array is left
+//! unclosed so the client's parser fails instead of seeing a short result.
+//!
+//! At most [`PRODUCER_THREADS`] queries stream at once. Further streams wait,
+//! parked, for a free thread, and a client that stops reading holds its
+//! thread until it disconnects.
+use crate::db;
+use crate::http::Response;
+use crate::longpoll::{Feed, FeedSender, feed};
+use chopin_orm::{DEFAULT_CONNECTION, OrmResult, PgValue, Row, ToSql};
+use serde::Serialize;
+use std::sync::mpsc::{Sender, channel};
+use std::sync::{Arc, Mutex, OnceLock};
+
+/// Rows fetched per `Execute` unless [`RowStream::batch_size`] is set.
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Encoded batches buffered ahead of the socket unless
+/// [`RowStream::buffer`] is set.
+pub const DEFAULT_BUFFER: usize = 4;
+
+/// Threads running stream queries, started on first use.
+pub const PRODUCER_THREADS: usize = 4;
+
+/// Body encoding of a streamed result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// One JSON document per line (`application/x-ndjson`).
+    NdJson,
+    /// A single JSON array (`application/json`).
+    JsonArray,
+    /// Server-sent events, one `data:` event per row, then an `end` event.
+    Sse,
+}
+
+impl StreamFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::NdJson => "application/x-ndjson",
+            Self::JsonArray => "application/json",
+            Self::Sse => "text/event-stream",
+        }
+    }
+}
+
+/// A bound parameter, keeping the type OID of the value it came from.
+struct Param(PgValue, u32);
+
+impl ToSql for Param {
+    fn to_sql(&self) -> PgValue {
+        self.0.clone()
+    }
+
+    fn type_oid(&self) -> u32 {
+        self.1
+    }
+}
+
+/// A query whose rows are streamed into a response body.
+pub struct RowStream {
+    connection: String,
+    sql: String,
+    params: Vec<Param>,
+    batch_size: usize,
+    buffer: usize,
+}
+
+impl RowStream {
+    /// Stream `sql` over the default connection.
+    pub fn new(sql: impl Into<String>) -> Self {
+        Self {
+            connection: DEFAULT_CONNECTION.to_string(),
+            sql: sql.into(),
+            params: Vec::new(),
+            batch_size: DEFAULT_BATCH_SIZE,
+            buffer: DEFAULT_BUFFER,
+        }
+    }
+
+    /// Run on the connection registered as `name` instead.
+    pub fn connection(mut self, name: &str) -> Self {
+        self.connection = name.to_string();
+        self
+    }
+
+    /// Bind the next `$n` parameter.
+    pub fn bind(mut self, value: &dyn ToSql) -> Self {
+        self.params.push(Param(value.to_sql(), value.type_oid()));
+        self
+    }
+
+    /// Rows fetched per round-trip.
+    pub fn batch_size(mut self, rows: usize) -> Self {
+        self.batch_size = rows.max(1);
+        self
+    }
+
+    /// Encoded batches that may wait for the socket before the query pauses.
+    pub fn buffer(mut self, batches: usize) -> Self {
+        self.buffer = batches.max(1);
+        self
+    }
+
+    /// Stream as newline-delimited JSON.
+    pub fn ndjson<T, F>(self, map: F) -> Response
+    where
+        T: Serialize,
+        F: FnMut(&Row) -> OrmResult<T> + Send + 'static,
+    {
+        self.respond(StreamFormat::NdJson, map)
+    }
+
+    /// Stream as one JSON array.
+    pub fn json_array<T, F>(self, map: F) -> Response
+    where
+        T: Serialize,
+        F: FnMut(&Row) -> OrmResult<T> + Send + 'static,
+    {
+        self.respond(StreamFormat::JsonArray, map)
+    }
+
+    /// Stream as server-sent events.
+    pub fn sse<T, F>(self, map: F) -> Response
+    where
+        T: Serialize,
+        F: FnMut(&Row) -> OrmResult<T> + Send + 'static,
+    {
+        self.respond(StreamFormat::Sse, map)
+    }
+
+    /// Stream in `format`, mapping every row with `map`.
+    pub fn respond<T, F>(self, format: StreamFormat, map: F) -> Response
+    where
+        T: Serialize,
+        F: FnMut(&Row) -> OrmResult<T> + Send + 'static,
+    {
+        let (tx, rx) = feed(self.buffer);
+        if producers()
+            .send(Box::new(move || self.produce(format, map, tx)))
+            .is_err()
+        {
+            return Response::server_error();
+        }
+        // The first batch, or the sender hanging up without one, decides
+        // the status.
+        rx.respond(move |body: Option<Feed>| {
+            let Some(body) = body else {
+                return Response::server_error();
+            };
+            let mut response = Response::feed(body);
+            response.content_type = format.content_type();
+            if format == StreamFormat::Sse {
+                response = response.with_header("Cache-Control", "no-cache");
+            }
+            response
+        })
+    }
+
+    /// Runs on a producer thread: query, encode and send until done,
+    /// failed, or the response is dropped (send fails).
+    fn produce<T, F>(self, format: StreamFormat, mut map: F, tx: FeedSender)
+    where
+        T: Serialize,
+        F: FnMut(&Row) -> OrmResult<T>,
+    {
+        let mut encoder = Encoder::new(format);
+        let mut opened = false;
+        let params: Vec<&dyn ToSql> = self.params.iter().map(|p| p as &dyn ToSql).collect();
+        let result = db::with_named_db(&self.connection, |db| {
+            db.query_batches(&self.sql, &params, self.batch_size, &mut |rows| {
+                let mut buf = encoder.open();
+                for row in &rows {
+                    encoder.row(&mut buf, &map(row)?)?;
+                }
+                opened = true;
+                Ok(tx.send(buf).is_ok())
+            })
+        });
+        match result {
+            Ok(()) => {
+                let mut buf = if opened { Vec::new() } else { encoder.open() };
+                encoder.close(&mut buf);
+                let _ = tx.send(buf);
+            }
+            Err(e) => {
+                eprintln!("[chopin] row stream `{}` failed: {e}", self.sql);
+                // Before the first batch, hanging up is what makes the 500.
+                if opened {
+                    let _ = tx.send(encoder.error());
+                }
+            }
+        }
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// Queue of the producer threads, started on first use.
+fn producers() -> &'static Sender<Job> {
+    static PRODUCERS: OnceLock<Sender<Job>> = OnceLock::new();
+    PRODUCERS.get_or_init(|| {
+        let (tx, rx) = channel::<Job>();
+        let rx = Arc::new(Mutex::new(rx));
+        for i in 0..PRODUCER_THREADS {
+            let rx = rx.clone();
+            let _ = std::thread::Builder::new()
+                .name(format!("chopin-row-stream-{i}"))
+                .spawn(move || {
+                    loop {
+                        let job = rx.lock().unwrap_or_else(|e| e.into_inner()).recv();
+                        let Ok(job) = job else {
+                            return;
+                        };
+                        // A panicking row mapping ends its stream, not the thread.
+                        let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(job));
+                    }
+                });
+        }
+        tx
+    })
+}
+
+/// Frames rows for one [`StreamFormat`].
+struct Encoder {
+    format: StreamFormat,
+    started: bool,
+    rows: usize,
+}
+
+impl Encoder {
+    fn new(format: StreamFormat) -> Self {
+        Self {
+            format,
+            started: false,
+            rows: 0,
+        }
+    }
+
+    /// Buffer for the next batch, starting with the array opener if nothing
+    /// has been sent yet.
+    fn open(&mut self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        if !self.started && self.format == StreamFormat::JsonArray {
+            buf.push(b'[');
+        }
+        self.started = true;
+        buf
+    }
+
+    fn row<T: Serialize>(&mut self, buf: &mut Vec<u8>, value: &T) -> OrmResult<()> {
+        match self.format {
+            StreamFormat::NdJson => {
+                Self::json(buf, value)?;
+                buf.push(b'\n');
+            }
+            StreamFormat::JsonArray => {
+                if self.rows > 0 {
+                    buf.push(b',');
+                }
+                Self::json(buf, value)?;
+            }
+            StreamFormat::Sse => {
+                buf.extend_from_slice(b"data: ");
+                Self::json(buf, value)?;
+                buf.extend_from_slice(b"\n\n");
+            }
+        }
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn close(&self, buf: &mut Vec<u8>) {
+        match self.format {
+            StreamFormat::NdJson => {}
+            StreamFormat::JsonArray => buf.push(b']'),
+            StreamFormat::Sse => buf.extend_from_slice(b"event: end\ndata:\n\n"),
+        }
+    }
+
+    /// What the client sees when the query fails mid-stream.
+    fn error(&self) -> Vec<u8> {
+        match self.format {
+            StreamFormat::NdJson => b"{\"error\":\"stream aborted\"}\n".to_vec(),
+            StreamFormat::JsonArray => Vec::new(),
+            StreamFormat::Sse => b"event: error\ndata: stream aborted\n\n".to_vec(),
+        }
+    }
+
+    fn json<T: Serialize>(buf: &mut Vec<u8>, value: &T) -> OrmResult<()> {
+        serde_json::to_writer(buf, value)
+            .map_err(|e| chopin_orm::OrmError::Extraction(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(format: StreamFormat, batches: &[&[i32]]) -> String {
+        let mut encoder = Encoder::new(format);
+        let mut out = Vec::new();
+        for batch in batches {
+            let mut buf = encoder.open();
+            for v in *batch {
+                encoder.row(&mut buf, v).unwrap();
+            }
+            out.extend(buf);
+        }
+        let mut buf = if batches.is_empty() {
+            encoder.open()
+        } else {
+            Vec::new()
+        };
+        encoder.close(&mut buf);
+        out.extend(buf);
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_json_array_spans_batches() {
+        assert_eq!(encode(StreamFormat::JsonArray, &[&[1, 2], &[3]]), "[1,2,3]");
+        assert_eq!(encode(StreamFormat::JsonArray, &[]), "[]");
+    }
+
+    #[test]
+    fn test_ndjson_and_sse_framing() {
+        assert_eq!(encode(StreamFormat::NdJson, &[&[1], &[2]]), "1\n2\n");
+        assert_eq!(
+            encode(StreamFormat::Sse, &[&[7]]),
+            "data: 7\n\nevent: end\ndata:\n\n"
+        );
+    }
+}
@@ -1,5 +1,7 @@
 // src/server.rs
-use crate::error::ChopinError;
+use crate::error::{ChopinError, ChopinResult};
+use crate::module::ChopinModule;
+use crate::openapi::DocsConfig;
 use crate::router::Router;
 use crate::syscalls::{self};
 use crate::worker::Worker;
@@ -7,6 +9,70 @@ use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
 
+/// Hidden command-line flag that makes [`Chopin::serve`] print the OpenAPI
+/// spec to stdout instead of starting the server.
+pub const PRINT_OPENAPI_FLAG: &str = "--print-openapi";
+
+/// What [`Chopin::serve`] should do, as selected by command-line flags.
+///
+/// Unrecognised arguments are ignored so applications remain free to parse
+/// their own flags; the first recognised flag wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartupCommand {
+    /// No maintenance flag: start the server.
+    Serve,
+    /// `--migrate`: apply every module's pending migrations.
+    Migrate,
+    /// `--rollback N`: revert the last `N` migrations of every module.
+    Rollback(u32),
+    /// `--seed`: run every module's seeder.
+    Seed,
+    /// `--print-routes`: list the registered routes.
+    PrintRoutes,
+    /// `--print-openapi`: write the OpenAPI spec to stdout.
+    PrintOpenApi,
+    /// `--print-plugins`: list the mounted plugins.
+    PrintPlugins,
+}
+
+impl StartupCommand {
+    /// Parse the command from process arguments (excluding the program name).
+    pub fn from_args<I, S>(args: I) -> ChopinResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let arg = arg.as_ref();
+            let cmd = match arg {
+                "--migrate" => Self::Migrate,
+                "--seed" => Self::Seed,
+                "--print-routes" => Self::PrintRoutes,
+                PRINT_OPENAPI_FLAG => Self::PrintOpenApi,
+                "--print-plugins" => Self::PrintPlugins,
+                "--rollback" => {
+                    let steps = args.next().ok_or_else(|| {
+                        ChopinError::Other("--rollback requires a step count".into())
+                    })?;
+                    Self::Rollback(parse_steps(steps.as_ref())?)
+                }
+                _ => match arg.strip_prefix("--rollback=") {
+                    Some(steps) => Self::Rollback(parse_steps(steps)?),
+                    None => continue,
+                },
+            };
+            return Ok(cmd);
+        }
+        Ok(Self::Serve)
+    }
+}
+
+fn parse_steps(s: &str) -> ChopinResult<u32> {
+    s.parse()
+        .map_err(|_| ChopinError::Other(format!("invalid --rollback step count `{s}`")))
+}
+
 /// High-level application builder for Chopin.
 ///
 /// Collects routes registered via `#[get]`/`#[post]`/… macros, optionally
@@ -29,8 +95,16 @@ use std::thread;
 ///         .unwrap();
 /// }
 /// ```
+/// Opens the connection `--migrate` and `--rollback` run module migrations on.
+#[cfg(feature = "orm")]
+pub type MigrationsExecutor = fn() -> chopin_orm::OrmResult<Box<dyn chopin_orm::Executor>>;
+
 pub struct Chopin {
     router: Router,
+    modules: Vec<Box<dyn ChopinModule>>,
+    plugins: Vec<crate::plugin::Registered>,
+    #[cfg(feature = "orm")]
+    migrations_executor: Option<MigrationsExecutor>,
 }
 
 impl Default for Chopin {
@@ -44,6 +118,10 @@ impl Chopin {
     pub fn new() -> Self {
         Self {
             router: Router::new(),
+            modules: Vec::new(),
+            plugins: Vec::new(),
+            #[cfg(feature = "orm")]
+            migrations_executor: None,
         }
     }
 
@@ -56,19 +134,221 @@ impl Chopin {
         self
     }
 
+    /// Mount a [`ChopinModule`]: register its routes and include it in
+    /// startup hooks and `--migrate`, `--rollback` and `--seed` runs.
+    ///
+    /// Modules whose [`enabled`](ChopinModule::enabled) returns `false` are
+    /// skipped. Dependencies are checked when the application starts.
+    pub fn mount_module<M: ChopinModule + 'static>(mut self, module: M) -> Self {
+        self.mount_boxed(Box::new(module));
+        self
+    }
+
+    fn mount_boxed(&mut self, module: Box<dyn ChopinModule>) {
+        if !module.enabled() {
+            return;
+        }
+        module.routes(&mut self.router);
+        self.modules.push(module);
+    }
+
+    /// Mount every [`Plugin`](crate::plugin::Plugin) linked into the binary,
+    /// with its modules. Plugins are checked when the application starts.
+    pub fn mount_plugins(mut self) -> Self {
+        for registered in crate::plugin::discover() {
+            for module in registered.plugin.modules() {
+                self.mount_boxed(module);
+            }
+            self.plugins.push(registered);
+        }
+        self
+    }
+
+    /// Set the database connection used to apply modules'
+    /// [`migrations`](ChopinModule::migrations).
+    ///
+    /// ```rust,ignore
+    /// Chopin::new()
+    ///     .mount_module(Users)
+    ///     .migrations_executor(|| Ok(Box::new(PgPool::connect(db_config(), 1)?)))
+    /// ```
+    #[cfg(feature = "orm")]
+    pub fn migrations_executor(mut self, connect: MigrationsExecutor) -> Self {
+        self.migrations_executor = Some(connect);
+        self
+    }
+
     /// Enable the built-in OpenAPI documentation at `/openapi.json` and `/docs`.
-    pub fn with_openapi(mut self) -> Self {
+    pub fn with_openapi(self) -> Self {
+        self.with_docs(DocsConfig::default())
+    }
+
+    /// Enable the built-in OpenAPI documentation with a custom [`DocsConfig`].
+    ///
+    /// Mounts nothing when `config.enabled` is `false`. Each configured group
+    /// gets its own spec at `{groups_path}/{name}` and page at `{docs_path}/{name}`.
+    pub fn with_docs(mut self, config: DocsConfig) -> Self {
+        if !config.enabled {
+            return self;
+        }
+
         self.router
-            .get("/openapi.json", crate::openapi::openapi_json_handler);
+            .get(&config.spec_path, crate::openapi::openapi_json_handler);
         self.router
-            .get("/docs", crate::openapi::scalar_docs_handler);
+            .get(&config.docs_path, crate::openapi::scalar_docs_handler);
+
+        let groups_path = config.groups_path.trim_end_matches('/');
+        let docs_path = config.docs_path.trim_end_matches('/');
+        if !config.groups.is_empty() {
+            self.router.get(
+                &format!("{groups_path}/:group"),
+                crate::openapi::group_spec_handler,
+            );
+            self.router.get(
+                &format!("{docs_path}/:group"),
+                crate::openapi::group_docs_handler,
+            );
+        }
+
+        if let Some(guard) = config.guard {
+            self.router.layer_path(&config.spec_path, guard);
+            self.router.layer_path(&config.docs_path, guard);
+            if !config.groups.is_empty() {
+                self.router.layer_path(groups_path, guard);
+            }
+        }
+
+        crate::openapi::set_docs_config(config);
         self
     }
 
     /// Start the server, binding to `host_port` (e.g. `"0.0.0.0:8080"`).
-    pub fn serve(self, host_port: &str) -> crate::error::ChopinResult<()> {
-        let server = Server::bind(host_port);
-        server.serve(self.router)
+    ///
+    /// Maintenance flags on the command line run instead of the server (see
+    /// [`StartupCommand`]):
+    ///
+    /// - `--migrate` / `--seed` run each mounted module's hook in dependency
+    ///   order.
+    /// - `--rollback N` runs the modules' rollbacks in reverse dependency order.
+    /// - `--print-routes` lists every registered route.
+    /// - `--print-openapi` writes the OpenAPI spec to stdout; `chopin docs
+    ///   export` relies on this to capture the spec of the user's application.
+    /// - `--print-plugins` lists the mounted plugins.
+    /// - `--run <plugin>:<command> [args...]` runs a plugin command.
+    ///
+    /// Without a flag, the plugins' config sections are checked and every
+    /// module's [`on_start`](ChopinModule::on_start) hook runs before the
+    /// server binds.
+    pub fn serve(self, host_port: &str) -> ChopinResult<()> {
+        if let Some((command, args)) = crate::plugin::run_args(std::env::args().skip(1))? {
+            crate::plugin::check(&self.plugins, crate::config::config().as_deref())?;
+            return crate::plugin::run_command(&self.plugins, &command, &args);
+        }
+        match StartupCommand::from_args(std::env::args().skip(1))? {
+            StartupCommand::Serve => {
+                crate::plugin::check(&self.plugins, crate::config::config().as_deref())?;
+                self.each_module(false, "start", |m| m.on_start())?;
+                Server::bind(host_port).serve(self.router)
+            }
+            cmd => self.run_command(cmd),
+        }
+    }
+
+    /// Run a maintenance command without starting the server.
+    pub fn run_command(self, cmd: StartupCommand) -> ChopinResult<()> {
+        match cmd {
+            StartupCommand::Serve => Ok(()),
+            StartupCommand::Migrate => {
+                #[cfg(feature = "orm")]
+                self.run_orm_migrations(None)?;
+                self.each_module(false, "migrate", |m| m.migrate())
+            }
+            StartupCommand::Rollback(steps) => {
+                self.each_module(true, "rollback", |m| m.rollback(steps))?;
+                #[cfg(feature = "orm")]
+                self.run_orm_migrations(Some(steps))?;
+                Ok(())
+            }
+            StartupCommand::Seed => self.each_module(false, "seed", |m| m.seed()),
+            StartupCommand::PrintRoutes => {
+                for (method, path) in self.router.routes() {
+                    let method = crate::openapi::method_name(method).to_uppercase();
+                    println!("{method:<8}{path}");
+                }
+                Ok(())
+            }
+            StartupCommand::PrintOpenApi => {
+                let spec = crate::openapi::generate_spec();
+                let json = serde_json::to_string_pretty(&spec)
+                    .map_err(|e| ChopinError::Other(format!("Failed to serialize spec: {e}")))?;
+                println!("{json}");
+                Ok(())
+            }
+            StartupCommand::PrintPlugins => {
+                print!("{}", crate::plugin::describe(&self.plugins));
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply (`rollback: None`) or revert the modules' chopin-orm migrations.
+    #[cfg(feature = "orm")]
+    fn run_orm_migrations(&self, rollback: Option<u32>) -> ChopinResult<()> {
+        use chopin_orm::{DEFAULT_CONNECTION, MigrationManager, ModuleMigrations};
+        use std::collections::BTreeMap;
+
+        // Group by connection, keeping dependency order within each group.
+        let mut by_connection: BTreeMap<&'static str, Vec<ModuleMigrations>> = BTreeMap::new();
+        for i in crate::module::dependency_order(&self.modules)? {
+            let module = &self.modules[i];
+            let migrations = ModuleMigrations::new(module.name(), module.migrations());
+            if !migrations.migrations.is_empty() {
+                by_connection
+                    .entry(module.connection())
+                    .or_default()
+                    .push(migrations);
+            }
+        }
+
+        let orm_err = |e: chopin_orm::OrmError| ChopinError::Other(format!("migrations: {e}"));
+        for (name, modules) in by_connection {
+            let mut executor = match (name, self.migrations_executor) {
+                (DEFAULT_CONNECTION, Some(connect)) => connect().map_err(orm_err)?,
+                _ if crate::db::is_registered(name) => crate::db::connect(name)?,
+                (DEFAULT_CONNECTION, None) => {
+                    return Err(ChopinError::Other(
+                        "modules declare migrations but no migrations_executor is set".into(),
+                    ));
+                }
+                _ => {
+                    return Err(ChopinError::Other(format!(
+                        "modules declare migrations for database `{name}`, which is not registered"
+                    )));
+                }
+            };
+            match rollback {
+                None => MigrationManager::up_modules(executor.as_mut(), &modules),
+                Some(steps) => MigrationManager::down_modules(executor.as_mut(), &modules, steps),
+            }
+            .map_err(orm_err)?;
+        }
+        Ok(())
+    }
+
+    fn each_module(
+        &self,
+        reverse: bool,
+        task: &str,
+        f: impl Fn(&dyn ChopinModule) -> ChopinResult<()>,
+    ) -> ChopinResult<()> {
+        let mut order = crate::module::dependency_order(&self.modules)?;
+        if reverse {
+            order.reverse();
+        }
+        order.into_iter().try_for_each(|i| {
+            let m = &*self.modules[i];
+            f(m).map_err(|e| ChopinError::Other(format!("{task} `{}`: {e}", m.name())))
+        })
     }
 }
 
@@ -96,14 +376,19 @@ impl Chopin {
 pub struct Server {
     host_port: String,
     workers: usize,
+    auto_tune: bool,
 }
 
+/// How often the auto-tune monitor inspects worker metrics.
+const AUTO_TUNE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
 impl Server {
     /// Bind to the given address. Defaults to one worker per logical CPU.
     pub fn bind(host_port: &str) -> Self {
         Self {
             host_port: host_port.to_string(),
             workers: num_cpus::get(),
+            auto_tune: false,
         }
     }
 
@@ -113,6 +398,13 @@ impl Server {
         self
     }
 
+    /// Periodically inspect loop lag, accept-queue depth and connection usage,
+    /// and log tuning recommendations to stderr. Off by default.
+    pub fn auto_tune(mut self, enabled: bool) -> Self {
+        self.auto_tune = enabled;
+        self
+    }
+
     /// Start the server with the provided router. Spawns one thread per worker,
     /// each pinned to a CPU core, and blocks until shutdown.
     pub fn serve(self, mut router: Router) -> crate::error::ChopinResult<()> {
@@ -132,9 +424,35 @@ impl Server {
         for _ in 0..self.workers {
             worker_metrics.push(Arc::new(crate::metrics::WorkerMetrics::new()));
         }
+        crate::metrics::publish(worker_metrics.clone());
 
-        let _metrics_clones = worker_metrics.clone();
-        let _shutdown_metrics = shutdown_flag.clone();
+        if self.auto_tune {
+            let metrics = worker_metrics.clone();
+            let shutdown = shutdown_flag.clone();
+            let slab_capacity = crate::worker::slab_capacity_from_env();
+            thread::Builder::new()
+                .name("chopin-auto-tune".into())
+                .spawn(move || {
+                    #[cfg(feature = "orm-metrics")]
+                    let mut orm_prev = chopin_orm::metrics::snapshot();
+                    while !shutdown.load(Ordering::Acquire) {
+                        thread::sleep(AUTO_TUNE_INTERVAL);
+                        let snapshots: Vec<_> = metrics.iter().map(|m| m.take_snapshot()).collect();
+                        let recs = crate::metrics::recommendations(&snapshots, slab_capacity);
+                        #[cfg(feature = "orm-metrics")]
+                        let recs = {
+                            let orm_cur = chopin_orm::metrics::snapshot();
+                            let orm_recs = crate::metrics::orm_recommendations(&orm_prev, &orm_cur);
+                            orm_prev = orm_cur;
+                            [recs, orm_recs].concat()
+                        };
+                        for rec in recs {
+                            eprintln!("[chopin] auto-tune: {rec}");
+                        }
+                    }
+                })
+                .map_err(ChopinError::from)?;
+        }
 
         let Parts { host, port } = parse_host_port(&self.host_port)?;
 
@@ -180,6 +498,9 @@ impl Server {
         for handle in handles {
             let _ = handle.join();
         }
+        crate::logging::flush();
+        #[cfg(feature = "sentry")]
+        crate::error_tracking::flush(std::time::Duration::from_secs(2));
 
         Ok(())
     }
@@ -222,6 +543,46 @@ fn parse_host_port(hp: &str) -> crate::error::ChopinResult<Parts> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::http::Method;
+
+    #[test]
+    fn test_with_docs_custom_paths_and_groups() {
+        let mut app = Chopin::new().with_docs(
+            DocsConfig::new()
+                .docs_path("/reference")
+                .group("admin", "/admin"),
+        );
+        app.router.finalize();
+        assert!(app.router.match_route(Method::Get, "/reference").is_some());
+        assert!(
+            app.router
+                .match_route(Method::Get, "/openapi.json")
+                .is_some()
+        );
+        assert!(
+            app.router
+                .match_route(Method::Get, "/api-docs/admin")
+                .is_some()
+        );
+        assert!(
+            app.router
+                .match_route(Method::Get, "/reference/admin")
+                .is_some()
+        );
+        assert!(app.router.match_route(Method::Get, "/docs").is_none());
+    }
+
+    #[test]
+    fn test_with_docs_disabled_mounts_nothing() {
+        let mut app = Chopin::new().with_docs(DocsConfig::new().enabled(false));
+        app.router.finalize();
+        assert!(app.router.match_route(Method::Get, "/docs").is_none());
+        assert!(
+            app.router
+                .match_route(Method::Get, "/openapi.json")
+                .is_none()
+        );
+    }
 
     #[test]
     fn test_parse_ipv4() {
@@ -255,4 +616,161 @@ mod tests {
     fn test_parse_missing_port() {
         assert!(parse_host_port("0.0.0.0").is_err());
     }
+
+    #[test]
+    fn test_startup_command_from_args() {
+        let parse = |args: &[&str]| StartupCommand::from_args(args.iter().copied());
+        assert_eq!(parse(&[]).unwrap(), StartupCommand::Serve);
+        assert_eq!(parse(&["--port", "80"]).unwrap(), StartupCommand::Serve);
+        assert_eq!(parse(&["--migrate"]).unwrap(), StartupCommand::Migrate);
+        assert_eq!(parse(&["--seed"]).unwrap(), StartupCommand::Seed);
+        assert_eq!(
+            parse(&["--verbose", "--print-routes"]).unwrap(),
+            StartupCommand::PrintRoutes
+        );
+        assert_eq!(
+            parse(&[PRINT_OPENAPI_FLAG]).unwrap(),
+            StartupCommand::PrintOpenApi
+        );
+        assert_eq!(
+            parse(&["--rollback", "3"]).unwrap(),
+            StartupCommand::Rollback(3)
+        );
+        assert_eq!(
+            parse(&["--rollback=2"]).unwrap(),
+            StartupCommand::Rollback(2)
+        );
+        assert!(parse(&["--rollback"]).is_err());
+        assert!(parse(&["--rollback", "x"]).is_err());
+    }
+
+    // ─── Modules ─────────────────────────────────────────────────────────────
+
+    static MODULE_LOG: std::sync::Mutex<Vec<String>> = std::sync::Mutex::new(Vec::new());
+
+    struct TestModule(&'static str, &'static [&'static str]);
+
+    fn module_ping(_ctx: crate::http::Context) -> crate::http::Response {
+        crate::http::Response::text("pong")
+    }
+
+    impl ChopinModule for TestModule {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+        fn depends_on(&self) -> &[&'static str] {
+            self.1
+        }
+        fn enabled(&self) -> bool {
+            self.0 != "disabled"
+        }
+        fn routes(&self, router: &mut Router) {
+            router.get(&format!("/{}/ping", self.0), module_ping);
+        }
+        fn migrate(&self) -> ChopinResult<()> {
+            MODULE_LOG
+                .lock()
+                .unwrap()
+                .push(format!("migrate {}", self.0));
+            Ok(())
+        }
+        fn rollback(&self, steps: u32) -> ChopinResult<()> {
+            if self.0 == "broken" {
+                return Err(ChopinError::Other("boom".into()));
+            }
+            MODULE_LOG
+                .lock()
+                .unwrap()
+                .push(format!("rollback {} {steps}", self.0));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_module_commands_run_in_dependency_order() {
+        let app = || {
+            Chopin::new()
+                .mount_module(TestModule("posts", &["users"]))
+                .mount_module(TestModule("users", &[]))
+                .mount_module(TestModule("disabled", &[]))
+        };
+        let mut routed = app();
+        routed.router.finalize();
+        assert!(
+            routed
+                .router
+                .match_route(Method::Get, "/posts/ping")
+                .is_some()
+        );
+        assert_eq!(routed.modules.len(), 2);
+
+        app().run_command(StartupCommand::Migrate).unwrap();
+        app().run_command(StartupCommand::Rollback(2)).unwrap();
+        let log = std::mem::take(&mut *MODULE_LOG.lock().unwrap());
+        assert_eq!(
+            log,
+            [
+                "migrate users",
+                "migrate posts",
+                "rollback posts 2",
+                "rollback users 2"
+            ]
+        );
+
+        let err = Chopin::new()
+            .mount_module(TestModule("broken", &[]))
+            .run_command(StartupCommand::Rollback(1))
+            .unwrap_err();
+        assert!(err.to_string().contains("rollback `broken`"), "{err}");
+
+        // Depending on a disabled module is the same as depending on a missing one.
+        let err = Chopin::new()
+            .mount_module(TestModule("disabled", &[]))
+            .mount_module(TestModule("posts", &["disabled"]))
+            .run_command(StartupCommand::Migrate)
+            .unwrap_err();
+        assert!(err.to_string().contains("not mounted"), "{err}");
+        assert!(MODULE_LOG.lock().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "orm")]
+    #[test]
+    fn test_module_orm_migrations() {
+        static APPLIED: AtomicBool = AtomicBool::new(false);
+        struct Schema;
+        impl chopin_orm::Migration for Schema {
+            fn name(&self) -> &'static str {
+                "001_schema"
+            }
+            fn up(&self, _: &mut dyn chopin_orm::Executor) -> chopin_orm::OrmResult<()> {
+                APPLIED.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+            fn down(&self, _: &mut dyn chopin_orm::Executor) -> chopin_orm::OrmResult<()> {
+                Ok(())
+            }
+        }
+        struct WithSchema;
+        impl ChopinModule for WithSchema {
+            fn name(&self) -> &'static str {
+                "with_schema"
+            }
+            fn migrations(&self) -> Vec<Box<dyn chopin_orm::Migration>> {
+                vec![Box::new(Schema)]
+            }
+        }
+
+        let err = Chopin::new()
+            .mount_module(WithSchema)
+            .run_command(StartupCommand::Migrate)
+            .unwrap_err();
+        assert!(err.to_string().contains("no migrations_executor"), "{err}");
+
+        Chopin::new()
+            .mount_module(WithSchema)
+            .migrations_executor(|| Ok(Box::new(chopin_orm::MockExecutor::new())))
+            .run_command(StartupCommand::Migrate)
+            .unwrap();
+        assert!(APPLIED.load(Ordering::Relaxed));
+    }
 }
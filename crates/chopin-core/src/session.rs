@@ -0,0 +1,640 @@
+// src/session.rs
+//! Server-side sessions and flash messages for classic web apps.
+//!
+//! The browser holds only a random session id in a cookie; the data lives in
+//! a [`SessionStore`]. [`MemoryStore`] keeps it in the process, and with the
+//! `orm` feature [`PgSessionStore`] keeps it in the `chopin_sessions` table.
+//! Other backends (Redis, memcached) implement the trait.
+//!
+//! ```rust,ignore
+//! Chopin::new().mount_module(SessionModule::new(MemoryStore::new()));
+//!
+//! #[post("/login")]
+//! fn login(ctx: Context, form: Form<Login>) -> Response {
+//!     let Some(user) = check_password(&form) else {
+//!         session::flash("error", "Wrong email or password");
+//!         return Response::new(303).with_header("Location", "/login");
+//!     };
+//!     session::regenerate();
+//!     let _ = session::insert("user_id", &user.id);
+//!     session::flash("success", "Welcome back!");
+//!     Response::new(303).with_header("Location", "/")
+//! }
+//!
+//! #[get("/")]
+//! fn home(_ctx: Context) -> Response {
+//!     let user: Option<i64> = session::get("user_id");
+//!     render_home(user, &session::flash_html())
+//! }
+//! ```
+//!
+//! Session functions act on the request being handled on the calling
+//! thread and do nothing outside the [`session_layer`] middleware. A flash
+//! message is shown on the next request that loads the session and then
+//! dropped, which is what a redirect-after-POST needs.
+//!
+//! Nothing is stored and no cookie is sent until a handler writes to the
+//! session, so anonymous traffic costs no storage.
+use crate::error::{ChopinError, ChopinResult};
+use crate::form::{escape_html, is_token, new_token};
+use crate::http::{Context, Response};
+use crate::module::ChopinModule;
+use crate::router::{BoxedHandler, Router};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+/// Contents of one session.
+pub type SessionData = serde_json::Map<String, serde_json::Value>;
+
+/// Session key holding flash messages queued for the next request.
+pub const FLASH_KEY: &str = "_flash";
+
+/// Default idle lifetime of a session.
+pub const DEFAULT_TTL: Duration = Duration::from_secs(14 * 24 * 60 * 60);
+
+/// Where session data lives between requests.
+///
+/// Ids are 64 hex characters from the OS random source. A session not
+/// saved again within its `ttl` must stop loading.
+pub trait SessionStore: Send + Sync {
+    fn load(&self, id: &str) -> ChopinResult<Option<SessionData>>;
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> ChopinResult<()>;
+    fn delete(&self, id: &str) -> ChopinResult<()>;
+
+    /// Migrations creating the store's tables, run by `--migrate` on behalf
+    /// of [`SessionModule`].
+    #[cfg(feature = "orm")]
+    fn migrations(&self) -> Vec<Box<dyn chopin_orm::Migration>> {
+        Vec::new()
+    }
+}
+
+/// Lets the application keep a handle on the store it mounted.
+impl<S: SessionStore + ?Sized> SessionStore for Arc<S> {
+    fn load(&self, id: &str) -> ChopinResult<Option<SessionData>> {
+        (**self).load(id)
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> ChopinResult<()> {
+        (**self).save(id, data, ttl)
+    }
+
+    fn delete(&self, id: &str) -> ChopinResult<()> {
+        (**self).delete(id)
+    }
+
+    #[cfg(feature = "orm")]
+    fn migrations(&self) -> Vec<Box<dyn chopin_orm::Migration>> {
+        (**self).migrations()
+    }
+}
+
+/// In-process store. Sessions are lost on restart and not shared between
+/// instances, so it suits development and single-instance deployments.
+#[derive(Default)]
+pub struct MemoryStore {
+    sessions: Mutex<HashMap<String, (SessionData, Instant)>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        let now = Instant::now();
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .filter(|(_, expires)| *expires > now)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl SessionStore for MemoryStore {
+    fn load(&self, id: &str) -> ChopinResult<Option<SessionData>> {
+        let sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(sessions
+            .get(id)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(data, _)| data.clone()))
+    }
+
+    fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> ChopinResult<()> {
+        let now = Instant::now();
+        let mut sessions = self.sessions.lock().unwrap_or_else(|e| e.into_inner());
+        sessions.retain(|_, (_, expires)| *expires > now);
+        sessions.insert(id.to_string(), (data.clone(), now + ttl));
+        Ok(())
+    }
+
+    fn delete(&self, id: &str) -> ChopinResult<()> {
+        self.sessions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(id);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "orm")]
+pub use pg_store::PgSessionStore;
+
+#[cfg(feature = "orm")]
+mod pg_store {
+    use super::{SessionData, SessionStore};
+    use crate::db;
+    use crate::error::{ChopinError, ChopinResult};
+    use chopin_orm::{Executor, Migration, OrmResult};
+    use std::time::Duration;
+
+    const CREATE_TABLE_SQL: &str = r#"
+        CREATE TABLE IF NOT EXISTS chopin_sessions (
+            id TEXT PRIMARY KEY,
+            data TEXT NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL
+        )
+    "#;
+
+    const CREATE_INDEX_SQL: &str = "CREATE INDEX IF NOT EXISTS chopin_sessions_expires_idx \
+         ON chopin_sessions (expires_at)";
+
+    /// Sessions in the `chopin_sessions` table, on the worker's default
+    /// connection. Expired rows stop loading at once; remove them now and
+    /// then with [`delete_expired`](Self::delete_expired), e.g. from a cron
+    /// job.
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct PgSessionStore;
+
+    impl PgSessionStore {
+        pub fn new() -> Self {
+            Self
+        }
+
+        pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+            executor.execute(CREATE_TABLE_SQL, &[])?;
+            executor.execute(CREATE_INDEX_SQL, &[])?;
+            Ok(())
+        }
+
+        /// Delete expired sessions. Returns how many were removed.
+        pub fn delete_expired(executor: &mut dyn Executor) -> OrmResult<u64> {
+            executor.execute("DELETE FROM chopin_sessions WHERE expires_at <= NOW()", &[])
+        }
+    }
+
+    impl SessionStore for PgSessionStore {
+        fn load(&self, id: &str) -> ChopinResult<Option<SessionData>> {
+            let data: Option<String> = db::with_db(|db| {
+                let rows = db.query(
+                    "SELECT data FROM chopin_sessions WHERE id = $1 AND expires_at > NOW()",
+                    &[&id],
+                )?;
+                match rows.first() {
+                    Some(row) => Ok(Some(row.get_typed(0)?)),
+                    None => Ok(None),
+                }
+            })?;
+            data.map(|data| serde_json::from_str(&data))
+                .transpose()
+                .map_err(|e| ChopinError::Other(format!("session {id}: {e}")))
+        }
+
+        fn save(&self, id: &str, data: &SessionData, ttl: Duration) -> ChopinResult<()> {
+            let json = serde_json::to_string(data)
+                .map_err(|e| ChopinError::Other(format!("session {id}: {e}")))?;
+            let ttl = ttl.as_secs() as i64;
+            db::with_db(|db| {
+                db.execute(
+                    "INSERT INTO chopin_sessions (id, data, expires_at) \
+                     VALUES ($1, $2, NOW() + $3::BIGINT * INTERVAL '1 second') \
+                     ON CONFLICT (id) DO UPDATE \
+                     SET data = EXCLUDED.data, expires_at = EXCLUDED.expires_at",
+                    &[&id, &json, &ttl],
+                )
+            })?;
+            Ok(())
+        }
+
+        fn delete(&self, id: &str) -> ChopinResult<()> {
+            db::with_db(|db| db.execute("DELETE FROM chopin_sessions WHERE id = $1", &[&id]))?;
+            Ok(())
+        }
+
+        fn migrations(&self) -> Vec<Box<dyn Migration>> {
+            vec![Box::new(CreateSessionsTable)]
+        }
+    }
+
+    struct CreateSessionsTable;
+
+    impl Migration for CreateSessionsTable {
+        fn name(&self) -> &'static str {
+            "001_create_sessions"
+        }
+
+        fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+            PgSessionStore::ensure_table(executor)
+        }
+
+        fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+            executor.execute("DROP TABLE IF EXISTS chopin_sessions", &[])?;
+            Ok(())
+        }
+    }
+}
+
+// ─── Flash messages ──────────────────────────────────────────────────────────
+
+/// A one-shot message for the next page, e.g. "Saved" after a redirect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Flash {
+    /// Category for styling: `"success"`, `"error"`, ...
+    pub kind: String,
+    pub message: String,
+}
+
+// ─── Request state ───────────────────────────────────────────────────────────
+
+struct Settings {
+    store: Arc<dyn SessionStore>,
+    cookie: String,
+    secure: bool,
+    ttl: Duration,
+}
+
+static SESSIONS: RwLock<Option<Arc<Settings>>> = RwLock::new(None);
+
+#[derive(Default)]
+struct State {
+    /// Id from the cookie, if it named a live session.
+    id: Option<String>,
+    data: SessionData,
+    /// Flash messages left by the previous request.
+    incoming: Vec<Flash>,
+    dirty: bool,
+    regenerate: bool,
+    destroy: bool,
+}
+
+thread_local! {
+    /// Session of the request being handled on this thread.
+    static CURRENT: RefCell<Option<State>> = const { RefCell::new(None) };
+}
+
+fn settings() -> Option<Arc<Settings>> {
+    SESSIONS.read().unwrap_or_else(|e| e.into_inner()).clone()
+}
+
+fn with_state<R>(f: impl FnOnce(&mut State) -> R) -> Option<R> {
+    CURRENT.with(|c| c.borrow_mut().as_mut().map(f))
+}
+
+/// Value stored under `key`, if present and of type `T`.
+pub fn get<T: DeserializeOwned>(key: &str) -> Option<T> {
+    with_state(|s| s.data.get(key).cloned())
+        .flatten()
+        .and_then(|v| serde_json::from_value(v).ok())
+}
+
+/// Store `value` under `key`. Fails outside [`session_layer`].
+pub fn insert<T: Serialize + ?Sized>(key: &str, value: &T) -> ChopinResult<()> {
+    let value = serde_json::to_value(value)
+        .map_err(|e| ChopinError::Other(format!("session value {key}: {e}")))?;
+    with_state(|s| {
+        s.data.insert(key.to_string(), value);
+        s.dirty = true;
+    })
+    .ok_or_else(|| ChopinError::Other("no session: SessionModule is not mounted".into()))
+}
+
+/// Remove `key`, returning its stored value.
+pub fn remove(key: &str) -> Option<serde_json::Value> {
+    with_state(|s| {
+        let old = s.data.remove(key);
+        s.dirty |= old.is_some();
+        old
+    })
+    .flatten()
+}
+
+/// Remove everything, keeping the session id.
+pub fn clear() {
+    with_state(|s| {
+        s.dirty |= !s.data.is_empty();
+        s.data.clear();
+    });
+}
+
+/// Move the session to a new id when the response is sent. Call on login
+/// and privilege changes so an id planted before login is worthless after.
+pub fn regenerate() {
+    with_state(|s| {
+        s.regenerate = true;
+        s.dirty = true;
+    });
+}
+
+/// Delete the session and expire its cookie, e.g. on logout.
+pub fn destroy() {
+    with_state(|s| {
+        s.destroy = true;
+        s.data.clear();
+    });
+}
+
+/// Queue a message for the next request.
+pub fn flash(kind: &str, message: impl Into<String>) {
+    let entry = serde_json::json!({ "kind": kind, "message": message.into() });
+    with_state(|s| {
+        if let serde_json::Value::Array(list) = s
+            .data
+            .entry(FLASH_KEY)
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+        {
+            list.push(entry);
+        }
+        s.dirty = true;
+    });
+}
+
+/// Messages queued by the previous request.
+pub fn flashes() -> Vec<Flash> {
+    with_state(|s| s.incoming.clone()).unwrap_or_default()
+}
+
+/// [`flashes`] as `<div class="flash flash-{kind}">{message}</div>`
+/// elements, escaped, for dropping into a page layout.
+pub fn flash_html() -> String {
+    flashes()
+        .iter()
+        .map(|f| {
+            format!(
+                r#"<div class="flash flash-{}">{}</div>"#,
+                escape_html(&f.kind),
+                escape_html(&f.message)
+            )
+        })
+        .collect()
+}
+
+fn load(settings: &Settings, ctx: &Context) -> State {
+    let Some(id) = ctx.cookie(&settings.cookie).filter(|id| is_token(id)) else {
+        return State::default();
+    };
+    let mut data = match settings.store.load(id) {
+        Ok(Some(data)) => data,
+        Ok(None) => return State::default(),
+        Err(e) => {
+            eprintln!("[chopin] session load failed: {e}");
+            return State::default();
+        }
+    };
+    let incoming: Vec<Flash> = data
+        .remove(FLASH_KEY)
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    State {
+        id: Some(id.to_string()),
+        data,
+        dirty: !incoming.is_empty(),
+        incoming,
+        ..State::default()
+    }
+}
+
+fn cookie(settings: &Settings, id: &str, max_age: u64) -> String {
+    let secure = if settings.secure { "; Secure" } else { "" };
+    format!(
+        "{}={id}; Path=/; Max-Age={max_age}; HttpOnly; SameSite=Lax{secure}",
+        settings.cookie
+    )
+}
+
+fn store(settings: &Settings, state: State, res: Response) -> Response {
+    if state.destroy {
+        let Some(id) = state.id else {
+            return res;
+        };
+        if let Err(e) = settings.store.delete(&id) {
+            eprintln!("[chopin] session delete failed: {e}");
+        }
+        return res.with_header("Set-Cookie", cookie(settings, "", 0));
+    }
+    if !state.dirty {
+        return res;
+    }
+    let id = match state.id {
+        Some(old) if state.regenerate => {
+            if let Err(e) = settings.store.delete(&old) {
+                eprintln!("[chopin] session delete failed: {e}");
+            }
+            new_token()
+        }
+        Some(id) => id,
+        None => new_token(),
+    };
+    if let Err(e) = settings.store.save(&id, &state.data, settings.ttl) {
+        eprintln!("[chopin] session save failed: {e}");
+        return res;
+    }
+    // Re-sent on every save so the cookie lapses together with the session.
+    res.with_header("Set-Cookie", cookie(settings, &id, settings.ttl.as_secs()))
+}
+
+/// Middleware loading the session named by the cookie before the handler
+/// and saving it afterwards. Installed by [`SessionModule`].
+pub fn session_layer(ctx: Context, next: BoxedHandler) -> Response {
+    let Some(settings) = settings() else {
+        return next(ctx);
+    };
+    let state = load(&settings, &ctx);
+    CURRENT.with(|c| *c.borrow_mut() = Some(state));
+
+    let res = next(ctx);
+
+    match CURRENT.with(|c| c.borrow_mut().take()) {
+        Some(state) => store(&settings, state, res),
+        None => res,
+    }
+}
+
+/// Turns on sessions backed by `store` and installs [`session_layer`].
+pub struct SessionModule {
+    store: Arc<dyn SessionStore>,
+    cookie: String,
+    secure: bool,
+    ttl: Duration,
+}
+
+impl SessionModule {
+    pub fn new(store: impl SessionStore + 'static) -> Self {
+        Self {
+            store: Arc::new(store),
+            cookie: "chopin_session".to_string(),
+            secure: true,
+            ttl: DEFAULT_TTL,
+        }
+    }
+
+    pub fn cookie_name(mut self, name: &str) -> Self {
+        self.cookie = name.to_string();
+        self
+    }
+
+    /// Mark the cookie `Secure` (the default). Turn off for plain-HTTP
+    /// development servers.
+    pub fn secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// How long a session lives after its last write.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+}
+
+impl ChopinModule for SessionModule {
+    fn name(&self) -> &'static str {
+        "sessions"
+    }
+
+    fn routes(&self, router: &mut Router) {
+        *SESSIONS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(Settings {
+            store: self.store.clone(),
+            cookie: self.cookie.clone(),
+            secure: self.secure,
+            ttl: self.ttl,
+        }));
+        router.layer(session_layer);
+    }
+
+    #[cfg(feature = "orm")]
+    fn migrations(&self) -> Vec<Box<dyn chopin_orm::Migration>> {
+        self.store.migrations()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::Method;
+    use crate::testing::{TestApp, TestResponse};
+
+    /// Serializes tests that install session settings.
+    static SESSION_LOCK: Mutex<()> = Mutex::new(());
+
+    fn login(_ctx: Context) -> Response {
+        regenerate();
+        insert("user_id", &42).unwrap();
+        flash("success", "Welcome <back>");
+        Response::new(303).with_header("Location", "/session-test/home")
+    }
+
+    fn home(_ctx: Context) -> Response {
+        let user: Option<i64> = get("user_id");
+        Response::text(format!("{user:?}|{}", flash_html()))
+    }
+
+    fn logout(_ctx: Context) -> Response {
+        destroy();
+        Response::new(204)
+    }
+
+    fn app(store: Arc<MemoryStore>) -> TestApp {
+        let mut router = Router::new();
+        SessionModule::new(store).secure(false).routes(&mut router);
+        router.post("/session-test/login", login);
+        router.get("/session-test/home", home);
+        router.post("/session-test/logout", logout);
+        TestApp::new(router)
+    }
+
+    fn session_id(res: &TestResponse) -> Option<String> {
+        let cookie = res.header("Set-Cookie")?;
+        let id = cookie.strip_prefix("chopin_session=")?.split(';').next()?;
+        Some(id.to_string())
+    }
+
+    fn send(app: &TestApp, method: Method, path: &str, id: &str) -> TestResponse {
+        let cookie = format!("chopin_session={id}");
+        app.request(method, path, &[("Cookie", &cookie)], b"")
+    }
+
+    #[test]
+    fn test_session_round_trip_and_flash() {
+        let _lock = SESSION_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let store = Arc::new(MemoryStore::new());
+        let app = app(store.clone());
+
+        // Reading an absent session stores nothing.
+        let anon = app.get("/session-test/home");
+        assert_eq!(anon.text(), "None|");
+        assert!(anon.header("Set-Cookie").is_none());
+        assert!(store.is_empty());
+
+        let res = app.request(Method::Post, "/session-test/login", &[], b"");
+        assert_eq!(res.status, 303);
+        assert!(
+            res.header("Set-Cookie")
+                .unwrap()
+                .ends_with("; Path=/; Max-Age=1209600; HttpOnly; SameSite=Lax")
+        );
+        let id = session_id(&res).unwrap();
+
+        let first = send(&app, Method::Get, "/session-test/home", &id);
+        assert_eq!(
+            first.text(),
+            r#"Some(42)|<div class="flash flash-success">Welcome &lt;back&gt;</div>"#
+        );
+        // Shown once, then gone.
+        let second = send(&app, Method::Get, "/session-test/home", &id);
+        assert_eq!(second.text(), "Some(42)|");
+        assert!(second.header("Set-Cookie").is_none());
+
+        // Logging in again moves the session to a fresh id.
+        let again = send(&app, Method::Post, "/session-test/login", &id);
+        let new_id = session_id(&again).unwrap();
+        assert_ne!(new_id, id);
+        assert!(store.load(&id).unwrap().is_none());
+
+        let out = send(&app, Method::Post, "/session-test/logout", &new_id);
+        assert!(
+            out.header("Set-Cookie")
+                .unwrap()
+                .starts_with("chopin_session=; Path=/; Max-Age=0")
+        );
+        assert!(store.is_empty());
+        let gone = send(&app, Method::Get, "/session-test/home", &new_id);
+        assert_eq!(gone.text(), "None|");
+
+        *SESSIONS.write().unwrap() = None;
+    }
+
+    #[test]
+    fn test_memory_store_expiry_and_helpers_outside_requests() {
+        let store = MemoryStore::new();
+        let mut data = SessionData::new();
+        data.insert("a".into(), 1.into());
+        store.save("live", &data, Duration::from_secs(60)).unwrap();
+        store.save("dead", &data, Duration::ZERO).unwrap();
+        assert_eq!(store.load("live").unwrap(), Some(data));
+        assert_eq!(store.load("dead").unwrap(), None);
+        assert_eq!(store.len(), 1);
+
+        assert!(insert("a", &1).is_err());
+        assert_eq!(get::<i64>("a"), None);
+        assert!(flashes().is_empty());
+    }
+}
@@ -88,6 +88,11 @@ impl ConnectionSlab {
             return; // Double free prevention
         }
 
+        // A long-poll left waiting has no one to answer; dropping a feed
+        // tells its sender to stop.
+        conn.parked = None;
+        conn.body_feed = None;
+
         // Point this free entry at the old head
         conn.fd = self.head_free;
         conn.state = ConnState::Free;
@@ -802,6 +802,36 @@ pub fn write_nonblocking(fd: c_int, buf: &[u8]) -> ChopinResult<usize> {
     }
 }
 
+/// Current accept-queue length and its capacity for a listening TCP socket.
+///
+/// For listeners, Linux reports the queue length in `tcpi_unacked` and the
+/// backlog limit in `tcpi_sacked`.
+#[cfg(target_os = "linux")]
+pub fn accept_queue_depth(listen_fd: c_int) -> Option<(u32, u32)> {
+    unsafe {
+        let mut info: libc::tcp_info = mem::zeroed();
+        let mut len = mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+        let rc = libc::getsockopt(
+            listen_fd,
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut info as *mut _ as *mut libc::c_void,
+            &mut len,
+        );
+        if rc < 0 {
+            None
+        } else {
+            Some((info.tcpi_unacked, info.tcpi_sacked))
+        }
+    }
+}
+
+/// Accept-queue introspection is not available on this platform.
+#[cfg(not(target_os = "linux"))]
+pub fn accept_queue_depth(_listen_fd: c_int) -> Option<(u32, u32)> {
+    None
+}
+
 // ---- File Operations for Zero-Copy Serving ----
 
 /// Open a file in read-only mode, returning its file descriptor.
@@ -830,6 +860,21 @@ pub fn file_size(fd: c_int) -> io::Result<u64> {
     }
 }
 
+/// Get the last modification time of an open file descriptor using `fstat`.
+pub fn file_mtime(fd: c_int) -> io::Result<std::time::SystemTime> {
+    unsafe {
+        let mut stat: libc::stat = mem::zeroed();
+        if libc::fstat(fd, &mut stat) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let since_epoch = std::time::Duration::new(
+            stat.st_mtime.max(0) as u64,
+            stat.st_mtime_nsec.clamp(0, 999_999_999) as u32,
+        );
+        Ok(std::time::UNIX_EPOCH + since_epoch)
+    }
+}
+
 /// Zero-copy sendfile: transfer data directly from a file descriptor to a socket
 /// entirely within the kernel. Returns the number of bytes transferred, or 0 for
 /// `EAGAIN`/`EWOULDBLOCK` (socket buffer full — wait for EPOLLOUT).
@@ -962,6 +1007,7 @@ pub mod uring {
     pub const IORING_OP_WRITE_FIXED: u8 = 5;
     pub const IORING_OP_READ: u8 = 22;
     pub const IORING_OP_WRITE: u8 = 23;
+    pub const IORING_OP_TIMEOUT: u8 = 11;
     pub const IORING_OP_ACCEPT: u8 = 13;
     pub const IORING_OP_CLOSE: u8 = 19;
     pub const IORING_OP_SEND: u8 = 26;
@@ -998,6 +1044,8 @@ pub mod uring {
     pub const OP_TYPE_CLOSE: u8 = 4;
     pub const OP_TYPE_SENDFILE: u8 = 5;
     pub const OP_TYPE_SPLICE: u8 = 6;
+    pub const OP_TYPE_WAKE: u8 = 7;
+    pub const OP_TYPE_TIMEOUT: u8 = 8;
 
     /// Sentinel connection index for accept operations
     pub const ACCEPT_CONN_IDX: u64 = 0x00FF_FFFF;
@@ -1034,6 +1082,14 @@ pub mod uring {
         pub __pad2: [u64; 1],
     }
 
+    /// `struct __kernel_timespec`, for timeout SQEs.
+    #[repr(C)]
+    #[derive(Clone, Copy, Default)]
+    pub struct kernel_timespec {
+        pub tv_sec: i64,
+        pub tv_nsec: i64,
+    }
+
     #[repr(C)]
     #[derive(Clone, Copy)]
     pub struct io_uring_cqe {
@@ -1534,6 +1590,17 @@ pub mod uring {
         sqe.user_data = user_data;
     }
 
+    /// Prepare a relative timeout SQE. It completes with `-ETIME` once `ts`
+    /// has elapsed; `ts` must stay valid until the SQE is submitted.
+    #[inline(always)]
+    pub fn prep_timeout(sqe: &mut io_uring_sqe, ts: *const kernel_timespec, user_data: u64) {
+        sqe.opcode = IORING_OP_TIMEOUT;
+        sqe.fd = -1;
+        sqe.addr_or_splice = ts as u64;
+        sqe.len = 1;
+        sqe.user_data = user_data;
+    }
+
     /// Prepare a NOP SQE (useful for testing / wakeup).
     #[inline(always)]
     pub fn prep_nop(sqe: &mut io_uring_sqe, user_data: u64) {
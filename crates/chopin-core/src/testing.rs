@@ -0,0 +1,417 @@
+// src/testing.rs
+//! In-process test harness.
+//!
+//! [`TestApp`] dispatches requests straight through a [`Router`] — no sockets,
+//! no worker threads — and returns a fully buffered [`TestResponse`].
+//!
+//! With [`assert_conforms_to_openapi`](TestApp::assert_conforms_to_openapi)
+//! enabled, every response produced by a matched route is checked against the
+//! OpenAPI spec: the operation must be documented, the status code must be
+//! declared, and JSON bodies must satisfy the declared schema (if any). A
+//! violation panics, failing the test that triggered it.
+//!
+//! ```rust,ignore
+//! let app = TestApp::from_routes().assert_conforms_to_openapi();
+//! let res = app.get("/todos/1");
+//! assert_eq!(res.status, 200);
+//! ```
+use crate::http::{Body, Context, MAX_HEADERS, MAX_PARAMS, Method, Request, Response};
+use crate::router::{RouteDef, Router};
+use serde_json::Value;
+
+/// A buffered response returned by [`TestApp`].
+#[derive(Debug, Clone)]
+pub struct TestResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl TestResponse {
+    /// Body as UTF-8 text (lossy).
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// Body parsed as JSON.
+    pub fn json(&self) -> serde_json::Result<Value> {
+        serde_json::from_slice(&self.body)
+    }
+
+    /// First header named `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Drives a [`Router`] in-process for tests.
+pub struct TestApp {
+    router: Router,
+    contract: Option<Value>,
+}
+
+impl TestApp {
+    /// Wrap an explicitly built router.
+    pub fn new(mut router: Router) -> Self {
+        router.finalize();
+        Self {
+            router,
+            contract: None,
+        }
+    }
+
+    /// Build a router from every route registered with `#[get]`, `#[post]`, etc.
+    pub fn from_routes() -> Self {
+        let mut router = Router::new();
+        for route in inventory::iter::<RouteDef> {
+            router.add(route.method, route.path, route.handler);
+        }
+        Self::new(router)
+    }
+
+    /// Validate every response against the generated OpenAPI spec.
+    pub fn assert_conforms_to_openapi(self) -> Self {
+        self.with_contract(crate::openapi::generate_spec())
+    }
+
+    /// Validate every response against the given OpenAPI document.
+    pub fn with_contract(mut self, spec: Value) -> Self {
+        self.contract = Some(spec);
+        self
+    }
+
+    pub fn get(&self, path: &str) -> TestResponse {
+        self.request(Method::Get, path, &[], b"")
+    }
+
+    pub fn delete(&self, path: &str) -> TestResponse {
+        self.request(Method::Delete, path, &[], b"")
+    }
+
+    /// POST a JSON body.
+    pub fn post_json(&self, path: &str, body: &str) -> TestResponse {
+        self.request(
+            Method::Post,
+            path,
+            &[("Content-Type", "application/json")],
+            body.as_bytes(),
+        )
+    }
+
+    /// PUT a JSON body.
+    pub fn put_json(&self, path: &str, body: &str) -> TestResponse {
+        self.request(
+            Method::Put,
+            path,
+            &[("Content-Type", "application/json")],
+            body.as_bytes(),
+        )
+    }
+
+    /// Dispatch a request. `target` may include a query string.
+    ///
+    /// # Panics
+    /// In contract mode, panics if a matched route's response does not conform
+    /// to the spec.
+    pub fn request(
+        &self,
+        method: Method,
+        target: &str,
+        headers: &[(&str, &str)],
+        body: &[u8],
+    ) -> TestResponse {
+        let (path, query) = match target.split_once('?') {
+            Some((p, q)) => (p, Some(q)),
+            None => (target, None),
+        };
+
+        let mut req_headers = [("", ""); MAX_HEADERS];
+        let header_count = headers.len().min(MAX_HEADERS);
+        req_headers[..header_count].copy_from_slice(&headers[..header_count]);
+
+        let mut ctx = Context {
+            req: Request {
+                method,
+                path,
+                query,
+                headers: req_headers,
+                header_count: header_count as u8,
+                body,
+            },
+            params: [("", ""); MAX_PARAMS],
+            param_count: 0,
+        };
+
+        let (response, matched) = match self.router.match_route(method, path) {
+            Some((handler, params, param_count, composed)) => {
+                ctx.params = params;
+                ctx.param_count = param_count;
+                let res = match composed {
+                    Some(c) => (**c)(ctx),
+                    None => handler(ctx),
+                };
+                (res, true)
+            }
+            None => (Response::not_found(), false),
+        };
+
+        let res = buffer(response);
+        if matched
+            && let Some(spec) = &self.contract
+            && let Err(violation) = check_contract(spec, method, path, &res)
+        {
+            panic!("OpenAPI contract violation: {violation}");
+        }
+        res
+    }
+}
+
+fn buffer(mut response: Response) -> TestResponse {
+    if let Some(parked) = response.take_parked() {
+        return buffer(parked.wait());
+    }
+    let headers = response
+        .headers
+        .iter()
+        .map(|h| (h.name.to_string(), h.value.as_str().to_string()))
+        .collect();
+    let body = match response.body {
+        Body::Empty => Vec::new(),
+        Body::Static(b) | Body::Raw(b) => b.to_vec(),
+        Body::Bytes(b) => b,
+        Body::Stream(chunks) => chunks.flatten().collect(),
+        Body::Feed(feed) => feed.flatten().collect(),
+        Body::Parked(_) => unreachable!("resolved above"),
+        Body::File { fd, offset, len } => {
+            let mut buf = vec![0u8; len as usize];
+            let n = unsafe {
+                libc::pread(
+                    fd.raw(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    offset as libc::off_t,
+                )
+            };
+            buf.truncate(n.max(0) as usize);
+            buf
+        }
+    };
+    TestResponse {
+        status: response.status,
+        content_type: response.content_type,
+        headers,
+        body,
+    }
+}
+
+/// Check a response against an OpenAPI document.
+pub fn check_contract(
+    spec: &Value,
+    method: Method,
+    path: &str,
+    res: &TestResponse,
+) -> Result<(), String> {
+    let method_key = crate::openapi::method_name(method);
+    let label = format!("{} {}", method_key.to_uppercase(), path);
+
+    let operation = spec["paths"]
+        .as_object()
+        .and_then(|paths| {
+            paths
+                .iter()
+                .find(|(template, _)| template_matches(template, path))
+        })
+        .and_then(|(_, item)| item.get(method_key))
+        .ok_or_else(|| format!("{label} is not documented"))?;
+
+    let responses = &operation["responses"];
+    let declared = responses
+        .get(res.status.to_string())
+        .or_else(|| responses.get("default"))
+        .ok_or_else(|| format!("{label} returned undocumented status {}", res.status))?;
+
+    if let Some(schema) = declared
+        .pointer("/content/application~1json/schema")
+        .filter(|s| !s.is_null())
+    {
+        let body: Value = serde_json::from_slice(&res.body)
+            .map_err(|e| format!("{label} returned a non-JSON body: {e}"))?;
+        validate_schema(schema, &body, "$").map_err(|e| format!("{label}: {e}"))?;
+    }
+    Ok(())
+}
+
+/// Matches an OpenAPI path template (`/users/{id}`) against a concrete path.
+fn template_matches(template: &str, path: &str) -> bool {
+    let mut tpl = template.split('/').filter(|s| !s.is_empty());
+    let mut segs = path.split('/').filter(|s| !s.is_empty());
+    loop {
+        match (tpl.next(), segs.next()) {
+            (None, None) => return true,
+            (Some(t), Some(s)) if (t.starts_with('{') && t.ends_with('}')) || t == s => {}
+            _ => return false,
+        }
+    }
+}
+
+/// Validates `value` against a JSON Schema subset: `type`, `nullable`,
+/// `properties`, `required`, `items` and `enum`.
+pub fn validate_schema(schema: &Value, value: &Value, at: &str) -> Result<(), String> {
+    if value.is_null() && schema["nullable"].as_bool() == Some(true) {
+        return Ok(());
+    }
+
+    if let Some(ty) = schema["type"].as_str() {
+        let ok = match ty {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_i64() || value.is_u64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => true,
+        };
+        if !ok {
+            return Err(format!("{at}: expected {ty}, got {value}"));
+        }
+    }
+
+    if let Some(allowed) = schema["enum"].as_array()
+        && !allowed.contains(value)
+    {
+        return Err(format!("{at}: {value} is not one of {allowed:?}"));
+    }
+
+    if let Some(obj) = value.as_object() {
+        if let Some(required) = schema["required"].as_array() {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !obj.contains_key(key) {
+                    return Err(format!("{at}: missing required property `{key}`"));
+                }
+            }
+        }
+        if let Some(props) = schema["properties"].as_object() {
+            for (key, prop_schema) in props {
+                if let Some(v) = obj.get(key) {
+                    validate_schema(prop_schema, v, &format!("{at}.{key}"))?;
+                }
+            }
+        }
+    }
+
+    if let (Some(items), Some(arr)) = (schema.get("items"), value.as_array()) {
+        for (i, v) in arr.iter().enumerate() {
+            validate_schema(items, v, &format!("{at}[{i}]"))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn hello(_ctx: Context) -> Response {
+        Response::json_bytes(br#"{"id":1,"name":"a"}"#.to_vec())
+    }
+
+    fn created(_ctx: Context) -> Response {
+        let mut res = Response::text("made");
+        res.status = 201;
+        res
+    }
+
+    fn echo_param(ctx: Context) -> Response {
+        Response::text(ctx.param("id").unwrap_or("").to_string())
+    }
+
+    fn spec() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "paths": {
+                "/items/{id}": {
+                    "get": {
+                        "responses": {
+                            "200": {
+                                "description": "OK",
+                                "content": { "application/json": { "schema": {
+                                    "type": "object",
+                                    "required": ["id", "name"],
+                                    "properties": {
+                                        "id": { "type": "integer" },
+                                        "name": { "type": "string" }
+                                    }
+                                }}}
+                            }
+                        }
+                    }
+                },
+                "/items": { "post": { "responses": { "200": { "description": "OK" } } } }
+            }
+        })
+    }
+
+    fn app() -> TestApp {
+        let mut router = Router::new();
+        router.get("/items/:id", hello);
+        router.post("/items", created);
+        router.get("/echo/:id", echo_param);
+        TestApp::new(router)
+    }
+
+    #[test]
+    fn test_dispatch_with_params_and_query() {
+        let res = app().get("/echo/42?x=1");
+        assert_eq!(res.status, 200);
+        assert_eq!(res.text(), "42");
+        assert_eq!(app().get("/missing").status, 404);
+    }
+
+    #[test]
+    fn test_contract_passes() {
+        let app = app().with_contract(spec());
+        let res = app.get("/items/7");
+        assert_eq!(res.json().unwrap()["name"], "a");
+        // Unmatched routes are the router's 404, not a handler drifting.
+        assert_eq!(app.get("/nope").status, 404);
+    }
+
+    #[test]
+    #[should_panic(expected = "undocumented status 201")]
+    fn test_contract_undocumented_status() {
+        app().with_contract(spec()).post_json("/items", "{}");
+    }
+
+    #[test]
+    #[should_panic(expected = "is not documented")]
+    fn test_contract_undocumented_route() {
+        app().with_contract(spec()).get("/echo/1");
+    }
+
+    #[test]
+    fn test_validate_schema() {
+        let schema = json!({
+            "type": "array",
+            "items": { "type": "object", "required": ["id"],
+                       "properties": { "id": { "type": "integer" },
+                                       "tag": { "type": "string", "nullable": true } } }
+        });
+        assert!(validate_schema(&schema, &json!([{"id": 1, "tag": null}]), "$").is_ok());
+        let err = validate_schema(&schema, &json!([{"id": "x"}]), "$").unwrap_err();
+        assert!(err.contains("$[0].id"), "{err}");
+        assert!(validate_schema(&schema, &json!([{}]), "$").is_err());
+    }
+
+    #[test]
+    fn test_template_matches() {
+        assert!(template_matches("/users/{id}", "/users/9"));
+        assert!(template_matches("/", "/"));
+        assert!(!template_matches("/users/{id}", "/users"));
+    }
+}
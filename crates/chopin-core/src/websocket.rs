@@ -297,9 +297,11 @@ pub fn parse_close_payload(payload: &[u8]) -> Option<(u16, String)> {
 }
 
 // ── Minimal SHA-1 (RFC 3174) ────────────────────────────────────────────────
-// Only used for WebSocket accept key derivation. Not for security purposes.
 
-fn sha1(data: &[u8]) -> [u8; 20] {
+/// SHA-1 digest, for protocols that dictate it: the WebSocket accept key
+/// here, Pwned Passwords range keys in `chopin-auth`. Not for security
+/// purposes.
+pub fn sha1(data: &[u8]) -> [u8; 20] {
     let mut h0: u32 = 0x67452301;
     let mut h1: u32 = 0xEFCDAB89;
     let mut h2: u32 = 0x98BADCFE;
@@ -374,7 +376,7 @@ fn sha1(data: &[u8]) -> [u8; 20] {
 
 const B64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
 
-fn base64_encode(data: &[u8]) -> String {
+pub(crate) fn base64_encode(data: &[u8]) -> String {
     let mut result = String::with_capacity(data.len().div_ceil(3) * 4);
     for chunk in data.chunks(3) {
         let b0 = chunk[0] as u32;
@@ -0,0 +1,46 @@
+use chopin_core::assets::{self, Assets, AssetsModule};
+use chopin_core::testing::TestApp;
+use chopin_core::{ChopinModule, Method, Router, embed_assets};
+
+static PUBLIC: Assets = embed_assets!("tests/fixtures/public");
+
+#[test]
+fn test_embed_assets_lists_files_with_hashed_names() {
+    let paths: Vec<_> = PUBLIC.files().iter().map(|a| a.path).collect();
+    assert_eq!(paths, ["css/app.css", "index.html"]);
+
+    let css = PUBLIC.get("css/app.css").unwrap();
+    assert_eq!(css.body, include_bytes!("fixtures/public/css/app.css"));
+    let hash = css
+        .hashed
+        .strip_prefix("css/app.")
+        .and_then(|rest| rest.strip_suffix(".css"))
+        .unwrap();
+    assert_eq!(hash.len(), 8);
+    assert!(css.etag.starts_with('"') && css.etag.contains(hash));
+    // Too small to shrink.
+    assert!(css.gzip.is_none());
+
+    let html = PUBLIC.get("index.html").unwrap();
+    let gzip = html.gzip.unwrap();
+    assert_eq!(&gzip[..2], &[0x1f, 0x8b]);
+    assert!(gzip.len() < html.body.len());
+}
+
+#[test]
+fn test_embedded_assets_are_served() {
+    let mut router = Router::new();
+    AssetsModule::new("/assets", &PUBLIC).routes(&mut router);
+    let app = TestApp::new(router);
+
+    let url = assets::url("index.html").unwrap();
+    assert!(url.starts_with("/assets/index.") && url.ends_with(".html"));
+    let res = app.request(Method::Get, &url, &[("Accept-Encoding", "gzip")], b"");
+    assert_eq!(res.status, 200);
+    assert_eq!(res.content_type, "text/html; charset=utf-8");
+    assert_eq!(res.header("Content-Encoding"), Some("gzip"));
+
+    let res = app.get("/assets/css/app.css");
+    assert_eq!(res.text(), "body{margin:0}\n");
+    assert_eq!(app.get("/assets/.gitkeep").status, 404);
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "orm")]
+
+use chopin_core::{ChopinResult, cached, memo};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static SQUARE_CALLS: AtomicU32 = AtomicU32::new(0);
+static LOOKUP_CALLS: AtomicU32 = AtomicU32::new(0);
+
+#[cached(ttl = "30s")]
+fn square(n: u64) -> u64 {
+    SQUARE_CALLS.fetch_add(1, Ordering::SeqCst);
+    n * n
+}
+
+#[cached(ttl = "1m", key = format!("test-user:{id}"))]
+fn lookup(id: i64) -> ChopinResult<Vec<String>> {
+    LOOKUP_CALLS.fetch_add(1, Ordering::SeqCst);
+    if id < 0 {
+        return Err(chopin_core::ChopinError::Other("negative id".into()));
+    }
+    Ok(vec![format!("user {id}")])
+}
+
+#[test]
+fn test_cached_by_arguments() {
+    assert_eq!(square(3), 9);
+    assert_eq!(square(3), 9);
+    assert_eq!(square(4), 16);
+    assert_eq!(SQUARE_CALLS.load(Ordering::SeqCst), 2);
+
+    memo::invalidate_prefix(&memo::fn_key_prefix(concat!(module_path!(), "::square")));
+    assert_eq!(square(3), 9);
+    assert_eq!(SQUARE_CALLS.load(Ordering::SeqCst), 3);
+}
+
+#[test]
+fn test_cached_result_with_key_override() {
+    assert_eq!(lookup(1).unwrap(), ["user 1"]);
+    assert_eq!(lookup(1).unwrap(), ["user 1"]);
+    assert_eq!(LOOKUP_CALLS.load(Ordering::SeqCst), 1);
+
+    // Errors are not cached.
+    assert!(lookup(-1).is_err());
+    assert!(lookup(-1).is_err());
+    assert_eq!(LOOKUP_CALLS.load(Ordering::SeqCst), 3);
+
+    memo::invalidate_prefix("test-user:");
+    lookup(1).unwrap();
+    assert_eq!(LOOKUP_CALLS.load(Ordering::SeqCst), 4);
+}
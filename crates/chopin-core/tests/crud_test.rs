@@ -0,0 +1,294 @@
+#![cfg(feature = "orm")]
+
+use chopin_core::crud::{Action, CreateRequest, UpdateRequest};
+use chopin_core::testing::TestApp;
+use chopin_core::{Method, Response, Router, crud_routes, db};
+use chopin_orm::{Executor, Model, OrmResult, PgError, PgValue, Row, ToSql, Validate, mock_row};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, Once};
+
+#[derive(Model, Debug, Clone, Serialize)]
+#[model(table_name = "crud_posts", connection = "crud_test")]
+struct Post {
+    id: i64,
+    title: String,
+    published: bool,
+}
+
+impl Validate for Post {
+    fn validate(&self) -> Result<(), Vec<String>> {
+        if self.published && self.title.len() < 3 {
+            return Err(vec!["published titles need 3+ characters".into()]);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Deserialize)]
+struct CreatePost {
+    title: String,
+}
+
+impl Validate for CreatePost {
+    fn validate(&self) -> Result<(), Vec<String>> {
+        if self.title.is_empty() {
+            return Err(vec!["title is required".into()]);
+        }
+        Ok(())
+    }
+}
+
+impl CreateRequest<Post> for CreatePost {
+    fn into_model(self) -> Post {
+        Post {
+            id: 0,
+            title: self.title,
+            published: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct UpdatePost {
+    title: Option<String>,
+    published: Option<bool>,
+}
+
+impl Validate for UpdatePost {}
+
+impl UpdateRequest<Post> for UpdatePost {
+    fn apply(self, post: &mut Post) {
+        if let Some(title) = self.title {
+            post.title = title;
+        }
+        if let Some(published) = self.published {
+            post.published = published;
+        }
+    }
+}
+
+crud_routes!(
+    Post,
+    base = "/crud/posts",
+    create = CreatePost,
+    update = UpdatePost,
+    tag = "crud-test",
+);
+
+crud_routes!(
+    Post,
+    base = "/crud/admin/posts",
+    create = CreatePost,
+    update = UpdatePost,
+    authorize = |ctx, action| match (ctx.header("X-Role"), action) {
+        (Some("admin"), _) | (Some("viewer"), Action::List | Action::Get) => Ok(()),
+        (Some(_), _) => Err(Response::forbidden()),
+        (None, _) => Err(Response::unauthorized()),
+    },
+    tag = "crud-test",
+);
+
+static POSTS: Mutex<Vec<Post>> = Mutex::new(Vec::new());
+
+/// Answers the handful of statements the CRUD handlers issue against
+/// `crud_posts`, backed by `POSTS`.
+struct Store;
+
+fn text(param: &dyn ToSql) -> String {
+    match param.to_sql() {
+        PgValue::Text(s) => s,
+        other => panic!("unexpected parameter {other:?}"),
+    }
+}
+
+fn row(p: &Post) -> Row {
+    mock_row!("id" => p.id, "title" => p.title.clone(), "published" => p.published)
+}
+
+fn clause(sql: &str, keyword: &str) -> usize {
+    sql.split(keyword)
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .map_or(0, |n| n.parse().unwrap())
+}
+
+impl Executor for Store {
+    fn execute(&mut self, sql: &str, params: &[&dyn ToSql]) -> OrmResult<u64> {
+        let mut posts = POSTS.lock().unwrap();
+        if sql.starts_with("DELETE FROM crud_posts WHERE id = $1") {
+            let id: i64 = text(params[0]).parse().unwrap();
+            let before = posts.len();
+            posts.retain(|p| p.id != id);
+            return Ok((before - posts.len()) as u64);
+        }
+        assert!(sql.starts_with("UPDATE crud_posts SET"), "{sql}");
+        let (PgValue::Int8(id), PgValue::Text(title), PgValue::Bool(published)) =
+            (params[2].to_sql(), params[0].to_sql(), params[1].to_sql())
+        else {
+            panic!("unexpected update parameters for {sql}");
+        };
+        let post = posts.iter_mut().find(|p| p.id == id).unwrap();
+        post.title = title;
+        post.published = published;
+        Ok(1)
+    }
+
+    fn query(&mut self, sql: &str, params: &[&dyn ToSql]) -> OrmResult<Vec<Row>> {
+        let mut posts = POSTS.lock().unwrap();
+        if sql.starts_with("INSERT INTO crud_posts") {
+            let (PgValue::Text(title), PgValue::Bool(published)) =
+                (params[0].to_sql(), params[1].to_sql())
+            else {
+                panic!("unexpected insert parameters for {sql}");
+            };
+            // Titles starting with "unique" behave as if under a unique index.
+            if title.starts_with("unique") && posts.iter().any(|p| p.title == title) {
+                return Err(PgError::from_fields(&[
+                    (b'S', "ERROR".to_string()),
+                    (b'C', "23505".to_string()),
+                    (b'M', "duplicate key value".to_string()),
+                    (b'n', "crud_posts_title_key".to_string()),
+                ])
+                .into());
+            }
+            let id = posts.iter().map(|p| p.id).max().unwrap_or(0) + 1;
+            posts.push(Post {
+                id,
+                title,
+                published,
+            });
+            return Ok(vec![mock_row!("id" => id)]);
+        }
+        if sql.starts_with("SELECT COUNT(*)") {
+            return Ok(vec![mock_row!("count" => posts.len() as i64)]);
+        }
+        if sql.contains("WHERE id = $1") {
+            let id: i64 = text(params[0]).parse().unwrap();
+            return Ok(posts.iter().filter(|p| p.id == id).map(row).collect());
+        }
+        assert!(sql.contains("ORDER BY id"), "{sql}");
+        let (limit, offset) = (clause(sql, " LIMIT "), clause(sql, " OFFSET "));
+        Ok(posts.iter().skip(offset).take(limit).map(row).collect())
+    }
+}
+
+fn app() -> TestApp {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        db::add_database("crud_test", || Ok(Box::new(Store) as Box<dyn Executor>)).unwrap();
+    });
+    let mut router = Router::new();
+    router.mount_tagged("crud-test");
+    TestApp::new(router)
+}
+
+fn send(
+    app: &TestApp,
+    method: Method,
+    path: &str,
+    role: Option<&str>,
+    body: &str,
+) -> (u16, String) {
+    let headers: Vec<(&str, &str)> = role.iter().map(|r| ("X-Role", *r)).collect();
+    let res = app.request(method, path, &headers, body.as_bytes());
+    (res.status, res.text())
+}
+
+#[test]
+fn test_crud_lifecycle() {
+    let app = app();
+    let base = "/crud/posts";
+
+    let (status, body) = send(&app, Method::Post, base, None, r#"{"title":"hello"}"#);
+    assert_eq!(status, 201);
+    let created: serde_json::Value = serde_json::from_str(&body).unwrap();
+    let id = created["id"].as_i64().unwrap();
+    assert_eq!(created["title"], "hello");
+    let path = format!("{base}/{id}");
+
+    let (status, body) = send(&app, Method::Get, &path, None, "");
+    assert_eq!((status, body.contains("\"hello\"")), (200, true));
+
+    let (status, body) = send(&app, Method::Put, &path, None, r#"{"published":true}"#);
+    assert_eq!(status, 200);
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&body).unwrap()["published"],
+        true
+    );
+
+    // Model-level validation runs on the updated row.
+    let (status, body) = send(&app, Method::Put, &path, None, r#"{"title":"x"}"#);
+    assert_eq!(status, 422);
+    assert!(body.contains("3+ characters"));
+
+    assert_eq!(send(&app, Method::Delete, &path, None, "").0, 204);
+    assert_eq!(send(&app, Method::Get, &path, None, "").0, 404);
+    assert_eq!(send(&app, Method::Delete, &path, None, "").0, 404);
+    assert_eq!(send(&app, Method::Put, &path, None, "{}").0, 404);
+}
+
+#[test]
+fn test_create_validation() {
+    let app = app();
+    let (status, body) = send(&app, Method::Post, "/crud/posts", None, r#"{"title":""}"#);
+    assert_eq!(status, 422);
+    assert_eq!(body, r#"{"errors":["title is required"]}"#);
+    assert_eq!(send(&app, Method::Post, "/crud/posts", None, "nope").0, 400);
+}
+
+#[test]
+fn test_create_conflict() {
+    let app = app();
+    let body = r#"{"title":"unique headline"}"#;
+    assert_eq!(send(&app, Method::Post, "/crud/posts", None, body).0, 201);
+    let (status, body) = send(&app, Method::Post, "/crud/posts", None, body);
+    assert_eq!(status, 409);
+    assert_eq!(
+        body,
+        r#"{"constraint":"crud_posts_title_key","error":"conflict"}"#
+    );
+}
+
+#[test]
+fn test_list_pagination() {
+    let app = app();
+    for i in 0..5 {
+        let body = format!(r#"{{"title":"page {i}"}}"#);
+        assert_eq!(send(&app, Method::Post, "/crud/posts", None, &body).0, 201);
+    }
+    let (status, body) = send(&app, Method::Get, "/crud/posts?page=2&per_page=2", None, "");
+    assert_eq!(status, 200);
+    let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["page"], 2);
+    assert_eq!(page["per_page"], 2);
+    assert_eq!(page["items"].as_array().unwrap().len(), 2);
+    assert!(page["total"].as_i64().unwrap() >= 5);
+
+    let (_, body) = send(&app, Method::Get, "/crud/posts?per_page=1000", None, "");
+    let page: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(page["per_page"], 100);
+}
+
+#[test]
+fn test_authorize_per_action() {
+    let app = app();
+    let base = "/crud/admin/posts";
+    assert_eq!(send(&app, Method::Get, base, None, "").0, 401);
+    assert_eq!(send(&app, Method::Get, base, Some("viewer"), "").0, 200);
+    let body = r#"{"title":"admin post"}"#;
+    assert_eq!(send(&app, Method::Post, base, Some("viewer"), body).0, 403);
+    assert_eq!(send(&app, Method::Post, base, Some("admin"), body).0, 201);
+}
+
+#[test]
+fn test_routes_are_documented() {
+    let spec = chopin_core::openapi::generate_spec();
+    let item = &spec["paths"]["/crud/posts/{id}"];
+    assert_eq!(item["get"]["summary"], "Get Post");
+    assert_eq!(item["delete"]["tags"][0], "crud-test");
+    assert_eq!(item["put"]["parameters"][0]["name"], "id");
+    assert_eq!(
+        spec["paths"]["/crud/posts"]["post"]["summary"],
+        "Create Post"
+    );
+}
@@ -15,6 +15,7 @@
 //!   - HTTP/1.1 pipelining (send N requests, then read N responses)
 //!   - Chunked request body (Transfer-Encoding: chunked)
 //!   - Chunked response (streaming)
+//!   - Streamed response larger than the write buffer, pipelined
 //!   - Content-Length accuracy
 //!   - Server: header
 //!   - JSON content-type
@@ -24,15 +25,18 @@
 //!   - 404 for unknown path
 //!   - 404 when method has no registered handler
 //!   - 20 concurrent connections racing the same endpoint
+//!   - Long-polls parked without blocking the worker
+//!   - Fed bodies parked between chunks without blocking the worker
 //!   - Wildcard route matching
 
 use chopin_core::headers::Headers;
+use chopin_core::longpoll::{self, Event, FeedSender};
 use chopin_core::{Context, Json, Method, Response, Router, Server};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::io::{Read, Write};
 use std::net::TcpStream;
-use std::sync::Once;
+use std::sync::{Mutex, Once};
 use std::thread;
 use std::time::Duration;
 
@@ -41,6 +45,15 @@ use std::time::Duration;
 const ADDR: &str = "127.0.0.1:8090";
 static SERVER: Once = Once::new();
 
+/// Notified through `POST /notify`; waited on by `GET /poll/wake/:since`.
+static WAKE: Event = Event::new();
+/// Never notified; waited on by `GET /poll/idle/:since`.
+static IDLE: Event = Event::new();
+/// Notified once up front; waited on by `GET /poll/behind/:since`.
+static BEHIND: Event = Event::new();
+/// Sender of the body `GET /feed` is waiting on.
+static FEED: Mutex<Option<FeedSender>> = Mutex::new(None);
+
 #[derive(Deserialize)]
 struct NameMsg {
     name: String,
@@ -122,6 +135,15 @@ fn ensure_server() {
             Response::stream(chunks.into_iter())
         });
 
+        // GET /stream-large → ~300 KB of chunks, one larger than the write buffer
+        router.add(Method::Get, "/stream-large", |_: Context| {
+            let small = (0..200u32).map(|i| vec![b'a' + (i % 26) as u8; 1000]);
+            let chunks = small
+                .chain(std::iter::once(Vec::new()))
+                .chain(std::iter::once(vec![b'Z'; 100_000]));
+            Response::stream(chunks)
+        });
+
         // POST /upload → receive body (including chunked), return byte count
         router.add(Method::Post, "/upload", |ctx: Context| {
             Response::text(ctx.req.body.len().to_string())
@@ -159,6 +181,36 @@ fn ensure_server() {
             Response::text_static(b"ok")
         });
 
+        // GET /poll/{wake,idle,behind}/:since → long-poll, 200 + version or 204
+        fn poll(event: &'static Event, ctx: &Context, timeout: Duration) -> Response {
+            let since = ctx.param("since").and_then(|s| s.parse().ok()).unwrap_or(0);
+            longpoll::respond(event, since, timeout, |version| match version {
+                Some(v) => Response::text(v.to_string()),
+                None => Response::new(204),
+            })
+        }
+        router.add(Method::Get, "/poll/wake/:since", |ctx: Context| {
+            poll(&WAKE, &ctx, Duration::from_secs(5))
+        });
+        router.add(Method::Get, "/poll/idle/:since", |ctx: Context| {
+            poll(&IDLE, &ctx, Duration::from_millis(200))
+        });
+        router.add(Method::Get, "/poll/behind/:since", |ctx: Context| {
+            poll(&BEHIND, &ctx, Duration::from_secs(5))
+        });
+
+        // POST /notify → bump WAKE
+        router.add(Method::Post, "/notify", |_: Context| {
+            Response::text(WAKE.notify().to_string())
+        });
+
+        // GET /feed → chunked body sent through FEED by the test
+        router.add(Method::Get, "/feed", |_: Context| {
+            let (tx, rx) = longpoll::feed(1);
+            *FEED.lock().unwrap() = Some(tx);
+            rx.respond(|feed| feed.map_or_else(Response::server_error, Response::feed))
+        });
+
         thread::spawn(move || {
             Server::bind(ADDR).workers(1).serve(router).unwrap();
         });
@@ -514,6 +566,25 @@ fn test_chunked_response_streaming() {
     assert_eq!(r.body_str(), "hello world");
 }
 
+#[test]
+fn test_chunked_response_larger_than_write_buffer() {
+    ensure_server();
+    let mut c = Conn::open();
+    c.send(b"GET /stream-large HTTP/1.1\r\nHost: localhost\r\nConnection: keep-alive\r\n\r\n");
+    c.send(b"GET /ping HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+
+    let r = c.recv();
+    assert_eq!(r.status, 200);
+    assert_eq!(r.body.len(), 300_000);
+    assert!(r.body[..1000].iter().all(|&b| b == b'a'));
+    assert!(r.body[199_000..200_000].iter().all(|&b| b == b'r'));
+    assert!(r.body[200_000..].iter().all(|&b| b == b'Z'));
+
+    // The pipelined request is answered after the stream ends.
+    let r = c.recv();
+    assert_eq!((r.status, r.body_str()), (200, "pong"));
+}
+
 #[test]
 fn test_chunked_request_body_decoded() {
     ensure_server();
@@ -799,3 +870,90 @@ fn test_multiple_response_headers_coexist() {
     );
     assert_eq!(r.header("x-custom"), Some("chopin-e2e"));
 }
+
+// ── long-polling ────────────────────────────────────────────────────────────
+
+#[test]
+fn test_long_poll_parks_until_notified() {
+    ensure_server();
+    let since = WAKE.version();
+    let mut c = Conn::open();
+    // A request pipelined behind the poll waits for it.
+    c.send(
+        format!(
+            "GET /poll/wake/{since} HTTP/1.1\r\nHost: localhost\r\n\r\n\
+             GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n"
+        )
+        .as_bytes(),
+    );
+    thread::sleep(Duration::from_millis(100));
+
+    // The single worker keeps serving while the poll is parked.
+    let start = std::time::Instant::now();
+    let r = once(b"POST /notify HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+    assert_eq!(r.status, 200);
+    assert!(start.elapsed() < Duration::from_secs(1));
+
+    let r = c.recv();
+    assert_eq!(r.status, 200);
+    assert_eq!(r.body_str(), (since + 1).to_string());
+    let r = c.recv();
+    assert_eq!(r.body_str(), "pong");
+}
+
+#[test]
+fn test_long_poll_answers_behind_client_at_once() {
+    ensure_server();
+    BEHIND.notify();
+    let r = once(b"GET /poll/behind/0 HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+    assert_eq!(r.status, 200);
+    assert!(r.body_str().parse::<u64>().unwrap() >= 1);
+}
+
+#[test]
+fn test_feed_parks_between_chunks() {
+    ensure_server();
+    let mut c = Conn::open();
+    c.send(
+        b"GET /feed HTTP/1.1\r\nHost: localhost\r\n\r\n\
+          GET /ping HTTP/1.1\r\nHost: localhost\r\n\r\n",
+    );
+    let tx = loop {
+        if let Some(tx) = FEED.lock().unwrap().take() {
+            break tx;
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // The single worker keeps serving before the first chunk and between
+    // chunks.
+    let start = std::time::Instant::now();
+    assert_eq!(
+        once(b"GET /ping HTTP/1.1\r\nConnection: close\r\n\r\n").status,
+        200
+    );
+    tx.send(b"hello ".to_vec()).unwrap();
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(
+        once(b"GET /ping HTTP/1.1\r\nConnection: close\r\n\r\n").status,
+        200
+    );
+    assert!(start.elapsed() < Duration::from_secs(1));
+    tx.send(b"world".to_vec()).unwrap();
+    drop(tx);
+
+    let r = c.recv();
+    assert_eq!(r.header("transfer-encoding"), Some("chunked"));
+    assert_eq!(r.body_str(), "hello world");
+    let r = c.recv();
+    assert_eq!(r.body_str(), "pong");
+}
+
+#[test]
+fn test_long_poll_times_out() {
+    ensure_server();
+    let start = std::time::Instant::now();
+    let r = once(b"GET /poll/idle/0 HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n");
+    assert_eq!(r.status, 204);
+    assert!(start.elapsed() >= Duration::from_millis(200));
+}
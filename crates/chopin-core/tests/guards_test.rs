@@ -0,0 +1,155 @@
+use chopin_core::testing::TestApp;
+use chopin_core::{
+    Context, Form, FromRequest, Json, Method, Response, Router, authorize, get, post, validate,
+};
+use serde::Deserialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static USER_EXTRACTIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// `X-User: name:role,role`
+struct User {
+    name: String,
+    roles: Vec<String>,
+}
+
+impl User {
+    fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+impl<'a> FromRequest<'a> for User {
+    type Error = Response;
+
+    fn from_request(ctx: &'a Context<'a>) -> Result<Self, Self::Error> {
+        USER_EXTRACTIONS.fetch_add(1, Ordering::SeqCst);
+        let (name, roles) = ctx
+            .header("X-User")
+            .and_then(|v| v.split_once(':'))
+            .ok_or_else(Response::unauthorized)?;
+        Ok(User {
+            name: name.to_string(),
+            roles: roles.split(',').map(str::to_string).collect(),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct NewPost {
+    title: String,
+}
+
+fn is_editor(user: &User) -> bool {
+    user.has_role("editor")
+}
+
+// Guards below the route attribute, with injection and a typed path param.
+#[post("/guards/blogs/:blog/posts")]
+#[authorize(User)]
+#[authorize(User, is_editor)]
+#[validate(Json<NewPost>, |Json(p)| !p.title.is_empty())]
+fn create_post(_ctx: Context, blog: u32, user: User, body: Json<NewPost>) -> Response {
+    Response::text(format!("{} posted {} in {blog}", user.name, body.0.title))
+}
+
+// Guards above the route attribute, validate written before authorize.
+#[validate(Json<NewPost>)]
+#[authorize(User, |u| u.has_role("admin"))]
+#[post("/guards/admin/posts")]
+fn admin_post(_: Context, Json(post): Json<NewPost>) -> Response {
+    Response::text(post.title)
+}
+
+#[post("/guards/comments")]
+#[validate(Form<NewPost>, |Form(p)| p.title.len() <= 10)]
+fn comment_form(_: Context, form: Form<NewPost>) -> Response {
+    Response::text(form.0.title)
+}
+
+#[get("/guards/me")]
+#[authorize(User)]
+fn me(ctx: Context) -> Response {
+    Response::text(ctx.header("X-User").unwrap_or_default().to_string())
+}
+
+fn app() -> TestApp {
+    let mut router = Router::new();
+    router.post("/guards/blogs/:blog/posts", create_post);
+    router.post("/guards/admin/posts", admin_post);
+    router.get("/guards/me", me);
+    router.post("/guards/comments", comment_form);
+    TestApp::new(router)
+}
+
+fn post_as(app: &TestApp, path: &str, user: Option<&str>, body: &str) -> u16 {
+    let headers: &[(&str, &str)] = match user {
+        Some(user) => &[("X-User", user)],
+        None => &[],
+    };
+    app.request(Method::Post, path, headers, body.as_bytes())
+        .status
+}
+
+#[test]
+fn test_stacked_guards_inject_shared_extractor() {
+    let app = app();
+    let before = USER_EXTRACTIONS.load(Ordering::SeqCst);
+    let res = app.request(
+        Method::Post,
+        "/guards/blogs/7/posts",
+        &[("X-User", "ann:editor")],
+        br#"{"title":"hi"}"#,
+    );
+    assert_eq!(res.text(), "ann posted hi in 7");
+    // Two guards and the injected argument share one extraction.
+    assert_eq!(USER_EXTRACTIONS.load(Ordering::SeqCst) - before, 1);
+}
+
+#[test]
+fn test_guard_rejections() {
+    let app = app();
+    let path = "/guards/blogs/7/posts";
+    assert_eq!(post_as(&app, path, None, r#"{"title":"hi"}"#), 401);
+    assert_eq!(
+        post_as(&app, path, Some("bob:reader"), r#"{"title":"hi"}"#),
+        403
+    );
+    assert_eq!(post_as(&app, path, Some("ann:editor"), "not json"), 400);
+    assert_eq!(
+        post_as(&app, path, Some("ann:editor"), r#"{"title":""}"#),
+        422
+    );
+    assert_eq!(post_as(&app, "/guards/blogs/x/posts", None, "{}"), 400);
+}
+
+#[test]
+fn test_authorize_runs_before_validate_regardless_of_order() {
+    let app = app();
+    let path = "/guards/admin/posts";
+    assert_eq!(post_as(&app, path, None, "not json"), 401);
+    assert_eq!(post_as(&app, path, Some("ann:editor"), "not json"), 403);
+    assert_eq!(post_as(&app, path, Some("root:admin"), "not json"), 400);
+    assert_eq!(
+        post_as(&app, path, Some("root:admin"), r#"{"title":"ok"}"#),
+        200
+    );
+}
+
+#[test]
+fn test_guard_without_injection_keeps_context() {
+    let app = app();
+    assert_eq!(app.get("/guards/me").status, 401);
+    let res = app.request(Method::Get, "/guards/me", &[("X-User", "ann:editor")], b"");
+    assert_eq!(res.text(), "ann:editor");
+}
+
+#[test]
+fn test_validate_form_extractor() {
+    let app = app();
+    let form = [("Content-Type", "application/x-www-form-urlencoded")];
+    let post = |body: &str| app.request(Method::Post, "/guards/comments", &form, body.as_bytes());
+    assert_eq!(post("title=short+one").text(), "short one");
+    assert_eq!(post("title=far+too+long+a+title").status, 422);
+    assert_eq!(post("nothing=here").status, 400);
+}
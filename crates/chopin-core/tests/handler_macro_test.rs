@@ -0,0 +1,45 @@
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Response, Router, handler};
+
+/// Fetch a post.
+#[handler(get, "/handler/posts/{id}", tag = "posts")]
+fn show_post(_ctx: Context, id: u64) -> Response {
+    Response::text(format!("post {id}"))
+}
+
+#[handler(post, "/handler/posts", tag = "posts", tag = "writes")]
+fn create_post(_ctx: Context) -> Response {
+    Response::text("created")
+}
+
+#[handler(get, "/handler/health")]
+fn health(_ctx: Context) -> Response {
+    Response::text("ok")
+}
+
+#[test]
+fn test_mount_tagged_registers_only_tagged_routes() {
+    let mut router = Router::new();
+    router.mount_tagged("posts");
+    let app = TestApp::new(router);
+
+    assert_eq!(app.get("/handler/posts/3").text(), "post 3");
+    assert_eq!(app.get("/handler/posts/x").status, 400);
+    assert_eq!(app.post_json("/handler/posts", "{}").text(), "created");
+    assert_eq!(app.get("/handler/health").status, 404);
+}
+
+#[test]
+fn test_tags_in_openapi_spec() {
+    let spec = chopin_core::openapi::generate_spec();
+    let paths = &spec["paths"];
+    let show = &paths["/handler/posts/{id}"]["get"];
+    assert_eq!(show["tags"], serde_json::json!(["posts"]));
+    assert_eq!(show["summary"], "Fetch a post.");
+    assert_eq!(show["parameters"][0]["schema"]["type"], "integer");
+    assert_eq!(
+        paths["/handler/posts"]["post"]["tags"],
+        serde_json::json!(["posts", "writes"])
+    );
+    assert!(paths["/handler/health"]["get"].get("tags").is_none());
+}
@@ -0,0 +1,42 @@
+use chopin_core::Redact;
+
+#[derive(Redact)]
+struct Login {
+    username: String,
+    #[redact]
+    password: String,
+}
+
+#[derive(Redact)]
+struct ApiKey(u32, #[redact] String);
+
+#[derive(Redact)]
+struct Wrapper<T> {
+    #[redact]
+    inner: T,
+    tag: T,
+}
+
+#[test]
+fn test_derive_redact_named_fields() {
+    let login = Login {
+        username: "alice".to_string(),
+        password: "hunter2".to_string(),
+    };
+    let out = format!("{:?}", login);
+    assert_eq!(out, r#"Login { username: "alice", password: [REDACTED] }"#);
+    assert!(!out.contains("hunter2"));
+}
+
+#[test]
+fn test_derive_redact_tuple_struct() {
+    let key = ApiKey(7, "sk_live_123".to_string());
+    assert_eq!(format!("{:?}", key), "ApiKey(7, [REDACTED])");
+}
+
+#[test]
+fn test_derive_redact_generic_and_alternate() {
+    let w = Wrapper { inner: 1, tag: 2 };
+    assert_eq!(format!("{:?}", w), "Wrapper { inner: [REDACTED], tag: 2 }");
+    assert!(format!("{:#?}", w).contains("inner: [REDACTED],\n"));
+}
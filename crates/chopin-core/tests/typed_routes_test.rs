@@ -0,0 +1,43 @@
+use chopin_core::testing::TestApp;
+use chopin_core::{Context, Response, get};
+
+/// Show one post of a user.
+#[get("/typed/users/:id/posts/:slug")]
+fn show_post(_ctx: Context, id: u32, slug: String) -> Response {
+    Response::text(format!("{id}:{slug}"))
+}
+
+#[get("/typed/flags/:on")]
+fn flag(ctx: Context, _on: bool) -> Response {
+    Response::text(ctx.param("on").unwrap_or("").to_string())
+}
+
+#[test]
+fn test_typed_params_are_parsed() {
+    let app = TestApp::from_routes();
+    let res = app.get("/typed/users/7/posts/hello");
+    assert_eq!(res.status, 200);
+    assert_eq!(res.text(), "7:hello");
+
+    assert_eq!(app.get("/typed/flags/true").text(), "true");
+}
+
+#[test]
+fn test_unparseable_param_is_bad_request() {
+    let app = TestApp::from_routes();
+    assert_eq!(app.get("/typed/users/seven/posts/hello").status, 400);
+    assert_eq!(app.get("/typed/users/-1/posts/hello").status, 400);
+}
+
+#[test]
+fn test_typed_params_in_openapi_spec() {
+    let spec = chopin_core::openapi::generate_spec();
+    let op = &spec["paths"]["/typed/users/{id}/posts/{slug}"]["get"];
+    assert_eq!(op["summary"], "Show one post of a user.");
+    let params = op["parameters"].as_array().unwrap();
+    assert_eq!(
+        params[0]["schema"],
+        serde_json::json!({ "type": "integer", "format": "int64" })
+    );
+    assert_eq!(params[1]["schema"], serde_json::json!({ "type": "string" }));
+}
@@ -0,0 +1,116 @@
+//! Expansion of `embed_assets!`: walks a directory at compile time and emits
+//! a `chopin_core::assets::Assets` table with hashed names and gzip copies.
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use proc_macro2::{Literal, TokenStream};
+use quote::quote;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Extensions that get a precompressed gzip copy. Raster images, woff
+/// fonts and media are compressed already.
+const COMPRESSIBLE: &[&str] = &[
+    "html", "htm", "css", "js", "mjs", "json", "map", "xml", "txt", "csv", "svg", "ico", "ttf",
+    "otf", "wasm",
+];
+
+pub fn expand(dir: syn::LitStr) -> syn::Result<TokenStream> {
+    let base = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    let root = base.join(dir.value());
+    let mut files = Vec::new();
+    collect(&root, &mut files)
+        .map_err(|e| syn::Error::new(dir.span(), format!("cannot read {}: {e}", root.display())))?;
+    files.sort();
+
+    let mut entries = Vec::with_capacity(files.len());
+    for file in &files {
+        let data = std::fs::read(file).map_err(|e| {
+            syn::Error::new(dir.span(), format!("cannot read {}: {e}", file.display()))
+        })?;
+        let rel = file
+            .strip_prefix(&root)
+            .unwrap_or(file)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let hash = fnv1a(&data);
+        let hashed = hashed_name(&rel, hash);
+        let etag = format!("\"{hash:016x}\"");
+        // `include_bytes!` makes Cargo rebuild when a file changes.
+        let abs = file.to_string_lossy();
+        let gzip = match gzip(&rel, &data) {
+            Some(bytes) => {
+                let lit = Literal::byte_string(&bytes);
+                quote! { ::std::option::Option::Some(#lit) }
+            }
+            None => quote! { ::std::option::Option::None },
+        };
+        entries.push(quote! {
+            ::chopin_core::assets::Asset {
+                path: #rel,
+                hashed: #hashed,
+                etag: #etag,
+                body: include_bytes!(#abs),
+                gzip: #gzip,
+            }
+        });
+    }
+
+    Ok(quote! {
+        ::chopin_core::assets::Assets::new(&[#(#entries),*])
+    })
+}
+
+/// Every regular file below `dir`, skipping dotfiles and dot-directories.
+fn collect(dir: &Path, out: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            collect(&path, out)?;
+        } else {
+            out.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn fnv1a(data: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in data {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
+
+/// `css/app.css` → `css/app.1a2b3c4d.css`.
+fn hashed_name(path: &str, hash: u64) -> String {
+    let short = format!("{:08x}", hash >> 32);
+    let (dir, file) = match path.rfind('/') {
+        Some(i) => path.split_at(i + 1),
+        None => ("", path),
+    };
+    match file.rfind('.').filter(|&i| i > 0) {
+        Some(i) => format!("{dir}{}.{short}{}", &file[..i], &file[i..]),
+        None => format!("{dir}{file}.{short}"),
+    }
+}
+
+/// Gzip copy of a compressible file, if it comes out smaller.
+fn gzip(path: &str, data: &[u8]) -> Option<Vec<u8>> {
+    let ext = path.rsplit_once('.')?.1.to_ascii_lowercase();
+    if !COMPRESSIBLE.contains(&ext.as_str()) || data.is_empty() {
+        return None;
+    }
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(data).ok()?;
+    let compressed = encoder.finish().ok()?;
+    (compressed.len() < data.len()).then_some(compressed)
+}
@@ -0,0 +1,346 @@
+//! Expansion of stacked `#[authorize]` / `#[validate]` attributes into one
+//! guard chain. Route attributes run the same expansion before handling
+//! path parameters, so the result does not depend on attribute order.
+use quote::{ToTokens, quote};
+use syn::parse::ParseStream;
+use syn::spanned::Spanned;
+use syn::{Attribute, Expr, ItemFn, Type};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Authorize,
+    Validate,
+}
+
+/// Where a guarded value comes from.
+enum Source {
+    /// `<T as FromRequest>::from_request`.
+    Extract,
+    /// `chopin_auth::owner::load_owned`, for `#[owner_required]`.
+    Owner {
+        param: syn::LitStr,
+        field: syn::Ident,
+        roles: Vec<syn::LitStr>,
+    },
+    /// `chopin_auth::mfa::require_fresh`, for `#[mfa_required]`.
+    Mfa { max_age: Option<syn::LitInt> },
+}
+
+struct Guard {
+    kind: Kind,
+    path: syn::Path,
+    ty: Type,
+    source: Source,
+    pred: Option<Expr>,
+}
+
+/// Guard attributes: `authorize`, `validate`, and the `authorize`
+/// shorthands `role_required` (on `chopin_auth::Roles`), `owner_required`
+/// (on a model loaded by primary key) and `mfa_required` (on
+/// `chopin_auth::MfaVerified`).
+fn guard_kind(attr: &Attribute) -> Option<Kind> {
+    let ident = &attr.path().segments.last()?.ident;
+    if ident == "authorize"
+        || ident == "role_required"
+        || ident == "owner_required"
+        || ident == "mfa_required"
+    {
+        Some(Kind::Authorize)
+    } else if ident == "validate" {
+        Some(Kind::Validate)
+    } else {
+        None
+    }
+}
+
+/// `#[owner_required(Post, field = "author_id", param = "id", roles("admin"))]`;
+/// `param` defaults to `"id"` and `roles` to none.
+fn parse_owner_required(kind: Kind, attr: &Attribute) -> syn::Result<Guard> {
+    attr.parse_args_with(|input: ParseStream| {
+        let ty: Type = input.parse()?;
+        let mut param = None;
+        let mut field = None;
+        let mut roles = Vec::new();
+        while input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            let key: syn::Ident = input.parse()?;
+            if key == "roles" {
+                let content;
+                syn::parenthesized!(content in input);
+                roles.extend(
+                    content.parse_terminated(
+                        |p: ParseStream| p.parse::<syn::LitStr>(),
+                        syn::Token![,],
+                    )?,
+                );
+                continue;
+            }
+            input.parse::<syn::Token![=]>()?;
+            let value: syn::LitStr = input.parse()?;
+            if key == "param" {
+                param = Some(value);
+            } else if key == "field" {
+                field = Some(value.parse::<syn::Ident>()?);
+            } else {
+                return Err(syn::Error::new_spanned(
+                    key,
+                    "expected `param`, `field` or `roles`",
+                ));
+            }
+        }
+        let field = field.ok_or_else(|| {
+            syn::Error::new_spanned(attr, "#[owner_required] needs `field = \"...\"`")
+        })?;
+        Ok(Guard {
+            kind,
+            path: attr.path().clone(),
+            ty,
+            source: Source::Owner {
+                param: param.unwrap_or_else(|| syn::LitStr::new("id", attr.span())),
+                field,
+                roles,
+            },
+            pred: None,
+        })
+    })
+}
+
+/// `#[mfa_required]` or `#[mfa_required(max_age = 60)]`.
+fn parse_mfa_required(kind: Kind, attr: &Attribute) -> syn::Result<Guard> {
+    let mut max_age = None;
+    if !matches!(attr.meta, syn::Meta::Path(_)) {
+        attr.parse_args_with(|input: ParseStream| {
+            if input.is_empty() {
+                return Ok(());
+            }
+            let key: syn::Ident = input.parse()?;
+            if key != "max_age" {
+                return Err(syn::Error::new_spanned(key, "expected `max_age`"));
+            }
+            input.parse::<syn::Token![=]>()?;
+            max_age = Some(input.parse::<syn::LitInt>()?);
+            input.parse::<Option<syn::Token![,]>>()?;
+            Ok(())
+        })?;
+    }
+    Ok(Guard {
+        kind,
+        path: attr.path().clone(),
+        ty: syn::parse_quote!(::chopin_auth::MfaVerified),
+        source: Source::Mfa { max_age },
+        pred: None,
+    })
+}
+
+fn parse_guard(kind: Kind, attr: &Attribute) -> syn::Result<Guard> {
+    let name = attr.path().segments.last().map(|s| s.ident.to_string());
+    if name.as_deref() == Some("mfa_required") {
+        return parse_mfa_required(kind, attr);
+    }
+    if name.as_deref() == Some("owner_required") {
+        return parse_owner_required(kind, attr);
+    }
+    if name.as_deref() == Some("role_required") {
+        let roles = attr.parse_args_with(
+            syn::punctuated::Punctuated::<syn::LitStr, syn::Token![,]>::parse_terminated,
+        )?;
+        if roles.is_empty() {
+            return Err(syn::Error::new_spanned(
+                attr,
+                "#[role_required] needs at least one role",
+            ));
+        }
+        let roles = roles.iter();
+        return Ok(Guard {
+            kind,
+            path: attr.path().clone(),
+            ty: syn::parse_quote!(::chopin_auth::Roles),
+            source: Source::Extract,
+            pred: Some(syn::parse_quote!(
+                |roles: &::chopin_auth::Roles| roles.has_any(&[#(#roles),*])
+            )),
+        });
+    }
+    attr.parse_args_with(|input: ParseStream| {
+        let ty: Type = input.parse()?;
+        let mut pred = None;
+        if input.parse::<Option<syn::Token![,]>>()?.is_some() && !input.is_empty() {
+            pred = Some(input.parse()?);
+            input.parse::<Option<syn::Token![,]>>()?;
+        }
+        Ok(Guard {
+            kind,
+            path: attr.path().clone(),
+            ty,
+            source: Source::Extract,
+            pred,
+        })
+    })
+}
+
+/// Extractors are matched by their last path segment, so `Roles` and
+/// `chopin_auth::Roles` (or `Auth<Claims>` and `chopin_auth::Auth<Claims>`)
+/// share one extraction.
+fn type_key(ty: &Type) -> String {
+    match ty {
+        Type::Path(p) if p.qself.is_none() => match p.path.segments.last() {
+            Some(last) => last.to_token_stream().to_string(),
+            None => ty.to_token_stream().to_string(),
+        },
+        _ => ty.to_token_stream().to_string(),
+    }
+}
+
+/// Removes the guard attributes from `input_fn` and prepends their checks
+/// to its body. Arguments typed as a guarded extractor are removed from the
+/// signature and bound inside the body instead.
+pub(crate) fn expand(input_fn: &mut ItemFn) -> syn::Result<()> {
+    let mut guards = Vec::new();
+    let mut kept = Vec::new();
+    for attr in std::mem::take(&mut input_fn.attrs) {
+        match guard_kind(&attr) {
+            Some(kind) => guards.push(parse_guard(kind, &attr)?),
+            None => kept.push(attr),
+        }
+    }
+    input_fn.attrs = kept;
+    if guards.is_empty() {
+        return Ok(());
+    }
+    // Stable, so each kind keeps its source order.
+    guards.sort_by_key(|g| g.kind == Kind::Validate);
+
+    let ctx = match input_fn.sig.inputs.first_mut() {
+        Some(syn::FnArg::Typed(arg)) => match &mut *arg.pat {
+            syn::Pat::Ident(p) if p.ident != "_" => p.ident.clone(),
+            pat => {
+                let ident = syn::Ident::new("__chopin_ctx", proc_macro2::Span::call_site());
+                *pat = syn::Pat::Ident(syn::PatIdent {
+                    attrs: Vec::new(),
+                    by_ref: None,
+                    mutability: None,
+                    ident: ident.clone(),
+                    subpat: None,
+                });
+                ident
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input_fn.sig,
+                "guarded handlers must take `Context` as their first argument",
+            ));
+        }
+    };
+
+    let mut extracted: Vec<(String, syn::Ident)> = Vec::new();
+    let mut stmts = Vec::new();
+    for guard in &guards {
+        let key = type_key(&guard.ty);
+        let var = match extracted.iter().find(|(k, _)| *k == key) {
+            Some((_, var)) => var.clone(),
+            None => {
+                let var = syn::Ident::new(
+                    &format!("__chopin_guard_{}", extracted.len()),
+                    proc_macro2::Span::call_site(),
+                );
+                let ty = &guard.ty;
+                let load = match &guard.source {
+                    Source::Extract => quote! {
+                        <#ty as ::chopin_core::FromRequest<'_>>::from_request(&#ctx)
+                    },
+                    Source::Owner {
+                        param,
+                        field,
+                        roles,
+                    } => quote! {
+                        ::chopin_auth::owner::load_owned::<#ty>(
+                            &#ctx,
+                            #param,
+                            |model: &#ty| ::std::string::ToString::to_string(&model.#field),
+                            &[#(#roles),*],
+                        )
+                    },
+                    Source::Mfa { max_age } => {
+                        let max_age = match max_age {
+                            Some(secs) => quote! { ::core::option::Option::Some(#secs) },
+                            None => quote! { ::core::option::Option::None },
+                        };
+                        quote! { ::chopin_auth::mfa::require_fresh(&#ctx, #max_age) }
+                    }
+                };
+                stmts.push(quote! {
+                    let #var = match #load {
+                        Ok(value) => value,
+                        Err(err) => {
+                            return ::core::convert::Into::<::chopin_core::http::Response>::into(err);
+                        }
+                    };
+                });
+                extracted.push((key, var.clone()));
+                var
+            }
+        };
+        if let Some(pred) = &guard.pred {
+            let reject = match guard.kind {
+                Kind::Authorize => quote! { ::chopin_core::http::Response::forbidden() },
+                Kind::Validate => quote! { ::chopin_core::http::Response::unprocessable() },
+            };
+            stmts.push(quote! {
+                if !::chopin_core::guard::check(&#var, #pred) {
+                    return #reject;
+                }
+            });
+        }
+    }
+
+    let mut bindings = Vec::new();
+    let mut injected: Vec<String> = Vec::new();
+    let inputs = std::mem::take(&mut input_fn.sig.inputs);
+    for (i, input) in inputs.into_iter().enumerate() {
+        if i > 0
+            && let syn::FnArg::Typed(arg) = &input
+        {
+            let key = type_key(&arg.ty);
+            if let Some((_, var)) = extracted.iter().find(|(k, _)| *k == key) {
+                if injected.contains(&key) {
+                    return Err(syn::Error::new_spanned(
+                        &arg.ty,
+                        "this extractor is already bound to another argument",
+                    ));
+                }
+                injected.push(key);
+                let pat = &arg.pat;
+                let ty = &arg.ty;
+                bindings.push(quote! { let #pat: #ty = #var; });
+                continue;
+            }
+        }
+        input_fn.sig.inputs.push(input);
+    }
+
+    // Attributes consumed here are never expanded themselves; naming their
+    // paths keeps them resolved (and their imports used) in any order.
+    let paths = guards.iter().map(|g| &g.path);
+    let block = &input_fn.block;
+    *input_fn.block = syn::parse_quote!({
+        #(#[allow(unused_imports)] use #paths as _;)*
+        #(#stmts)*
+        #(#bindings)*
+        #block
+    });
+    Ok(())
+}
+
+/// Entry point for the guard attributes themselves. The attribute being
+/// expanded was stripped by the compiler, so it is put back in front of the
+/// remaining ones before expanding the whole stack.
+pub(crate) fn expand_attribute(
+    name: &str,
+    attr: proc_macro2::TokenStream,
+    mut input_fn: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let name = syn::Ident::new(name, proc_macro2::Span::call_site());
+    input_fn.attrs.insert(0, syn::parse_quote!(#[#name(#attr)]));
+    expand(&mut input_fn)?;
+    Ok(input_fn.into_token_stream())
+}
@@ -2,6 +2,9 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{ItemFn, parse_macro_input};
 
+mod assets;
+mod guards;
+
 #[proc_macro_attribute]
 pub fn get(attr: TokenStream, item: TokenStream) -> TokenStream {
     generate_route("Get", attr, item)
@@ -47,9 +50,118 @@ pub fn connect(attr: TokenStream, item: TokenStream) -> TokenStream {
     generate_route("Connect", attr, item)
 }
 
+/// Registers a route from a single attribute, with OpenAPI-style `{param}`
+/// path segments and optional tags:
+///
+/// ```rust,ignore
+/// #[handler(get, "/api/posts/{id}", tag = "posts")]
+/// fn show(ctx: Context, id: u64) -> Response { .. }
+/// ```
+///
+/// Tags are added to the operation in the OpenAPI spec, and
+/// `Router::mount_tagged` registers every route carrying a tag, so a
+/// module can mount its own handlers from `routes()`.
+#[proc_macro_attribute]
+pub fn handler(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(
+        attr with syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated
+    );
+    match parse_handler_args(args) {
+        Ok((method, path_lit, tags)) => expand_route(&method, path_lit, &tags, item),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+const METHODS: [&str; 9] = [
+    "Get", "Post", "Put", "Delete", "Patch", "Head", "Options", "Trace", "Connect",
+];
+
+fn parse_handler_args(
+    args: syn::punctuated::Punctuated<syn::Expr, syn::Token![,]>,
+) -> syn::Result<(String, syn::LitStr, Vec<String>)> {
+    let usage = "expected #[handler(<method>, \"/path\", tag = \"...\")]";
+    let mut args = args.into_iter();
+
+    let method = match args.next() {
+        Some(syn::Expr::Path(p)) if p.path.get_ident().is_some() => {
+            let ident = p.path.get_ident().unwrap();
+            let lower = ident.to_string().to_ascii_lowercase();
+            METHODS
+                .iter()
+                .find(|m| m.to_ascii_lowercase() == lower)
+                .map(|m| m.to_string())
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(ident, format!("unknown HTTP method `{ident}`"))
+                })?
+        }
+        other => return Err(syn::Error::new(spanned_or_call_site(other.as_ref()), usage)),
+    };
+
+    let path = match args.next() {
+        Some(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Str(s),
+            ..
+        })) => s,
+        other => return Err(syn::Error::new(spanned_or_call_site(other.as_ref()), usage)),
+    };
+    let path = syn::LitStr::new(&braces_to_colons(&path.value()), path.span());
+
+    let mut tags = Vec::new();
+    for arg in args {
+        match &arg {
+            syn::Expr::Assign(assign) if matches!(&*assign.left, syn::Expr::Path(p) if p.path.is_ident("tag")) => {
+                match &*assign.right {
+                    syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(s),
+                        ..
+                    }) => tags.push(s.value()),
+                    right => return Err(syn::Error::new_spanned(right, "tag must be a string")),
+                }
+            }
+            _ => return Err(syn::Error::new_spanned(arg, usage)),
+        }
+    }
+    Ok((method, path, tags))
+}
+
+fn spanned_or_call_site(expr: Option<&syn::Expr>) -> proc_macro2::Span {
+    use syn::spanned::Spanned;
+    expr.map(|e| e.span())
+        .unwrap_or_else(proc_macro2::Span::call_site)
+}
+
+/// `/posts/{id}/files/{*rest}` → `/posts/:id/files/*rest`.
+fn braces_to_colons(path: &str) -> String {
+    path.split('/')
+        .map(
+            |segment| match segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')) {
+                Some(name) => match name.strip_prefix('*') {
+                    Some(rest) => format!("*{rest}"),
+                    None => format!(":{name}"),
+                },
+                None => segment.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
 fn generate_route(method: &str, attr: TokenStream, item: TokenStream) -> TokenStream {
-    let path = parse_macro_input!(attr as syn::LitStr).value();
-    let input_fn = parse_macro_input!(item as ItemFn);
+    let path_lit = parse_macro_input!(attr as syn::LitStr);
+    expand_route(method, path_lit, &[], item)
+}
+
+fn expand_route(
+    method: &str,
+    path_lit: syn::LitStr,
+    tags: &[String],
+    item: TokenStream,
+) -> TokenStream {
+    let path = path_lit.value();
+    let mut input_fn = parse_macro_input!(item as ItemFn);
+    if let Err(e) = guards::expand(&mut input_fn) {
+        return e.to_compile_error().into();
+    }
 
     let fn_name = &input_fn.sig.ident;
     let method_ident = syn::Ident::new(method, proc_macro2::Span::call_site());
@@ -75,8 +187,59 @@ fn generate_route(method: &str, attr: TokenStream, item: TokenStream) -> TokenSt
         String::new()
     };
 
+    let typed = match typed_params(&path, &path_lit, &input_fn) {
+        Ok(typed) => typed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let param_defs = typed.iter().map(|(name, ty)| {
+        quote! {
+            ::chopin_core::PathParamDef {
+                name: #name,
+                openapi_type: <#ty as ::chopin_core::PathParam>::OPENAPI_TYPE,
+                openapi_format: <#ty as ::chopin_core::PathParam>::OPENAPI_FORMAT,
+            }
+        }
+    });
+
+    let handler = if typed.is_empty() {
+        quote! { #input_fn }
+    } else {
+        // Keep the user's function as an inner item and expose a plain
+        // `fn(Context) -> Response` under its name that parses the params.
+        let attrs = &input_fn.attrs;
+        let vis = &input_fn.vis;
+        let output = &input_fn.sig.output;
+        let mut inner = input_fn.clone();
+        inner.attrs.retain(|a| !a.path().is_ident("doc"));
+        inner.vis = syn::Visibility::Inherited;
+        inner.sig.ident = syn::Ident::new("__chopin_typed_handler", fn_name.span());
+        let vars: Vec<syn::Ident> = (0..typed.len())
+            .map(|i| {
+                syn::Ident::new(
+                    &format!("__chopin_param_{i}"),
+                    proc_macro2::Span::call_site(),
+                )
+            })
+            .collect();
+        let names = typed.iter().map(|(name, _)| name);
+        let tys = typed.iter().map(|(_, ty)| ty);
+        quote! {
+            #(#attrs)*
+            #vis fn #fn_name(__chopin_ctx: ::chopin_core::http::Context) #output {
+                #inner
+                #(
+                    let #vars = match ::chopin_core::extract::path_param::<#tys>(&__chopin_ctx, #names) {
+                        Some(value) => value,
+                        None => return ::chopin_core::http::Response::bad_request(),
+                    };
+                )*
+                __chopin_typed_handler(__chopin_ctx, #(#vars),*)
+            }
+        }
+    };
+
     let expanded = quote! {
-        #input_fn
+        #handler
 
         ::chopin_core::inventory::submit! {
             ::chopin_core::RouteDef {
@@ -85,9 +248,439 @@ fn generate_route(method: &str, attr: TokenStream, item: TokenStream) -> TokenSt
                 handler: #fn_name,
                 summary: #summary,
                 description: #description,
+                params: &[#(#param_defs),*],
+                tags: &[#(#tags),*],
+            }
+        }
+    };
+
+    TokenStream::from(expanded)
+}
+
+/// Typed path parameters declared after the `Context` argument, as
+/// `(name, type)` in argument order. Once a handler takes any, it must take every
+/// `:name` / `*name` segment of the path, and nothing else.
+fn typed_params(
+    path: &str,
+    path_lit: &syn::LitStr,
+    input_fn: &ItemFn,
+) -> syn::Result<Vec<(String, syn::Type)>> {
+    let path_params: Vec<&str> = path
+        .split('/')
+        .filter_map(|s| s.strip_prefix(':').or_else(|| s.strip_prefix('*')))
+        .collect();
+
+    let mut args = Vec::new();
+    for input in input_fn.sig.inputs.iter().skip(1) {
+        let syn::FnArg::Typed(arg) = input else {
+            return Err(syn::Error::new_spanned(
+                input,
+                "route handlers cannot take `self`",
+            ));
+        };
+        let syn::Pat::Ident(pat) = &*arg.pat else {
+            return Err(syn::Error::new_spanned(
+                &arg.pat,
+                "path parameters must be plain identifiers",
+            ));
+        };
+        let ident = pat.ident.to_string();
+        // `_id` binds `:id` so unused parameters need no `#[allow]`.
+        let stripped = ident.strip_prefix('_').unwrap_or(&ident);
+        let Some(name) = path_params
+            .iter()
+            .find(|p| **p == ident)
+            .or_else(|| path_params.iter().find(|p| **p == stripped))
+        else {
+            return Err(syn::Error::new_spanned(
+                &pat.ident,
+                format!("`{ident}` is not a parameter of route `{path}`"),
+            ));
+        };
+        if args.iter().any(|(n, _)| n == name) {
+            return Err(syn::Error::new_spanned(
+                &pat.ident,
+                format!("path parameter `{name}` is bound twice"),
+            ));
+        }
+        args.push((name.to_string(), (*arg.ty).clone()));
+    }
+
+    if !args.is_empty()
+        && let Some(missing) = path_params
+            .iter()
+            .find(|p| !args.iter().any(|(n, _)| n == *p))
+    {
+        return Err(syn::Error::new_spanned(
+            path_lit,
+            format!("path parameter `{missing}` has no matching handler argument"),
+        ));
+    }
+    Ok(args)
+}
+
+/// Rejects the request unless the extractor succeeds and, if given, the
+/// predicate holds (`403 Forbidden` otherwise):
+///
+/// ```rust,ignore
+/// #[get("/api/admin")]
+/// #[authorize(Auth<Claims>, |auth| auth.claims.has_role(&Role::Admin))]
+/// fn admin(ctx: Context, auth: Auth<Claims>) -> Response { .. }
+/// ```
+///
+/// Stacks with other `#[authorize]` and `#[validate]` attributes; see
+/// `chopin_core::guard` for the ordering and argument injection rules.
+#[proc_macro_attribute]
+pub fn authorize(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match guards::expand_attribute("authorize", attr.into(), input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Like [`macro@authorize`], but runs after every `#[authorize]` guard and
+/// answers a failed predicate with `422 Unprocessable Entity`:
+///
+/// ```rust,ignore
+/// #[post("/api/posts")]
+/// #[validate(Json<NewPost>, |Json(p)| !p.title.is_empty())]
+/// fn create(ctx: Context, body: Json<NewPost>) -> Response { .. }
+/// ```
+#[proc_macro_attribute]
+pub fn validate(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match guards::expand_attribute("validate", attr.into(), input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Requires the bearer token to carry one of the given roles, following the
+/// hierarchy configured with `chopin_auth::init_rbac` (`403 Forbidden`
+/// otherwise):
+///
+/// ```rust,ignore
+/// #[get("/api/reports")]
+/// #[role_required("staff")]
+/// fn reports(ctx: Context) -> Response { .. }
+/// ```
+///
+/// Shorthand for `#[authorize(chopin_auth::Roles, ..)]`, so it stacks with
+/// the other guards and a `Roles` argument receives the caller's roles.
+#[proc_macro_attribute]
+pub fn role_required(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match guards::expand_attribute("role_required", attr.into(), input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Loads the model named by a path parameter and admits only its owner:
+///
+/// ```rust,ignore
+/// #[put("/api/posts/:id")]
+/// #[owner_required(Post, param = "id", field = "author_id", roles("admin"))]
+/// fn update_post(ctx: Context, post: Post) -> Response { .. }
+/// ```
+///
+/// The token's `sub` claim must equal `post.author_id` (compared through
+/// `Display`) unless the caller holds one of `roles`. `param` defaults to
+/// `"id"`. A `Post` argument receives the loaded row. Requires the `orm`
+/// feature of `chopin_auth`; see `chopin_auth::owner` for the responses.
+#[proc_macro_attribute]
+pub fn owner_required(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match guards::expand_attribute("owner_required", attr.into(), input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Admits only sessions that passed a second factor recently:
+///
+/// ```rust,ignore
+/// #[delete("/api/account")]
+/// #[mfa_required(max_age = 60)]
+/// fn delete_account(ctx: Context, mfa: MfaVerified) -> Response { .. }
+/// ```
+///
+/// Shorthand for `#[authorize(chopin_auth::MfaVerified)]` using the global
+/// `chopin_auth::MfaService` window; `max_age` (seconds) narrows that window
+/// for this handler. See `chopin_auth::mfa` for the responses.
+#[proc_macro_attribute]
+pub fn mfa_required(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match guards::expand_attribute("mfa_required", attr.into(), input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Derives a `Debug` impl that prints `[REDACTED]` for fields marked `#[redact]`.
+///
+/// ```rust,ignore
+/// #[derive(Redact)]
+/// struct Signup {
+///     name: String,
+///     #[redact]
+///     email: String,
+/// }
+/// ```
+#[proc_macro_derive(Redact, attributes(redact))]
+pub fn derive_redact(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    let name = &input.ident;
+    let name_str = name.to_string();
+
+    let syn::Data::Struct(data) = &input.data else {
+        return syn::Error::new_spanned(name, "Redact can only be derived for structs")
+            .to_compile_error()
+            .into();
+    };
+
+    let is_redacted = |f: &syn::Field| f.attrs.iter().any(|a| a.path().is_ident("redact"));
+
+    let body = match &data.fields {
+        syn::Fields::Named(fields) => {
+            let entries = fields.named.iter().map(|f| {
+                let ident = f.ident.as_ref().unwrap();
+                let label = ident.to_string();
+                if is_redacted(f) {
+                    // Touch the field so a value only read through `Debug` is
+                    // not reported as dead code.
+                    quote! { .field(#label, { let _ = &self.#ident; &::chopin_core::redact::Redacted }) }
+                } else {
+                    quote! { .field(#label, &self.#ident) }
+                }
+            });
+            quote! { f.debug_struct(#name_str) #(#entries)* .finish() }
+        }
+        syn::Fields::Unnamed(fields) => {
+            let entries = fields.unnamed.iter().enumerate().map(|(i, f)| {
+                let index = syn::Index::from(i);
+                if is_redacted(f) {
+                    quote! { .field({ let _ = &self.#index; &::chopin_core::redact::Redacted }) }
+                } else {
+                    quote! { .field(&self.#index) }
+                }
+            });
+            quote! { f.debug_tuple(#name_str) #(#entries)* .finish() }
+        }
+        syn::Fields::Unit => quote! { f.write_str(#name_str) },
+    };
+
+    let mut generics = input.generics.clone();
+    for param in generics.type_params_mut() {
+        param.bounds.push(syn::parse_quote!(::core::fmt::Debug));
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let expanded = quote! {
+        impl #impl_generics ::core::fmt::Debug for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                #body
             }
         }
     };
 
     TokenStream::from(expanded)
 }
+
+/// Memoizes a function's return value in the cache service
+/// (chopin-core's `orm` feature).
+///
+/// ```rust,ignore
+/// #[cached(ttl = "30s")]
+/// fn trending(limit: u32) -> Vec<Post> { .. }
+///
+/// #[cached(ttl = "5m", key = format!("profile:{id}"))]
+/// fn profile(id: i64) -> ChopinResult<Profile> { .. }
+/// ```
+///
+/// `ttl` accepts `ms`, `s`, `m`, `h` and `d` suffixes. The return value must
+/// implement `Serialize` and `DeserializeOwned`; for `Result` return types
+/// only `Ok` values are cached. Without `key`, arguments must be `Debug`.
+#[proc_macro_attribute]
+pub fn cached(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(
+        attr with syn::punctuated::Punctuated::<syn::MetaNameValue, syn::Token![,]>::parse_terminated
+    );
+    let input_fn = parse_macro_input!(item as ItemFn);
+    match expand_cached(args, input_fn) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+fn expand_cached(
+    args: syn::punctuated::Punctuated<syn::MetaNameValue, syn::Token![,]>,
+    input_fn: ItemFn,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let mut ttl_ms = None;
+    let mut key_expr = None;
+    for arg in args {
+        if arg.path.is_ident("ttl") {
+            let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(s),
+                ..
+            }) = &arg.value
+            else {
+                return Err(syn::Error::new_spanned(
+                    &arg.value,
+                    "ttl must be a string like \"30s\"",
+                ));
+            };
+            ttl_ms = Some(parse_duration_ms(&s.value()).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    s,
+                    "invalid ttl; expected e.g. \"500ms\", \"30s\", \"5m\", \"1h\"",
+                )
+            })?);
+        } else if arg.path.is_ident("key") {
+            key_expr = Some(arg.value);
+        } else {
+            return Err(syn::Error::new_spanned(
+                &arg.path,
+                "expected `ttl` or `key`",
+            ));
+        }
+    }
+    let ttl_ms = ttl_ms.ok_or_else(|| {
+        syn::Error::new(
+            proc_macro2::Span::call_site(),
+            "#[cached] requires `ttl = \"...\"`",
+        )
+    })?;
+
+    let syn::ReturnType::Type(_, ret) = &input_fn.sig.output else {
+        return Err(syn::Error::new_spanned(
+            &input_fn.sig,
+            "#[cached] functions must return a value",
+        ));
+    };
+    // `Result<T, E>` and aliases such as `ChopinResult<T>` cache only `T`.
+    let ok_type = match &**ret {
+        syn::Type::Path(p) => p.path.segments.last().and_then(|seg| {
+            if !seg.ident.to_string().ends_with("Result") {
+                return None;
+            }
+            match &seg.arguments {
+                syn::PathArguments::AngleBracketed(a) => a.args.iter().find_map(|arg| match arg {
+                    syn::GenericArgument::Type(t) => Some(t.clone()),
+                    _ => None,
+                }),
+                _ => None,
+            }
+        }),
+        _ => None,
+    };
+
+    let fn_name = input_fn.sig.ident.to_string();
+    let key = match key_expr {
+        Some(expr) => quote! { ::std::string::ToString::to_string(&(#expr)) },
+        None => {
+            let mut idents = Vec::new();
+            for input in &input_fn.sig.inputs {
+                match input {
+                    syn::FnArg::Typed(arg) => match &*arg.pat {
+                        syn::Pat::Ident(p) => idents.push(p.ident.clone()),
+                        other => {
+                            return Err(syn::Error::new_spanned(
+                                other,
+                                "#[cached] without `key` needs plain identifier arguments",
+                            ));
+                        }
+                    },
+                    syn::FnArg::Receiver(r) => {
+                        return Err(syn::Error::new_spanned(
+                            r,
+                            "#[cached] without `key` cannot take `self`",
+                        ));
+                    }
+                }
+            }
+            let fmt = format!("{{}}{})", vec!["{:?}"; idents.len()].join(", "));
+            quote! {
+                format!(
+                    #fmt,
+                    ::chopin_core::memo::fn_key_prefix(concat!(module_path!(), "::", #fn_name)),
+                    #(&#idents),*
+                )
+            }
+        }
+    };
+
+    let attrs = &input_fn.attrs;
+    let vis = &input_fn.vis;
+    let sig = &input_fn.sig;
+    let block = &input_fn.block;
+    let lookup_and_store = match ok_type {
+        Some(ok) => quote! {
+            if let Some(hit) = ::chopin_core::memo::get::<#ok>(&__chopin_key) {
+                return Ok(hit);
+            }
+            let __chopin_value: #ret = (move || -> #ret #block)();
+            if let Ok(value) = &__chopin_value {
+                ::chopin_core::memo::put(&__chopin_key, value, __chopin_ttl);
+            }
+            __chopin_value
+        },
+        None => quote! {
+            if let Some(hit) = ::chopin_core::memo::get::<#ret>(&__chopin_key) {
+                return hit;
+            }
+            let __chopin_value: #ret = (move || -> #ret #block)();
+            ::chopin_core::memo::put(&__chopin_key, &__chopin_value, __chopin_ttl);
+            __chopin_value
+        },
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+        #vis #sig {
+            let __chopin_ttl = ::std::time::Duration::from_millis(#ttl_ms);
+            let __chopin_key: ::std::string::String = #key;
+            #lookup_and_store
+        }
+    })
+}
+
+/// `"500ms"`, `"30s"`, `"5m"`, `"1h"`, `"2d"` → milliseconds.
+fn parse_duration_ms(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit())?;
+    let (n, unit) = s.split_at(split);
+    let n: u64 = n.parse().ok()?;
+    let scale = match unit.trim() {
+        "ms" => 1,
+        "s" => 1_000,
+        "m" => 60_000,
+        "h" => 3_600_000,
+        "d" => 86_400_000,
+        _ => return None,
+    };
+    n.checked_mul(scale)
+}
+
+/// Compiles every file under a directory into the binary and evaluates to a
+/// `chopin_core::assets::Assets` table. The path is relative to the crate's
+/// `Cargo.toml`; dotfiles are skipped.
+///
+/// ```rust,ignore
+/// static PUBLIC: Assets = embed_assets!("./public");
+/// ```
+///
+/// Each file is also listed under a content-hashed name
+/// (`css/app.3f9a1c2e.css`), and text formats carry a gzip copy compressed
+/// at build time. Edits to embedded files trigger a rebuild; files added to
+/// the directory are picked up on the next rebuild of the crate.
+#[proc_macro]
+pub fn embed_assets(input: TokenStream) -> TokenStream {
+    let dir = parse_macro_input!(input as syn::LitStr);
+    match assets::expand(dir) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
@@ -0,0 +1,146 @@
+//! `#[derive(ModelDto)]`: a serializable response projection of a model.
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, Type};
+
+pub(crate) fn is_hidden(field: &syn::Field) -> bool {
+    let mut hidden = false;
+    for attr in &field.attrs {
+        if attr.path().is_ident("model") {
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("hidden") {
+                    hidden = true;
+                } else if meta.input.peek(syn::Token![=]) {
+                    // Skip `as = "..."` and friends so later flags are still seen.
+                    meta.value()?.parse::<syn::Expr>()?;
+                }
+                Ok(())
+            });
+        }
+    }
+    hidden
+}
+
+/// `T` of `Wrapper<T>` when the last path segment is `wrapper`.
+pub(crate) fn generic_arg<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(p) = ty else { return None };
+    let segment = p.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) => match args.args.first()? {
+            syn::GenericArgument::Type(t) => Some(t),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// OpenAPI 3.0 schema of a field type, as JSON text.
+fn schema_of(ty: &Type) -> String {
+    if let Some(inner) = generic_arg(ty, "Option") {
+        let inner = schema_of(inner);
+        return match inner.strip_suffix('}') {
+            Some(open) if open.len() > 1 => format!("{open},\"nullable\":true}}"),
+            _ => "{\"nullable\":true}".to_string(),
+        };
+    }
+    if let Some(inner) = generic_arg(ty, "Vec") {
+        if quote!(#inner).to_string() == "u8" {
+            return r#"{"type":"string","format":"byte"}"#.to_string();
+        }
+        return format!(r#"{{"type":"array","items":{}}}"#, schema_of(inner));
+    }
+    let last = match ty {
+        Type::Path(p) => p
+            .path
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default(),
+        Type::Reference(r) => return schema_of(&r.elem),
+        _ => String::new(),
+    };
+    match last.as_str() {
+        "i8" | "i16" | "i32" | "u8" | "u16" => r#"{"type":"integer","format":"int32"}"#,
+        "i64" | "u32" | "u64" | "isize" | "usize" => r#"{"type":"integer","format":"int64"}"#,
+        "f32" => r#"{"type":"number","format":"float"}"#,
+        "f64" => r#"{"type":"number","format":"double"}"#,
+        "Decimal" => r#"{"type":"string","format":"decimal"}"#,
+        "bool" => r#"{"type":"boolean"}"#,
+        "String" | "str" | "char" => r#"{"type":"string"}"#,
+        "DateTime" | "NaiveDateTime" | "OffsetDateTime" | "PrimitiveDateTime" | "SystemTime" => {
+            r#"{"type":"string","format":"date-time"}"#
+        }
+        "NaiveDate" | "Date" => r#"{"type":"string","format":"date"}"#,
+        "NaiveTime" | "Time" => r#"{"type":"string","format":"time"}"#,
+        "Uuid" => r#"{"type":"string","format":"uuid"}"#,
+        _ => "{}",
+    }
+    .to_string()
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let dto = format_ident!("{}Response", name);
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "ModelDto can only be derived for structs with named fields",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "ModelDto can only be derived for structs with named fields",
+        ));
+    };
+
+    let mut decls = Vec::new();
+    let mut idents = Vec::new();
+    let mut properties = Vec::new();
+    let mut required = Vec::new();
+    for field in fields.named.iter().filter(|f| !is_hidden(f)) {
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let field_vis = &field.vis;
+        let docs = field.attrs.iter().filter(|a| a.path().is_ident("doc"));
+        decls.push(quote! { #(#docs)* #field_vis #ident: #ty });
+        idents.push(ident);
+        properties.push(format!("\"{ident}\":{}", schema_of(ty)));
+        if generic_arg(ty, "Option").is_none() {
+            required.push(format!("\"{ident}\""));
+        }
+    }
+    let schema = format!(
+        r#"{{"type":"object","properties":{{{}}},"required":[{}]}}"#,
+        properties.join(","),
+        required.join(",")
+    );
+    let doc = format!("Response projection of [`{name}`] generated by `#[derive(ModelDto)]`.");
+    let dto_name = dto.to_string();
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, ::serde::Serialize)]
+        #vis struct #dto {
+            #(#decls,)*
+        }
+
+        impl ::core::convert::From<#name> for #dto {
+            fn from(model: #name) -> Self {
+                Self {
+                    #(#idents: model.#idents,)*
+                }
+            }
+        }
+
+        impl chopin_orm::ModelDto for #dto {
+            type Model = #name;
+            const NAME: &'static str = #dto_name;
+            const SCHEMA: &'static str = #schema;
+        }
+    })
+}
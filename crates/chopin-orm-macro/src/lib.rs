@@ -4,16 +4,100 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
 
+mod dto;
+mod naming;
+mod patch;
+mod pg_enum;
+
+/// Generates `{Name}Response`: the model's fields minus those marked
+/// `#[model(hidden)]`, with `Serialize`, `From<{Name}>` and a
+/// `chopin_orm::ModelDto` impl carrying its OpenAPI schema.
+#[proc_macro_derive(ModelDto, attributes(model))]
+pub fn derive_model_dto(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match dto::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Generates `{Name}Patch`: every field except keys, generated and hidden
+/// ones, wrapped in `Option` and deriving `Deserialize`, with a
+/// `chopin_orm::ModelPatch` impl that applies it to a model.
+#[proc_macro_derive(ModelPatch, attributes(model))]
+pub fn derive_model_patch(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match patch::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Implements `chopin_orm::PgEnum`, `ToSql`, `FromSql` and `ExtractValue`
+/// for a fieldless enum. Variants are stored as their snake_case name unless
+/// renamed with `#[model(rename = "...")]`; `#[model(type_name = "...")]` on
+/// the enum names the PostgreSQL enum type it maps to.
+#[proc_macro_derive(PgEnum, attributes(model))]
+pub fn derive_pg_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    match pg_enum::expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Column type of a `PgRange<T>` field, from its element type.
+fn range_sql_type(type_str: &str) -> Option<&'static str> {
+    let (head, elem) = type_str.strip_suffix('>')?.split_once('<')?;
+    if head.rsplit("::").next() != Some("PgRange") {
+        return None;
+    }
+    let last = elem.split('<').next().unwrap_or(elem);
+    let last = last.rsplit("::").next().unwrap_or(last);
+    Some(match last {
+        "i32" => "INT4RANGE",
+        "i64" => "INT8RANGE",
+        "Decimal" => "NUMRANGE",
+        "DateTime" | "OffsetDateTime" => "TSTZRANGE",
+        "NaiveDateTime" | "PrimitiveDateTime" => "TSRANGE",
+        "NaiveDate" | "Date" => "DATERANGE",
+        _ => return None,
+    })
+}
+
+/// Column type of a `chrono` or `time` field, matched on the type's last
+/// path segment so both `NaiveDate` and `chrono::NaiveDate` are recognised.
+fn temporal_sql_type(type_str: &str) -> Option<&'static str> {
+    let (head, generic) = type_str.split_once('<').unwrap_or((type_str, ""));
+    let last = head.rsplit("::").next().unwrap_or(head);
+    Some(match last {
+        "DateTime" if !generic.is_empty() => "TIMESTAMPTZ",
+        "OffsetDateTime" => "TIMESTAMPTZ",
+        "NaiveDateTime" | "PrimitiveDateTime" => "TIMESTAMP",
+        "NaiveDate" | "Date" => "DATE",
+        "NaiveTime" | "Time" => "TIME",
+        _ => return None,
+    })
+}
+
 #[proc_macro_derive(Model, attributes(model))]
 pub fn derive_model(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
 
-    let mut table_name = name.to_string().to_lowercase() + "s"; // Default plural table name
+    let mut naming = match naming::Naming::from_manifest() {
+        Ok(naming) => naming,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut naming_error = None;
+    let mut explicit_table_name = None;
     let mut pk_fields = Vec::new();
     let mut generated_fields = Vec::new();
     let mut columns = Vec::new();
     let mut has_many_rels = Vec::new(); // stores (related_model_ident, fk_column_name_str)
+    let mut connection: Option<String> = None;
+    let mut cache: Option<(u64, String)> = None; // (ttl seconds, format)
+    let mut columns_module = false;
 
     // Parse struct attributes for table_name
     for attr in &input.attrs {
@@ -22,7 +106,35 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
                 if meta.path.is_ident("table_name") {
                     let value = meta.value()?;
                     let s: LitStr = value.parse()?;
-                    table_name = s.value();
+                    explicit_table_name = Some(s.value());
+                }
+                match naming.parse_meta(&meta) {
+                    Ok(true) => return Ok(()),
+                    Ok(false) => {}
+                    Err(e) => naming_error = Some(e),
+                }
+                if meta.path.is_ident("columns_module") {
+                    columns_module = true;
+                }
+                if meta.path.is_ident("connection") {
+                    let value = meta.value()?;
+                    let s: LitStr = value.parse()?;
+                    connection = Some(s.value());
+                }
+                if meta.path.is_ident("cache") {
+                    let mut ttl = 60u64;
+                    let mut format = "wire".to_string();
+                    meta.parse_nested_meta(|inner| {
+                        if inner.path.is_ident("ttl") {
+                            let lit: syn::LitInt = inner.value()?.parse()?;
+                            ttl = lit.base10_parse()?;
+                        } else if inner.path.is_ident("format") {
+                            let s: LitStr = inner.value()?.parse()?;
+                            format = s.value();
+                        }
+                        Ok(())
+                    })?;
+                    cache = Some((ttl, format));
                 }
                 if meta.path.is_ident("has_many") {
                     let mut target_ident: Option<syn::Ident> = None;
@@ -48,10 +160,17 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
         }
     }
 
+    if let Some(e) = naming_error {
+        return e.to_compile_error().into();
+    }
+    let table_name = explicit_table_name.unwrap_or_else(|| naming.table_name(&name.to_string()));
+
     let mut field_types = Vec::new();
+    let mut sql_type_overrides: Vec<Option<String>> = Vec::new();
     let mut non_pk_fields = Vec::new();
     let mut non_pk_types = Vec::new();
     let mut belongs_to_fks = Vec::new(); // stores (field_ident, related_model_ident)
+    let mut unique_fields = Vec::new();
 
     let fields_list = if let Data::Struct(data_struct) = &input.data {
         if let Fields::Named(syn_fields) = &data_struct.fields {
@@ -71,6 +190,7 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
 
                 let mut is_pk = false;
                 let mut is_gen = false;
+                let mut sql_type_override = None;
                 // Check for primary_key attribute
                 for attr in &f.attrs {
                     if attr.path().is_ident("model") {
@@ -81,6 +201,13 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
                             if meta.path.is_ident("generated") {
                                 is_gen = true;
                             }
+                            if meta.path.is_ident("unique") {
+                                unique_fields.push(field_name.clone());
+                            }
+                            if meta.path.is_ident("as") {
+                                let lit: LitStr = meta.value()?.parse()?;
+                                sql_type_override = Some(lit.value());
+                            }
                             if meta.path.is_ident("belongs_to") {
                                 let _ = meta.parse_nested_meta(|inner| {
                                     if let Some(ident) = inner.path.get_ident() {
@@ -94,6 +221,8 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
                     }
                 }
 
+                sql_type_overrides.push(sql_type_override);
+
                 if is_pk {
                     pk_fields.push(field_name.clone());
                     let ty = &f.ty;
@@ -145,6 +274,40 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
 
     let column_enum_name =
         syn::Ident::new(&format!("{}Column", name), proc_macro2::Span::call_site());
+    // Typed column tokens: `Post::TITLE` and `post::columns::TITLE`.
+    let vis = &input.vis;
+    let columns_mod = syn::Ident::new(
+        &naming::snake_case(&name.to_string()),
+        proc_macro2::Span::call_site(),
+    );
+    let column_consts: Vec<syn::Ident> = fields_list
+        .iter()
+        .map(|f| {
+            let upper = f.to_string().trim_start_matches("r#").to_uppercase();
+            syn::Ident::new(&upper, f.span())
+        })
+        .collect();
+    // The module names the model through `super::super`, which cannot reach
+    // structs declared inside a function body, so it is opt-in.
+    let columns_mod_tokens = if columns_module {
+        let columns_doc = format!("Typed column tokens of [`{name}`].");
+        quote! {
+            #[allow(dead_code)]
+            #vis mod #columns_mod {
+                #[doc = #columns_doc]
+                pub mod columns {
+                    #[allow(unused_imports)]
+                    use super::super::*;
+                    #(
+                        pub const #column_consts: chopin_orm::builder::Column<#name, #field_types> =
+                            #name::#column_consts;
+                    )*
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
     let _active_model_name = syn::Ident::new(
         &format!("{}ActiveModel", name),
         proc_macro2::Span::call_site(),
@@ -177,17 +340,38 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
 
         let type_str = quote::quote!(#inner_ty).to_string().replace(" ", "");
 
-        let mut sql_type = match type_str.as_str() {
-            "i32" if is_gen && is_pk && pk_fields.len() == 1 => "SERIAL PRIMARY KEY".to_string(),
-            "i32" if is_gen => "SERIAL".to_string(),
-            "i32" => "INT".to_string(),
-            "i64" if is_gen && is_pk && pk_fields.len() == 1 => "BIGSERIAL PRIMARY KEY".to_string(),
-            "i64" if is_gen => "BIGSERIAL".to_string(),
-            "i64" => "BIGINT".to_string(),
-            "String" => "TEXT".to_string(),
-            "bool" => "BOOLEAN".to_string(),
-            "f64" => "DOUBLE PRECISION".to_string(),
-            _ => "TEXT".to_string(),
+        let mut sql_type = match sql_type_overrides[i].as_deref() {
+            Some(ty) if ty.eq_ignore_ascii_case("text") => "TEXT".to_string(),
+            Some(ty) => ty.to_string(),
+            None => match type_str.as_str() {
+                "i32" if is_gen && is_pk && pk_fields.len() == 1 => {
+                    "SERIAL PRIMARY KEY".to_string()
+                }
+                "i32" if is_gen => "SERIAL".to_string(),
+                "i32" => "INT".to_string(),
+                "i64" if is_gen && is_pk && pk_fields.len() == 1 => {
+                    "BIGSERIAL PRIMARY KEY".to_string()
+                }
+                "i64" if is_gen => "BIGSERIAL".to_string(),
+                "i64" => "BIGINT".to_string(),
+                "String" => "TEXT".to_string(),
+                "bool" => "BOOLEAN".to_string(),
+                "f64" => "DOUBLE PRECISION".to_string(),
+                "Vec<u8>" => "BYTEA".to_string(),
+                "Vec<i16>" => "SMALLINT[]".to_string(),
+                "Vec<i32>" => "INT[]".to_string(),
+                "Vec<i64>" => "BIGINT[]".to_string(),
+                "Vec<f32>" => "REAL[]".to_string(),
+                "Vec<f64>" => "DOUBLE PRECISION[]".to_string(),
+                "Vec<bool>" => "BOOLEAN[]".to_string(),
+                "Vec<String>" => "TEXT[]".to_string(),
+                "Vec<[u8;16]>" => "UUID[]".to_string(),
+                s if temporal_sql_type(s).is_some() => temporal_sql_type(s).unwrap().to_string(),
+                s if range_sql_type(s).is_some() => range_sql_type(s).unwrap().to_string(),
+                "serde_json::Value" => "JSONB".to_string(),
+                s if s.starts_with("Json<") || s.contains("::Json<") => "JSONB".to_string(),
+                _ => "TEXT".to_string(),
+            },
         };
 
         if is_pk && pk_fields.len() == 1 && !sql_type.contains("PRIMARY KEY") {
@@ -198,6 +382,10 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
             sql_type.push_str(" NOT NULL");
         }
 
+        if unique_fields.iter().any(|f| f == field_name) && !sql_type.contains("PRIMARY KEY") {
+            sql_type.push_str(" UNIQUE");
+        }
+
         col_names.push(field_name.clone());
         col_types.push(sql_type.clone());
         column_defs.push(format!("{} {}", field_name, sql_type));
@@ -214,6 +402,21 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
         column_defs.join(",\n    ")
     );
 
+    let unique_names: Vec<String> = unique_fields.iter().map(|f| f.to_string()).collect();
+    let find_by_names: Vec<_> = unique_fields
+        .iter()
+        .map(|f| quote::format_ident!("find_by_{}", f.to_string().trim_start_matches("r#")))
+        .collect();
+    let get_or_create_names: Vec<_> = unique_fields
+        .iter()
+        .map(|f| {
+            quote::format_ident!(
+                "get_or_create_by_{}",
+                f.to_string().trim_start_matches("r#")
+            )
+        })
+        .collect();
+
     let fk_fields: Vec<_> = belongs_to_fks.iter().map(|(f, _)| f.clone()).collect();
     let fk_models: Vec<_> = belongs_to_fks.iter().map(|(_, m)| m.clone()).collect();
 
@@ -240,12 +443,47 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
     let field_names_join = field_names_str.join(", ");
     let fields_indices: Vec<usize> = (0..columns.len()).collect();
 
+    let connection_fn = connection.map(|connection| {
+        quote! {
+            fn connection() -> &'static str {
+                #connection
+            }
+        }
+    });
+
+    let cache_fn = match cache {
+        Some((ttl, format)) => {
+            let format = match format.as_str() {
+                "wire" => quote! { chopin_orm::CacheFormat::Wire },
+                "text" => quote! { chopin_orm::CacheFormat::Text },
+                other => {
+                    let msg =
+                        format!("unknown cache format `{other}`; expected \"wire\" or \"text\"");
+                    return syn::Error::new_spanned(name, msg).to_compile_error().into();
+                }
+            };
+            Some(quote! {
+                fn cache_config() -> Option<chopin_orm::CacheConfig> {
+                    Some(chopin_orm::CacheConfig::new(
+                        std::time::Duration::from_secs(#ttl),
+                        #format,
+                    ))
+                }
+            })
+        }
+        None => None,
+    };
+
     let expanded = quote! {
         impl chopin_orm::Model for #name {
             fn table_name() -> &'static str {
                 #table_name
             }
 
+            #connection_fn
+
+            #cache_fn
+
             fn create_table_stmt() -> String {
                 let mut sql = String::from(#base_sql);
                 #(
@@ -320,6 +558,29 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
         }
 
         impl #name {
+            #(
+                /// Fetch the row with this value in its unique column.
+                pub fn #find_by_names(
+                    executor: &mut impl chopin_orm::Executor,
+                    value: impl chopin_pg::types::ToSql,
+                ) -> chopin_orm::OrmResult<Option<Self>> {
+                    use chopin_pg::types::ToParam;
+                    <Self as chopin_orm::Model>::find()
+                        .filter((format!("{} = $1", #unique_names), vec![value.to_param()]))
+                        .one(executor)
+                }
+
+                /// Fetch the row sharing `model`'s value in this unique column,
+                /// inserting `model` if there is none. The flag is `true` when
+                /// the row was created.
+                pub fn #get_or_create_names(
+                    executor: &mut impl chopin_orm::Executor,
+                    model: Self,
+                ) -> chopin_orm::OrmResult<(Self, bool)> {
+                    <Self as chopin_orm::Model>::get_or_create(executor, model, &[#unique_names])
+                }
+            )*
+
             #(
                 pub fn #fetch_bt_names(&self, executor: &mut impl chopin_orm::Executor) -> chopin_orm::OrmResult<Option<#fk_models>> {
                     use chopin_pg::types::ToSql;
@@ -352,6 +613,16 @@ pub fn derive_model(input: TokenStream) -> TokenStream {
             #(#fields_list),*
         }
 
+        #[allow(dead_code)]
+        impl #name {
+            #(
+                pub const #column_consts: chopin_orm::builder::Column<#name, #field_types> =
+                    chopin_orm::builder::Column::new(#field_names_str);
+            )*
+        }
+
+        #columns_mod_tokens
+
         impl chopin_orm::builder::ColumnTrait<#name> for #column_enum_name {
             fn column_name(&self) -> &'static str {
                 match self {
@@ -0,0 +1,237 @@
+//! Table naming strategies for `#[derive(Model)]`.
+//!
+//! Mirrors `chopin_orm::naming`, which the CLI and applications use at run
+//! time; keep the two in sync.
+use syn::{LitBool, LitStr};
+
+/// Words whose plural is not formed by a suffix rule.
+const IRREGULAR: &[(&str, &str)] = &[
+    ("person", "people"),
+    ("man", "men"),
+    ("woman", "women"),
+    ("child", "children"),
+    ("mouse", "mice"),
+    ("goose", "geese"),
+    ("foot", "feet"),
+    ("tooth", "teeth"),
+    ("ox", "oxen"),
+    ("leaf", "leaves"),
+    ("life", "lives"),
+    ("knife", "knives"),
+    ("wife", "wives"),
+    ("half", "halves"),
+    ("wolf", "wolves"),
+    ("shelf", "shelves"),
+    ("thief", "thieves"),
+    ("hero", "heroes"),
+    ("potato", "potatoes"),
+    ("tomato", "tomatoes"),
+    ("echo", "echoes"),
+    ("quiz", "quizzes"),
+    ("index", "indices"),
+    ("matrix", "matrices"),
+    ("vertex", "vertices"),
+    ("criterion", "criteria"),
+    ("phenomenon", "phenomena"),
+    ("datum", "data"),
+    ("medium", "media"),
+];
+
+/// Words with no distinct plural form.
+const UNCOUNTABLE: &[&str] = &[
+    "sheep",
+    "fish",
+    "deer",
+    "series",
+    "species",
+    "news",
+    "data",
+    "media",
+    "information",
+    "equipment",
+    "metadata",
+    "feedback",
+    "software",
+    "staff",
+];
+
+/// Plural of a single lowercase word.
+fn pluralize_word(word: &str) -> String {
+    if UNCOUNTABLE.contains(&word) || IRREGULAR.iter().any(|(_, p)| *p == word) {
+        return word.to_string();
+    }
+    if let Some((_, plural)) = IRREGULAR.iter().find(|(s, _)| *s == word) {
+        return plural.to_string();
+    }
+    let ends_in_consonant_y = word.len() > 1
+        && word.ends_with('y')
+        && !matches!(
+            word.as_bytes()[word.len() - 2],
+            b'a' | b'e' | b'i' | b'o' | b'u'
+        );
+    if ends_in_consonant_y {
+        return format!("{}ies", &word[..word.len() - 1]);
+    }
+    if let Some(stem) = word.strip_suffix("is") {
+        // analysis -> analyses, axis -> axes
+        return format!("{stem}es");
+    }
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|s| word.ends_with(s))
+    {
+        return format!("{word}es");
+    }
+    format!("{word}s")
+}
+
+/// Plural of a snake_case name; only the last word changes (`blog_category`
+/// -> `blog_categories`).
+pub(crate) fn pluralize(name: &str) -> String {
+    match name.rsplit_once('_') {
+        Some((head, last)) => format!("{head}_{}", pluralize_word(last)),
+        None => pluralize_word(name),
+    }
+}
+
+/// `BlogPost` -> `blog_post`, `HTTPRequest` -> `http_request`.
+pub(crate) fn snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut out = String::with_capacity(ident.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev_lower =
+                i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit());
+            let acronym_end = i > 0
+                && chars[i - 1].is_uppercase()
+                && chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev_lower || acronym_end {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// How a model's table name is derived when `table_name` is not given.
+///
+/// Set crate-wide under `[package.metadata.chopin-orm]` in `Cargo.toml` and
+/// per model with `#[model(...)]`, using the same keys:
+/// `table_naming = "legacy" | "snake_case" | "lowercase"`,
+/// `plural_tables = bool` and `table_prefix = "..."`.
+#[derive(Clone, Default)]
+pub(crate) struct Naming {
+    style: Style,
+    plural: Option<bool>,
+    prefix: String,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Style {
+    /// `BlogPost` -> `blogposts`: lowercased with an `s` appended, as the
+    /// derive has always done.
+    #[default]
+    Legacy,
+    SnakeCase,
+    Lowercase,
+}
+
+impl Style {
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "legacy" => Some(Style::Legacy),
+            "snake_case" => Some(Style::SnakeCase),
+            "lowercase" => Some(Style::Lowercase),
+            _ => None,
+        }
+    }
+}
+
+const STYLE_ERROR: &str = "table_naming must be \"legacy\", \"snake_case\" or \"lowercase\"";
+
+impl Naming {
+    /// Crate-wide settings from the deriving crate's `Cargo.toml`.
+    pub(crate) fn from_manifest() -> syn::Result<Self> {
+        let mut naming = Naming::default();
+        let Ok(dir) = std::env::var("CARGO_MANIFEST_DIR") else {
+            return Ok(naming);
+        };
+        let path = std::path::Path::new(&dir).join("Cargo.toml");
+        let Ok(text) = std::fs::read_to_string(&path) else {
+            return Ok(naming);
+        };
+        let err = |msg: String| syn::Error::new(proc_macro2::Span::call_site(), msg);
+        let manifest: toml::Table = text
+            .parse()
+            .map_err(|e| err(format!("{}: {e}", path.display())))?;
+        let Some(table) = manifest
+            .get("package")
+            .and_then(|p| p.get("metadata"))
+            .and_then(|m| m.get("chopin-orm"))
+            .and_then(|t| t.as_table())
+        else {
+            return Ok(naming);
+        };
+        let key_err = |key: &str, what: &str| {
+            err(format!(
+                "[package.metadata.chopin-orm] {key} must be {what}"
+            ))
+        };
+        if let Some(v) = table.get("table_naming") {
+            let style = v.as_str().and_then(Style::parse);
+            naming.style =
+                style.ok_or_else(|| err(format!("[package.metadata.chopin-orm] {STYLE_ERROR}")))?;
+        }
+        if let Some(v) = table.get("plural_tables") {
+            naming.plural = Some(
+                v.as_bool()
+                    .ok_or_else(|| key_err("plural_tables", "a boolean"))?,
+            );
+        }
+        if let Some(v) = table.get("table_prefix") {
+            naming.prefix = v
+                .as_str()
+                .ok_or_else(|| key_err("table_prefix", "a string"))?
+                .to_string();
+        }
+        Ok(naming)
+    }
+
+    /// Applies a struct-level naming key; `false` if `meta` is not one.
+    pub(crate) fn parse_meta(&mut self, meta: &syn::meta::ParseNestedMeta) -> syn::Result<bool> {
+        if meta.path.is_ident("table_naming") {
+            let lit: LitStr = meta.value()?.parse()?;
+            self.style = Style::parse(&lit.value())
+                .ok_or_else(|| syn::Error::new(lit.span(), STYLE_ERROR))?;
+        } else if meta.path.is_ident("plural_tables") {
+            let lit: LitBool = meta.value()?.parse()?;
+            self.plural = Some(lit.value);
+        } else if meta.path.is_ident("table_prefix") {
+            let lit: LitStr = meta.value()?.parse()?;
+            self.prefix = lit.value();
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    pub(crate) fn table_name(&self, ident: &str) -> String {
+        let plural = self.plural.unwrap_or(true);
+        let base = match self.style {
+            Style::Legacy => {
+                let lower = ident.to_lowercase();
+                return match plural {
+                    true => format!("{}{lower}s", self.prefix),
+                    false => format!("{}{lower}", self.prefix),
+                };
+            }
+            Style::SnakeCase => snake_case(ident),
+            Style::Lowercase => ident.to_lowercase(),
+        };
+        let base = if plural { pluralize(&base) } else { base };
+        format!("{}{base}", self.prefix)
+    }
+}
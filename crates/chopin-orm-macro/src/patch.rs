@@ -0,0 +1,119 @@
+//! `#[derive(ModelPatch)]`: a deserializable partial update of a model.
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields};
+
+use crate::dto::{generic_arg, is_hidden};
+
+/// `#[model(<flag>)]` is present on the field.
+fn has_flag(field: &syn::Field, flag: &str) -> bool {
+    let mut found = false;
+    for attr in &field.attrs {
+        if attr.path().is_ident("model") {
+            let _ = attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident(flag) {
+                    found = true;
+                } else if meta.input.peek(syn::Token![=]) {
+                    meta.value()?.parse::<syn::Expr>()?;
+                } else if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|_| Ok(()))?;
+                }
+                Ok(())
+            });
+        }
+    }
+    found
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let vis = &input.vis;
+    let patch = format_ident!("{}Patch", name);
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "ModelPatch can only be derived for structs with named fields",
+        ));
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "ModelPatch can only be derived for structs with named fields",
+        ));
+    };
+
+    // Same key rule as `#[derive(Model)]`: marked fields, else `id`.
+    let any_pk = fields.named.iter().any(|f| has_flag(f, "primary_key"));
+    let is_key = |f: &syn::Field| match any_pk {
+        true => has_flag(f, "primary_key"),
+        false => f.ident.as_ref().is_some_and(|i| i == "id"),
+    };
+
+    let nullable_fn = format_ident!(
+        "__{}_patch_nullable",
+        crate::naming::snake_case(&name.to_string())
+    );
+    let mut decls = Vec::new();
+    let mut idents = Vec::new();
+    let mut columns = Vec::new();
+    for field in fields.named.iter() {
+        if is_key(field) || is_hidden(field) || has_flag(field, "generated") {
+            continue;
+        }
+        let ident = field.ident.as_ref().expect("named field");
+        let ty = &field.ty;
+        let field_vis = &field.vis;
+        let docs = field.attrs.iter().filter(|a| a.path().is_ident("doc"));
+        // `null` clears a nullable column; a missing key leaves it alone.
+        let serde_attr = match generic_arg(ty, "Option") {
+            Some(_) => {
+                let path = nullable_fn.to_string();
+                quote! { #[serde(default, deserialize_with = #path)] }
+            }
+            None => quote! { #[serde(default)] },
+        };
+        decls.push(quote! { #(#docs)* #serde_attr #field_vis #ident: ::core::option::Option<#ty> });
+        idents.push(ident);
+        columns.push(ident.to_string());
+    }
+    let doc = format!("Partial update of [`{name}`] generated by `#[derive(ModelPatch)]`.");
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(Debug, Clone, Default, ::serde::Deserialize)]
+        #[serde(deny_unknown_fields)]
+        #vis struct #patch {
+            #(#decls,)*
+        }
+
+        #[doc(hidden)]
+        #[allow(non_snake_case, dead_code)]
+        fn #nullable_fn<'de, D, T>(
+            deserializer: D,
+        ) -> ::core::result::Result<::core::option::Option<T>, D::Error>
+        where
+            D: ::serde::Deserializer<'de>,
+            T: ::serde::Deserialize<'de>,
+        {
+            <T as ::serde::Deserialize<'de>>::deserialize(deserializer).map(::core::option::Option::Some)
+        }
+
+        impl chopin_orm::ModelPatch for #patch {
+            type Model = #name;
+
+            fn apply_to(self, model: &mut #name) -> ::std::vec::Vec<&'static str> {
+                use chopin_pg::types::ToSql;
+                let mut changed = ::std::vec::Vec::new();
+                #(
+                    if let ::core::option::Option::Some(value) = self.#idents {
+                        if value.to_sql() != model.#idents.to_sql() {
+                            changed.push(#columns);
+                        }
+                        model.#idents = value;
+                    }
+                )*
+                changed
+            }
+        }
+    })
+}
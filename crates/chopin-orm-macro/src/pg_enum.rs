@@ -0,0 +1,126 @@
+//! `#[derive(PgEnum)]`: fieldless enums stored as text or a PostgreSQL enum.
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr};
+
+/// `InReview` -> `in_review`.
+fn snake_case(ident: &str) -> String {
+    let mut out = String::with_capacity(ident.len() + 4);
+    for (i, c) in ident.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+pub(crate) fn expand(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let name = &input.ident;
+    let Data::Enum(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            name,
+            "PgEnum can only be derived for enums",
+        ));
+    };
+
+    let mut type_name: Option<String> = None;
+    for attr in &input.attrs {
+        if attr.path().is_ident("model") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("type_name") {
+                    type_name = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `type_name = \"...\"`"))
+                }
+            })?;
+        }
+    }
+
+    let mut variants = Vec::new();
+    let mut labels = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(
+                variant,
+                "PgEnum variants cannot carry fields",
+            ));
+        }
+        let mut label = snake_case(&variant.ident.to_string());
+        for attr in &variant.attrs {
+            if attr.path().is_ident("model") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        label = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `rename = \"...\"`"))
+                    }
+                })?;
+            }
+        }
+        variants.push(&variant.ident);
+        labels.push(label);
+    }
+    if variants.is_empty() {
+        return Err(syn::Error::new_spanned(
+            name,
+            "PgEnum needs at least one variant",
+        ));
+    }
+
+    let type_name = match type_name {
+        Some(ty) => quote! { Some(#ty) },
+        None => quote! { None },
+    };
+    let error = format!("Invalid {name} value: {{}}");
+
+    Ok(quote! {
+        impl chopin_orm::PgEnum for #name {
+            const TYPE_NAME: Option<&'static str> = #type_name;
+            const LABELS: &'static [&'static str] = &[#(#labels),*];
+
+            fn label(&self) -> &'static str {
+                match self {
+                    #(Self::#variants => #labels,)*
+                }
+            }
+
+            fn from_label(label: &str) -> Option<Self> {
+                match label {
+                    #(#labels => Some(Self::#variants),)*
+                    _ => None,
+                }
+            }
+        }
+
+        impl chopin_pg::types::ToSql for #name {
+            fn to_sql(&self) -> chopin_pg::PgValue {
+                chopin_pg::PgValue::Text(chopin_orm::PgEnum::label(self).to_string())
+            }
+        }
+
+        impl chopin_pg::types::FromSql for #name {
+            fn from_sql(value: &chopin_pg::PgValue) -> chopin_pg::PgResult<Self> {
+                match value {
+                    chopin_pg::PgValue::Text(s) => <Self as chopin_orm::PgEnum>::from_label(s)
+                        .ok_or_else(|| chopin_pg::PgError::TypeConversion(format!(#error, s))),
+                    _ => Err(chopin_pg::PgError::TypeConversion(
+                        concat!("Cannot convert to ", stringify!(#name)).into(),
+                    )),
+                }
+            }
+        }
+
+        impl chopin_orm::ExtractValue for #name {
+            fn from_pg_value(val: chopin_pg::PgValue) -> chopin_orm::OrmResult<Self> {
+                <Self as chopin_pg::types::FromSql>::from_sql(&val)
+                    .map_err(|e| chopin_orm::OrmError::Extraction(e.to_string()))
+            }
+        }
+    })
+}
@@ -1,3 +1,4 @@
+use crate::metrics::{self, Op};
 use crate::{Executor, Model, OrmError, OrmResult, PgValue};
 
 /// State wrapper for a model field, tracking whether it has been modified.
@@ -136,7 +137,9 @@ impl<M: Model> ActiveModel<M> {
         );
 
         let params: Vec<&dyn chopin_pg::types::ToSql> = vals.iter().map(|v| v as _).collect();
-        let rows = executor.query(&query, &params)?;
+        let rows = metrics::observe(M::table_name(), Op::Insert, || {
+            executor.query(&query, &params)
+        })?;
 
         if let Some(row) = rows.first() {
             self.inner = M::from_row(row)?;
@@ -190,7 +193,9 @@ impl<M: Model> ActiveModel<M> {
 
         let params: Vec<&dyn chopin_pg::types::ToSql> =
             query_values.iter().map(|v| v as _).collect();
-        let rows = executor.query(&query, &params)?;
+        let rows = metrics::observe(M::table_name(), Op::Update, || {
+            executor.query(&query, &params)
+        })?;
 
         if let Some(row) = rows.first() {
             self.inner = M::from_row(row)?;
@@ -1,3 +1,4 @@
+use crate::metrics::{self, Op};
 use crate::{Model, OrmError, OrmResult, PgValue};
 use std::marker::PhantomData;
 
@@ -219,6 +220,50 @@ pub trait ColumnTrait<M: Model> {
     }
 }
 
+/// A column of model `M` holding values of type `T`.
+///
+/// `#[derive(Model)]` emits one per field, both as an associated constant
+/// (`Post::TITLE`) and in a `post::columns` module, so a renamed or removed
+/// field is a compile error at every query that uses it.
+pub struct Column<M, T> {
+    name: &'static str,
+    _marker: PhantomData<fn() -> (M, T)>,
+}
+
+impl<M, T> Column<M, T> {
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            _marker: PhantomData,
+        }
+    }
+
+    /// The SQL column name.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+impl<M, T> Clone for Column<M, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M, T> Copy for Column<M, T> {}
+
+impl<M, T> std::fmt::Debug for Column<M, T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Column").field(&self.name).finish()
+    }
+}
+
+impl<M: Model, T> ColumnTrait<M> for Column<M, T> {
+    fn column_name(&self) -> &'static str {
+        self.name
+    }
+}
+
 /// A type-safe SQL query builder.
 ///
 /// Constructed primarily via `<Model>::find()` and `<Model>::select(...)`.
@@ -433,7 +478,9 @@ impl<M: Model + Send + Sync> QueryBuilder<M> {
         log::debug!("into_raw: {} | params: {}", query, all_params.len());
         let params_ref: Vec<&dyn chopin_pg::types::ToSql> =
             all_params.iter().map(|p| *p as _).collect();
-        executor.query(&query, &params_ref)
+        metrics::observe(M::table_name(), Op::Select, || {
+            executor.query(&query, &params_ref)
+        })
     }
 
     /// Executes the query and returns a list of models.
@@ -443,7 +490,9 @@ impl<M: Model + Send + Sync> QueryBuilder<M> {
         let params_ref: Vec<&dyn chopin_pg::types::ToSql> =
             all_params.iter().map(|p| *p as _).collect();
 
-        let rows = executor.query(&query, &params_ref)?;
+        let rows = metrics::observe(M::table_name(), Op::Select, || {
+            executor.query(&query, &params_ref)
+        })?;
 
         let mut result = Vec::with_capacity(rows.len());
         for row in rows {
@@ -472,7 +521,9 @@ impl<M: Model + Send + Sync> QueryBuilder<M> {
         let params_ref: Vec<&dyn chopin_pg::types::ToSql> =
             all_params.iter().map(|p| *p as _).collect();
 
-        let rows = executor.query(&query, &params_ref)?;
+        let rows = metrics::observe(M::table_name(), Op::Select, || {
+            executor.query(&query, &params_ref)
+        })?;
         if let Some(row) = rows.first() {
             let val: PgValue = row.get(0).map_err(OrmError::from)?;
             return Ok(match val {
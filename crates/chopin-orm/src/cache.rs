@@ -0,0 +1,389 @@
+//! Read-through model cache.
+//!
+//! Models opt in per type and are then fetched by primary key through
+//! [`Model::find_cached`](crate::Model::find_cached):
+//!
+//! ```rust,ignore
+//! #[derive(Model)]
+//! #[model(table_name = "users", cache(ttl = 300))]
+//! struct User { id: i64, name: String }
+//!
+//! let user = User::find_cached(&mut db, &[&7i64])?;
+//! ```
+//!
+//! Entries are dropped whenever `update`, `update_columns`, `upsert` or
+//! `delete` succeeds for the same primary key, so a cached read never
+//! outlives a write made through the ORM. Writes made with raw SQL are only
+//! bounded by the TTL.
+//!
+//! Entries live in the process-wide [`CacheService`]: an in-memory
+//! [`MemoryCache`] unless another backend was installed with
+//! [`set_cache_service`].
+use crate::metrics::{self, Op};
+use crate::{Executor, Model, OrmError, OrmResult, stats};
+use chopin_pg::Row;
+use chopin_pg::codec::ColumnDesc;
+use chopin_pg::protocol::FormatCode;
+use chopin_pg::types::{PgValue, ToSql};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// Byte-oriented key/value store with per-entry expiry.
+pub trait CacheService: Send + Sync {
+    fn get(&self, key: &str) -> Option<Vec<u8>>;
+    fn set(&self, key: &str, value: Vec<u8>, ttl: Duration);
+    fn delete(&self, key: &str);
+    /// Remove every entry whose key starts with `prefix`.
+    fn delete_prefix(&self, prefix: &str);
+}
+
+/// Process-local [`CacheService`]. Expired entries are dropped when read or
+/// by [`purge_expired`](Self::purge_expired).
+#[derive(Default)]
+pub struct MemoryCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<u8>)>>,
+}
+
+impl MemoryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|e| e.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Remove expired entries. Returns the number removed.
+    pub fn purge_expired(&self) -> usize {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let before = entries.len();
+        let now = Instant::now();
+        entries.retain(|_, (expires, _)| *expires > now);
+        before - entries.len()
+    }
+}
+
+impl CacheService for MemoryCache {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        match entries.get(key) {
+            Some((expires, value)) if *expires > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn set(&self, key: &str, value: Vec<u8>, ttl: Duration) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.insert(key.to_string(), (Instant::now() + ttl, value));
+    }
+
+    fn delete(&self, key: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.remove(key);
+    }
+
+    fn delete_prefix(&self, prefix: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.retain(|key, _| !key.starts_with(prefix));
+    }
+}
+
+static SERVICE: RwLock<Option<Arc<dyn CacheService>>> = RwLock::new(None);
+static DEFAULT_SERVICE: OnceLock<Arc<dyn CacheService>> = OnceLock::new();
+
+/// Replace the process-wide cache backend.
+pub fn set_cache_service(service: impl CacheService + 'static) {
+    let mut slot = SERVICE.write().unwrap_or_else(|e| e.into_inner());
+    *slot = Some(Arc::new(service));
+}
+
+/// The installed cache backend, or a shared [`MemoryCache`].
+pub fn cache_service() -> Arc<dyn CacheService> {
+    if let Some(service) = SERVICE.read().ok().and_then(|s| s.clone()) {
+        return service;
+    }
+    DEFAULT_SERVICE
+        .get_or_init(|| Arc::new(MemoryCache::new()))
+        .clone()
+}
+
+/// How cached rows are encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheFormat {
+    /// Column bytes exactly as the server sent them. Cheapest to store and
+    /// decode; binary columns are only readable by chopin.
+    Wire,
+    /// Every column re-encoded in Postgres text format, so the entry can be
+    /// inspected in the backing store.
+    Text,
+}
+
+/// Per-model cache settings, declared with `#[model(cache(...))]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheConfig {
+    pub ttl: Duration,
+    pub format: CacheFormat,
+}
+
+impl CacheConfig {
+    pub const fn new(ttl: Duration, format: CacheFormat) -> Self {
+        Self { ttl, format }
+    }
+}
+
+/// Cache key for the row of `M` with primary key `pk`.
+pub fn cache_key<M: Model>(pk: &[PgValue]) -> String {
+    let mut key = format!("chopin:{}:{}", M::connection(), M::table_name());
+    for value in pk {
+        key.push(':');
+        match value.to_text_bytes() {
+            Some(bytes) => key.push_str(&String::from_utf8_lossy(&bytes)),
+            None => key.push_str("\\N"),
+        }
+    }
+    key
+}
+
+/// Drop the cached row of `M` with primary key `pk`. A no-op for models
+/// without a cache configuration.
+pub fn invalidate<M: Model>(pk: &[PgValue]) {
+    if M::cache_config().is_some() {
+        cache_service().delete(&cache_key::<M>(pk));
+    }
+}
+
+/// Backs [`Model::find_cached`](crate::Model::find_cached).
+pub(crate) fn find_cached<M: Model>(
+    executor: &mut impl Executor,
+    pk: &[&dyn ToSql],
+) -> OrmResult<Option<M>> {
+    let pk_cols = M::primary_key_columns();
+    if pk_cols.is_empty() || pk.len() != pk_cols.len() {
+        return Err(OrmError::ModelError(format!(
+            "{} has {} primary key column(s), got {} value(s)",
+            M::table_name(),
+            pk_cols.len(),
+            pk.len()
+        )));
+    }
+
+    let Some(config) = M::cache_config() else {
+        return fetch::<M>(executor, pk)?
+            .map(|row| M::from_row(&row))
+            .transpose();
+    };
+
+    let service = cache_service();
+    let key = cache_key::<M>(&pk.iter().map(|v| v.to_sql()).collect::<Vec<_>>());
+    if let Some(bytes) = service.get(&key) {
+        // A corrupt or stale-shaped entry is treated as a miss.
+        match decode_row(&bytes).and_then(|row| M::from_row(&row)) {
+            Ok(model) => {
+                stats::record_cache(true);
+                return Ok(Some(model));
+            }
+            Err(_) => service.delete(&key),
+        }
+    }
+    stats::record_cache(false);
+
+    let Some(row) = fetch::<M>(executor, pk)? else {
+        return Ok(None);
+    };
+    let model = M::from_row(&row)?;
+    service.set(&key, encode_row(&row, config.format)?, config.ttl);
+    Ok(Some(model))
+}
+
+fn fetch<M: Model>(executor: &mut impl Executor, pk: &[&dyn ToSql]) -> OrmResult<Option<Row>> {
+    let where_clauses: Vec<String> = (1..)
+        .zip(M::primary_key_columns())
+        .map(|(i, col)| format!("{col} = ${i}"))
+        .collect();
+    let query = format!(
+        "SELECT {} FROM {} WHERE {} LIMIT 1",
+        M::select_clause(),
+        M::table_name(),
+        where_clauses.join(" AND ")
+    );
+    let rows = metrics::observe(M::table_name(), Op::Select, || executor.query(&query, pk))?;
+    Ok(rows.into_iter().next())
+}
+
+// ─── Encoding ────────────────────────────────────────────────────────────────
+//
+// version u8, column count u16, then per column: name length u16, name,
+// type oid u32, format u8, value length i32 (-1 for NULL), value bytes.
+// Integers are big-endian.
+
+const ENCODING_VERSION: u8 = 1;
+
+pub(crate) fn encode_row(row: &Row, format: CacheFormat) -> OrmResult<Vec<u8>> {
+    let mut out = vec![ENCODING_VERSION];
+    out.extend_from_slice(&(row.len() as u16).to_be_bytes());
+    for (i, col) in row.columns().iter().enumerate() {
+        let (format_code, value) = match format {
+            CacheFormat::Wire => (col.format_code, row.raw(i).map(<[u8]>::to_vec)),
+            CacheFormat::Text => (FormatCode::Text, row.get::<PgValue>(i)?.to_text_bytes()),
+        };
+        out.extend_from_slice(&(col.name.len() as u16).to_be_bytes());
+        out.extend_from_slice(col.name.as_bytes());
+        out.extend_from_slice(&col.type_oid.to_be_bytes());
+        out.push(format_code as u8);
+        match value {
+            Some(bytes) => {
+                out.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+                out.extend_from_slice(&bytes);
+            }
+            None => out.extend_from_slice(&(-1i32).to_be_bytes()),
+        }
+    }
+    Ok(out)
+}
+
+pub(crate) fn decode_row(bytes: &[u8]) -> OrmResult<Row> {
+    let corrupt = || OrmError::ModelError("corrupt cache entry".into());
+    let mut rest = bytes;
+    let mut take = |n: usize| -> OrmResult<&[u8]> {
+        if rest.len() < n {
+            return Err(corrupt());
+        }
+        let (head, tail) = rest.split_at(n);
+        rest = tail;
+        Ok(head)
+    };
+
+    if take(1)? != [ENCODING_VERSION] {
+        return Err(corrupt());
+    }
+    let count = u16::from_be_bytes(take(2)?.try_into().map_err(|_| corrupt())?);
+    let mut columns = Vec::with_capacity(count as usize);
+    let mut values = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let name_len = u16::from_be_bytes(take(2)?.try_into().map_err(|_| corrupt())?);
+        let name = std::str::from_utf8(take(name_len as usize)?)
+            .map_err(|_| corrupt())?
+            .to_string();
+        let type_oid = u32::from_be_bytes(take(4)?.try_into().map_err(|_| corrupt())?);
+        let format_code = match take(1)?[0] {
+            0 => FormatCode::Text,
+            1 => FormatCode::Binary,
+            _ => return Err(corrupt()),
+        };
+        let len = i32::from_be_bytes(take(4)?.try_into().map_err(|_| corrupt())?);
+        values.push(if len < 0 {
+            None
+        } else {
+            Some(take(len as usize)?)
+        });
+        columns.push(ColumnDesc {
+            name,
+            table_oid: 0,
+            col_attr: 0,
+            type_oid,
+            type_size: -1,
+            type_modifier: -1,
+            format_code,
+        });
+    }
+    Ok(Row::new(Rc::new(columns), values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate as chopin_orm;
+    use crate::{MockExecutor, mock_row};
+
+    #[derive(crate::Model, Debug, Clone)]
+    #[model(table_name = "cached_users", cache(ttl = 300, format = "text"))]
+    pub struct CachedUser {
+        #[model(primary_key)]
+        pub id: i64,
+        pub name: String,
+    }
+    impl crate::Validate for CachedUser {}
+
+    #[test]
+    fn test_find_cached_reads_through_and_invalidates() {
+        assert_eq!(
+            CachedUser::cache_config(),
+            Some(CacheConfig::new(
+                Duration::from_secs(300),
+                CacheFormat::Text
+            ))
+        );
+        let mut db = MockExecutor::new();
+        db.push_result(vec![mock_row!("id" => 41i64, "name" => "Ada")]);
+
+        let user = CachedUser::find_cached(&mut db, &[&41i64])
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.name, "Ada");
+        // Served from the cache: no second query.
+        let again = CachedUser::find_cached(&mut db, &[&41i64])
+            .unwrap()
+            .unwrap();
+        assert_eq!(again.name, "Ada");
+        assert_eq!(db.executed_queries.len(), 1);
+
+        user.update(&mut db).unwrap();
+        db.push_result(vec![mock_row!("id" => 41i64, "name" => "Grace")]);
+        let fresh = CachedUser::find_cached(&mut db, &[&41i64])
+            .unwrap()
+            .unwrap();
+        assert_eq!(fresh.name, "Grace");
+        assert_eq!(db.executed_queries.len(), 3);
+
+        assert!(CachedUser::find_cached(&mut db, &[]).is_err());
+    }
+
+    #[test]
+    fn test_row_round_trip() {
+        let row = Row::mock(
+            &["id", "name", "bio"],
+            &[PgValue::Int8(7), PgValue::Text("Ada".into()), PgValue::Null],
+        );
+        for format in [CacheFormat::Wire, CacheFormat::Text] {
+            let decoded = decode_row(&encode_row(&row, format).unwrap()).unwrap();
+            assert_eq!(decoded.get_typed_by_name::<i64>("id").unwrap(), 7);
+            assert_eq!(
+                decoded.get_by_name::<PgValue>("name").unwrap(),
+                PgValue::Text("Ada".into())
+            );
+            assert_eq!(
+                decoded.get_by_name::<PgValue>("bio").unwrap(),
+                PgValue::Null
+            );
+        }
+        let bytes = encode_row(&row, CacheFormat::Wire).unwrap();
+        assert!(decode_row(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_memory_cache_expiry() {
+        let cache = MemoryCache::new();
+        cache.set("a", b"1".to_vec(), Duration::from_secs(60));
+        cache.set("b", b"2".to_vec(), Duration::ZERO);
+        assert_eq!(cache.get("a"), Some(b"1".to_vec()));
+        assert_eq!(cache.get("b"), None);
+        cache.delete("a");
+        assert!(cache.is_empty());
+
+        cache.set("user:1", b"x".to_vec(), Duration::from_secs(60));
+        cache.set("user:2", b"y".to_vec(), Duration::from_secs(60));
+        cache.set("post:1", b"z".to_vec(), Duration::from_secs(60));
+        cache.delete_prefix("user:");
+        assert_eq!(cache.len(), 1);
+    }
+}
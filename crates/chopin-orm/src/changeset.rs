@@ -0,0 +1,91 @@
+//! Applying partial input to a model, generated by `#[derive(ModelPatch)]`.
+//!
+//! ```rust,ignore
+//! #[derive(Model, ModelPatch, Clone)]
+//! pub struct Post {
+//!     pub id: i64,
+//!     pub title: String,
+//!     pub summary: Option<String>,
+//! }
+//!
+//! // Generated: `pub struct PostPatch { pub title: Option<String>,
+//! // pub summary: Option<Option<String>> }`, deriving `Deserialize`
+//! // (the crate needs a `serde` dependency). A missing key is left alone;
+//! // `"summary": null` clears the column.
+//! let post = Post::find().filter(Post::ID.eq(id)).one(&mut exec)?.unwrap();
+//! let saved = Changeset::new(post).apply(patch).save(&mut exec)?;
+//! ```
+//!
+//! Keys, `#[model(generated)]` and `#[model(hidden)]` fields are left out of
+//! the patch, so request bodies cannot touch them.
+use crate::{Executor, Model, OrmResult};
+
+/// Implemented for every generated `{Model}Patch` struct.
+pub trait ModelPatch {
+    /// The model this updates.
+    type Model: Model;
+
+    /// Write the present fields into `model`, returning the columns whose
+    /// value actually changed.
+    fn apply_to(self, model: &mut Self::Model) -> Vec<&'static str>;
+}
+
+/// A model plus the columns changed since it was loaded.
+#[derive(Debug, Clone)]
+pub struct Changeset<M: Model> {
+    model: M,
+    changed: Vec<&'static str>,
+}
+
+impl<M: Model> Changeset<M> {
+    pub fn new(model: M) -> Self {
+        Self {
+            model,
+            changed: Vec::new(),
+        }
+    }
+
+    /// Apply a patch; may be called repeatedly.
+    pub fn apply<P: ModelPatch<Model = M>>(mut self, patch: P) -> Self {
+        for column in patch.apply_to(&mut self.model) {
+            if !self.changed.contains(&column) {
+                self.changed.push(column);
+            }
+        }
+        self
+    }
+
+    pub fn has_changes(&self) -> bool {
+        !self.changed.is_empty()
+    }
+
+    /// Columns whose value some patch changed, in first-change order.
+    pub fn changed_columns(&self) -> &[&'static str] {
+        &self.changed
+    }
+
+    /// The model with all patches applied.
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    pub fn into_model(self) -> M {
+        self.model
+    }
+
+    /// Run the model's [`Validate`](crate::Validate) rules.
+    pub fn validate(&self) -> OrmResult<()> {
+        self.model.validate_or_err()
+    }
+
+    /// Validate and `UPDATE` only the changed columns, returning the row as
+    /// stored. Without changes nothing is sent and the model is returned
+    /// as is.
+    pub fn save(self, executor: &mut impl Executor) -> OrmResult<M> {
+        self.validate()?;
+        if self.changed.is_empty() {
+            return Ok(self.model);
+        }
+        self.model.update_columns(executor, &self.changed)
+    }
+}
@@ -0,0 +1,29 @@
+//! Response projections of models, generated by `#[derive(ModelDto)]`.
+//!
+//! ```rust,ignore
+//! #[derive(Model, ModelDto)]
+//! pub struct User {
+//!     pub id: i64,
+//!     pub email: String,
+//!     #[model(hidden)]
+//!     pub password_hash: String,
+//! }
+//!
+//! // Generated: `pub struct UserResponse { pub id: i64, pub email: String }`,
+//! // deriving `Serialize` (the crate needs a `serde` dependency).
+//! let body: UserResponse = user.into();
+//! ```
+//!
+//! Field docs are carried over; other field attributes are not.
+
+/// Implemented for every generated `{Model}Response` struct.
+pub trait ModelDto: From<Self::Model> {
+    /// The model this projects.
+    type Model;
+    /// Name of the struct, for use as an OpenAPI component key.
+    const NAME: &'static str;
+    /// OpenAPI 3.0 schema object of the struct, as JSON.
+    ///
+    /// Types the derive does not recognise are described as `{}` (any value).
+    const SCHEMA: &'static str;
+}
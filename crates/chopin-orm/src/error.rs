@@ -34,6 +34,41 @@ impl std::fmt::Display for OrmError {
     }
 }
 
+impl OrmError {
+    /// The driver error, if this came from the database.
+    pub fn pg(&self) -> Option<&PgError> {
+        match self {
+            OrmError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+
+    /// A unique index or primary key already holds the value.
+    pub fn is_unique_violation(&self) -> bool {
+        self.pg().is_some_and(PgError::is_unique_violation)
+    }
+
+    /// The write broke an integrity constraint (SQLSTATE class 23).
+    pub fn is_constraint_violation(&self) -> bool {
+        self.pg().is_some_and(PgError::is_constraint_violation)
+    }
+
+    /// The write clashes with existing rows — a unique, exclusion or foreign
+    /// key violation — and is usually answered with `409 Conflict`. `NOT NULL`
+    /// and `CHECK` violations describe bad input instead, so they are not
+    /// conflicts.
+    pub fn is_conflict(&self) -> bool {
+        self.pg().is_some_and(|e| {
+            e.is_unique_violation() || e.is_exclusion_violation() || e.is_foreign_key_violation()
+        })
+    }
+
+    /// Name of the violated constraint, if the server sent one.
+    pub fn constraint(&self) -> Option<&str> {
+        self.pg()?.constraint()
+    }
+}
+
 impl std::error::Error for OrmError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
@@ -149,6 +184,38 @@ mod tests {
         assert!(matches!(orm_err, OrmError::ModelError(_)));
     }
 
+    // ─── Constraint Helpers ─────────────────────────────────────────────────
+
+    fn violation(code: &str, constraint: &str) -> OrmError {
+        OrmError::Database(PgError::from_fields(&[
+            (b'S', "ERROR".to_string()),
+            (b'C', code.to_string()),
+            (b'M', "violation".to_string()),
+            (b'n', constraint.to_string()),
+        ]))
+    }
+
+    #[test]
+    fn test_conflicts() {
+        let unique = violation("23505", "users_email_key");
+        assert!(unique.is_unique_violation());
+        assert!(unique.is_conflict());
+        assert_eq!(unique.constraint(), Some("users_email_key"));
+        assert!(violation("23503", "posts_author_fkey").is_conflict());
+        assert!(violation("23P01", "bookings_room_excl").is_conflict());
+    }
+
+    #[test]
+    fn test_input_violations_are_not_conflicts() {
+        let check = violation("23514", "prices_positive");
+        assert!(check.is_constraint_violation());
+        assert!(!check.is_conflict());
+        assert!(!violation("23502", "").is_conflict());
+        assert!(!OrmError::RecordNotFound.is_constraint_violation());
+        assert!(OrmError::RecordNotFound.constraint().is_none());
+        assert!(OrmError::Validation(vec![]).pg().is_none());
+    }
+
     // ─── Debug ───────────────────────────────────────────────────────────────
 
     #[test]
@@ -3,26 +3,53 @@
 //! An easy-to-use Object-Relational Mapper (ORM) for `chopin2`, backed by the high-performance
 //! `chopin-pg` synchronous PostgreSQL driver.
 
-pub use chopin_orm_macro::Model;
+pub use chopin_orm_macro::{Model, ModelDto, ModelPatch, PgEnum};
+#[cfg(feature = "json")]
+pub use chopin_pg::Json;
 pub use chopin_pg::{
-    PgResult, Row, connection::PgConnection, error::PgError, pool::PgPool, types::PgValue,
+    PgResult, Row,
+    connection::{CopyWriter, PgConfig, PgConnection},
+    error::PgError,
+    pool::{PerCorePool, PgPool},
+    types::PgRange,
+    types::PgValue,
     types::ToSql,
 };
 
+/// Name of the connection models use unless declared with
+/// `#[model(connection = "...")]`.
+pub const DEFAULT_CONNECTION: &str = "default";
+
 pub mod builder;
-pub use builder::{Condition, QueryBuilder};
+pub use builder::{Column, Condition, QueryBuilder};
+pub mod cache;
+pub use cache::{CacheConfig, CacheFormat, CacheService, MemoryCache};
+pub mod changeset;
+pub use changeset::{Changeset, ModelPatch};
+pub mod dto;
+pub use dto::ModelDto;
 pub mod error;
 pub use error::{OrmError, OrmResult};
 pub mod active_model;
 pub use active_model::ActiveModel;
+pub mod metrics;
 pub mod migrations;
-pub use migrations::{Index, Migration, MigrationManager, MigrationStatus};
+pub use migrations::{Index, Migration, MigrationManager, MigrationStatus, ModuleMigrations};
 pub mod mock;
 pub use mock::MockExecutor;
+pub mod naming;
+pub mod outbox;
+pub use outbox::{Outbox, OutboxMessage, OutboxRelay};
+pub mod pg_enum;
+pub use pg_enum::PgEnum;
+pub mod privacy;
+pub use privacy::{PrivacyRegistry, UserData};
+pub mod stats;
+pub use stats::QueryStats;
 
 /// A trait for types that can execute SQL queries and return results.
 ///
-/// Implemented by `PgPool`, `PgConnection`, and `Transaction`.
+/// Implemented by `PgPool`, `PerCorePool`, `PgConnection`, and `Transaction`.
 pub trait Executor {
     /// Executes a command (e.g., INSERT, UPDATE, DELETE) and returns the number of affected rows.
     fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64>;
@@ -33,14 +60,65 @@ pub trait Executor {
         query: &str,
         params: &[&dyn chopin_pg::types::ToSql],
     ) -> OrmResult<Vec<Row>>;
+
+    /// Executes a query and hands its rows to `on_batch` up to `batch_size`
+    /// at a time, stopping early once `on_batch` returns `false`.
+    ///
+    /// The default runs [`query`](Self::query) and splits the result.
+    /// Postgres executors read through a portal instead, so only one batch
+    /// is held in memory.
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        let mut rows = self.query(query, params)?.into_iter().peekable();
+        while rows.peek().is_some() {
+            let batch = rows.by_ref().take(batch_size.max(1)).collect();
+            if !on_batch(batch)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lets `&mut dyn Executor` (as handed out by `chopin_core::db::with_db`)
+/// be passed where `&mut impl Executor` is expected.
+impl<E: Executor + ?Sized> Executor for &mut E {
+    fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
+        (**self).execute(query, params)
+    }
+
+    fn query(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+    ) -> OrmResult<Vec<Row>> {
+        (**self).query(query, params)
+    }
+
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        (**self).query_batches(query, params, batch_size, on_batch)
+    }
 }
 
 impl Executor for PgPool {
     fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
-        self.get()
-            .map_err(OrmError::from)?
-            .execute(query, params)
-            .map_err(OrmError::from)
+        stats::timed(|| {
+            self.get()
+                .map_err(OrmError::from)?
+                .execute(query, params)
+                .map_err(OrmError::from)
+        })
     }
 
     fn query(
@@ -48,16 +126,57 @@ impl Executor for PgPool {
         query: &str,
         params: &[&dyn chopin_pg::types::ToSql],
     ) -> OrmResult<Vec<Row>> {
-        self.get()
-            .map_err(OrmError::from)?
-            .query(query, params)
-            .map_err(OrmError::from)
+        stats::timed(|| {
+            self.get()
+                .map_err(OrmError::from)?
+                .query(query, params)
+                .map_err(OrmError::from)
+        })
+    }
+
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        let mut conn = self.get().map_err(OrmError::from)?;
+        Executor::query_batches(&mut *conn, query, params, batch_size, on_batch)
+    }
+}
+
+/// Runs on the calling thread's shard.
+impl Executor for PerCorePool {
+    fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
+        self.with_local(|pool| Executor::execute(pool, query, params))?
+    }
+
+    fn query(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+    ) -> OrmResult<Vec<Row>> {
+        self.with_local(|pool| Executor::query(pool, query, params))?
+    }
+
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        self.with_local(|pool| Executor::query_batches(pool, query, params, batch_size, on_batch))?
     }
 }
 
 impl Executor for PgConnection {
     fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
-        chopin_pg::connection::PgConnection::execute(self, query, params).map_err(OrmError::from)
+        stats::timed(|| {
+            chopin_pg::connection::PgConnection::execute(self, query, params)
+                .map_err(OrmError::from)
+        })
     }
 
     fn query(
@@ -65,7 +184,42 @@ impl Executor for PgConnection {
         query: &str,
         params: &[&dyn chopin_pg::types::ToSql],
     ) -> OrmResult<Vec<Row>> {
-        chopin_pg::connection::PgConnection::query(self, query, params).map_err(OrmError::from)
+        stats::timed(|| {
+            chopin_pg::connection::PgConnection::query(self, query, params).map_err(OrmError::from)
+        })
+    }
+
+    /// Reads through a portal, inside a transaction of its own unless one
+    /// is already open.
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        let own_tx = !self.in_transaction();
+        if own_tx {
+            self.begin()?;
+        }
+        let result = (|| {
+            let mut portal = self.query_portal(query, params, batch_size)?;
+            while let Some(rows) = portal.next_batch()? {
+                if !on_batch(rows)? {
+                    break;
+                }
+            }
+            Ok(())
+        })();
+        if own_tx {
+            match result {
+                Ok(()) => self.commit()?,
+                Err(_) => {
+                    let _ = self.rollback();
+                }
+            }
+        }
+        result
     }
 }
 
@@ -118,7 +272,7 @@ impl<'a> Drop for Transaction<'a> {
 
 impl<'a> Executor for Transaction<'a> {
     fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
-        self.conn.execute(query, params).map_err(OrmError::from)
+        stats::timed(|| self.conn.execute(query, params).map_err(OrmError::from))
     }
 
     fn query(
@@ -126,7 +280,17 @@ impl<'a> Executor for Transaction<'a> {
         query: &str,
         params: &[&dyn chopin_pg::types::ToSql],
     ) -> OrmResult<Vec<Row>> {
-        self.conn.query(query, params).map_err(OrmError::from)
+        stats::timed(|| self.conn.query(query, params).map_err(OrmError::from))
+    }
+
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        Executor::query_batches(&mut *self.conn, query, params, batch_size, on_batch)
     }
 }
 
@@ -148,6 +312,17 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
     fn columns() -> &'static [&'static str];
     fn select_clause() -> &'static str;
 
+    /// Named database connection this model lives on.
+    fn connection() -> &'static str {
+        DEFAULT_CONNECTION
+    }
+
+    /// Read-through cache settings, set with `#[model(cache(...))]`.
+    /// `None` (the default) disables caching for this model.
+    fn cache_config() -> Option<CacheConfig> {
+        None
+    }
+
     fn primary_key_values(&self) -> Vec<PgValue>;
     fn set_generated_values(&mut self, values: Vec<PgValue>) -> OrmResult<()>;
     fn get_values(&self) -> Vec<PgValue>;
@@ -174,6 +349,15 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
         QueryBuilder::new()
     }
 
+    /// Fetch a row by primary key (one value per key column, in order),
+    /// consulting the [`cache`] first when the model opts in.
+    fn find_cached(
+        executor: &mut impl Executor,
+        pk: &[&dyn chopin_pg::types::ToSql],
+    ) -> OrmResult<Option<Self>> {
+        cache::find_cached(executor, pk)
+    }
+
     /// Automatically diffs and migrates the table schema based on structural column metadata
     fn sync_schema(executor: &mut impl Executor) -> OrmResult<()> {
         Self::create_table(executor)?;
@@ -262,9 +446,13 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
             final_values.iter().map(|v| v as _).collect();
 
         if gen_cols.is_empty() {
-            executor.execute(&query, &params)?;
+            metrics::observe(Self::table_name(), metrics::Op::Insert, || {
+                executor.execute(&query, &params)
+            })?;
         } else {
-            let rows = executor.query(&query, &params)?;
+            let rows = metrics::observe(Self::table_name(), metrics::Op::Insert, || {
+                executor.query(&query, &params)
+            })?;
             if let Some(row) = rows.first() {
                 let mut returned_vals = Vec::new();
                 for i in 0..gen_cols.len() {
@@ -331,9 +519,13 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
             final_values.iter().map(|v| v as _).collect();
 
         if gen_cols.is_empty() {
-            executor.execute(&query, &params)?;
+            metrics::observe(Self::table_name(), metrics::Op::Insert, || {
+                executor.execute(&query, &params)
+            })?;
         } else {
-            let rows = executor.query(&query, &params)?;
+            let rows = metrics::observe(Self::table_name(), metrics::Op::Insert, || {
+                executor.query(&query, &params)
+            })?;
             if let Some(row) = rows.first() {
                 let mut returned_vals = Vec::new();
                 for i in 0..gen_cols.len() {
@@ -342,9 +534,50 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
                 self.set_generated_values(returned_vals)?;
             }
         }
+        cache::invalidate::<Self>(&self.primary_key_values());
         Ok(())
     }
 
+    /// Fetch the row matching `model` on `columns` (a unique key), inserting
+    /// `model` if there is none. The flag is `true` when the row was created.
+    ///
+    /// An insert that loses a race to a concurrent one (unique violation) is
+    /// answered with the winner's row. Inside a transaction the violation
+    /// aborts the transaction, so the error is returned instead.
+    fn get_or_create(
+        executor: &mut impl Executor,
+        mut model: Self,
+        columns: &[&str],
+    ) -> OrmResult<(Self, bool)> {
+        let all_cols = Self::columns();
+        let values = model.get_values();
+        let mut clauses = Vec::with_capacity(columns.len());
+        let mut params = Vec::with_capacity(columns.len());
+        for col in columns {
+            let i = all_cols
+                .iter()
+                .position(|c| c == col)
+                .ok_or_else(|| OrmError::ModelError(format!("Column not found: {}", col)))?;
+            clauses.push(format!("{} = {{}}", col));
+            params.push(values[i].clone());
+        }
+        let lookup = builder::Expr::<Self>::new(clauses.join(" AND "), params);
+
+        if let Some(found) = Self::find().filter(lookup.clone()).one(executor)? {
+            return Ok((found, false));
+        }
+        match model.insert(executor) {
+            Ok(()) => Ok((model, true)),
+            Err(OrmError::Database(e)) if e.sql_state() == Some("23505") => {
+                match Self::find().filter(lookup).one(executor)? {
+                    Some(found) => Ok((found, false)),
+                    None => Err(OrmError::Database(e)),
+                }
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     /// Partially update the model, persisting only the specified columns to the database.
     fn update_columns(
         &self,
@@ -396,7 +629,10 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
 
         let params_ref: Vec<&dyn chopin_pg::types::ToSql> =
             query_values.iter().map(|v| v as _).collect();
-        let rows = executor.query(&query, &params_ref)?;
+        let rows = metrics::observe(Self::table_name(), metrics::Op::Update, || {
+            executor.query(&query, &params_ref)
+        })?;
+        cache::invalidate::<Self>(&pk_vals);
 
         if let Some(row) = rows.first() {
             Self::from_row(row)
@@ -453,7 +689,10 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
 
         let params: Vec<&dyn chopin_pg::types::ToSql> =
             query_values.iter().map(|v| v as _).collect();
-        executor.execute(&query, &params)?;
+        metrics::observe(Self::table_name(), metrics::Op::Update, || {
+            executor.execute(&query, &params)
+        })?;
+        cache::invalidate::<Self>(&pk_values);
         Ok(())
     }
 
@@ -480,7 +719,10 @@ pub trait Model: FromRow + Validate + Sized + Send + Sync {
         let pk_values = self.primary_key_values();
         let params: Vec<&dyn chopin_pg::types::ToSql> = pk_values.iter().map(|v| v as _).collect();
 
-        executor.execute(&query, &params)?;
+        metrics::observe(Self::table_name(), metrics::Op::Delete, || {
+            executor.execute(&query, &params)
+        })?;
+        cache::invalidate::<Self>(&pk_values);
         Ok(())
     }
 }
@@ -575,6 +817,40 @@ impl<T: ExtractValue> ExtractValue for Option<T> {
     }
 }
 
+// ─── Array ExtractValue ───────────────────────────────────────────────────────
+
+impl<T: ExtractValue> ExtractValue for Vec<T> {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        match val {
+            PgValue::Array(items) => items.into_iter().map(T::from_pg_value).collect(),
+            _ => Err(OrmError::Extraction("Expected Array".into())),
+        }
+    }
+}
+
+// `bytea`, not an array: `u8` deliberately has no `ExtractValue` impl, so this
+// does not overlap the blanket impl above.
+impl ExtractValue for Vec<u8> {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        match val {
+            PgValue::Bytes(b) => Ok(b),
+            _ => Err(OrmError::Extraction("Expected Bytea".into())),
+        }
+    }
+}
+
+impl<T: chopin_pg::FromSql> ExtractValue for PgRange<T> {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        chopin_pg::FromSql::from_sql(&val).map_err(OrmError::from)
+    }
+}
+
+impl ExtractValue for [u8; 16] {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        chopin_pg::FromSql::from_sql(&val).map_err(OrmError::from)
+    }
+}
+
 // ─── f32 ExtractValue ─────────────────────────────────────────────────────────
 
 impl ExtractValue for f32 {
@@ -646,6 +922,39 @@ impl ExtractValue for chrono::NaiveDateTime {
     }
 }
 
+/// Date/time types whose `FromSql` impl already covers every case.
+macro_rules! extract_via_from_sql {
+    ($($(#[$cfg:meta])* $ty:ty),* $(,)?) => {
+        $(
+            $(#[$cfg])*
+            impl ExtractValue for $ty {
+                fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+                    chopin_pg::FromSql::from_sql(&val).map_err(OrmError::from)
+                }
+            }
+        )*
+    };
+}
+
+extract_via_from_sql!(
+    #[cfg(feature = "chrono")]
+    chrono::NaiveDate,
+    #[cfg(feature = "chrono")]
+    chrono::NaiveTime,
+    #[cfg(feature = "chrono")]
+    chrono::DateTime<chrono::Utc>,
+    #[cfg(feature = "chrono")]
+    chrono::DateTime<chrono::FixedOffset>,
+    #[cfg(feature = "time")]
+    time::Date,
+    #[cfg(feature = "time")]
+    time::Time,
+    #[cfg(feature = "time")]
+    time::PrimitiveDateTime,
+    #[cfg(feature = "time")]
+    time::OffsetDateTime,
+);
+
 // ─── rust_decimal::Decimal ExtractValue ───────────────────────────────────────
 
 #[cfg(feature = "decimal")]
@@ -692,6 +1001,22 @@ impl ExtractValue for rust_decimal::Decimal {
     }
 }
 
+// ─── JSON / JSONB ExtractValue ────────────────────────────────────────────────
+
+#[cfg(feature = "json")]
+impl<T: serde::de::DeserializeOwned> ExtractValue for Json<T> {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        val.to_json().map(Json).map_err(OrmError::from)
+    }
+}
+
+#[cfg(feature = "json")]
+impl ExtractValue for serde_json::Value {
+    fn from_pg_value(val: PgValue) -> OrmResult<Self> {
+        val.to_json().map_err(OrmError::from)
+    }
+}
+
 pub trait HasForeignKey<M: Model> {
     /// Returns the table name of the child and a list of (child_column, parent_column) mappings.
     fn foreign_key_info() -> (&'static str, Vec<(&'static str, &'static str)>);
@@ -727,7 +1052,9 @@ pub trait SoftDelete: Model {
         );
 
         let params: Vec<&dyn chopin_pg::types::ToSql> = pk_vals.iter().map(|v| v as _).collect();
-        executor.execute(&query, &params)?;
+        metrics::observe(Self::table_name(), metrics::Op::Update, || {
+            executor.execute(&query, &params)
+        })?;
         Ok(())
     }
 
@@ -749,7 +1076,9 @@ pub trait SoftDelete: Model {
         );
 
         let params: Vec<&dyn chopin_pg::types::ToSql> = pk_vals.iter().map(|v| v as _).collect();
-        executor.execute(&query, &params)?;
+        metrics::observe(Self::table_name(), metrics::Op::Update, || {
+            executor.execute(&query, &params)
+        })?;
         Ok(())
     }
 
@@ -837,9 +1166,13 @@ pub fn batch_insert<M: Model>(models: &mut [M], executor: &mut impl Executor) ->
     let params: Vec<&dyn chopin_pg::types::ToSql> = all_values.iter().map(|v| v as _).collect();
 
     if gen_cols.is_empty() {
-        executor.execute(&query, &params)?;
+        metrics::observe(M::table_name(), metrics::Op::Insert, || {
+            executor.execute(&query, &params)
+        })?;
     } else {
-        let rows = executor.query(&query, &params)?;
+        let rows = metrics::observe(M::table_name(), metrics::Op::Insert, || {
+            executor.query(&query, &params)
+        })?;
         for (model, row) in models.iter_mut().zip(rows.iter()) {
             let mut returned_vals = Vec::with_capacity(gen_cols.len());
             for i in 0..gen_cols.len() {
@@ -903,6 +1236,30 @@ impl<'a, E: Executor> Executor for LoggedExecutor<'a, E> {
         let _ = elapsed;
         res
     }
+
+    fn query_batches(
+        &mut self,
+        query: &str,
+        params: &[&dyn chopin_pg::types::ToSql],
+        batch_size: usize,
+        on_batch: &mut dyn FnMut(Vec<Row>) -> OrmResult<bool>,
+    ) -> OrmResult<()> {
+        let start = std::time::Instant::now();
+        let res = self
+            .inner
+            .query_batches(query, params, batch_size, on_batch);
+        let elapsed = start.elapsed();
+        #[cfg(feature = "log")]
+        log::debug!(
+            "query_batches ({}ms): {} | params: {:?}",
+            elapsed.as_millis(),
+            query,
+            params.len()
+        );
+        #[cfg(not(feature = "log"))]
+        let _ = elapsed;
+        res
+    }
 }
 
 #[cfg(test)]
@@ -1080,4 +1437,106 @@ mod tests {
         batch_insert(&mut items, &mut mock).unwrap();
         assert!(mock.executed_queries.is_empty());
     }
+
+    #[test]
+    fn test_query_batches_default_splits_and_stops() {
+        let mut mock = MockExecutor::new();
+        let rows = (1..=5).map(|id| mock_row!("id" => id)).collect();
+        mock.push_result(rows);
+
+        let mut sizes = Vec::new();
+        mock.query_batches("SELECT id FROM items", &[], 2, &mut |batch| {
+            sizes.push(batch.len());
+            Ok(sizes.len() < 2)
+        })
+        .unwrap();
+        assert_eq!(sizes, vec![2, 2]);
+    }
+
+    #[test]
+    fn test_extract_bytea_column() {
+        let row = crate::mock_row!(
+            "blob" => PgValue::Bytes(vec![0, 0x5c, 0xff]),
+            "tags" => PgValue::Array(vec![PgValue::Int4(1)]),
+        );
+        assert_eq!(
+            Vec::<u8>::extract(&row, "blob").unwrap(),
+            vec![0, 0x5c, 0xff]
+        );
+        assert!(Vec::<u8>::extract(&row, "tags").is_err());
+        assert_eq!(Vec::<i32>::extract(&row, "tags").unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_extract_range_column() {
+        let row = crate::mock_row!(
+            "slots" => PgValue::Range("[9,12)".to_string()),
+            "id" => PgValue::Int4(1),
+        );
+        assert_eq!(
+            PgRange::<i32>::extract(&row, "slots").unwrap(),
+            PgRange::from(9..12)
+        );
+        assert!(PgRange::<i32>::extract(&row, "id").is_err());
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_extract_date_time_columns() {
+        use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+        let row = crate::mock_row!(
+            "day" => PgValue::Date(-1),
+            "at" => PgValue::Time(3_600_000_000),
+            "seen" => PgValue::Timestamptz(0),
+        );
+        assert_eq!(
+            NaiveDate::extract(&row, "day").unwrap(),
+            NaiveDate::from_ymd_opt(1999, 12, 31).unwrap()
+        );
+        assert_eq!(
+            NaiveTime::extract(&row, "at").unwrap(),
+            NaiveTime::from_hms_opt(1, 0, 0).unwrap()
+        );
+        assert_eq!(
+            DateTime::<Utc>::extract(&row, "seen").unwrap().to_rfc3339(),
+            "2000-01-01T00:00:00+00:00"
+        );
+        assert!(
+            Option::<NaiveDate>::from_pg_value(PgValue::Null)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_extract_array_columns() {
+        let row = crate::mock_row!(
+            "tags" => vec!["a".to_string(), "b".to_string()],
+            "scores" => PgValue::Array(vec![PgValue::Int4(1), PgValue::Null]),
+            "ids" => vec![[7u8; 16]],
+        );
+        assert_eq!(Vec::<String>::extract(&row, "tags").unwrap(), ["a", "b"]);
+        assert_eq!(
+            Vec::<Option<i32>>::extract(&row, "scores").unwrap(),
+            [Some(1), None]
+        );
+        assert!(Vec::<i32>::extract(&row, "scores").is_err());
+        assert_eq!(Vec::<[u8; 16]>::extract(&row, "ids").unwrap(), [[7u8; 16]]);
+        assert!(Vec::<String>::from_pg_value(PgValue::Text("{a}".into())).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_extract_json_columns() {
+        let row = crate::mock_row!(
+            "prefs" => Json(serde_json::json!({ "theme": "dark" })),
+            "raw" => PgValue::Json("[1,2]".into()),
+        );
+        let Json(prefs): Json<std::collections::HashMap<String, String>> =
+            ExtractValue::extract(&row, "prefs").unwrap();
+        assert_eq!(prefs["theme"], "dark");
+        let raw: serde_json::Value = ExtractValue::extract_at(&row, 1).unwrap();
+        assert_eq!(raw, serde_json::json!([1, 2]));
+        assert!(<Json<Vec<String>> as ExtractValue>::extract(&row, "raw").is_err());
+    }
 }
@@ -0,0 +1,352 @@
+//! Per-table query counters and latency histograms.
+//!
+//! The default [`Model`](crate::Model) methods, the [`QueryBuilder`]
+//! terminals and [`batch_insert`](crate::batch_insert) report each statement
+//! under the model's table and an [`Op`]. Recording is compiled in with the
+//! `metrics` feature; without it [`observe`] just runs the query.
+//!
+//! Unlike [`stats`](crate::stats), which is reset per request, these counters
+//! are cumulative, so a scraper can diff them. Each thread records into its
+//! own counters; [`snapshot`] sums them across threads, including threads
+//! that have exited.
+//! Raw SQL sent straight to an executor is not attributed to any table.
+//!
+//! [`QueryBuilder`]: crate::QueryBuilder
+
+/// The kind of statement a model method issued.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Op {
+    Select,
+    Insert,
+    Update,
+    Delete,
+}
+
+impl Op {
+    pub const ALL: [Op; 4] = [Op::Select, Op::Insert, Op::Update, Op::Delete];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Op::Select => "select",
+            Op::Insert => "insert",
+            Op::Update => "update",
+            Op::Delete => "delete",
+        }
+    }
+}
+
+/// Run one model statement, recording it against `table`.
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) fn observe<T>(
+    _table: &'static str,
+    _op: Op,
+    f: impl FnOnce() -> crate::OrmResult<T>,
+) -> crate::OrmResult<T> {
+    f()
+}
+
+#[cfg(feature = "metrics")]
+pub use recording::*;
+
+#[cfg(feature = "metrics")]
+mod recording {
+    use super::Op;
+    use crate::OrmResult;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex, OnceLock};
+    use std::time::{Duration, Instant};
+
+    /// Upper bounds of the latency buckets, in microseconds. Slower queries
+    /// land in a final overflow bucket.
+    pub const BUCKETS_US: [u64; 12] = [
+        100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000, 250_000, 1_000_000,
+    ];
+
+    const SLOTS: usize = BUCKETS_US.len() + 1;
+
+    #[derive(Default)]
+    struct Counters {
+        count: AtomicU64,
+        errors: AtomicU64,
+        sum_us: AtomicU64,
+        buckets: [AtomicU64; SLOTS],
+    }
+
+    impl Counters {
+        fn add(&self, other: &Counters) {
+            let add = |to: &AtomicU64, from: &AtomicU64| {
+                to.fetch_add(from.load(Ordering::Relaxed), Ordering::Relaxed);
+            };
+            add(&self.count, &other.count);
+            add(&self.errors, &other.errors);
+            add(&self.sum_us, &other.sum_us);
+            for (to, from) in self.buckets.iter().zip(&other.buckets) {
+                add(to, from);
+            }
+        }
+
+        fn clear(&self) {
+            self.count.store(0, Ordering::Relaxed);
+            self.errors.store(0, Ordering::Relaxed);
+            self.sum_us.store(0, Ordering::Relaxed);
+            for b in &self.buckets {
+                b.store(0, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// One thread's counters for one table, one entry per [`Op`].
+    #[derive(Default)]
+    struct TableCounters([Counters; 4]);
+
+    /// Every thread's counters for one table.
+    #[derive(Default)]
+    struct Table {
+        live: Vec<Arc<TableCounters>>,
+        /// Counts folded in from threads that have exited.
+        retired: TableCounters,
+    }
+
+    /// Only touched when a thread first records against a table, when it
+    /// exits, and by [`snapshot`] and [`reset`]; recording itself only
+    /// writes the calling thread's own counters.
+    fn registry() -> &'static Mutex<HashMap<&'static str, Table>> {
+        static REGISTRY: OnceLock<Mutex<HashMap<&'static str, Table>>> = OnceLock::new();
+        REGISTRY.get_or_init(Default::default)
+    }
+
+    /// The calling thread's counters, by table.
+    #[derive(Default)]
+    struct Local(RefCell<HashMap<&'static str, Arc<TableCounters>>>);
+
+    impl Drop for Local {
+        fn drop(&mut self) {
+            let mut registry = registry().lock().unwrap_or_else(|e| e.into_inner());
+            for (table, mine) in self.0.get_mut().drain() {
+                let Some(t) = registry.get_mut(table) else {
+                    continue;
+                };
+                t.live.retain(|c| !Arc::ptr_eq(c, &mine));
+                for (to, from) in t.retired.0.iter().zip(&mine.0) {
+                    to.add(from);
+                }
+            }
+        }
+    }
+
+    thread_local! {
+        static LOCAL: Local = Local::default();
+    }
+
+    /// Statements issued while the thread is being torn down go unrecorded.
+    fn with_counters(table: &'static str, f: impl FnOnce(&TableCounters)) {
+        let _ = LOCAL.try_with(|local| {
+            if let Some(c) = local.0.borrow().get(table) {
+                return f(c);
+            }
+            let mine = Arc::new(TableCounters::default());
+            registry()
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .entry(table)
+                .or_default()
+                .live
+                .push(mine.clone());
+            f(&mine);
+            local.0.borrow_mut().insert(table, mine);
+        });
+    }
+
+    fn bucket(micros: u64) -> usize {
+        BUCKETS_US.partition_point(|&bound| bound < micros)
+    }
+
+    /// Run one model statement, recording it against `table`.
+    pub(crate) fn observe<T>(
+        table: &'static str,
+        op: Op,
+        f: impl FnOnce() -> OrmResult<T>,
+    ) -> OrmResult<T> {
+        let start = Instant::now();
+        let result = f();
+        record(table, op, start.elapsed(), result.is_err());
+        result
+    }
+
+    /// Record one statement by hand, for queries the ORM does not issue
+    /// itself but that should count towards a table.
+    pub fn record(table: &'static str, op: Op, elapsed: Duration, failed: bool) {
+        let micros = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        with_counters(table, |tc| {
+            let c = &tc.0[op as usize];
+            c.count.fetch_add(1, Ordering::Relaxed);
+            if failed {
+                c.errors.fetch_add(1, Ordering::Relaxed);
+            }
+            c.sum_us.fetch_add(micros, Ordering::Relaxed);
+            c.buckets[bucket(micros)].fetch_add(1, Ordering::Relaxed);
+        });
+    }
+
+    /// Point-in-time copy of one table/operation pair.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct OpMetrics {
+        pub table: &'static str,
+        pub op: Op,
+        pub count: u64,
+        pub errors: u64,
+        /// Total time spent in these statements.
+        pub total: Duration,
+        /// Per-bucket counts (not cumulative), aligned with [`BUCKETS_US`]
+        /// plus a trailing overflow bucket.
+        pub buckets: [u64; SLOTS],
+    }
+
+    impl OpMetrics {
+        /// Upper bound of the bucket holding the `q` quantile (0.0–1.0).
+        /// `None` when nothing was recorded or the quantile falls in the
+        /// overflow bucket.
+        pub fn quantile(&self, q: f64) -> Option<Duration> {
+            if self.count == 0 {
+                return None;
+            }
+            let rank = ((self.count as f64) * q.clamp(0.0, 1.0)).ceil().max(1.0) as u64;
+            let mut seen = 0;
+            for (i, n) in self.buckets.iter().enumerate() {
+                seen += n;
+                if seen >= rank {
+                    return BUCKETS_US.get(i).map(|&us| Duration::from_micros(us));
+                }
+            }
+            None
+        }
+    }
+
+    /// Every table/operation pair that has recorded a statement, summed
+    /// across threads and ordered by table then operation.
+    pub fn snapshot() -> Vec<OpMetrics> {
+        let mut out = Vec::new();
+        for (&table, t) in registry().lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let total = TableCounters::default();
+            for tc in t.live.iter().map(|c| &**c).chain([&t.retired]) {
+                for (to, from) in total.0.iter().zip(&tc.0) {
+                    to.add(from);
+                }
+            }
+            for op in Op::ALL {
+                let c = &total.0[op as usize];
+                let count = c.count.load(Ordering::Relaxed);
+                if count == 0 {
+                    continue;
+                }
+                out.push(OpMetrics {
+                    table,
+                    op,
+                    count,
+                    errors: c.errors.load(Ordering::Relaxed),
+                    total: Duration::from_micros(c.sum_us.load(Ordering::Relaxed)),
+                    buckets: std::array::from_fn(|i| c.buckets[i].load(Ordering::Relaxed)),
+                });
+            }
+        }
+        out.sort_by(|a, b| (a.table, a.op).cmp(&(b.table, b.op)));
+        out
+    }
+
+    /// Zero every thread's counters. Tables stay registered.
+    pub fn reset() {
+        for t in registry()
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+        {
+            for tc in t.live.iter().map(|c| &**c).chain([&t.retired]) {
+                for c in &tc.0 {
+                    c.clear();
+                }
+            }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::OrmError;
+
+        fn find(table: &str, op: Op) -> Option<OpMetrics> {
+            snapshot()
+                .into_iter()
+                .find(|m| m.table == table && m.op == op)
+        }
+
+        #[test]
+        fn test_buckets_are_upper_bounds() {
+            assert_eq!(bucket(0), 0);
+            assert_eq!(bucket(100), 0);
+            assert_eq!(bucket(101), 1);
+            assert_eq!(bucket(1_000_000), BUCKETS_US.len() - 1);
+            assert_eq!(bucket(5_000_000), BUCKETS_US.len());
+        }
+
+        #[test]
+        fn test_records_counts_errors_and_latency() {
+            record("metrics_t", Op::Select, Duration::from_micros(80), false);
+            record("metrics_t", Op::Select, Duration::from_millis(3), false);
+            let _ = observe("metrics_t", Op::Delete, || -> OrmResult<()> {
+                Err(OrmError::ModelError("boom".into()))
+            });
+
+            let select = find("metrics_t", Op::Select).unwrap();
+            assert_eq!((select.count, select.errors), (2, 0));
+            assert_eq!(select.total, Duration::from_micros(3_080));
+            assert_eq!(select.buckets[0], 1);
+            assert_eq!(select.quantile(0.5), Some(Duration::from_micros(100)));
+            assert_eq!(select.quantile(0.99), Some(Duration::from_millis(5)));
+
+            let delete = find("metrics_t", Op::Delete).unwrap();
+            assert_eq!((delete.count, delete.errors), (1, 1));
+            assert!(find("metrics_t", Op::Insert).is_none());
+        }
+
+        #[test]
+        fn test_snapshot_sums_threads() {
+            record(
+                "metrics_threads",
+                Op::Insert,
+                Duration::from_micros(50),
+                false,
+            );
+            std::thread::spawn(|| {
+                record(
+                    "metrics_threads",
+                    Op::Insert,
+                    Duration::from_micros(50),
+                    true,
+                );
+            })
+            .join()
+            .unwrap();
+
+            // The second thread has exited; its counts are kept.
+            let insert = find("metrics_threads", Op::Insert).unwrap();
+            assert_eq!((insert.count, insert.errors), (2, 1));
+            assert_eq!(insert.total, Duration::from_micros(100));
+        }
+
+        #[test]
+        fn test_quantile_overflow_is_unbounded() {
+            let m = OpMetrics {
+                table: "t",
+                op: Op::Select,
+                count: 1,
+                errors: 0,
+                total: Duration::from_secs(2),
+                buckets: std::array::from_fn(|i| (i == SLOTS - 1) as u64),
+            };
+            assert_eq!(m.quantile(0.5), None);
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Executor, OrmResult};
+use crate::{Executor, OrmError, OrmResult};
 
 /// Defines a single database migration with forward and reverse operations.
 pub trait Migration {
@@ -98,9 +98,217 @@ impl MigrationManager {
     }
 }
 
+/// The migrations contributed by one application module.
+pub struct ModuleMigrations {
+    /// Module name, recorded alongside each applied migration.
+    pub module: &'static str,
+    /// Migrations in the order they must be applied.
+    pub migrations: Vec<Box<dyn Migration>>,
+}
+
+impl ModuleMigrations {
+    pub fn new(module: &'static str, migrations: Vec<Box<dyn Migration>>) -> Self {
+        Self { module, migrations }
+    }
+}
+
+/// Ledger for module migrations. Unlike `__chopin_migrations`, names only
+/// need to be unique within a module.
+const MODULE_LEDGER_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS _chopin_module_migrations (
+        id BIGSERIAL PRIMARY KEY,
+        module TEXT NOT NULL,
+        name TEXT NOT NULL,
+        applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        UNIQUE (module, name)
+    )
+"#;
+
+impl MigrationManager {
+    /// Creates the `_chopin_module_migrations` ledger table if it does not exist.
+    pub fn ensure_module_migrations_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        executor.execute(MODULE_LEDGER_SQL, &[])?;
+        Ok(())
+    }
+
+    /// Returns `(module, status)` for every module migration.
+    pub fn module_status(
+        executor: &mut dyn Executor,
+        modules: &[ModuleMigrations],
+    ) -> OrmResult<Vec<(&'static str, MigrationStatus)>> {
+        Self::ensure_module_migrations_table(executor)?;
+
+        let mut statuses = Vec::new();
+        for module in modules {
+            for m in &module.migrations {
+                let applied = Self::module_migration_applied(executor, module.module, m.name())?;
+                statuses.push((
+                    module.module,
+                    MigrationStatus {
+                        name: m.name().to_string(),
+                        applied,
+                    },
+                ));
+            }
+        }
+        Ok(statuses)
+    }
+
+    /// Applies pending module migrations.
+    ///
+    /// Modules are processed in the order given — callers pass them in
+    /// dependency order — and each module's migrations in declaration order.
+    /// The ledger's `id` records the resulting global order, which
+    /// [`down_modules`](Self::down_modules) unwinds.
+    pub fn up_modules(executor: &mut dyn Executor, modules: &[ModuleMigrations]) -> OrmResult<()> {
+        Self::ensure_module_migrations_table(executor)?;
+
+        for module in modules {
+            for m in &module.migrations {
+                let name = m.name();
+                if Self::module_migration_applied(executor, module.module, name)? {
+                    continue;
+                }
+                #[cfg(feature = "log")]
+                log::info!("Applying migration: {}/{}", module.module, name);
+                m.up(executor)?;
+                let insert_sql =
+                    "INSERT INTO _chopin_module_migrations (module, name) VALUES ($1, $2)";
+                executor.execute(insert_sql, &[&module.module, &name])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reverts the `steps` most recently applied module migrations, newest
+    /// first, regardless of which module they belong to.
+    pub fn down_modules(
+        executor: &mut dyn Executor,
+        modules: &[ModuleMigrations],
+        steps: u32,
+    ) -> OrmResult<()> {
+        Self::ensure_module_migrations_table(executor)?;
+
+        let limit = steps as i64;
+        let rows = executor.query(
+            "SELECT module, name FROM _chopin_module_migrations ORDER BY id DESC LIMIT $1",
+            &[&limit],
+        )?;
+        for row in rows {
+            let module_name: String = row.get_typed_by_name("module")?;
+            let name: String = row.get_typed_by_name("name")?;
+            let m = modules
+                .iter()
+                .filter(|m| m.module == module_name)
+                .flat_map(|m| m.migrations.iter())
+                .find(|m| m.name() == name)
+                .ok_or_else(|| {
+                    OrmError::ModelError(format!(
+                        "applied migration {module_name}/{name} is not registered"
+                    ))
+                })?;
+            #[cfg(feature = "log")]
+            log::info!("Reverting migration: {}/{}", module_name, name);
+            m.down(executor)?;
+            executor.execute(
+                "DELETE FROM _chopin_module_migrations WHERE module = $1 AND name = $2",
+                &[&module_name, &name],
+            )?;
+        }
+        Ok(())
+    }
+
+    fn module_migration_applied(
+        executor: &mut dyn Executor,
+        module: &str,
+        name: &str,
+    ) -> OrmResult<bool> {
+        let check_sql = "SELECT 1 FROM _chopin_module_migrations WHERE module = $1 AND name = $2";
+        Ok(!executor.query(check_sql, &[&module, &name])?.is_empty())
+    }
+}
+
 /// Declares a database index to be created during schema sync or migrations.
 pub struct Index {
     pub name: &'static str,
     pub columns: &'static [&'static str],
     pub unique: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockExecutor, mock_row};
+
+    struct Create(&'static str);
+
+    impl Migration for Create {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+        fn up(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+            executor.execute(&format!("UP {}", self.0), &[])?;
+            Ok(())
+        }
+        fn down(&self, executor: &mut dyn Executor) -> OrmResult<()> {
+            executor.execute(&format!("DOWN {}", self.0), &[])?;
+            Ok(())
+        }
+    }
+
+    fn modules() -> Vec<ModuleMigrations> {
+        vec![
+            ModuleMigrations::new("users", vec![Box::new(Create("001_init"))]),
+            ModuleMigrations::new(
+                "posts",
+                vec![Box::new(Create("001_init")), Box::new(Create("002_tags"))],
+            ),
+        ]
+    }
+
+    fn statements(executor: &MockExecutor) -> Vec<&str> {
+        executor
+            .executed_queries
+            .iter()
+            .map(|(sql, _)| sql.as_str())
+            .filter(|sql| sql.starts_with("UP") || sql.starts_with("DOWN"))
+            .collect()
+    }
+
+    #[test]
+    fn test_up_modules_skips_applied() {
+        let mut executor = MockExecutor::new();
+        // users/001_init already applied; the posts checks return no rows.
+        executor.push_result(vec![mock_row!("?column?" => 1i32)]);
+        MigrationManager::up_modules(&mut executor, &modules()).unwrap();
+
+        assert_eq!(statements(&executor), ["UP 001_init", "UP 002_tags"]);
+        assert!(
+            executor.executed_queries[0]
+                .0
+                .contains("_chopin_module_migrations")
+        );
+        let inserts = executor
+            .executed_queries
+            .iter()
+            .filter(|(sql, n)| sql.starts_with("INSERT") && *n == 2)
+            .count();
+        assert_eq!(inserts, 2);
+    }
+
+    #[test]
+    fn test_down_modules_follows_ledger_order() {
+        let mut executor = MockExecutor::new();
+        executor.push_result(vec![
+            mock_row!("module" => "posts", "name" => "002_tags"),
+            mock_row!("module" => "users", "name" => "001_init"),
+        ]);
+        MigrationManager::down_modules(&mut executor, &modules(), 2).unwrap();
+        assert_eq!(statements(&executor), ["DOWN 002_tags", "DOWN 001_init"]);
+
+        let mut executor = MockExecutor::new();
+        executor.push_result(vec![mock_row!("module" => "gone", "name" => "001_init")]);
+        let err = MigrationManager::down_modules(&mut executor, &modules(), 1).unwrap_err();
+        assert!(err.to_string().contains("gone/001_init"), "{err}");
+    }
+}
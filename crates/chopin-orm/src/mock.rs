@@ -46,7 +46,7 @@ impl Executor for MockExecutor {
     fn execute(&mut self, query: &str, params: &[&dyn chopin_pg::types::ToSql]) -> OrmResult<u64> {
         self.executed_queries
             .push((query.to_string(), params.len()));
-        Ok(1)
+        crate::stats::timed(|| Ok(1))
     }
 
     fn query(
@@ -56,11 +56,7 @@ impl Executor for MockExecutor {
     ) -> OrmResult<Vec<Row>> {
         self.executed_queries
             .push((query.to_string(), params.len()));
-        if let Some(rows) = self.mocked_results.pop_front() {
-            Ok(rows)
-        } else {
-            Ok(vec![])
-        }
+        crate::stats::timed(|| Ok(self.mocked_results.pop_front().unwrap_or_default()))
     }
 }
 
@@ -0,0 +1,169 @@
+//! Naming helpers shared by `#[derive(Model)]` and the CLI.
+//!
+//! The derive carries its own copy (proc-macro crates cannot depend on this
+//! one); keep the two in sync. Tables are named by the derive as follows:
+//!
+//! | `table_naming`     | `BlogCategory`                          |
+//! |--------------------|-----------------------------------------|
+//! | `legacy` (default) | `blogcategorys`                         |
+//! | `snake_case`       | `blog_categories`                       |
+//! | `lowercase`        | `blogcategories`                        |
+//!
+//! `plural_tables = false` drops the plural and `table_prefix = "app_"`
+//! prepends a prefix. Set them per model, e.g.
+//! `#[model(table_naming = "snake_case", plural_tables = false)]`, or for the
+//! whole crate in `Cargo.toml`:
+//!
+//! ```toml
+//! [package.metadata.chopin-orm]
+//! table_naming = "snake_case"
+//! table_prefix = "app_"
+//! ```
+//!
+//! An explicit `#[model(table_name = "...")]` always wins.
+
+/// Words whose plural is not formed by a suffix rule.
+const IRREGULAR: &[(&str, &str)] = &[
+    ("person", "people"),
+    ("man", "men"),
+    ("woman", "women"),
+    ("child", "children"),
+    ("mouse", "mice"),
+    ("goose", "geese"),
+    ("foot", "feet"),
+    ("tooth", "teeth"),
+    ("ox", "oxen"),
+    ("leaf", "leaves"),
+    ("life", "lives"),
+    ("knife", "knives"),
+    ("wife", "wives"),
+    ("half", "halves"),
+    ("wolf", "wolves"),
+    ("shelf", "shelves"),
+    ("thief", "thieves"),
+    ("hero", "heroes"),
+    ("potato", "potatoes"),
+    ("tomato", "tomatoes"),
+    ("echo", "echoes"),
+    ("quiz", "quizzes"),
+    ("index", "indices"),
+    ("matrix", "matrices"),
+    ("vertex", "vertices"),
+    ("criterion", "criteria"),
+    ("phenomenon", "phenomena"),
+    ("datum", "data"),
+    ("medium", "media"),
+];
+
+/// Words with no distinct plural form.
+const UNCOUNTABLE: &[&str] = &[
+    "sheep",
+    "fish",
+    "deer",
+    "series",
+    "species",
+    "news",
+    "data",
+    "media",
+    "information",
+    "equipment",
+    "metadata",
+    "feedback",
+    "software",
+    "staff",
+];
+
+/// Plural of a single lowercase word.
+fn pluralize_word(word: &str) -> String {
+    if UNCOUNTABLE.contains(&word) || IRREGULAR.iter().any(|(_, p)| *p == word) {
+        return word.to_string();
+    }
+    if let Some((_, plural)) = IRREGULAR.iter().find(|(s, _)| *s == word) {
+        return plural.to_string();
+    }
+    let ends_in_consonant_y = word.len() > 1
+        && word.ends_with('y')
+        && !matches!(
+            word.as_bytes()[word.len() - 2],
+            b'a' | b'e' | b'i' | b'o' | b'u'
+        );
+    if ends_in_consonant_y {
+        return format!("{}ies", &word[..word.len() - 1]);
+    }
+    if let Some(stem) = word.strip_suffix("is") {
+        // analysis -> analyses, axis -> axes
+        return format!("{stem}es");
+    }
+    if ["s", "x", "z", "ch", "sh"]
+        .iter()
+        .any(|s| word.ends_with(s))
+    {
+        return format!("{word}es");
+    }
+    format!("{word}s")
+}
+
+/// Plural of a snake_case name; only the last word changes (`blog_category`
+/// -> `blog_categories`).
+pub fn pluralize(name: &str) -> String {
+    match name.rsplit_once('_') {
+        Some((head, last)) => format!("{head}_{}", pluralize_word(last)),
+        None => pluralize_word(name),
+    }
+}
+
+/// `BlogPost` -> `blog_post`, `HTTPRequest` -> `http_request`.
+pub fn snake_case(ident: &str) -> String {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut out = String::with_capacity(ident.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() {
+            let prev_lower =
+                i > 0 && (chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit());
+            let acronym_end = i > 0
+                && chars[i - 1].is_uppercase()
+                && chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev_lower || acronym_end {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pluralize() {
+        for (singular, plural) in [
+            ("post", "posts"),
+            ("person", "people"),
+            ("category", "categories"),
+            ("day", "days"),
+            ("status", "statuses"),
+            ("box", "boxes"),
+            ("batch", "batches"),
+            ("analysis", "analyses"),
+            ("child", "children"),
+            ("sheep", "sheep"),
+            ("people", "people"),
+            ("blog_category", "blog_categories"),
+            ("sales_person", "sales_people"),
+        ] {
+            assert_eq!(pluralize(singular), plural, "{singular}");
+        }
+    }
+
+    #[test]
+    fn test_snake_case() {
+        assert_eq!(snake_case("BlogPost"), "blog_post");
+        assert_eq!(snake_case("HTTPRequest"), "http_request");
+        assert_eq!(snake_case("Invoice2024Line"), "invoice2024_line");
+        assert_eq!(snake_case("user"), "user");
+    }
+}
@@ -0,0 +1,365 @@
+//! Transactional outbox for reliable side effects.
+//!
+//! Side effects (emails, webhooks, events) are recorded with
+//! [`Outbox::enqueue`] on the same [`Transaction`](crate::Transaction) as the
+//! data change that causes them, so they are committed — or rolled back —
+//! together. An [`OutboxRelay`] later claims pending messages and hands them to
+//! the handler registered for their topic.
+//!
+//! Delivery is at-least-once: a relay that crashes after a handler succeeded
+//! but before the message was marked sent will deliver it again once its lease
+//! expires. Handlers should therefore be idempotent, e.g. by using
+//! [`OutboxMessage::id`] as an idempotency key. Messages that keep failing are
+//! moved to the dead-letter state after [`OutboxRelay::max_attempts`] tries and
+//! can be inspected with [`Outbox::dead_letters`] and retried with
+//! [`Outbox::requeue`].
+//!
+//! ```ignore
+//! let mut tx = Transaction::begin(&mut conn)?;
+//! order.insert(&mut tx)?;
+//! Outbox::enqueue(&mut tx, "order.created", &format!(r#"{{"id":{}}}"#, order.id))?;
+//! tx.commit()?;
+//!
+//! OutboxRelay::new()
+//!     .handler("order.created", send_confirmation_email)
+//!     .start(|| Ok(Box::new(PgPool::connect(config(), 1)?)), Duration::from_secs(1));
+//! ```
+use crate::{Executor, OrmError, OrmResult};
+use std::time::Duration;
+
+/// A message claimed from the outbox.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OutboxMessage {
+    pub id: i64,
+    pub topic: String,
+    pub payload: String,
+    /// Delivery attempts so far, including the current one.
+    pub attempts: i32,
+    /// Error from the last failed attempt (dead letters only).
+    pub last_error: Option<String>,
+}
+
+/// Handles messages of one topic. `Err` schedules a retry.
+pub type OutboxHandler = fn(&OutboxMessage) -> Result<(), String>;
+
+/// Outcome of one [`OutboxRelay::run_once`] pass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RelayReport {
+    pub sent: usize,
+    pub retried: usize,
+    pub dead: usize,
+}
+
+/// Storage operations on the `__chopin_outbox` table.
+pub struct Outbox;
+
+impl Outbox {
+    /// Creates the `__chopin_outbox` table if it does not exist.
+    pub fn ensure_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        let sql = r#"
+            CREATE TABLE IF NOT EXISTS __chopin_outbox (
+                id BIGSERIAL PRIMARY KEY,
+                topic TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                attempts INT NOT NULL DEFAULT 0,
+                last_error TEXT,
+                available_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                sent_at TIMESTAMPTZ
+            )
+        "#;
+        executor.execute(sql, &[])?;
+        executor.execute(
+            "CREATE INDEX IF NOT EXISTS __chopin_outbox_pending_idx \
+             ON __chopin_outbox (available_at) WHERE status = 'pending'",
+            &[],
+        )?;
+        Ok(())
+    }
+
+    /// Record a side effect. Call on the transaction performing the change
+    /// that triggers it. Returns the message id.
+    pub fn enqueue(executor: &mut dyn Executor, topic: &str, payload: &str) -> OrmResult<i64> {
+        let rows = executor.query(
+            "INSERT INTO __chopin_outbox (topic, payload) VALUES ($1, $2) RETURNING id",
+            &[&topic, &payload],
+        )?;
+        let row = rows.first().ok_or(OrmError::RecordNotFound)?;
+        Ok(row.get_typed_by_name("id")?)
+    }
+
+    /// Messages that exhausted their attempts, oldest first.
+    pub fn dead_letters(executor: &mut dyn Executor, limit: i64) -> OrmResult<Vec<OutboxMessage>> {
+        let rows = executor.query(
+            "SELECT id, topic, payload, attempts, last_error FROM __chopin_outbox \
+             WHERE status = 'dead' ORDER BY id LIMIT $1",
+            &[&limit],
+        )?;
+        rows.iter()
+            .map(|row| {
+                Ok(OutboxMessage {
+                    id: row.get_typed_by_name("id")?,
+                    topic: row.get_typed_by_name("topic")?,
+                    payload: row.get_typed_by_name("payload")?,
+                    attempts: row.get_typed_by_name("attempts")?,
+                    last_error: row.get_typed_by_name("last_error")?,
+                })
+            })
+            .collect()
+    }
+
+    /// Move a dead letter back to pending with a fresh attempt budget.
+    /// Returns `false` if `id` is not a dead letter.
+    pub fn requeue(executor: &mut dyn Executor, id: i64) -> OrmResult<bool> {
+        let n = executor.execute(
+            "UPDATE __chopin_outbox SET status = 'pending', attempts = 0, \
+             last_error = NULL, available_at = NOW() WHERE id = $1 AND status = 'dead'",
+            &[&id],
+        )?;
+        Ok(n > 0)
+    }
+}
+
+/// Publishes outbox messages to per-topic handlers.
+#[derive(Clone)]
+pub struct OutboxRelay {
+    handlers: Vec<(&'static str, OutboxHandler)>,
+    max_attempts: i32,
+    base_backoff: Duration,
+    lease: Duration,
+}
+
+impl Default for OutboxRelay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutboxRelay {
+    pub fn new() -> Self {
+        Self {
+            handlers: Vec::new(),
+            max_attempts: 5,
+            base_backoff: Duration::from_secs(2),
+            lease: Duration::from_secs(60),
+        }
+    }
+
+    /// Register the handler for `topic`. Messages without a handler are
+    /// dead-lettered on first claim.
+    pub fn handler(mut self, topic: &'static str, handler: OutboxHandler) -> Self {
+        self.handlers.push((topic, handler));
+        self
+    }
+
+    /// Attempts before a message is dead-lettered (default 5).
+    pub fn max_attempts(mut self, attempts: i32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry; doubled on every further failure and
+    /// capped at one hour (default 2s).
+    pub fn base_backoff(mut self, backoff: Duration) -> Self {
+        self.base_backoff = backoff;
+        self
+    }
+
+    /// How long a claimed message is hidden from other relays. A relay that
+    /// dies mid-batch releases its messages when the lease runs out
+    /// (default 60s).
+    pub fn lease(mut self, lease: Duration) -> Self {
+        self.lease = lease;
+        self
+    }
+
+    fn backoff_secs(&self, attempts: i32) -> f64 {
+        let exp = (attempts - 1).clamp(0, 20) as u32;
+        (self.base_backoff.as_secs_f64() * f64::from(2u32.pow(exp))).min(3600.0)
+    }
+
+    /// Claim up to `batch` due messages and deliver them.
+    ///
+    /// Claiming uses `FOR UPDATE SKIP LOCKED`, so several relays (one per
+    /// process or core) can run against the same table.
+    pub fn run_once(&self, executor: &mut dyn Executor, batch: i64) -> OrmResult<RelayReport> {
+        let lease = self.lease.as_secs_f64();
+        let rows = executor.query(
+            "UPDATE __chopin_outbox SET attempts = attempts + 1, \
+             available_at = NOW() + $2 * INTERVAL '1 second' \
+             WHERE id IN (SELECT id FROM __chopin_outbox \
+                 WHERE status = 'pending' AND available_at <= NOW() \
+                 ORDER BY id LIMIT $1 FOR UPDATE SKIP LOCKED) \
+             RETURNING id, topic, payload, attempts",
+            &[&batch, &lease],
+        )?;
+
+        let mut report = RelayReport::default();
+        for row in &rows {
+            let msg = OutboxMessage {
+                id: row.get_typed_by_name("id")?,
+                topic: row.get_typed_by_name("topic")?,
+                payload: row.get_typed_by_name("payload")?,
+                attempts: row.get_typed_by_name("attempts")?,
+                last_error: None,
+            };
+
+            let result = match self.handlers.iter().find(|(t, _)| *t == msg.topic) {
+                Some((_, handler)) => handler(&msg),
+                None => Err(format!("no handler for topic `{}`", msg.topic)),
+            };
+            let has_handler = self.handlers.iter().any(|(t, _)| *t == msg.topic);
+
+            match result {
+                Ok(()) => {
+                    executor.execute(
+                        "UPDATE __chopin_outbox SET status = 'sent', sent_at = NOW() WHERE id = $1",
+                        &[&msg.id],
+                    )?;
+                    report.sent += 1;
+                }
+                Err(e) if !has_handler || msg.attempts >= self.max_attempts => {
+                    #[cfg(feature = "log")]
+                    log::warn!("Outbox message {} dead-lettered: {}", msg.id, e);
+                    executor.execute(
+                        "UPDATE __chopin_outbox SET status = 'dead', last_error = $2 WHERE id = $1",
+                        &[&msg.id, &e],
+                    )?;
+                    report.dead += 1;
+                }
+                Err(e) => {
+                    let delay = self.backoff_secs(msg.attempts);
+                    executor.execute(
+                        "UPDATE __chopin_outbox SET last_error = $2, \
+                         available_at = NOW() + $3 * INTERVAL '1 second' WHERE id = $1",
+                        &[&msg.id, &e, &delay],
+                    )?;
+                    report.retried += 1;
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Run the relay on a background thread, polling every `interval`.
+    ///
+    /// The executor is opened on the relay thread by `connect` and reopened
+    /// after any error.
+    pub fn start(
+        self,
+        connect: fn() -> OrmResult<Box<dyn Executor>>,
+        interval: Duration,
+    ) -> std::thread::JoinHandle<()> {
+        std::thread::Builder::new()
+            .name("chopin-outbox-relay".into())
+            .spawn(move || {
+                let mut executor: Option<Box<dyn Executor>> = None;
+                loop {
+                    if executor.is_none() {
+                        executor = connect().ok();
+                    }
+                    if let Some(ex) = executor.as_mut() {
+                        match self.run_once(ex.as_mut(), 100) {
+                            // A full batch means there is probably more work queued.
+                            Ok(r) if r.sent + r.retried + r.dead >= 100 => continue,
+                            Ok(_) => {}
+                            Err(_e) => {
+                                #[cfg(feature = "log")]
+                                log::warn!("Outbox relay error: {}", _e);
+                                executor = None;
+                            }
+                        }
+                    }
+                    std::thread::sleep(interval);
+                }
+            })
+            .expect("failed to spawn outbox relay thread")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MockExecutor, mock_row};
+
+    fn claimed(id: i64, topic: &str, attempts: i32) -> chopin_pg::Row {
+        mock_row!("id" => id, "topic" => topic, "payload" => "{}", "attempts" => attempts)
+    }
+
+    fn ok(_: &OutboxMessage) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn fail(_: &OutboxMessage) -> Result<(), String> {
+        Err("smtp down".to_string())
+    }
+
+    #[test]
+    fn test_enqueue_returns_id() {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![mock_row!("id" => 11i64)]);
+        assert_eq!(Outbox::enqueue(&mut db, "t", "{}").unwrap(), 11);
+        assert!(
+            db.executed_queries[0]
+                .0
+                .starts_with("INSERT INTO __chopin_outbox")
+        );
+    }
+
+    #[test]
+    fn test_run_once_sends_retries_and_dead_letters() {
+        let relay = OutboxRelay::new()
+            .handler("ok", ok)
+            .handler("fail", fail)
+            .max_attempts(3);
+        let mut db = MockExecutor::new();
+        db.push_result(vec![
+            claimed(1, "ok", 1),
+            claimed(2, "fail", 1),
+            claimed(3, "fail", 3),
+            claimed(4, "unknown", 1),
+        ]);
+        let report = relay.run_once(&mut db, 10).unwrap();
+        assert_eq!(
+            report,
+            RelayReport {
+                sent: 1,
+                retried: 1,
+                dead: 2
+            }
+        );
+
+        let updates: Vec<&str> = db.executed_queries[1..]
+            .iter()
+            .map(|(sql, _)| sql.as_str())
+            .collect();
+        assert!(updates[0].contains("status = 'sent'"));
+        assert!(updates[1].contains("available_at = NOW() + $3"));
+        assert!(updates[2].contains("status = 'dead'"));
+        assert!(updates[3].contains("status = 'dead'"));
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        let relay = OutboxRelay::new().base_backoff(Duration::from_secs(2));
+        assert_eq!(relay.backoff_secs(1), 2.0);
+        assert_eq!(relay.backoff_secs(3), 8.0);
+        assert_eq!(relay.backoff_secs(30), 3600.0);
+    }
+
+    #[test]
+    fn test_dead_letters_and_requeue() {
+        let mut db = MockExecutor::new();
+        db.push_result(vec![mock_row!(
+            "id" => 5i64,
+            "topic" => "t",
+            "payload" => "{}",
+            "attempts" => 5i32,
+            "last_error" => Some("boom".to_string()),
+        )]);
+        let dead = Outbox::dead_letters(&mut db, 10).unwrap();
+        assert_eq!(dead[0].last_error.as_deref(), Some("boom"));
+        assert!(Outbox::requeue(&mut db, 5).unwrap());
+    }
+}
@@ -0,0 +1,50 @@
+//! Rust enums as model columns, via `#[derive(PgEnum)]`.
+//!
+//! ```rust,ignore
+//! #[derive(PgEnum, Debug, Clone, Copy, PartialEq)]
+//! #[model(type_name = "post_status")] // omit to store as plain text
+//! pub enum PostStatus {
+//!     Draft,                      // "draft"
+//!     #[model(rename = "live")]
+//!     Published,                  // "live"
+//! }
+//!
+//! #[derive(Model)]
+//! pub struct Post {
+//!     pub id: i64,
+//!     #[model(as = "post_status")] // or `as = "text"`
+//!     pub status: PostStatus,
+//! }
+//!
+//! PostStatus::create_type_stmt(); // CREATE TYPE post_status AS ENUM (...)
+//! ```
+//!
+//! Values are bound as untyped text, so the same enum works against both a
+//! `TEXT` column and a PostgreSQL enum column; `#[model(as = "...")]` only
+//! decides the column type used by `create_table` and `sync_schema`.
+
+/// Implemented by `#[derive(PgEnum)]` for fieldless enums.
+pub trait PgEnum: Sized + 'static {
+    /// The PostgreSQL enum type, or `None` when stored as text.
+    const TYPE_NAME: Option<&'static str>;
+    /// Database labels of every variant, in declaration order.
+    const LABELS: &'static [&'static str];
+
+    /// The database label of this variant.
+    fn label(&self) -> &'static str;
+
+    /// The variant stored as `label`, if any.
+    fn from_label(label: &str) -> Option<Self>;
+
+    /// `CREATE TYPE ... AS ENUM (...)` for [`TYPE_NAME`](Self::TYPE_NAME).
+    ///
+    /// PostgreSQL has no `CREATE TYPE IF NOT EXISTS`, so run this from a
+    /// migration rather than on every start-up.
+    fn create_type_stmt() -> Option<String> {
+        let labels: Vec<String> = Self::LABELS
+            .iter()
+            .map(|l| format!("'{}'", l.replace('\'', "''")))
+            .collect();
+        Self::TYPE_NAME.map(|ty| format!("CREATE TYPE {} AS ENUM ({})", ty, labels.join(", ")))
+    }
+}
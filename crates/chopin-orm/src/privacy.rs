@@ -0,0 +1,429 @@
+//! GDPR-style data export and erasure for user-owned records.
+//!
+//! Models that hold personal data implement [`UserData`] and are registered on a
+//! [`PrivacyRegistry`]. The registry can then:
+//!
+//! - [`export`](PrivacyRegistry::export) every row owned by a user as a single
+//!   JSON document (`{"table": [{...}, ...], ...}`), ready to be written to
+//!   storage or returned from an endpoint;
+//! - [`erase`](PrivacyRegistry::erase) a user's rows, either deleting them or
+//!   anonymizing selected columns in place.
+//!
+//! Every export and erasure is recorded in the `__chopin_privacy_audit` table.
+//! Run `erase` on a [`Transaction`](crate::Transaction) so a failure part-way
+//! through leaves no table half-erased.
+//!
+//! ```ignore
+//! impl UserData for Order {
+//!     fn owner_column() -> &'static str { "user_id" }
+//!     fn anonymize_columns() -> &'static [(&'static str, &'static str)] {
+//!         &[("shipping_address", "NULL"), ("email", "'erased@invalid'")]
+//!     }
+//! }
+//!
+//! let registry = PrivacyRegistry::new().register::<User>().register::<Order>();
+//! let json = registry.export(&mut pool, &user_id, "self-service")?;
+//! let report = registry.erase(&mut tx, &user_id, "admin:42")?;
+//! ```
+use crate::{Executor, Model, OrmResult, PgValue};
+use chopin_pg::types::ToSql;
+
+/// A model containing data owned by a single user.
+pub trait UserData: Model {
+    /// Column referencing the owning user.
+    fn owner_column() -> &'static str;
+
+    /// Columns to overwrite on erasure, as `(column, sql_expression)` pairs.
+    ///
+    /// The expressions are spliced into the `UPDATE` statement verbatim, so they
+    /// must be developer-supplied literals such as `"NULL"` or `"'erased'"`.
+    /// When empty (the default) the rows are deleted instead.
+    fn anonymize_columns() -> &'static [(&'static str, &'static str)] {
+        &[]
+    }
+}
+
+/// What happened to a registered table during [`PrivacyRegistry::erase`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErasureAction {
+    Deleted,
+    Anonymized,
+}
+
+impl ErasureAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ErasureAction::Deleted => "delete",
+            ErasureAction::Anonymized => "anonymize",
+        }
+    }
+}
+
+/// Per-table outcome of an erasure.
+#[derive(Debug, Clone)]
+pub struct ErasureReport {
+    pub tables: Vec<(&'static str, ErasureAction, u64)>,
+}
+
+impl ErasureReport {
+    /// Total number of rows deleted or anonymized.
+    pub fn total_rows(&self) -> u64 {
+        self.tables.iter().map(|(_, _, n)| n).sum()
+    }
+}
+
+struct Entry {
+    table: &'static str,
+    select: &'static str,
+    owner_column: &'static str,
+    anonymize: &'static [(&'static str, &'static str)],
+}
+
+/// Registry of models holding user-owned data.
+#[derive(Default)]
+pub struct PrivacyRegistry {
+    entries: Vec<Entry>,
+}
+
+impl PrivacyRegistry {
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Register a model. Tables are exported and erased in registration order,
+    /// so register child tables before the parents they reference.
+    pub fn register<M: UserData>(mut self) -> Self {
+        self.entries.push(Entry {
+            table: M::table_name(),
+            select: M::select_clause(),
+            owner_column: M::owner_column(),
+            anonymize: M::anonymize_columns(),
+        });
+        self
+    }
+
+    /// Names of the registered tables, in registration order.
+    pub fn tables(&self) -> Vec<&'static str> {
+        self.entries.iter().map(|e| e.table).collect()
+    }
+
+    /// Creates the `__chopin_privacy_audit` table if it does not exist.
+    pub fn ensure_audit_table(executor: &mut dyn Executor) -> OrmResult<()> {
+        let sql = r#"
+            CREATE TABLE IF NOT EXISTS __chopin_privacy_audit (
+                id BIGSERIAL PRIMARY KEY,
+                subject TEXT NOT NULL,
+                action TEXT NOT NULL,
+                table_name TEXT NOT NULL,
+                row_count BIGINT NOT NULL,
+                performed_by TEXT NOT NULL,
+                performed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+            )
+        "#;
+        executor.execute(sql, &[])?;
+        Ok(())
+    }
+
+    /// Export every registered row owned by `user_id` as a JSON document.
+    pub fn export(
+        &self,
+        executor: &mut dyn Executor,
+        user_id: &dyn ToSql,
+        performed_by: &str,
+    ) -> OrmResult<String> {
+        Self::ensure_audit_table(executor)?;
+        let subject = subject_text(user_id);
+
+        let mut out = String::from("{");
+        for (i, entry) in self.entries.iter().enumerate() {
+            let sql = format!(
+                "SELECT {} FROM {} WHERE {} = $1",
+                entry.select, entry.table, entry.owner_column
+            );
+            let rows = executor.query(&sql, &[user_id])?;
+
+            if i > 0 {
+                out.push(',');
+            }
+            push_json_string(&mut out, entry.table);
+            out.push_str(":[");
+            for (r, row) in rows.iter().enumerate() {
+                if r > 0 {
+                    out.push(',');
+                }
+                out.push('{');
+                for c in 0..row.len() {
+                    if c > 0 {
+                        out.push(',');
+                    }
+                    push_json_string(&mut out, row.column_name(c).unwrap_or_default());
+                    out.push(':');
+                    push_json_value(&mut out, &row.get(c)?);
+                }
+                out.push('}');
+            }
+            out.push(']');
+
+            record(
+                executor,
+                &subject,
+                "export",
+                entry.table,
+                rows.len() as u64,
+                performed_by,
+            )?;
+        }
+        out.push('}');
+        Ok(out)
+    }
+
+    /// Delete or anonymize every registered row owned by `user_id`.
+    pub fn erase(
+        &self,
+        executor: &mut dyn Executor,
+        user_id: &dyn ToSql,
+        performed_by: &str,
+    ) -> OrmResult<ErasureReport> {
+        Self::ensure_audit_table(executor)?;
+        let subject = subject_text(user_id);
+
+        let mut tables = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let (sql, action) = if entry.anonymize.is_empty() {
+                (
+                    format!(
+                        "DELETE FROM {} WHERE {} = $1",
+                        entry.table, entry.owner_column
+                    ),
+                    ErasureAction::Deleted,
+                )
+            } else {
+                let sets: Vec<String> = entry
+                    .anonymize
+                    .iter()
+                    .map(|(col, expr)| format!("{} = {}", col, expr))
+                    .collect();
+                (
+                    format!(
+                        "UPDATE {} SET {} WHERE {} = $1",
+                        entry.table,
+                        sets.join(", "),
+                        entry.owner_column
+                    ),
+                    ErasureAction::Anonymized,
+                )
+            };
+
+            #[cfg(feature = "log")]
+            log::info!("Privacy erasure ({}) on {}", action.as_str(), entry.table);
+            let affected = executor.execute(&sql, &[user_id])?;
+            record(
+                executor,
+                &subject,
+                action.as_str(),
+                entry.table,
+                affected,
+                performed_by,
+            )?;
+            tables.push((entry.table, action, affected));
+        }
+        Ok(ErasureReport { tables })
+    }
+}
+
+fn record(
+    executor: &mut dyn Executor,
+    subject: &str,
+    action: &str,
+    table: &str,
+    rows: u64,
+    performed_by: &str,
+) -> OrmResult<()> {
+    let rows = rows as i64;
+    executor.execute(
+        "INSERT INTO __chopin_privacy_audit (subject, action, table_name, row_count, performed_by) VALUES ($1, $2, $3, $4, $5)",
+        &[&subject, &action, &table, &rows, &performed_by],
+    )?;
+    Ok(())
+}
+
+fn subject_text(user_id: &dyn ToSql) -> String {
+    user_id
+        .to_sql()
+        .to_text_bytes()
+        .map(|b| String::from_utf8_lossy(&b).into_owned())
+        .unwrap_or_default()
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_json_value(out: &mut String, value: &PgValue) {
+    match value {
+        PgValue::Null => out.push_str("null"),
+        PgValue::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        PgValue::Int2(v) => out.push_str(&v.to_string()),
+        PgValue::Int4(v) => out.push_str(&v.to_string()),
+        PgValue::Int8(v) => out.push_str(&v.to_string()),
+        PgValue::Float4(v) if v.is_finite() => out.push_str(&v.to_string()),
+        PgValue::Float8(v) if v.is_finite() => out.push_str(&v.to_string()),
+        PgValue::Json(s) => out.push_str(s),
+        PgValue::Jsonb(b) => out.push_str(&String::from_utf8_lossy(b)),
+        PgValue::Bytes(b) => {
+            let mut hex = String::with_capacity(2 + b.len() * 2);
+            hex.push_str("\\x");
+            for byte in b {
+                hex.push_str(&format!("{:02x}", byte));
+            }
+            push_json_string(out, &hex);
+        }
+        PgValue::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                push_json_value(out, item);
+            }
+            out.push(']');
+        }
+        other => match other.to_text_bytes() {
+            Some(b) => push_json_string(out, &String::from_utf8_lossy(&b)),
+            None => out.push_str("null"),
+        },
+    }
+}
+
+#[cfg(test)]
+#[allow(dead_code)]
+mod tests {
+    use super::*;
+    use crate as chopin_orm;
+    use crate::{MockExecutor, mock_row};
+
+    #[derive(Model, Debug, Clone)]
+    #[model(table_name = "profiles")]
+    pub struct Profile {
+        #[model(primary_key)]
+        pub id: i32,
+        pub user_id: i32,
+        pub email: String,
+    }
+    impl crate::Validate for Profile {}
+    impl UserData for Profile {
+        fn owner_column() -> &'static str {
+            "user_id"
+        }
+        fn anonymize_columns() -> &'static [(&'static str, &'static str)] {
+            &[("email", "'erased@invalid'")]
+        }
+    }
+
+    #[derive(Model, Debug, Clone)]
+    #[model(table_name = "notes")]
+    pub struct Note {
+        #[model(primary_key)]
+        pub id: i32,
+        pub user_id: i32,
+        pub body: String,
+    }
+    impl crate::Validate for Note {}
+    impl UserData for Note {
+        fn owner_column() -> &'static str {
+            "user_id"
+        }
+    }
+
+    fn registry() -> PrivacyRegistry {
+        PrivacyRegistry::new()
+            .register::<Note>()
+            .register::<Profile>()
+    }
+
+    #[test]
+    fn test_export_builds_json_document() {
+        let mut mock = MockExecutor::new();
+        mock.push_result(vec![
+            mock_row!("id" => 1, "user_id" => 7, "body" => "say \"hi\""),
+            mock_row!("id" => 2, "user_id" => 7, "body" => "second"),
+        ]);
+        mock.push_result(vec![
+            mock_row!("id" => 3, "user_id" => 7, "email" => "a@b.c"),
+        ]);
+
+        let json = registry().export(&mut mock, &7i32, "user:7").unwrap();
+        assert_eq!(
+            json,
+            r#"{"notes":[{"id":1,"user_id":7,"body":"say \"hi\""},{"id":2,"user_id":7,"body":"second"}],"profiles":[{"id":3,"user_id":7,"email":"a@b.c"}]}"#
+        );
+
+        let sql: Vec<&str> = mock
+            .executed_queries
+            .iter()
+            .map(|(q, _)| q.as_str())
+            .collect();
+        assert!(sql[0].contains("__chopin_privacy_audit"));
+        assert_eq!(
+            sql[1],
+            "SELECT id, user_id, body FROM notes WHERE user_id = $1"
+        );
+        assert!(sql[2].starts_with("INSERT INTO __chopin_privacy_audit"));
+        assert_eq!(
+            sql[3],
+            "SELECT id, user_id, email FROM profiles WHERE user_id = $1"
+        );
+    }
+
+    #[test]
+    fn test_erase_deletes_or_anonymizes() {
+        let mut mock = MockExecutor::new();
+        let report = registry().erase(&mut mock, &7i32, "admin:1").unwrap();
+
+        assert_eq!(report.tables.len(), 2);
+        assert_eq!(report.tables[0].1, ErasureAction::Deleted);
+        assert_eq!(report.tables[1].1, ErasureAction::Anonymized);
+        assert_eq!(report.total_rows(), 2);
+
+        let sql: Vec<&str> = mock
+            .executed_queries
+            .iter()
+            .map(|(q, _)| q.as_str())
+            .collect();
+        assert_eq!(sql[1], "DELETE FROM notes WHERE user_id = $1");
+        assert_eq!(sql[2].matches("$").count(), 5);
+        assert_eq!(
+            sql[3],
+            "UPDATE profiles SET email = 'erased@invalid' WHERE user_id = $1"
+        );
+    }
+
+    #[test]
+    fn test_json_value_encoding() {
+        let mut out = String::new();
+        push_json_value(
+            &mut out,
+            &PgValue::Array(vec![
+                PgValue::Null,
+                PgValue::Bool(true),
+                PgValue::Bytes(vec![0xde, 0xad]),
+                PgValue::Float8(f64::NAN),
+            ]),
+        );
+        assert_eq!(out, r#"[null,true,"\\xdead","NaN"]"#);
+    }
+}
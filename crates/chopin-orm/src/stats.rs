@@ -0,0 +1,96 @@
+//! Per-thread query and cache counters.
+//!
+//! Every query run through the built-in executors, and every
+//! [`find_cached`](crate::Model::find_cached) lookup on a caching model, is
+//! counted on the calling thread. Workers serve one request at a time, so
+//! resetting at the start of a request and calling [`take`] at the end
+//! yields that request's database activity.
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueryStats {
+    pub queries: u32,
+    /// Wall time spent waiting on queries.
+    pub query_time: Duration,
+    pub cache_hits: u32,
+    pub cache_misses: u32,
+}
+
+thread_local! {
+    static STATS: Cell<QueryStats> = const { Cell::new(QueryStats {
+        queries: 0,
+        query_time: Duration::ZERO,
+        cache_hits: 0,
+        cache_misses: 0,
+    }) };
+}
+
+/// Counters accumulated on this thread since the last [`reset`] or [`take`].
+pub fn current() -> QueryStats {
+    STATS.with(Cell::get)
+}
+
+pub fn reset() {
+    STATS.with(|s| s.set(QueryStats::default()));
+}
+
+/// Return this thread's counters and reset them.
+pub fn take() -> QueryStats {
+    STATS.with(Cell::take)
+}
+
+fn update(f: impl FnOnce(&mut QueryStats)) {
+    STATS.with(|s| {
+        let mut stats = s.get();
+        f(&mut stats);
+        s.set(stats);
+    });
+}
+
+/// Run one query, counting it and its duration.
+pub(crate) fn timed<T>(f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    update(|s| {
+        s.queries += 1;
+        s.query_time += elapsed;
+    });
+    result
+}
+
+pub(crate) fn record_cache(hit: bool) {
+    update(|s| {
+        if hit {
+            s.cache_hits += 1;
+        } else {
+            s.cache_misses += 1;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Executor, MockExecutor};
+
+    #[test]
+    fn test_counts_queries_per_thread() {
+        reset();
+        let mut db = MockExecutor::new();
+        db.execute("UPDATE t SET a = 1", &[]).unwrap();
+        db.query("SELECT 1", &[]).unwrap();
+        record_cache(true);
+        record_cache(false);
+
+        let stats = take();
+        assert_eq!(stats.queries, 2);
+        assert_eq!((stats.cache_hits, stats.cache_misses), (1, 1));
+        assert_eq!(current(), QueryStats::default());
+
+        std::thread::spawn(|| assert_eq!(current().queries, 0))
+            .join()
+            .unwrap();
+    }
+}
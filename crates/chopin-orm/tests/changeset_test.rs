@@ -0,0 +1,115 @@
+use chopin_orm::{Changeset, MockExecutor, Model, ModelPatch, OrmError, mock_row};
+use serde_json::json;
+
+#[derive(Model, ModelPatch, Debug, Clone, PartialEq)]
+#[model(table_name = "cs_posts")]
+pub struct Post {
+    pub id: i64,
+    pub title: String,
+    pub summary: Option<String>,
+    pub views: i32,
+    #[model(hidden)]
+    pub secret: String,
+}
+
+impl chopin_orm::Validate for Post {
+    fn validate(&self) -> Result<(), Vec<String>> {
+        match self.title.is_empty() {
+            true => Err(vec!["title must not be empty".into()]),
+            false => Ok(()),
+        }
+    }
+}
+
+fn post() -> Post {
+    Post {
+        id: 4,
+        title: "Draft".into(),
+        summary: Some("short".into()),
+        views: 10,
+        secret: "s3cret".into(),
+    }
+}
+
+fn patch(body: serde_json::Value) -> PostPatch {
+    serde_json::from_value(body).unwrap()
+}
+
+#[test]
+fn test_missing_keys_and_nulls() {
+    let p = patch(json!({ "title": "Final", "summary": null }));
+    assert_eq!(p.title.as_deref(), Some("Final"));
+    assert_eq!(p.summary, Some(None));
+    assert_eq!(p.views, None);
+
+    let p = patch(json!({}));
+    assert_eq!(p.summary, None);
+}
+
+#[test]
+fn test_keys_and_hidden_fields_are_not_patchable() {
+    for body in [
+        json!({ "id": 9 }),
+        json!({ "secret": "x" }),
+        json!({ "bogus": 1 }),
+    ] {
+        assert!(serde_json::from_value::<PostPatch>(body).is_err());
+    }
+}
+
+#[test]
+fn test_tracks_only_real_changes() {
+    let cs = Changeset::new(post())
+        .apply(patch(json!({ "title": "Draft", "summary": null })))
+        .apply(patch(json!({ "views": 11, "summary": "again" })));
+    assert_eq!(cs.changed_columns(), &["summary", "views"]);
+    assert_eq!(cs.model().summary.as_deref(), Some("again"));
+    assert_eq!(cs.model().title, "Draft");
+}
+
+#[test]
+fn test_save_updates_changed_columns() {
+    let mut mock = MockExecutor::new();
+    mock.push_result(vec![mock_row!(
+        "id" => 4_i64,
+        "title" => "Final",
+        "summary" => "short",
+        "views" => 10,
+        "secret" => "s3cret",
+    )]);
+
+    let saved = Changeset::new(post())
+        .apply(patch(json!({ "title": "Final" })))
+        .save(&mut mock)
+        .unwrap();
+    assert_eq!(saved.title, "Final");
+    assert_eq!(
+        mock.executed_queries[0],
+        (
+            "UPDATE cs_posts SET title = $1 WHERE id = $2 \
+             RETURNING id, title, summary, views, secret"
+                .to_string(),
+            2
+        )
+    );
+}
+
+#[test]
+fn test_save_without_changes_is_a_no_op() {
+    let mut mock = MockExecutor::new();
+    let cs = Changeset::new(post()).apply(PostPatch::default());
+    assert!(!cs.has_changes());
+    assert_eq!(cs.save(&mut mock).unwrap(), post());
+    assert!(mock.executed_queries.is_empty());
+}
+
+#[test]
+fn test_save_validates_first() {
+    let mut mock = MockExecutor::new();
+    let err = Changeset::new(post())
+        .apply(patch(json!({ "title": "" })))
+        .save(&mut mock)
+        .unwrap_err();
+    assert!(matches!(err, OrmError::Validation(_)));
+    assert!(mock.executed_queries.is_empty());
+}
@@ -0,0 +1,41 @@
+use chopin_orm::builder::{Column, ColumnTrait};
+use chopin_orm::{MockExecutor, Model};
+
+#[derive(Model, Debug, Clone)]
+#[model(table_name = "posts", columns_module)]
+pub struct Post {
+    pub id: i64,
+    pub title: String,
+    pub views: Option<i32>,
+}
+impl chopin_orm::Validate for Post {}
+
+#[test]
+fn test_associated_column_constants() {
+    let title: Column<Post, String> = Post::TITLE;
+    assert_eq!(title.name(), "title");
+    assert_eq!(Post::VIEWS.column_name(), "views");
+}
+
+#[test]
+fn test_columns_module_matches_constants() {
+    assert_eq!(post::columns::ID.name(), Post::ID.name());
+    assert_eq!(post::columns::TITLE.name(), "title");
+}
+
+#[test]
+fn test_filter_with_column_constants() {
+    let mut mock = MockExecutor::new();
+    mock.push_result(vec![]);
+    Post::find()
+        .filter(Post::TITLE.eq("Hello"))
+        .filter(post::columns::VIEWS.gt(10))
+        .all(&mut mock)
+        .unwrap();
+    let (sql, params) = &mock.executed_queries[0];
+    assert_eq!(
+        sql,
+        "SELECT id, title, views FROM posts WHERE title = $1 AND views > $2"
+    );
+    assert_eq!(*params, 2);
+}
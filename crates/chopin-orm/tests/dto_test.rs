@@ -0,0 +1,56 @@
+use chopin_orm::{Model, ModelDto};
+use serde_json::json;
+
+#[derive(Model, ModelDto, Debug, Clone)]
+#[model(table_name = "dto_users")]
+pub struct User {
+    #[model(primary_key)]
+    pub id: i64,
+    /// Login address.
+    pub email: String,
+    pub nickname: Option<String>,
+    pub score: f64,
+    #[model(hidden)]
+    pub password_hash: String,
+}
+impl chopin_orm::Validate for User {}
+
+fn user() -> User {
+    User {
+        id: 7,
+        email: "ann@example.com".into(),
+        nickname: None,
+        score: 1.5,
+        password_hash: "$argon2id$...".into(),
+    }
+}
+
+#[test]
+fn test_response_omits_hidden_fields() {
+    let body: UserResponse = user().into();
+    assert_eq!(
+        serde_json::to_value(&body).unwrap(),
+        json!({ "id": 7, "email": "ann@example.com", "nickname": null, "score": 1.5 })
+    );
+    // Hidden fields are still ordinary model columns.
+    assert!(User::columns().contains(&"password_hash"));
+}
+
+#[test]
+fn test_response_schema() {
+    assert_eq!(UserResponse::NAME, "UserResponse");
+    let schema: serde_json::Value = serde_json::from_str(UserResponse::SCHEMA).unwrap();
+    assert_eq!(
+        schema,
+        json!({
+            "type": "object",
+            "properties": {
+                "id": { "type": "integer", "format": "int64" },
+                "email": { "type": "string" },
+                "nickname": { "type": "string", "nullable": true },
+                "score": { "type": "number", "format": "double" },
+            },
+            "required": ["id", "email", "score"],
+        })
+    );
+}
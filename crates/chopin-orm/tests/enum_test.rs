@@ -0,0 +1,85 @@
+use chopin_orm::{ExtractValue, FromRow, MockExecutor, Model, PgEnum, PgValue, ToSql, mock_row};
+use chopin_pg::FromSql;
+
+#[derive(PgEnum, Debug, Clone, Copy, PartialEq)]
+#[model(type_name = "ticket_status")]
+pub enum Status {
+    Open,
+    InReview,
+    #[model(rename = "done")]
+    Closed,
+}
+
+#[derive(PgEnum, Debug, Clone, Copy, PartialEq)]
+pub enum Priority {
+    Low,
+    High,
+}
+
+#[derive(Model, Debug, Clone)]
+#[model(table_name = "tickets")]
+pub struct Ticket {
+    pub id: i64,
+    #[model(as = "ticket_status")]
+    pub status: Status,
+    #[model(as = "text")]
+    pub priority: Option<Priority>,
+}
+impl chopin_orm::Validate for Ticket {}
+
+#[test]
+fn test_labels() {
+    assert_eq!(Status::LABELS, &["open", "in_review", "done"]);
+    assert_eq!(Status::InReview.label(), "in_review");
+    assert_eq!(Status::from_label("done"), Some(Status::Closed));
+    assert_eq!(Status::from_label("Closed"), None);
+    assert_eq!(
+        Status::create_type_stmt().unwrap(),
+        "CREATE TYPE ticket_status AS ENUM ('open', 'in_review', 'done')"
+    );
+    assert_eq!(Priority::TYPE_NAME, None);
+    assert_eq!(Priority::create_type_stmt(), None);
+}
+
+#[test]
+fn test_bind_and_extract() {
+    assert_eq!(Status::Closed.to_sql(), PgValue::Text("done".into()));
+    // Untyped, so the server coerces to either TEXT or the enum type.
+    assert_eq!(Status::Closed.type_oid(), 0);
+    assert_eq!(
+        Status::from_sql(&PgValue::Text("open".into())).unwrap(),
+        Status::Open
+    );
+    assert!(Status::from_sql(&PgValue::Text("nope".into())).is_err());
+    assert!(Priority::from_pg_value(PgValue::Int4(1)).is_err());
+
+    let row = mock_row!("id" => 1_i64, "status" => "in_review", "priority" => PgValue::Null);
+    let ticket = Ticket::from_row(&row).unwrap();
+    assert_eq!(ticket.status, Status::InReview);
+    assert_eq!(ticket.priority, None);
+}
+
+#[test]
+fn test_column_types() {
+    let stmt = Ticket::create_table_stmt();
+    assert!(stmt.contains("status ticket_status NOT NULL"), "{stmt}");
+    assert!(stmt.contains("priority TEXT\n"), "{stmt}");
+
+    let mut db = MockExecutor::new();
+    db.push_result(vec![mock_row!("id" => 5_i64)]);
+    let mut ticket = Ticket {
+        id: 0,
+        status: Status::Open,
+        priority: Some(Priority::High),
+    };
+    ticket.insert(&mut db).unwrap();
+    assert_eq!(ticket.id, 5);
+    assert_eq!(
+        ticket.get_values(),
+        vec![
+            PgValue::Int8(5),
+            PgValue::Text("open".into()),
+            PgValue::Text("high".into())
+        ]
+    );
+}
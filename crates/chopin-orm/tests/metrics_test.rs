@@ -0,0 +1,38 @@
+#![cfg(feature = "metrics")]
+
+use chopin_orm::metrics::{self, Op};
+use chopin_orm::{MockExecutor, Model, mock_row};
+
+#[derive(Model, Debug, Clone, PartialEq)]
+#[model(table_name = "metered_users")]
+pub struct User {
+    #[model(primary_key)]
+    pub id: i64,
+    pub name: String,
+}
+
+impl chopin_orm::Validate for User {}
+
+fn count(op: Op) -> u64 {
+    metrics::snapshot()
+        .iter()
+        .find(|m| m.table == "metered_users" && m.op == op)
+        .map_or(0, |m| m.count)
+}
+
+#[test]
+fn test_model_methods_are_counted_per_table() {
+    let mut db = MockExecutor::new();
+    db.push_result(vec![mock_row!("id" => 1_i64, "name" => "Ada")]);
+    db.push_result(vec![mock_row!("count" => 1_i64)]);
+
+    let user = User::find().one(&mut db).unwrap().unwrap();
+    assert_eq!(User::find().count(&mut db).unwrap(), 1);
+    user.update(&mut db).unwrap();
+    user.delete(&mut db).unwrap();
+
+    assert_eq!(count(Op::Select), 2);
+    assert_eq!(count(Op::Update), 1);
+    assert_eq!(count(Op::Delete), 1);
+    assert_eq!(count(Op::Insert), 0);
+}
@@ -0,0 +1,59 @@
+use chopin_orm::{Model, naming};
+
+#[derive(Model, Debug, Clone)]
+pub struct BlogPost {
+    pub id: i64,
+}
+
+#[derive(Model, Debug, Clone)]
+#[model(table_naming = "snake_case")]
+pub struct BlogCategory {
+    pub id: i64,
+}
+
+#[derive(Model, Debug, Clone)]
+#[model(
+    table_naming = "snake_case",
+    plural_tables = false,
+    table_prefix = "cms_"
+)]
+pub struct PageRevision {
+    pub id: i64,
+}
+
+#[derive(Model, Debug, Clone)]
+#[model(table_naming = "lowercase", table_prefix = "hr_")]
+pub struct Person {
+    pub id: i64,
+}
+
+#[derive(Model, Debug, Clone)]
+#[model(table_naming = "snake_case", table_name = "legacy_items")]
+pub struct Item {
+    pub id: i64,
+}
+
+macro_rules! no_validation {
+    ($($model:ty),*) => { $(impl chopin_orm::Validate for $model {})* };
+}
+no_validation!(BlogPost, BlogCategory, PageRevision, Person, Item);
+
+#[test]
+fn test_default_naming_is_unchanged() {
+    assert_eq!(BlogPost::table_name(), "blogposts");
+}
+
+#[test]
+fn test_struct_level_naming() {
+    assert_eq!(BlogCategory::table_name(), "blog_categories");
+    assert_eq!(PageRevision::table_name(), "cms_page_revision");
+    assert_eq!(Person::table_name(), "hr_people");
+    assert_eq!(Item::table_name(), "legacy_items");
+    assert!(BlogCategory::create_table_stmt().contains("blog_categories"));
+}
+
+#[test]
+fn test_derive_matches_runtime_helpers() {
+    let expected = naming::pluralize(&naming::snake_case("BlogCategory"));
+    assert_eq!(BlogCategory::table_name(), expected);
+}
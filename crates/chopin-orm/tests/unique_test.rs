@@ -0,0 +1,82 @@
+use chopin_orm::{MockExecutor, Model, mock_row};
+
+#[derive(Model, Debug, Clone, PartialEq)]
+#[model(table_name = "articles")]
+pub struct Article {
+    pub id: i64,
+    #[model(unique)]
+    pub slug: String,
+    pub title: String,
+}
+impl chopin_orm::Validate for Article {}
+
+fn article_row(id: i64, slug: &str) -> chopin_pg::Row {
+    mock_row!("id" => id, "slug" => slug, "title" => "Hello")
+}
+
+#[test]
+fn test_unique_column_definition() {
+    assert_eq!(
+        Article::create_table_stmt(),
+        "CREATE TABLE IF NOT EXISTS articles (\n    id BIGSERIAL PRIMARY KEY,\n    slug TEXT NOT NULL UNIQUE,\n    title TEXT NOT NULL\n)"
+    );
+}
+
+#[test]
+fn test_find_by_unique_column() {
+    let mut mock = MockExecutor::new();
+    mock.push_result(vec![article_row(7, "hello-world")]);
+    mock.push_result(vec![]);
+
+    let found = Article::find_by_slug(&mut mock, "hello-world").unwrap();
+    assert_eq!(found.map(|a| a.id), Some(7));
+    assert!(
+        Article::find_by_slug(&mut mock, "missing")
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(
+        mock.executed_queries[0],
+        (
+            "SELECT id, slug, title FROM articles WHERE slug = $1 LIMIT 1".to_string(),
+            1
+        )
+    );
+}
+
+#[test]
+fn test_get_or_create_returns_existing_row() {
+    let mut mock = MockExecutor::new();
+    mock.push_result(vec![article_row(3, "hello")]);
+
+    let draft = Article {
+        id: 0,
+        slug: "hello".into(),
+        title: "Ignored".into(),
+    };
+    let (article, created) = Article::get_or_create_by_slug(&mut mock, draft).unwrap();
+    assert!(!created);
+    assert_eq!((article.id, article.title.as_str()), (3, "Hello"));
+    assert_eq!(mock.executed_queries.len(), 1);
+}
+
+#[test]
+fn test_get_or_create_inserts_missing_row() {
+    let mut mock = MockExecutor::new();
+    mock.push_result(vec![]);
+    mock.push_result(vec![mock_row!("id" => 9_i64)]);
+
+    let draft = Article {
+        id: 0,
+        slug: "fresh".into(),
+        title: "Fresh".into(),
+    };
+    let (article, created) = Article::get_or_create_by_slug(&mut mock, draft).unwrap();
+    assert!(created);
+    assert_eq!(article.id, 9);
+    assert!(
+        mock.executed_queries[1]
+            .0
+            .starts_with("INSERT INTO articles")
+    );
+}
@@ -343,6 +343,22 @@ pub fn md5_password_hash(user: &str, password: &str, salt: &[u8; 4]) -> String {
     format!("md5{}", outer_hex)
 }
 
+/// Human-readable name of an `AuthenticationRequest` code, for errors
+/// about methods this client does not implement.
+pub(crate) fn method_name(code: i32) -> &'static str {
+    match code {
+        0 => "trust",
+        2 => "Kerberos V5",
+        3 => "cleartext password",
+        5 => "MD5 password",
+        6 => "SCM credentials",
+        7 | 8 => "GSSAPI",
+        9 => "SSPI",
+        10..=12 => "SASL",
+        _ => "unknown",
+    }
+}
+
 /// Lowercase hex encoding.
 fn hex_encode_lower(bytes: &[u8]) -> String {
     const HEX: &[u8; 16] = b"0123456789abcdef";
@@ -920,6 +936,7 @@ mod tests {
         let result = md5_password_hash("user", "pass", &[0x01, 0x02, 0x03, 0x04]);
         assert!(result.starts_with("md5"), "Must start with 'md5'");
         assert_eq!(result.len(), 35, "md5 + 32 hex chars");
+        assert_eq!(result, "md56cf524962d8413e6b0cdf79fddff891c");
         // Verify determinism
         let result2 = md5_password_hash("user", "pass", &[0x01, 0x02, 0x03, 0x04]);
         assert_eq!(result, result2);
@@ -932,6 +949,13 @@ mod tests {
         assert_ne!(r1, r2, "Different salts must produce different hashes");
     }
 
+    #[test]
+    fn test_method_name() {
+        assert_eq!(method_name(5), "MD5 password");
+        assert_eq!(method_name(7), "GSSAPI");
+        assert_eq!(method_name(42), "unknown");
+    }
+
     // ─── SCRAM-SHA-256-PLUS channel binding (B.3) ────────────────────────────
 
     #[test]
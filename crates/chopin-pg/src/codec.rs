@@ -388,6 +388,13 @@ pub fn read_cstring(buf: &[u8], offset: usize) -> (&str, usize) {
     (s, end - start + 1) // +1 for null terminator
 }
 
+/// Parse a ParameterStatus message body into `(name, value)`.
+pub fn parse_parameter_status(body: &[u8]) -> (String, String) {
+    let (name, consumed) = read_cstring(body, 0);
+    let (value, _) = read_cstring(body, consumed);
+    (name.to_string(), value.to_string())
+}
+
 /// Parse an ErrorResponse or NoticeResponse message body.
 /// Returns a list of (field_type, value) pairs.
 pub fn parse_error_fields(body: &[u8]) -> Vec<(u8, String)> {
@@ -443,6 +450,12 @@ pub fn parse_row_description(body: &[u8]) -> Vec<ColumnDesc> {
     columns
 }
 
+/// Parse a ParameterDescription message body into parameter type OIDs.
+pub fn parse_parameter_description(body: &[u8]) -> Vec<u32> {
+    let count = read_i16(body, 0).max(0) as usize;
+    (0..count).map(|i| read_u32(body, 2 + i * 4)).collect()
+}
+
 /// Parse a DataRow message body. Returns column byte slices.
 /// Each column is Option<&[u8]> where None = SQL NULL.
 pub fn parse_data_row(body: &[u8]) -> Vec<Option<&[u8]>> {
@@ -464,18 +477,42 @@ pub fn parse_data_row(body: &[u8]) -> Vec<Option<&[u8]>> {
     columns
 }
 
-/// A column descriptor from RowDescription.
+/// A column descriptor from RowDescription, available on every row with
+/// [`Row::columns`](crate::Row::columns).
 #[derive(Debug, Clone)]
 pub struct ColumnDesc {
+    /// Column name or alias as the server reports it (`?column?` for
+    /// unnamed expressions).
     pub name: String,
+    /// OID of the table the column comes from, or 0 for computed columns.
     pub table_oid: u32,
+    /// Attribute number of the column within that table, or 0.
     pub col_attr: i16,
     pub type_oid: u32,
+    /// `pg_type.typlen`: fixed size in bytes, or negative for variable size.
     pub type_size: i16,
+    /// `pg_attribute.atttypmod`, e.g. the length of a `varchar(n)`; -1 if none.
     pub type_modifier: i32,
+    /// Wire format of the values. Parameterized queries always ask for
+    /// binary results; simple queries return text.
     pub format_code: FormatCode,
 }
 
+impl ColumnDesc {
+    /// Name of a built-in type in `pg_type.typname` spelling (`int4`,
+    /// `_text` for `text[]`), or `None` for types the driver does not know,
+    /// such as enums and domains.
+    pub fn type_name(&self) -> Option<&'static str> {
+        crate::types::oid::name(self.type_oid)
+    }
+
+    /// Table OID and attribute number of the column, if it comes straight
+    /// from a table.
+    pub fn source(&self) -> Option<(u32, i16)> {
+        (self.table_oid != 0).then_some((self.table_oid, self.col_attr))
+    }
+}
+
 // ─── Helper Functions ──────────────────────────────────────────
 
 fn put_i32(buf: &mut [u8], offset: usize, value: i32) {
@@ -840,6 +877,16 @@ mod tests {
         assert!(matches!(cols[0].format_code, FormatCode::Binary));
     }
 
+    #[test]
+    fn test_parse_parameter_description() {
+        let mut body = vec![];
+        body.extend_from_slice(&2i16.to_be_bytes());
+        body.extend_from_slice(&23i32.to_be_bytes()); // INT4
+        body.extend_from_slice(&25i32.to_be_bytes()); // TEXT
+        assert_eq!(parse_parameter_description(&body), vec![23, 25]);
+        assert!(parse_parameter_description(&0i16.to_be_bytes()).is_empty());
+    }
+
     #[test]
     fn test_parse_error_fields_basic() {
         // Severity='S', Code='C', Message='M', terminator='\0'
@@ -865,6 +912,13 @@ mod tests {
         assert!(fields.is_empty());
     }
 
+    #[test]
+    fn test_parse_parameter_status() {
+        let (name, value) = parse_parameter_status(b"TimeZone\0Europe/Paris\0");
+        assert_eq!(name, "TimeZone");
+        assert_eq!(value, "Europe/Paris");
+    }
+
     // ─── Helper read functions ────────────────────────────────────────────────
 
     #[test]
@@ -21,7 +21,8 @@ pub enum PgError {
     BufferOverflow,
     /// Would block — operation cannot complete without blocking.
     WouldBlock,
-    /// I/O operation timed out (application-level timeout).
+    /// I/O operation timed out (application-level timeout), or a query run
+    /// with `query_with_timeout` / `execute_with_timeout` hit its limit.
     Timeout,
     /// Pool: timed out waiting for a connection.
     PoolTimeout,
@@ -53,6 +54,15 @@ pub struct ServerError {
     pub routine: Option<String>,
 }
 
+/// SQLSTATE codes for the integrity constraint violations (class 23).
+pub mod sqlstate {
+    pub const NOT_NULL_VIOLATION: &str = "23502";
+    pub const FOREIGN_KEY_VIOLATION: &str = "23503";
+    pub const UNIQUE_VIOLATION: &str = "23505";
+    pub const CHECK_VIOLATION: &str = "23514";
+    pub const EXCLUSION_VIOLATION: &str = "23P01";
+}
+
 /// Error classification for retry logic.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ErrorClass {
@@ -115,6 +125,69 @@ impl PgError {
         }
     }
 
+    /// The full server error, if this is one.
+    pub fn server(&self) -> Option<&ServerError> {
+        match self {
+            PgError::Server(err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Name of the constraint the statement violated, if the server sent one.
+    pub fn constraint(&self) -> Option<&str> {
+        self.server()?.constraint_name.as_deref()
+    }
+
+    /// Table the error is about, if the server sent one.
+    pub fn table(&self) -> Option<&str> {
+        self.server()?.table_name.as_deref()
+    }
+
+    /// Column the error is about, if the server sent one.
+    pub fn column(&self) -> Option<&str> {
+        self.server()?.column_name.as_deref()
+    }
+
+    /// Schema of the table the error is about, if the server sent one.
+    pub fn schema(&self) -> Option<&str> {
+        self.server()?.schema_name.as_deref()
+    }
+
+    /// 1-based character offset into the query text where the error occurred.
+    pub fn position(&self) -> Option<i32> {
+        self.server()?.position
+    }
+
+    /// SQLSTATE class 23 — any integrity constraint violation.
+    pub fn is_constraint_violation(&self) -> bool {
+        self.sql_state().is_some_and(|c| c.starts_with("23"))
+    }
+
+    /// SQLSTATE 23505 — a unique index or primary key already holds the value.
+    pub fn is_unique_violation(&self) -> bool {
+        self.sql_state() == Some(sqlstate::UNIQUE_VIOLATION)
+    }
+
+    /// SQLSTATE 23503 — the referenced row is missing, or still referenced.
+    pub fn is_foreign_key_violation(&self) -> bool {
+        self.sql_state() == Some(sqlstate::FOREIGN_KEY_VIOLATION)
+    }
+
+    /// SQLSTATE 23502 — NULL written to a `NOT NULL` column.
+    pub fn is_not_null_violation(&self) -> bool {
+        self.sql_state() == Some(sqlstate::NOT_NULL_VIOLATION)
+    }
+
+    /// SQLSTATE 23514 — a `CHECK` constraint rejected the row.
+    pub fn is_check_violation(&self) -> bool {
+        self.sql_state() == Some(sqlstate::CHECK_VIOLATION)
+    }
+
+    /// SQLSTATE 23P01 — an exclusion constraint rejected the row.
+    pub fn is_exclusion_violation(&self) -> bool {
+        self.sql_state() == Some(sqlstate::EXCLUSION_VIOLATION)
+    }
+
     /// Build a Server error from parsed error/notice fields.
     pub fn from_fields(fields: &[(u8, String)]) -> Self {
         let mut severity = String::new();
@@ -512,6 +585,58 @@ mod tests {
         assert!(matches!(e, PgError::Server(_)));
     }
 
+    // ─── Constraint Helpers ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_constraint_violation_helpers() {
+        assert!(server_err("23505").is_unique_violation());
+        assert!(server_err("23503").is_foreign_key_violation());
+        assert!(server_err("23502").is_not_null_violation());
+        assert!(server_err("23514").is_check_violation());
+        assert!(server_err("23P01").is_exclusion_violation());
+        for code in ["23502", "23503", "23505", "23514", "23P01"] {
+            assert!(server_err(code).is_constraint_violation(), "{code}");
+        }
+        assert!(!server_err("23503").is_unique_violation());
+        assert!(!server_err("42601").is_constraint_violation());
+        assert!(!PgError::ConnectionClosed.is_constraint_violation());
+        assert!(!PgError::Timeout.is_unique_violation());
+    }
+
+    #[test]
+    fn test_field_accessors() {
+        let e = PgError::from_fields(&[
+            (b'S', "ERROR".to_string()),
+            (b'C', "23505".to_string()),
+            (
+                b'M',
+                "duplicate key value violates unique constraint".to_string(),
+            ),
+            (
+                b'D',
+                "Key (email)=(a@example.com) already exists.".to_string(),
+            ),
+            (b'P', "12".to_string()),
+            (b's', "public".to_string()),
+            (b't', "users".to_string()),
+            (b'c', "email".to_string()),
+            (b'n', "users_email_key".to_string()),
+        ]);
+        assert!(e.is_unique_violation());
+        assert_eq!(e.constraint(), Some("users_email_key"));
+        assert_eq!(e.table(), Some("users"));
+        assert_eq!(e.column(), Some("email"));
+        assert_eq!(e.schema(), Some("public"));
+        assert_eq!(e.position(), Some(12));
+        assert_eq!(e.server().unwrap().severity, "ERROR");
+
+        let other = PgError::NoRows;
+        assert!(other.server().is_none());
+        assert!(other.constraint().is_none());
+        assert!(other.table().is_none());
+        assert!(other.position().is_none());
+    }
+
     // ─── Display Format ───────────────────────────────────────────────────────
 
     #[test]
@@ -7,18 +7,39 @@
 //! - **Thread-per-core**: Each worker owns its own PG connections.
 //! - **Non-blocking I/O**: Socket is set to non-blocking after connect; all
 //!   reads/writes go through poll-based primitives with configurable timeouts.
+//! - **Event-loop integration**: `start_query` returns a `PendingQuery` that
+//!   never blocks, for driving from a worker's own epoll/kqueue loop.
 //! - **Zero-copy**: Row data is sliced directly from the read buffer.
 //! - **SCRAM-SHA-256**: Full authentication support.
-//! - **Extended Query Protocol**: Parse/Bind/Execute with implicit caching.
+//! - **Extended Query Protocol**: Parse/Bind/Execute with implicit caching,
+//!   or unnamed statements only behind PgBouncer in transaction mode.
 //! - **Transaction support**: Safe closure-based API with auto-rollback.
 //! - **COPY protocol**: Both COPY IN (writer) and COPY OUT (reader).
+//! - **Server-side cursors**: `DECLARE`/`FETCH` in batches for very large results.
+//! - **Suspended portals**: `Execute` with a row limit, resumed batch by batch.
 //! - **LISTEN/NOTIFY**: Notification buffering during query processing.
-//! - **Rich types**: UUID, Date, Time, Timestamp, Interval, Numeric, INET, Arrays.
+//! - **Logical replication**: `replication=database` connections, slots, and
+//!   a keepalive-answering stream of decoded `pgoutput` changes for CDC.
+//! - **Rich types**: UUID, Date, Time, Timestamp, Interval, Numeric, INET, Arrays,
+//!   serde-backed JSON/JSONB with the `json` feature, and `chrono` / `time`
+//!   date-time conversions behind the features of the same name.
 //! - **Binary INET/CIDR**: Proper binary encoding/decoding for network types.
 //! - **Type-safe queries**: `ToSql`/`FromSql` traits for ergonomic parameter passing.
 //! - **Connection pool**: Worker-local pool with RAII `ConnectionGuard`, FIFO idle
 //!   queue, `try_get()` / `get()` with timeout, and automatic return on drop.
+//! - **Async checkout**: With the `tokio` feature, a thread-safe
+//!   `SharedPool` whose `get_async()` suits tokio
+//!   servers such as Axum.
 //! - **Error classification**: Transient vs permanent errors for retry logic.
+//! - **Retries**: Opt-in [`RetryPolicy`] rerunning serialization failures,
+//!   deadlocks and lost connections with jittered backoff and a budget.
+//!
+//! ## Round-trips
+//! Every query is its own Parse/Bind/Execute/Sync round-trip. Pipeline mode
+//! (several queries written before one `Sync`) was removed on purpose: the
+//! TechEmpower rules the driver is benchmarked under require a separate
+//! round-trip per query, so it is not offered. Use `COPY` or a single
+//! multi-row statement to cut round-trips for bulk work.
 //!
 //! ## Quick Start
 //! ```ignore
@@ -58,17 +79,35 @@ pub mod connection;
 pub mod error;
 pub mod pool;
 pub mod protocol;
+pub mod replication;
+pub mod retry;
 pub mod row;
+#[cfg(feature = "tokio")]
+pub mod shared;
 pub mod statement;
 #[cfg(feature = "tls")]
 pub mod tls;
 pub mod types;
 
-pub use connection::{CopyReader, CopyWriter, Notification, PgConfig, PgConnection, Transaction};
-pub use error::{ErrorClass, PgError, PgResult};
-pub use pool::{ConnectionGuard, PgPool, PgPoolConfig, PoolStats};
+pub use codec::ColumnDesc;
+pub use connection::{
+    CopyReader, CopyWriter, Cursor, Interest, NoticeHandler, Notification, PendingQuery, PgConfig,
+    PgConnection, Portal, ReplicationStream, TargetSessionAttrs, Transaction, log_notice,
+};
+pub use error::{ErrorClass, PgError, PgResult, ServerError};
+pub use pool::{ConnectionGuard, PerCorePool, PgPool, PgPoolConfig, PoolStats};
+pub use protocol::FormatCode;
+pub use replication::{Lsn, PgOutputMessage};
+pub use retry::{RetryBudget, RetryPolicy};
 pub use row::Row;
-pub use statement::Statement;
+#[cfg(feature = "tokio")]
+pub use shared::{Acquired, SharedPool};
+pub use statement::{Statement, StatementCacheStats};
 #[cfg(feature = "tls")]
 pub use tls::SslMode;
-pub use types::{FromSql, PgValue, ToParam, ToSql, TypeRegistry, encode_inet_binary};
+#[cfg(feature = "json")]
+pub use types::Json;
+pub use types::{
+    CompositeField, FromSql, PgRange, PgValue, Record, ToParam, ToSql, TypeRegistry,
+    encode_inet_binary,
+};
@@ -8,26 +8,53 @@
 //! - Idle connections are kept in a **FIFO queue** (`VecDeque`).
 //! - `get()` / `try_get()` return a [`ConnectionGuard`] that automatically
 //!   returns the connection to the idle queue when dropped.
-//! - A waiter queue allows callers to register interest when the pool is
-//!   exhausted; the next `ConnectionGuard` drop will service the first waiter.
 //! - Connections are validated on checkout (optional) and reaped on idle /
 //!   lifetime expiry.
 //!
+//! ## Sizing
+//!
+//! The pool grows on demand up to `max_size`. Every `reap_interval`, the next
+//! checkout runs [`PgPool::reap`]. That closes idle connections past
+//! `idle_timeout` until only `min_size` remain. It also closes every
+//! connection older than `max_lifetime` and opens replacements to keep
+//! `min_size` warm. A checked-out connection that outlives `max_lifetime`
+//! is closed when it is returned, so no session lasts much longer than the
+//! limit. Set the limit below any load balancer or PgBouncer timeout that
+//! kills long-lived server connections.
+//!
+//! ## Exhaustion
+//!
+//! A [`ConnectionGuard`] borrows the pool mutably, so nothing can return a
+//! connection while its owner is asking for another one. Waiting would only
+//! burn the timeout: when every connection is checked out, [`PgPool::get`]
+//! fails at once with `PoolTimeout`, and `try_get()` with `PoolExhausted`.
+//! Size the pool for
+//! the connections one worker holds at a time. Tasks that share a pool and
+//! should queue for it fairly, with `checkout_timeout`, use
+//! [`SharedPool`](crate::SharedPool) (`tokio` feature).
+//!
 //! ## Features
 //! - Lazy and eager connection initialization
-//! - `try_get()` — non-blocking, returns `WouldBlock` if exhausted
-//! - `get()` — blocks with timeout, returns `PoolTimeout` if exceeded
+//! - `try_get()` — non-blocking, returns `PoolExhausted` if exhausted
+//! - `get()` — fails fast too, returning `PoolTimeout` if exhausted
 //! - RAII `ConnectionGuard` — connection returned on drop
 //! - Connection validation (`test_on_checkout`)
 //! - Max lifetime and idle timeout
 //! - Automatic reconnection on stale connections
 //! - Graceful shutdown via `close_all()`
+//! - Per-core sharding via [`PgPool::per_core`]
+//! - Opt-in retries of serialization failures, deadlocks and lost
+//!   connections via [`PgPool::run`] / [`PgPool::transaction`]
 
+use std::cell::RefCell;
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
-use crate::connection::{PgConfig, PgConnection};
+use crate::connection::{PgConfig, PgConnection, Transaction};
 use crate::error::{PgError, PgResult};
+use crate::retry::{Retrier, RetryPolicy};
 
 // ─── Pool Configuration ───────────────────────────────────────
 
@@ -42,7 +69,8 @@ pub struct PgPoolConfig {
     pub max_lifetime: Option<Duration>,
     /// Close connections that have been idle for longer than this.
     pub idle_timeout: Option<Duration>,
-    /// Maximum time to wait when all connections are busy (`get()`).
+    /// Maximum time a [`SharedPool`](crate::SharedPool) checkout waits when
+    /// all connections are busy. A worker-local [`PgPool`] never waits.
     pub checkout_timeout: Option<Duration>,
     /// Maximum time to wait when creating a new connection.
     pub connection_timeout: Option<Duration>,
@@ -52,6 +80,12 @@ pub struct PgPoolConfig {
     pub validation_query: String,
     /// If true, automatically reconnect when a connection is found to be dead.
     pub auto_reconnect: bool,
+    /// How often checkouts run [`PgPool::reap`] on their own. `None` leaves
+    /// reaping to explicit calls.
+    pub reap_interval: Option<Duration>,
+    /// Retry policy for [`PgPool::run`] and [`PgPool::transaction`]. `None`
+    /// runs them once.
+    pub retry: Option<RetryPolicy>,
 }
 
 impl Default for PgPoolConfig {
@@ -66,6 +100,8 @@ impl Default for PgPoolConfig {
             test_on_checkout: false,
             validation_query: "SELECT 1".to_string(),
             auto_reconnect: true,
+            reap_interval: Some(Duration::from_secs(30)),
+            retry: None,
         }
     }
 }
@@ -129,19 +165,37 @@ impl PgPoolConfig {
         self.idle_timeout = None;
         self
     }
+
+    /// Set how often checkouts reap expired connections.
+    pub fn reap_interval(mut self, interval: Duration) -> Self {
+        self.reap_interval = Some(interval);
+        self
+    }
+
+    /// Only reap when [`PgPool::reap`] is called.
+    pub fn no_reap_interval(mut self) -> Self {
+        self.reap_interval = None;
+        self
+    }
+
+    /// Retry [`PgPool::run`] and [`PgPool::transaction`] under `policy`.
+    pub fn retry(mut self, policy: RetryPolicy) -> Self {
+        self.retry = Some(policy);
+        self
+    }
 }
 
 // ─── PooledConn ───────────────────────────────────────────────
 
 /// Metadata for a pooled connection.
-struct PooledConn {
-    conn: PgConnection,
+pub(crate) struct PooledConn {
+    pub(crate) conn: PgConnection,
     created_at: Instant,
     last_used: Instant,
 }
 
 impl PooledConn {
-    fn new(conn: PgConnection) -> Self {
+    pub(crate) fn new(conn: PgConnection) -> Self {
         let now = Instant::now();
         Self {
             conn,
@@ -161,6 +215,17 @@ impl PooledConn {
     }
 }
 
+/// What [`PgPool::reserve`] claimed under a shared pool's lock, for the
+/// caller to finish once it is released.
+#[cfg(feature = "tokio")]
+pub(crate) struct Reservation {
+    /// An idle connection, `None` for room to open one, or why there was
+    /// neither.
+    pub(crate) slot: PgResult<Option<PooledConn>>,
+    /// Expired connections, to be closed.
+    pub(crate) closing: Vec<PooledConn>,
+}
+
 // ─── Pool Statistics ──────────────────────────────────────────
 
 /// Pool statistics.
@@ -173,6 +238,8 @@ pub struct PoolStats {
     pub lifetime_expirations: u64,
     pub idle_expirations: u64,
     pub checkout_timeouts: u64,
+    /// Attempts rerun under the pool's [`RetryPolicy`].
+    pub retries: u64,
 }
 
 // ─── PgPool ───────────────────────────────────────────────────
@@ -192,6 +259,9 @@ pub struct PgPool {
     active: usize,
     /// Statistics.
     stats: PoolStats,
+    /// When [`reap`](Self::reap) last ran.
+    last_reap: Instant,
+    retrier: Option<Retrier>,
 }
 
 impl PgPool {
@@ -205,6 +275,8 @@ impl PgPool {
             idle: VecDeque::with_capacity(size),
             active: 0,
             stats: PoolStats::default(),
+            last_reap: Instant::now(),
+            retrier: None,
         }
     }
 
@@ -212,10 +284,12 @@ impl PgPool {
     pub fn with_config(config: PgConfig, pool_config: PgPoolConfig) -> Self {
         Self {
             idle: VecDeque::with_capacity(pool_config.max_size),
+            retrier: pool_config.retry.clone().map(Retrier::new),
             config,
             pool_config,
             active: 0,
             stats: PoolStats::default(),
+            last_reap: Instant::now(),
         }
     }
 
@@ -252,17 +326,14 @@ impl PgPool {
 
         // Try to pop an idle connection (FIFO – oldest first)
         while let Some(mut pooled) = self.idle.pop_front() {
-            // Check lifetime / idle expiry
+            // Idle expiry is left to `reap()`: a connection that is about to
+            // be used is no longer idle, and closing it would only force a
+            // reconnect.
             if pooled.is_lifetime_expired(self.pool_config.max_lifetime) {
                 self.stats.lifetime_expirations += 1;
                 self.stats.total_connections_closed += 1;
                 continue; // drop it, try next
             }
-            if pooled.is_idle_expired(self.pool_config.idle_timeout) {
-                self.stats.idle_expirations += 1;
-                self.stats.total_connections_closed += 1;
-                continue;
-            }
 
             // Optionally validate
             if self.pool_config.test_on_checkout
@@ -312,77 +383,170 @@ impl PgPool {
     /// Returns `Err(PgError::PoolExhausted)` if no connection is available and
     /// the pool is at capacity.
     pub fn try_get(&mut self) -> PgResult<ConnectionGuard<'_>> {
+        self.maybe_reap();
         let pooled = self.try_checkout()?;
-        self.active += 1;
-        Ok(ConnectionGuard {
-            pool: self as *mut PgPool,
-            conn: Some(pooled),
-            _marker: std::marker::PhantomData,
-        })
+        Ok(self.guard(pooled))
     }
 
-    /// Get a connection, waiting up to the configured `checkout_timeout`.
+    /// Get a connection.
     ///
-    /// Internally calls `try_checkout` in a loop with a short sleep between
-    /// attempts.  In a production event-loop the sleep would be replaced
-    /// by yielding to the scheduler.
+    /// An exhausted worker-local pool cannot be refilled by waiting, so this
+    /// does not sleep: it returns `Err(PgError::PoolTimeout)` at once and
+    /// counts a checkout timeout. See [Exhaustion](self#exhaustion).
     pub fn get(&mut self) -> PgResult<ConnectionGuard<'_>> {
-        let timeout = self
-            .pool_config
-            .checkout_timeout
-            .unwrap_or(Duration::from_secs(5));
-        let start = Instant::now();
-
-        // First attempt — fast path.
+        self.maybe_reap();
         match self.try_checkout() {
-            Ok(pooled) => {
-                self.active += 1;
-                return Ok(ConnectionGuard {
-                    pool: self as *mut PgPool,
-                    conn: Some(pooled),
-                    _marker: std::marker::PhantomData,
-                });
+            Ok(pooled) => Ok(self.guard(pooled)),
+            Err(PgError::PoolExhausted) => {
+                self.stats.checkout_timeouts += 1;
+                Err(PgError::PoolTimeout)
             }
-            Err(PgError::PoolExhausted) => { /* fall through to retry loop */ }
-            Err(e) => return Err(e),
+            Err(e) => Err(e),
         }
+    }
+
+    // ─── Retries ──────────────────────────────────────────────
 
-        // Retry loop with back-off: 100µs → 500µs → 1ms (capped).
-        let backoff_us = [100u64, 250, 500, 1000];
-        let mut attempt = 0usize;
+    /// Check out a connection and run `f` on it.
+    ///
+    /// With a [`retry`](PgPoolConfig::retry) policy, failures it covers are
+    /// retried after a backoff, each time on a connection checked out anew,
+    /// so a lost connection is replaced. `f` must be safe to run again; see
+    /// [`retry`](crate::retry).
+    pub fn run<T>(&mut self, mut f: impl FnMut(&mut PgConnection) -> PgResult<T>) -> PgResult<T> {
+        if let Some(retrier) = &mut self.retrier {
+            retrier.start();
+        }
+        let mut retry = 0;
         loop {
-            if start.elapsed() >= timeout {
-                self.stats.checkout_timeouts += 1;
-                return Err(PgError::PoolTimeout);
-            }
+            let err = match self.get().and_then(|mut guard| f(guard.conn())) {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            retry += 1;
+            let Some(delay) = self
+                .retrier
+                .as_mut()
+                .and_then(|retrier| retrier.backoff(&err, retry))
+            else {
+                return Err(err);
+            };
+            self.stats.retries += 1;
+            std::thread::sleep(delay);
+        }
+    }
 
-            let sleep_us = backoff_us[attempt.min(backoff_us.len() - 1)];
-            std::thread::sleep(Duration::from_micros(sleep_us));
-            attempt += 1;
-
-            match self.try_checkout() {
-                Ok(pooled) => {
-                    self.active += 1;
-                    return Ok(ConnectionGuard {
-                        pool: self as *mut PgPool,
-                        conn: Some(pooled),
-                        _marker: std::marker::PhantomData,
-                    });
+    /// [`run`](Self::run) `f` in a transaction, committed if `f` succeeds
+    /// and rolled back otherwise. A retry reruns the whole transaction.
+    pub fn transaction<T>(
+        &mut self,
+        mut f: impl FnMut(&mut Transaction<'_>) -> PgResult<T>,
+    ) -> PgResult<T> {
+        self.run(|conn| conn.transaction(&mut f))
+    }
+
+    /// Claim a connection for a [`SharedPool`](crate::SharedPool) checkout
+    /// without any I/O, so its lock is held only briefly. The caller
+    /// validates or opens the connection, and closes the expired ones, after
+    /// releasing the lock. Reaping here does not refill `min_size`; shared
+    /// pools open connections on demand.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn reserve(&mut self) -> Reservation {
+        let mut closing = if self.reap_due() {
+            self.take_expired()
+        } else {
+            Vec::new()
+        };
+        self.stats.total_checkouts += 1;
+        let slot = loop {
+            match self.idle.pop_front() {
+                Some(pooled) if pooled.is_lifetime_expired(self.pool_config.max_lifetime) => {
+                    self.stats.lifetime_expirations += 1;
+                    self.stats.total_connections_closed += 1;
+                    closing.push(pooled);
+                }
+                Some(mut pooled) => {
+                    pooled.last_used = Instant::now();
+                    break Ok(Some(pooled));
                 }
-                Err(PgError::PoolExhausted) => continue,
-                Err(e) => return Err(e),
+                None if self.active + self.idle.len() < self.pool_config.max_size => {
+                    break Ok(None);
+                }
+                None => break Err(PgError::PoolExhausted),
             }
+        };
+        if slot.is_ok() {
+            self.active += 1;
+        }
+        Reservation { slot, closing }
+    }
+
+    /// Give back a slot from [`reserve`](Self::reserve) whose connection
+    /// could not be opened.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn release_slot(&mut self) {
+        self.active = self.active.saturating_sub(1);
+    }
+
+    /// Count a connection opened for a reserved slot.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn record_opened(&mut self) {
+        self.stats.total_connections_created += 1;
+    }
+
+    /// Count a reserved idle connection that failed validation.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn record_validation_failure(&mut self) {
+        self.stats.validation_failures += 1;
+        self.stats.total_connections_closed += 1;
+    }
+
+    /// Return a connection from [`reserve`](Self::reserve). Returns it back
+    /// if it must be closed instead, for the caller to drop after releasing
+    /// the lock.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn checkin(&mut self, pooled: PooledConn) -> Option<PooledConn> {
+        self.release(pooled)
+    }
+
+    /// Count a checkout that timed out before reaching the pool.
+    #[cfg(feature = "tokio")]
+    pub(crate) fn record_checkout_timeout(&mut self) {
+        self.stats.checkout_timeouts += 1;
+    }
+
+    fn guard(&mut self, pooled: PooledConn) -> ConnectionGuard<'_> {
+        self.active += 1;
+        ConnectionGuard {
+            pool: self as *mut PgPool,
+            conn: Some(pooled),
+            _marker: std::marker::PhantomData,
         }
     }
 
     /// Return a connection to the pool (called by `ConnectionGuard::drop`).
-    fn return_conn(&mut self, mut pooled: PooledConn) {
+    fn return_conn(&mut self, pooled: PooledConn) {
+        // A connection handed back is dropped here, and
+        // PgConnection::drop sends Terminate.
+        self.release(pooled);
+    }
+
+    /// Put a checked-out connection back in the idle queue, or hand it
+    /// back if it must be closed.
+    fn release(&mut self, mut pooled: PooledConn) -> Option<PooledConn> {
         self.active = self.active.saturating_sub(1);
 
-        // Discard broken connections — they cannot be reused.
-        if pooled.conn.is_broken() {
+        // Discard broken connections — they cannot be reused. The same goes
+        // for one left with a non-blocking query unfinished.
+        if pooled.conn.is_broken() || pooled.conn.has_pending_query() {
+            self.stats.total_connections_closed += 1;
+            return Some(pooled);
+        }
+
+        if pooled.is_lifetime_expired(self.pool_config.max_lifetime) {
+            self.stats.lifetime_expirations += 1;
             self.stats.total_connections_closed += 1;
-            return; // pooled dropped here → PgConnection::drop sends Terminate
+            return Some(pooled);
         }
 
         pooled.last_used = Instant::now();
@@ -390,45 +554,84 @@ impl PgPool {
         // Only return if pool is not over capacity
         if self.idle.len() + self.active < self.pool_config.max_size {
             self.idle.push_back(pooled);
+            None
         } else {
             self.stats.total_connections_closed += 1;
-            // pooled is dropped here, calling PgConnection::drop → Terminate
+            Some(pooled)
         }
     }
 
     // ─── Maintenance ──────────────────────────────────────────
 
-    /// Reap expired connections (call periodically from your event loop).
+    /// Reap expired connections.
     ///
-    /// Removes connections that have exceeded `max_lifetime` or `idle_timeout`,
-    /// then ensures `min_size` idle connections exist.
+    /// Closes idle connections past `max_lifetime`, then idle connections
+    /// past `idle_timeout` for as long as the pool holds more than
+    /// `min_size`, then opens connections until `min_size` exist again.
+    /// Checkouts call this every `reap_interval`. Call it yourself from an
+    /// event loop or timer if the pool can sit unused for long stretches.
     pub fn reap(&mut self) {
+        // The expired connections are closed as they are dropped here.
+        self.take_expired();
+
+        let min_size = self.pool_config.min_size.min(self.pool_config.max_size);
+        while self.active + self.idle.len() < min_size {
+            match PgConnection::connect(&self.config) {
+                Ok(conn) => {
+                    self.idle.push_back(PooledConn::new(conn));
+                    self.stats.total_connections_created += 1;
+                }
+                Err(_) => break,
+            }
+        }
+    }
+
+    /// Take the idle connections [`reap`](Self::reap) closes out of the
+    /// pool, without closing them.
+    fn take_expired(&mut self) -> Vec<PooledConn> {
+        self.last_reap = Instant::now();
+        let max_lifetime = self.pool_config.max_lifetime;
+        let idle_timeout = self.pool_config.idle_timeout;
+        let min_size = self.pool_config.min_size.min(self.pool_config.max_size);
+        let mut expired = Vec::new();
+
         let mut i = 0;
         while i < self.idle.len() {
-            let expired = {
-                let pooled = &self.idle[i];
-                pooled.is_lifetime_expired(self.pool_config.max_lifetime)
-                    || pooled.is_idle_expired(self.pool_config.idle_timeout)
-            };
-            if expired {
-                self.idle.remove(i);
+            if self.idle[i].is_lifetime_expired(max_lifetime) {
+                expired.extend(self.idle.remove(i));
+                self.stats.lifetime_expirations += 1;
                 self.stats.total_connections_closed += 1;
             } else {
                 i += 1;
             }
         }
 
-        // Ensure min_size connections
-        let total = self.active + self.idle.len();
-        if total < self.pool_config.min_size {
-            let need = self.pool_config.min_size - total;
-            for _ in 0..need {
-                if let Ok(conn) = PgConnection::connect(&self.config) {
-                    self.idle.push_back(PooledConn::new(conn));
-                    self.stats.total_connections_created += 1;
-                }
+        // Longest-idle connections sit at the front of the queue.
+        let mut i = 0;
+        while i < self.idle.len() && self.active + self.idle.len() > min_size {
+            if self.idle[i].is_idle_expired(idle_timeout) {
+                expired.extend(self.idle.remove(i));
+                self.stats.idle_expirations += 1;
+                self.stats.total_connections_closed += 1;
+            } else {
+                i += 1;
             }
         }
+        expired
+    }
+
+    /// Whether `reap_interval` has passed since [`reap`](Self::reap) last ran.
+    fn reap_due(&self) -> bool {
+        self.pool_config
+            .reap_interval
+            .is_some_and(|every| self.last_reap.elapsed() >= every)
+    }
+
+    /// Run [`reap`](Self::reap) if `reap_interval` has passed since the last run.
+    fn maybe_reap(&mut self) {
+        if self.reap_due() {
+            self.reap();
+        }
     }
 
     // ─── Accessors ────────────────────────────────────────────
@@ -491,6 +694,128 @@ impl PgPool {
     }
 }
 
+// ─── Per-core sharding ────────────────────────────────────────
+
+thread_local! {
+    /// This thread's shards, keyed by [`PerCorePool`] id. `None` while the
+    /// shard is lent out to [`PerCorePool::with_local`].
+    static SHARDS: RefCell<Vec<(u64, Option<Box<PgPool>>)>> = const { RefCell::new(Vec::new()) };
+}
+
+static NEXT_SHARDED_ID: AtomicU64 = AtomicU64::new(0);
+
+struct Shared {
+    id: u64,
+    config: PgConfig,
+    pool_config: PgPoolConfig,
+    shards: AtomicUsize,
+}
+
+/// A pool split into one [`PgPool`] per thread.
+///
+/// Cloning is cheap and every clone refers to the same set of shards, so one
+/// handle can be moved into each worker. A worker only ever touches its own
+/// shard, created with [`PgPool::connect_with_config`] on first use; there
+/// is no lock and no connection crosses threads. Size `pool_config` for a
+/// single worker: the server sees up to `max_size` connections per thread.
+///
+/// Shards live in thread-local storage and close when their thread exits.
+#[derive(Clone)]
+pub struct PerCorePool {
+    shared: Arc<Shared>,
+}
+
+impl PgPool {
+    /// A pool that gives each thread its own shard configured by
+    /// `pool_config`. See [`PerCorePool`].
+    pub fn per_core(config: PgConfig, pool_config: PgPoolConfig) -> PerCorePool {
+        PerCorePool {
+            shared: Arc::new(Shared {
+                id: NEXT_SHARDED_ID.fetch_add(1, Ordering::Relaxed),
+                config,
+                pool_config,
+                shards: AtomicUsize::new(0),
+            }),
+        }
+    }
+}
+
+impl PerCorePool {
+    /// Run `f` with the calling thread's shard, creating it first if needed.
+    ///
+    /// Fails with the connection error if the shard cannot be created, and
+    /// with `PoolExhausted` if called again from inside `f` on the same
+    /// thread.
+    pub fn with_local<R>(&self, f: impl FnOnce(&mut PgPool) -> R) -> PgResult<R> {
+        let id = self.shared.id;
+        let taken = SHARDS.with(|shards| {
+            let mut shards = shards.borrow_mut();
+            match shards.iter_mut().find(|(shard_id, _)| *shard_id == id) {
+                Some((_, slot)) => slot.take().map(Some).ok_or(PgError::PoolExhausted),
+                None => Ok(None),
+            }
+        })?;
+        let pool = match taken {
+            Some(pool) => pool,
+            None => {
+                let pool = Box::new(PgPool::connect_with_config(
+                    self.shared.config.clone(),
+                    self.shared.pool_config.clone(),
+                )?);
+                self.shared.shards.fetch_add(1, Ordering::Relaxed);
+                SHARDS.with(|shards| shards.borrow_mut().push((id, None)));
+                pool
+            }
+        };
+
+        // Put the shard back even if `f` panics.
+        struct Return(u64, Option<Box<PgPool>>);
+        impl Drop for Return {
+            fn drop(&mut self) {
+                let pool = self.1.take();
+                let _ = SHARDS.try_with(|shards| {
+                    if let Some((_, slot)) = shards
+                        .borrow_mut()
+                        .iter_mut()
+                        .find(|(shard_id, _)| *shard_id == self.0)
+                    {
+                        *slot = pool;
+                    }
+                });
+            }
+        }
+        let mut guard = Return(id, Some(pool));
+        let pool = guard.1.as_mut().expect("shard was just put in the guard");
+        Ok(f(pool))
+    }
+
+    /// Create the calling thread's shard now, opening its `min_size`
+    /// connections, instead of on the first query. Call it from each worker
+    /// at startup.
+    pub fn init_local(&self) -> PgResult<()> {
+        self.with_local(|_| ())
+    }
+
+    /// Statistics of the calling thread's shard.
+    pub fn local_stats(&self) -> PgResult<PoolStats> {
+        self.with_local(|pool| pool.stats().clone())
+    }
+
+    /// Number of threads that have created a shard.
+    pub fn shard_count(&self) -> usize {
+        self.shared.shards.load(Ordering::Relaxed)
+    }
+
+    /// The configuration every shard is created with.
+    pub fn pool_config(&self) -> &PgPoolConfig {
+        &self.shared.pool_config
+    }
+
+    pub fn config(&self) -> &PgConfig {
+        &self.shared.config
+    }
+}
+
 // ─── ConnectionGuard ──────────────────────────────────────────
 
 /// RAII guard for a pooled connection.
@@ -597,6 +922,7 @@ mod tests {
         );
         assert!(cfg.auto_reconnect, "auto_reconnect should default to true");
         assert_eq!(cfg.validation_query, "SELECT 1");
+        assert_eq!(cfg.reap_interval, Some(Duration::from_secs(30)));
     }
 
     #[test]
@@ -647,6 +973,37 @@ mod tests {
         assert!(cfg.idle_timeout.is_none());
     }
 
+    #[test]
+    fn test_builder_reap_interval() {
+        let d = Duration::from_secs(5);
+        let cfg = PgPoolConfig::new().reap_interval(d);
+        assert_eq!(cfg.reap_interval, Some(d));
+        assert!(cfg.no_reap_interval().reap_interval.is_none());
+    }
+
+    #[test]
+    fn test_builder_retry() {
+        assert!(PgPoolConfig::new().retry.is_none());
+        let cfg = PgPoolConfig::new().retry(RetryPolicy::new().max_retries(7));
+        assert_eq!(cfg.retry.map(|p| p.max_retries), Some(7));
+    }
+
+    #[test]
+    fn test_run_does_not_retry_failed_connects() {
+        // Nothing listens on port 1, and a refused connect is not retried.
+        let cfg = PgConfig::new("127.0.0.1", 1, "test", "test", "testdb");
+        let pool_cfg = PgPoolConfig::new().retry(RetryPolicy::new());
+        let mut pool = PgPool::with_config(cfg, pool_cfg);
+        let mut calls = 0;
+        let result = pool.run(|_| {
+            calls += 1;
+            Ok(())
+        });
+        assert!(matches!(result, Err(PgError::Io(_))));
+        assert_eq!(calls, 0);
+        assert_eq!(pool.stats().retries, 0);
+    }
+
     #[test]
     fn test_builder_checkout_timeout() {
         let d = Duration::from_secs(10);
@@ -890,4 +1247,57 @@ mod tests {
         pool.reap(); // must not panic on empty idle queue
         assert_eq!(pool.idle_connections(), 0);
     }
+
+    #[test]
+    fn test_get_fails_fast_when_exhausted() {
+        let pool_cfg = PgPoolConfig::new()
+            .max_size(0)
+            .checkout_timeout(Duration::from_secs(5));
+        let mut pool = PgPool::with_config(dummy_config(), pool_cfg);
+        let start = Instant::now();
+        assert!(matches!(pool.get(), Err(PgError::PoolTimeout)));
+        assert!(start.elapsed() < Duration::from_secs(1));
+    }
+
+    // ─── PerCorePool ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_per_core_failed_connect_creates_no_shard() {
+        let config = PgConfig::new("127.0.0.1", 1, "test", "test", "testdb");
+        let pool = PgPool::per_core(config, PgPoolConfig::new().min_size(1));
+        let clone = pool.clone();
+        assert!(clone.init_local().is_err());
+        assert_eq!(pool.shard_count(), 0);
+        // The failed attempt must not leave a lent-out slot behind.
+        assert!(!matches!(
+            pool.with_local(|_| ()),
+            Err(PgError::PoolExhausted)
+        ));
+    }
+
+    #[test]
+    fn test_per_core_lazy_shard_and_nesting() {
+        let pool = PgPool::per_core(dummy_config(), PgPoolConfig::new().min_size(0));
+        let size = pool.with_local(|shard| shard.pool_size()).unwrap();
+        assert_eq!(size, 10);
+        assert_eq!(pool.shard_count(), 1);
+        let nested = pool.with_local(|_| pool.with_local(|_| ())).unwrap();
+        assert!(matches!(nested, Err(PgError::PoolExhausted)));
+        // Still usable after the nested attempt.
+        assert_eq!(pool.local_stats().unwrap().total_checkouts, 0);
+        assert_eq!(pool.shard_count(), 1);
+    }
+
+    #[test]
+    fn test_per_core_shard_survives_panic() {
+        let pool = PgPool::per_core(dummy_config(), PgPoolConfig::new().min_size(0));
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            pool.with_local(|_| panic!("handler failed"))
+        }));
+        assert!(panicked.is_err());
+        // The shard went back to its slot instead of being dropped.
+        let size = pool.with_local(|shard| shard.pool_size()).unwrap();
+        assert_eq!(size, 10);
+        assert_eq!(pool.shard_count(), 1);
+    }
 }
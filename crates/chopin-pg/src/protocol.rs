@@ -57,10 +57,12 @@ pub enum BackendTag {
     CloseComplete = b'3',
     NoData = b'n',
     ParameterDescription = b't',
+    PortalSuspended = b's',
     EmptyQueryResponse = b'I',
     NotificationResponse = b'A',
     CopyInResponse = b'G',
     CopyOutResponse = b'H',
+    CopyBothResponse = b'W',
     CopyDone = b'c',
     CopyData = b'd',
     NegotiateProtocolVersion = b'v',
@@ -84,10 +86,12 @@ impl From<u8> for BackendTag {
             b'3' => BackendTag::CloseComplete,
             b'n' => BackendTag::NoData,
             b't' => BackendTag::ParameterDescription,
+            b's' => BackendTag::PortalSuspended,
             b'I' => BackendTag::EmptyQueryResponse,
             b'A' => BackendTag::NotificationResponse,
             b'G' => BackendTag::CopyInResponse,
             b'H' => BackendTag::CopyOutResponse,
+            b'W' => BackendTag::CopyBothResponse,
             b'c' => BackendTag::CopyDone,
             b'd' => BackendTag::CopyData,
             b'v' => BackendTag::NegotiateProtocolVersion,
@@ -284,6 +288,11 @@ mod tests {
         assert_eq!(BackendTag::from(b'v'), BackendTag::NegotiateProtocolVersion);
     }
 
+    #[test]
+    fn test_backend_tag_portal_suspended() {
+        assert_eq!(BackendTag::from(b's'), BackendTag::PortalSuspended);
+    }
+
     #[test]
     fn test_backend_tag_unknown_byte() {
         assert_eq!(BackendTag::from(0xFF), BackendTag::Unknown);
@@ -450,6 +459,7 @@ mod tests {
             (b'3', BackendTag::CloseComplete),
             (b'n', BackendTag::NoData),
             (b't', BackendTag::ParameterDescription),
+            (b's', BackendTag::PortalSuspended),
             (b'I', BackendTag::EmptyQueryResponse),
             (b'A', BackendTag::NotificationResponse),
             (b'G', BackendTag::CopyInResponse),
@@ -0,0 +1,644 @@
+//! Logical replication: WAL positions, replication-protocol messages and the
+//! `pgoutput` decoder.
+//!
+//! A connection opened with [`PgConfig::with_replication`] (or
+//! `?replication=database` in the URL) speaks the walsender protocol. On it,
+//! [`PgConnection::start_replication`] switches to COPY BOTH mode and returns
+//! a [`ReplicationStream`] yielding [`XLogData`] messages; the stream answers
+//! the server's keepalives by itself.
+//!
+//! ```ignore
+//! let config = PgConfig::from_url("postgres://repl:pw@db/app?replication=database")?;
+//! let mut conn = PgConnection::connect(&config)?;
+//! conn.create_replication_slot("app_cdc", "pgoutput", false)?;
+//!
+//! let mut stream = conn.start_replication(
+//!     "app_cdc",
+//!     Lsn::ZERO,
+//!     &[("proto_version", "1"), ("publication_names", "app_pub")],
+//! )?;
+//! while let Some(data) = stream.recv()? {
+//!     match PgOutputMessage::decode(&data.data)? {
+//!         PgOutputMessage::Insert { relation_id, new } => { /* ... */ }
+//!         PgOutputMessage::Commit { end_lsn, .. } => stream.ack(end_lsn),
+//!         _ => {}
+//!     }
+//! }
+//! ```
+//!
+//! The server only discards WAL the slot no longer needs once the consumer
+//! has [`ack`](ReplicationStream::ack)ed it, so acknowledge the `end_lsn` of
+//! each `Commit` after its changes are durably applied.
+//!
+//! [`PgConfig::with_replication`]: crate::PgConfig::with_replication
+//! [`PgConnection::start_replication`]: crate::PgConnection::start_replication
+//! [`ReplicationStream`]: crate::connection::ReplicationStream
+
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::error::{PgError, PgResult};
+use crate::protocol::FormatCode;
+use crate::types::FromSql;
+
+/// Seconds from the Unix epoch to the PostgreSQL epoch (2000-01-01 UTC).
+const PG_EPOCH_UNIX_SECS: u64 = 946_684_800;
+
+/// A position in the write-ahead log, written `16/B374D848`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Lsn(pub u64);
+
+impl Lsn {
+    /// `0/0`; as a start position it means "wherever the slot is".
+    pub const ZERO: Lsn = Lsn(0);
+}
+
+impl fmt::Display for Lsn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:X}/{:X}", self.0 >> 32, self.0 as u32)
+    }
+}
+
+impl FromStr for Lsn {
+    type Err = PgError;
+
+    fn from_str(s: &str) -> PgResult<Self> {
+        let invalid = || PgError::Protocol(format!("Invalid LSN '{}'", s));
+        let (hi, lo) = s.split_once('/').ok_or_else(invalid)?;
+        let hi = u32::from_str_radix(hi, 16).map_err(|_| invalid())?;
+        let lo = u32::from_str_radix(lo, 16).map_err(|_| invalid())?;
+        Ok(Lsn(((hi as u64) << 32) | lo as u64))
+    }
+}
+
+/// Result of `IDENTIFY_SYSTEM`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemIdentity {
+    pub system_id: String,
+    pub timeline: u32,
+    /// Current WAL flush position.
+    pub xlog_pos: Lsn,
+    pub dbname: Option<String>,
+}
+
+/// A slot made by `CREATE_REPLICATION_SLOT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicationSlot {
+    pub name: String,
+    /// Changes committed after this position are streamed from the slot.
+    pub consistent_point: Lsn,
+    pub output_plugin: String,
+}
+
+/// One `XLogData` message: a chunk of output from the slot's plugin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XLogData {
+    /// WAL position of the data.
+    pub wal_start: Lsn,
+    /// Current end of WAL on the server.
+    pub wal_end: Lsn,
+    pub server_time: SystemTime,
+    /// Plugin output; for `pgoutput`, see [`PgOutputMessage::decode`].
+    pub data: Vec<u8>,
+}
+
+/// A server keepalive, sent on idle streams and when the server wants a
+/// status update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PrimaryKeepalive {
+    pub wal_end: Lsn,
+    pub reply_requested: bool,
+}
+
+/// A decoded COPY BOTH payload from the walsender.
+#[derive(Debug)]
+pub(crate) enum ReplicationMessage {
+    XLogData(XLogData),
+    Keepalive(PrimaryKeepalive),
+}
+
+impl ReplicationMessage {
+    pub(crate) fn decode(buf: &[u8]) -> PgResult<Self> {
+        let mut r = Reader::new(buf);
+        match r.u8()? {
+            b'w' => Ok(Self::XLogData(XLogData {
+                wal_start: Lsn(r.u64()?),
+                wal_end: Lsn(r.u64()?),
+                server_time: pg_time(r.i64()?),
+                data: r.rest().to_vec(),
+            })),
+            b'k' => {
+                let wal_end = Lsn(r.u64()?);
+                r.i64()?;
+                Ok(Self::Keepalive(PrimaryKeepalive {
+                    wal_end,
+                    reply_requested: r.u8()? == 1,
+                }))
+            }
+            tag => Err(PgError::Protocol(format!(
+                "Unexpected replication message '{}'",
+                tag as char
+            ))),
+        }
+    }
+}
+
+/// Body of a Standby Status Update: the client has written `write`, flushed
+/// `flush` and applied `apply`.
+pub(crate) fn encode_standby_status(write: Lsn, flush: Lsn, apply: Lsn, reply: bool) -> [u8; 34] {
+    let mut buf = [0u8; 34];
+    buf[0] = b'r';
+    buf[1..9].copy_from_slice(&write.0.to_be_bytes());
+    buf[9..17].copy_from_slice(&flush.0.to_be_bytes());
+    buf[17..25].copy_from_slice(&apply.0.to_be_bytes());
+    buf[25..33].copy_from_slice(&pg_now().to_be_bytes());
+    buf[33] = reply as u8;
+    buf
+}
+
+/// Microseconds since the PostgreSQL epoch to a `SystemTime`.
+fn pg_time(micros: i64) -> SystemTime {
+    let epoch = UNIX_EPOCH + Duration::from_secs(PG_EPOCH_UNIX_SECS);
+    if micros >= 0 {
+        epoch + Duration::from_micros(micros as u64)
+    } else {
+        epoch - Duration::from_micros(micros.unsigned_abs())
+    }
+}
+
+fn pg_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64 - PG_EPOCH_UNIX_SECS as i64 * 1_000_000)
+        .unwrap_or(0)
+}
+
+// ─── pgoutput ─────────────────────────────────────────────────
+
+/// A message from the `pgoutput` plugin, protocol version 1.
+///
+/// Row changes refer to their table by `relation_id`; a `Relation` message
+/// describing it is sent before its first change in the stream and again
+/// after its definition changes, so consumers keep a map of them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PgOutputMessage {
+    Begin {
+        /// LSN of the commit record.
+        final_lsn: Lsn,
+        commit_time: SystemTime,
+        xid: u32,
+    },
+    Commit {
+        commit_lsn: Lsn,
+        /// End of the transaction; the position to acknowledge.
+        end_lsn: Lsn,
+        commit_time: SystemTime,
+    },
+    /// The transaction was replicated in from another node.
+    Origin {
+        commit_lsn: Lsn,
+        name: String,
+    },
+    Relation(Relation),
+    /// A user-defined type used by a following `Relation`.
+    Type {
+        id: u32,
+        namespace: String,
+        name: String,
+    },
+    Insert {
+        relation_id: u32,
+        new: Tuple,
+    },
+    Update {
+        relation_id: u32,
+        /// The old key columns (`REPLICA IDENTITY DEFAULT`/`INDEX`) when
+        /// they changed, or the whole old row with `REPLICA IDENTITY FULL`.
+        old: Option<Tuple>,
+        new: Tuple,
+    },
+    Delete {
+        relation_id: u32,
+        /// Key columns, or the whole row with `REPLICA IDENTITY FULL`.
+        old: Tuple,
+    },
+    Truncate {
+        relation_ids: Vec<u32>,
+        cascade: bool,
+        restart_identity: bool,
+    },
+    /// A `pg_logical_emit_message`, sent when the `messages` option is on.
+    Message {
+        transactional: bool,
+        lsn: Lsn,
+        prefix: String,
+        content: Vec<u8>,
+    },
+}
+
+/// Table description from a `Relation` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Relation {
+    pub id: u32,
+    pub namespace: String,
+    pub name: String,
+    /// `d` default, `n` nothing, `f` full, `i` index.
+    pub replica_identity: u8,
+    pub columns: Vec<RelationColumn>,
+}
+
+impl Relation {
+    /// Index of the column called `name`.
+    pub fn column_index(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.name == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelationColumn {
+    pub name: String,
+    pub type_oid: u32,
+    pub type_modifier: i32,
+    /// Part of the replica identity key.
+    pub is_key: bool,
+}
+
+/// Row values of an `Insert`, `Update` or `Delete`, in column order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tuple(pub Vec<TupleValue>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TupleValue {
+    Null,
+    /// A TOASTed value the update did not change; it is not sent.
+    Unchanged,
+    /// Text format.
+    Text(Vec<u8>),
+    /// Binary format, with the `binary` option.
+    Binary(Vec<u8>),
+}
+
+impl Tuple {
+    /// Decode column `index`, whose type `relation` gives.
+    pub fn get<T: FromSql>(&self, relation: &Relation, index: usize) -> PgResult<T> {
+        let column = relation
+            .columns
+            .get(index)
+            .ok_or_else(|| PgError::Protocol(format!("Column index {} out of range", index)))?;
+        let (format, raw) = match self.0.get(index) {
+            Some(TupleValue::Null) => (FormatCode::Text, None),
+            Some(TupleValue::Text(raw)) => (FormatCode::Text, Some(raw.as_slice())),
+            Some(TupleValue::Binary(raw)) => (FormatCode::Binary, Some(raw.as_slice())),
+            Some(TupleValue::Unchanged) => {
+                return Err(PgError::TypeConversion(format!(
+                    "Column '{}' is an unchanged TOAST value",
+                    column.name
+                )));
+            }
+            None => {
+                return Err(PgError::Protocol(format!(
+                    "Column index {} out of range",
+                    index
+                )));
+            }
+        };
+        T::from_sql_raw(column.type_oid, format, raw)
+    }
+
+    /// Decode the column called `name`.
+    pub fn get_by_name<T: FromSql>(&self, relation: &Relation, name: &str) -> PgResult<T> {
+        let index = relation
+            .column_index(name)
+            .ok_or_else(|| PgError::Protocol(format!("No column named '{}'", name)))?;
+        self.get(relation, index)
+    }
+}
+
+impl PgOutputMessage {
+    /// Decode the `data` of an [`XLogData`] from a `pgoutput` slot started
+    /// with `proto_version '1'`.
+    pub fn decode(buf: &[u8]) -> PgResult<Self> {
+        let mut r = Reader::new(buf);
+        let msg = match r.u8()? {
+            b'B' => Self::Begin {
+                final_lsn: Lsn(r.u64()?),
+                commit_time: pg_time(r.i64()?),
+                xid: r.u32()?,
+            },
+            b'C' => {
+                r.u8()?; // flags, unused
+                Self::Commit {
+                    commit_lsn: Lsn(r.u64()?),
+                    end_lsn: Lsn(r.u64()?),
+                    commit_time: pg_time(r.i64()?),
+                }
+            }
+            b'O' => Self::Origin {
+                commit_lsn: Lsn(r.u64()?),
+                name: r.cstr()?,
+            },
+            b'R' => {
+                let id = r.u32()?;
+                let namespace = r.cstr()?;
+                let name = r.cstr()?;
+                let replica_identity = r.u8()?;
+                let count = r.u16()?;
+                let columns = (0..count)
+                    .map(|_| {
+                        let flags = r.u8()?;
+                        Ok(RelationColumn {
+                            name: r.cstr()?,
+                            type_oid: r.u32()?,
+                            type_modifier: r.i32()?,
+                            is_key: flags & 1 != 0,
+                        })
+                    })
+                    .collect::<PgResult<_>>()?;
+                Self::Relation(Relation {
+                    id,
+                    namespace,
+                    name,
+                    replica_identity,
+                    columns,
+                })
+            }
+            b'Y' => Self::Type {
+                id: r.u32()?,
+                namespace: r.cstr()?,
+                name: r.cstr()?,
+            },
+            b'I' => {
+                let relation_id = r.u32()?;
+                r.expect(b'N')?;
+                Self::Insert {
+                    relation_id,
+                    new: r.tuple()?,
+                }
+            }
+            b'U' => {
+                let relation_id = r.u32()?;
+                let old = match r.u8()? {
+                    b'K' | b'O' => {
+                        let old = r.tuple()?;
+                        r.expect(b'N')?;
+                        Some(old)
+                    }
+                    b'N' => None,
+                    tag => return Err(unexpected("Update", tag)),
+                };
+                Self::Update {
+                    relation_id,
+                    old,
+                    new: r.tuple()?,
+                }
+            }
+            b'D' => {
+                let relation_id = r.u32()?;
+                match r.u8()? {
+                    b'K' | b'O' => Self::Delete {
+                        relation_id,
+                        old: r.tuple()?,
+                    },
+                    tag => return Err(unexpected("Delete", tag)),
+                }
+            }
+            b'T' => {
+                let count = r.u32()?;
+                let options = r.u8()?;
+                Self::Truncate {
+                    relation_ids: (0..count).map(|_| r.u32()).collect::<PgResult<_>>()?,
+                    cascade: options & 1 != 0,
+                    restart_identity: options & 2 != 0,
+                }
+            }
+            b'M' => {
+                let transactional = r.u8()? & 1 != 0;
+                let lsn = Lsn(r.u64()?);
+                let prefix = r.cstr()?;
+                let len = r.u32()? as usize;
+                Self::Message {
+                    transactional,
+                    lsn,
+                    prefix,
+                    content: r.bytes(len)?.to_vec(),
+                }
+            }
+            tag => return Err(unexpected("pgoutput", tag)),
+        };
+        Ok(msg)
+    }
+}
+
+fn unexpected(what: &str, tag: u8) -> PgError {
+    PgError::Protocol(format!("Unexpected {} tag '{}'", what, tag as char))
+}
+
+/// Big-endian cursor over a message body.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn bytes(&mut self, n: usize) -> PgResult<&'a [u8]> {
+        if self.buf.len() < n {
+            return Err(PgError::Protocol(
+                "Truncated replication message".to_string(),
+            ));
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> PgResult<[u8; N]> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.bytes(N)?);
+        Ok(out)
+    }
+
+    fn u8(&mut self) -> PgResult<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> PgResult<u16> {
+        Ok(u16::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> PgResult<u32> {
+        Ok(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> PgResult<i32> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> PgResult<u64> {
+        Ok(u64::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> PgResult<i64> {
+        Ok(i64::from_be_bytes(self.array()?))
+    }
+
+    fn expect(&mut self, tag: u8) -> PgResult<()> {
+        match self.u8()? {
+            t if t == tag => Ok(()),
+            t => Err(unexpected("tuple", t)),
+        }
+    }
+
+    fn cstr(&mut self) -> PgResult<String> {
+        let end = self.buf.iter().position(|&b| b == 0).ok_or_else(|| {
+            PgError::Protocol("Unterminated string in replication message".to_string())
+        })?;
+        let s = String::from_utf8_lossy(&self.buf[..end]).into_owned();
+        self.buf = &self.buf[end + 1..];
+        Ok(s)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        std::mem::take(&mut self.buf)
+    }
+
+    fn tuple(&mut self) -> PgResult<Tuple> {
+        let count = self.u16()?;
+        let values = (0..count)
+            .map(|_| {
+                Ok(match self.u8()? {
+                    b'n' => TupleValue::Null,
+                    b'u' => TupleValue::Unchanged,
+                    b't' => {
+                        let len = self.u32()? as usize;
+                        TupleValue::Text(self.bytes(len)?.to_vec())
+                    }
+                    b'b' => {
+                        let len = self.u32()? as usize;
+                        TupleValue::Binary(self.bytes(len)?.to_vec())
+                    }
+                    tag => return Err(unexpected("column", tag)),
+                })
+            })
+            .collect::<PgResult<_>>()?;
+        Ok(Tuple(values))
+    }
+}
+
+/// Whether `name` is usable as a slot or plugin name without quoting.
+pub(crate) fn is_plain_name(name: &str) -> bool {
+    !name.is_empty()
+        && name.len() < 64
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lsn_round_trips_through_text() {
+        let lsn: Lsn = "16/B374D848".parse().unwrap();
+        assert_eq!(lsn, Lsn(0x16_B374_D848));
+        assert_eq!(lsn.to_string(), "16/B374D848");
+        assert_eq!(Lsn::ZERO.to_string(), "0/0");
+        assert!("16B374D848".parse::<Lsn>().is_err());
+        assert!("x/1".parse::<Lsn>().is_err());
+    }
+
+    #[test]
+    fn test_decode_xlogdata_and_keepalive() {
+        let mut buf = vec![b'w'];
+        buf.extend_from_slice(&10u64.to_be_bytes());
+        buf.extend_from_slice(&20u64.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        buf.extend_from_slice(b"payload");
+        match ReplicationMessage::decode(&buf).unwrap() {
+            ReplicationMessage::XLogData(x) => {
+                assert_eq!((x.wal_start, x.wal_end), (Lsn(10), Lsn(20)));
+                assert_eq!(x.server_time, pg_time(0));
+                assert_eq!(x.data, b"payload");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let mut buf = vec![b'k'];
+        buf.extend_from_slice(&30u64.to_be_bytes());
+        buf.extend_from_slice(&0i64.to_be_bytes());
+        buf.push(1);
+        match ReplicationMessage::decode(&buf).unwrap() {
+            ReplicationMessage::Keepalive(k) => {
+                assert_eq!(k.wal_end, Lsn(30));
+                assert!(k.reply_requested);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(ReplicationMessage::decode(&buf[..5]).is_err());
+    }
+
+    #[test]
+    fn test_standby_status_layout() {
+        let buf = encode_standby_status(Lsn(3), Lsn(2), Lsn(1), true);
+        assert_eq!(buf[0], b'r');
+        assert_eq!(u64::from_be_bytes(buf[1..9].try_into().unwrap()), 3);
+        assert_eq!(u64::from_be_bytes(buf[9..17].try_into().unwrap()), 2);
+        assert_eq!(u64::from_be_bytes(buf[17..25].try_into().unwrap()), 1);
+        assert_eq!(buf[33], 1);
+    }
+
+    #[test]
+    fn test_decode_relation_and_update() {
+        let mut buf = vec![b'R'];
+        buf.extend_from_slice(&16384u32.to_be_bytes());
+        buf.extend_from_slice(b"public\0users\0");
+        buf.push(b'd');
+        buf.extend_from_slice(&2u16.to_be_bytes());
+        buf.push(1);
+        buf.extend_from_slice(b"id\0");
+        buf.extend_from_slice(&23u32.to_be_bytes());
+        buf.extend_from_slice(&(-1i32).to_be_bytes());
+        buf.push(0);
+        buf.extend_from_slice(b"name\0");
+        buf.extend_from_slice(&25u32.to_be_bytes());
+        buf.extend_from_slice(&(-1i32).to_be_bytes());
+        let PgOutputMessage::Relation(rel) = PgOutputMessage::decode(&buf).unwrap() else {
+            panic!("expected a relation");
+        };
+        assert_eq!(
+            (rel.namespace.as_str(), rel.name.as_str()),
+            ("public", "users")
+        );
+        assert!(rel.columns[0].is_key && !rel.columns[1].is_key);
+        assert_eq!(rel.column_index("name"), Some(1));
+
+        let mut buf = vec![b'U'];
+        buf.extend_from_slice(&16384u32.to_be_bytes());
+        buf.push(b'N');
+        buf.extend_from_slice(&2u16.to_be_bytes());
+        buf.push(b't');
+        buf.extend_from_slice(&1u32.to_be_bytes());
+        buf.extend_from_slice(b"7");
+        buf.push(b'u');
+        let PgOutputMessage::Update { old, new, .. } = PgOutputMessage::decode(&buf).unwrap()
+        else {
+            panic!("expected an update");
+        };
+        assert_eq!(old, None);
+        assert_eq!(new.get::<i32>(&rel, 0).unwrap(), 7);
+        assert!(new.get::<String>(&rel, 1).is_err());
+        assert!(PgOutputMessage::decode(&buf[..buf.len() - 3]).is_err());
+    }
+
+    #[test]
+    fn test_slot_names() {
+        assert!(is_plain_name("app_cdc_1"));
+        assert!(!is_plain_name("App"));
+        assert!(!is_plain_name("x\"; DROP"));
+        assert!(!is_plain_name(""));
+    }
+}
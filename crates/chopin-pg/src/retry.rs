@@ -0,0 +1,299 @@
+//! Rerunning work that failed for a reason that may not happen again.
+//!
+//! A [`RetryPolicy`] is opt-in. Set it with [`PgPoolConfig::retry`] or
+//! [`PgConnection::set_retry_policy`] and run the work through
+//! [`PgPool::run`], [`PgPool::transaction`] or
+//! [`PgConnection::transaction_with_retry`]:
+//!
+//! ```ignore
+//! let pool_cfg = PgPoolConfig::new().retry(RetryPolicy::new().max_retries(5));
+//! let mut pool = PgPool::connect_with_config(config, pool_cfg)?;
+//!
+//! pool.transaction(|tx| {
+//!     tx.execute("SET TRANSACTION ISOLATION LEVEL SERIALIZABLE", &[])?;
+//!     let balance: i64 = tx.query_one("SELECT balance FROM accounts WHERE id = $1", &[&id])?.get_typed(0)?;
+//!     tx.execute("UPDATE accounts SET balance = $1 WHERE id = $2", &[&(balance - 10), &id])?;
+//!     Ok(())
+//! })?;
+//! ```
+//!
+//! Only these failures are retried:
+//!
+//! - serialization failures (`40001`) and deadlocks (`40P01`), after which
+//!   the server has already rolled the transaction back;
+//! - lost connections: resets, aborts, broken pipes and SQLSTATE class
+//!   `08`. The pool retries these on a fresh connection. A lone
+//!   [`PgConnection`] cannot, so it returns them.
+//!
+//! The closure runs again from the start, so it must be safe to repeat. A
+//! write whose connection dropped may or may not have committed. Keep it
+//! inside a transaction, or make it idempotent.
+//!
+//! Between attempts the caller sleeps for an exponentially growing delay,
+//! randomised by half its length so clients that collided do not collide
+//! again. A [`RetryBudget`] caps retries across all calls, so a database
+//! that keeps failing does not get several times its normal load.
+//!
+//! [`PgPoolConfig::retry`]: crate::PgPoolConfig::retry
+//! [`PgPool::run`]: crate::PgPool::run
+//! [`PgPool::transaction`]: crate::PgPool::transaction
+//! [`PgConnection`]: crate::PgConnection
+//! [`PgConnection::set_retry_policy`]: crate::PgConnection::set_retry_policy
+//! [`PgConnection::transaction_with_retry`]: crate::PgConnection::transaction_with_retry
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::ErrorKind;
+use std::time::Duration;
+
+use crate::error::PgError;
+
+/// When to rerun failed work, and how long to wait first.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt. Default: 3.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each one after. Default: 10 ms.
+    pub base_delay: Duration,
+    /// Longest delay between attempts. Default: 1 s.
+    pub max_delay: Duration,
+    /// Wait a random 50–100% of each delay. Default: `true`.
+    pub jitter: bool,
+    /// Shared limit on retries across calls. Default: 20% extra load,
+    /// bursts of 10.
+    pub budget: Option<RetryBudget>,
+}
+
+/// A token bucket limiting retries across calls.
+///
+/// The bucket starts full. Every call adds `ratio` tokens, up to `burst`,
+/// and every retry takes one. When it is empty, failures are returned
+/// without retrying.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryBudget {
+    /// Tokens earned per call: the share of calls that may be retried.
+    pub ratio: f64,
+    /// Most tokens the bucket holds.
+    pub burst: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            jitter: true,
+            budget: Some(RetryBudget {
+                ratio: 0.2,
+                burst: 10,
+            }),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the number of retries after the first attempt.
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Set the delay before the first retry.
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the longest delay between attempts.
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Wait exactly the computed delays, without randomising them.
+    pub fn no_jitter(mut self) -> Self {
+        self.jitter = false;
+        self
+    }
+
+    /// Set the retry budget.
+    pub fn budget(mut self, ratio: f64, burst: u32) -> Self {
+        self.budget = Some(RetryBudget { ratio, burst });
+        self
+    }
+
+    /// Retry up to `max_retries` on every call, however many fail.
+    pub fn no_budget(mut self) -> Self {
+        self.budget = None;
+        self
+    }
+
+    /// Whether `err` is one this policy retries.
+    pub fn should_retry(&self, err: &PgError) -> bool {
+        is_lost_connection(err) || matches!(err.sql_state(), Some("40001" | "40P01"))
+    }
+
+    /// Delay before retry number `retry` (1-based), before jitter.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32 << retry.saturating_sub(1).min(20);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// A lost connection, which only a new connection can retry.
+pub(crate) fn is_lost_connection(err: &PgError) -> bool {
+    match err {
+        PgError::ConnectionClosed => true,
+        PgError::Io(e) => matches!(
+            e.kind(),
+            ErrorKind::ConnectionReset
+                | ErrorKind::ConnectionAborted
+                | ErrorKind::BrokenPipe
+                | ErrorKind::UnexpectedEof
+        ),
+        _ => err.sql_state().is_some_and(|c| c.starts_with("08")),
+    }
+}
+
+/// A [`RetryPolicy`] with the state it keeps between calls.
+#[derive(Debug)]
+pub(crate) struct Retrier {
+    policy: RetryPolicy,
+    tokens: f64,
+    rng: u64,
+}
+
+impl Retrier {
+    pub(crate) fn new(policy: RetryPolicy) -> Self {
+        let tokens = policy.budget.map_or(0.0, |b| f64::from(b.burst));
+        Self {
+            policy,
+            tokens,
+            rng: RandomState::new().build_hasher().finish() | 1,
+        }
+    }
+
+    pub(crate) fn policy(&self) -> &RetryPolicy {
+        &self.policy
+    }
+
+    /// Note the start of a call, earning its share of the budget.
+    pub(crate) fn start(&mut self) {
+        if let Some(budget) = self.policy.budget {
+            self.tokens = (self.tokens + budget.ratio).min(f64::from(budget.burst));
+        }
+    }
+
+    /// How long to wait before retry number `retry` after `err`, or `None`
+    /// to give up and return the error.
+    pub(crate) fn backoff(&mut self, err: &PgError, retry: u32) -> Option<Duration> {
+        if retry > self.policy.max_retries || !self.policy.should_retry(err) {
+            return None;
+        }
+        if self.policy.budget.is_some() {
+            if self.tokens < 1.0 {
+                return None;
+            }
+            self.tokens -= 1.0;
+        }
+        let delay = self.policy.delay(retry);
+        if !self.policy.jitter {
+            return Some(delay);
+        }
+        // xorshift64: plenty to spread clients apart.
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        let half = delay / 2;
+        let spread = half.as_nanos() as u64 + 1;
+        Some(half + Duration::from_nanos(self.rng % spread))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PgError;
+
+    fn sqlstate(code: &str) -> PgError {
+        PgError::from_fields(&[(b'S', "ERROR".to_string()), (b'C', code.to_string())])
+    }
+
+    fn io(kind: ErrorKind) -> PgError {
+        PgError::Io(std::io::Error::new(kind, "io"))
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let policy = RetryPolicy::new();
+        assert!(policy.should_retry(&sqlstate("40001")));
+        assert!(policy.should_retry(&sqlstate("40P01")));
+        assert!(policy.should_retry(&sqlstate("08006")));
+        assert!(policy.should_retry(&io(ErrorKind::ConnectionReset)));
+        assert!(policy.should_retry(&io(ErrorKind::BrokenPipe)));
+        assert!(policy.should_retry(&PgError::ConnectionClosed));
+
+        assert!(!policy.should_retry(&sqlstate("23505")));
+        assert!(!policy.should_retry(&sqlstate("57014")));
+        assert!(!policy.should_retry(&io(ErrorKind::ConnectionRefused)));
+        assert!(!policy.should_retry(&PgError::Timeout));
+        assert!(!policy.should_retry(&PgError::PoolTimeout));
+    }
+
+    #[test]
+    fn test_delay_doubles_up_to_max() {
+        let policy = RetryPolicy::new()
+            .base_delay(Duration::from_millis(10))
+            .max_delay(Duration::from_millis(50));
+        assert_eq!(policy.delay(1), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(20));
+        assert_eq!(policy.delay(3), Duration::from_millis(40));
+        assert_eq!(policy.delay(4), Duration::from_millis(50));
+        assert_eq!(policy.delay(64), Duration::from_millis(50));
+    }
+
+    #[test]
+    fn test_backoff_stops_after_max_retries() {
+        let mut retrier = Retrier::new(RetryPolicy::new().max_retries(2).no_jitter());
+        let err = sqlstate("40001");
+        retrier.start();
+        assert_eq!(retrier.backoff(&err, 1), Some(Duration::from_millis(10)));
+        assert_eq!(retrier.backoff(&err, 2), Some(Duration::from_millis(20)));
+        assert_eq!(retrier.backoff(&err, 3), None);
+        assert_eq!(retrier.backoff(&sqlstate("42601"), 1), None);
+    }
+
+    #[test]
+    fn test_jitter_stays_within_half_to_full_delay() {
+        let mut retrier = Retrier::new(RetryPolicy::new().max_retries(10).no_budget());
+        let err = sqlstate("40P01");
+        for retry in 1..=10 {
+            let full = retrier.policy().delay(retry);
+            let delay = retrier.backoff(&err, retry).unwrap();
+            assert!(delay >= full / 2 && delay <= full, "{delay:?} vs {full:?}");
+        }
+    }
+
+    #[test]
+    fn test_budget_limits_retries_across_calls() {
+        let mut retrier = Retrier::new(RetryPolicy::new().budget(0.5, 2));
+        let err = sqlstate("40001");
+        retrier.start();
+        assert!(retrier.backoff(&err, 1).is_some());
+        assert!(retrier.backoff(&err, 2).is_some());
+        // The bucket is empty, even though max_retries allows a third.
+        assert!(retrier.backoff(&err, 3).is_none());
+
+        // Two calls earn one more retry.
+        retrier.start();
+        assert!(retrier.backoff(&err, 1).is_none());
+        retrier.start();
+        assert!(retrier.backoff(&err, 1).is_some());
+    }
+}
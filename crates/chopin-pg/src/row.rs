@@ -5,12 +5,13 @@
 //! **inline** — zero heap allocations per column.  Only values larger than
 //! 24 bytes (e.g. long text, bytea, jsonb) spill to the heap.
 
+use std::borrow::Cow;
 use std::rc::Rc;
 
 use crate::codec::ColumnDesc;
 use crate::error::{PgError, PgResult};
 use crate::protocol::FormatCode;
-use crate::types::{FromSql, PgValue};
+use crate::types::{FromSql, PgValue, decode_bytea_text, oid};
 
 /// Maximum number of bytes stored inline (no heap allocation).
 /// 24 bytes covers: bool(1), i16(2), i32(4), i64(8), f32(4), f64(8),
@@ -95,6 +96,20 @@ impl Row {
                 PgValue::Int8(v) => (20, Some(v.to_string().into_bytes())),
                 PgValue::Text(s) => (25, Some(s.clone().into_bytes())),
                 PgValue::Bool(b) => (16, Some(if *b { b"t".to_vec() } else { b"f".to_vec() })),
+                PgValue::Bytes(_) => (oid::BYTEA, values[i].to_text_bytes()),
+                PgValue::Array(items) => {
+                    let array_oid = match items.iter().find(|v| !matches!(v, PgValue::Null)) {
+                        Some(PgValue::Bool(_)) => oid::BOOL_ARRAY,
+                        Some(PgValue::Int2(_)) => oid::INT2_ARRAY,
+                        Some(PgValue::Int4(_)) => oid::INT4_ARRAY,
+                        Some(PgValue::Int8(_)) => oid::INT8_ARRAY,
+                        Some(PgValue::Float4(_)) => oid::FLOAT4_ARRAY,
+                        Some(PgValue::Float8(_)) => oid::FLOAT8_ARRAY,
+                        Some(PgValue::Uuid(_)) => oid::UUID_ARRAY,
+                        _ => oid::TEXT_ARRAY,
+                    };
+                    (array_oid, values[i].to_text_bytes())
+                }
                 // A complete map isn't necessary for a lightweight mock just matching basic fields
                 _ => (25, values[i].to_text_bytes()),
             };
@@ -125,69 +140,125 @@ impl Row {
         self.columns.is_empty()
     }
 
-    /// Get a column value by index as a PgValue.
-    pub fn get(&self, index: usize) -> PgResult<PgValue> {
-        if index >= self.columns.len() {
-            return Err(PgError::TypeConversion(format!(
-                "Column index {} out of range",
-                index
-            )));
-        }
+    /// Decode a column by index into any [`FromSql`] type, straight from the
+    /// wire bytes. Ask for [`PgValue`] to get the dynamic representation.
+    ///
+    /// # Example
+    /// ```ignore
+    /// let name: String = row.get(0)?;
+    /// let age: Option<i32> = row.get(1)?;
+    /// let any = row.get::<PgValue>(2)?;
+    /// ```
+    pub fn get<T: FromSql>(&self, index: usize) -> PgResult<T> {
+        let col = self.columns.get(index).ok_or_else(|| {
+            PgError::TypeConversion(format!("Column index {} out of range", index))
+        })?;
+        T::from_sql_raw(col.type_oid, col.format_code, self.raw(index))
+    }
 
-        let col = &self.columns[index];
-        match &self.values[index] {
-            None => Ok(PgValue::Null),
-            Some(data) => match col.format_code {
-                FormatCode::Text => PgValue::from_text(col.type_oid, data.as_slice()),
-                FormatCode::Binary => PgValue::from_binary(col.type_oid, data.as_slice()),
-            },
-        }
+    /// Raw bytes of a column as received, in the column's format code.
+    /// `None` for NULL or an out-of-range index.
+    pub fn raw(&self, index: usize) -> Option<&[u8]> {
+        self.values.get(index)?.as_ref().map(CompactBytes::as_slice)
     }
 
-    /// Get a column value by name as a PgValue.
-    pub fn get_by_name(&self, name: &str) -> PgResult<PgValue> {
-        let index = self
-            .columns
-            .iter()
-            .position(|c| c.name == name)
-            .ok_or_else(|| PgError::TypeConversion(format!("Column '{}' not found", name)))?;
-        self.get(index)
+    /// [`get`](Self::get) by column name.
+    pub fn get_by_name<T: FromSql>(&self, name: &str) -> PgResult<T> {
+        self.get(self.index_of(name)?)
     }
 
-    /// Get a typed value by column index using the `FromSql` trait.
-    ///
-    /// # Example
-    /// ```ignore
-    /// let name: String = row.get_typed(0)?;
-    /// let age: Option<i32> = row.get_typed(1)?;
-    /// ```
+    fn index_of(&self, name: &str) -> PgResult<usize> {
+        self.column_index(name)
+            .ok_or_else(|| PgError::TypeConversion(format!("Column '{}' not found", name)))
+    }
+
+    /// Same as [`get`](Self::get).
     pub fn get_typed<T: FromSql>(&self, index: usize) -> PgResult<T> {
-        let value = self.get(index)?;
-        T::from_sql(&value)
+        self.get(index)
     }
 
-    /// Get a typed value by column name using the `FromSql` trait.
+    /// Same as [`get_by_name`](Self::get_by_name).
     pub fn get_typed_by_name<T: FromSql>(&self, name: &str) -> PgResult<T> {
-        let value = self.get_by_name(name)?;
-        T::from_sql(&value)
+        self.get_by_name(name)
     }
 
-    /// Get a column as a string (text representation).
+    /// Borrow a column as `&str` without allocating.
+    ///
+    /// Works for any text-format column and for binary-format columns whose
+    /// binary encoding is their text (`text`, `varchar`, `char(n)`, `name`,
+    /// `json`, `jsonb`, `xml`, enums). Other binary columns, such as `int4`,
+    /// are rejected; read those with [`get_typed`](Self::get_typed).
     pub fn get_str(&self, index: usize) -> PgResult<Option<&str>> {
-        if index >= self.columns.len() {
+        let col = self.columns.get(index).ok_or_else(|| {
+            PgError::TypeConversion(format!("Column index {} out of range", index))
+        })?;
+        let Some(data) = &self.values[index] else {
+            return Ok(None);
+        };
+        let mut data = data.as_slice();
+        if col.format_code == FormatCode::Binary {
+            match col.type_oid {
+                oid::TEXT
+                | oid::VARCHAR
+                | oid::BPCHAR
+                | oid::NAME
+                | oid::CHAR
+                | oid::JSON
+                | oid::XML => {}
+                // jsonb's binary form is a version byte followed by the text.
+                oid::JSONB => data = data.get(1..).unwrap_or_default(),
+                t if t >= oid::FIRST_NORMAL_OBJECT_ID => {}
+                _ => {
+                    return Err(PgError::TypeConversion(format!(
+                        "Column '{}' is not text",
+                        col.name
+                    )));
+                }
+            }
+        }
+        std::str::from_utf8(data)
+            .map(Some)
+            .map_err(|_| PgError::TypeConversion("Invalid UTF-8".to_string()))
+    }
+
+    /// [`get_str`](Self::get_str) by column name.
+    pub fn get_str_by_name(&self, name: &str) -> PgResult<Option<&str>> {
+        self.get_str(self.index_of(name)?)
+    }
+
+    /// Get a `bytea` column without copying it.
+    ///
+    /// Binary-format results (every extended-protocol query) borrow straight
+    /// from the row; text-format results are hex- or escape-decoded into an
+    /// owned buffer.
+    pub fn get_bytes(&self, index: usize) -> PgResult<Option<Cow<'_, [u8]>>> {
+        let col = self.columns.get(index).ok_or_else(|| {
+            PgError::TypeConversion(format!("Column index {} out of range", index))
+        })?;
+        if col.type_oid != oid::BYTEA {
             return Err(PgError::TypeConversion(format!(
-                "Column index {} out of range",
-                index
+                "Column '{}' is not bytea",
+                col.name
             )));
         }
-        match &self.values[index] {
-            None => Ok(None),
-            Some(data) => std::str::from_utf8(data.as_slice())
-                .map(Some)
-                .map_err(|_| PgError::TypeConversion("Invalid UTF-8".to_string())),
+        let Some(data) = &self.values[index] else {
+            return Ok(None);
+        };
+        match col.format_code {
+            FormatCode::Binary => Ok(Some(Cow::Borrowed(data.as_slice()))),
+            FormatCode::Text => {
+                let text = std::str::from_utf8(data.as_slice())
+                    .map_err(|_| PgError::TypeConversion("Invalid UTF-8".to_string()))?;
+                Ok(Some(Cow::Owned(decode_bytea_text(text))))
+            }
         }
     }
 
+    /// [`get_bytes`](Self::get_bytes) by column name.
+    pub fn get_bytes_by_name(&self, name: &str) -> PgResult<Option<Cow<'_, [u8]>>> {
+        self.get_bytes(self.index_of(name)?)
+    }
+
     /// Get a column as i32.
     pub fn get_i32(&self, index: usize) -> PgResult<Option<i32>> {
         match self.get(index)? {
@@ -378,7 +449,7 @@ mod tests {
     #[test]
     fn test_get_index_out_of_range_returns_error() {
         let row = make_row(&[("name", OID_TEXT)], &[Some(b"alice")]);
-        let err = row.get(99);
+        let err = row.get::<PgValue>(99);
         assert!(err.is_err());
         if let Err(PgError::TypeConversion(msg)) = err {
             assert!(
@@ -408,7 +479,7 @@ mod tests {
     #[test]
     fn test_get_by_name_not_found_returns_error() {
         let row = make_row(&[("id", OID_INT4)], &[Some(b"5")]);
-        let err = row.get_by_name("nonexistent");
+        let err = row.get_by_name::<PgValue>("nonexistent");
         assert!(err.is_err());
         if let Err(PgError::TypeConversion(msg)) = err {
             assert!(
@@ -435,6 +506,36 @@ mod tests {
         assert_eq!(row.get_str(0).unwrap(), Some("hello world"));
     }
 
+    #[test]
+    fn test_get_str_binary_columns() {
+        let mut cols: Vec<ColumnDesc> = [
+            ("name", oid::TEXT),
+            ("doc", oid::JSONB),
+            ("mood", 70_000),
+            ("n", oid::INT4),
+        ]
+        .iter()
+        .map(|(n, o)| col(n, *o))
+        .collect();
+        for c in &mut cols {
+            c.format_code = FormatCode::Binary;
+        }
+        let row = Row::new(
+            Rc::new(cols),
+            vec![
+                Some(b"alice"),
+                Some(b"\x01{\"a\": 1}"),
+                Some(b"happy"),
+                Some(&7i32.to_be_bytes()),
+            ],
+        );
+        assert_eq!(row.get_str_by_name("name").unwrap(), Some("alice"));
+        assert_eq!(row.get_str(1).unwrap(), Some("{\"a\": 1}"));
+        assert_eq!(row.get_str(2).unwrap(), Some("happy"));
+        assert!(row.get_str(3).is_err());
+        assert!(row.get_str_by_name("missing").is_err());
+    }
+
     #[test]
     fn test_get_str_null() {
         let row = make_row(&[("msg", OID_TEXT)], &[None]);
@@ -522,6 +623,58 @@ mod tests {
         assert_eq!(cols[1].name, "b");
     }
 
+    /// A type `PgValue` knows nothing about, decoded from the wire only.
+    #[derive(Debug, PartialEq)]
+    struct Labels(Vec<String>);
+
+    impl FromSql for Labels {
+        fn from_sql_raw(_oid: u32, _format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+            let raw = raw.ok_or_else(|| PgError::TypeConversion("NULL labels".into()))?;
+            let text = std::str::from_utf8(raw)
+                .map_err(|_| PgError::TypeConversion("Invalid UTF-8".into()))?;
+            Ok(Labels(text.split('.').map(str::to_string).collect()))
+        }
+    }
+
+    #[test]
+    fn test_get_dispatches_on_target_type() {
+        let mut cols = vec![col("n", OID_INT4), col("path", 70_000), col("none", 70_000)];
+        for c in &mut cols {
+            c.format_code = FormatCode::Binary;
+        }
+        let row = Row::new(
+            Rc::new(cols),
+            vec![Some(&7i32.to_be_bytes()), Some(b"a.b"), None],
+        );
+        assert_eq!(row.get::<i32>(0).unwrap(), 7);
+        assert_eq!(row.get::<i64>(0).unwrap(), 7);
+        assert_eq!(row.get::<PgValue>(0).unwrap(), PgValue::Int4(7));
+        assert!(row.get::<bool>(0).is_err());
+
+        let labels = Labels(vec!["a".into(), "b".into()]);
+        assert_eq!(row.get_by_name::<Labels>("path").unwrap(), labels);
+        assert_eq!(row.get::<Option<Labels>>(2).unwrap(), None);
+        assert!(row.get::<Labels>(2).is_err());
+        // The PgValue route defaults to the text form.
+        assert_eq!(
+            Labels::from_sql(&PgValue::Text("a.b".into())).unwrap(),
+            labels
+        );
+    }
+
+    #[test]
+    fn test_column_metadata() {
+        let row = make_row(
+            &[("a", OID_TEXT), ("b", OID_INT4), ("c", 99_999)],
+            &[Some(b"x"), Some(b"1"), None],
+        );
+        let cols = row.columns();
+        assert_eq!(cols[0].type_name(), Some("text"));
+        assert_eq!(cols[1].type_name(), Some("int4"));
+        assert_eq!(cols[2].type_name(), None);
+        assert_eq!(cols[0].source(), None);
+    }
+
     // ─── get_typed / get_typed_by_name ────────────────────────────────────────
 
     #[test]
@@ -627,4 +780,47 @@ mod tests {
         assert!(matches!(row.get(0).unwrap(), PgValue::Int4(1)));
         assert!(matches!(row.get(2).unwrap(), PgValue::Bool(true)));
     }
+
+    // ─── Bytea ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_get_bytes_binary_borrows() {
+        let mut desc = col("blob", oid::BYTEA);
+        desc.format_code = FormatCode::Binary;
+        let blob: Vec<u8> = (0..=255).collect();
+        let row = Row::new(Rc::new(vec![desc]), vec![Some(&blob)]);
+        let got = row.get_bytes(0).unwrap().unwrap();
+        assert!(matches!(got, Cow::Borrowed(_)));
+        assert_eq!(&*got, blob.as_slice());
+    }
+
+    #[test]
+    fn test_get_bytes_text_formats() {
+        let row = make_row(
+            &[
+                ("hex", oid::BYTEA),
+                ("esc", oid::BYTEA),
+                ("none", oid::BYTEA),
+            ],
+            &[Some(b"\\x00ff5c"), Some(b"a\\\\b\\001"), None],
+        );
+        assert_eq!(&*row.get_bytes(0).unwrap().unwrap(), &[0x00, 0xff, b'\\']);
+        assert_eq!(&*row.get_bytes(1).unwrap().unwrap(), b"a\\b\x01");
+        assert!(row.get_bytes(2).unwrap().is_none());
+        assert!(matches!(row.get(0).unwrap(), PgValue::Bytes(b) if b == [0x00, 0xff, b'\\']));
+    }
+
+    #[test]
+    fn test_get_bytes_rejects_other_types() {
+        let row = make_row(&[("name", OID_TEXT)], &[Some(b"alice")]);
+        assert!(row.get_bytes(0).is_err());
+        assert!(row.get_bytes(1).is_err());
+    }
+
+    #[test]
+    fn test_mock_bytes_round_trip() {
+        let row = Row::mock(&["blob"], &[PgValue::Bytes(vec![0, 1, 0x5c, 0xff])]);
+        assert_eq!(row.columns()[0].type_oid, oid::BYTEA);
+        assert_eq!(row.get_typed::<Vec<u8>>(0).unwrap(), vec![0, 1, 0x5c, 0xff]);
+    }
 }
@@ -0,0 +1,326 @@
+//! A pool shared across threads, checked out from async code (`tokio`
+//! feature).
+//!
+//! [`PgPool`] belongs to one worker thread. Servers built on tokio, such as
+//! Axum, run a handler on whichever thread is free, and a query blocks the
+//! thread it runs on. [`PgPool::into_shared`] turns a pool into a
+//! [`SharedPool`] for them. [`SharedPool::get_async`] waits for a permit
+//! without blocking, then checks out on tokio's blocking threads, because
+//! opening a connection blocks. The [`Acquired`] connection it returns is
+//! `Send`. Move it into `tokio::task::spawn_blocking` to run queries:
+//!
+//! ```ignore
+//! let pool = PgPool::with_config(config, PgPoolConfig::new().max_size(16)).into_shared();
+//!
+//! async fn user_name(State(pool): State<SharedPool>, Path(id): Path<i32>) -> Result<String, StatusCode> {
+//!     let mut conn = pool.get_async().await.map_err(|_| StatusCode::SERVICE_UNAVAILABLE)?;
+//!     tokio::task::spawn_blocking(move || {
+//!         let row = conn.query_one("SELECT name FROM users WHERE id = $1", &[&id])?;
+//!         row.get_typed::<String>(0)
+//!     })
+//!     .await
+//!     .unwrap()
+//!     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)
+//! }
+//! ```
+//!
+//! Rows are not `Send`. Convert them to owned values inside the closure.
+//! There are `max_size` permits, one per connection, and waiters get them
+//! in arrival order. A waiter gives up with `PoolTimeout` after
+//! `checkout_timeout` (5 seconds if unset). The runtime must have its time
+//! driver enabled, as `#[tokio::main]` does.
+//!
+//! The lock around the pool is held only to pick a connection or reserve
+//! room for one. Connecting, validating and closing connections happen
+//! after it is released, so one slow server does not hold up checkouts and
+//! returns on other threads.
+//!
+//! This bridges the synchronous driver until the driver can run queries
+//! without blocking. Each query still holds a blocking thread while it runs.
+//!
+//! [`PgPool`]: crate::PgPool
+//! [`PgPool::into_shared`]: crate::PgPool::into_shared
+
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::connection::{PgConfig, PgConnection};
+use crate::error::{PgError, PgResult};
+use crate::pool::{PgPool, PgPoolConfig, PoolStats, PooledConn, Reservation};
+
+/// A [`PgPool`] behind a lock, checked out with [`get_async`](Self::get_async).
+/// Cloning shares the pool.
+#[derive(Clone)]
+pub struct SharedPool {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    pool: Mutex<PgPool>,
+    permits: Arc<Semaphore>,
+    checkout_timeout: Duration,
+    /// Copies of the pool's settings, read without the lock.
+    config: PgConfig,
+    pool_config: PgPoolConfig,
+}
+
+impl Inner {
+    fn lock(&self) -> MutexGuard<'_, PgPool> {
+        self.pool.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Check out a connection, holding the lock only to reserve it.
+    fn checkout(&self) -> PgResult<PooledConn> {
+        let Reservation { slot, closing } = self.lock().reserve();
+        drop(closing);
+        let pooled = match slot? {
+            Some(pooled) => self.validate(pooled),
+            None => self.open(),
+        };
+        if pooled.is_err() {
+            self.lock().release_slot();
+        }
+        pooled
+    }
+
+    fn open(&self) -> PgResult<PooledConn> {
+        let conn = PgConnection::connect(&self.config)?;
+        self.lock().record_opened();
+        Ok(PooledConn::new(conn))
+    }
+
+    fn validate(&self, mut pooled: PooledConn) -> PgResult<PooledConn> {
+        let pool_config = &self.pool_config;
+        if !pool_config.test_on_checkout
+            || pooled
+                .conn
+                .query_simple(&pool_config.validation_query)
+                .is_ok()
+        {
+            return Ok(pooled);
+        }
+        self.lock().record_validation_failure();
+        drop(pooled);
+        if !pool_config.auto_reconnect {
+            return Err(PgError::PoolValidationFailed);
+        }
+        self.open()
+    }
+}
+
+impl PgPool {
+    /// Share this pool across threads and async tasks. See [`SharedPool`].
+    pub fn into_shared(self) -> SharedPool {
+        let config = self.config().clone();
+        let pool_config = self.pool_config().clone();
+        let permits = Arc::new(Semaphore::new(pool_config.max_size));
+        let checkout_timeout = pool_config
+            .checkout_timeout
+            .unwrap_or(Duration::from_secs(5));
+        SharedPool {
+            inner: Arc::new(Inner {
+                pool: Mutex::new(self),
+                permits,
+                checkout_timeout,
+                config,
+                pool_config,
+            }),
+        }
+    }
+}
+
+impl SharedPool {
+    /// Wait for a free connection without blocking the calling task.
+    ///
+    /// Returns `PoolTimeout` if no permit comes free within
+    /// `checkout_timeout`. Otherwise the checkout runs on a blocking thread
+    /// and returns its error, if any.
+    pub async fn get_async(&self) -> PgResult<Acquired> {
+        let permits = self.inner.permits.clone();
+        let permit = match tokio::time::timeout(
+            self.inner.checkout_timeout,
+            permits.acquire_owned(),
+        )
+        .await
+        {
+            Ok(Ok(permit)) => permit,
+            Ok(Err(_closed)) => return Err(PgError::ConnectionClosed),
+            Err(_elapsed) => {
+                self.inner.lock().record_checkout_timeout();
+                return Err(PgError::PoolTimeout);
+            }
+        };
+        let inner = self.inner.clone();
+        // The guard is built on the blocking thread, so a connection checked
+        // out for a caller that stopped waiting still goes back to the pool.
+        let checkout = tokio::task::spawn_blocking(move || {
+            let pooled = inner.checkout()?;
+            Ok(Acquired {
+                conn: Some(pooled),
+                pool: inner,
+                _permit: permit,
+            })
+        });
+        match checkout.await {
+            Ok(result) => result,
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(_cancelled) => Err(PgError::ConnectionClosed),
+        }
+    }
+
+    /// Statistics of the underlying pool.
+    pub fn stats(&self) -> PoolStats {
+        self.inner.lock().stats().clone()
+    }
+
+    /// Permits free right now: connections a caller could check out
+    /// without waiting.
+    pub fn available_permits(&self) -> usize {
+        self.inner.permits.available_permits()
+    }
+}
+
+/// A connection checked out of a [`SharedPool`], returned to it on drop.
+///
+/// Derefs to [`PgConnection`]. Its queries block, so use it inside
+/// `tokio::task::spawn_blocking` rather than on an async task.
+pub struct Acquired {
+    conn: Option<PooledConn>,
+    pool: Arc<Inner>,
+    /// Released after the connection is back in the pool.
+    _permit: OwnedSemaphorePermit,
+}
+
+impl Acquired {
+    /// Get a mutable reference to the underlying connection.
+    #[inline]
+    pub fn conn(&mut self) -> &mut PgConnection {
+        &mut self.conn.as_mut().expect("Acquired used after drop").conn
+    }
+}
+
+impl std::ops::Deref for Acquired {
+    type Target = PgConnection;
+    fn deref(&self) -> &PgConnection {
+        &self.conn.as_ref().expect("Acquired used after drop").conn
+    }
+}
+
+impl std::ops::DerefMut for Acquired {
+    fn deref_mut(&mut self) -> &mut PgConnection {
+        self.conn()
+    }
+}
+
+impl Drop for Acquired {
+    fn drop(&mut self) {
+        if let Some(pooled) = self.conn.take() {
+            // Closing sends Terminate, so do it after releasing the lock.
+            let closing = self.pool.lock().checkin(pooled);
+            drop(closing);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::PgConfig;
+    use crate::pool::PgPoolConfig;
+
+    fn unreachable() -> PgConfig {
+        // Nothing listens on port 1.
+        PgConfig::new("127.0.0.1", 1, "test", "test", "testdb")
+    }
+
+    /// A server that lets the first client in without a password and keeps
+    /// every later one waiting for `stall` before hanging up.
+    fn stalling_server(stall: Duration) -> PgConfig {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let mut open = Vec::new();
+            for (i, stream) in listener.incoming().enumerate() {
+                let mut s = stream.unwrap();
+                if i > 0 {
+                    std::thread::spawn(move || {
+                        std::thread::sleep(stall);
+                        drop(s);
+                    });
+                    continue;
+                }
+                let mut len = [0u8; 4];
+                s.read_exact(&mut len).unwrap();
+                let mut startup = vec![0u8; i32::from_be_bytes(len) as usize - 4];
+                s.read_exact(&mut startup).unwrap();
+                s.write_all(&[b'R', 0, 0, 0, 8, 0, 0, 0, 0]).unwrap();
+                s.write_all(&[b'Z', 0, 0, 0, 5, b'I']).unwrap();
+                open.push(s);
+            }
+        });
+        let config = PgConfig::new("127.0.0.1", port, "test", "test", "testdb");
+        #[cfg(feature = "tls")]
+        let config = config.with_ssl_mode(crate::tls::SslMode::Disable);
+        config
+    }
+
+    fn is_send<T: Send>() {}
+
+    #[test]
+    fn test_handles_are_send() {
+        is_send::<SharedPool>();
+        is_send::<Acquired>();
+    }
+
+    #[tokio::test]
+    async fn test_get_async_times_out_without_permits() {
+        let pool_cfg = PgPoolConfig::new()
+            .max_size(0)
+            .checkout_timeout(Duration::from_millis(20));
+        let pool = PgPool::with_config(unreachable(), pool_cfg).into_shared();
+        assert!(matches!(pool.get_async().await, Err(PgError::PoolTimeout)));
+        assert_eq!(pool.stats().checkout_timeouts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_checkout_releases_its_permit() {
+        let pool =
+            PgPool::with_config(unreachable(), PgPoolConfig::new().max_size(1)).into_shared();
+        for _ in 0..2 {
+            assert!(matches!(pool.get_async().await, Err(PgError::Io(_))));
+            assert_eq!(pool.available_permits(), 1);
+        }
+        assert_eq!(pool.stats().checkout_timeouts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_slow_connect_does_not_block_other_callers() {
+        let stall = Duration::from_secs(2);
+        let pool = PgPool::with_config(stalling_server(stall), PgPoolConfig::new().max_size(2))
+            .into_shared();
+        let conn = pool.get_async().await.unwrap();
+
+        // The second checkout hangs in its connect until the server gives up.
+        let slow = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.get_async().await.map(drop) }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let start = std::time::Instant::now();
+        drop(conn);
+        assert_eq!(pool.stats().total_connections_created, 1);
+        assert_eq!(pool.stats().total_checkouts, 2);
+        assert!(
+            start.elapsed() < stall / 4,
+            "blocked for {:?}",
+            start.elapsed()
+        );
+
+        assert!(slow.await.unwrap().is_err());
+        assert_eq!(pool.available_permits(), 2);
+    }
+}
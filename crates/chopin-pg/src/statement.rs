@@ -1,14 +1,26 @@
-//! Implicit statement caching with LRU eviction.
+//! Implicit statement caching with LRU eviction, and explicitly named
+//! prepared statements.
 //!
 //! Each connection maintains its own local cache — no synchronization needed.
 //! When the cache exceeds `max_capacity`, the least-recently-used entry is
 //! evicted and a Close message should be sent to the server.
+//!
+//! Hot statements can instead be prepared once, by name, with
+//! [`PgConnection::prepare`](crate::PgConnection::prepare) and run with
+//! [`PgConnection::execute_prepared`](crate::PgConnection::execute_prepared).
+//! Named statements live outside the cache and are never evicted.
+//!
+//! Behind a transaction-pooling proxy such as PgBouncer, consecutive
+//! transactions may run on different server connections, so a statement
+//! prepared on one is missing on the next. An [`unnamed`](StatementCache::unnamed)
+//! cache caches nothing: every query parses and describes the unnamed
+//! statement again in the same round-trip as its Bind and Execute.
 
 use crate::codec::ColumnDesc;
 use std::collections::HashMap;
 
 /// Default maximum number of cached statements before LRU eviction kicks in.
-const DEFAULT_MAX_CAPACITY: usize = 256;
+pub const DEFAULT_MAX_CAPACITY: usize = 256;
 
 /// A cached prepared statement with its row description.
 #[derive(Debug, Clone)]
@@ -34,9 +46,33 @@ pub struct StatementCache {
     tick: u64,
     /// Maximum number of entries before LRU eviction.
     max_capacity: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+    /// Use the unnamed statement for every query instead of caching.
+    unnamed: bool,
+}
+
+/// Counters for a connection's implicit statement cache.
+///
+/// A steadily rising `evictions` count means the workload has more distinct
+/// query shapes than the cache holds, so statements are re-parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatementCacheStats {
+    /// Lookups that reused a server-side statement.
+    pub hits: u64,
+    /// Lookups that had to Parse a new statement.
+    pub misses: u64,
+    /// Statements evicted (and closed on the server) to stay under capacity.
+    pub evictions: u64,
+    /// Statements currently cached.
+    pub len: usize,
+    pub capacity: usize,
 }
 
-/// A reference to a cached or new statement.
+/// A reference to a cached or new statement, or a named statement returned
+/// by [`PgConnection::prepare`](crate::PgConnection::prepare).
+#[derive(Debug, Clone)]
 pub struct Statement {
     /// Statement name on the server.
     pub name: String,
@@ -44,6 +80,16 @@ pub struct Statement {
     pub is_new: bool,
     /// Cached column descriptions (if previously executed).
     pub columns: Option<Vec<ColumnDesc>>,
+    /// Parameter type OIDs from the server's ParameterDescription. Only
+    /// known for prepared statements; empty for cache lookups.
+    pub param_types: Vec<u32>,
+}
+
+impl Statement {
+    /// Number of parameters, as described by the server.
+    pub fn param_count(&self) -> usize {
+        self.param_types.len()
+    }
 }
 
 /// Info about an evicted statement, so the caller can send a Close message.
@@ -66,6 +112,10 @@ impl StatementCache {
             counter: 0,
             tick: 0,
             max_capacity: DEFAULT_MAX_CAPACITY,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            unnamed: false,
         }
     }
 
@@ -76,14 +126,59 @@ impl StatementCache {
             counter: 0,
             tick: 0,
             max_capacity,
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+            unnamed: false,
         }
     }
 
-    /// Set the maximum capacity. Does not immediately evict.
+    /// A cache that never caches: every lookup is a miss for the unnamed
+    /// statement, and [`insert`](Self::insert) does nothing. For connections
+    /// behind a transaction-pooling proxy.
+    pub fn unnamed() -> Self {
+        Self {
+            unnamed: true,
+            ..Self::with_capacity(0)
+        }
+    }
+
+    /// Whether this cache uses the unnamed statement instead of caching.
+    pub fn is_unnamed(&self) -> bool {
+        self.unnamed
+    }
+
+    /// Set the maximum capacity. Does not immediately evict; see
+    /// [`shrink_to_capacity`](Self::shrink_to_capacity).
     pub fn set_max_capacity(&mut self, max_capacity: usize) {
         self.max_capacity = max_capacity;
     }
 
+    /// Evict least-recently-used entries until the cache fits its capacity.
+    /// Returns the evicted statements so the caller can Close them.
+    pub fn shrink_to_capacity(&mut self) -> Vec<EvictedStatement> {
+        let mut evicted = Vec::new();
+        while self.cache.len() > self.max_capacity {
+            match self.evict_lru() {
+                Some(e) => evicted.push(e),
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    /// Hit/miss/eviction counters since the cache was created. Counters
+    /// survive [`clear`](Self::clear).
+    pub fn stats(&self) -> StatementCacheStats {
+        StatementCacheStats {
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+            len: self.cache.len(),
+            capacity: self.max_capacity,
+        }
+    }
+
     /// Get the maximum capacity.
     pub fn max_capacity(&self) -> usize {
         self.max_capacity
@@ -92,24 +187,37 @@ impl StatementCache {
     /// Look up or create a statement for the given SQL.
     /// Returns a Statement reference indicating whether it's new or cached.
     pub fn get_or_create(&mut self, sql: &str) -> Statement {
+        if self.unnamed {
+            self.misses += 1;
+            return Statement {
+                name: String::new(),
+                is_new: true,
+                columns: None,
+                param_types: Vec::new(),
+            };
+        }
         let hash = Self::hash_sql(sql);
         self.tick += 1;
         let current_tick = self.tick;
 
         if let Some(cached) = self.cache.get_mut(&hash) {
+            self.hits += 1;
             cached.access_tick = current_tick;
             Statement {
                 name: cached.name.clone(),
                 is_new: false,
                 columns: cached.columns.clone(),
+                param_types: Vec::new(),
             }
         } else {
+            self.misses += 1;
             let name = format!("s{}", self.counter);
             self.counter += 1;
             Statement {
                 name,
                 is_new: true,
                 columns: None,
+                param_types: Vec::new(),
             }
         }
     }
@@ -123,6 +231,9 @@ impl StatementCache {
         param_count: usize,
         columns: Option<Vec<ColumnDesc>>,
     ) -> Option<EvictedStatement> {
+        if self.unnamed {
+            return None;
+        }
         let hash = Self::hash_sql(sql);
         self.tick += 1;
         let evicted = if self.cache.len() >= self.max_capacity && !self.cache.contains_key(&hash) {
@@ -154,6 +265,7 @@ impl StatementCache {
             .min_by_key(|(_, v)| v.access_tick)
             .map(|(k, _)| k)?;
         let evicted = self.cache.remove(&lru_key)?;
+        self.evictions += 1;
         Some(EvictedStatement { name: evicted.name })
     }
 
@@ -192,6 +304,13 @@ impl StatementCache {
         self.cache.values().map(|c| c.name.clone()).collect()
     }
 
+    /// Whether `name` has the `s<N>` form used for implicitly cached
+    /// statements, which named statements must not reuse.
+    pub fn is_reserved_name(name: &str) -> bool {
+        name.strip_prefix('s')
+            .is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()))
+    }
+
     /// FNV-1a hash for SQL strings (fast, no allocations).
     fn hash_sql(sql: &str) -> u64 {
         let mut hash: u64 = 0xcbf29ce484222325;
@@ -503,6 +622,69 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_stats_count_hits_misses_evictions() {
+        let mut cache = StatementCache::with_capacity(2);
+        for sql in ["A", "B", "A", "C", "A"] {
+            let stmt = cache.get_or_create(sql);
+            if stmt.is_new {
+                cache.insert(sql, stmt.name, 0, None);
+            }
+        }
+        let stats = cache.stats();
+        assert_eq!(stats.hits, 2);
+        assert_eq!(stats.misses, 3);
+        assert_eq!(stats.evictions, 1); // "B" made room for "C"
+        assert_eq!((stats.len, stats.capacity), (2, 2));
+
+        cache.clear();
+        assert_eq!(cache.stats().misses, 3, "counters survive clear()");
+    }
+
+    #[test]
+    fn test_shrink_to_capacity_evicts_lru_first() {
+        let mut cache = StatementCache::with_capacity(3);
+        cache.insert("A", "s0".to_string(), 0, None);
+        cache.insert("B", "s1".to_string(), 0, None);
+        cache.insert("C", "s2".to_string(), 0, None);
+        let _ = cache.get_or_create("A");
+
+        cache.set_max_capacity(1);
+        let evicted: Vec<String> = cache
+            .shrink_to_capacity()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(evicted, vec!["s1", "s2"]);
+        assert!(!cache.get_or_create("A").is_new);
+        assert_eq!(cache.stats().evictions, 2);
+    }
+
+    #[test]
+    fn test_unnamed_cache_never_caches() {
+        let mut cache = StatementCache::unnamed();
+        assert!(cache.is_unnamed());
+        for _ in 0..2 {
+            let stmt = cache.get_or_create("SELECT 1");
+            assert_eq!(stmt.name, "");
+            assert!(stmt.is_new);
+            assert!(cache.insert("SELECT 1", stmt.name, 0, None).is_none());
+        }
+        assert!(cache.is_empty());
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (0, 2, 0));
+        assert!(!StatementCache::new().is_unnamed());
+    }
+
+    #[test]
+    fn test_reserved_names() {
+        assert!(StatementCache::is_reserved_name("s0"));
+        assert!(StatementCache::is_reserved_name("s123"));
+        assert!(!StatementCache::is_reserved_name("s"));
+        assert!(!StatementCache::is_reserved_name("select_user"));
+        assert!(!StatementCache::is_reserved_name("s1x"));
+    }
+
     #[test]
     fn test_cache_len_is_empty_consistent() {
         let mut cache = StatementCache::new();
@@ -7,28 +7,33 @@ use std::io::{self, BufReader, Read, Write};
 use std::net::TcpStream;
 use std::sync::Arc;
 
-use rustls::pki_types::ServerName;
-use rustls::{ClientConfig, ClientConnection, RootCertStore};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::WebPkiSupportedAlgorithms;
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{
+    ClientConfig, ClientConnection, DigitallySignedStruct, RootCertStore, SignatureScheme,
+};
 
 use crate::error::{PgError, PgResult};
 
 // ─── SSL Mode ─────────────────────────────────────────────────
 
-/// SSL/TLS mode for PostgreSQL connections.
+/// SSL/TLS mode for PostgreSQL connections, following libpq's `sslmode`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum SslMode {
     /// Never use TLS. Fail if the server requires it.
     Disable,
-    /// Try TLS first; fall back to plaintext if the server doesn't support it.
+    /// Try TLS first; fall back to plaintext if the server doesn't support it
+    /// or the handshake fails.
     #[default]
     Prefer,
-    /// Require TLS. Verify the server certificate against the configured
-    /// root CA(s). Fail if the server doesn't support TLS or the certificate
-    /// cannot be verified.
+    /// Require TLS. As with libpq, the certificate is only verified when a
+    /// root certificate is configured (`sslrootcert`); otherwise the
+    /// connection is encrypted but the server is not authenticated.
     Require,
-    /// Require TLS, verify the server certificate, **and** verify that the
-    /// server hostname matches the certificate's CN / SAN. This is the
-    /// strictest mode and is recommended for production / AWS RDS connections.
+    /// Require TLS, verify the server certificate against the configured
+    /// root CA(s) (or the Mozilla roots), **and** verify that the server
+    /// hostname matches it. Recommended for production / AWS RDS connections.
     VerifyFull,
 }
 
@@ -37,14 +42,22 @@ impl SslMode {
     pub fn parse(s: &str) -> Option<Self> {
         match s {
             "disable" => Some(SslMode::Disable),
-            "prefer" => Some(SslMode::Prefer),
+            // libpq's allow tries plaintext first; encrypting when possible
+            // is at least as strong.
+            "allow" | "prefer" => Some(SslMode::Prefer),
             "require" => Some(SslMode::Require),
             "verify-full" | "verify_full" => Some(SslMode::VerifyFull),
-            // Map verify-ca to Require (cert verification without extra hostname check)
-            "verify-ca" | "verify_ca" => Some(SslMode::Require),
+            // verify-ca is not modelled separately; verifying the hostname as
+            // well is stricter, never weaker.
+            "verify-ca" | "verify_ca" => Some(SslMode::VerifyFull),
             _ => None,
         }
     }
+
+    /// Whether the server certificate must be verified in this mode.
+    fn verifies(self, ssl_root_cert: Option<&str>) -> bool {
+        self == SslMode::VerifyFull || ssl_root_cert.is_some()
+    }
 }
 
 // ─── PostgreSQL SSLRequest ────────────────────────────────────
@@ -59,7 +72,7 @@ const SSL_REQUEST: [u8; 8] = [0x00, 0x00, 0x00, 0x08, 0x04, 0xd2, 0x16, 0x2f];
 /// Result of attempting TLS negotiation with the server.
 pub(crate) enum TlsNegotiateResult {
     /// Server accepted TLS — stream is encrypted.
-    Tls(TlsStream),
+    Tls(Box<TlsStream>),
     /// Server rejected TLS — TCP stream returned for plain-text use.
     Rejected(TcpStream),
 }
@@ -70,6 +83,9 @@ pub(crate) enum TlsNegotiateResult {
 /// response (`S` = proceed, `N` = refused), and either completes the TLS
 /// handshake or returns the TCP stream for plain-text use.
 ///
+/// The certificate is verified for [`SslMode::VerifyFull`], or whenever
+/// `ssl_root_cert` is given; otherwise the session is encrypted only.
+///
 /// `ssl_root_cert` — optional path to a PEM file containing one or more root
 /// CA certificates to use as the trust store. When `Some`, these certs
 /// **replace** the Mozilla WebPKI roots, which is required for AWS RDS
@@ -80,6 +96,7 @@ pub(crate) enum TlsNegotiateResult {
 pub(crate) fn negotiate(
     mut tcp: TcpStream,
     host: &str,
+    mode: SslMode,
     ssl_root_cert: Option<&str>,
 ) -> PgResult<TlsNegotiateResult> {
     // Send SSLRequest
@@ -93,16 +110,22 @@ pub(crate) fn negotiate(
         return Ok(TlsNegotiateResult::Rejected(tcp));
     }
 
-    // Build root cert store — custom CA bundle takes priority over WebPKI roots.
-    let root_store = match ssl_root_cert {
-        Some(path) => load_root_certs_from_pem(path)?,
-        None => RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    let config = if mode.verifies(ssl_root_cert) {
+        // Build root cert store — custom CA bundle takes priority over WebPKI roots.
+        let root_store = match ssl_root_cert {
+            Some(path) => load_root_certs_from_pem(path)?,
+            None => RootCertStore::from_iter(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+        };
+        ClientConfig::builder()
+            .with_root_certificates(root_store)
+            .with_no_client_auth()
+    } else {
+        ClientConfig::builder()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(EncryptOnly::new()))
+            .with_no_client_auth()
     };
 
-    let config = ClientConfig::builder()
-        .with_root_certificates(root_store)
-        .with_no_client_auth();
-
     let server_name = ServerName::try_from(host.to_owned())
         .map_err(|e| PgError::Protocol(format!("Invalid TLS server name '{}': {}", host, e)))?;
 
@@ -114,7 +137,59 @@ pub(crate) fn negotiate(
     // Complete the TLS handshake (blocking)
     stream.complete_handshake()?;
 
-    Ok(TlsNegotiateResult::Tls(stream))
+    Ok(TlsNegotiateResult::Tls(Box::new(stream)))
+}
+
+// ─── Unverified Certificates ──────────────────────────────────
+
+/// Accepts any server certificate while still checking handshake
+/// signatures, for `prefer` / `require` without a root certificate.
+#[derive(Debug)]
+struct EncryptOnly {
+    algorithms: WebPkiSupportedAlgorithms,
+}
+
+impl EncryptOnly {
+    fn new() -> Self {
+        Self {
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+        }
+    }
+}
+
+impl ServerCertVerifier for EncryptOnly {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        rustls::crypto::verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
 }
 
 // ─── PEM Certificate Loading ──────────────────────────────────
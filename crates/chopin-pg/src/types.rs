@@ -1,5 +1,8 @@
 //! PostgreSQL type system — type OIDs, value conversions, and ToSql/FromSql traits.
+use std::ops::{Bound, RangeBounds};
+
 use crate::error::{PgError, PgResult};
+use crate::protocol::FormatCode;
 
 /// Well-known PostgreSQL type OIDs.
 pub mod oid {
@@ -14,6 +17,9 @@ pub mod oid {
     pub const FLOAT4: u32 = 700;
     pub const FLOAT8: u32 = 701;
     pub const VARCHAR: u32 = 1043;
+    pub const BPCHAR: u32 = 1042;
+    pub const NAME: u32 = 19;
+    pub const XML: u32 = 142;
     pub const DATE: u32 = 1082;
     pub const TIME: u32 = 1083;
     pub const TIMESTAMP: u32 = 1114;
@@ -59,6 +65,73 @@ pub mod oid {
     pub const TSRANGE: u32 = 3908;
     pub const TSTZRANGE: u32 = 3910;
     pub const DATERANGE: u32 = 3912;
+
+    // Anonymous composite (`ROW(...)`, functions returning `record`)
+    pub const RECORD: u32 = 2249;
+
+    /// OIDs below this are built in; user-defined types are allocated from here.
+    pub const FIRST_NORMAL_OBJECT_ID: u32 = 16384;
+
+    /// `pg_type.typname` of the types above.
+    pub fn name(oid: u32) -> Option<&'static str> {
+        Some(match oid {
+            BOOL => "bool",
+            BYTEA => "bytea",
+            CHAR => "char",
+            INT8 => "int8",
+            INT2 => "int2",
+            INT4 => "int4",
+            TEXT => "text",
+            OID => "oid",
+            FLOAT4 => "float4",
+            FLOAT8 => "float8",
+            VARCHAR => "varchar",
+            BPCHAR => "bpchar",
+            NAME => "name",
+            XML => "xml",
+            DATE => "date",
+            TIME => "time",
+            TIMESTAMP => "timestamp",
+            TIMESTAMPTZ => "timestamptz",
+            INTERVAL => "interval",
+            NUMERIC => "numeric",
+            UUID => "uuid",
+            JSONB => "jsonb",
+            JSON => "json",
+            INET => "inet",
+            CIDR => "cidr",
+            MACADDR => "macaddr",
+            MACADDR8 => "macaddr8",
+            POINT => "point",
+            LINE => "line",
+            LSEG => "lseg",
+            BOX => "box",
+            PATH => "path",
+            POLYGON => "polygon",
+            CIRCLE => "circle",
+            BOOL_ARRAY => "_bool",
+            INT2_ARRAY => "_int2",
+            INT4_ARRAY => "_int4",
+            INT8_ARRAY => "_int8",
+            TEXT_ARRAY => "_text",
+            FLOAT4_ARRAY => "_float4",
+            FLOAT8_ARRAY => "_float8",
+            VARCHAR_ARRAY => "_varchar",
+            UUID_ARRAY => "_uuid",
+            JSONB_ARRAY => "_jsonb",
+            JSON_ARRAY => "_json",
+            BIT => "bit",
+            VARBIT => "varbit",
+            INT4RANGE => "int4range",
+            INT8RANGE => "int8range",
+            NUMRANGE => "numrange",
+            TSRANGE => "tsrange",
+            TSTZRANGE => "tstzrange",
+            DATERANGE => "daterange",
+            RECORD => "record",
+            _ => return None,
+        })
+    }
 }
 
 /// A PostgreSQL value that can be used as a query parameter or read from a row.
@@ -114,6 +187,9 @@ pub enum PgValue {
     Range(String),
     /// Array of values (homogeneous).
     Array(Vec<PgValue>),
+    /// Composite (row) value, fields in declaration order. Field names are
+    /// not sent with the value; see `PgConnection::composite_fields`.
+    Composite(Vec<PgValue>),
 }
 
 impl PgValue {
@@ -128,7 +204,7 @@ impl PgValue {
             PgValue::Float4(v) => Some(v.to_string().into_bytes()),
             PgValue::Float8(v) => Some(v.to_string().into_bytes()),
             PgValue::Text(s) => Some(s.as_bytes().to_vec()),
-            PgValue::Bytes(b) => Some(b.clone()),
+            PgValue::Bytes(b) => Some(encode_bytea_hex(b).into_bytes()),
             PgValue::Json(s) => Some(s.as_bytes().to_vec()),
             PgValue::Jsonb(b) => Some(b.clone()),
             PgValue::Uuid(bytes) => Some(format_uuid(bytes).into_bytes()),
@@ -188,6 +264,7 @@ impl PgValue {
                     .collect();
                 Some(format!("{{{}}}", inner.join(",")).into_bytes())
             }
+            PgValue::Composite(fields) => Some(format_record(fields).into_bytes()),
         }
     }
 
@@ -232,8 +309,9 @@ impl PgValue {
             }
             PgValue::Inet(s) => encode_inet_binary(s).ok(),
             PgValue::Numeric(s) => {
-                // NUMERIC is complex in binary — fall back to text encoding
-                Some(s.as_bytes().to_vec())
+                // Strings that are not numeric literals are sent as text (see
+                // `prefers_binary`) so the server reports the error.
+                encode_numeric_binary(s).or_else(|| Some(s.as_bytes().to_vec()))
             }
             PgValue::MacAddr(bytes) => Some(bytes.to_vec()),
             PgValue::MacAddr8(bytes) => Some(bytes.to_vec()),
@@ -255,8 +333,11 @@ impl PgValue {
                 Some(s.as_bytes().to_vec())
             }
             PgValue::Array(values) => {
-                // Use text array format for encoding — binary array encoding
-                // requires knowing the element OID which PgValue doesn't carry.
+                if let Some(elem_oid) = binary_array_element_oid(values) {
+                    return Some(encode_binary_array(elem_oid, values));
+                }
+                // Element OID can't be inferred (empty, mixed or text-like
+                // elements): fall back to the text array literal.
                 let inner: Vec<String> = values
                     .iter()
                     .map(|v| match v {
@@ -272,15 +353,24 @@ impl PgValue {
                     .collect();
                 Some(format!("{{{}}}", inner.join(",")).into_bytes())
             }
+            // Field OIDs are unknown here; the text literal lets the server
+            // coerce each field.
+            PgValue::Composite(fields) => Some(format_record(fields).into_bytes()),
         }
     }
 
     /// Determine if this value should be sent as binary or text format.
     ///
     /// Returns `true` for types that have an efficient binary encoding
-    /// (scalars, dates, etc.), `false` for types best sent as text
-    /// (arrays, numeric, inet).
+    /// (scalars, dates, numeric literals, etc.), `false` for types best
+    /// sent as text (arrays, inet).
     pub fn prefers_binary(&self) -> bool {
+        if let PgValue::Numeric(s) = self {
+            return parse_numeric(s).is_some();
+        }
+        if let PgValue::Array(values) = self {
+            return binary_array_element_oid(values).is_some();
+        }
         matches!(
             self,
             PgValue::Bool(_)
@@ -336,7 +426,8 @@ impl PgValue {
             oid::NUMERIC => Ok(PgValue::Numeric(s.to_string())),
             oid::JSONB => Ok(PgValue::Jsonb(data.to_vec())),
             oid::JSON => Ok(PgValue::Json(s.to_string())),
-            oid::BYTEA => Ok(PgValue::Bytes(decode_bytea_hex(s))),
+            oid::BYTEA => Ok(PgValue::Bytes(decode_bytea_text(s))),
+            oid::RECORD => parse_text_record(s),
             oid::UUID => Ok(PgValue::Uuid(parse_uuid_text(s)?)),
             oid::DATE => Ok(PgValue::Date(parse_date_text(s)?)),
             oid::TIME => Ok(PgValue::Time(parse_time_text(s)?)),
@@ -385,7 +476,10 @@ impl PgValue {
             | oid::TSRANGE
             | oid::TSTZRANGE
             | oid::DATERANGE => Ok(PgValue::Range(s.to_string())),
-            _ => Ok(PgValue::Text(s.to_string())),
+            _ => match array_element_oid(type_oid) {
+                Some(elem_oid) => parse_text_array(elem_oid, s),
+                None => Ok(PgValue::Text(s.to_string())),
+            },
         }
     }
 
@@ -442,11 +536,14 @@ impl PgValue {
                 })
             }
             oid::JSONB => {
-                // First byte is version (1), rest is JSON
-                if data.len() > 1 {
-                    Ok(PgValue::Jsonb(data[1..].to_vec()))
-                } else {
-                    Ok(PgValue::Jsonb(Vec::new()))
+                // First byte is the format version; 1 is the only one defined.
+                match data.split_first() {
+                    Some((1, json)) => Ok(PgValue::Jsonb(json.to_vec())),
+                    Some((version, _)) => Err(PgError::TypeConversion(format!(
+                        "Unsupported JSONB version {}",
+                        version
+                    ))),
+                    None => Ok(PgValue::Jsonb(Vec::new())),
                 }
             }
             oid::BYTEA => Ok(PgValue::Bytes(data.to_vec())),
@@ -555,6 +652,21 @@ impl PgValue {
             | oid::UUID_ARRAY
             | oid::JSONB_ARRAY
             | oid::JSON_ARRAY => parse_binary_array(data),
+            oid::INT4RANGE
+            | oid::INT8RANGE
+            | oid::NUMRANGE
+            | oid::TSRANGE
+            | oid::TSTZRANGE
+            | oid::DATERANGE => parse_binary_range(type_oid, data),
+            oid::RECORD => parse_binary_record(data),
+            // Named composite types get a fresh OID per database. Their binary
+            // layout is self-describing, so accept anything that parses as a
+            // record exactly; enums, domains over text and the like do not.
+            _ if type_oid >= oid::FIRST_NORMAL_OBJECT_ID
+                && let Ok(record) = parse_binary_record(data) =>
+            {
+                Ok(record)
+            }
             _ => {
                 // Fallback: treat as text
                 Ok(PgValue::Text(String::from_utf8_lossy(data).to_string()))
@@ -615,6 +727,14 @@ impl PgValue {
     pub fn is_null(&self) -> bool {
         matches!(self, PgValue::Null)
     }
+
+    /// Fields of a composite value.
+    pub fn as_composite(&self) -> Option<&[PgValue]> {
+        match self {
+            PgValue::Composite(fields) => Some(fields),
+            _ => None,
+        }
+    }
 }
 
 // ─── ToSql / FromSql Traits ──────────────────────────────────
@@ -622,6 +742,12 @@ impl PgValue {
 /// Trait for converting Rust types to PostgreSQL parameter values.
 /// Replaces the older `ToParam` — provides the same functionality with
 /// a more standard name and the ability to specify the OID.
+///
+/// Parameters are sent with [`to_sql_raw`](Self::to_sql_raw), whose default
+/// encodes [`to_sql`](Self::to_sql). Types with no `PgValue` equivalent
+/// (extension types, custom binary formats) override `to_sql_raw` to write
+/// their own wire form and keep `to_sql` as a dynamic approximation, e.g.
+/// the text form, for callers that need a `PgValue`.
 pub trait ToSql {
     /// Convert this value to a PgValue for use as a query parameter.
     fn to_sql(&self) -> PgValue;
@@ -630,12 +756,60 @@ pub trait ToSql {
     fn type_oid(&self) -> u32 {
         0
     }
+
+    /// Wire encoding of the parameter: its format code and bytes, `None`
+    /// for NULL. Binary formats must match what the server expects for the
+    /// parameter's type, so pair them with [`type_oid`](Self::type_oid) or
+    /// a cast in the SQL.
+    fn to_sql_raw(&self) -> (FormatCode, Option<Vec<u8>>) {
+        let value = self.to_sql();
+        if value.prefers_binary() {
+            (FormatCode::Binary, value.to_binary_bytes())
+        } else {
+            (FormatCode::Text, value.to_text_bytes())
+        }
+    }
 }
 
 /// Trait for converting PostgreSQL values to Rust types.
+///
+/// Rows decode columns with [`from_sql_raw`](Self::from_sql_raw), which
+/// sees the column's type OID, format code and wire bytes. Implement it for
+/// types `PgValue` cannot represent, [`from_sql`](Self::from_sql) to convert
+/// from an already decoded value, or both. Each defaults to going through
+/// the other, so at least one must be implemented.
 pub trait FromSql: Sized {
-    /// Convert a PgValue to this Rust type.
-    fn from_sql(value: &PgValue) -> PgResult<Self>;
+    /// Convert a PgValue to this Rust type. The default passes the value's
+    /// text form to [`from_sql_raw`](Self::from_sql_raw) with a type OID of 0.
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        Self::from_sql_raw(0, FormatCode::Text, value.to_text_bytes().as_deref())
+    }
+
+    /// Decode a column of type `type_oid` received in `format`; `raw` is
+    /// `None` for NULL. The default decodes a [`PgValue`] and calls
+    /// [`from_sql`](Self::from_sql).
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        Self::from_sql(&decode_value(type_oid, format, raw)?)
+    }
+}
+
+/// Decode wire bytes into the dynamic [`PgValue`] representation.
+pub fn decode_value(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<PgValue> {
+    match (raw, format) {
+        (None, _) => Ok(PgValue::Null),
+        (Some(data), FormatCode::Text) => PgValue::from_text(type_oid, data),
+        (Some(data), FormatCode::Binary) => PgValue::from_binary(type_oid, data),
+    }
+}
+
+impl FromSql for PgValue {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        Ok(value.clone())
+    }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        decode_value(type_oid, format, raw)
+    }
 }
 
 // ─── ToSql Implementations ───────────────────────────────────
@@ -730,6 +904,15 @@ impl ToSql for Vec<u8> {
     }
 }
 
+impl ToSql for std::borrow::Cow<'_, [u8]> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Bytes(self.to_vec())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::BYTEA
+    }
+}
+
 impl<T: ToSql> ToSql for Option<T> {
     fn to_sql(&self) -> PgValue {
         match self {
@@ -737,6 +920,13 @@ impl<T: ToSql> ToSql for Option<T> {
             None => PgValue::Null,
         }
     }
+
+    fn to_sql_raw(&self) -> (FormatCode, Option<Vec<u8>>) {
+        match self {
+            Some(v) => v.to_sql_raw(),
+            None => (FormatCode::Text, None),
+        }
+    }
 }
 
 impl ToSql for PgValue {
@@ -864,6 +1054,62 @@ impl ToSql for &[bool] {
     }
 }
 
+impl ToSql for Vec<&str> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Array(self.iter().map(|v| v.to_sql()).collect())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TEXT_ARRAY
+    }
+}
+
+impl ToSql for &[&str] {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Array(self.iter().map(|v| v.to_sql()).collect())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TEXT_ARRAY
+    }
+}
+
+impl ToSql for &[String] {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Array(self.iter().map(|v| v.to_sql()).collect())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TEXT_ARRAY
+    }
+}
+
+// ─── UUID ToSql Implementations ───────────────────────────────
+
+impl ToSql for [u8; 16] {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Uuid(*self)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::UUID
+    }
+}
+
+impl ToSql for Vec<[u8; 16]> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Array(self.iter().map(|v| v.to_sql()).collect())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::UUID_ARRAY
+    }
+}
+
+impl ToSql for &[[u8; 16]] {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Array(self.iter().map(|v| v.to_sql()).collect())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::UUID_ARRAY
+    }
+}
+
 // ─── Network Type ToSql Implementations ───────────────────────
 
 impl ToSql for std::net::IpAddr {
@@ -916,12 +1162,92 @@ impl ToSql for (f64, f64) {
     }
 }
 
-// ─── Chrono ToSql Implementations ────────────────────────────
+// ─── Chrono ToSql / FromSql Implementations ──────────────────
 
 /// PostgreSQL epoch offset: 2000-01-01T00:00:00 UTC − Unix epoch = 946,684,800 seconds.
-#[cfg(feature = "chrono")]
+#[cfg(any(feature = "chrono", feature = "time"))]
 const PG_EPOCH_OFFSET_SECS: i64 = 946_684_800;
 
+/// Split microseconds since the PostgreSQL epoch into Unix seconds and
+/// nanoseconds, rejecting `±infinity`, which no Rust type can hold.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn pg_micros_to_unix(us: i64) -> PgResult<(i64, u32)> {
+    if us == i64::MAX || us == i64::MIN {
+        return Err(PgError::TypeConversion(
+            "Cannot convert an infinite timestamp".into(),
+        ));
+    }
+    let secs = us.div_euclid(1_000_000) + PG_EPOCH_OFFSET_SECS;
+    let nanos = (us.rem_euclid(1_000_000) * 1_000) as u32;
+    Ok((secs, nanos))
+}
+
+/// Microseconds since the PostgreSQL epoch of `value`, which must be a
+/// timestamp, a timestamptz or its text form.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn timestamp_micros(value: &PgValue, target: &str) -> PgResult<i64> {
+    match value {
+        PgValue::Timestamp(us) | PgValue::Timestamptz(us) => Ok(*us),
+        PgValue::Text(s) => parse_timestamp_text(s),
+        PgValue::Null => Err(PgError::TypeConversion(format!(
+            "Cannot convert NULL to {}",
+            target
+        ))),
+        _ => Err(PgError::TypeConversion(format!(
+            "Cannot convert to {}",
+            target
+        ))),
+    }
+}
+
+/// Days since the PostgreSQL epoch of a date value or its text form.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn date_days(value: &PgValue, target: &str) -> PgResult<i32> {
+    let days = match value {
+        PgValue::Date(days) => *days,
+        PgValue::Text(s) => parse_date_text(s)?,
+        PgValue::Null => {
+            return Err(PgError::TypeConversion(format!(
+                "Cannot convert NULL to {}",
+                target
+            )));
+        }
+        _ => {
+            return Err(PgError::TypeConversion(format!(
+                "Cannot convert to {}",
+                target
+            )));
+        }
+    };
+    if days == i32::MAX || days == i32::MIN {
+        return Err(PgError::TypeConversion(
+            "Cannot convert an infinite date".into(),
+        ));
+    }
+    Ok(days)
+}
+
+/// Microseconds since midnight of a time value or its text form.
+#[cfg(any(feature = "chrono", feature = "time"))]
+fn time_micros(value: &PgValue, target: &str) -> PgResult<i64> {
+    match value {
+        PgValue::Time(us) => Ok(*us),
+        PgValue::Text(s) => parse_time_text(s),
+        PgValue::Null => Err(PgError::TypeConversion(format!(
+            "Cannot convert NULL to {}",
+            target
+        ))),
+        _ => Err(PgError::TypeConversion(format!(
+            "Cannot convert to {}",
+            target
+        ))),
+    }
+}
+
+/// `chrono::NaiveDate::num_days_from_ce()` of 2000-01-01.
+#[cfg(feature = "chrono")]
+const PG_EPOCH_DAYS_FROM_CE: i32 = 730_120;
+
 #[cfg(feature = "chrono")]
 impl ToSql for chrono::NaiveDateTime {
     fn to_sql(&self) -> PgValue {
@@ -935,7 +1261,190 @@ impl ToSql for chrono::NaiveDateTime {
     }
 }
 
-// ─── rust_decimal ToSql Implementations ──────────────────────
+#[cfg(feature = "chrono")]
+impl FromSql for chrono::NaiveDateTime {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let (secs, nanos) = pg_micros_to_unix(timestamp_micros(value, "NaiveDateTime")?)?;
+        chrono::DateTime::from_timestamp(secs, nanos)
+            .map(|dt| dt.naive_utc())
+            .ok_or_else(|| PgError::TypeConversion("Timestamp out of range".into()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl<Tz: chrono::TimeZone> ToSql for chrono::DateTime<Tz> {
+    fn to_sql(&self) -> PgValue {
+        match self.naive_utc().to_sql() {
+            PgValue::Timestamp(us) => PgValue::Timestamptz(us),
+            other => other,
+        }
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TIMESTAMPTZ
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromSql for chrono::DateTime<chrono::Utc> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        chrono::NaiveDateTime::from_sql(value).map(|naive| naive.and_utc())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromSql for chrono::DateTime<chrono::FixedOffset> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        chrono::DateTime::<chrono::Utc>::from_sql(value).map(|dt| dt.fixed_offset())
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToSql for chrono::NaiveDate {
+    fn to_sql(&self) -> PgValue {
+        use chrono::Datelike;
+        PgValue::Date(self.num_days_from_ce() - PG_EPOCH_DAYS_FROM_CE)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::DATE
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromSql for chrono::NaiveDate {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let days = date_days(value, "NaiveDate")?;
+        days.checked_add(PG_EPOCH_DAYS_FROM_CE)
+            .and_then(chrono::NaiveDate::from_num_days_from_ce_opt)
+            .ok_or_else(|| PgError::TypeConversion("Date out of range".into()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl ToSql for chrono::NaiveTime {
+    fn to_sql(&self) -> PgValue {
+        use chrono::Timelike;
+        let secs = self.num_seconds_from_midnight() as i64;
+        // Leap seconds (nanosecond >= 1e9) clamp to the end of the second.
+        let micros = (self.nanosecond() / 1_000).min(999_999) as i64;
+        PgValue::Time(secs * 1_000_000 + micros)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TIME
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl FromSql for chrono::NaiveTime {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let us = time_micros(value, "NaiveTime")?;
+        let secs = u32::try_from(us.div_euclid(1_000_000)).ok();
+        let nanos = (us.rem_euclid(1_000_000) * 1_000) as u32;
+        // PostgreSQL allows 24:00:00, which chrono cannot represent.
+        secs.and_then(|secs| chrono::NaiveTime::from_num_seconds_from_midnight_opt(secs, nanos))
+            .ok_or_else(|| PgError::TypeConversion(format!("Time out of range: {}us", us)))
+    }
+}
+
+// ─── time Crate ToSql / FromSql Implementations ──────────────
+
+/// Julian day number of 2000-01-01.
+#[cfg(feature = "time")]
+const PG_EPOCH_JULIAN_DAY: i32 = 2_451_545;
+
+#[cfg(feature = "time")]
+impl ToSql for time::PrimitiveDateTime {
+    fn to_sql(&self) -> PgValue {
+        match self.assume_utc().to_sql() {
+            PgValue::Timestamptz(us) => PgValue::Timestamp(us),
+            other => other,
+        }
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TIMESTAMP
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromSql for time::PrimitiveDateTime {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        time::OffsetDateTime::from_sql(value)
+            .map(|dt| time::PrimitiveDateTime::new(dt.date(), dt.time()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToSql for time::OffsetDateTime {
+    fn to_sql(&self) -> PgValue {
+        let unix_micros = self.unix_timestamp_nanos().div_euclid(1_000) as i64;
+        PgValue::Timestamptz(unix_micros - PG_EPOCH_OFFSET_SECS * 1_000_000)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TIMESTAMPTZ
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromSql for time::OffsetDateTime {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let (secs, nanos) = pg_micros_to_unix(timestamp_micros(value, "OffsetDateTime")?)?;
+        time::OffsetDateTime::from_unix_timestamp(secs)
+            .map(|dt| dt + time::Duration::nanoseconds(nanos as i64))
+            .map_err(|e| PgError::TypeConversion(format!("Timestamp out of range: {}", e)))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToSql for time::Date {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Date(self.to_julian_day() - PG_EPOCH_JULIAN_DAY)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::DATE
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromSql for time::Date {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let days = date_days(value, "Date")?;
+        days.checked_add(PG_EPOCH_JULIAN_DAY)
+            .and_then(|jd| time::Date::from_julian_day(jd).ok())
+            .ok_or_else(|| PgError::TypeConversion("Date out of range".into()))
+    }
+}
+
+#[cfg(feature = "time")]
+impl ToSql for time::Time {
+    fn to_sql(&self) -> PgValue {
+        let (h, m, s, micros) = self.as_hms_micro();
+        let secs = h as i64 * 3600 + m as i64 * 60 + s as i64;
+        PgValue::Time(secs * 1_000_000 + micros as i64)
+    }
+    fn type_oid(&self) -> u32 {
+        oid::TIME
+    }
+}
+
+#[cfg(feature = "time")]
+impl FromSql for time::Time {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let us = time_micros(value, "Time")?;
+        let out_of_range = || PgError::TypeConversion(format!("Time out of range: {}us", us));
+        if !(0..86_400_000_000).contains(&us) {
+            return Err(out_of_range());
+        }
+        let secs = us / 1_000_000;
+        time::Time::from_hms_micro(
+            (secs / 3600) as u8,
+            (secs % 3600 / 60) as u8,
+            (secs % 60) as u8,
+            (us % 1_000_000) as u32,
+        )
+        .map_err(|_| out_of_range())
+    }
+}
+
+// ─── rust_decimal ToSql / FromSql Implementations ────────────
 
 #[cfg(feature = "decimal")]
 impl ToSql for rust_decimal::Decimal {
@@ -947,8 +1456,113 @@ impl ToSql for rust_decimal::Decimal {
     }
 }
 
+#[cfg(feature = "decimal")]
+impl FromSql for rust_decimal::Decimal {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        match value {
+            PgValue::Numeric(s) | PgValue::Text(s) => s.parse().map_err(|_| {
+                PgError::TypeConversion(format!("NUMERIC '{}' does not fit a Decimal", s))
+            }),
+            PgValue::Int2(v) => Ok((*v).into()),
+            PgValue::Int4(v) => Ok((*v).into()),
+            PgValue::Int8(v) => Ok((*v).into()),
+            _ => Err(PgError::TypeConversion("Cannot convert to Decimal".into())),
+        }
+    }
+}
+
+// ─── serde JSON ToSql / FromSql Implementations ──────────────
+
+/// A JSON value bound as `jsonb` and decoded from `json`/`jsonb` columns
+/// with serde (`json` feature).
+///
+/// ```rust,ignore
+/// conn.execute("INSERT INTO events (payload) VALUES ($1)", &[&Json(&event)])?;
+/// let Json(event): Json<Event> = row.get_typed(0)?;
+/// ```
+#[cfg(feature = "json")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Json<T>(pub T);
+
+#[cfg(feature = "json")]
+impl PgValue {
+    /// Serialize `value` as a `jsonb` value.
+    pub fn jsonb<T: serde::Serialize + ?Sized>(value: &T) -> PgResult<Self> {
+        serde_json::to_vec(value)
+            .map(PgValue::Jsonb)
+            .map_err(|e| PgError::TypeConversion(format!("Cannot encode JSON: {}", e)))
+    }
+
+    /// Deserialize a `json`, `jsonb` or text value.
+    pub fn to_json<T: serde::de::DeserializeOwned>(&self) -> PgResult<T> {
+        let bytes = match self {
+            PgValue::Json(s) | PgValue::Text(s) => s.as_bytes(),
+            PgValue::Jsonb(b) | PgValue::Bytes(b) => b.as_slice(),
+            PgValue::Null => {
+                return Err(PgError::TypeConversion(
+                    "Cannot convert NULL to JSON".into(),
+                ));
+            }
+            _ => return Err(PgError::TypeConversion("Cannot convert to JSON".into())),
+        };
+        serde_json::from_slice(bytes)
+            .map_err(|e| PgError::TypeConversion(format!("Invalid JSON: {}", e)))
+    }
+}
+
+/// Values that fail to serialize (e.g. maps with non-string keys) are
+/// bound as JSON `null`; use [`PgValue::jsonb`] to see the error.
+#[cfg(feature = "json")]
+impl<T: serde::Serialize> ToSql for Json<T> {
+    fn to_sql(&self) -> PgValue {
+        PgValue::jsonb(&self.0).unwrap_or_else(|_| PgValue::Jsonb(b"null".to_vec()))
+    }
+    fn type_oid(&self) -> u32 {
+        oid::JSONB
+    }
+}
+
+#[cfg(feature = "json")]
+impl<T: serde::de::DeserializeOwned> FromSql for Json<T> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        value.to_json().map(Json)
+    }
+}
+
+#[cfg(feature = "json")]
+impl ToSql for serde_json::Value {
+    fn to_sql(&self) -> PgValue {
+        PgValue::Jsonb(self.to_string().into_bytes())
+    }
+    fn type_oid(&self) -> u32 {
+        oid::JSONB
+    }
+}
+
+#[cfg(feature = "json")]
+impl FromSql for serde_json::Value {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        value.to_json()
+    }
+}
+
 // ─── FromSql Implementations ─────────────────────────────────
 
+/// Bytes of a binary-format column of exactly type `want`, for the scalar
+/// fast paths that skip building a `PgValue`.
+#[inline]
+fn binary_scalar<const N: usize>(
+    type_oid: u32,
+    format: FormatCode,
+    raw: Option<&[u8]>,
+    want: u32,
+) -> Option<[u8; N]> {
+    if type_oid != want || format != FormatCode::Binary {
+        return None;
+    }
+    raw?.try_into().ok()
+}
+
 impl FromSql for i16 {
     fn from_sql(value: &PgValue) -> PgResult<Self> {
         match value {
@@ -959,6 +1573,13 @@ impl FromSql for i16 {
             _ => Err(PgError::TypeConversion("Cannot convert to i16".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<2>(type_oid, format, raw, oid::INT2) {
+            Some(b) => Ok(i16::from_be_bytes(b)),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for i32 {
@@ -972,6 +1593,13 @@ impl FromSql for i32 {
             _ => Err(PgError::TypeConversion("Cannot convert to i32".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<4>(type_oid, format, raw, oid::INT4) {
+            Some(b) => Ok(i32::from_be_bytes(b)),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for i64 {
@@ -986,6 +1614,13 @@ impl FromSql for i64 {
             _ => Err(PgError::TypeConversion("Cannot convert to i64".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<8>(type_oid, format, raw, oid::INT8) {
+            Some(b) => Ok(i64::from_be_bytes(b)),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for f32 {
@@ -998,6 +1633,13 @@ impl FromSql for f32 {
             _ => Err(PgError::TypeConversion("Cannot convert to f32".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<4>(type_oid, format, raw, oid::FLOAT4) {
+            Some(b) => Ok(f32::from_be_bytes(b)),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for f64 {
@@ -1007,12 +1649,19 @@ impl FromSql for f64 {
             PgValue::Float4(v) => Ok(*v as f64),
             PgValue::Int4(v) => Ok(*v as f64),
             PgValue::Int8(v) => Ok(*v as f64),
-            PgValue::Text(s) => s
+            PgValue::Text(s) | PgValue::Numeric(s) => s
                 .parse()
                 .map_err(|_| PgError::TypeConversion("Not an f64".into())),
             _ => Err(PgError::TypeConversion("Cannot convert to f64".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<8>(type_oid, format, raw, oid::FLOAT8) {
+            Some(b) => Ok(f64::from_be_bytes(b)),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for bool {
@@ -1023,6 +1672,13 @@ impl FromSql for bool {
             _ => Err(PgError::TypeConversion("Cannot convert to bool".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match binary_scalar::<1>(type_oid, format, raw, oid::BOOL) {
+            Some(b) => Ok(b[0] != 0),
+            None => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl FromSql for String {
@@ -1030,6 +1686,8 @@ impl FromSql for String {
         match value {
             PgValue::Text(s) => Ok(s.clone()),
             PgValue::Json(s) => Ok(s.clone()),
+            PgValue::Jsonb(b) => String::from_utf8(b.clone())
+                .map_err(|_| PgError::TypeConversion("JSONB is not valid UTF-8".into())),
             PgValue::Inet(s) => Ok(s.clone()),
             PgValue::Numeric(s) => Ok(s.clone()),
             PgValue::Int2(v) => Ok(v.to_string()),
@@ -1045,6 +1703,15 @@ impl FromSql for String {
             _ => Err(PgError::TypeConversion("Cannot convert to String".into())),
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match (type_oid, raw) {
+            // Text and varchar are UTF-8 in both formats.
+            (oid::TEXT | oid::VARCHAR, Some(data)) => String::from_utf8(data.to_vec())
+                .map_err(|_| PgError::TypeConversion("Invalid UTF-8".into())),
+            _ => Self::from_sql(&decode_value(type_oid, format, raw)?),
+        }
+    }
 }
 
 impl<T: FromSql> FromSql for Option<T> {
@@ -1055,6 +1722,13 @@ impl<T: FromSql> FromSql for Option<T> {
             T::from_sql(value).map(Some)
         }
     }
+
+    fn from_sql_raw(type_oid: u32, format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match raw {
+            None => Ok(None),
+            Some(_) => T::from_sql_raw(type_oid, format, raw).map(Some),
+        }
+    }
 }
 
 impl FromSql for Vec<u8> {
@@ -1171,6 +1845,20 @@ impl FromSql for Vec<String> {
     }
 }
 
+impl FromSql for Vec<[u8; 16]> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        match value {
+            PgValue::Array(arr) => arr.iter().map(<[u8; 16]>::from_sql).collect(),
+            PgValue::Null => Err(PgError::TypeConversion(
+                "Cannot convert NULL to Vec<[u8; 16]>".into(),
+            )),
+            _ => Err(PgError::TypeConversion(
+                "Cannot convert to Vec<[u8; 16]>".into(),
+            )),
+        }
+    }
+}
+
 // ─── Network Type FromSql Implementations ─────────────────────
 
 impl FromSql for std::net::IpAddr {
@@ -1254,6 +1942,211 @@ impl FromSql for (f64, f64) {
     }
 }
 
+// ─── Range ToSql / FromSql ────────────────────────────────────
+
+/// A range value (`int4range`, `tsrange`, ...): each end unbounded or an
+/// inclusive/exclusive bound, or the empty range.
+///
+/// ```ignore
+/// conn.execute(
+///     "INSERT INTO bookings (room, during) VALUES ($1, $2::tsrange)",
+///     &[&room, &PgRange::from(start..end)],
+/// )?;
+/// let during: PgRange<NaiveDateTime> = row.get_typed(0)?;
+/// ```
+///
+/// Bounds are exchanged in text form, so `T` only needs `ToSql`/`FromSql`
+/// with a text representation PostgreSQL accepts for the element type.
+/// Discrete ranges come back canonicalised (`[1,3]` reads as `[1,4)`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgRange<T> {
+    Empty,
+    Bounds(Bound<T>, Bound<T>),
+}
+
+impl<T> PgRange<T> {
+    pub fn new(lower: Bound<T>, upper: Bound<T>) -> Self {
+        PgRange::Bounds(lower, upper)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        matches!(self, PgRange::Empty)
+    }
+
+    pub fn lower(&self) -> Bound<&T> {
+        match self {
+            PgRange::Empty => Bound::Unbounded,
+            PgRange::Bounds(lower, _) => lower.as_ref(),
+        }
+    }
+
+    pub fn upper(&self) -> Bound<&T> {
+        match self {
+            PgRange::Empty => Bound::Unbounded,
+            PgRange::Bounds(_, upper) => upper.as_ref(),
+        }
+    }
+}
+
+impl<T: PartialOrd> PgRange<T> {
+    /// Whether `value` lies within the range, honouring bound inclusivity.
+    pub fn contains(&self, value: &T) -> bool {
+        match self {
+            PgRange::Empty => false,
+            PgRange::Bounds(lower, upper) => (lower.as_ref(), upper.as_ref()).contains(value),
+        }
+    }
+}
+
+impl<T> From<std::ops::Range<T>> for PgRange<T> {
+    fn from(r: std::ops::Range<T>) -> Self {
+        PgRange::Bounds(Bound::Included(r.start), Bound::Excluded(r.end))
+    }
+}
+
+impl<T> From<std::ops::RangeInclusive<T>> for PgRange<T> {
+    fn from(r: std::ops::RangeInclusive<T>) -> Self {
+        let (start, end) = r.into_inner();
+        PgRange::Bounds(Bound::Included(start), Bound::Included(end))
+    }
+}
+
+impl<T> From<std::ops::RangeFrom<T>> for PgRange<T> {
+    fn from(r: std::ops::RangeFrom<T>) -> Self {
+        PgRange::Bounds(Bound::Included(r.start), Bound::Unbounded)
+    }
+}
+
+impl<T> From<std::ops::RangeTo<T>> for PgRange<T> {
+    fn from(r: std::ops::RangeTo<T>) -> Self {
+        PgRange::Bounds(Bound::Unbounded, Bound::Excluded(r.end))
+    }
+}
+
+impl<T: ToSql> ToSql for PgRange<T> {
+    fn to_sql(&self) -> PgValue {
+        let (lower, upper) = match self {
+            PgRange::Empty => return PgValue::Range("empty".to_string()),
+            PgRange::Bounds(lower, upper) => (lower, upper),
+        };
+        let bound_text = |bound: &T| {
+            let text = bound.to_sql().to_text_bytes().unwrap_or_default();
+            quote_range_bound(&String::from_utf8_lossy(&text))
+        };
+        let mut out = String::new();
+        match lower {
+            Bound::Included(v) => {
+                out.push('[');
+                out.push_str(&bound_text(v));
+            }
+            Bound::Excluded(v) => {
+                out.push('(');
+                out.push_str(&bound_text(v));
+            }
+            Bound::Unbounded => out.push('('),
+        }
+        out.push(',');
+        match upper {
+            Bound::Included(v) => {
+                out.push_str(&bound_text(v));
+                out.push(']');
+            }
+            Bound::Excluded(v) => {
+                out.push_str(&bound_text(v));
+                out.push(')');
+            }
+            Bound::Unbounded => out.push(')'),
+        }
+        PgValue::Range(out)
+    }
+    fn type_oid(&self) -> u32 {
+        // The server infers the concrete range type from the statement.
+        0
+    }
+}
+
+impl<T: FromSql> FromSql for PgRange<T> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        let text = match value {
+            PgValue::Range(s) | PgValue::Text(s) => s,
+            _ => return Err(PgError::TypeConversion("Cannot convert to range".into())),
+        };
+        let Some((lower, upper)) = parse_range_text(text)? else {
+            return Ok(PgRange::Empty);
+        };
+        let bound = |b: Option<(String, bool)>| -> PgResult<Bound<T>> {
+            Ok(match b {
+                None => Bound::Unbounded,
+                Some((v, true)) => Bound::Included(T::from_sql(&PgValue::Text(v))?),
+                Some((v, false)) => Bound::Excluded(T::from_sql(&PgValue::Text(v))?),
+            })
+        };
+        Ok(PgRange::Bounds(bound(lower)?, bound(upper)?))
+    }
+}
+
+// ─── Composite FromSql ────────────────────────────────────────
+
+/// A composite (row) value decoded field by field into a tuple.
+///
+/// ```ignore
+/// // CREATE FUNCTION stats() RETURNS TABLE ... / RETURNS my_type
+/// let Record((count, label)): Record<(i64, Option<String>)> = row.get_typed(0)?;
+/// ```
+///
+/// Fields of anonymous records read through `query_simple` arrive as text
+/// and are parsed by each element's `FromSql`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<T>(pub T);
+
+macro_rules! record_from_sql {
+    ($len:literal: $($t:ident $i:tt),+) => {
+        impl<$($t: FromSql),+> FromSql for Record<($($t,)+)> {
+            fn from_sql(value: &PgValue) -> PgResult<Self> {
+                match value {
+                    PgValue::Composite(fields) if fields.len() == $len => {
+                        Ok(Record(($($t::from_sql(&fields[$i])?,)+)))
+                    }
+                    PgValue::Composite(fields) => Err(PgError::TypeConversion(format!(
+                        "Expected a record of {} fields, got {}",
+                        $len,
+                        fields.len()
+                    ))),
+                    _ => Err(PgError::TypeConversion("Cannot convert to record".into())),
+                }
+            }
+        }
+    };
+}
+
+record_from_sql!(1: A 0);
+record_from_sql!(2: A 0, B 1);
+record_from_sql!(3: A 0, B 1, C 2);
+record_from_sql!(4: A 0, B 1, C 2, D 3);
+record_from_sql!(5: A 0, B 1, C 2, D 3, E 4);
+record_from_sql!(6: A 0, B 1, C 2, D 3, E 4, F 5);
+record_from_sql!(7: A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+record_from_sql!(8: A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+impl FromSql for Vec<PgValue> {
+    fn from_sql(value: &PgValue) -> PgResult<Self> {
+        match value {
+            PgValue::Composite(fields) | PgValue::Array(fields) => Ok(fields.clone()),
+            _ => Err(PgError::TypeConversion(
+                "Cannot convert to Vec<PgValue>".into(),
+            )),
+        }
+    }
+}
+
+/// One attribute of a named composite type, from
+/// `PgConnection::composite_fields`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompositeField {
+    pub name: String,
+    pub type_oid: u32,
+}
+
 // ─── Backward Compatibility ──────────────────────────────────
 
 /// Convenience trait for converting Rust types to PgValue parameters.
@@ -1348,12 +2241,6 @@ fn parse_point_text(s: &str) -> PgResult<(f64, f64)> {
 /// (exponent in base-10000), a sign flag, and a display scale.
 fn format_numeric_binary(weight: i16, sign: u16, dscale: usize, digits: &[u16]) -> String {
     // Special values
-    const _NUMERIC_POS: u16 = 0x0000;
-    const NUMERIC_NEG: u16 = 0x4000;
-    const NUMERIC_NAN: u16 = 0xC000;
-    const NUMERIC_PINF: u16 = 0xD000;
-    const NUMERIC_NINF: u16 = 0xF000;
-
     match sign {
         NUMERIC_NAN => return "NaN".to_string(),
         NUMERIC_PINF => return "Infinity".to_string(),
@@ -1421,6 +2308,126 @@ fn format_numeric_binary(weight: i16, sign: u16, dscale: usize, digits: &[u16])
     result
 }
 
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+const NUMERIC_PINF: u16 = 0xD000;
+const NUMERIC_NINF: u16 = 0xF000;
+
+/// A numeric literal split into PostgreSQL's binary NUMERIC fields.
+struct NumericParts {
+    weight: i16,
+    sign: u16,
+    dscale: u16,
+    digits: Vec<u16>,
+}
+
+/// Parse a decimal literal (`-12.50`, `1e-3`, `NaN`, `Infinity`) into
+/// base-10000 digit groups. Returns `None` for anything PostgreSQL's
+/// binary NUMERIC cannot carry.
+fn parse_numeric(s: &str) -> Option<NumericParts> {
+    let s = s.trim();
+    let special = |sign| {
+        Some(NumericParts {
+            weight: 0,
+            sign,
+            dscale: 0,
+            digits: Vec::new(),
+        })
+    };
+    match s.to_ascii_lowercase().as_str() {
+        "nan" => return special(NUMERIC_NAN),
+        "infinity" | "+infinity" | "inf" | "+inf" => return special(NUMERIC_PINF),
+        "-infinity" | "-inf" => return special(NUMERIC_NINF),
+        _ => {}
+    }
+
+    let (negative, rest) = match s.as_bytes().first()? {
+        b'-' => (true, &s[1..]),
+        b'+' => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (mantissa, exp) = match rest.find(['e', 'E']) {
+        Some(i) => (&rest[..i], rest[i + 1..].parse::<i32>().ok()?),
+        None => (rest, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    if int_part.is_empty() && frac_part.is_empty()
+        || !int_part
+            .bytes()
+            .chain(frac_part.bytes())
+            .all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+
+    let mut digits: Vec<u8> = int_part
+        .bytes()
+        .chain(frac_part.bytes())
+        .map(|b| b - b'0')
+        .collect();
+    // Decimal digits before the point.
+    let mut point = int_part.len() as i64 + exp as i64;
+    let dscale = (digits.len() as i64 - point).max(0);
+    if dscale > 0x3FFF {
+        return None;
+    }
+
+    let leading = digits.iter().take_while(|&&d| d == 0).count();
+    digits.drain(..leading);
+    point -= leading as i64;
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    if digits.is_empty() {
+        return Some(NumericParts {
+            weight: 0,
+            sign: 0,
+            dscale: dscale as u16,
+            digits: Vec::new(),
+        });
+    }
+
+    // Align to base-10000 groups around the decimal point.
+    let left_pad = (4 - point.rem_euclid(4)) % 4;
+    point += left_pad;
+    let mut padded = vec![0u8; left_pad as usize];
+    padded.extend_from_slice(&digits);
+    padded.resize(padded.len().div_ceil(4) * 4, 0);
+    let mut groups: Vec<u16> = padded
+        .chunks(4)
+        .map(|c| c.iter().fold(0u16, |acc, &d| acc * 10 + d as u16))
+        .collect();
+    while groups.last() == Some(&0) {
+        groups.pop();
+    }
+    let weight = point / 4 - 1;
+    if groups.len() > u16::MAX as usize || weight < i16::MIN as i64 || weight > i16::MAX as i64 {
+        return None;
+    }
+
+    Some(NumericParts {
+        weight: weight as i16,
+        sign: if negative { NUMERIC_NEG } else { 0 },
+        dscale: dscale as u16,
+        digits: groups,
+    })
+}
+
+/// Encode a decimal literal as a binary NUMERIC parameter:
+/// `ndigits(u16) + weight(i16) + sign(u16) + dscale(u16) + digits(u16 * ndigits)`.
+pub fn encode_numeric_binary(s: &str) -> Option<Vec<u8>> {
+    let parts = parse_numeric(s)?;
+    let mut buf = Vec::with_capacity(8 + parts.digits.len() * 2);
+    buf.extend_from_slice(&(parts.digits.len() as u16).to_be_bytes());
+    buf.extend_from_slice(&parts.weight.to_be_bytes());
+    buf.extend_from_slice(&parts.sign.to_be_bytes());
+    buf.extend_from_slice(&parts.dscale.to_be_bytes());
+    for d in &parts.digits {
+        buf.extend_from_slice(&d.to_be_bytes());
+    }
+    Some(buf)
+}
+
 // ─── Binary Array Parsing ─────────────────────────────────────
 
 /// Parse a PostgreSQL binary array value.
@@ -1441,52 +2448,428 @@ fn parse_binary_array(data: &[u8]) -> PgResult<PgValue> {
     if ndim == 0 {
         return Ok(PgValue::Array(Vec::new()));
     }
-    if ndim != 1 {
-        // We only support 1-dimensional arrays
-        return Err(PgError::TypeConversion(format!(
-            "Unsupported array dimensions: {}",
-            ndim
-        )));
+    if ndim != 1 {
+        // We only support 1-dimensional arrays
+        return Err(PgError::TypeConversion(format!(
+            "Unsupported array dimensions: {}",
+            ndim
+        )));
+    }
+
+    let mut pos = 12;
+    // Dimension length and lower bound
+    if data.len() < pos + 8 {
+        return Err(PgError::TypeConversion(
+            "Binary array dimension truncated".into(),
+        ));
+    }
+    let num_elements =
+        i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+    pos += 4;
+    let _lower_bound = i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+    pos += 4;
+
+    let mut values = Vec::with_capacity(num_elements);
+    for _ in 0..num_elements {
+        if data.len() < pos + 4 {
+            return Err(PgError::TypeConversion(
+                "Binary array element truncated".into(),
+            ));
+        }
+        let elem_len = i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+        pos += 4;
+
+        if elem_len < 0 {
+            values.push(PgValue::Null);
+        } else {
+            let elem_len = elem_len as usize;
+            if data.len() < pos + elem_len {
+                return Err(PgError::TypeConversion(
+                    "Binary array element data truncated".into(),
+                ));
+            }
+            let elem_data = &data[pos..pos + elem_len];
+            values.push(PgValue::from_binary(element_oid, elem_data)?);
+            pos += elem_len;
+        }
+    }
+
+    Ok(PgValue::Array(values))
+}
+
+/// Element OID for sending `values` as a binary array, if every non-NULL
+/// element is the same fixed-width type. Text-like elements stay in text
+/// format so the server can coerce them to `varchar[]`, enum arrays etc.
+fn binary_array_element_oid(values: &[PgValue]) -> Option<u32> {
+    let mut elem_oid = None;
+    for v in values {
+        let oid = match v {
+            PgValue::Null => continue,
+            PgValue::Bool(_) => oid::BOOL,
+            PgValue::Int2(_) => oid::INT2,
+            PgValue::Int4(_) => oid::INT4,
+            PgValue::Int8(_) => oid::INT8,
+            PgValue::Float4(_) => oid::FLOAT4,
+            PgValue::Float8(_) => oid::FLOAT8,
+            PgValue::Uuid(_) => oid::UUID,
+            _ => return None,
+        };
+        match elem_oid {
+            None => elem_oid = Some(oid),
+            Some(prev) if prev != oid => return None,
+            Some(_) => {}
+        }
+    }
+    elem_oid
+}
+
+/// Encode a one-dimensional binary array (the inverse of `parse_binary_array`).
+fn encode_binary_array(elem_oid: u32, values: &[PgValue]) -> Vec<u8> {
+    let has_null = values.iter().any(|v| matches!(v, PgValue::Null));
+    let mut buf = Vec::with_capacity(20 + values.len() * 12);
+    buf.extend_from_slice(&1_i32.to_be_bytes()); // ndim
+    buf.extend_from_slice(&(has_null as i32).to_be_bytes());
+    buf.extend_from_slice(&elem_oid.to_be_bytes());
+    buf.extend_from_slice(&(values.len() as i32).to_be_bytes());
+    buf.extend_from_slice(&1_i32.to_be_bytes()); // lower bound
+    for v in values {
+        match v.to_binary_bytes() {
+            Some(bytes) => {
+                buf.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+                buf.extend_from_slice(&bytes);
+            }
+            None => buf.extend_from_slice(&(-1_i32).to_be_bytes()),
+        }
+    }
+    buf
+}
+
+// ─── Text Array Parsing ───────────────────────────────────────
+
+/// Element type of a one-dimensional array type OID.
+fn array_element_oid(array_oid: u32) -> Option<u32> {
+    Some(match array_oid {
+        oid::BOOL_ARRAY => oid::BOOL,
+        oid::INT2_ARRAY => oid::INT2,
+        oid::INT4_ARRAY => oid::INT4,
+        oid::INT8_ARRAY => oid::INT8,
+        oid::TEXT_ARRAY => oid::TEXT,
+        oid::FLOAT4_ARRAY => oid::FLOAT4,
+        oid::FLOAT8_ARRAY => oid::FLOAT8,
+        oid::VARCHAR_ARRAY => oid::VARCHAR,
+        oid::UUID_ARRAY => oid::UUID,
+        oid::JSONB_ARRAY => oid::JSONB,
+        oid::JSON_ARRAY => oid::JSON,
+        _ => return None,
+    })
+}
+
+/// Parse a text-format array literal such as `{1,NULL,"a b"}`.
+///
+/// Only one-dimensional arrays are supported, as with binary arrays. An
+/// explicit bounds prefix (`[0:1]={...}`) is accepted and ignored.
+/// Element type of a built-in range type.
+fn range_element_oid(range_oid: u32) -> Option<u32> {
+    Some(match range_oid {
+        oid::INT4RANGE => oid::INT4,
+        oid::INT8RANGE => oid::INT8,
+        oid::NUMRANGE => oid::NUMERIC,
+        oid::TSRANGE => oid::TIMESTAMP,
+        oid::TSTZRANGE => oid::TIMESTAMPTZ,
+        oid::DATERANGE => oid::DATE,
+        _ => return None,
+    })
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+/// Decode a binary range (flags byte, then each finite bound as a
+/// length-prefixed element) into its text form, e.g. `[1,10)`.
+fn parse_binary_range(range_oid: u32, data: &[u8]) -> PgResult<PgValue> {
+    let err = || PgError::TypeConversion("Malformed binary range".to_string());
+    let elem_oid = range_element_oid(range_oid).ok_or_else(err)?;
+    let (&flags, mut rest) = data.split_first().ok_or_else(err)?;
+    if flags & RANGE_EMPTY != 0 {
+        return Ok(PgValue::Range("empty".to_string()));
+    }
+    let mut bound = |present: bool| -> PgResult<String> {
+        if !present {
+            return Ok(String::new());
+        }
+        let len = rest.get(..4).ok_or_else(err)?;
+        let len = i32::from_be_bytes([len[0], len[1], len[2], len[3]]);
+        let value = rest
+            .get(4..4 + usize::try_from(len).map_err(|_| err())?)
+            .ok_or_else(err)?;
+        rest = &rest[4 + value.len()..];
+        let text = PgValue::from_binary(elem_oid, value)?
+            .to_text_bytes()
+            .ok_or_else(err)?;
+        Ok(quote_range_bound(&String::from_utf8_lossy(&text)))
+    };
+    let lower = bound(flags & RANGE_LB_INF == 0)?;
+    let upper = bound(flags & RANGE_UB_INF == 0)?;
+    Ok(PgValue::Range(format!(
+        "{}{},{}{}",
+        if flags & RANGE_LB_INC != 0 { '[' } else { '(' },
+        lower,
+        upper,
+        if flags & RANGE_UB_INC != 0 { ']' } else { ')' },
+    )))
+}
+
+/// Quote a range bound the way the server's range output does.
+fn quote_range_bound(s: &str) -> String {
+    let needs_quotes = s.is_empty()
+        || s.chars()
+            .any(|c| matches!(c, '(' | ')' | '[' | ']' | ',' | '"' | '\\') || c.is_whitespace());
+    if !needs_quotes {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        if c == '"' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out.push('"');
+    out
+}
+
+/// A parsed bound: its text and whether it is inclusive. `None` is unbounded.
+type RangeBoundText = Option<(String, bool)>;
+
+/// Parse range text into its bounds; `None` for `empty`.
+fn parse_range_text(s: &str) -> PgResult<Option<(RangeBoundText, RangeBoundText)>> {
+    let s = s.trim();
+    if s.eq_ignore_ascii_case("empty") {
+        return Ok(None);
+    }
+    let err = || PgError::TypeConversion(format!("Invalid range literal: {}", s));
+    let lower_inc = match s.chars().next() {
+        Some('[') => true,
+        Some('(') => false,
+        _ => return Err(err()),
+    };
+    let upper_inc = match s.chars().last() {
+        Some(']') => true,
+        Some(')') => false,
+        _ => return Err(err()),
+    };
+    let inner = s.get(1..s.len() - 1).ok_or_else(err)?;
+
+    let mut bounds = Vec::with_capacity(2);
+    let mut text = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                text.push('"');
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' => text.extend(chars.next()),
+            ',' if !in_quotes => {
+                bounds.push((std::mem::take(&mut text), quoted));
+                quoted = false;
+            }
+            _ => text.push(c),
+        }
+    }
+    bounds.push((text, quoted));
+    let [lower, upper]: [(String, bool); 2] = bounds.try_into().map_err(|_| err())?;
+    let finite = |(text, quoted): (String, bool), inclusive| {
+        (quoted || !text.is_empty()).then_some((text, inclusive))
+    };
+    Ok(Some((finite(lower, lower_inc), finite(upper, upper_inc))))
+}
+
+/// Decode a binary record: field count, then per field its type OID, length
+/// (-1 for NULL) and binary value.
+fn parse_binary_record(data: &[u8]) -> PgResult<PgValue> {
+    let err = || PgError::TypeConversion("Malformed binary record".to_string());
+    let read_i32 = |pos: usize| -> PgResult<i32> {
+        let bytes = data.get(pos..pos + 4).ok_or_else(err)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let count = read_i32(0)?;
+    // A table has at most 1600 columns.
+    if !(0..=1600).contains(&count) {
+        return Err(err());
+    }
+    let mut pos = 4;
+    let mut fields = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let field_oid = read_i32(pos)? as u32;
+        let len = read_i32(pos + 4)?;
+        pos += 8;
+        if field_oid == 0 {
+            return Err(err());
+        }
+        if len < 0 {
+            fields.push(PgValue::Null);
+            continue;
+        }
+        let value = data.get(pos..pos + len as usize).ok_or_else(err)?;
+        fields.push(PgValue::from_binary(field_oid, value)?);
+        pos += len as usize;
+    }
+    if pos != data.len() {
+        return Err(err());
+    }
+    Ok(PgValue::Composite(fields))
+}
+
+/// Decode a text record literal such as `(1,"a b",)`. Field types are not
+/// part of the text form, so fields come back as `Text` (or `Null` for an
+/// empty unquoted field).
+fn parse_text_record(s: &str) -> PgResult<PgValue> {
+    let inner = s
+        .strip_prefix('(')
+        .and_then(|r| r.strip_suffix(')'))
+        .ok_or_else(|| PgError::TypeConversion(format!("Invalid record literal: {}", s)))?;
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = inner.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => {
+                in_quotes = !in_quotes;
+                quoted = true;
+            }
+            '\\' => field.extend(chars.next()),
+            ',' if !in_quotes => {
+                fields.push(record_field(std::mem::take(&mut field), quoted));
+                quoted = false;
+            }
+            _ => field.push(c),
+        }
+    }
+    fields.push(record_field(field, quoted));
+    Ok(PgValue::Composite(fields))
+}
+
+fn record_field(text: String, quoted: bool) -> PgValue {
+    if text.is_empty() && !quoted {
+        PgValue::Null
+    } else {
+        PgValue::Text(text)
     }
+}
 
-    let mut pos = 12;
-    // Dimension length and lower bound
-    if data.len() < pos + 8 {
-        return Err(PgError::TypeConversion(
-            "Binary array dimension truncated".into(),
-        ));
+/// Text record literal: NULL fields are empty, others quoted when needed.
+fn format_record(fields: &[PgValue]) -> String {
+    let mut out = String::from("(");
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        let Some(bytes) = field.to_text_bytes() else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let needs_quotes = text.is_empty()
+            || text
+                .chars()
+                .any(|c| matches!(c, '(' | ')' | ',' | '"' | '\\') || c.is_whitespace());
+        if !needs_quotes {
+            out.push_str(&text);
+            continue;
+        }
+        out.push('"');
+        for c in text.chars() {
+            if c == '"' || c == '\\' {
+                out.push(c);
+            }
+            out.push(c);
+        }
+        out.push('"');
     }
-    let num_elements =
-        i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
-    pos += 4;
-    let _lower_bound = i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
-    pos += 4;
+    out.push(')');
+    out
+}
 
-    let mut values = Vec::with_capacity(num_elements);
-    for _ in 0..num_elements {
-        if data.len() < pos + 4 {
-            return Err(PgError::TypeConversion(
-                "Binary array element truncated".into(),
-            ));
+fn parse_text_array(elem_oid: u32, s: &str) -> PgResult<PgValue> {
+    let s = match s.strip_prefix('[') {
+        Some(_) => s.split_once('=').map_or(s, |(_, rest)| rest),
+        None => s,
+    };
+    let body = s
+        .strip_prefix('{')
+        .and_then(|b| b.strip_suffix('}'))
+        .ok_or_else(|| PgError::TypeConversion(format!("Invalid array literal: {}", s)))?;
+    let mut values = Vec::new();
+    if body.trim().is_empty() {
+        return Ok(PgValue::Array(values));
+    }
+
+    let mut chars = body.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut elem = String::new();
+        let quoted = chars.next_if_eq(&'"').is_some();
+        if quoted {
+            loop {
+                match chars.next() {
+                    Some('\\') => elem.extend(chars.next()),
+                    Some('"') => break,
+                    Some(c) => elem.push(c),
+                    None => {
+                        return Err(PgError::TypeConversion(
+                            "Unterminated quoted array element".into(),
+                        ));
+                    }
+                }
+            }
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        } else {
+            while let Some(c) = chars.next_if(|&c| c != ',') {
+                match c {
+                    '{' => {
+                        return Err(PgError::TypeConversion(
+                            "Unsupported multi-dimensional array".into(),
+                        ));
+                    }
+                    '\\' => elem.extend(chars.next()),
+                    c => elem.push(c),
+                }
+            }
+            elem.truncate(elem.trim_end().len());
         }
-        let elem_len = i32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
-        pos += 4;
 
-        if elem_len < 0 {
+        if !quoted && elem.eq_ignore_ascii_case("NULL") {
             values.push(PgValue::Null);
         } else {
-            let elem_len = elem_len as usize;
-            if data.len() < pos + elem_len {
-                return Err(PgError::TypeConversion(
-                    "Binary array element data truncated".into(),
-                ));
+            values.push(PgValue::from_text(elem_oid, elem.as_bytes())?);
+        }
+        match chars.next() {
+            Some(',') => {}
+            None => break,
+            Some(c) => {
+                return Err(PgError::TypeConversion(format!(
+                    "Unexpected '{}' in array literal",
+                    c
+                )));
             }
-            let elem_data = &data[pos..pos + elem_len];
-            values.push(PgValue::from_binary(element_oid, elem_data)?);
-            pos += elem_len;
         }
     }
-
     Ok(PgValue::Array(values))
 }
 
@@ -1534,7 +2917,16 @@ const PG_EPOCH_DAYS: i32 = 10957;
 
 /// Format a PostgreSQL date (days since 2000-01-01) as YYYY-MM-DD.
 fn format_date(days: i32) -> String {
+    match days {
+        i32::MAX => return "infinity".to_string(),
+        i32::MIN => return "-infinity".to_string(),
+        _ => {}
+    }
     let (y, m, d) = days_to_ymd(days + PG_EPOCH_DAYS);
+    if y <= 0 {
+        // There is no year 0: 1 BC is year 0 in the proleptic calendar.
+        return format!("{:04}-{:02}-{:02} BC", 1 - y, m, d);
+    }
     format!("{:04}-{:02}-{:02}", y, m, d)
 }
 
@@ -1554,6 +2946,11 @@ fn format_time(us: i64) -> String {
 
 /// Format a PostgreSQL timestamp as YYYY-MM-DD HH:MM:SS.ffffff.
 fn format_timestamp(us: i64) -> String {
+    match us {
+        i64::MAX => return "infinity".to_string(),
+        i64::MIN => return "-infinity".to_string(),
+        _ => {}
+    }
     let total_days = (us / 86_400_000_000) as i32;
     let time_us = us % 86_400_000_000;
     let (time_us, total_days) = if time_us < 0 {
@@ -1563,12 +2960,22 @@ fn format_timestamp(us: i64) -> String {
     };
     let date = format_date(total_days);
     let time = format_time(time_us);
-    format!("{} {}", date, time)
+    match date.strip_suffix(" BC") {
+        Some(date) => format!("{} {} BC", date, time),
+        None => format!("{} {}", date, time),
+    }
 }
 
-/// Format a PostgreSQL timestamptz.
+/// Format a PostgreSQL timestamptz (always rendered in UTC).
 fn format_timestamp_tz(us: i64) -> String {
-    format!("{}+00", format_timestamp(us))
+    if us == i64::MAX || us == i64::MIN {
+        return format_timestamp(us);
+    }
+    let ts = format_timestamp(us);
+    match ts.strip_suffix(" BC") {
+        Some(ts) => format!("{}+00 BC", ts),
+        None => format!("{}+00", ts),
+    }
 }
 
 /// Format a PostgreSQL interval.
@@ -1607,6 +3014,15 @@ fn format_interval(months: i32, days: i32, us: i64) -> String {
 
 /// Parse YYYY-MM-DD to PG days since 2000-01-01.
 fn parse_date_text(s: &str) -> PgResult<i32> {
+    match s {
+        "infinity" => return Ok(i32::MAX),
+        "-infinity" => return Ok(i32::MIN),
+        _ => {}
+    }
+    let (s, bc) = match s.strip_suffix(" BC") {
+        Some(s) => (s, true),
+        None => (s, false),
+    };
     let parts: Vec<&str> = s.split('-').collect();
     if parts.len() != 3 {
         return Err(PgError::TypeConversion(format!("Invalid date: {}", s)));
@@ -1620,6 +3036,7 @@ fn parse_date_text(s: &str) -> PgResult<i32> {
     let d: u32 = parts[2]
         .parse()
         .map_err(|_| PgError::TypeConversion("Bad day".into()))?;
+    let y = if bc { 1 - y } else { y };
     Ok(ymd_to_days(y, m, d) - PG_EPOCH_DAYS)
 }
 
@@ -1643,17 +3060,56 @@ fn parse_time_text(s: &str) -> PgResult<i64> {
     Ok(h * 3_600_000_000 + m * 60_000_000 + s_int * 1_000_000 + frac)
 }
 
-/// Parse a timestamp text: "YYYY-MM-DD HH:MM:SS[.ffffff][+/-TZ]"
+/// Parse a timestamp text: "YYYY-MM-DD HH:MM:SS[.ffffff][+/-TZ][ BC]".
+///
+/// A UTC offset (`+05:30`, `-08`) is applied, so timestamptz values sent in
+/// a non-UTC session `TimeZone` still yield the UTC instant.
 fn parse_timestamp_text(s: &str) -> PgResult<i64> {
-    // Strip timezone suffix for basic parsing
-    let s = s.trim_end_matches("+00").trim_end_matches("+00:00");
-    let parts: Vec<&str> = s.splitn(2, ' ').collect();
-    if parts.len() != 2 {
+    match s {
+        "infinity" => return Ok(i64::MAX),
+        "-infinity" => return Ok(i64::MIN),
+        _ => {}
+    }
+    let (s, bc) = match s.strip_suffix(" BC") {
+        Some(s) => (s, true),
+        None => (s, false),
+    };
+    let Some((date, time)) = s.split_once(' ') else {
         return Err(PgError::TypeConversion(format!("Invalid timestamp: {}", s)));
+    };
+    let (time, offset_us) = match time.find(['+', '-']) {
+        Some(pos) => (&time[..pos], parse_utc_offset(&time[pos..])?),
+        None => (time, 0),
+    };
+    let date_days = if bc {
+        parse_date_text(&format!("{} BC", date))?
+    } else {
+        parse_date_text(date)?
+    };
+    let time_us = parse_time_text(time)?;
+    Ok(date_days as i64 * 86_400_000_000 + time_us - offset_us)
+}
+
+/// Parse a UTC offset such as `+00`, `-08`, `+05:30` or `+05:30:15` to
+/// microseconds east of UTC.
+fn parse_utc_offset(s: &str) -> PgResult<i64> {
+    let bad = || PgError::TypeConversion(format!("Invalid UTC offset: {}", s));
+    let (sign, rest) = match s.split_at(1) {
+        ("+", rest) => (1, rest),
+        ("-", rest) => (-1, rest),
+        _ => return Err(bad()),
+    };
+    let mut secs = 0i64;
+    for (i, part) in rest.split(':').enumerate() {
+        let n: i64 = part.parse().map_err(|_| bad())?;
+        secs += match i {
+            0 => n * 3600,
+            1 => n * 60,
+            2 => n,
+            _ => return Err(bad()),
+        };
     }
-    let date_days = parse_date_text(parts[0])?;
-    let time_us = parse_time_text(parts[1])?;
-    Ok(date_days as i64 * 86_400_000_000 + time_us)
+    Ok(sign * secs * 1_000_000)
 }
 
 /// Parse a PostgreSQL interval text representation.
@@ -1898,8 +3354,21 @@ fn days_to_ymd(days: i32) -> (i32, u32, u32) {
     (y, m, d)
 }
 
-/// Decode PostgreSQL hex-format bytea (\\x prefix).
-fn decode_bytea_hex(s: &str) -> Vec<u8> {
+/// Encode bytea in the hex text format (`\x0a1b...`).
+fn encode_bytea_hex(bytes: &[u8]) -> String {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("\\x");
+    for b in bytes {
+        out.push(DIGITS[(b >> 4) as usize] as char);
+        out.push(DIGITS[(b & 0x0f) as usize] as char);
+    }
+    out
+}
+
+/// Decode text-format bytea: hex (`\x...`, the server default) or the
+/// legacy escape format used when `bytea_output = 'escape'`.
+pub(crate) fn decode_bytea_text(s: &str) -> Vec<u8> {
     if let Some(hex) = s.strip_prefix("\\x") {
         let mut result = Vec::with_capacity(hex.len() / 2);
         let bytes = hex.as_bytes();
@@ -1912,7 +3381,30 @@ fn decode_bytea_hex(s: &str) -> Vec<u8> {
         }
         result
     } else {
-        s.as_bytes().to_vec()
+        let bytes = s.as_bytes();
+        let mut result = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            let octal = bytes
+                .get(i + 1..i + 4)
+                .filter(|d| d.iter().all(|b| (b'0'..=b'7').contains(b)));
+            match (bytes[i], bytes.get(i + 1), octal) {
+                (b'\\', Some(b'\\'), _) => {
+                    result.push(b'\\');
+                    i += 2;
+                }
+                (b'\\', _, Some(d)) => {
+                    let v = d.iter().fold(0u32, |acc, b| acc * 8 + u32::from(b - b'0'));
+                    result.push(v as u8);
+                    i += 4;
+                }
+                (b, _, _) => {
+                    result.push(b);
+                    i += 1;
+                }
+            }
+        }
+        result
     }
 }
 
@@ -2327,6 +3819,94 @@ mod tests {
         assert_eq!(Vec::<u8>::from_sql(&val).unwrap(), vec![1, 2, 3]);
     }
 
+    fn binary_record(fields: &[(u32, Option<&[u8]>)]) -> Vec<u8> {
+        let mut buf = (fields.len() as i32).to_be_bytes().to_vec();
+        for (field_oid, data) in fields {
+            buf.extend_from_slice(&field_oid.to_be_bytes());
+            match data {
+                Some(d) => {
+                    buf.extend_from_slice(&(d.len() as i32).to_be_bytes());
+                    buf.extend_from_slice(d);
+                }
+                None => buf.extend_from_slice(&(-1_i32).to_be_bytes()),
+            }
+        }
+        buf
+    }
+
+    #[test]
+    fn test_binary_record_decoding() {
+        let inner = binary_record(&[(oid::BOOL, Some(&[1]))]);
+        let data = binary_record(&[
+            (oid::INT4, Some(&7_i32.to_be_bytes())),
+            (oid::TEXT, Some(b"seven")),
+            (oid::TEXT, None),
+            (oid::RECORD, Some(&inner)),
+        ]);
+        let expected = PgValue::Composite(vec![
+            PgValue::Int4(7),
+            PgValue::Text("seven".into()),
+            PgValue::Null,
+            PgValue::Composite(vec![PgValue::Bool(true)]),
+        ]);
+        assert_eq!(PgValue::from_binary(oid::RECORD, &data).unwrap(), expected);
+        // Named composite types have user OIDs but the same layout.
+        assert_eq!(PgValue::from_binary(16_500, &data).unwrap(), expected);
+
+        assert!(PgValue::from_binary(oid::RECORD, &data[..data.len() - 1]).is_err());
+        // Other user types (an enum label here) stay text.
+        assert_eq!(
+            PgValue::from_binary(16_500, b"active").unwrap(),
+            PgValue::Text("active".into())
+        );
+    }
+
+    #[test]
+    fn test_text_record_round_trip() {
+        let value =
+            PgValue::from_text(oid::RECORD, br#"(1,"a, b",,"","say ""hi"" \\o/")"#).unwrap();
+        assert_eq!(
+            value,
+            PgValue::Composite(vec![
+                PgValue::Text("1".into()),
+                PgValue::Text("a, b".into()),
+                PgValue::Null,
+                PgValue::Text("".into()),
+                PgValue::Text(r#"say "hi" \o/"#.into()),
+            ])
+        );
+        let text = value.to_text_bytes().unwrap();
+        assert_eq!(text, br#"(1,"a, b",,"","say ""hi"" \\o/")"#);
+        assert_eq!(PgValue::from_text(oid::RECORD, &text).unwrap(), value);
+    }
+
+    #[test]
+    fn test_record_from_sql() {
+        let value = PgValue::Composite(vec![
+            PgValue::Int8(3),
+            PgValue::Text("x".into()),
+            PgValue::Null,
+        ]);
+        let Record((n, s, missing)) =
+            Record::<(i64, String, Option<i32>)>::from_sql(&value).unwrap();
+        assert_eq!((n, s.as_str(), missing), (3, "x", None));
+        assert!(Record::<(i64, String)>::from_sql(&value).is_err());
+        assert!(Record::<(i64,)>::from_sql(&PgValue::Int8(3)).is_err());
+        assert_eq!(value.as_composite().map(<[PgValue]>::len), Some(3));
+    }
+
+    #[test]
+    fn test_bytea_text_round_trip() {
+        let blob = vec![0x00, b'\\', 0x7f, 0xff];
+        let text = PgValue::Bytes(blob.clone()).to_text_bytes().unwrap();
+        assert_eq!(text, b"\\x005c7fff");
+        assert_eq!(
+            PgValue::from_text(oid::BYTEA, &text).unwrap(),
+            PgValue::Bytes(blob)
+        );
+        assert_eq!(decode_bytea_text("\\\\\\101"), b"\\A");
+    }
+
     #[test]
     fn test_from_sql_vec_u8_null() {
         let val = PgValue::Null;
@@ -2616,11 +4196,118 @@ mod tests {
         assert!(PgValue::Float8(1.0).prefers_binary());
         assert!(PgValue::Uuid([0; 16]).prefers_binary());
         assert!(!PgValue::Text("hi".into()).prefers_binary());
-        assert!(!PgValue::Numeric("1.23".into()).prefers_binary());
+        assert!(PgValue::Numeric("1.23".into()).prefers_binary());
+        assert!(!PgValue::Numeric("1,23".into()).prefers_binary());
         assert!(!PgValue::Array(vec![]).prefers_binary());
         assert!(!PgValue::Inet("127.0.0.1".into()).prefers_binary());
     }
 
+    #[test]
+    fn test_numeric_binary_round_trip() {
+        for literal in [
+            "0",
+            "0.00",
+            "1.23",
+            "-42",
+            "10000",
+            "0.0001",
+            "123456789.987654321",
+            "-0.5",
+            "1.50",
+            "NaN",
+            "Infinity",
+            "-Infinity",
+        ] {
+            let bytes = encode_numeric_binary(literal).unwrap();
+            let val = PgValue::from_binary(oid::NUMERIC, &bytes).unwrap();
+            assert_eq!(val, PgValue::Numeric(literal.to_string()), "{literal}");
+        }
+    }
+
+    #[test]
+    fn test_numeric_binary_normalizes_input() {
+        let decode = |s: &str| {
+            PgValue::from_binary(oid::NUMERIC, &encode_numeric_binary(s).unwrap()).unwrap()
+        };
+        assert_eq!(decode("+007.10"), PgValue::Numeric("7.10".into()));
+        assert_eq!(decode("1e3"), PgValue::Numeric("1000".into()));
+        assert_eq!(decode("25E-3"), PgValue::Numeric("0.025".into()));
+        assert_eq!(decode("-0"), PgValue::Numeric("0".into()));
+        assert_eq!(decode(".5"), PgValue::Numeric("0.5".into()));
+    }
+
+    #[test]
+    fn test_numeric_binary_layout() {
+        // 1.23 → ndigits=2, weight=0, sign=+, dscale=2, digits [1, 2300]
+        assert_eq!(
+            encode_numeric_binary("1.23").unwrap(),
+            vec![0, 2, 0, 0, 0, 0, 0, 2, 0, 1, 0x08, 0xFC]
+        );
+    }
+
+    #[test]
+    fn test_numeric_binary_rejects_non_literals() {
+        for bad in ["", "-", ".", "1,5", "abc", "1e", "1.2.3"] {
+            assert!(encode_numeric_binary(bad).is_none(), "{bad:?}");
+        }
+        // Falls back to text so the server reports the error.
+        let val = PgValue::Numeric("abc".into());
+        assert_eq!(val.to_binary_bytes(), Some(b"abc".to_vec()));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn test_decimal_from_numeric() {
+        use std::str::FromStr;
+        let d: rust_decimal::Decimal =
+            FromSql::from_sql(&PgValue::Numeric("-12.340".into())).unwrap();
+        assert_eq!(d, rust_decimal::Decimal::from_str("-12.340").unwrap());
+        assert!(
+            <rust_decimal::Decimal as FromSql>::from_sql(&PgValue::Numeric("NaN".into())).is_err()
+        );
+    }
+
+    #[test]
+    fn test_from_binary_jsonb_checks_version() {
+        let val = PgValue::from_binary(oid::JSONB, b"\x01{\"a\":1}").unwrap();
+        assert_eq!(val, PgValue::Jsonb(br#"{"a":1}"#.to_vec()));
+        assert!(PgValue::from_binary(oid::JSONB, b"\x02{}").is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_json_round_trip() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq)]
+        struct Prefs {
+            theme: String,
+            tags: Vec<String>,
+        }
+        let prefs = Prefs {
+            theme: "dark".into(),
+            tags: vec!["a".into()],
+        };
+        let param = Json(&prefs);
+        assert_eq!(param.type_oid(), oid::JSONB);
+        let wire = param.to_sql().to_binary_bytes().unwrap();
+        assert_eq!(wire[0], 1);
+
+        let decoded = PgValue::from_binary(oid::JSONB, &wire).unwrap();
+        let Json(back): Json<Prefs> = FromSql::from_sql(&decoded).unwrap();
+        assert_eq!(back, prefs);
+
+        let text = PgValue::from_text(oid::JSON, br#"{"theme":"x","tags":[]}"#).unwrap();
+        let value: serde_json::Value = FromSql::from_sql(&text).unwrap();
+        assert_eq!(value["theme"], "x");
+        assert!(<Json<Prefs> as FromSql>::from_sql(&PgValue::Null).is_err());
+        assert!(<Json<Prefs> as FromSql>::from_sql(&PgValue::Json("[]".into())).is_err());
+    }
+
+    #[test]
+    fn test_f64_from_numeric() {
+        let v: f64 = FromSql::from_sql(&PgValue::Numeric("2.5".into())).unwrap();
+        assert_eq!(v, 2.5);
+    }
+
     #[test]
     fn test_from_binary_numeric_zero() {
         // ndigits=0, weight=0, sign=0 (pos), dscale=0
@@ -2815,6 +4502,187 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_binary_array_encoding() {
+        let arr = PgValue::Array(vec![PgValue::Int8(7), PgValue::Null, PgValue::Int8(-1)]);
+        assert!(arr.prefers_binary());
+        let bytes = arr.to_binary_bytes().unwrap();
+        assert_eq!(&bytes[..4], &1_i32.to_be_bytes()); // ndim
+        assert_eq!(&bytes[4..8], &1_i32.to_be_bytes()); // has nulls
+        assert_eq!(&bytes[8..12], &oid::INT8.to_be_bytes());
+        assert_eq!(&bytes[12..16], &3_i32.to_be_bytes()); // length
+        assert_eq!(&bytes[16..20], &1_i32.to_be_bytes()); // lower bound
+        assert_eq!(PgValue::from_binary(oid::INT8_ARRAY, &bytes).unwrap(), arr);
+
+        let ids = vec![[1u8; 16], [2u8; 16]];
+        let bytes = ids.to_sql().to_binary_bytes().unwrap();
+        let decoded = PgValue::from_binary(oid::UUID_ARRAY, &bytes).unwrap();
+        assert_eq!(Vec::<[u8; 16]>::from_sql(&decoded).unwrap(), ids);
+    }
+
+    #[test]
+    fn test_array_binary_eligibility() {
+        assert!(vec![1.5_f64].to_sql().prefers_binary());
+        assert!(PgValue::Array(vec![PgValue::Null, PgValue::Bool(true)]).prefers_binary());
+        // Text elements and mixed element types keep the text literal.
+        assert!(!vec!["a"].to_sql().prefers_binary());
+        assert!(!PgValue::Array(vec![PgValue::Int4(1), PgValue::Int8(2)]).prefers_binary());
+        assert!(!PgValue::Array(vec![PgValue::Null]).prefers_binary());
+    }
+
+    #[test]
+    fn test_from_text_array() {
+        let val = PgValue::from_text(oid::INT4_ARRAY, b"{1,NULL,3}").unwrap();
+        assert_eq!(
+            val,
+            PgValue::Array(vec![PgValue::Int4(1), PgValue::Null, PgValue::Int4(3)])
+        );
+        let val = PgValue::from_text(
+            oid::TEXT_ARRAY,
+            br#"{plain,"a b","say \"hi\"","NULL",NULL,""}"#,
+        )
+        .unwrap();
+        // `Vec<String>` has no slot for the NULL element.
+        assert!(Vec::<String>::from_sql(&val).is_err());
+        let PgValue::Array(items) = val else {
+            panic!("expected array")
+        };
+        assert_eq!(
+            items,
+            vec![
+                PgValue::Text("plain".into()),
+                PgValue::Text("a b".into()),
+                PgValue::Text("say \"hi\"".into()),
+                PgValue::Text("NULL".into()),
+                PgValue::Null,
+                PgValue::Text(String::new()),
+            ]
+        );
+        let val =
+            PgValue::from_text(oid::UUID_ARRAY, b"{00000000-0000-0000-0000-000000000001}").unwrap();
+        let mut one = [0u8; 16];
+        one[15] = 1;
+        assert_eq!(Vec::<[u8; 16]>::from_sql(&val).unwrap(), vec![one]);
+        assert_eq!(
+            PgValue::from_text(oid::FLOAT8_ARRAY, b"[0:1]={1.5,2}").unwrap(),
+            PgValue::Array(vec![PgValue::Float8(1.5), PgValue::Float8(2.0)])
+        );
+        assert_eq!(
+            PgValue::from_text(oid::INT4_ARRAY, b"{}").unwrap(),
+            PgValue::Array(Vec::new())
+        );
+        assert!(PgValue::from_text(oid::INT4_ARRAY, b"{{1,2},{3,4}}").is_err());
+        assert!(PgValue::from_text(oid::TEXT_ARRAY, br#"{"open"#).is_err());
+    }
+
+    #[test]
+    fn test_text_array_round_trip() {
+        let original = vec!["x,y".to_string(), "back\\slash".into(), " pad ".into()];
+        let bytes = original.to_sql().to_text_bytes().unwrap();
+        let decoded = PgValue::from_text(oid::TEXT_ARRAY, &bytes).unwrap();
+        assert_eq!(Vec::<String>::from_sql(&decoded).unwrap(), original);
+    }
+
+    #[test]
+    fn test_timestamp_text_offsets_and_infinity() {
+        let utc = parse_timestamp_text("2024-03-01 06:30:00").unwrap();
+        assert_eq!(
+            parse_timestamp_text("2024-03-01 12:00:00+05:30").unwrap(),
+            utc
+        );
+        assert_eq!(parse_timestamp_text("2024-02-29 22:30:00-08").unwrap(), utc);
+        assert_eq!(parse_timestamp_text("2024-03-01 06:30:00+00").unwrap(), utc);
+        assert_eq!(
+            PgValue::from_text(oid::TIMESTAMPTZ, b"infinity").unwrap(),
+            PgValue::Timestamptz(i64::MAX)
+        );
+        assert_eq!(
+            PgValue::from_text(oid::DATE, b"-infinity").unwrap(),
+            PgValue::Date(i32::MIN)
+        );
+        assert_eq!(format_timestamp(i64::MIN), "-infinity");
+        assert!(parse_timestamp_text("2024-03-01 06:30:00+xx").is_err());
+    }
+
+    #[test]
+    fn test_bc_dates_round_trip() {
+        let days = parse_date_text("0044-03-15 BC").unwrap();
+        assert_eq!(format_date(days), "0044-03-15 BC");
+        assert_eq!(
+            format_date(parse_date_text("0001-01-01").unwrap()),
+            "0001-01-01"
+        );
+        let ts = parse_timestamp_text("0001-12-31 23:00:00 BC").unwrap();
+        assert_eq!(format_timestamp(ts), "0001-12-31 23:00:00 BC");
+        assert_eq!(format_timestamp_tz(ts), "0001-12-31 23:00:00+00 BC");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_round_trip() {
+        use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+        let epoch = NaiveDate::from_ymd_opt(2000, 1, 1).unwrap();
+        assert_eq!(epoch.to_sql(), PgValue::Date(0));
+        let eve = NaiveDate::from_ymd_opt(1999, 12, 31).unwrap();
+        assert_eq!(eve.to_sql(), PgValue::Date(-1));
+        assert_eq!(NaiveDate::from_sql(&PgValue::Date(-1)).unwrap(), eve);
+        assert_eq!(
+            NaiveDate::from_sql(&PgValue::Text("1999-12-31".into())).unwrap(),
+            eve
+        );
+
+        // Before the PostgreSQL epoch with sub-second precision, via binary.
+        let ts: NaiveDateTime = eve.and_hms_micro_opt(23, 59, 59, 250_000).unwrap();
+        let val = ts.to_sql();
+        assert_eq!(val, PgValue::Timestamp(-750_000));
+        let bytes = val.to_binary_bytes().unwrap();
+        let decoded = PgValue::from_binary(oid::TIMESTAMP, &bytes).unwrap();
+        assert_eq!(NaiveDateTime::from_sql(&decoded).unwrap(), ts);
+
+        let instant: DateTime<Utc> = ts.and_utc();
+        assert_eq!(instant.to_sql(), PgValue::Timestamptz(-750_000));
+        assert_eq!(instant.type_oid(), oid::TIMESTAMPTZ);
+        let offset = instant.with_timezone(&chrono::FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(offset.to_sql(), PgValue::Timestamptz(-750_000));
+        let text = PgValue::from_text(oid::TIMESTAMPTZ, b"2000-01-01 00:59:59.25+01").unwrap();
+        assert_eq!(DateTime::<Utc>::from_sql(&text).unwrap(), instant);
+
+        let t = NaiveTime::from_hms_micro_opt(13, 5, 7, 42).unwrap();
+        assert_eq!(NaiveTime::from_sql(&t.to_sql()).unwrap(), t);
+        assert!(NaiveTime::from_sql(&PgValue::Time(86_400_000_000)).is_err());
+
+        assert!(NaiveDateTime::from_sql(&PgValue::Timestamp(i64::MAX)).is_err());
+        assert!(NaiveDate::from_sql(&PgValue::Date(i32::MIN)).is_err());
+        assert!(NaiveDate::from_sql(&PgValue::Null).is_err());
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_crate_round_trip() {
+        use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time};
+
+        let eve = Date::from_calendar_date(1999, Month::December, 31).unwrap();
+        assert_eq!(eve.to_sql(), PgValue::Date(-1));
+        assert_eq!(Date::from_sql(&PgValue::Date(-1)).unwrap(), eve);
+
+        let at = Time::from_hms_micro(23, 59, 59, 250_000).unwrap();
+        assert_eq!(Time::from_sql(&at.to_sql()).unwrap(), at);
+
+        let ts = PrimitiveDateTime::new(eve, at);
+        assert_eq!(ts.to_sql(), PgValue::Timestamp(-750_000));
+        assert_eq!(
+            PrimitiveDateTime::from_sql(&PgValue::Timestamp(-750_000)).unwrap(),
+            ts
+        );
+
+        let instant = ts.assume_offset(time::UtcOffset::from_hms(2, 0, 0).unwrap());
+        let val = instant.to_sql();
+        assert_eq!(val, PgValue::Timestamptz(-750_000 - 7_200_000_000));
+        assert_eq!(OffsetDateTime::from_sql(&val).unwrap(), instant);
+        assert!(OffsetDateTime::from_sql(&PgValue::Timestamptz(i64::MIN)).is_err());
+    }
+
     #[test]
     fn test_binary_roundtrip_int4() {
         let original = PgValue::Int4(12345);
@@ -3020,6 +4888,92 @@ mod tests {
         }
     }
 
+    fn binary_range(flags: u8, bounds: &[&[u8]]) -> Vec<u8> {
+        let mut buf = vec![flags];
+        for b in bounds {
+            buf.extend_from_slice(&(b.len() as i32).to_be_bytes());
+            buf.extend_from_slice(b);
+        }
+        buf
+    }
+
+    #[test]
+    fn test_range_from_binary() {
+        let one = 1_i32.to_be_bytes();
+        let ten = 10_i32.to_be_bytes();
+        let cases = [
+            (binary_range(RANGE_LB_INC, &[&one, &ten]), "[1,10)"),
+            (binary_range(RANGE_EMPTY, &[]), "empty"),
+            (binary_range(RANGE_LB_INF | RANGE_UB_INC, &[&ten]), "(,10]"),
+            (binary_range(RANGE_LB_INF | RANGE_UB_INF, &[]), "(,)"),
+        ];
+        for (data, text) in cases {
+            assert_eq!(
+                PgValue::from_binary(oid::INT4RANGE, &data).unwrap(),
+                PgValue::Range(text.to_string())
+            );
+        }
+        // 2000-01-01 00:00 to 2000-01-01 01:00, quoted like server output.
+        let data = binary_range(
+            RANGE_LB_INC,
+            &[&0_i64.to_be_bytes(), &3_600_000_000_i64.to_be_bytes()],
+        );
+        assert_eq!(
+            PgValue::from_binary(oid::TSRANGE, &data).unwrap(),
+            PgValue::Range(r#"["2000-01-01 00:00:00","2000-01-01 01:00:00")"#.to_string())
+        );
+        assert!(PgValue::from_binary(oid::INT4RANGE, &data[..5]).is_err());
+    }
+
+    #[test]
+    fn test_pg_range_to_sql() {
+        assert_eq!(
+            PgRange::from(1..10).to_sql(),
+            PgValue::Range("[1,10)".into())
+        );
+        assert_eq!(
+            PgRange::from(1..=9).to_sql(),
+            PgValue::Range("[1,9]".into())
+        );
+        assert_eq!(PgRange::from(5..).to_sql(), PgValue::Range("[5,)".into()));
+        assert_eq!(PgRange::from(..5).to_sql(), PgValue::Range("(,5)".into()));
+        assert_eq!(
+            PgRange::<i32>::Empty.to_sql(),
+            PgValue::Range("empty".into())
+        );
+        assert_eq!(
+            PgRange::new(Bound::Excluded("a b"), Bound::Included("c\"d")).to_sql(),
+            PgValue::Range(r#"("a b","c\"d"]"#.into())
+        );
+    }
+
+    #[test]
+    fn test_pg_range_from_sql() {
+        let r = PgRange::<i64>::from_sql(&PgValue::Range("[1,10)".into())).unwrap();
+        assert_eq!(r, PgRange::from(1..10));
+        assert!(r.contains(&1) && r.contains(&9));
+        assert!(!r.contains(&10) && !r.contains(&0));
+
+        let r = PgRange::<i32>::from_sql(&PgValue::Range("(,10]".into())).unwrap();
+        assert_eq!(r, PgRange::new(Bound::Unbounded, Bound::Included(10)));
+        assert!(r.contains(&-1_000) && r.contains(&10));
+
+        let r =
+            PgRange::<String>::from_sql(&PgValue::Range(r#"("a b","c\"d""e"]"#.into())).unwrap();
+        assert_eq!(
+            r,
+            PgRange::new(
+                Bound::Excluded("a b".into()),
+                Bound::Included(r#"c"d"e"#.into())
+            )
+        );
+
+        let empty = PgRange::<i32>::from_sql(&PgValue::Range("empty".into())).unwrap();
+        assert!(empty.is_empty() && !empty.contains(&0));
+        assert!(PgRange::<i32>::from_sql(&PgValue::Range("1,10".into())).is_err());
+        assert!(PgRange::<i32>::from_sql(&PgValue::Int4(1)).is_err());
+    }
+
     // ─── Unix Socket Config Tests ─────────────────────────────────
 
     #[test]
@@ -3178,6 +5132,46 @@ mod tests {
         assert_eq!(encoded, Some(b"(42,hello)".to_vec()));
     }
 
+    #[test]
+    fn test_to_sql_raw_defaults_to_preferred_format() {
+        assert_eq!(
+            7i32.to_sql_raw(),
+            (FormatCode::Binary, Some(7i32.to_be_bytes().to_vec()))
+        );
+        assert_eq!("hi".to_sql_raw(), (FormatCode::Text, Some(b"hi".to_vec())));
+        assert_eq!(None::<i32>.to_sql_raw(), (FormatCode::Text, None));
+    }
+
+    #[test]
+    fn test_from_sql_raw_fast_paths_match_pg_value() {
+        let cases: [(u32, &[u8]); 3] = [
+            (oid::INT8, &(-5i64).to_be_bytes()),
+            (oid::FLOAT8, &1.5f64.to_be_bytes()),
+            (oid::BOOL, &[1]),
+        ];
+        for (type_oid, data) in cases {
+            let value = PgValue::from_binary(type_oid, data).unwrap();
+            assert_eq!(
+                String::from_sql_raw(type_oid, FormatCode::Binary, Some(data)).unwrap(),
+                String::from_sql(&value).unwrap()
+            );
+        }
+        assert_eq!(
+            i64::from_sql_raw(oid::INT8, FormatCode::Binary, Some(&(-5i64).to_be_bytes())).unwrap(),
+            -5
+        );
+        assert_eq!(
+            f64::from_sql_raw(oid::INT4, FormatCode::Text, Some(b"3")).unwrap(),
+            3.0
+        );
+        // A short buffer is not taken by the fast path.
+        assert!(i32::from_sql_raw(oid::INT4, FormatCode::Binary, Some(&[0, 1])).is_err());
+        assert_eq!(
+            String::from_sql_raw(oid::VARCHAR, FormatCode::Binary, Some(b"abc")).unwrap(),
+            "abc"
+        );
+    }
+
     #[test]
     fn test_parse_composite_fields_quoted() {
         let fields = parse_composite_fields(r#"42,"hello, world",t"#);
@@ -14,8 +14,13 @@
 //! runs, then drops the database automatically via RAII. Tests are isolated
 //! and can run in parallel without conflict.
 
-use chopin_pg::{PgConfig, PgConnection, PgError, PgPool, PgPoolConfig, PgResult};
+use chopin_pg::{
+    FormatCode, Interest, PendingQuery, PgConfig, PgConnection, PgError, PgPool, PgPoolConfig,
+    PgResult, RetryPolicy, TargetSessionAttrs,
+};
 use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
 use std::time::Duration;
 
 // ─── TestDb — RAII isolated test database ─────────────────────────────────────
@@ -150,6 +155,247 @@ fn test_select_text_and_bool() {
     assert!(!b2);
 }
 
+#[test]
+fn test_insert_many_chunks_past_param_limit() {
+    let Some(mut db) = TestDb::with_schema("CREATE TABLE pts (x INT4, label TEXT)") else {
+        return;
+    };
+    // 40_000 rows × 2 columns needs two statements.
+    let labels: Vec<String> = (0..40_000).map(|i| format!("p{}", i)).collect();
+    let xs: Vec<i32> = (0..40_000).collect();
+    let rows: Vec<[&dyn chopin_pg::ToSql; 2]> = xs
+        .iter()
+        .zip(&labels)
+        .map(|(x, l)| [x as &dyn chopin_pg::ToSql, l])
+        .collect();
+    let rows: Vec<&[&dyn chopin_pg::ToSql]> = rows.iter().map(|r| &r[..]).collect();
+
+    let n = db.conn.insert_many("pts", &["x", "label"], &rows).unwrap();
+    assert_eq!(n, 40_000);
+    assert!(!db.conn.in_transaction());
+    let row = db
+        .conn
+        .query_one("SELECT count(*), max(x) FROM pts", &[])
+        .unwrap();
+    assert_eq!(row.get::<i64>(0).unwrap(), 40_000);
+    assert_eq!(row.get::<i32>(1).unwrap(), 39_999);
+
+    // A bad row in a later chunk rolls back the earlier ones.
+    let mut bad = rows.clone();
+    bad[39_000] = &[&"not a number", &"x"];
+    assert!(db.conn.insert_many("pts", &["x", "label"], &bad).is_err());
+    assert!(!db.conn.in_transaction());
+    let row = db.conn.query_one("SELECT count(*) FROM pts", &[]).unwrap();
+    assert_eq!(row.get::<i64>(0).unwrap(), 40_000);
+
+    assert!(matches!(
+        db.conn.insert_many("pts", &["x", "label"], &[&[&1_i32]]),
+        Err(PgError::Protocol(_))
+    ));
+    assert_eq!(db.conn.insert_many("pts", &["x"], &[]).unwrap(), 0);
+}
+
+#[test]
+fn test_execute_many_runs_each_param_set() {
+    let Some(mut db) =
+        TestDb::with_schema("CREATE TABLE events (id INT4 PRIMARY KEY, kind TEXT NOT NULL)")
+    else {
+        return;
+    };
+    let sql = "INSERT INTO events VALUES ($1, $2)";
+    let ids: Vec<i32> = (0..2500).collect();
+    let kinds = ["click", "view"];
+    let sets: Vec<[&dyn chopin_pg::ToSql; 2]> = ids
+        .iter()
+        .map(|id| [id as &dyn chopin_pg::ToSql, &kinds[*id as usize % 2]])
+        .collect();
+    let sets: Vec<&[&dyn chopin_pg::ToSql]> = sets.iter().map(|s| &s[..]).collect();
+
+    // One round-trip per set, inside one transaction of its own.
+    assert_eq!(db.conn.execute_many(sql, &sets).unwrap(), 2500);
+    assert!(!db.conn.in_transaction());
+    let n: i64 = db
+        .conn
+        .query_one("SELECT count(*) FROM events WHERE kind = 'view'", &[])
+        .unwrap()
+        .get(0)
+        .unwrap();
+    assert_eq!(n, 1250);
+
+    // Updates report the sum over every set.
+    let updated = db
+        .conn
+        .execute_many(
+            "UPDATE events SET kind = $1 WHERE id < $2",
+            &[&[&"a", &10_i32], &[&"b", &5_i32]],
+        )
+        .unwrap();
+    assert_eq!(updated, 15);
+
+    // A duplicate key in the last set rolls back the whole call.
+    let err = db
+        .conn
+        .execute_many(
+            sql,
+            &[&[&5000_i32, &"x"], &[&5001_i32, &"x"], &[&0_i32, &"x"]],
+        )
+        .unwrap_err();
+    assert!(matches!(err, PgError::Server(_)));
+    let n: i64 = db
+        .conn
+        .query_one("SELECT count(*) FROM events", &[])
+        .unwrap()
+        .get(0)
+        .unwrap();
+    assert_eq!(n, 2500);
+    assert_eq!(db.conn.execute_many(sql, &[]).unwrap(), 0);
+
+    // The connection is still usable after the error.
+    db.conn
+        .transaction(|tx| tx.execute_many(sql, &[&[&6000_i32, &"y"]]))
+        .unwrap();
+}
+
+#[test]
+fn test_transaction_pooling_leaves_no_prepared_statements() {
+    let Some(db) = TestDb::with_schema("CREATE TABLE items (id INT4 PRIMARY KEY, name TEXT)")
+    else {
+        return;
+    };
+    let cfg =
+        PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name).with_transaction_pooling();
+    let mut conn = PgConnection::connect(&cfg).unwrap();
+
+    let insert = "INSERT INTO items VALUES ($1, $2)";
+    for id in 0..3_i32 {
+        assert_eq!(conn.execute(insert, &[&id, &"x"]).unwrap(), 1);
+    }
+    assert_eq!(
+        conn.execute_many(insert, &[&[&10_i32, &"y"], &[&11_i32, &"z"]])
+            .unwrap(),
+        2
+    );
+    let name: String = conn
+        .query_one("SELECT name FROM items WHERE id = $1", &[&11_i32])
+        .unwrap()
+        .get(0)
+        .unwrap();
+    assert_eq!(name, "z");
+    assert!(
+        conn.query_opt("SELECT name FROM items WHERE id = $1", &[&99_i32])
+            .unwrap()
+            .is_none()
+    );
+    let rows = conn
+        .transaction(|tx| tx.query("SELECT id FROM items ORDER BY id", &[]))
+        .unwrap();
+    assert_eq!(rows.len(), 5);
+
+    // Every query re-parsed the unnamed statement; none was kept.
+    let prepared: i64 = conn
+        .query_one("SELECT count(*) FROM pg_prepared_statements", &[])
+        .unwrap()
+        .get(0)
+        .unwrap();
+    assert_eq!(prepared, 0);
+    assert_eq!(conn.cached_statements(), 0);
+    assert_eq!(conn.statement_cache_stats().hits, 0);
+    assert!(matches!(
+        conn.prepare("get_item", "SELECT 1"),
+        Err(PgError::Protocol(_))
+    ));
+}
+
+/// A user type mapped entirely through the raw trait methods.
+#[derive(Debug, PartialEq)]
+enum Mood {
+    Happy,
+    Sad,
+}
+
+impl chopin_pg::FromSql for Mood {
+    fn from_sql_raw(_oid: u32, _format: FormatCode, raw: Option<&[u8]>) -> PgResult<Self> {
+        match raw {
+            Some(b"happy") => Ok(Mood::Happy),
+            Some(b"sad") => Ok(Mood::Sad),
+            _ => Err(PgError::TypeConversion("not a mood".into())),
+        }
+    }
+}
+
+impl chopin_pg::ToSql for Mood {
+    fn to_sql(&self) -> chopin_pg::PgValue {
+        chopin_pg::PgValue::Text(self.label().into())
+    }
+
+    fn to_sql_raw(&self) -> (FormatCode, Option<Vec<u8>>) {
+        (FormatCode::Text, Some(self.label().as_bytes().to_vec()))
+    }
+}
+
+impl Mood {
+    fn label(&self) -> &'static str {
+        match self {
+            Mood::Happy => "happy",
+            Mood::Sad => "sad",
+        }
+    }
+}
+
+#[test]
+fn test_third_party_type_round_trip() {
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TYPE mood AS ENUM ('happy', 'sad');
+         CREATE TABLE people (name TEXT, mood mood);",
+    ) else {
+        return;
+    };
+    db.conn
+        .execute(
+            "INSERT INTO people VALUES ($1, $2), ($3, NULL)",
+            &[&"ann", &Mood::Sad, &"bob"],
+        )
+        .unwrap();
+    let rows = db
+        .conn
+        .query("SELECT name, mood FROM people ORDER BY name", &[])
+        .unwrap();
+    assert_eq!(rows[0].get::<Mood>(1).unwrap(), Mood::Sad);
+    assert_eq!(rows[1].get::<Option<Mood>>(1).unwrap(), None);
+    let name: String = rows[0].get_by_name("name").unwrap();
+    assert_eq!(name, "ann");
+
+    let matched = db
+        .conn
+        .query("SELECT name FROM people WHERE mood = $1", &[&Mood::Sad])
+        .unwrap();
+    assert_eq!(matched.len(), 1);
+}
+
+#[test]
+fn test_borrowed_str_getters() {
+    let Some(mut db) = TestDb::with_schema("CREATE TYPE mood AS ENUM ('happy', 'sad');") else {
+        return;
+    };
+    let rows = db
+        .conn
+        .query(
+            "SELECT 'hi'::varchar AS v, 'x'::char(3) AS c, '{\"a\":1}'::jsonb AS j, \
+             'sad'::mood AS m, 1 AS n",
+            &[],
+        )
+        .unwrap();
+    let row = &rows[0];
+    assert_eq!(row.get_str_by_name("v").unwrap(), Some("hi"));
+    assert_eq!(row.get_str_by_name("c").unwrap(), Some("x  "));
+    assert_eq!(row.get_str_by_name("j").unwrap(), Some("{\"a\": 1}"));
+    assert_eq!(row.get_str_by_name("m").unwrap(), Some("sad"));
+    assert!(row.get_str_by_name("n").is_err());
+
+    let simple = db.conn.query_simple("SELECT 1 AS n").unwrap();
+    assert_eq!(simple[0].get_str(0).unwrap(), Some("1"));
+}
+
 #[test]
 fn test_insert_and_select_round_trip() {
     let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
@@ -175,6 +421,376 @@ fn test_insert_and_select_round_trip() {
     assert_eq!(score, 99);
 }
 
+#[test]
+fn test_array_parameters_and_columns() {
+    let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
+        return;
+    };
+    for (name, score) in [("a", 1_i32), ("b", 2), ("c", 3)] {
+        db.conn
+            .execute(
+                "INSERT INTO items (name, score) VALUES ($1, $2)",
+                &[&name, &score],
+            )
+            .unwrap();
+    }
+
+    let rows = db
+        .conn
+        .query(
+            "SELECT name FROM items WHERE score = ANY($1) ORDER BY score",
+            &[&vec![1_i32, 3]],
+        )
+        .unwrap();
+    let names: Vec<String> = rows.iter().map(|r| r.get_typed(0).unwrap()).collect();
+    assert_eq!(names, ["a", "c"]);
+
+    let rows = db
+        .conn
+        .query(
+            "SELECT array_agg(name ORDER BY name), array_agg(score ORDER BY score) \
+             FROM items WHERE name = ANY($1)",
+            &[&vec!["a", "b"]],
+        )
+        .unwrap();
+    let names: Vec<String> = rows[0].get_typed(0).unwrap();
+    let scores: Vec<i32> = rows[0].get_typed(1).unwrap();
+    assert_eq!((names, scores), (vec!["a".into(), "b".into()], vec![1, 2]));
+}
+
+#[test]
+fn test_bytea_round_trip() {
+    let Some(mut db) = TestDb::open() else { return };
+    let blob: Vec<u8> = (0..4096).map(|i| (i % 256) as u8).collect();
+
+    let rows = db
+        .conn
+        .query("SELECT $1::bytea, $2::bytea", &[&blob, &blob.as_slice()])
+        .unwrap();
+    assert_eq!(
+        rows[0].get_bytes(0).unwrap().as_deref(),
+        Some(blob.as_slice())
+    );
+    assert_eq!(rows[0].get_typed::<Vec<u8>>(1).unwrap(), blob);
+
+    // Text results (simple protocol) in both output formats.
+    for output in ["hex", "escape"] {
+        db.conn
+            .execute_batch(&format!("SET bytea_output = '{output}'"))
+            .unwrap();
+        let rows = db
+            .conn
+            .query_simple("SELECT decode('00015c7fff', 'hex')")
+            .unwrap();
+        assert_eq!(
+            rows[0].get_bytes(0).unwrap().as_deref(),
+            Some(&[0x00, 0x01, 0x5c, 0x7f, 0xff][..])
+        );
+    }
+}
+
+#[test]
+fn test_composite_values() {
+    use chopin_pg::{CompositeField, PgValue, Record};
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TYPE inventory_item AS (name text, supplier_id int4, price numeric);
+         CREATE FUNCTION cheapest() RETURNS inventory_item
+             LANGUAGE sql AS $$ SELECT ROW('fuzzy dice', 42, 1.99)::inventory_item $$;",
+    ) else {
+        return;
+    };
+
+    let rows = db.conn.query("SELECT cheapest()", &[]).unwrap();
+    assert_eq!(
+        rows[0].get::<PgValue>(0).unwrap(),
+        PgValue::Composite(vec![
+            PgValue::Text("fuzzy dice".into()),
+            PgValue::Int4(42),
+            PgValue::Numeric("1.99".into()),
+        ])
+    );
+    let Record((name, supplier, price)): Record<(String, i32, String)> =
+        rows[0].get_typed(0).unwrap();
+    assert_eq!(
+        (name.as_str(), supplier, price.as_str()),
+        ("fuzzy dice", 42, "1.99")
+    );
+
+    let type_oid = rows[0].columns()[0].type_oid;
+    let fields = db.conn.composite_fields(type_oid).unwrap();
+    let names: Vec<&str> = fields.iter().map(|f| f.name.as_str()).collect();
+    assert_eq!(names, ["name", "supplier_id", "price"]);
+    assert_eq!(
+        fields[1],
+        CompositeField {
+            name: "supplier_id".into(),
+            type_oid: 23
+        }
+    );
+
+    // Anonymous records, with a NULL field, in binary and text results.
+    let rows = db
+        .conn
+        .query("SELECT ROW(1::int8, NULL::text, true)", &[])
+        .unwrap();
+    let Record((id, note, flag)): Record<(i64, Option<String>, bool)> =
+        rows[0].get_typed(0).unwrap();
+    assert_eq!((id, note, flag), (1, None, true));
+    let rows = db.conn.query_simple("SELECT ROW(1, 'a b', NULL)").unwrap();
+    let Record((id, text, none)): Record<(i32, String, Option<String>)> =
+        rows[0].get_typed(0).unwrap();
+    assert_eq!((id, text.as_str(), none), (1, "a b", None));
+
+    // A composite parameter goes out as a record literal.
+    let value = PgValue::Composite(vec![
+        PgValue::Text("cup, large".into()),
+        PgValue::Int4(7),
+        PgValue::Null,
+    ]);
+    let rows = db
+        .conn
+        .query(
+            "SELECT ($1::inventory_item).name, ($1::inventory_item).price IS NULL",
+            &[&value],
+        )
+        .unwrap();
+    assert_eq!(rows[0].get_typed::<String>(0).unwrap(), "cup, large");
+    assert!(rows[0].get_typed::<bool>(1).unwrap());
+}
+
+#[test]
+fn test_cursor_fetches_in_batches() {
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TABLE events (id int PRIMARY KEY, kind text NOT NULL);
+         INSERT INTO events SELECT g, CASE WHEN g % 2 = 0 THEN 'even' ELSE 'odd' END
+         FROM generate_series(1, 2500) g;",
+    ) else {
+        return;
+    };
+
+    let err = db.conn.cursor("SELECT id FROM events", &[], 100).err();
+    assert!(err.is_some(), "cursors need a transaction");
+
+    let sizes = db
+        .conn
+        .transaction(|tx| {
+            let mut cursor = tx.cursor(
+                "SELECT id FROM events WHERE kind = $1 ORDER BY id",
+                &[&"even"],
+                500,
+            )?;
+            let mut sizes = Vec::new();
+            let mut next = 2;
+            while let Some(rows) = cursor.next_batch()? {
+                for row in &rows {
+                    assert_eq!(row.get_typed::<i32>(0)?, next);
+                    next += 2;
+                }
+                sizes.push(rows.len());
+                cursor.set_batch_size(cursor.batch_size() * 2);
+            }
+            assert!(cursor.is_exhausted());
+            assert_eq!(next, 2502);
+            Ok(sizes)
+        })
+        .unwrap();
+    assert_eq!(sizes, vec![500, 750]);
+
+    // Closing early, then declaring again in the same transaction.
+    let total = db
+        .conn
+        .transaction(|tx| {
+            let mut cursor = tx.cursor("SELECT id FROM events", &[], 1000)?;
+            assert_eq!(cursor.next_batch()?.unwrap().len(), 1000);
+            cursor.close()?;
+            let mut total = 0;
+            for batch in tx.cursor("SELECT id FROM events", &[], 1000)? {
+                total += batch?.len();
+            }
+            // Dropping an unfinished cursor closes it too.
+            drop(tx.cursor("SELECT id FROM events", &[], 1)?);
+            assert_eq!(tx.cursor("SELECT 1 WHERE false", &[], 10)?.count(), 0);
+            Ok(total)
+        })
+        .unwrap();
+    assert_eq!(total, 2500);
+}
+
+#[test]
+fn test_portal_suspends_at_max_rows() {
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TABLE readings (id int PRIMARY KEY, value float8 NOT NULL);
+         INSERT INTO readings SELECT g, g / 10.0 FROM generate_series(1, 1000) g;",
+    ) else {
+        return;
+    };
+
+    let err = db
+        .conn
+        .query_portal("SELECT id FROM readings", &[], 100)
+        .err();
+    assert!(err.is_some(), "portals need a transaction");
+
+    let sizes = db
+        .conn
+        .transaction(|tx| {
+            let mut portal = tx.query_portal(
+                "SELECT id, value FROM readings WHERE id > $1 ORDER BY id",
+                &[&100i32],
+                300,
+            )?;
+            assert!(portal.is_suspended());
+            let mut sizes = Vec::new();
+            let mut next = 101;
+            while let Some(rows) = portal.next_batch()? {
+                for row in &rows {
+                    assert_eq!(row.get_typed::<i32>(0)?, next);
+                    next += 1;
+                }
+                sizes.push(rows.len());
+            }
+            assert!(portal.is_exhausted());
+            assert_eq!(next, 1001);
+            Ok(sizes)
+        })
+        .unwrap();
+    assert_eq!(sizes, vec![300, 300, 300]);
+
+    let total = db
+        .conn
+        .transaction(|tx| {
+            // A result that fits in one batch is complete right away.
+            let portal = tx.query_portal("SELECT 1", &[], 10)?;
+            assert!(!portal.is_suspended());
+            assert_eq!(portal.count(), 1);
+
+            // Closing or dropping early, then binding again.
+            let mut portal = tx.query_portal("SELECT id FROM readings", &[], 400)?;
+            assert_eq!(portal.next_batch()?.unwrap().len(), 400);
+            portal.close()?;
+            drop(tx.query_portal("SELECT id FROM readings", &[], 1)?);
+
+            let mut portal = tx.query_portal("SELECT id FROM readings", &[], 250)?;
+            portal.set_max_rows(500);
+            let mut total = 0;
+            for batch in portal {
+                total += batch?.len();
+            }
+            // The connection is usable afterwards.
+            tx.execute("DELETE FROM readings WHERE id > $1", &[&500i32])?;
+            Ok(total)
+        })
+        .unwrap();
+    assert_eq!(total, 1000);
+    assert_eq!(
+        db.conn.query("SELECT id FROM readings", &[]).unwrap().len(),
+        500
+    );
+}
+
+#[test]
+fn test_range_bookings() {
+    use chopin_pg::PgRange;
+    use std::ops::Bound;
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TABLE bookings (
+             id serial PRIMARY KEY,
+             slots int4range NOT NULL,
+             EXCLUDE USING gist (slots WITH &&)
+         )",
+    ) else {
+        return;
+    };
+
+    db.conn
+        .execute(
+            "INSERT INTO bookings (slots) VALUES ($1)",
+            &[&PgRange::from(9..12)],
+        )
+        .unwrap();
+    // Overlaps [9,12): rejected by the exclusion constraint.
+    let err = db
+        .conn
+        .execute(
+            "INSERT INTO bookings (slots) VALUES ($1)",
+            &[&PgRange::from(11..=13)],
+        )
+        .unwrap_err();
+    assert_eq!(err.sql_state(), Some("23P01"));
+    // Touching but not overlapping.
+    db.conn
+        .execute(
+            "INSERT INTO bookings (slots) VALUES ($1)",
+            &[&PgRange::from(12..14)],
+        )
+        .unwrap();
+
+    let rows = db
+        .conn
+        .query(
+            "SELECT slots FROM bookings WHERE slots @> $1::int4 ORDER BY id",
+            &[&13_i32],
+        )
+        .unwrap();
+    assert_eq!(
+        rows[0].get_typed::<PgRange<i32>>(0).unwrap(),
+        PgRange::from(12..14)
+    );
+
+    // Discrete ranges are canonicalised; unbounded and empty round-trip.
+    let rows = db
+        .conn
+        .query(
+            "SELECT $1::int4range, $2::int8range, 'empty'::int4range",
+            &[&PgRange::from(1..=3), &PgRange::<i64>::from(5..)],
+        )
+        .unwrap();
+    assert_eq!(
+        rows[0].get_typed::<PgRange<i32>>(0).unwrap(),
+        PgRange::from(1..4)
+    );
+    assert_eq!(
+        rows[0].get_typed::<PgRange<i64>>(1).unwrap(),
+        PgRange::new(Bound::Included(5), Bound::Unbounded)
+    );
+    assert!(rows[0].get_typed::<PgRange<i32>>(2).unwrap().is_empty());
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_tsrange_round_trip() {
+    use chopin_pg::PgRange;
+    use chrono::NaiveDate;
+    let Some(mut db) = TestDb::open() else { return };
+    let start = NaiveDate::from_ymd_opt(2024, 3, 1)
+        .unwrap()
+        .and_hms_opt(9, 0, 0)
+        .unwrap();
+    let end = start + chrono::Duration::minutes(90);
+    let during = PgRange::from(start..end);
+
+    let rows = db
+        .conn
+        .query(
+            "SELECT $1::tsrange, $2::daterange",
+            &[&during, &PgRange::from(start.date()..end.date())],
+        )
+        .unwrap();
+    assert_eq!(
+        rows[0]
+            .get_typed::<PgRange<chrono::NaiveDateTime>>(0)
+            .unwrap(),
+        during
+    );
+    // [d,d) is empty.
+    assert!(
+        rows[0]
+            .get_typed::<PgRange<NaiveDate>>(1)
+            .unwrap()
+            .is_empty()
+    );
+}
+
 #[test]
 fn test_affected_rows_insert_update_delete() {
     let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
@@ -300,6 +916,91 @@ fn test_multiple_result_rows_ordered() {
     assert_eq!(n2, "b");
 }
 
+#[test]
+fn test_named_prepared_statement() {
+    let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
+        return;
+    };
+
+    let insert = db
+        .conn
+        .prepare(
+            "insert_item",
+            "INSERT INTO items (name, score) VALUES ($1, $2)",
+        )
+        .unwrap();
+    assert_eq!(insert.param_types, vec![25, 23]); // TEXT, INT4
+    assert!(insert.columns.is_none());
+    for (name, score) in [("a", 1_i32), ("b", 2)] {
+        let n = db.conn.execute_prepared(&insert, &[&name, &score]).unwrap();
+        assert_eq!(n, 1);
+    }
+
+    let by_score = db
+        .conn
+        .prepare("item_by_score", "SELECT name FROM items WHERE score = $1")
+        .unwrap();
+    assert_eq!(by_score.columns.as_ref().unwrap()[0].name, "name");
+    let rows = db.conn.query_prepared(&by_score, &[&2_i32]).unwrap();
+    let name: String = rows[0].get_typed(0).unwrap();
+    assert_eq!(name, "b");
+
+    // Wrong arity is rejected client-side; the connection stays usable.
+    assert!(matches!(
+        db.conn.query_prepared(&by_score, &[]),
+        Err(PgError::TypeConversion(_))
+    ));
+    // `s<N>` names belong to the implicit statement cache.
+    assert!(db.conn.prepare("s0", "SELECT 1").is_err());
+
+    db.conn.close_prepared(by_score).unwrap();
+    assert!(db.conn.query("SELECT 1", &[]).is_ok());
+}
+
+#[test]
+fn test_result_column_metadata() {
+    let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
+        return;
+    };
+    db.conn
+        .execute("INSERT INTO items (name) VALUES ($1)", &[&"a"])
+        .unwrap();
+
+    let rows = db
+        .conn
+        .query("SELECT id, name, score + 1 AS next FROM items", &[])
+        .unwrap();
+    let cols = rows[0].columns();
+    let names: Vec<_> = cols.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, ["id", "name", "next"]);
+    assert_eq!(cols[0].type_name(), Some("int4"));
+    assert_eq!(cols[1].type_name(), Some("text"));
+    assert_eq!(cols[0].format_code, FormatCode::Binary);
+
+    let (table, attr) = cols[1].source().unwrap();
+    assert_eq!(attr, 2);
+    let owner = db
+        .conn
+        .query_one("SELECT $1::int8::oid::regclass::text", &[&(table as i64)])
+        .unwrap();
+    assert_eq!(owner.get_typed::<String>(0).unwrap(), "items");
+    assert_eq!(cols[2].source(), None);
+
+    let simple = db.conn.query_simple("SELECT name FROM items").unwrap();
+    assert_eq!(simple[0].columns()[0].format_code, FormatCode::Text);
+
+    // Empty results still have a header.
+    let header = db
+        .conn
+        .describe("SELECT name, active FROM items WHERE false")
+        .unwrap();
+    assert_eq!(header.len(), 2);
+    assert_eq!(header[1].type_name(), Some("bool"));
+    assert!(db.conn.describe("DELETE FROM items").unwrap().is_empty());
+    assert!(db.conn.describe("SELECT nope FROM items").is_err());
+    assert!(db.conn.query("SELECT 1", &[]).is_ok());
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 //  Phase 8.5 — Connection Pool
 // ─────────────────────────────────────────────────────────────────────────────
@@ -371,31 +1072,53 @@ fn test_pool_try_get_exhausted_returns_error() {
 }
 
 #[test]
-fn test_pool_get_timeout_when_exhausted() {
+fn test_pool_get_fails_fast_when_exhausted() {
     let Some(db) = TestDb::open() else { return };
 
     let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
     let pool_cfg = PgPoolConfig::new()
         .max_size(1)
-        .checkout_timeout(Duration::from_millis(50));
+        .checkout_timeout(Duration::from_secs(5));
     let mut pool = PgPool::connect_with_config(cfg, pool_cfg).unwrap();
 
-    // SAFETY: single-threaded test; `pool` outlives `held`; raw-pointer return
-    // in ConnectionGuard::drop remains valid because `held` drops before `pool`
-    // (reverse declaration order).  We transmute only to silence the lifetime
-    // annotation so we can call `pool.get()` a second time.
-    let held: chopin_pg::ConnectionGuard<'static> =
-        unsafe { std::mem::transmute(pool.get().unwrap()) };
+    // Nothing can return a connection while the pool is borrowed, so an
+    // exhausted pool gives up at once instead of waiting out the timeout.
+    pool.set_max_size(0);
     let start = std::time::Instant::now();
-    let result = pool.get(); // should time out
+    let result = pool.get().map(|_| ());
     let elapsed = start.elapsed();
-    drop(held); // return connection before pool is destroyed
 
-    assert!(result.is_err(), "get() should time out");
-    assert!(
-        elapsed >= Duration::from_millis(40),
-        "should have waited ~50 ms"
-    );
+    assert!(matches!(result, Err(PgError::PoolTimeout)));
+    assert!(elapsed < Duration::from_secs(1), "get() should not wait");
+    assert_eq!(pool.stats().checkout_timeouts, 1);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_shared_pool_get_async() {
+    let Some(db) = TestDb::open() else { return };
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
+    let pool = PgPool::with_config(cfg, PgPoolConfig::new().max_size(2)).into_shared();
+
+    let tasks: Vec<_> = (0..8)
+        .map(|i: i32| {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut conn = pool.get_async().await.unwrap();
+                tokio::task::spawn_blocking(move || {
+                    let row = conn.query_one("SELECT $1::int4 * 2", &[&i]).unwrap();
+                    row.get_typed::<i32>(0).unwrap()
+                })
+                .await
+                .unwrap()
+            })
+        })
+        .collect();
+    for (i, task) in tasks.into_iter().enumerate() {
+        assert_eq!(task.await.unwrap(), i as i32 * 2);
+    }
+    assert_eq!(pool.available_permits(), 2);
+    assert!(pool.stats().total_connections_created <= 2);
 }
 
 #[test]
@@ -418,6 +1141,102 @@ fn test_pool_reap_removes_idle_connections() {
     assert_eq!(pool.idle_connections(), 0);
 }
 
+#[test]
+fn test_pool_reap_keeps_min_size_warm() {
+    let Some(db) = TestDb::open() else { return };
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
+    let pool_cfg = PgPoolConfig::new()
+        .max_size(4)
+        .min_size(2)
+        .idle_timeout(Duration::from_millis(1))
+        .no_reap_interval();
+    let mut pool = PgPool::connect_with_config(cfg, pool_cfg).unwrap();
+
+    // Grow past min_size, then let every connection go idle.
+    // SAFETY: single-threaded; guards dropped before `pool`.
+    let guards: Vec<chopin_pg::ConnectionGuard<'static>> = (0..4)
+        .map(|_| unsafe { std::mem::transmute(pool.try_get().unwrap()) })
+        .collect();
+    drop(guards);
+    assert_eq!(pool.idle_connections(), 4);
+
+    std::thread::sleep(Duration::from_millis(20));
+    pool.reap();
+    assert_eq!(pool.total_connections(), 2);
+    assert_eq!(pool.stats().idle_expirations, 2);
+    assert_eq!(pool.stats().total_connections_created, 4);
+}
+
+#[test]
+fn test_pool_max_lifetime_replaces_connections() {
+    let Some(db) = TestDb::open() else { return };
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
+    let pool_cfg = PgPoolConfig::new()
+        .max_size(2)
+        .min_size(1)
+        .max_lifetime(Duration::from_millis(30))
+        .reap_interval(Duration::from_millis(30));
+    let mut pool = PgPool::connect_with_config(cfg, pool_cfg).unwrap();
+    let first_pid = pool.get().unwrap().process_id();
+
+    // Held past its lifetime: closed on return instead of going back idle.
+    {
+        let mut guard = pool.get().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+        guard.query_simple("SELECT 1").unwrap();
+    }
+    assert_eq!(pool.idle_connections(), 0);
+    assert_eq!(pool.stats().lifetime_expirations, 1);
+
+    // The next checkout reaps on its own and refills min_size first.
+    let pid = pool.get().unwrap().process_id();
+    assert_ne!(pid, first_pid);
+    assert_eq!(pool.idle_connections(), 1);
+
+    std::thread::sleep(Duration::from_millis(50));
+    let replaced = pool.get().unwrap().process_id();
+    assert_ne!(replaced, pid);
+    assert_eq!(pool.total_connections(), 1);
+    assert_eq!(pool.stats().lifetime_expirations, 2);
+}
+
+#[test]
+fn test_per_core_pool_gives_each_thread_its_own_shard() {
+    let Some(db) = TestDb::open() else { return };
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
+    let pool = PgPool::per_core(cfg, PgPoolConfig::new().max_size(2).min_size(1));
+
+    let pid = |pool: &chopin_pg::PerCorePool| -> i32 {
+        pool.with_local(|shard| {
+            let rows = shard
+                .get()
+                .unwrap()
+                .query_simple("SELECT pg_backend_pid()")
+                .unwrap();
+            rows[0].get_typed::<i32>(0).unwrap()
+        })
+        .unwrap()
+    };
+
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let pool = pool.clone();
+            std::thread::spawn(move || {
+                let first = pid(&pool);
+                let second = pid(&pool);
+                assert_eq!(first, second, "a thread should reuse its shard");
+                assert_eq!(pool.local_stats().unwrap().total_connections_created, 1);
+                first
+            })
+        })
+        .collect();
+    let mut pids: Vec<i32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+    pids.sort();
+    pids.dedup();
+    assert_eq!(pids.len(), 4, "threads must not share connections");
+    assert_eq!(pool.shard_count(), 4);
+}
+
 #[test]
 fn test_pool_multiple_sequential_queries() {
     let Some(db) = TestDb::with_schema(ITEMS_DDL) else {
@@ -713,6 +1532,77 @@ fn test_transaction_closure_commits_on_ok() {
     assert_eq!(count, 1);
 }
 
+const SERIALIZATION_FAILURE: &str =
+    "DO $$ BEGIN RAISE EXCEPTION 'conflict' USING ERRCODE = 'serialization_failure'; END $$";
+
+#[test]
+fn test_transaction_with_retry_reruns_serialization_failures() {
+    let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
+        return;
+    };
+    db.conn
+        .set_retry_policy(RetryPolicy::new().base_delay(Duration::from_millis(1)));
+
+    let mut attempts = 0;
+    db.conn
+        .transaction_with_retry(|tx| {
+            attempts += 1;
+            tx.execute("INSERT INTO items (name) VALUES ('retried')", &[])?;
+            if attempts == 1 {
+                tx.execute(SERIALIZATION_FAILURE, &[])?;
+            }
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(attempts, 2);
+    let rows = db.conn.query("SELECT count(*) FROM items", &[]).unwrap();
+    let count: i64 = rows[0].get_typed(0).unwrap();
+    assert_eq!(count, 1, "the failed attempt must have rolled back");
+
+    // Other errors are returned at once.
+    attempts = 0;
+    let err = db
+        .conn
+        .transaction_with_retry(|tx| {
+            attempts += 1;
+            tx.execute("SELECT 1/0", &[])
+        })
+        .unwrap_err();
+    assert_eq!(err.sql_state(), Some("22012"));
+    assert_eq!(attempts, 1);
+}
+
+#[test]
+fn test_pool_transaction_retries_up_to_the_limit() {
+    let Some(db) = TestDb::with_schema(ITEMS_DDL) else {
+        return;
+    };
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name);
+    let policy = RetryPolicy::new()
+        .max_retries(2)
+        .base_delay(Duration::from_millis(1))
+        .no_budget();
+    let mut pool =
+        PgPool::connect_with_config(cfg, PgPoolConfig::new().max_size(2).retry(policy)).unwrap();
+
+    let mut attempts = 0;
+    let err = pool
+        .transaction(|tx| {
+            attempts += 1;
+            tx.execute(SERIALIZATION_FAILURE, &[])
+        })
+        .unwrap_err();
+    assert_eq!(err.sql_state(), Some("40001"));
+    assert_eq!(attempts, 3);
+    assert_eq!(pool.stats().retries, 2);
+
+    let n = pool
+        .run(|conn| conn.execute("INSERT INTO items (name) VALUES ('pooled')", &[]))
+        .unwrap();
+    assert_eq!(n, 1);
+    assert_eq!(pool.stats().retries, 2);
+}
+
 #[test]
 fn test_transaction_closure_rolls_back_on_err() {
     let Some(mut db) = TestDb::with_schema(ITEMS_DDL) else {
@@ -936,3 +1826,399 @@ fn test_transaction_status_after_begin() {
     db.conn.rollback().unwrap();
     assert_eq!(db.conn.transaction_status(), TransactionStatus::Idle);
 }
+
+#[cfg(feature = "json")]
+#[test]
+fn test_jsonb_round_trip() {
+    use chopin_pg::Json;
+    let Some(mut db) = TestDb::open() else { return };
+
+    let doc = serde_json::json!({ "tags": ["a", "b"], "n": 3 });
+    let rows = db
+        .conn
+        .query("SELECT $1::jsonb, $1::jsonb -> 'n'", &[&Json(&doc)])
+        .unwrap();
+    let Json(back): Json<serde_json::Value> = rows[0].get_typed(0).unwrap();
+    assert_eq!(back, doc);
+    let n: serde_json::Value = rows[0].get_typed(1).unwrap();
+    assert_eq!(n, 3);
+}
+
+#[cfg(feature = "chrono")]
+#[test]
+fn test_chrono_round_trip() {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    let Some(mut db) = TestDb::open() else { return };
+
+    let date = NaiveDate::from_ymd_opt(1999, 12, 31).unwrap();
+    let time = NaiveTime::from_hms_micro_opt(23, 59, 59, 123_456).unwrap();
+    let ts: NaiveDateTime = date.and_time(time);
+    let instant: DateTime<Utc> = ts.and_utc();
+    let rows = db
+        .conn
+        .query(
+            "SELECT $1::date, $2::time, $3::timestamp, $4::timestamptz",
+            &[&date, &time, &ts, &instant],
+        )
+        .unwrap();
+    let got: (NaiveDate, NaiveTime, NaiveDateTime, DateTime<Utc>) = (
+        rows[0].get_typed(0).unwrap(),
+        rows[0].get_typed(1).unwrap(),
+        rows[0].get_typed(2).unwrap(),
+        rows[0].get_typed(3).unwrap(),
+    );
+    assert_eq!(got, (date, time, ts, instant));
+
+    // Text results in a non-UTC session still decode to the same instant.
+    db.conn
+        .execute_batch("SET TimeZone = 'Asia/Kolkata'")
+        .unwrap();
+    let rows = db
+        .conn
+        .query_simple("SELECT '1999-12-31 23:59:59.123456+00'::timestamptz")
+        .unwrap();
+    let text: DateTime<Utc> = rows[0].get_typed(0).unwrap();
+    assert_eq!(text, instant);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Multi-host failover
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_failover_skips_unreachable_and_mismatched_hosts() {
+    if PgConnection::connect(&admin_cfg()).is_err() {
+        return;
+    }
+    // Nothing listens on port 1; the fallback is the live (primary) server.
+    let cfg = PgConfig::new("127.0.0.1", 1, "chopin", "chopin", ADMIN_DB)
+        .with_fallback_host("localhost", 5432)
+        .with_target_session_attrs(TargetSessionAttrs::ReadWrite);
+    let mut conn = PgConnection::connect(&cfg).unwrap();
+    let rows = conn.query_simple("SHOW transaction_read_only").unwrap();
+    assert_eq!(rows[0].get_typed::<String>(0).unwrap(), "off");
+
+    let standby_only = cfg.with_target_session_attrs(TargetSessionAttrs::ReadOnly);
+    let err = PgConnection::connect(&standby_only).err().unwrap();
+    assert!(
+        err.to_string()
+            .contains("localhost:5432 does not match target_session_attrs=read-only"),
+        "{err}"
+    );
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Notices
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_notices_reach_the_handler() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = seen.clone();
+    let cfg = admin_cfg().with_notice_handler(move |severity, code, message| {
+        sink.lock()
+            .unwrap()
+            .push(format!("{severity} {code}: {message}"));
+    });
+    let Ok(mut conn) = PgConnection::connect(&cfg) else {
+        return;
+    };
+
+    conn.execute_batch("DO $$ BEGIN RAISE NOTICE 'hello'; RAISE WARNING 'careful'; END $$")
+        .unwrap();
+    conn.execute("DROP TABLE IF EXISTS chopin_no_such_table", &[])
+        .unwrap();
+    assert_eq!(
+        *seen.lock().unwrap(),
+        [
+            "NOTICE 00000: hello",
+            "WARNING 01000: careful",
+            "NOTICE 00000: table \"chopin_no_such_table\" does not exist, skipping",
+        ]
+    );
+
+    conn.clear_notice_handler();
+    conn.execute_batch("DO $$ BEGIN RAISE NOTICE 'unheard'; END $$")
+        .unwrap();
+    assert_eq!(seen.lock().unwrap().len(), 3);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  ParameterStatus
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_parameters_track_the_session() {
+    let Ok(mut conn) = PgConnection::connect(&admin_cfg()) else {
+        return;
+    };
+    assert!(conn.parameter("client_encoding").is_some());
+    assert_eq!(conn.parameter("standard_conforming_strings"), Some("on"));
+    let (major, _) = conn.server_version().unwrap();
+    assert!(major >= 10, "{:?}", conn.parameter("server_version"));
+
+    conn.execute("SET TimeZone = 'Asia/Bangkok'", &[]).unwrap();
+    assert_eq!(conn.parameter("timezone"), Some("Asia/Bangkok"));
+    conn.execute_batch("SET TimeZone = 'UTC'").unwrap();
+    assert_eq!(conn.parameter("TimeZone"), Some("UTC"));
+    assert_eq!(
+        conn.server_params()
+            .iter()
+            .filter(|(k, _)| k == "TimeZone")
+            .count(),
+        1
+    );
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Per-query timeouts
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[test]
+fn test_query_with_timeout() {
+    let Some(mut db) = TestDb::open() else {
+        return;
+    };
+    let conn = &mut db.conn;
+    conn.execute_batch("CREATE TABLE t (id INT)").unwrap();
+
+    let rows = conn
+        .query_with_timeout("SELECT $1::INT", &[&7i32], Duration::from_secs(5))
+        .unwrap();
+    assert_eq!(rows[0].get_typed::<i32>(0).unwrap(), 7);
+    let n = conn
+        .execute_with_timeout("INSERT INTO t VALUES (1), (2)", &[], Duration::from_secs(5))
+        .unwrap();
+    assert_eq!((n, conn.last_affected_rows()), (2, 2));
+
+    let err = conn
+        .query_with_timeout("SELECT pg_sleep(5)", &[], Duration::from_millis(50))
+        .unwrap_err();
+    assert!(matches!(err, PgError::Timeout), "{err:?}");
+    assert!(!conn.is_broken());
+    assert!(!conn.in_transaction());
+    let rows = conn.query_simple("SHOW statement_timeout").unwrap();
+    assert_eq!(rows[0].get_typed::<String>(0).unwrap(), "0");
+
+    // Inside a transaction the previous setting comes back.
+    conn.transaction(|tx| {
+        tx.execute("SET LOCAL statement_timeout = '30s'", &[])?;
+        tx.execute_with_timeout("INSERT INTO t VALUES (3)", &[], Duration::from_secs(1))?;
+        let rows = tx.query_simple("SHOW statement_timeout")?;
+        assert_eq!(rows[0].get_typed::<String>(0)?, "30s");
+        Ok(())
+    })
+    .unwrap();
+    let rows = conn.query_simple("SELECT count(*)::INT FROM t").unwrap();
+    assert_eq!(rows[0].get_typed::<i32>(0).unwrap(), 3);
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+//  Non-blocking queries
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Drive pending queries to completion from one `poll(2)` loop, the way a
+/// worker's event loop would.
+fn drive(queries: &mut [(&mut PgConnection, PendingQuery)]) -> Vec<PgResult<Vec<chopin_pg::Row>>> {
+    let mut results: Vec<Option<PgResult<Vec<chopin_pg::Row>>>> =
+        queries.iter().map(|_| None).collect();
+    while results.iter().any(Option::is_none) {
+        let mut fds: Vec<libc::pollfd> = queries
+            .iter()
+            .map(|(conn, pending)| libc::pollfd {
+                fd: conn.raw_fd(),
+                events: match pending.interest() {
+                    Some(Interest::Write) => libc::POLLOUT,
+                    Some(Interest::Read) => libc::POLLIN,
+                    None => 0,
+                },
+                revents: 0,
+            })
+            .collect();
+        assert!(unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as _, 5000) } > 0);
+        for (i, (conn, pending)) in queries.iter_mut().enumerate() {
+            if results[i].is_some() || fds[i].revents == 0 {
+                continue;
+            }
+            match pending.poll_write(conn) {
+                Ok(Poll::Ready(())) => {}
+                Ok(Poll::Pending) => continue,
+                Err(e) => {
+                    results[i] = Some(Err(e));
+                    continue;
+                }
+            }
+            match pending.poll_read(conn) {
+                Ok(Poll::Ready(rows)) => results[i] = Some(Ok(rows)),
+                Ok(Poll::Pending) => {}
+                Err(e) => results[i] = Some(Err(e)),
+            }
+        }
+    }
+    results.into_iter().map(Option::unwrap).collect()
+}
+
+#[test]
+fn test_pending_queries_share_one_thread() {
+    let Some(mut db) = TestDb::open() else {
+        return;
+    };
+    let mut other = PgConnection::connect(&PgConfig::new(
+        "localhost",
+        5432,
+        "chopin",
+        "chopin",
+        &db.name,
+    ))
+    .unwrap();
+
+    let a = db
+        .conn
+        .start_query("SELECT $1::INT FROM pg_sleep(0.5)", &[&1i32])
+        .unwrap();
+    assert_eq!(a.interest(), Some(Interest::Write));
+    assert!(matches!(
+        db.conn.start_query("SELECT 1", &[]),
+        Err(PgError::Protocol(_))
+    ));
+    let b = other
+        .start_query("SELECT $1::INT FROM pg_sleep(0.5)", &[&2i32])
+        .unwrap();
+
+    let started = std::time::Instant::now();
+    let results = drive(&mut [(&mut db.conn, a), (&mut other, b)]);
+    assert!(started.elapsed() < Duration::from_millis(900));
+    let values: Vec<i32> = results
+        .into_iter()
+        .map(|r| r.unwrap()[0].get_typed(0).unwrap())
+        .collect();
+    assert_eq!(values, [1, 2]);
+    assert!(!db.conn.has_pending_query());
+
+    // A server error is returned once the server is ready again, and the
+    // connection stays usable.
+    let bad = db.conn.start_query("SELECT * FROM missing", &[]).unwrap();
+    let mut results = drive(&mut [(&mut db.conn, bad)]);
+    match results.remove(0) {
+        Err(PgError::Server(e)) => assert_eq!(e.code, "42P01"),
+        other => panic!("{other:?}"),
+    }
+    let rows = db.conn.query("SELECT 3::INT", &[]).unwrap();
+    assert_eq!(rows[0].get_typed::<i32>(0).unwrap(), 3);
+}
+
+// ─── Logical replication ──────────────────────────────────────────────────────
+
+#[test]
+fn test_logical_replication_streams_pgoutput_changes() {
+    use chopin_pg::replication::Relation;
+    use chopin_pg::{Lsn, PgOutputMessage};
+
+    let Some(mut db) = TestDb::with_schema(
+        "CREATE TABLE cdc (id INT4 PRIMARY KEY, name TEXT);
+         CREATE PUBLICATION cdc_pub FOR TABLE cdc;",
+    ) else {
+        return;
+    };
+    let wal_level: String = db.conn.query_simple("SHOW wal_level").unwrap()[0]
+        .get_typed(0)
+        .unwrap();
+    if wal_level != "logical" {
+        return;
+    }
+
+    let cfg = PgConfig::new("localhost", 5432, "chopin", "chopin", &db.name).with_replication();
+    let mut repl = PgConnection::connect(&cfg).unwrap();
+    let system = repl.identify_system().unwrap();
+    assert_eq!(system.dbname.as_deref(), Some(db.name.as_str()));
+
+    // Unknown slots fail without wrecking the connection.
+    assert!(
+        repl.start_replication("no_such_slot", Lsn::ZERO, &[])
+            .is_err()
+    );
+    assert!(repl.start_replication("Bad Name", Lsn::ZERO, &[]).is_err());
+
+    let slot_name = format!("chopin_it_{}", std::process::id());
+    let slot = repl
+        .create_replication_slot(&slot_name, "pgoutput", true)
+        .unwrap();
+    assert!(slot.consistent_point >= system.xlog_pos);
+
+    db.conn
+        .execute("INSERT INTO cdc VALUES ($1, $2)", &[&1i32, &"alice"])
+        .unwrap();
+    db.conn
+        .execute("UPDATE cdc SET name = 'bob' WHERE id = 1", &[])
+        .unwrap();
+    db.conn
+        .execute("DELETE FROM cdc WHERE id = 1", &[])
+        .unwrap();
+
+    let mut stream = repl
+        .start_replication(
+            &slot.name,
+            slot.consistent_point,
+            &[("proto_version", "1"), ("publication_names", "cdc_pub")],
+        )
+        .unwrap();
+    stream.set_status_interval(Duration::from_millis(100));
+
+    let mut relation: Option<Relation> = None;
+    let mut changes = Vec::new();
+    let mut last_end = Lsn::ZERO;
+    let mut commits = 0;
+    let deadline = std::time::Instant::now() + Duration::from_secs(10);
+    while commits < 3 && std::time::Instant::now() < deadline {
+        let Some(data) = stream.recv_timeout(Duration::from_millis(200)).unwrap() else {
+            continue;
+        };
+        match PgOutputMessage::decode(&data.data).unwrap() {
+            PgOutputMessage::Relation(rel) => relation = Some(rel),
+            PgOutputMessage::Insert { new, .. } => {
+                let rel = relation.as_ref().unwrap();
+                changes.push(format!(
+                    "insert {} {}",
+                    new.get::<i32>(rel, 0).unwrap(),
+                    new.get_by_name::<String>(rel, "name").unwrap()
+                ));
+            }
+            PgOutputMessage::Update { old, new, .. } => {
+                let rel = relation.as_ref().unwrap();
+                assert_eq!(old, None);
+                changes.push(format!(
+                    "update {}",
+                    new.get_by_name::<String>(rel, "name").unwrap()
+                ));
+            }
+            PgOutputMessage::Delete { old, .. } => {
+                let rel = relation.as_ref().unwrap();
+                changes.push(format!("delete {}", old.get::<i32>(rel, 0).unwrap()));
+            }
+            PgOutputMessage::Commit { end_lsn, .. } => {
+                commits += 1;
+                last_end = end_lsn;
+                stream.ack(end_lsn);
+            }
+            _ => {}
+        }
+    }
+    assert_eq!(changes, ["insert 1 alice", "update bob", "delete 1"]);
+    assert_eq!(relation.unwrap().name, "cdc");
+    assert_eq!(stream.flushed_lsn(), last_end);
+    stream.stop().unwrap();
+
+    // The acknowledgement reached the server, and the connection is usable.
+    let confirmed: String = db
+        .conn
+        .query_one(
+            "SELECT confirmed_flush_lsn::text FROM pg_replication_slots WHERE slot_name = $1",
+            &[&slot_name],
+        )
+        .unwrap()
+        .get(0)
+        .unwrap();
+    assert!(confirmed.parse::<Lsn>().unwrap() >= last_end);
+    assert!(repl.identify_system().unwrap().xlog_pos >= last_end);
+    drop(repl);
+}